@@ -0,0 +1,71 @@
+// The measured cost of V against the one claimed by the book: O(n^2) field ops for n * n
+// matrices, rather than the O(n^3) of computing A · B. C = A · I keeps P cheap at n = 256.
+use crate::freivalds::{
+    verify_committed, verify_committed_with_cost, verify_uncommitted, verify_uncommitted_with_cost,
+    CommittedProver,
+};
+use crate::matrix::Matrix;
+use sumcheck::cost::grows_like;
+use Fiat_Shamir::default::Keccak256Transcript;
+
+const SIZES: [usize; 3] = [16, 64, 256];
+
+#[test]
+fn test_uncommitted_cost_is_quadratic() {
+    let mut mults = vec![];
+    for n in SIZES {
+        let (a, b) = (Matrix::random(n, n), Matrix::identity(n));
+        let (res, cost) =
+            verify_uncommitted_with_cost(&a, &b, &a, &mut Keccak256Transcript::default());
+        assert_eq!(
+            res,
+            verify_uncommitted(&a, &b, &a, &mut Keccak256Transcript::default())
+        );
+        assert_eq!(res, Ok(()));
+        // B · x, C · x and A · y, and the seed of x and its n scalars.
+        assert_eq!(cost.field_mults, 3 * (n * n) as u64);
+        assert_eq!(cost.hash_calls, 1 + n as u64);
+        mults.push(cost.field_mults);
+    }
+    assert!(
+        grows_like(&SIZES, &mults, |n| (n * n) as f64, 1.5),
+        "{:?}",
+        mults
+    );
+    assert!(!grows_like(&SIZES, &mults, |n| (n * n * n) as f64, 1.5));
+}
+
+#[test]
+fn test_committed_cost() {
+    let (mut mults, mut hashes) = (vec![], vec![]);
+    for n in SIZES {
+        let (a, b) = (Matrix::random(n, n), Matrix::identity(n));
+        let (roots, prover) = CommittedProver::from_committed(&a, &b, &a);
+        let roots = (&roots.0, &roots.1, &roots.2);
+        let (res, cost) =
+            verify_committed_with_cost(roots, &prover, 1, &mut Keccak256Transcript::default());
+        assert_eq!(
+            res,
+            verify_committed(roots, &prover, 1, &mut Keccak256Transcript::default())
+        );
+        assert_eq!(res, Ok(()));
+        // y and z against the rows of B and C, and A · y.
+        assert_eq!(cost.field_mults, 3 * (n * n) as u64);
+        // the rows of A, B and C, each one a batch of 2n hashes: the n leaves, the n - 1 nodes
+        // above them and the root. And the seed of x and its n scalars.
+        assert_eq!(cost.hash_calls, (3 * 2 * n + 1 + n) as u64);
+        mults.push(cost.field_mults);
+        hashes.push(cost.hash_calls);
+    }
+    assert!(
+        grows_like(&SIZES, &mults, |n| (n * n) as f64, 1.5),
+        "{:?}",
+        mults
+    );
+    // the paths share their nodes, so the openings of the n rows are O(n) hashes, not O(n log n).
+    assert!(
+        grows_like(&SIZES, &hashes, |n| n as f64, 1.5),
+        "{:?}",
+        hashes
+    );
+}
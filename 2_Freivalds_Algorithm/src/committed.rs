@@ -0,0 +1,300 @@
+// A matrix committed row by row with a Merkle tree, so that a single row can be revealed
+// and checked against the root without revealing the others. The tree is the one of
+// `Merkle_tree_commitment`, whose leaves are the rows, see `Leaf for Vec<Scalar>`:
+//
+//     leaf_i = hash_leaf(row_i), the 32 bytes of each scalar in order
+//
+// The leaves are padded with empty rows up to a power of 2, which no opening of a row
+// i < rows is, as V checks the index and the length of each opened row.
+//
+// `commit_columns` commits column by column instead, which is the row commitment of Bᵀ, so
+// its root is of Bᵀ (rows and cols swapped) and `open_column(j)` is the row opening j of Bᵀ.
+//
+// `commit_mle` commits M̃ by `ni_sumcheck::mlpc` instead, for the protocols which read M at a
+// point of F rather than a row, see `matvec` and `membership`. The table of M̃ is the rows of M one after another,
+// padded with zero rows to a power of 2, so its first log(rows) variables are the row and the
+// rest the column.
+//
+// `verify_row` checks a claimed row i against the root, `verify_rows` checks the openings of
+// rows 0, 1, ... as one batch (see `verify_multi`), and `prove_row_membership` proves that
+// some row of the M̃ of `commit_mle` equals v without opening it, see `membership`.
+use crate::matrix::Matrix;
+use bls12_381::Scalar;
+use ff::PrimeField;
+use ni_sumcheck::mlpc::{self, MlRoot, ProverState};
+use Fiat_Shamir::statement::MatrixBinding;
+use Merkle_tree_commitment::merkle_tree::hasher::Digest;
+use Merkle_tree_commitment::merkle_tree::leaf::Leaf;
+use Merkle_tree_commitment::merkle_tree::proof::MerkleProof;
+use Merkle_tree_commitment::merkle_tree::{MerkleTree, MultiVerifier};
+
+mod membership;
+
+pub use membership::{verify_row_membership, MembershipError, MembershipProof};
+
+pub type Hash = [u8; 32];
+
+/// The commitment of a matrix, which is all the verifier holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatrixRoot {
+    pub rows: usize,
+    pub cols: usize,
+    pub digest: Hash,
+}
+
+impl MatrixRoot {
+    // The root of the tree, None if the bytes aren't a canonical scalar, which no root is.
+    fn tree_root(&self) -> Option<Digest> {
+        Digest::from_repr(self.digest).into()
+    }
+
+    // The levels of the path of a row, the rows are padded up to a power of 2.
+    fn levels(&self) -> usize {
+        self.rows.next_power_of_two().trailing_zeros() as usize
+    }
+}
+
+/// The commitment of M̃, see `CommittedMatrix::commit_mle`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MleRoot {
+    pub rows: usize,
+    pub cols: usize,
+    pub table: MlRoot,
+}
+
+// The root as a binding of a `Statement`, and back, see `freivalds::verify_statement`.
+impl From<MatrixRoot> for MatrixBinding {
+    fn from(root: MatrixRoot) -> Self {
+        MatrixBinding {
+            rows: root.rows,
+            cols: root.cols,
+            digest: root.digest,
+        }
+    }
+}
+
+impl From<MatrixBinding> for MatrixRoot {
+    fn from(binding: MatrixBinding) -> Self {
+        MatrixRoot {
+            rows: binding.rows,
+            cols: binding.cols,
+            digest: binding.digest,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CommittedMatrix {
+    matrix: Matrix,
+    tree: MerkleTree<Vec<Scalar>>,
+}
+
+/// Row i with its authentication path, the index is the one of the proof.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowOpening {
+    pub row: Vec<Scalar>,
+    pub proof: MerkleProof<Vec<Scalar>>,
+}
+
+impl CommittedMatrix {
+    pub fn commit(m: &Matrix) -> (MatrixRoot, CommittedMatrix) {
+        assert!(m.rows() > 0, "can't commit an empty matrix");
+        let mut leaves = (0..m.rows()).map(|i| m.row(i).to_vec()).collect::<Vec<_>>();
+        leaves.resize(m.rows().next_power_of_two(), vec![]);
+
+        let committed = CommittedMatrix {
+            matrix: m.clone(),
+            tree: MerkleTree::commit(&leaves),
+        };
+        (committed.root(), committed)
+    }
+
+    // Commit the columns of m as the leaves, eg: for a column of B at a challenged index.
+    pub fn commit_columns(m: &Matrix) -> (MatrixRoot, CommittedMatrix) {
+        Self::commit(&m.transpose())
+    }
+
+    // Commit M̃ of m, see above. The columns of m must be a power of 2.
+    pub fn commit_mle(m: &Matrix) -> (MleRoot, ProverState) {
+        assert!(
+            m.cols().is_power_of_two(),
+            "the columns of the matrix must be a power of 2"
+        );
+        let mut table = (0..m.rows())
+            .flat_map(|i| m.row(i).to_vec())
+            .collect::<Vec<_>>();
+        table.resize(m.rows().next_power_of_two() * m.cols(), Scalar::zero());
+        let (root, state) = mlpc::commit(&table);
+        let root = MleRoot {
+            rows: m.rows(),
+            cols: m.cols(),
+            table: root,
+        };
+        (root, state)
+    }
+
+    pub fn root(&self) -> MatrixRoot {
+        MatrixRoot {
+            rows: self.matrix.rows(),
+            cols: self.matrix.cols(),
+            digest: self.tree.root_hash().to_repr(),
+        }
+    }
+
+    pub fn matrix(&self) -> &Matrix {
+        &self.matrix
+    }
+
+    pub fn open_row(&self, index: usize) -> RowOpening {
+        assert!(index < self.matrix.rows(), "row index out of range");
+        RowOpening {
+            row: self.matrix.row(index).to_vec(),
+            proof: self.tree.open_by_index(index),
+        }
+    }
+
+    // The column j of m, for the matrix committed by `commit_columns(m)`.
+    pub fn open_column(&self, index: usize) -> RowOpening {
+        self.open_row(index)
+    }
+}
+
+impl RowOpening {
+    pub fn index(&self) -> usize {
+        self.proof.index
+    }
+
+    // A row of the matrix of root, with a path of its height.
+    fn fits(&self, root: &MatrixRoot) -> bool {
+        self.index() < root.rows
+            && self.row.len() == root.cols
+            && self.proof.children.len() == root.levels()
+    }
+
+    /// Recompute the root from the row and the path.
+    pub fn verify(&self, root: &MatrixRoot) -> bool {
+        let leaf = self.row.to_leaf_bytes();
+        match root.tree_root() {
+            Some(digest) => self.fits(root) && MerkleTree::verify(&digest, &self.proof, &leaf),
+            None => false,
+        }
+    }
+}
+
+// The claimed row i is the committed one: the opening is of row i, with the claimed values, and
+// its path leads to the root.
+pub fn verify_row(
+    root: &MatrixRoot,
+    index: usize,
+    claimed_row: &[Scalar],
+    opening: &RowOpening,
+) -> bool {
+    opening.index() == index && opening.row == claimed_row && opening.verify(root)
+}
+
+// The openings of the rows 0, 1, ... in order, as one batch: the paths of the rows share their
+// upper nodes, which are hashed once, see `verify_multi`. Err(i) if the opening of row i is the
+// first rejected one, with the hashes it took, eg: for the cost of V.
+pub fn verify_rows(root: &MatrixRoot, openings: &[RowOpening]) -> (Result<(), usize>, usize) {
+    let digest = match root.tree_root() {
+        Some(digest) => digest,
+        None => return (Err(0), 0),
+    };
+    let mut batch = MultiVerifier::new();
+    for (i, opening) in openings.iter().enumerate() {
+        if !opening.fits(root) {
+            // the ones before are accepted iff the batch of them is.
+            let (res, hashes) = batch.check_counted();
+            return (res.map_err(|e| e.claim).and(Err(i)), hashes);
+        }
+        batch.add(digest, i, &opening.row, &opening.proof);
+    }
+    let (res, hashes) = batch.check_counted();
+    (res.map_err(|e| e.claim), hashes)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::committed::{verify_row, verify_rows, CommittedMatrix};
+    use crate::matrix::Matrix;
+    use bls12_381::Scalar;
+
+    #[test]
+    fn test_open_every_row() {
+        // 5 rows, padded to 8 leaves
+        let m = Matrix::random(5, 3);
+        let (root, committed) = CommittedMatrix::commit(&m);
+        for i in 0..5 {
+            let opening = committed.open_row(i);
+            assert_eq!(opening.row, m.row(i));
+            assert!(opening.verify(&root));
+        }
+
+        // a wrong row, or the right row at another index
+        let mut opening = committed.open_row(1);
+        opening.row[0] += Scalar::one();
+        assert!(!opening.verify(&root));
+
+        let mut opening = committed.open_row(1);
+        opening.proof.index = 2;
+        assert!(!opening.verify(&root));
+    }
+
+    #[test]
+    fn test_verify_row() {
+        let m = Matrix::random(16, 8);
+        let (root, committed) = CommittedMatrix::commit(&m);
+        let opening = committed.open_row(3);
+        assert!(verify_row(&root, 3, m.row(3), &opening));
+
+        // the opening of row 3 for row 4, or for row 3 with another value
+        assert!(!verify_row(&root, 4, m.row(3), &opening));
+        assert!(!verify_row(&root, 4, m.row(4), &opening));
+        let mut row = m.row(3).to_vec();
+        row[7] += Scalar::one();
+        assert!(!verify_row(&root, 3, &row, &opening));
+
+        // a tampered opening of the claimed row
+        let mut tampered = opening.clone();
+        tampered.row = row.clone();
+        assert!(!verify_row(&root, 3, &row, &tampered));
+    }
+
+    #[test]
+    fn test_open_every_column() {
+        let m = Matrix::random(5, 3);
+        let (root, committed) = CommittedMatrix::commit_columns(&m);
+        assert_eq!((root.rows, root.cols), (3, 5));
+        for j in 0..3 {
+            let opening = committed.open_column(j);
+            let column = (0..5).map(|i| m.row(i)[j]).collect::<Vec<_>>();
+            assert_eq!(opening.row, column);
+            assert!(opening.verify(&root));
+        }
+    }
+
+    #[test]
+    fn test_verify_rows() {
+        let m = Matrix::random(6, 4);
+        let (root, committed) = CommittedMatrix::commit(&m);
+        let openings = (0..6).map(|i| committed.open_row(i)).collect::<Vec<_>>();
+        let (res, hashes) = verify_rows(&root, &openings);
+        assert_eq!(res, Ok(()));
+        // 8 leaves: the 6 rows, the 3 nodes above them, the 2 above those, the top and the
+        // root, rather than 6 paths of 5 hashes each.
+        assert_eq!(hashes, 6 + 3 + 2 + 1 + 1);
+
+        // the first rejected opening: a wrong row, rows out of order, or a row of another length.
+        let mut tampered = openings.clone();
+        tampered[4].row[0] += Scalar::one();
+        assert_eq!(verify_rows(&root, &tampered).0, Err(4));
+
+        let mut swapped = openings.clone();
+        swapped.swap(2, 3);
+        assert_eq!(verify_rows(&root, &swapped).0, Err(2));
+
+        let mut short = openings.clone();
+        short[1].row.pop();
+        assert_eq!(verify_rows(&root, &short).0, Err(1));
+    }
+}
@@ -0,0 +1,456 @@
+// Row membership: V holds the root of M̃(m * n) (see `CommittedMatrix::commit_mle`) and v, P
+// claims that some row of M equals v, without opening that row.
+//
+// P commits the indicator S = e_{i*} of a row with M_{i*} = v by `ni_sumcheck::mlpc`, then V
+// derives ρ ∈ F^(log n), τ ∈ F^(log m) and β. With h the combination of each row minus v by
+// eq̃(ρ,·), which is M̃ at the column ρ,
+//
+//     h(i) = ∑_j eq̃(ρ,j)·(M_{i,j} - v_j) = M̃(i,ρ) - ṽ(ρ)
+//     F(i) = S(i)·h(i) + β·S(i) + β²·eq̃(τ,i)·S(i)·(S(i) - 1)
+//
+// P runs the sum-check of ∑_i F(i) = β over i ∈ {0,1}^(log m), with a scalar challenge r_j of
+// the transcript in each round, so it ends at the claim F(r). P opens S̃(r) and M̃(r,ρ) against
+// their roots, see `mlpc::open`, and V computes ṽ(ρ), eq̃(τ,r) and so F(r) by itself.
+//
+// The statement proven is exactly the coefficients of β^0, β^1 and β^2, for the S committed
+// before ρ, τ and β:
+//
+//     ∑_i S(i)·h(i) = 0,   ∑_i S(i) = 1,   ∑_i eq̃(τ,i)·S(i)·(S(i) - 1) = 0
+//
+// The last one makes S(i) ∈ {0,1} except with log(m)/|F| over τ, so S = e_{i*} for one i* (as
+// m < p), and h(i*) = 0 makes M_{i*} = v except with log(n)/|F| over ρ, as M̃(i*,·) - ṽ is a
+// nonzero multilinear poly of log n variables else. So: some row of M is v.
+//
+// NOTE: the limitations:
+//  - i* is never sent, but it's not hidden: the commitment of S is not salted, so V finds i* by
+//    committing each e_i and comparing the roots. Hiding it needs a hiding commitment and masked
+//    rounds, eg: `ni_sumcheck::sumcheck::masked`.
+//  - S̃(r) and M̃(r,ρ) are revealed, and so are the opened columns of the encoded rows of S and
+//    M, see `mlpc`.
+//  - m must be a power of 2, as zero rows of padding would make v = 0 a row of any M.
+//
+// The transcript order is: (root, v), the root of S, ρ, τ, β, then per round: g_j, r_j. The
+// openings of S̃(r) and M̃(r,ρ) bind their points and values on transcripts of their own.
+use crate::committed::{CommittedMatrix, MleRoot};
+use crate::freivalds::encode_mle_root;
+use crate::matrix::Matrix;
+use crate::matvec::bind;
+use bls12_381::Scalar;
+use ni_sumcheck::mlpc::{self, MlOpening, MlRoot, MlpcError};
+use std::fmt;
+use sumcheck::poly::univar_poly::Polynomial;
+use sumcheck::utils::{eq_eval, eq_table};
+use Fiat_Shamir::codec::{encode_digest, encode_scalars, encode_u64};
+use Fiat_Shamir::Transcript;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MembershipProof {
+    // the root of S̃, absorbed before the challenges.
+    pub indicator: MlRoot,
+    // g_1, ..., g_(log m)
+    pub rounds: Vec<Polynomial>,
+    // S̃(r) and M̃(r,ρ) at the point r of the challenges, with their openings.
+    pub s_value: Scalar,
+    pub s_opening: MlOpening,
+    pub m_value: Scalar,
+    pub m_opening: MlOpening,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MembershipError {
+    ShapeMismatch,
+    WrongRoundNum,
+    DegreeTooLarge { round: usize },
+    RoundCheckFailed { round: usize },
+    // the opening of S̃(r) or M̃(r,ρ) is rejected.
+    OpeningFailed(MlpcError),
+    // F(r) != the last claim
+    FinalCheckFailed,
+}
+
+impl fmt::Display for MembershipError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MembershipError::ShapeMismatch => write!(f, "The shapes of M, v and S mismatch"),
+            MembershipError::WrongRoundNum => write!(f, "Wrong number of rounds"),
+            MembershipError::DegreeTooLarge { round } => {
+                write!(f, "g_{} is of degree above 3", round)
+            }
+            MembershipError::RoundCheckFailed { round } => {
+                write!(f, "Not-equal in round_{}", round)
+            }
+            MembershipError::OpeningFailed(e) => write!(f, "An opening of S̃ or M̃ failed: {}", e),
+            MembershipError::FinalCheckFailed => write!(f, "Verifier rejected the last claim"),
+        }
+    }
+}
+
+impl std::error::Error for MembershipError {}
+
+// ρ, τ and β.
+struct Challenges {
+    rho: Vec<Scalar>,
+    tau: Vec<Scalar>,
+    beta: Scalar,
+}
+
+// Bind the statement and the root of S, and derive the challenges.
+fn init_transcript(
+    transcript: &mut dyn Transcript,
+    root: &MleRoot,
+    v: &[Scalar],
+    indicator: &MlRoot,
+) -> Challenges {
+    let mut message = encode_mle_root(root);
+    message.extend(encode_scalars(v));
+    transcript.append_labeled("membership.statement", &message);
+    let mut message = encode_u64(indicator.var_num as u64).to_vec();
+    message.extend(encode_digest(&indicator.digest));
+    transcript.append_labeled("membership.indicator", &message);
+
+    let mut challenges = |k: usize| {
+        (0..k)
+            .map(|_| transcript.challenge_scalar())
+            .collect::<Vec<_>>()
+    };
+    let rho = challenges(root.cols.trailing_zeros() as usize);
+    let tau = challenges(root.rows.trailing_zeros() as usize);
+    Challenges {
+        rho,
+        tau,
+        beta: transcript.challenge_scalar(),
+    }
+}
+
+// The challenge r_j of round j.
+fn challenge_round(g_j: &Polynomial, transcript: &mut dyn Transcript) -> Scalar {
+    transcript.append_labeled("membership.round", &encode_scalars(&g_j.coeffs()));
+    transcript.challenge_scalar()
+}
+
+// F(i) from S(i), h(i) and eq̃(τ,i).
+fn combine(s: Scalar, h: Scalar, e: Scalar, beta: Scalar) -> Scalar {
+    s * h + beta * s + beta.square() * e * s * (s - Scalar::one())
+}
+
+// g_j(t) = ∑_i F of the lines between the low/high halves of S, h and eq̃(τ,·), which has
+// degree 3.
+fn round_poly(s: &[Scalar], h: &[Scalar], e: &[Scalar], beta: Scalar) -> Polynomial {
+    let half = s.len() / 2;
+    let line = |f: &[Scalar], i: usize, t: Scalar| f[i] + t * (f[i + half] - f[i]);
+    let evals = (0..4u64)
+        .map(|t| {
+            let t = Scalar::from(t);
+            (0..half)
+                .map(|i| combine(line(s, i, t), line(h, i, t), line(e, i, t), beta))
+                .fold(Scalar::zero(), |acc, v| acc + v)
+        })
+        .collect();
+    Polynomial::lagrange_interpolate((0..4u64).map(Scalar::from).collect(), evals)
+}
+
+impl CommittedMatrix {
+    // Prove that some row of the matrix is v, against the root of `commit_mle`, see above. v must
+    // be a row.
+    pub fn prove_row_membership(
+        &self,
+        v: &[Scalar],
+        transcript: &mut dyn Transcript,
+    ) -> MembershipProof {
+        let index = (0..self.matrix.rows())
+            .find(|&i| self.matrix.row(i) == v)
+            .expect("v is not a row of the matrix");
+        self.prove_with_indicator(v, index, transcript)
+    }
+
+    // P with S = e_index, whether M_index is v or not.
+    fn prove_with_indicator(
+        &self,
+        v: &[Scalar],
+        index: usize,
+        transcript: &mut dyn Transcript,
+    ) -> MembershipProof {
+        let m = self.matrix.rows();
+        assert!(
+            m.is_power_of_two(),
+            "the rows of the matrix must be a power of 2"
+        );
+        assert_eq!(v.len(), self.matrix.cols(), "v must have cols entries");
+
+        let (root, m_state) = CommittedMatrix::commit_mle(&self.matrix);
+        let mut s = vec![Scalar::zero(); m];
+        s[index] = Scalar::one();
+        let (indicator, s_state) = mlpc::commit(&s);
+        let Challenges { rho, tau, beta } = init_transcript(transcript, &root, v, &indicator);
+
+        // h = M·eq̃(ρ) - <v, eq̃(ρ)>
+        let eq_rho = eq_table(&rho);
+        let v_rho = Matrix::vec_mul(v, &eq_rho);
+        let mut h = self
+            .matrix
+            .matrix_mul_vec(&eq_rho)
+            .into_iter()
+            .map(|m_i| m_i - v_rho)
+            .collect::<Vec<_>>();
+        let mut e = eq_table(&tau);
+        let mut rounds = vec![];
+        let mut r = vec![];
+        while s.len() > 1 {
+            let g_j = round_poly(&s, &h, &e, beta);
+            let r_j = challenge_round(&g_j, transcript);
+            rounds.push(g_j);
+
+            s = bind(&s, r_j);
+            h = bind(&h, r_j);
+            e = bind(&e, r_j);
+            r.push(r_j);
+        }
+
+        let point = [r.clone(), rho].concat();
+        MembershipProof {
+            indicator,
+            rounds,
+            s_value: s[0],
+            s_opening: mlpc::open(&s_state, &r),
+            m_value: m_state.evaluate(&point),
+            m_opening: mlpc::open(&m_state, &point),
+        }
+    }
+}
+
+// Check that some row of the matrix committed by root is v, see above.
+pub fn verify_row_membership(
+    root: &MleRoot,
+    v: &[Scalar],
+    proof: &MembershipProof,
+    transcript: &mut dyn Transcript,
+) -> Result<(), MembershipError> {
+    let (m, n) = (root.rows, root.cols);
+    let var_num = m.trailing_zeros() as usize;
+    if !m.is_power_of_two() || v.len() != n || proof.indicator.var_num != var_num {
+        return Err(MembershipError::ShapeMismatch);
+    }
+    if proof.rounds.len() != var_num {
+        return Err(MembershipError::WrongRoundNum);
+    }
+    let Challenges { rho, tau, beta } = init_transcript(transcript, root, v, &proof.indicator);
+
+    // 1. the sum-check from β, which ends at the claim F(r)
+    let mut claim = beta;
+    let mut r = Vec::with_capacity(var_num);
+    for (j, g_j) in proof.rounds.iter().enumerate() {
+        if g_j.num_coeffs() > 4 {
+            return Err(MembershipError::DegreeTooLarge { round: j + 1 });
+        }
+        if g_j.evaluate(Scalar::zero()) + g_j.evaluate(Scalar::one()) != claim {
+            return Err(MembershipError::RoundCheckFailed { round: j + 1 });
+        }
+        let r_j = challenge_round(g_j, transcript);
+        claim = g_j.evaluate(r_j);
+        r.push(r_j);
+    }
+
+    // 2. S̃(r) and M̃(r,ρ) are the committed ones
+    let point = [r.clone(), rho.clone()].concat();
+    mlpc::verify(&proof.indicator, &r, proof.s_value, &proof.s_opening)
+        .and_then(|()| mlpc::verify(&root.table, &point, proof.m_value, &proof.m_opening))
+        .map_err(MembershipError::OpeningFailed)?;
+
+    // 3. F(r) by V itself
+    let h = proof.m_value - Matrix::vec_mul(v, &eq_table(&rho));
+    if combine(proof.s_value, h, eq_eval(&tau, &r), beta) != claim {
+        return Err(MembershipError::FinalCheckFailed);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use Fiat_Shamir::default::Keccak256Transcript;
+
+    fn prove(committed: &CommittedMatrix, v: &[Scalar]) -> MembershipProof {
+        committed.prove_row_membership(v, &mut Keccak256Transcript::default())
+    }
+
+    fn verify(
+        root: &MleRoot,
+        v: &[Scalar],
+        proof: &MembershipProof,
+    ) -> Result<(), MembershipError> {
+        verify_row_membership(root, v, proof, &mut Keccak256Transcript::default())
+    }
+
+    fn commit(m: &Matrix) -> (MleRoot, CommittedMatrix) {
+        (
+            CommittedMatrix::commit_mle(m).0,
+            CommittedMatrix::commit(m).1,
+        )
+    }
+
+    #[test]
+    fn test_row_membership() {
+        let m = Matrix::random(16, 8);
+        let (root, committed) = commit(&m);
+        let v = m.row(3).to_vec();
+        let proof = prove(&committed, &v);
+        assert_eq!(verify(&root, &v, &proof), Ok(()));
+        assert_eq!(proof.rounds.len(), 4);
+
+        // a single row has no rounds.
+        let m = Matrix::random(1, 8);
+        let (root, committed) = commit(&m);
+        let proof = prove(&committed, m.row(0));
+        assert_eq!(verify(&root, m.row(0), &proof), Ok(()));
+    }
+
+    #[test]
+    fn test_not_a_row() {
+        let m = Matrix::random(16, 8);
+        let (root, committed) = commit(&m);
+        let v = m.row(3).to_vec();
+        let mut wrong = v.clone();
+        wrong[5] += Scalar::one();
+
+        // the proof of row 3 for v with one entry off
+        let proof = prove(&committed, &v);
+        assert_eq!(
+            verify(&root, &wrong, &proof),
+            Err(MembershipError::RoundCheckFailed { round: 1 })
+        );
+
+        // P with S = e_3 for it: ∑_i F(i) = h(3) + β, not β.
+        let proof = committed.prove_with_indicator(&wrong, 3, &mut Keccak256Transcript::default());
+        assert_eq!(
+            verify(&root, &wrong, &proof),
+            Err(MembershipError::RoundCheckFailed { round: 1 })
+        );
+    }
+
+    // P claims a v which is no row, with S = e_3, and shifts each g_j by a constant so it sums to
+    // the claim, with the challenges squeezed after the shifted g_j. It passes every round, but
+    // ends at a claim other than F(r), so either the values it sends or their openings are
+    // rejected.
+    #[test]
+    fn test_lying_prover() {
+        let m = Matrix::random(16, 8);
+        let (root, m_state) = CommittedMatrix::commit_mle(&m);
+        let mut v = m.row(3).to_vec();
+        v[5] += Scalar::one();
+
+        let mut transcript = Keccak256Transcript::default();
+        let mut s = vec![Scalar::zero(); 16];
+        s[3] = Scalar::one();
+        let (indicator, s_state) = mlpc::commit(&s);
+        let Challenges { rho, tau, beta } = init_transcript(&mut transcript, &root, &v, &indicator);
+        let eq_rho = eq_table(&rho);
+        let v_rho = Matrix::vec_mul(&v, &eq_rho);
+        let mut h = m
+            .matrix_mul_vec(&eq_rho)
+            .into_iter()
+            .map(|m_i| m_i - v_rho)
+            .collect::<Vec<_>>();
+        let mut e = eq_table(&tau);
+        let (mut claim, mut rounds, mut r) = (beta, vec![], vec![]);
+        let two_inv = Scalar::from(2).invert().unwrap();
+        while s.len() > 1 {
+            let honest = round_poly(&s, &h, &e, beta);
+            let sum = honest.evaluate(Scalar::zero()) + honest.evaluate(Scalar::one());
+            let g_j = &honest + &Polynomial::constant((claim - sum) * two_inv);
+            let r_j = challenge_round(&g_j, &mut transcript);
+            claim = g_j.evaluate(r_j);
+            rounds.push(g_j);
+            s = bind(&s, r_j);
+            h = bind(&h, r_j);
+            e = bind(&e, r_j);
+            r.push(r_j);
+        }
+        assert_ne!(claim, combine(s[0], h[0], e[0], beta));
+
+        // the true S̃(r) and M̃(r,ρ), which fail the last claim.
+        let point = [r.clone(), rho].concat();
+        let mut proof = MembershipProof {
+            indicator,
+            rounds,
+            s_value: s[0],
+            s_opening: mlpc::open(&s_state, &r),
+            m_value: m_state.evaluate(&point),
+            m_opening: mlpc::open(&m_state, &point),
+        };
+        assert_eq!(
+            verify(&root, &v, &proof),
+            Err(MembershipError::FinalCheckFailed)
+        );
+
+        // the M̃(r,ρ) the last claim asks for, which the opening doesn't back.
+        let h_claimed =
+            (claim - combine(s[0], Scalar::zero(), e[0], beta)) * s[0].invert().unwrap();
+        proof.m_value = h_claimed + v_rho;
+        assert!(matches!(
+            verify(&root, &v, &proof),
+            Err(MembershipError::OpeningFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_tampered_openings() {
+        let m = Matrix::random(16, 8);
+        let (root, committed) = commit(&m);
+        let v = m.row(3).to_vec();
+        let proof = prove(&committed, &v);
+
+        let mut crafted = proof.clone();
+        crafted.m_opening.columns[0][0] += Scalar::one();
+        assert_eq!(
+            verify(&root, &v, &crafted),
+            Err(MembershipError::OpeningFailed(MlpcError::MerkleCheckFailed))
+        );
+
+        let mut crafted = proof.clone();
+        crafted.s_value += Scalar::one();
+        assert!(matches!(
+            verify(&root, &v, &crafted),
+            Err(MembershipError::OpeningFailed(_))
+        ));
+
+        let m = Matrix::random(12, 8);
+        let (root, _) = CommittedMatrix::commit_mle(&m);
+        assert_eq!(
+            verify(&root, &v, &proof),
+            Err(MembershipError::ShapeMismatch)
+        );
+    }
+
+    // The statement is "some row is v", no more: a v of two rows is proven by either, and the
+    // index isn't hidden from V.
+    #[test]
+    fn test_exact_statement() {
+        let random = Matrix::random(16, 8);
+        let mut rows = (0..16).map(|i| random.row(i).to_vec()).collect::<Vec<_>>();
+        rows[9] = rows[3].clone();
+        let m = Matrix::from_rows(rows);
+        let (root, committed) = commit(&m);
+        let v = m.row(3).to_vec();
+        for index in [3, 9] {
+            let proof =
+                committed.prove_with_indicator(&v, index, &mut Keccak256Transcript::default());
+            assert_eq!(verify(&root, &v, &proof), Ok(()));
+
+            let found = (0..16).find(|&i| {
+                let e_i = (0..16)
+                    .map(|k| Scalar::from((k == i) as u64))
+                    .collect::<Vec<_>>();
+                mlpc::commit(&e_i).0 == proof.indicator
+            });
+            assert_eq!(found, Some(index));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "v is not a row of the matrix")]
+    fn test_prove_not_a_row() {
+        let m = Matrix::random(4, 2);
+        let (_, committed) = CommittedMatrix::commit(&m);
+        prove(&committed, &[Scalar::one(), Scalar::one()]);
+    }
+}
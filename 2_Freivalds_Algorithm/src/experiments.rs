@@ -0,0 +1,318 @@
+// Measure the soundness error of Freivalds empirically.
+//
+// P claims C' = A · B, where C' is C = A · B with a minimal corruption E, so V accepts iff
+// E · x = 0 for x = (1, r, ..., r^(n-1)). E · x is a poly of degree n-1 in r, so V accepts with
+// probability at most (n-1)/|S| when r is sampled from S, see `ChallengeDistribution`.
+// eg: E = delta at (i, j), j > 0, then E · x = delta · r^j, and V accepts iff r = 0.
+//
+// All randomness comes from the seed, so an experiment can be replayed.
+use crate::matrix::Matrix;
+use crate::utils::gen_x_with;
+use crate::ChallengeDistribution;
+use bls12_381::Scalar;
+use ff::Field;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rand_core::RngCore;
+
+/// How the claimed product is corrupted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corruption {
+    /// C[i][j] += delta
+    SingleEntry { i: usize, j: usize },
+    /// C[i] += d, for a random d
+    Row { i: usize },
+    /// C += u · v^T, for random u and v
+    RankOne,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExperimentConfig {
+    pub n: usize, // A, B and C are n * n
+    pub corruption: Corruption,
+    pub distributions: Vec<ChallengeDistribution>,
+    pub trials: usize,
+    pub seed: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DistributionResult {
+    pub distribution: ChallengeDistribution,
+    pub trials: usize,
+    pub accepted: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExperimentReport {
+    pub n: usize,
+    pub corruption: Corruption,
+    pub seed: u64,
+    pub results: Vec<DistributionResult>,
+}
+
+/// C'[i][j] = C[i][j] + delta
+pub fn corrupt_single_entry(c: &Matrix, i: usize, j: usize, delta: Scalar) -> Matrix {
+    assert!(i < c.rows() && j < c.cols(), "the entry is out of range");
+    let rows = (0..c.rows())
+        .map(|k| {
+            let mut row = c.row(k).to_vec();
+            if k == i {
+                row[j] += delta;
+            }
+            row
+        })
+        .collect::<Vec<_>>();
+    Matrix::from_rows(rows)
+}
+
+/// C'[i] = C[i] + delta
+pub fn corrupt_row(c: &Matrix, i: usize, delta: &[Scalar]) -> Matrix {
+    assert!(i < c.rows(), "the row is out of range");
+    assert_eq!(delta.len(), c.cols());
+    let rows = (0..c.rows())
+        .map(|k| {
+            let mut row = c.row(k).to_vec();
+            if k == i {
+                row.iter_mut().zip(delta).for_each(|(c_k, d)| *c_k += d);
+            }
+            row
+        })
+        .collect::<Vec<_>>();
+    Matrix::from_rows(rows)
+}
+
+/// C' = C + u · v^T
+pub fn corrupt_rank_one(c: &Matrix, u: &[Scalar], v: &[Scalar]) -> Matrix {
+    assert_eq!(u.len(), c.rows());
+    assert_eq!(v.len(), c.cols());
+    let rows = (0..c.rows())
+        .map(|k| {
+            c.row(k)
+                .iter()
+                .zip(v)
+                .map(|(c_kj, v_j)| c_kj + u[k] * v_j)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    Matrix::from_rows(rows)
+}
+
+fn random_nonzero(rng: &mut impl RngCore) -> Scalar {
+    loop {
+        let s = Scalar::random(&mut *rng);
+        if s != Scalar::zero() {
+            return s;
+        }
+    }
+}
+
+fn random_matrix(rng: &mut impl RngCore, n: usize) -> Matrix {
+    let rows = (0..n)
+        .map(|_| {
+            (0..n)
+                .map(|_| Scalar::random(&mut *rng))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    Matrix::from_rows(rows)
+}
+
+pub fn run_soundness_experiment(cfg: ExperimentConfig) -> ExperimentReport {
+    assert!(cfg.n > 0, "the matrices are empty");
+    let mut rng = StdRng::seed_from_u64(cfg.seed);
+
+    let a = random_matrix(&mut rng, cfg.n);
+    let b = random_matrix(&mut rng, cfg.n);
+    let c = Matrix::mul(&a, &b);
+    let corrupted = match cfg.corruption {
+        Corruption::SingleEntry { i, j } => {
+            corrupt_single_entry(&c, i, j, random_nonzero(&mut rng))
+        }
+        Corruption::Row { i } => {
+            let delta = (0..cfg.n)
+                .map(|_| random_nonzero(&mut rng))
+                .collect::<Vec<_>>();
+            corrupt_row(&c, i, &delta)
+        }
+        Corruption::RankOne => {
+            let u = (0..cfg.n)
+                .map(|_| random_nonzero(&mut rng))
+                .collect::<Vec<_>>();
+            let v = (0..cfg.n)
+                .map(|_| random_nonzero(&mut rng))
+                .collect::<Vec<_>>();
+            corrupt_rank_one(&c, &u, &v)
+        }
+    };
+
+    let results = cfg
+        .distributions
+        .iter()
+        .map(|dist| {
+            let accepted = (0..cfg.trials)
+                .filter(|_| {
+                    let x = gen_x_with(&mut rng, cfg.n, *dist);
+                    // y = C'x, z = A(Bx)
+                    let y = corrupted.matrix_mul_vec(&x);
+                    let z = a.matrix_mul_vec(&b.matrix_mul_vec(&x));
+                    y == z
+                })
+                .count();
+            DistributionResult {
+                distribution: *dist,
+                trials: cfg.trials,
+                accepted,
+            }
+        })
+        .collect();
+
+    ExperimentReport {
+        n: cfg.n,
+        corruption: cfg.corruption,
+        seed: cfg.seed,
+        results,
+    }
+}
+
+impl DistributionResult {
+    pub fn acceptance_rate(&self) -> f64 {
+        if self.trials == 0 {
+            return 0.0;
+        }
+        self.accepted as f64 / self.trials as f64
+    }
+}
+
+fn distribution_to_json(dist: &ChallengeDistribution) -> String {
+    match dist {
+        ChallengeDistribution::Binary => r#"{"type":"binary"}"#.to_string(),
+        ChallengeDistribution::SmallRange(k) => format!(r#"{{"type":"small_range","size":{}}}"#, k),
+        ChallengeDistribution::FullField => r#"{"type":"full_field"}"#.to_string(),
+    }
+}
+
+fn corruption_to_json(corruption: &Corruption) -> String {
+    match corruption {
+        Corruption::SingleEntry { i, j } => {
+            format!(r#"{{"type":"single_entry","i":{},"j":{}}}"#, i, j)
+        }
+        Corruption::Row { i } => format!(r#"{{"type":"row","i":{}}}"#, i),
+        Corruption::RankOne => r#"{"type":"rank_one"}"#.to_string(),
+    }
+}
+
+impl ExperimentReport {
+    /// The summary in JSON, eg:
+    /// {"n":4,"corruption":{"type":"rank_one"},"seed":7,
+    ///  "results":[{"distribution":{"type":"binary"},"trials":10,"accepted":5,"acceptance_rate":0.5}]}
+    pub fn to_json(&self) -> String {
+        let results = self
+            .results
+            .iter()
+            .map(|r| {
+                format!(
+                    r#"{{"distribution":{},"trials":{},"accepted":{},"acceptance_rate":{}}}"#,
+                    distribution_to_json(&r.distribution),
+                    r.trials,
+                    r.accepted,
+                    r.acceptance_rate()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            r#"{{"n":{},"corruption":{},"seed":{},"results":[{}]}}"#,
+            self.n,
+            corruption_to_json(&self.corruption),
+            self.seed,
+            results
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::experiments::{
+        corrupt_single_entry, run_soundness_experiment, Corruption, ExperimentConfig,
+    };
+    use crate::matrix::Matrix;
+    use crate::ChallengeDistribution;
+    use bls12_381::Scalar;
+
+    fn config(corruption: Corruption, dist: ChallengeDistribution) -> ExperimentConfig {
+        ExperimentConfig {
+            n: 4,
+            corruption,
+            distributions: vec![dist],
+            trials: 1000,
+            seed: 42,
+        }
+    }
+
+    #[test]
+    fn test_binary_single_entry() {
+        // V accepts iff r = 0
+        let cfg = config(
+            Corruption::SingleEntry { i: 0, j: 1 },
+            ChallengeDistribution::Binary,
+        );
+        let report = run_soundness_experiment(cfg);
+        let rate = report.results[0].acceptance_rate();
+        assert!(0.4 < rate && rate < 0.6, "acceptance rate: {}", rate);
+
+        // it's replayable
+        assert_eq!(
+            run_soundness_experiment(config(
+                Corruption::SingleEntry { i: 0, j: 1 },
+                ChallengeDistribution::Binary,
+            )),
+            report
+        );
+    }
+
+    #[test]
+    fn test_full_field() {
+        for corruption in [
+            Corruption::SingleEntry { i: 2, j: 3 },
+            Corruption::Row { i: 1 },
+            Corruption::RankOne,
+        ] {
+            let report =
+                run_soundness_experiment(config(corruption, ChallengeDistribution::FullField));
+            assert_eq!(report.results[0].accepted, 0);
+        }
+    }
+
+    #[test]
+    fn test_corrupt_single_entry() {
+        let c = Matrix::random(2, 2);
+        let corrupted = corrupt_single_entry(&c, 1, 0, Scalar::one());
+        assert_eq!(corrupted.row(0), c.row(0));
+        assert_eq!(corrupted.row(1)[0], c.row(1)[0] + Scalar::one());
+        assert_eq!(corrupted.row(1)[1], c.row(1)[1]);
+    }
+
+    #[test]
+    fn test_report_to_json() {
+        let cfg = ExperimentConfig {
+            n: 2,
+            corruption: Corruption::RankOne,
+            distributions: vec![
+                ChallengeDistribution::SmallRange(3),
+                ChallengeDistribution::FullField,
+            ],
+            trials: 0,
+            seed: 7,
+        };
+        let report = run_soundness_experiment(cfg);
+        assert_eq!(
+            report.to_json(),
+            concat!(
+                r#"{"n":2,"corruption":{"type":"rank_one"},"seed":7,"results":["#,
+                r#"{"distribution":{"type":"small_range","size":3},"trials":0,"accepted":0,"acceptance_rate":0},"#,
+                r#"{"distribution":{"type":"full_field"},"trials":0,"accepted":0,"acceptance_rate":0}"#,
+                "]}"
+            )
+        );
+    }
+}
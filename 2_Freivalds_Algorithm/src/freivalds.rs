@@ -0,0 +1,851 @@
+// Freivalds with committed inputs: V only holds the Merkle roots of A, B and C (row-wise), and
+// P claims C = A · B.
+//
+// First P opens every row of A, B and C, which V checks against the roots, each matrix as one
+// batch, see `committed::verify_rows`. Then in each round:
+//  1. V derives x from the transcript, see `Transcript::challenge_vector`.
+//  2. P responds y = B · x and z = C · x, which are absorbed.
+//  3. V checks every entry of the response against its opened row,
+//          y_j == <B_j, x>, z_i == <C_i, x>
+//     so the response is the one of the committed B and C. A check at a few random rows only
+//     would let P answer z = A · y for a committed C != A · B, and pass unless a wrong row of
+//     C is one of them.
+//  4. V checks A · y == z with the opened rows of A.
+//
+// So V reads the three matrices once, O(n^2) as the checks are, and holds only the roots before.
+//
+// The transcript order is: roots, then per round: x, (y, z).
+//
+// `respond` and `verify_transcript` are the non-interactive one round of it, where P sends the
+// openings along with (y, z) as `FreivaldsResponses`, which V checks as the oracle of
+// `verify_committed`, eg: for a claim C = A · B inside a larger proof, whose transcript is
+// shared. `verify_uncommitted` is for V who holds A, B and C, and only x comes from the
+// transcript.
+//
+// x is a seed expanded deterministically: a single squeeze, which is expanded into n independent
+// scalars, so a wrong C · x = A · (B · x) passes with probability 1/|F| rather than the (n-1)/|F|
+// of the powers of one r, see `sumcheck::rlc::CoeffMode`. B · x and C · x are the combinations of
+// the columns by x, so each checked entry is a `rlc::combine_scalars` of a row.
+//
+// `respond_statement` and `verify_statement` are the same round for the roots of a `Statement`,
+// under the labels "A", "B" and "C", which is bound first, so V looks the roots up by label
+// rather than taking them in an order the caller may get wrong.
+//
+// Each verify has a `_with_cost` variant, which returns the ops of V along with the result, see
+// `sumcheck::cost`, eg: the O(n^2) of `verify_uncommitted` against the O(n^3) of C = A · B.
+//
+// `check_auto` is for V who holds A, B and C and picks the cheaper of recomputing A · B and
+// Freivalds by a cost model, see `auto`.
+use crate::committed::{verify_rows, CommittedMatrix, MatrixRoot, MleRoot, RowOpening};
+use crate::matrix::Matrix;
+use bls12_381::Scalar;
+use std::fmt;
+use sumcheck::cost::{CostReport, CostSink};
+use sumcheck::rlc::{self, CoeffMode};
+use Fiat_Shamir::codec::{encode_digest, encode_scalars, encode_u64};
+use Fiat_Shamir::protocol::ProtocolId;
+use Fiat_Shamir::statement::{Statement, StatementError};
+use Fiat_Shamir::stream::MessageWriter;
+use Fiat_Shamir::Transcript;
+
+mod auto;
+
+pub use auto::{
+    check_auto, check_auto_with, rounds_for, CheckOutcome, CostModel, Estimates, Strategy,
+    SAMPLE_WEIGHT,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatrixId {
+    A,
+    B,
+    C,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FreivaldsError {
+    ShapeMismatch,
+    // the opened row is not consistent with the root.
+    OpeningFailed { matrix: MatrixId, row: usize },
+    // the response (y or z) is not consistent with the opened row.
+    ResponseMismatch { matrix: MatrixId, row: usize },
+    // (A · y)_i != z_i
+    ProductMismatch { row: usize },
+    // the roots can't be read from the statement, see `verify_statement`.
+    Statement(StatementError),
+}
+
+impl fmt::Display for FreivaldsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FreivaldsError::ShapeMismatch => write!(f, "The shapes of A, B and C mismatch"),
+            FreivaldsError::OpeningFailed { matrix, row } => {
+                write!(f, "The opening of row {} of {:?} failed", row, matrix)
+            }
+            FreivaldsError::ResponseMismatch { matrix, row } => {
+                write!(f, "The response differs from row {} of {:?}", row, matrix)
+            }
+            FreivaldsError::ProductMismatch { row } => write!(f, "(A · y)_{} != z_{}", row, row),
+            FreivaldsError::Statement(e) => write!(f, "Bad statement: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for FreivaldsError {}
+
+impl From<StatementError> for FreivaldsError {
+    fn from(e: StatementError) -> Self {
+        FreivaldsError::Statement(e)
+    }
+}
+
+// The protocol of the statements of `respond_statement`.
+pub fn protocol_id() -> ProtocolId {
+    ProtocolId::new("Freivalds_Algorithm", "committed_freivalds", 1, &[])
+}
+
+// The statement of the claim C = A · B on the committed A, B and C.
+pub fn statement(a: &MatrixRoot, b: &MatrixRoot, c: &MatrixRoot) -> Statement {
+    Statement::new(protocol_id())
+        .with_matrix("A", *a)
+        .with_matrix("B", *b)
+        .with_matrix("C", *c)
+}
+
+// The prover's side of the protocol.
+pub trait MatrixOracle {
+    // (y, z) = (B · x, C · x)
+    fn respond(&self, x: &[Scalar]) -> (Vec<Scalar>, Vec<Scalar>);
+
+    fn open(&self, matrix: MatrixId, row: usize) -> RowOpening;
+}
+
+// The honest prover, who holds the committed A, B and C.
+pub struct CommittedProver {
+    pub a: CommittedMatrix,
+    pub b: CommittedMatrix,
+    pub c: CommittedMatrix,
+}
+
+impl CommittedProver {
+    // Commit A, B and C = A · B.
+    pub fn new(a: &Matrix, b: &Matrix) -> ((MatrixRoot, MatrixRoot, MatrixRoot), Self) {
+        let c = Matrix::mul(a, b);
+        Self::from_committed(a, b, &c)
+    }
+
+    pub fn from_committed(
+        a: &Matrix,
+        b: &Matrix,
+        c: &Matrix,
+    ) -> ((MatrixRoot, MatrixRoot, MatrixRoot), Self) {
+        let (root_a, a) = CommittedMatrix::commit(a);
+        let (root_b, b) = CommittedMatrix::commit(b);
+        let (root_c, c) = CommittedMatrix::commit(c);
+        ((root_a, root_b, root_c), Self { a, b, c })
+    }
+}
+
+impl MatrixOracle for CommittedProver {
+    fn respond(&self, x: &[Scalar]) -> (Vec<Scalar>, Vec<Scalar>) {
+        let x = x.to_vec();
+        (
+            self.b.matrix().matrix_mul_vec(&x),
+            self.c.matrix().matrix_mul_vec(&x),
+        )
+    }
+
+    fn open(&self, matrix: MatrixId, row: usize) -> RowOpening {
+        match matrix {
+            MatrixId::A => self.a.open_row(row),
+            MatrixId::B => self.b.open_row(row),
+            MatrixId::C => self.c.open_row(row),
+        }
+    }
+}
+
+// P's messages of one round with the openings, so that V checks them without asking P,
+// see `respond`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FreivaldsResponses {
+    // all the rows of A, B and C, in order.
+    pub a_rows: Vec<RowOpening>,
+    pub b_rows: Vec<RowOpening>,
+    pub c_rows: Vec<RowOpening>,
+    // (y, z) = (B · x, C · x)
+    pub y: Vec<Scalar>,
+    pub z: Vec<Scalar>,
+}
+
+// The responses as the oracle of a single round, whatever x is, see `verify_transcript`.
+impl MatrixOracle for FreivaldsResponses {
+    fn respond(&self, _x: &[Scalar]) -> (Vec<Scalar>, Vec<Scalar>) {
+        (self.y.clone(), self.z.clone())
+    }
+
+    fn open(&self, matrix: MatrixId, row: usize) -> RowOpening {
+        match matrix {
+            MatrixId::A => self.a_rows[row].clone(),
+            MatrixId::B => self.b_rows[row].clone(),
+            MatrixId::C => self.c_rows[row].clone(),
+        }
+    }
+}
+
+// x of length n from a single squeeze of the transcript.
+fn challenge_x<S: CostSink>(
+    transcript: &mut dyn Transcript,
+    n: usize,
+    sink: &mut S,
+) -> Vec<Scalar> {
+    // the seed, and a Keccak512 per scalar.
+    sink.count(CostReport::hashes(1 + n));
+    rlc::coeffs_from_transcript(transcript, "freivalds.x", n, CoeffMode::Independent)
+}
+
+// rows: u64, cols: u64, digest
+pub(crate) fn encode_root(root: &MatrixRoot) -> Vec<u8> {
+    let mut bytes = encode_u64(root.rows as u64).to_vec();
+    bytes.extend(encode_u64(root.cols as u64));
+    bytes.extend(encode_digest(&root.digest));
+    bytes
+}
+
+// rows: u64, cols: u64, the digest of the table, see `CommittedMatrix::commit_mle`.
+pub(crate) fn encode_mle_root(root: &MleRoot) -> Vec<u8> {
+    let mut bytes = encode_u64(root.rows as u64).to_vec();
+    bytes.extend(encode_u64(root.cols as u64));
+    bytes.extend(encode_digest(&root.table.digest));
+    bytes
+}
+
+// rows: u64, cols: u64, rows in order, each row as scalars. Written row by row, so the
+// matrix isn't encoded in full, see `Fiat_Shamir::stream`.
+pub(crate) fn write_matrix<T: Transcript + ?Sized>(writer: &mut MessageWriter<T>, m: &Matrix) {
+    writer.push_bytes(&encode_u64(m.rows() as u64));
+    writer.push_bytes(&encode_u64(m.cols() as u64));
+    for i in 0..m.rows() {
+        let row = m.row(i);
+        writer.push_bytes(&encode_u64(row.len() as u64));
+        for s in row.iter() {
+            writer.push_scalar(s);
+        }
+    }
+}
+
+// A(m * n), B(n * p), C(m * p)
+fn check_shapes(
+    (a_rows, a_cols): (usize, usize),
+    (b_rows, b_cols): (usize, usize),
+    (c_rows, c_cols): (usize, usize),
+) -> Result<(), FreivaldsError> {
+    if a_cols != b_rows || a_rows != c_rows || b_cols != c_cols {
+        return Err(FreivaldsError::ShapeMismatch);
+    }
+    Ok(())
+}
+
+fn absorb_roots(transcript: &mut dyn Transcript, roots: [&MatrixRoot; 3]) {
+    let message = roots.map(encode_root).concat();
+    transcript.append_labeled("freivalds.roots", &message);
+}
+
+fn absorb_response(transcript: &mut dyn Transcript, y: &[Scalar], z: &[Scalar]) {
+    let mut message = encode_scalars(y);
+    message.extend(encode_scalars(z));
+    transcript.append_labeled("freivalds.response", &message);
+}
+
+// <a, b> of n elements, see `Matrix::vec_mul`.
+pub(crate) fn inner_product_cost(n: usize) -> CostReport {
+    CostReport::mults(n) + CostReport::adds(n)
+}
+
+// Every row of the matrix, in order, which are bound by the root.
+fn open_rows<S: CostSink>(
+    prover: &dyn MatrixOracle,
+    root: &MatrixRoot,
+    matrix: MatrixId,
+    sink: &mut S,
+) -> Result<Vec<Vec<Scalar>>, FreivaldsError> {
+    let openings = (0..root.rows)
+        .map(|i| prover.open(matrix, i))
+        .collect::<Vec<_>>();
+    let (res, hashes) = verify_rows(root, &openings);
+    // each node of the paths once, and the compare of the root.
+    sink.count(CostReport::hashes(hashes) + CostReport::comparisons(1));
+    res.map_err(|row| FreivaldsError::OpeningFailed { matrix, row })?;
+    Ok(openings.into_iter().map(|o| o.row).collect())
+}
+
+// The response against every opened row of B or C, eg: y_j == <B_j, x> for each j.
+fn check_response<S: CostSink>(
+    response: &[Scalar],
+    rows: &[Vec<Scalar>],
+    x: &[Scalar],
+    matrix: MatrixId,
+    sink: &mut S,
+) -> Result<(), FreivaldsError> {
+    for (row, (opened, r)) in rows.iter().zip(response.iter()).enumerate() {
+        sink.count(inner_product_cost(x.len()) + CostReport::comparisons(1));
+        if rlc::combine_scalars(opened, x) != Ok(*r) {
+            return Err(FreivaldsError::ResponseMismatch { matrix, row });
+        }
+    }
+    Ok(())
+}
+
+// A · y == z, row by row.
+fn check_product<'a, S: CostSink>(
+    a_rows: impl Iterator<Item = &'a [Scalar]>,
+    y: &[Scalar],
+    z: &[Scalar],
+    sink: &mut S,
+) -> Result<(), FreivaldsError> {
+    for (row, (a_row, z_row)) in a_rows.zip(z.iter()).enumerate() {
+        sink.count(inner_product_cost(y.len()) + CostReport::comparisons(1));
+        if rlc::combine_scalars(a_row, y) != Ok(*z_row) {
+            return Err(FreivaldsError::ProductMismatch { row });
+        }
+    }
+    Ok(())
+}
+
+pub fn verify_committed(
+    roots: (&MatrixRoot, &MatrixRoot, &MatrixRoot),
+    prover: &dyn MatrixOracle,
+    rounds: usize,
+    transcript: &mut dyn Transcript,
+) -> Result<(), FreivaldsError> {
+    verify_committed_with_sink(roots, prover, rounds, transcript, &mut ())
+}
+
+#[cfg(any(test, feature = "cost"))]
+pub fn verify_committed_with_cost(
+    roots: (&MatrixRoot, &MatrixRoot, &MatrixRoot),
+    prover: &dyn MatrixOracle,
+    rounds: usize,
+    transcript: &mut dyn Transcript,
+) -> (Result<(), FreivaldsError>, CostReport) {
+    let mut cost = CostReport::default();
+    let res = verify_committed_with_sink(roots, prover, rounds, transcript, &mut cost);
+    (res, cost)
+}
+
+fn verify_committed_with_sink<S: CostSink>(
+    roots: (&MatrixRoot, &MatrixRoot, &MatrixRoot),
+    prover: &dyn MatrixOracle,
+    rounds: usize,
+    transcript: &mut dyn Transcript,
+    sink: &mut S,
+) -> Result<(), FreivaldsError> {
+    let (root_a, root_b, root_c) = roots;
+    check_shapes(
+        (root_a.rows, root_a.cols),
+        (root_b.rows, root_b.cols),
+        (root_c.rows, root_c.cols),
+    )?;
+
+    // 0. absorb the roots with the shapes
+    absorb_roots(transcript, [root_a, root_b, root_c]);
+
+    // 1. the rows of A, B and C, which are bound by the roots.
+    let a_rows = open_rows(prover, root_a, MatrixId::A, sink)?;
+    let b_rows = open_rows(prover, root_b, MatrixId::B, sink)?;
+    let c_rows = open_rows(prover, root_c, MatrixId::C, sink)?;
+
+    for _ in 0..rounds {
+        // 2. x and the response
+        let x = challenge_x(transcript, root_b.cols, sink);
+        let (y, z) = prover.respond(&x);
+        if y.len() != root_b.rows || z.len() != root_c.rows {
+            return Err(FreivaldsError::ShapeMismatch);
+        }
+        absorb_response(transcript, &y, &z);
+
+        // 3. y and z are the ones of the committed B and C, entry by entry
+        check_response(&y, &b_rows, &x, MatrixId::B, sink)?;
+        check_response(&z, &c_rows, &x, MatrixId::C, sink)?;
+
+        // 4. A · y == z
+        check_product(a_rows.iter().map(|r| r.as_slice()), &y, &z, sink)?;
+    }
+    Ok(())
+}
+
+// P's side of the non-interactive round, with the same transcript order as `verify_committed`.
+pub fn respond(
+    a: &CommittedMatrix,
+    b: &CommittedMatrix,
+    c: &CommittedMatrix,
+    transcript: &mut dyn Transcript,
+) -> FreivaldsResponses {
+    absorb_roots(transcript, [&a.root(), &b.root(), &c.root()]);
+
+    let x = challenge_x(transcript, b.matrix().cols(), &mut ());
+    let (y, z) = (b.matrix().matrix_mul_vec(&x), c.matrix().matrix_mul_vec(&x));
+    absorb_response(transcript, &y, &z);
+
+    let open_all = |m: &CommittedMatrix| (0..m.matrix().rows()).map(|k| m.open_row(k)).collect();
+    FreivaldsResponses {
+        a_rows: open_all(a),
+        b_rows: open_all(b),
+        c_rows: open_all(c),
+        y,
+        z,
+    }
+}
+
+// V's side of `respond`, which checks the same as one round of `verify_committed`.
+pub fn verify_transcript(
+    a_commit: &MatrixRoot,
+    b_commit: &MatrixRoot,
+    c_commit: &MatrixRoot,
+    responses: &FreivaldsResponses,
+    transcript: &mut dyn Transcript,
+) -> Result<(), FreivaldsError> {
+    let commits = (a_commit, b_commit, c_commit);
+    verify_transcript_with_sink(commits, responses, transcript, &mut ())
+}
+
+#[cfg(any(test, feature = "cost"))]
+pub fn verify_transcript_with_cost(
+    a_commit: &MatrixRoot,
+    b_commit: &MatrixRoot,
+    c_commit: &MatrixRoot,
+    responses: &FreivaldsResponses,
+    transcript: &mut dyn Transcript,
+) -> (Result<(), FreivaldsError>, CostReport) {
+    let mut cost = CostReport::default();
+    let commits = (a_commit, b_commit, c_commit);
+    let res = verify_transcript_with_sink(commits, responses, transcript, &mut cost);
+    (res, cost)
+}
+
+fn verify_transcript_with_sink<S: CostSink>(
+    commits: (&MatrixRoot, &MatrixRoot, &MatrixRoot),
+    responses: &FreivaldsResponses,
+    transcript: &mut dyn Transcript,
+    sink: &mut S,
+) -> Result<(), FreivaldsError> {
+    // an opening per row, so the responses answer each open of V.
+    let (a_commit, b_commit, c_commit) = commits;
+    if responses.a_rows.len() != a_commit.rows
+        || responses.b_rows.len() != b_commit.rows
+        || responses.c_rows.len() != c_commit.rows
+    {
+        return Err(FreivaldsError::ShapeMismatch);
+    }
+    verify_committed_with_sink(commits, responses, 1, transcript, sink)
+}
+
+// `respond` on a statement of the roots of A, B and C, which is bound before them.
+pub fn respond_statement(
+    statement: &Statement,
+    a: &CommittedMatrix,
+    b: &CommittedMatrix,
+    c: &CommittedMatrix,
+    transcript: &mut dyn Transcript,
+) -> FreivaldsResponses {
+    assert_eq!(
+        statement.matrix("A").map(MatrixRoot::from),
+        Ok(a.root()),
+        "the statement is of another A"
+    );
+    statement.bind(transcript);
+    respond(a, b, c, transcript)
+}
+
+// `verify_transcript` with the roots of the statement.
+pub fn verify_statement(
+    statement: &Statement,
+    responses: &FreivaldsResponses,
+    transcript: &mut dyn Transcript,
+) -> Result<(), FreivaldsError> {
+    statement.check_protocol(&protocol_id())?;
+    let a_commit = MatrixRoot::from(statement.matrix("A")?);
+    let b_commit = MatrixRoot::from(statement.matrix("B")?);
+    let c_commit = MatrixRoot::from(statement.matrix("C")?);
+    statement.bind(transcript);
+    verify_transcript(&a_commit, &b_commit, &c_commit, responses, transcript)
+}
+
+// V holds A, B and C, so there's no response: x is squeezed after the matrices are absorbed,
+// and V checks A · (B · x) == C · x by itself.
+pub fn verify_uncommitted(
+    a: &Matrix,
+    b: &Matrix,
+    c: &Matrix,
+    transcript: &mut dyn Transcript,
+) -> Result<(), FreivaldsError> {
+    verify_uncommitted_with_sink(a, b, c, transcript, &mut ())
+}
+
+#[cfg(any(test, feature = "cost"))]
+pub fn verify_uncommitted_with_cost(
+    a: &Matrix,
+    b: &Matrix,
+    c: &Matrix,
+    transcript: &mut dyn Transcript,
+) -> (Result<(), FreivaldsError>, CostReport) {
+    let mut cost = CostReport::default();
+    let res = verify_uncommitted_with_sink(a, b, c, transcript, &mut cost);
+    (res, cost)
+}
+
+fn verify_uncommitted_with_sink<S: CostSink>(
+    a: &Matrix,
+    b: &Matrix,
+    c: &Matrix,
+    transcript: &mut dyn Transcript,
+    sink: &mut S,
+) -> Result<(), FreivaldsError> {
+    check_shapes(
+        (a.rows(), a.cols()),
+        (b.rows(), b.cols()),
+        (c.rows(), c.cols()),
+    )?;
+    let mut writer = MessageWriter::new(transcript, "freivalds.matrices");
+    for m in [a, b, c] {
+        write_matrix(&mut writer, m);
+    }
+    writer.finish();
+
+    let x = challenge_x(transcript, b.cols(), sink);
+    let (y, z) = (b.matrix_mul_vec(&x), c.matrix_mul_vec(&x));
+    sink.count(inner_product_cost(b.cols()).times(b.rows() + c.rows()));
+    check_product((0..a.rows()).map(|i| a.row(i)), &y, &z, sink)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use Fiat_Shamir::audit::{compare_audits, AuditDiff, AuditOp};
+    use Fiat_Shamir::default::Keccak256Transcript;
+
+    fn from_ints(rows: &[&[u64]]) -> Matrix {
+        Matrix::from_rows(
+            rows.iter()
+                .map(|r| r.iter().map(|v| Scalar::from(*v)).collect())
+                .collect(),
+        )
+    }
+
+    // A(2 * 3), B(3 * 2) and C = A · B, fixed so that the challenges are fixed as well.
+    fn fixed_product() -> (Matrix, Matrix, Matrix) {
+        let a = from_ints(&[&[1, 2, 3], &[4, 5, 6]]);
+        let b = from_ints(&[&[7, 8], &[9, 10], &[11, 12]]);
+        let c = Matrix::mul(&a, &b);
+        (a, b, c)
+    }
+
+    // C_{1, 0} + 1, which is caught whatever r is, as x_0 = 1.
+    fn corrupted(c: &Matrix) -> Matrix {
+        let mut rows = (0..c.rows()).map(|i| c.row(i).to_vec()).collect::<Vec<_>>();
+        rows[1][0] += Scalar::one();
+        Matrix::from_rows(rows)
+    }
+
+    fn prefixed() -> Keccak256Transcript {
+        let mut transcript = Keccak256Transcript::default();
+        transcript.append_labeled("test.prefix", b"fixed");
+        transcript
+    }
+
+    #[test]
+    fn test_committed_product() {
+        let (a, b) = (Matrix::random(4, 3), Matrix::random(3, 5));
+        let ((root_a, root_b, root_c), prover) = CommittedProver::new(&a, &b);
+
+        let mut transcript = Keccak256Transcript::default();
+        let res = verify_committed((&root_a, &root_b, &root_c), &prover, 4, &mut transcript);
+        assert_eq!(res, Ok(()));
+    }
+
+    #[test]
+    fn test_corrupted_row_of_c() {
+        let (a, b) = (Matrix::random(4, 3), Matrix::random(3, 5));
+        // the prover is honest w.r.t. the committed C, which has a wrong row.
+        let c = Matrix::mul(&a, &b);
+        let mut rows = (0..c.rows()).map(|i| c.row(i).to_vec()).collect::<Vec<_>>();
+        rows[2][1] += Scalar::one();
+        let c = Matrix::from_rows(rows);
+        let ((root_a, root_b, root_c), prover) = CommittedProver::from_committed(&a, &b, &c);
+
+        let mut transcript = Keccak256Transcript::default();
+        let res = verify_committed((&root_a, &root_b, &root_c), &prover, 4, &mut transcript);
+        assert_eq!(res, Err(FreivaldsError::ProductMismatch { row: 2 }));
+    }
+
+    // Responds with y != B · x, which makes A · y == z hold as well.
+    struct InconsistentProver(CommittedProver);
+
+    impl MatrixOracle for InconsistentProver {
+        fn respond(&self, x: &[Scalar]) -> (Vec<Scalar>, Vec<Scalar>) {
+            let (y, _) = self.0.respond(x);
+            let y = y.iter().map(|v| v + Scalar::one()).collect::<Vec<_>>();
+            let z = self.0.a.matrix().matrix_mul_vec(&y);
+            (y, z)
+        }
+
+        fn open(&self, matrix: MatrixId, row: usize) -> RowOpening {
+            self.0.open(matrix, row)
+        }
+    }
+
+    #[test]
+    fn test_inconsistent_response() {
+        let (a, b) = (Matrix::random(4, 3), Matrix::random(3, 5));
+        let ((root_a, root_b, root_c), prover) = CommittedProver::new(&a, &b);
+        let prover = InconsistentProver(prover);
+
+        let mut transcript = Keccak256Transcript::default();
+        let res = verify_committed((&root_a, &root_b, &root_c), &prover, 4, &mut transcript);
+        assert!(matches!(
+            res,
+            Err(FreivaldsError::ResponseMismatch {
+                matrix: MatrixId::B,
+                ..
+            })
+        ));
+    }
+
+    // Commits a C with one wrong row, and answers z = A · y rather than C · x, so A · y == z
+    // holds in every round: only the check of z against the rows of C catches it.
+    struct BadProductProver(CommittedProver);
+
+    impl MatrixOracle for BadProductProver {
+        fn respond(&self, x: &[Scalar]) -> (Vec<Scalar>, Vec<Scalar>) {
+            let (y, _) = self.0.respond(x);
+            let z = self.0.a.matrix().matrix_mul_vec(&y);
+            (y, z)
+        }
+
+        fn open(&self, matrix: MatrixId, row: usize) -> RowOpening {
+            self.0.open(matrix, row)
+        }
+    }
+
+    #[test]
+    fn test_bad_committed_product() {
+        let (a, b) = (Matrix::random(8, 3), Matrix::random(3, 5));
+        let c = Matrix::mul(&a, &b);
+        let mut rows = (0..c.rows()).map(|i| c.row(i).to_vec()).collect::<Vec<_>>();
+        rows[6][4] += Scalar::one();
+        let c = Matrix::from_rows(rows);
+        let ((root_a, root_b, root_c), prover) = CommittedProver::from_committed(&a, &b, &c);
+        let prover = BadProductProver(prover);
+
+        // whatever the transcript, eg: the 7 of 8 rows a check at one random row of C misses.
+        for label in 0..16u64 {
+            let mut transcript = Keccak256Transcript::default();
+            transcript.append(&label.to_le_bytes());
+            let res = verify_committed((&root_a, &root_b, &root_c), &prover, 1, &mut transcript);
+            assert_eq!(
+                res,
+                Err(FreivaldsError::ResponseMismatch {
+                    matrix: MatrixId::C,
+                    row: 6
+                })
+            );
+        }
+    }
+
+    // (1 * 1) matrices, the thinnest shapes `Matrix::mul` takes.
+    #[test]
+    fn test_one_by_one() {
+        let (a, b) = (from_ints(&[&[3]]), from_ints(&[&[5]]));
+        let ((root_a, root_b, root_c), prover) = CommittedProver::new(&a, &b);
+        let mut transcript = Keccak256Transcript::default();
+        let res = verify_committed((&root_a, &root_b, &root_c), &prover, 2, &mut transcript);
+        assert_eq!(res, Ok(()));
+
+        let c = Matrix::mul(&a, &b);
+        let mut transcript = Keccak256Transcript::default();
+        assert_eq!(verify_uncommitted(&a, &b, &c, &mut transcript), Ok(()));
+        let mut transcript = Keccak256Transcript::default();
+        assert_eq!(
+            verify_uncommitted(&a, &b, &from_ints(&[&[16]]), &mut transcript),
+            Err(FreivaldsError::ProductMismatch { row: 0 })
+        );
+    }
+
+    #[test]
+    fn test_uncommitted() {
+        let (a, b, c) = fixed_product();
+        assert_eq!(verify_uncommitted(&a, &b, &c, &mut prefixed()), Ok(()));
+
+        let c = corrupted(&c);
+        for _ in 0..2 {
+            assert_eq!(
+                verify_uncommitted(&a, &b, &c, &mut prefixed()),
+                Err(FreivaldsError::ProductMismatch { row: 1 })
+            );
+        }
+        assert_eq!(
+            verify_uncommitted(&a, &a, &c, &mut prefixed()),
+            Err(FreivaldsError::ShapeMismatch)
+        );
+    }
+
+    #[test]
+    fn test_transcript_round_trip() {
+        let (a, b, c) = fixed_product();
+        let ((root_a, root_b, root_c), prover) = CommittedProver::from_committed(&a, &b, &c);
+
+        let mut p_transcript = prefixed().with_audit();
+        let responses = respond(&prover.a, &prover.b, &prover.c, &mut p_transcript);
+        let mut v_transcript = prefixed().with_audit();
+        let res = verify_transcript(&root_a, &root_b, &root_c, &responses, &mut v_transcript);
+        assert_eq!(res, Ok(()));
+
+        // P and V derive the same x from the same messages.
+        assert_eq!(
+            compare_audits(p_transcript.audit().unwrap(), v_transcript.audit().unwrap()),
+            AuditDiff::Identical
+        );
+        assert_eq!(p_transcript.challenge(), v_transcript.challenge());
+    }
+
+    #[test]
+    fn test_transcript_rejects() {
+        let (a, b, c) = fixed_product();
+        let c = corrupted(&c);
+        let ((root_a, root_b, root_c), prover) = CommittedProver::from_committed(&a, &b, &c);
+        let responses = respond(&prover.a, &prover.b, &prover.c, &mut prefixed());
+
+        // P is honest w.r.t. the committed C, which is not A · B.
+        let verify = |responses: &FreivaldsResponses| {
+            verify_transcript(&root_a, &root_b, &root_c, responses, &mut prefixed())
+        };
+        assert_eq!(
+            verify(&responses),
+            Err(FreivaldsError::ProductMismatch { row: 1 })
+        );
+
+        // a row of A which is not the committed one
+        let mut tampered = responses.clone();
+        tampered.a_rows[0].row[0] += Scalar::one();
+        assert_eq!(
+            verify(&tampered),
+            Err(FreivaldsError::OpeningFailed {
+                matrix: MatrixId::A,
+                row: 0
+            })
+        );
+
+        // the rows of B out of order
+        let mut tampered = responses.clone();
+        tampered.b_rows.swap(0, 1);
+        assert_eq!(
+            verify(&tampered),
+            Err(FreivaldsError::OpeningFailed {
+                matrix: MatrixId::B,
+                row: 0
+            })
+        );
+
+        // z = A · y for the committed C, which is caught at the wrong row of C.
+        let mut cheating = responses.clone();
+        cheating.z = a.matrix_mul_vec(&cheating.y);
+        assert_eq!(
+            verify(&cheating),
+            Err(FreivaldsError::ResponseMismatch {
+                matrix: MatrixId::C,
+                row: 1
+            })
+        );
+
+        // a row missing
+        let mut short = responses.clone();
+        short.c_rows.pop();
+        assert_eq!(verify(&short), Err(FreivaldsError::ShapeMismatch));
+    }
+
+    // The check embedded in the transcript of an outer proof: the outer challenges before it are
+    // the same as without it, and the order of the messages is pinned.
+    #[test]
+    fn test_embedded_in_outer_transcript() {
+        let outer_commit = |transcript: &mut Keccak256Transcript| {
+            transcript.append_labeled("outer.commit", b"commitment");
+            transcript.challenge()
+        };
+        let c_1 = outer_commit(&mut Keccak256Transcript::default());
+
+        let (a, b, c) = fixed_product();
+        let ((root_a, root_b, root_c), prover) = CommittedProver::from_committed(&a, &b, &c);
+        let mut p_transcript = Keccak256Transcript::default();
+        outer_commit(&mut p_transcript);
+        let responses = respond(&prover.a, &prover.b, &prover.c, &mut p_transcript);
+
+        let mut transcript = Keccak256Transcript::default().with_audit();
+        assert_eq!(outer_commit(&mut transcript), c_1);
+        let res = verify_transcript(&root_a, &root_b, &root_c, &responses, &mut transcript);
+        assert_eq!(res, Ok(()));
+        transcript.append_labeled("outer.opening", b"opening");
+        transcript.challenge();
+
+        use AuditOp::{Append, Challenge};
+        let ops = transcript
+            .audit()
+            .unwrap()
+            .entries
+            .iter()
+            .map(|e| (e.op, e.label.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ops,
+            vec![
+                (Append, "outer.commit"),
+                (Challenge, ""),
+                (Append, "freivalds.roots"),
+                // the seed of x
+                (Append, "freivalds.x"),
+                (Challenge, ""),
+                (Append, "freivalds.response"),
+                (Append, "outer.opening"),
+                (Challenge, ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_statement() {
+        let (a, b, c) = fixed_product();
+        let ((root_a, root_b, root_c), prover) = CommittedProver::from_committed(&a, &b, &c);
+        let statement = statement(&root_a, &root_b, &root_c);
+        let responses = respond_statement(
+            &statement,
+            &prover.a,
+            &prover.b,
+            &prover.c,
+            &mut Keccak256Transcript::default(),
+        );
+        let verify = |statement: &Statement| {
+            verify_statement(statement, &responses, &mut Keccak256Transcript::default())
+        };
+        assert_eq!(verify(&statement), Ok(()));
+
+        // the same roots in another order of the builder.
+        let reordered = Statement::new(protocol_id())
+            .with_matrix("C", root_c)
+            .with_matrix("A", root_a)
+            .with_matrix("B", root_b);
+        assert_eq!(reordered.digest(), statement.digest());
+        assert_eq!(verify(&reordered), Ok(()));
+
+        // another root of C, which the openings of C aren't of.
+        let (other_c, _) = CommittedMatrix::commit(&corrupted(&c));
+        assert!(verify(&super::statement(&root_a, &root_b, &other_c)).is_err());
+
+        let missing = Statement::new(protocol_id())
+            .with_matrix("A", root_a)
+            .with_matrix("B", root_b);
+        assert_eq!(
+            verify(&missing),
+            Err(FreivaldsError::Statement(StatementError::MissingBinding {
+                label: "C"
+            }))
+        );
+    }
+}
@@ -0,0 +1,338 @@
+// V holds A, B and C, and checks C = A · B by whichever is cheaper for the shapes: recompute
+// A · B and compare, which is exact, or Freivalds with random x, which errs with 1/|F| per round.
+//
+// The cost model counts the field mults of A(m * n), B(n * p) and C(m * p):
+//      recompute: m·n·p
+//      Freivalds: rounds·(n·p + m·n + m·p + w·p), B · x, A · (B · x), C · x and the p scalars of x
+// where a sampled scalar weighs w mults, see `SAMPLE_WEIGHT`. For n * n matrices it's n^3
+// against rounds·(3n^2 + w·n), so Freivalds wins from n = 9 with a single round.
+//
+// The rounds are the fewest to reach the soundness target: each x is uniform in F^p, so a wrong
+// C passes a round with probability 1/|F| at most, and `rounds` of them with |F|^-rounds.
+//
+// `CostModel::crossover` overrides the model by the size alone, eg: to benchmark both strategies
+// at the same sizes.
+use crate::freivalds::{check_shapes, inner_product_cost};
+use crate::matrix::Matrix;
+use bls12_381::Scalar;
+use ff::Field;
+use rand_core::RngCore;
+use sumcheck::cost::{CostReport, CostSink};
+use sumcheck::rlc::FIELD_SIZE;
+
+// A scalar of x in mults, about 4 times what an OsRng draw costs with its syscall, so the ties
+// of the small sizes go to the exact recompute.
+pub const SAMPLE_WEIGHT: u64 = 48;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    Recompute,
+    Freivalds,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CostModel {
+    pub sample_weight: u64,
+    // recompute iff max(m, n, p) < crossover, whatever the estimates are.
+    pub crossover: Option<usize>,
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        Self {
+            sample_weight: SAMPLE_WEIGHT,
+            crossover: None,
+        }
+    }
+}
+
+// The mults of each strategy by the model, see above.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Estimates {
+    pub recompute: u64,
+    pub freivalds: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckOutcome {
+    pub accepted: bool,
+    pub strategy: Strategy,
+    pub rounds: usize, // 0 for the recompute
+    // a wrong C is accepted with probability at most this: 0 for the recompute, |F|^-rounds
+    pub soundness: f64,
+    pub estimates: Estimates,
+    // the ops of V as it ran, with `cost`. The samples of x are not field ops, so they are in the
+    // estimate only.
+    pub actual: Option<CostReport>,
+}
+
+impl CostModel {
+    // A(m * n) · B(n * p)
+    pub fn estimates(&self, (m, n, p): (usize, usize, usize), rounds: usize) -> Estimates {
+        let (m, n, p) = (m as u64, n as u64, p as u64);
+        Estimates {
+            recompute: m * n * p,
+            freivalds: rounds as u64 * (n * p + m * n + m * p + self.sample_weight * p),
+        }
+    }
+
+    pub fn choose(&self, (m, n, p): (usize, usize, usize), rounds: usize) -> Strategy {
+        let recompute = match self.crossover {
+            Some(crossover) => m.max(n).max(p) < crossover,
+            None => {
+                let estimates = self.estimates((m, n, p), rounds);
+                estimates.recompute <= estimates.freivalds
+            }
+        };
+        if recompute {
+            Strategy::Recompute
+        } else {
+            Strategy::Freivalds
+        }
+    }
+}
+
+// The fewest rounds with |F|^-rounds <= target, at least one.
+pub fn rounds_for(target: f64) -> usize {
+    assert!(
+        target > 0.0 && target <= 1.0,
+        "the soundness target {} is not in (0, 1]",
+        target
+    );
+    let rounds = (-target.log2() / FIELD_SIZE.log2()).ceil() as usize;
+    rounds.max(1)
+}
+
+// C == A · B by the cheaper strategy of the default model.
+pub fn check_auto(
+    a: &Matrix,
+    b: &Matrix,
+    c: &Matrix,
+    soundness_target: f64,
+    rng: impl RngCore,
+) -> CheckOutcome {
+    check_auto_with(a, b, c, soundness_target, CostModel::default(), rng)
+}
+
+pub fn check_auto_with(
+    a: &Matrix,
+    b: &Matrix,
+    c: &Matrix,
+    soundness_target: f64,
+    model: CostModel,
+    rng: impl RngCore,
+) -> CheckOutcome {
+    let rounds = rounds_for(soundness_target);
+    let shape = (a.rows(), a.cols(), b.cols());
+    let strategy = model.choose(shape, rounds);
+    let (rounds, soundness) = match strategy {
+        Strategy::Recompute => (0, 0.0),
+        Strategy::Freivalds => (rounds, FIELD_SIZE.powi(-(rounds as i32))),
+    };
+
+    #[cfg(feature = "cost")]
+    let (accepted, actual) = {
+        let mut cost = CostReport::default();
+        let accepted = run(strategy, (a, b, c), rounds, rng, &mut cost);
+        (accepted, Some(cost))
+    };
+    #[cfg(not(feature = "cost"))]
+    let (accepted, actual) = (run(strategy, (a, b, c), rounds, rng, &mut ()), None);
+
+    CheckOutcome {
+        accepted,
+        strategy,
+        rounds,
+        soundness,
+        estimates: model.estimates(shape, rounds.max(1)),
+        actual,
+    }
+}
+
+// A C of another shape is rejected.
+fn run<S: CostSink>(
+    strategy: Strategy,
+    (a, b, c): (&Matrix, &Matrix, &Matrix),
+    rounds: usize,
+    mut rng: impl RngCore,
+    sink: &mut S,
+) -> bool {
+    let shapes = check_shapes(
+        (a.rows(), a.cols()),
+        (b.rows(), b.cols()),
+        (c.rows(), c.cols()),
+    );
+    if shapes.is_err() {
+        return false;
+    }
+    let (m, n, p) = (a.rows(), a.cols(), b.cols());
+    match strategy {
+        Strategy::Recompute => {
+            sink.count(inner_product_cost(n).times(m * p) + CostReport::comparisons(m * p));
+            Matrix::mul(a, b) == *c
+        }
+        Strategy::Freivalds => (0..rounds).all(|_| {
+            let x = (0..p).map(|_| Scalar::random(&mut rng)).collect::<Vec<_>>();
+            let y = b.matrix_mul_vec(&x);
+            sink.count(
+                inner_product_cost(p).times(n + m)
+                    + inner_product_cost(n).times(m)
+                    + CostReport::comparisons(m),
+            );
+            a.matrix_mul_vec(&y) == c.matrix_mul_vec(&x)
+        }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand_core::OsRng;
+
+    const TARGET: f64 = 1.0 / (1u128 << 100) as f64;
+
+    // A · B and the same with C_{0, 0} + 1.
+    fn products(n: usize) -> (Matrix, Matrix, Matrix, Matrix) {
+        let (a, b) = (Matrix::random(n, n), Matrix::random(n, n));
+        let c = Matrix::mul(&a, &b);
+        let mut rows = (0..n).map(|i| c.row(i).to_vec()).collect::<Vec<_>>();
+        rows[0][0] += Scalar::one();
+        (a, b, c, Matrix::from_rows(rows))
+    }
+
+    fn forced(strategy: Strategy) -> CostModel {
+        let crossover = match strategy {
+            Strategy::Recompute => usize::MAX,
+            Strategy::Freivalds => 0,
+        };
+        CostModel {
+            crossover: Some(crossover),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_default_choice() {
+        let model = CostModel::default();
+        for n in 1..=8 {
+            assert_eq!(model.choose((n, n, n), 1), Strategy::Recompute, "n = {}", n);
+            let (a, b, c, _) = products(n);
+            let outcome = check_auto(&a, &b, &c, TARGET, OsRng);
+            assert_eq!(
+                (outcome.strategy, outcome.accepted),
+                (Strategy::Recompute, true)
+            );
+            assert_eq!((outcome.rounds, outcome.soundness), (0, 0.0));
+        }
+        // the crossover
+        assert_eq!(model.choose((9, 9, 9), 1), Strategy::Freivalds);
+        assert_eq!(model.choose((9, 9, 9), 2), Strategy::Recompute);
+
+        // C = A · I, so no product is built.
+        for n in [128, 256] {
+            let a = Matrix::random(n, n);
+            let outcome = check_auto(&a, &Matrix::identity(n), &a, TARGET, OsRng);
+            assert_eq!(
+                (outcome.strategy, outcome.accepted),
+                (Strategy::Freivalds, true)
+            );
+            assert_eq!(outcome.rounds, 1);
+            assert!(outcome.estimates.freivalds * 32 < outcome.estimates.recompute);
+        }
+    }
+
+    #[test]
+    fn test_strategies_agree_at_the_crossover() {
+        for n in [7, 8, 9, 10] {
+            let (a, b, c, wrong) = products(n);
+            for strategy in [Strategy::Recompute, Strategy::Freivalds] {
+                let check =
+                    |c: &Matrix| check_auto_with(&a, &b, c, TARGET, forced(strategy), OsRng);
+                let (honest, corrupted) = (check(&c), check(&wrong));
+                assert_eq!(honest.strategy, strategy);
+                assert!(honest.accepted, "{:?} at n = {}", strategy, n);
+                assert!(!corrupted.accepted, "{:?} at n = {}", strategy, n);
+            }
+        }
+        // a C of another shape
+        let (a, b, _, _) = products(4);
+        assert!(!check_auto(&a, &b, &Matrix::random(4, 3), TARGET, OsRng).accepted);
+    }
+
+    #[test]
+    fn test_rounds_meet_the_target() {
+        // |F| is about 2^254.86
+        let cases = [
+            (0.5, 1),
+            (TARGET, 1),
+            (2f64.powi(-254), 1),
+            (2f64.powi(-300), 2),
+        ];
+        for (target, rounds) in cases {
+            assert_eq!(rounds_for(target), rounds, "{}", target);
+        }
+        let (a, b, c, _) = products(4);
+        for target in [0.5, TARGET, 2f64.powi(-300), 2f64.powi(-600)] {
+            let outcome = check_auto_with(&a, &b, &c, target, forced(Strategy::Freivalds), OsRng);
+            assert!(outcome.soundness <= target);
+            // and one round less wouldn't do
+            let fewer = FIELD_SIZE.powi(1 - outcome.rounds as i32);
+            assert!(outcome.rounds == 1 || fewer > target);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "the soundness target 0 is not in (0, 1]")]
+    fn test_target_of_no_error() {
+        rounds_for(0.0);
+    }
+
+    #[test]
+    fn test_override() {
+        let (a, b, c, _) = products(2);
+        let outcome = check_auto_with(&a, &b, &c, TARGET, forced(Strategy::Freivalds), OsRng);
+        assert_eq!((outcome.strategy, outcome.rounds), (Strategy::Freivalds, 1));
+        assert!(outcome.estimates.recompute < outcome.estimates.freivalds);
+
+        let a = Matrix::random(32, 32);
+        let outcome = check_auto_with(
+            &a,
+            &Matrix::identity(32),
+            &a,
+            TARGET,
+            forced(Strategy::Recompute),
+            OsRng,
+        );
+        assert_eq!(
+            (outcome.strategy, outcome.accepted),
+            (Strategy::Recompute, true)
+        );
+
+        // the crossover splits by the largest dimension
+        let model = CostModel {
+            crossover: Some(16),
+            ..Default::default()
+        };
+        assert_eq!(model.choose((15, 15, 15), 1), Strategy::Recompute);
+        assert_eq!(model.choose((2, 16, 2), 1), Strategy::Freivalds);
+    }
+
+    #[test]
+    fn test_actual_cost() {
+        let (a, b, c, _) = products(16);
+        for strategy in [Strategy::Recompute, Strategy::Freivalds] {
+            let outcome = check_auto_with(&a, &b, &c, TARGET, forced(strategy), OsRng);
+            #[cfg(feature = "cost")]
+            {
+                let mults = outcome.actual.unwrap().field_mults;
+                let estimated = match strategy {
+                    Strategy::Recompute => outcome.estimates.recompute,
+                    Strategy::Freivalds => outcome.estimates.freivalds - SAMPLE_WEIGHT * 16,
+                };
+                assert_eq!(mults, estimated);
+            }
+            #[cfg(not(feature = "cost"))]
+            assert_eq!(outcome.actual, None);
+        }
+    }
+}
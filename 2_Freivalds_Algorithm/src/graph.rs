@@ -0,0 +1,291 @@
+// Graphs as 0/1 adjacency matrices, for the graph claims proved by sum-check, eg: counting
+// triangles (Section 4.3 of the book) or the walks of length 2, see `walks` for length k.
+//
+// The vertex count n is padded to N = 2^k, the padded vertices have no edge. The adjacency A is
+// N * N, and its MLE Ã(x, y) is `Matrix::to_mle`, so the pair (u, v) is the point
+// (bits(u), bits(v)) with the MSB first, see `vertex_pair_to_point`. eg:
+//      #triangles = 1/6 · ∑_{x,y,z ∈ {0,1}^k} Ã(x, y) · Ã(y, z) · Ã(z, x), if undirected
+//
+// Edges:
+//  - an edge with a vertex >= n is an error.
+//  - a self-loop (u, u) is allowed, it's the entry A[u][u] = 1, so it's counted in the walks,
+//    eg: `count_paths2` and the sums over Ã, but never in a triangle of 3 distinct vertices.
+//  - a duplicated edge is the same entry, A is 0/1.
+use crate::matrix::Matrix;
+use bls12_381::Scalar;
+use std::fmt;
+use sumcheck::poly::eval_table::EvalTable;
+use sumcheck::utils::convert_to_binary;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphError {
+    VertexOutOfRange { vertex: usize, n_vertices: usize },
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphError::VertexOutOfRange { vertex, n_vertices } => {
+                write!(f, "Vertex {} is out of the {} vertices", vertex, n_vertices)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GraphError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Graph {
+    n_vertices: usize,
+    directed: bool,
+    // n * n, adjacency[u][v] is the edge u -> v, which is symmetric if undirected.
+    adjacency: Vec<Vec<bool>>,
+}
+
+impl Graph {
+    /// An undirected edge (u, v) is both u -> v and v -> u.
+    pub fn from_edges(
+        n_vertices: usize,
+        edges: &[(usize, usize)],
+        directed: bool,
+    ) -> Result<Self, GraphError> {
+        let mut adjacency = vec![vec![false; n_vertices]; n_vertices];
+        for &(u, v) in edges.iter() {
+            if let Some(&vertex) = [u, v].iter().find(|w| **w >= n_vertices) {
+                return Err(GraphError::VertexOutOfRange { vertex, n_vertices });
+            }
+            adjacency[u][v] = true;
+            if !directed {
+                adjacency[v][u] = true;
+            }
+        }
+        Ok(Self {
+            n_vertices,
+            directed,
+            adjacency,
+        })
+    }
+
+    pub fn n_vertices(&self) -> usize {
+        self.n_vertices
+    }
+
+    pub fn is_directed(&self) -> bool {
+        self.directed
+    }
+
+    pub fn has_edge(&self, u: usize, v: usize) -> bool {
+        u < self.n_vertices && v < self.n_vertices && self.adjacency[u][v]
+    }
+
+    /// N = 2^k >= n, the size of the adjacency matrix.
+    pub fn padded_size(&self) -> usize {
+        self.n_vertices.next_power_of_two()
+    }
+
+    /// k, the number of variables of a vertex in Ã.
+    pub fn vertex_var_num(&self) -> usize {
+        self.padded_size().trailing_zeros() as usize
+    }
+
+    /// The N * N 0/1 matrix, padded with zeros.
+    pub fn adjacency(&self) -> Matrix {
+        let size = self.padded_size();
+        let values = (0..size)
+            .map(|u| {
+                (0..size)
+                    .map(|v| Scalar::from(self.has_edge(u, v) as u64))
+                    .collect()
+            })
+            .collect();
+        Matrix::from_rows(values)
+    }
+
+    /// Ã over {0,1}^k × {0,1}^k.
+    pub fn to_mle(&self) -> EvalTable {
+        self.adjacency().to_mle()
+    }
+
+    /// The point of Ã where Ã = A[u][v]: (bits(u), bits(v)), each one in k bits with the MSB first.
+    pub fn vertex_pair_to_point(&self, u: usize, v: usize) -> Vec<usize> {
+        let size = self.padded_size();
+        assert!(
+            u < size && v < size,
+            "vertex pair ({}, {}) is out of the padded size {}",
+            u,
+            v,
+            size
+        );
+        let k = self.vertex_var_num();
+        let mut point = convert_to_binary(&k, u);
+        point.extend(convert_to_binary(&k, v));
+        point
+    }
+
+    /// The triangles of 3 distinct vertices, naively, to cross-check the sum-check claims.
+    /// Undirected: the sets {u, v, w} connected pairwise. Directed: the cycles u -> v -> w -> u.
+    pub fn count_triangles(&self) -> u64 {
+        let n = self.n_vertices;
+        let mut closed = 0u64;
+        for u in 0..n {
+            for v in (0..n).filter(|v| *v != u) {
+                for w in (0..n).filter(|w| *w != u && *w != v) {
+                    if self.adjacency[u][v] && self.adjacency[v][w] && self.adjacency[w][u] {
+                        closed += 1;
+                    }
+                }
+            }
+        }
+        // each triangle is a closed walk from any of its 3 vertices, in 2 directions if undirected.
+        closed / if self.directed { 3 } else { 6 }
+    }
+
+    /// The walks u -> v -> w of length 2, aka the sum of the entries of A^2, so (u, v, u) of an
+    /// undirected edge and the walks through a self-loop are counted too.
+    pub fn count_paths2(&self) -> u64 {
+        let n = self.n_vertices;
+        (0..n)
+            .map(|v| {
+                let in_degree = (0..n).filter(|u| self.adjacency[*u][v]).count() as u64;
+                let out_degree = self.adjacency[v].iter().filter(|e| **e).count() as u64;
+                in_degree * out_degree
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sumcheck::sumcheck::outsource::{Client, Server};
+
+    // two triangles {0, 1, 2} and {2, 3, 4} sharing the vertex 2.
+    const BOWTIE: [(usize, usize); 6] = [(0, 1), (1, 2), (0, 2), (2, 3), (3, 4), (2, 4)];
+    const K4: [(usize, usize); 6] = [(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)];
+
+    fn to_scalars(point: &[usize]) -> Vec<Scalar> {
+        point.iter().map(|b| Scalar::from(*b as u64)).collect()
+    }
+
+    #[test]
+    fn test_padded_mle() {
+        let graph = Graph::from_edges(5, &BOWTIE, false).unwrap();
+        assert_eq!(graph.padded_size(), 8);
+        assert_eq!(graph.vertex_var_num(), 3);
+        assert_eq!(graph.adjacency().rows(), 8);
+        assert_eq!(graph.vertex_pair_to_point(2, 5), vec![0, 1, 0, 1, 0, 1]);
+
+        // Ã at the pair points is the edge set, the padded vertices 5, 6, 7 have no edge.
+        let mle = graph.to_mle();
+        for u in 0..8 {
+            for v in 0..8 {
+                let edge = BOWTIE.contains(&(u, v)) || BOWTIE.contains(&(v, u));
+                let point = to_scalars(&graph.vertex_pair_to_point(u, v));
+                assert_eq!(
+                    mle.evaluate(&point),
+                    Scalar::from(edge as u64),
+                    "({}, {})",
+                    u,
+                    v
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_counts() {
+        // degrees (2, 2, 4, 2, 2), so the walks are 4 + 4 + 16 + 4 + 4.
+        let bowtie = Graph::from_edges(5, &BOWTIE, false).unwrap();
+        assert_eq!(bowtie.count_triangles(), 2);
+        assert_eq!(bowtie.count_paths2(), 32);
+
+        // every 3 of the 4 vertices, and 3^2 walks through each vertex.
+        let k4 = Graph::from_edges(4, &K4, false).unwrap();
+        assert_eq!(k4.count_triangles(), 4);
+        assert_eq!(k4.count_paths2(), 36);
+    }
+
+    #[test]
+    fn test_directed() {
+        let directed = Graph::from_edges(5, &BOWTIE, true).unwrap();
+        let undirected = Graph::from_edges(5, &BOWTIE, false).unwrap();
+        assert!(directed.has_edge(0, 1) && !directed.has_edge(1, 0));
+        assert!(undirected.has_edge(0, 1) && undirected.has_edge(1, 0));
+        assert_ne!(directed.adjacency(), undirected.adjacency());
+
+        // no directed cycle, the walks are in-degree * out-degree: 0 + 1 + 4 + 1 + 0.
+        assert_eq!(directed.count_triangles(), 0);
+        assert_eq!(directed.count_paths2(), 6);
+
+        let cycle = Graph::from_edges(3, &[(0, 1), (1, 2), (2, 0)], true).unwrap();
+        assert_eq!(cycle.count_triangles(), 1);
+        assert_eq!(cycle.count_paths2(), 3);
+    }
+
+    #[test]
+    fn test_out_of_range_and_self_loop() {
+        assert_eq!(
+            Graph::from_edges(5, &[(0, 1), (2, 5)], false),
+            Err(GraphError::VertexOutOfRange {
+                vertex: 5,
+                n_vertices: 5
+            })
+        );
+
+        // A = [[1, 1], [1, 0]], A^2 = [[2, 1], [1, 1]]
+        let graph = Graph::from_edges(2, &[(0, 1), (0, 0)], false).unwrap();
+        assert!(graph.has_edge(0, 0));
+        assert_eq!(graph.count_paths2(), 5);
+        assert_eq!(graph.count_triangles(), 0);
+    }
+
+    // The sum-check of ∑ Ã(x, y) · Ã(y, z) · Ã(z, x) over x, y, z ∈ {0,1}^k, which is of degree 2
+    // in each variable. It's the closed walks of length 3, 6 per triangle if undirected.
+    fn prove_closed_walks(graph: &Graph) -> Scalar {
+        let k = graph.vertex_var_num();
+        let mle = graph.to_mle();
+        let g = move |point: &[Scalar]| {
+            let (x, rest) = point.split_at(k);
+            let (y, z) = rest.split_at(k);
+            let a = |u: &[Scalar], v: &[Scalar]| mle.evaluate(&[u, v].concat());
+            a(x, y) * a(y, z) * a(z, x)
+        };
+
+        let mut server = Server::new(g.clone(), 3 * k, 2);
+        let mut client = Client::new(g, 3 * k, 2);
+        client.run(&mut server).unwrap()
+    }
+
+    #[test]
+    fn test_sumcheck_triangles() {
+        let bowtie = Graph::from_edges(5, &BOWTIE, false).unwrap();
+        let sum = prove_closed_walks(&bowtie);
+        assert_eq!(sum, Scalar::from(6 * bowtie.count_triangles()));
+
+        let k4 = Graph::from_edges(4, &K4, false).unwrap();
+        assert_eq!(
+            prove_closed_walks(&k4),
+            Scalar::from(6 * k4.count_triangles())
+        );
+
+        let cycle = Graph::from_edges(3, &[(0, 1), (1, 2), (2, 0)], true).unwrap();
+        assert_eq!(prove_closed_walks(&cycle), Scalar::from(3));
+    }
+
+    #[test]
+    fn test_sumcheck_paths2() {
+        let graph = Graph::from_edges(5, &BOWTIE, true).unwrap();
+        let k = graph.vertex_var_num();
+        let mle = graph.to_mle();
+        // ∑ Ã(x, y) · Ã(y, z), of degree 2 in y.
+        let g = move |point: &[Scalar]| {
+            let (xy, z) = point.split_at(2 * k);
+            mle.evaluate(xy) * mle.evaluate(&[&xy[k..], z].concat())
+        };
+
+        let mut server = Server::new(g.clone(), 3 * k, 2);
+        let mut client = Client::new(g, 3 * k, 2);
+        let sum = client.run(&mut server).unwrap();
+        assert_eq!(sum, Scalar::from(graph.count_paths2()));
+    }
+}
@@ -3,10 +3,23 @@
 /// How can one verify that two matrices were multiplied correctly.
 /// First,choose a random `r∈Fp`,and let x=(1,r,r2,...,rn−1).
 /// Then compute `y=Cx` and `z=A·Bx`,outputting YES if y = z and NO otherwise.
-mod matrix;
+pub mod matrix;
+
+#[cfg(test)]
+mod analysis;
+pub mod committed;
+#[cfg(feature = "experiments")]
+pub mod experiments;
+pub mod freivalds;
+pub mod graph;
+pub mod low_rank;
+pub mod matvec;
 mod prover;
 mod utils;
 mod verifier;
+pub mod walks;
+
+pub use utils::ChallengeDistribution;
 
 use crate::matrix::Matrix;
 use crate::prover::Prover;
@@ -0,0 +1,245 @@
+// C = A · B given by its factors A(n * k) and B(k * m), k ≪ n, eg: a low-rank C which is too
+// large to build. Everything here reads the factors only:
+//  - an entry C_{i,j} = <A_i, B^j> is a dot product of length k,
+//  - a row C_i = A_i · B combines the k rows of B,
+//  - Freivalds against a claimed C: V computes B · x, then A · (B · x), in k(n + m) mults
+//    rather than the n·m·k of building C first. The claimed C · x is still read in full, n·m
+//    mults per round, as each entry of the claim must be checked.
+use crate::freivalds::{inner_product_cost, FreivaldsError};
+use crate::matrix::Matrix;
+use bls12_381::Scalar;
+use ff::Field;
+use rand_core::RngCore;
+use sumcheck::cost::{CostReport, CostSink};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LowRankProduct {
+    a: Matrix, // n * k
+    b: Matrix, // k * m
+}
+
+impl LowRankProduct {
+    // A.cols must be B.rows.
+    pub fn new(a: Matrix, b: Matrix) -> Result<Self, FreivaldsError> {
+        if a.cols() != b.rows() {
+            return Err(FreivaldsError::ShapeMismatch);
+        }
+        Ok(Self { a, b })
+    }
+
+    pub fn a(&self) -> &Matrix {
+        &self.a
+    }
+
+    pub fn b(&self) -> &Matrix {
+        &self.b
+    }
+
+    // (n, m), the shape of C.
+    pub fn shape(&self) -> (usize, usize) {
+        (self.a.rows(), self.b.cols())
+    }
+
+    // k
+    pub fn rank_bound(&self) -> usize {
+        self.a.cols()
+    }
+
+    // C_{i,j} = ∑_l A_{i,l}·B_{l,j}
+    pub fn entry(&self, i: usize, j: usize) -> Scalar {
+        let (n, m) = self.shape();
+        assert!(i < n && j < m, "({}, {}) is out of C({} * {})", i, j, n, m);
+        self.a
+            .row(i)
+            .iter()
+            .enumerate()
+            .map(|(l, a_il)| a_il * self.b.row(l)[j])
+            .sum()
+    }
+
+    // C_i = A_i · B
+    pub fn row(&self, i: usize) -> Vec<Scalar> {
+        assert!(i < self.a.rows(), "row {} is out of {}", i, self.a.rows());
+        Matrix::vec_mul_matrix(self.a.row(i), &self.b)
+    }
+
+    // C restricted to the rows and the cols, in the given orders.
+    pub fn submatrix(&self, rows: &[usize], cols: &[usize]) -> Matrix {
+        let values = rows
+            .iter()
+            .map(|i| cols.iter().map(|j| self.entry(*i, *j)).collect())
+            .collect();
+        Matrix::from_rows(values)
+    }
+
+    // Freivalds with `rounds` random x, claimed_c · x == A · (B · x). A claim of another shape
+    // is rejected.
+    pub fn verify_against_claimed(
+        &self,
+        claimed_c: &Matrix,
+        rounds: usize,
+        rng: impl RngCore,
+    ) -> bool {
+        self.verify_with_sink(claimed_c, rounds, rng, &mut ())
+    }
+
+    #[cfg(any(test, feature = "cost"))]
+    pub fn verify_against_claimed_with_cost(
+        &self,
+        claimed_c: &Matrix,
+        rounds: usize,
+        rng: impl RngCore,
+    ) -> (bool, CostReport) {
+        let mut cost = CostReport::default();
+        let res = self.verify_with_sink(claimed_c, rounds, rng, &mut cost);
+        (res, cost)
+    }
+
+    fn verify_with_sink<S: CostSink>(
+        &self,
+        claimed_c: &Matrix,
+        rounds: usize,
+        mut rng: impl RngCore,
+        sink: &mut S,
+    ) -> bool {
+        let (n, m) = self.shape();
+        if (claimed_c.rows(), claimed_c.cols()) != (n, m) {
+            return false;
+        }
+        let k = self.rank_bound();
+        (0..rounds).all(|_| {
+            let x = (0..m).map(|_| Scalar::random(&mut rng)).collect::<Vec<_>>();
+            // B · x, A · (B · x) and C · x
+            let y = self.b.matrix_mul_vec(&x);
+            let z = self.a.matrix_mul_vec(&y);
+            let expected = claimed_c.matrix_mul_vec(&x);
+            sink.count(
+                inner_product_cost(m).times(k)
+                    + inner_product_cost(k).times(n)
+                    + inner_product_cost(m).times(n)
+                    + CostReport::comparisons(n),
+            );
+            z == expected
+        })
+    }
+
+    // Each claim C_{i,j} = value is checked by itself in O(k), a claim out of C is false.
+    pub fn verify_entry_claims(&self, claims: &[(usize, usize, Scalar)]) -> Vec<bool> {
+        let (n, m) = self.shape();
+        claims
+            .iter()
+            .map(|(i, j, value)| *i < n && *j < m && self.entry(*i, *j) == *value)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::freivalds::FreivaldsError;
+    use crate::low_rank::LowRankProduct;
+    use crate::matrix::Matrix;
+    use bls12_381::Scalar;
+    use rand_core::OsRng;
+
+    fn random_factors(n: usize, k: usize) -> LowRankProduct {
+        let a = Matrix::random_with(n, k, OsRng);
+        let b = Matrix::random_with(k, n, OsRng);
+        LowRankProduct::new(a, b).unwrap()
+    }
+
+    #[test]
+    fn test_agrees_with_product() {
+        let (n, k) = (64, 4);
+        let product = random_factors(n, k);
+        let c = Matrix::mul(product.a(), product.b());
+
+        for (i, j) in [(0, 0), (5, 63), (63, 17), (31, 31)] {
+            assert_eq!(product.entry(i, j), c.row(i)[j]);
+        }
+        for i in [0, 40, 63] {
+            assert_eq!(product.row(i), c.row(i));
+        }
+
+        let (rows, cols) = ([3, 60, 7], [1, 0, 62, 33]);
+        let sub = product.submatrix(&rows, &cols);
+        assert_eq!((sub.rows(), sub.cols()), (3, 4));
+        for (r, i) in rows.iter().enumerate() {
+            for (s, j) in cols.iter().enumerate() {
+                assert_eq!(sub.row(r)[s], c.row(*i)[*j]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify_against_claimed() {
+        let (n, k) = (64, 4);
+        let product = random_factors(n, k);
+        let c = Matrix::mul(product.a(), product.b());
+        assert!(product.verify_against_claimed(&c, 3, OsRng));
+
+        let mut rows = (0..n).map(|i| c.row(i).to_vec()).collect::<Vec<_>>();
+        rows[12][45] += Scalar::one();
+        assert!(!product.verify_against_claimed(&Matrix::from_rows(rows), 1, OsRng));
+
+        // a claim of another shape
+        let wrong = Matrix::random_with(n, n - 1, OsRng);
+        assert!(!product.verify_against_claimed(&wrong, 1, OsRng));
+    }
+
+    #[test]
+    fn test_round_cost() {
+        let (n, k, rounds) = (64, 4, 3);
+        let product = random_factors(n, k);
+        let c = Matrix::mul(product.a(), product.b());
+        let (ok, cost) = product.verify_against_claimed_with_cost(&c, rounds, OsRng);
+        assert!(ok);
+
+        // per round: k(n + m) for the factors, and n·m for the claim.
+        let factors = (k * (n + n)) as u64;
+        let claim = (n * n) as u64;
+        assert_eq!(cost.field_mults, rounds as u64 * (factors + claim));
+        // the factors cost O(nk), far below the n^2·k of building C.
+        assert!(factors * 8 <= (n * n) as u64);
+    }
+
+    #[test]
+    fn test_entry_claims() {
+        let product = random_factors(16, 2);
+        let claims = [
+            (3, 4, product.entry(3, 4)),
+            (3, 4, product.entry(3, 4) + Scalar::one()),
+            (16, 0, Scalar::zero()),
+            (15, 15, product.entry(15, 15)),
+        ];
+        assert_eq!(
+            product.verify_entry_claims(&claims),
+            vec![true, false, false, true]
+        );
+    }
+
+    #[test]
+    fn test_edge_ranks() {
+        for (n, k) in [(8, 1), (8, 8)] {
+            let product = random_factors(n, k);
+            let c = Matrix::mul(product.a(), product.b());
+            assert_eq!(product.rank_bound(), k);
+            assert_eq!(product.row(n - 1), c.row(n - 1));
+            assert!(product.verify_against_claimed(&c, 2, OsRng));
+        }
+
+        let (a, b) = (
+            Matrix::random_with(8, 3, OsRng),
+            Matrix::random_with(4, 8, OsRng),
+        );
+        assert_eq!(
+            LowRankProduct::new(a, b),
+            Err(FreivaldsError::ShapeMismatch)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "(8, 0) is out of C(8 * 8)")]
+    fn test_entry_out_of_range() {
+        random_factors(8, 2).entry(8, 0);
+    }
+}
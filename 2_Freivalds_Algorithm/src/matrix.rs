@@ -1,24 +1,278 @@
 use bls12_381::Scalar;
 use ff::Field;
-use rand_core::OsRng;
+use rand_core::{OsRng, RngCore};
 use std::ops::AddAssign;
+use sumcheck::poly::eval_table::EvalTable;
 
 /// This define `matrix` (rows * cols) （m × n）
+///
+/// A matrix may be empty: `random(3, 0)` is a (3 * 0) one of 3 empty rows, `random(0, 3)` and
+/// `outer(&[], b)` are (0 * n), and `from_rows(vec![])` and `identity(0)` are (0 * 0), as the
+/// cols of `from_rows` are the length of the first row. The entrywise ops, `transpose` and the
+/// products with a vector take them, where an empty sum is 0, eg: A(3 * 0) * x() = (0, 0, 0).
+/// `mul` rejects them, since no product in this crate is over an empty dimension, so one is a
+/// bug of the caller rather than a (m * 0) or zero result.
 #[derive(Debug, Clone)]
 pub struct Matrix {
     rows: usize,
     cols: usize,
     // columns
     values: Vec<Vec<Scalar>>,
+    kind: Kind,
 }
 
+/// The structure of a matrix, which is only set by the constructors of `diagonal` and
+/// `permutation`, so `mul` can skip the zeros. Any other matrix, eg: a sum or a map of a
+/// diagonal one, is `General`, since the values are never changed in place.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Kind {
+    General,
+    Diagonal,
+    // perm[i] is the column of the 1 in the row i.
+    Permutation(Vec<usize>),
+}
+
+/// Two matrices are equal iff their values are, whatever they are tagged with.
+impl PartialEq for Matrix {
+    fn eq(&self, other: &Self) -> bool {
+        self.rows == other.rows && self.cols == other.cols && self.values == other.values
+    }
+}
+
+impl Eq for Matrix {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermutationError {
+    // perm[index] is not in [0, n).
+    OutOfRange { index: usize, value: usize },
+    // perm[index] is a value already taken.
+    Repeated { index: usize, value: usize },
+}
+
+impl std::fmt::Display for PermutationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PermutationError::OutOfRange { index, value } => {
+                write!(f, "perm[{}] = {} is out of range", index, value)
+            }
+            PermutationError::Repeated { index, value } => {
+                write!(f, "perm[{}] = {} is repeated", index, value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PermutationError {}
+
+// The samples of `Matrix::random_invertible`, a random n * n matrix is singular with
+// probability about 1/|F|, so it never runs out in practice.
+const MAX_INVERTIBLE_SAMPLES: usize = 16;
+
 impl Matrix {
     pub fn random(rows: usize, cols: usize) -> Self {
+        Self::random_with(rows, cols, OsRng)
+    }
+
+    /// The same as `random`, but sampled from the rng.
+    pub fn random_with(rows: usize, cols: usize, mut rng: impl RngCore) -> Self {
         let values = (0..rows)
-            .map(|_| (0..cols).map(|_| Scalar::random(OsRng)).collect::<Vec<_>>())
+            .map(|_| {
+                (0..cols)
+                    .map(|_| Scalar::random(&mut rng))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            rows,
+            cols,
+            values,
+            kind: Kind::General,
+        }
+    }
+
+    /// A random n * n matrix with det != 0, rejection sampled.
+    pub fn random_invertible(n: usize, mut rng: impl RngCore) -> Self {
+        for _ in 0..MAX_INVERTIBLE_SAMPLES {
+            let m = Self::random_with(n, n, &mut rng);
+            if !bool::from(m.determinant().is_zero()) {
+                return m;
+            }
+        }
+        panic!(
+            "{} random {} * {} matrices are all singular, but each one is with probability \
+            about 1/|F|, check the rng",
+            MAX_INVERTIBLE_SAMPLES, n, n
+        )
+    }
+
+    /// The identity I(n * n).
+    pub fn identity(n: usize) -> Self {
+        Self::diagonal(&vec![Scalar::one(); n])
+    }
+
+    /// diag(d_1, ..., d_n), the zeros are stored as the other matrices.
+    pub fn diagonal(entries: &[Scalar]) -> Self {
+        let n = entries.len();
+        let values = entries
+            .iter()
+            .enumerate()
+            .map(|(i, d)| {
+                let mut row = vec![Scalar::zero(); n];
+                row[i] = *d;
+                row
+            })
             .collect::<Vec<_>>();
 
-        Self { cols, rows, values }
+        Self {
+            rows: n,
+            cols: n,
+            values,
+            kind: Kind::Diagonal,
+        }
+    }
+
+    /// The permutation matrix P with P[i][perm[i]] = 1, so (P * A) moves the row perm[i] of A
+    /// to the row i, eg: permutation(&[1, 0]) swaps the rows.
+    pub fn permutation(perm: &[usize]) -> Result<Self, PermutationError> {
+        let n = perm.len();
+        let mut taken = vec![false; n];
+        for (index, value) in perm.iter().cloned().enumerate() {
+            if value >= n {
+                return Err(PermutationError::OutOfRange { index, value });
+            }
+            if taken[value] {
+                return Err(PermutationError::Repeated { index, value });
+            }
+            taken[value] = true;
+        }
+        Ok(Self::from_permutation(perm.to_vec()))
+    }
+
+    // perm must be a permutation of [0, n).
+    fn from_permutation(perm: Vec<usize>) -> Self {
+        let n = perm.len();
+        let values = perm
+            .iter()
+            .map(|j| {
+                let mut row = vec![Scalar::zero(); n];
+                row[*j] = Scalar::one();
+                row
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            rows: n,
+            cols: n,
+            values,
+            kind: Kind::Permutation(perm),
+        }
+    }
+
+    /// A random matrix whose entries are non-zero with probability density, the zeros are
+    /// stored as the other matrices.
+    pub fn random_sparse(rows: usize, cols: usize, density: f64, mut rng: impl RngCore) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&density),
+            "density must be in [0, 1], got {}",
+            density
+        );
+        // the entry is non-zero iff u < density, u is uniform in [0, 1).
+        let threshold = density * (u64::MAX as f64 + 1.0);
+        let values = (0..rows)
+            .map(|_| {
+                (0..cols)
+                    .map(|_| {
+                        if (rng.next_u64() as f64) < threshold {
+                            Self::random_non_zero(&mut rng)
+                        } else {
+                            Scalar::zero()
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            rows,
+            cols,
+            values,
+            kind: Kind::General,
+        }
+    }
+
+    fn random_non_zero(mut rng: impl RngCore) -> Scalar {
+        loop {
+            let x = Scalar::random(&mut rng);
+            if !bool::from(x.is_zero()) {
+                return x;
+            }
+        }
+    }
+
+    /// The fraction of the non-zero entries, 0 for an empty matrix.
+    pub fn density(&self) -> f64 {
+        let size = self.rows * self.cols;
+        if size == 0 {
+            return 0.0;
+        }
+        let non_zeros = self
+            .values
+            .iter()
+            .flatten()
+            .filter(|x| !bool::from(x.is_zero()))
+            .count();
+        non_zeros as f64 / size as f64
+    }
+
+    /// Each row and each column has a single 1, and the other entries are 0. It reads the
+    /// values, not how the matrix was built.
+    pub fn is_permutation(&self) -> bool {
+        if self.rows != self.cols {
+            return false;
+        }
+        let mut taken = vec![false; self.cols];
+        self.values.iter().all(|row| {
+            let mut ones = row
+                .iter()
+                .enumerate()
+                .filter(|(_, x)| !bool::from(x.is_zero()));
+            match (ones.next(), ones.next()) {
+                (Some((j, x)), None) if *x == Scalar::one() && !taken[j] => {
+                    taken[j] = true;
+                    true
+                }
+                _ => false,
+            }
+        })
+    }
+
+    /// Build a matrix from its rows, all rows must have the same length.
+    pub fn from_rows(values: Vec<Vec<Scalar>>) -> Self {
+        let rows = values.len();
+        let cols = values.first().map_or(0, |r| r.len());
+        assert!(
+            values.iter().all(|r| r.len() == cols),
+            "rows must have the same length"
+        );
+        Self {
+            rows,
+            cols,
+            values,
+            kind: Kind::General,
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn row(&self, i: usize) -> &[Scalar] {
+        &self.values[i]
     }
 
     fn get_columns(&self, column_index: usize) -> Vec<Scalar> {
@@ -30,7 +284,20 @@ impl Matrix {
             .collect::<Vec<_>>()
     }
 
-    fn vec_mul(a: &Vec<Scalar>, b: &Vec<Scalar>) -> Scalar {
+    /// Bᵀ(n * m) of B(m * n).
+    pub fn transpose(&self) -> Self {
+        let values = (0..self.cols)
+            .map(|j| self.get_columns(j))
+            .collect::<Vec<_>>();
+        Self {
+            rows: self.cols,
+            cols: self.rows,
+            values,
+            kind: Kind::General,
+        }
+    }
+
+    pub(crate) fn vec_mul(a: &[Scalar], b: &[Scalar]) -> Scalar {
         assert_eq!(a.len(), b.len());
 
         let mut res = Scalar::zero();
@@ -42,12 +309,21 @@ impl Matrix {
     }
 
     /// https://en.wikipedia.org/wiki/Dot_product
-    /// Suppose A(m * n), x(n) => A * x = y(n)
+    /// Suppose A(m * n), x(n) => A * x = y(m)
+    ///
+    /// x may be empty iff n = 0, and y is then the m zeros of the empty sums, eg: () for a
+    /// (0 * 0) matrix.
     pub fn matrix_mul_vec(&self, vector: &Vec<Scalar>) -> Vec<Scalar> {
-        assert_eq!(self.cols, vector.len());
-        let n = self.cols;
+        assert_eq!(
+            self.cols,
+            vector.len(),
+            "shape mismatch: ({} * {}) vs ({} * 1)",
+            self.rows,
+            self.cols,
+            vector.len()
+        );
 
-        let mut result: Vec<Scalar> = Vec::with_capacity(n);
+        let mut result: Vec<Scalar> = Vec::with_capacity(self.rows);
         for i in 0..self.rows {
             let row_i = self.values.get(i).unwrap().clone();
 
@@ -59,13 +335,78 @@ impl Matrix {
         result
     }
 
+    /// Suppose v(m), A(m * n) => vᵀ * A = y(n), the combination of the rows of A by v.
+    pub fn vec_mul_matrix(v: &[Scalar], m: &Matrix) -> Vec<Scalar> {
+        assert_eq!(
+            v.len(),
+            m.rows,
+            "shape mismatch: (1 * {}) vs ({} * {})",
+            v.len(),
+            m.rows,
+            m.cols
+        );
+        let mut result = vec![Scalar::zero(); m.cols];
+        for (v_i, row_i) in v.iter().zip(m.values.iter()) {
+            for (y_j, a_ij) in result.iter_mut().zip(row_i.iter()) {
+                *y_j += v_i * a_ij;
+            }
+        }
+        result
+    }
+
+    /// leftᵀ * A * right = ∑ left[i] * A[i][j] * right[j], row by row, so neither A * right nor
+    /// leftᵀ * A is built, eg: r_1ᵀ * C * r_2 = C̃(r_1, r_2) with the eq tables of r_1 and r_2.
+    pub fn bilinear(&self, left: &[Scalar], right: &[Scalar]) -> Scalar {
+        assert!(
+            left.len() == self.rows && right.len() == self.cols,
+            "shape mismatch: (1 * {}) vs ({} * {}) vs ({} * 1)",
+            left.len(),
+            self.rows,
+            self.cols,
+            right.len()
+        );
+        left.iter()
+            .zip(self.values.iter())
+            .fold(Scalar::zero(), |acc, (l_i, row_i)| {
+                acc + l_i * Self::vec_mul(row_i, right)
+            })
+    }
+
+    /// a(m), b(n) => a * bᵀ = A(m * n), A[i][j] = a[i] * b[j], which has rank 1 at most.
+    pub fn outer(a: &[Scalar], b: &[Scalar]) -> Matrix {
+        let values = a
+            .iter()
+            .map(|a_i| b.iter().map(|b_j| a_i * b_j).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        Self {
+            rows: a.len(),
+            cols: b.len(),
+            values,
+            kind: Kind::General,
+        }
+    }
+
     /// https://en.wikipedia.org/wiki/Dot_product
     /// Suppose A(m * n), B(n, p) => A * B = C(m * p)
+    ///
+    /// A diagonal or permutation operand, see `diagonal` and `permutation`, is multiplied in
+    /// O(n * p) or O(m * n) instead, which scales or moves the rows or columns of the other one.
+    ///
+    /// m, n and p must be > 0, see `Matrix`. The thin shapes are the usual ones, eg: a row by a
+    /// column A(1 * n) * B(n * 1) is the (1 * 1) of their inner product, and a column by a row
+    /// A(m * 1) * B(1 * p) is their outer product, see `outer`.
     pub fn mul(m_a: &Matrix, m_b: &Matrix) -> Self {
-        assert!(m_a.cols > 0 || m_b.rows > 0, "matrix a is empty");
-        assert!(m_b.cols > 0 || m_b.rows > 0, "matrix a is empty");
-        // ma.cols == mb.rows
-        assert_eq!(m_a.cols, m_b.rows);
+        m_a.assert_not_empty("a");
+        m_b.assert_not_empty("b");
+        assert_eq!(
+            m_a.cols, m_b.rows,
+            "shape mismatch: ({} * {}) vs ({} * {})",
+            m_a.rows, m_a.cols, m_b.rows, m_b.cols
+        );
+        if let Some(res) = Self::mul_structured(m_a, m_b) {
+            return res;
+        }
         let m = m_a.rows;
         let n = m_a.cols;
         // let n = m_b.rows;
@@ -90,15 +431,298 @@ impl Matrix {
             rows: m,
             cols: p,
             values: matrix,
+            kind: Kind::General,
+        }
+    }
+
+    // The product with a diagonal or permutation operand, None if both are general.
+    fn mul_structured(m_a: &Matrix, m_b: &Matrix) -> Option<Self> {
+        let (values, kind) = match (&m_a.kind, &m_b.kind) {
+            // D * B scales the row i of B by D[i][i].
+            (Kind::Diagonal, _) => {
+                let values = m_b
+                    .values
+                    .iter()
+                    .enumerate()
+                    .map(|(i, row)| row.iter().map(|x| m_a.values[i][i] * x).collect())
+                    .collect();
+                let kind = match m_b.kind {
+                    Kind::Diagonal => Kind::Diagonal,
+                    _ => Kind::General,
+                };
+                (values, kind)
+            }
+            // A * D scales the column j of A by D[j][j].
+            (_, Kind::Diagonal) => {
+                let values = m_a
+                    .values
+                    .iter()
+                    .map(|row| {
+                        row.iter()
+                            .enumerate()
+                            .map(|(j, x)| x * m_b.values[j][j])
+                            .collect()
+                    })
+                    .collect();
+                (values, Kind::General)
+            }
+            // P_σ * P_τ = P_(τ∘σ), as the row i of P_τ has its 1 at τ[i].
+            (Kind::Permutation(sigma), Kind::Permutation(tau)) => {
+                let perm = sigma.iter().map(|i| tau[*i]).collect();
+                return Some(Self::from_permutation(perm));
+            }
+            // P * B moves the row perm[i] of B to the row i.
+            (Kind::Permutation(perm), _) => {
+                let values = perm.iter().map(|i| m_b.values[*i].clone()).collect();
+                (values, Kind::General)
+            }
+            // A * P moves the column k of A to the column perm[k].
+            (_, Kind::Permutation(perm)) => {
+                let values = m_a
+                    .values
+                    .iter()
+                    .map(|row| {
+                        let mut new_row = vec![Scalar::zero(); m_b.cols];
+                        for (k, j) in perm.iter().enumerate() {
+                            new_row[*j] = row[k];
+                        }
+                        new_row
+                    })
+                    .collect();
+                (values, Kind::General)
+            }
+            (Kind::General, Kind::General) => return None,
+        };
+
+        Some(Self {
+            rows: m_a.rows,
+            cols: m_b.cols,
+            values,
+            kind,
+        })
+    }
+
+    /// The multilinear extension Ã(x, y) of A(i, j) over {0,1}^(log m) × {0,1}^(log n), in the
+    /// row-major order: evals[i * n + j] = A[i][j], so x is the bits of the row (MSB first)
+    /// and y is the bits of the column, see `EvalTable`. m and n must be powers of 2.
+    pub fn to_mle(&self) -> EvalTable {
+        assert!(
+            self.rows.is_power_of_two() && self.cols.is_power_of_two(),
+            "matrix shape must be 2^a * 2^b, got ({} * {})",
+            self.rows,
+            self.cols
+        );
+        EvalTable::new(self.values.iter().flatten().cloned().collect())
+    }
+
+    /// Apply f on each entry: B[i][j] = f(A[i][j])
+    pub fn map(&self, f: impl Fn(&Scalar) -> Scalar) -> Matrix {
+        let values = self
+            .values
+            .iter()
+            .map(|row| row.iter().map(&f).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        Self {
+            rows: self.rows,
+            cols: self.cols,
+            values,
+            kind: Kind::General,
+        }
+    }
+
+    /// <A, B> = ∑ A[i][j] * B[i][j], the sum of entrywise products.
+    /// Note: over a finite field <A, A> = 0 doesn't imply A = 0 (eg: A = [1, sqrt(-1)]),
+    /// it's a quick zero check which holds w.h.p. for random A only.
+    pub fn frobenius_dot(&self, other: &Matrix) -> Scalar {
+        self.assert_same_shape(other);
+        self.values
+            .iter()
+            .zip(other.values.iter())
+            .map(|(a, b)| Self::vec_mul(a, b))
+            .fold(Scalar::zero(), |acc, x| acc + x)
+    }
+
+    /// tr(A) = ∑ A[i][i], A must be square.
+    pub fn trace(&self) -> Scalar {
+        self.assert_square();
+        (0..self.rows).fold(Scalar::zero(), |acc, i| acc + self.values[i][i])
+    }
+
+    /// A^k by squaring, A must be square, A^0 = I. eg: the walks of length k of a graph are
+    /// the entries of the k-th power of its adjacency, see `walks`.
+    pub fn pow(&self, k: usize) -> Matrix {
+        self.assert_square();
+        let (mut res, mut base, mut k) = (Matrix::identity(self.rows), self.clone(), k);
+        while k > 0 {
+            if k & 1 == 1 {
+                res = Matrix::mul(&res, &base);
+            }
+            k >>= 1;
+            if k > 0 {
+                base = Matrix::mul(&base, &base);
+            }
+        }
+        res
+    }
+
+    /// det(A) by the gaussian elimination over the field, A must be square.
+    /// A zero pivot is swapped with a row below, which flips the sign, and the det is the
+    /// product of the pivots. eg: det([[0, 1], [1, 0]]) = -1.
+    pub fn determinant(&self) -> Scalar {
+        self.assert_square();
+        let n = self.rows;
+        let mut a = self.values.clone();
+        let mut det = Scalar::one();
+        for k in 0..n {
+            let p = match Self::pivot(&a, k) {
+                Some(p) => p,
+                None => return Scalar::zero(),
+            };
+            if p != k {
+                a.swap(p, k);
+                det = det.neg();
+            }
+            let pivot = a[k][k];
+            det *= pivot;
+
+            // row_i -= (a[i][k] / pivot) * row_k, for the rows below.
+            let pivot_inv = pivot.invert().unwrap();
+            let (upper, lower) = a.split_at_mut(k + 1);
+            for row_i in lower.iter_mut() {
+                let factor = row_i[k] * pivot_inv;
+                for (x, y) in row_i[k..].iter_mut().zip(&upper[k][k..]) {
+                    *x -= *y * factor;
+                }
+            }
+        }
+        det
+    }
+
+    /// A^(-1) by the gauss-jordan elimination on [A | I], None if A is singular.
+    pub fn inverse(&self) -> Option<Matrix> {
+        self.assert_square();
+        let n = self.rows;
+        let mut a = self.values.clone();
+        let mut inv = Self::identity(n).values;
+        for k in 0..n {
+            let p = Self::pivot(&a, k)?;
+            a.swap(p, k);
+            inv.swap(p, k);
+
+            // scale the pivot row to 1.
+            let pivot_inv = a[k][k].invert().unwrap();
+            a[k].iter_mut().for_each(|x| *x *= pivot_inv);
+            inv[k].iter_mut().for_each(|x| *x *= pivot_inv);
+
+            // clear the column k of the other rows.
+            for i in (0..n).filter(|i| *i != k) {
+                let factor = a[i][k];
+                if bool::from(factor.is_zero()) {
+                    continue;
+                }
+                for j in 0..n {
+                    let (t, u) = (a[k][j] * factor, inv[k][j] * factor);
+                    a[i][j] -= t;
+                    inv[i][j] -= u;
+                }
+            }
+        }
+        Some(Self {
+            rows: n,
+            cols: n,
+            values: inv,
+            kind: Kind::General,
+        })
+    }
+
+    // The first row from k with a non-zero entry in the column k.
+    fn pivot(a: &[Vec<Scalar>], k: usize) -> Option<usize> {
+        (k..a.len()).find(|i| !bool::from(a[*i][k].is_zero()))
+    }
+
+    fn assert_square(&self) {
+        assert_eq!(
+            self.rows, self.cols,
+            "matrix must be square, got ({} * {})",
+            self.rows, self.cols
+        );
+    }
+
+    fn assert_not_empty(&self, name: &str) {
+        assert!(
+            self.rows > 0 && self.cols > 0,
+            "matrix {} is empty, got ({} * {})",
+            name,
+            self.rows,
+            self.cols
+        );
+    }
+
+    fn assert_same_shape(&self, other: &Matrix) {
+        assert!(
+            self.rows == other.rows && self.cols == other.cols,
+            "matrix shape mismatch: ({} * {}) vs ({} * {})",
+            self.rows,
+            self.cols,
+            other.rows,
+            other.cols
+        );
+    }
+
+    // entrywise combine two matrices with the same shape.
+    fn zip_with(&self, other: &Matrix, f: impl Fn(&Scalar, &Scalar) -> Scalar) -> Matrix {
+        self.assert_same_shape(other);
+        let values = self
+            .values
+            .iter()
+            .zip(other.values.iter())
+            .map(|(a, b)| a.iter().zip(b.iter()).map(|(x, y)| f(x, y)).collect())
+            .collect::<Vec<_>>();
+
+        Self {
+            rows: self.rows,
+            cols: self.cols,
+            values,
+            kind: Kind::General,
         }
     }
 }
 
+/// A + B
+impl std::ops::Add<&Matrix> for &Matrix {
+    type Output = Matrix;
+
+    fn add(self, rhs: &Matrix) -> Self::Output {
+        self.zip_with(rhs, |a, b| a + b)
+    }
+}
+
+/// A - B
+impl std::ops::Sub<&Matrix> for &Matrix {
+    type Output = Matrix;
+
+    fn sub(self, rhs: &Matrix) -> Self::Output {
+        self.zip_with(rhs, |a, b| a - b)
+    }
+}
+
+/// c * A
+impl std::ops::Mul<Scalar> for &Matrix {
+    type Output = Matrix;
+
+    fn mul(self, rhs: Scalar) -> Self::Output {
+        self.map(|a| a * rhs)
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::matrix::Matrix;
+    use crate::matrix::{Kind, Matrix, PermutationError};
+    use crate::utils::gen_x;
     use bls12_381::Scalar;
     use ff::PrimeField;
+    use rand_core::OsRng;
 
     #[test]
     fn test_random_matrix() {
@@ -123,6 +747,7 @@ mod test {
             rows: m,
             cols: m,
             values,
+            kind: Kind::General,
         };
         let b = a.clone();
 
@@ -148,6 +773,7 @@ mod test {
             rows: m,
             cols: m,
             values,
+            kind: Kind::General,
         };
         let b = a.clone();
 
@@ -169,4 +795,440 @@ mod test {
         let res2 = A.matrix_mul_vec(&B.matrix_mul_vec(&x));
         assert_eq!(res1, res2);
     }
+
+    #[test]
+    fn test_vec_mul_matrix_and_bilinear() {
+        let m = Matrix::random(8, 16);
+        let (v, w) = (gen_x(OsRng, 8), gen_x(OsRng, 16));
+
+        // vᵀ * (M * w) = (vᵀ * M) * w
+        let expected = Matrix::vec_mul(&v, &m.matrix_mul_vec(&w));
+        assert_eq!(
+            Matrix::vec_mul(&Matrix::vec_mul_matrix(&v, &m), &w),
+            expected
+        );
+        assert_eq!(m.bilinear(&v, &w), expected);
+        // vᵀ * M = (Mᵀ * v)ᵀ
+        assert_eq!(
+            Matrix::vec_mul_matrix(&v, &m),
+            m.transpose().matrix_mul_vec(&v)
+        );
+
+        // Iᵀ is the inner product.
+        assert_eq!(
+            Matrix::identity(16).bilinear(&w, &w),
+            Matrix::vec_mul(&w, &w)
+        );
+        let u = gen_x(OsRng, 16);
+        assert_eq!(
+            Matrix::identity(16).bilinear(&u, &w),
+            Matrix::vec_mul(&u, &w)
+        );
+    }
+
+    #[test]
+    fn test_pow() {
+        let a = Matrix::random(4, 4);
+        assert_eq!(a.pow(0), Matrix::identity(4));
+        assert_eq!(a.pow(1), a);
+        let mut expected = a.clone();
+        for k in 2..=6 {
+            expected = Matrix::mul(&expected, &a);
+            assert_eq!(a.pow(k), expected, "k = {}", k);
+        }
+        // the walks of the path 0 - 1 - 2: A^2 = [[1, 0, 1], [0, 2, 0], [1, 0, 1]]
+        let to_rows = |rows: [[u64; 3]; 3]| {
+            Matrix::from_rows(rows.map(|r| r.map(Scalar::from).to_vec()).to_vec())
+        };
+        let path = to_rows([[0, 1, 0], [1, 0, 1], [0, 1, 0]]);
+        assert_eq!(path.pow(2), to_rows([[1, 0, 1], [0, 2, 0], [1, 0, 1]]));
+    }
+
+    #[test]
+    fn test_outer() {
+        let (a, b) = (gen_x(OsRng, 5), gen_x(OsRng, 7));
+        let outer = Matrix::outer(&a, &b);
+        assert_eq!((outer.rows(), outer.cols()), (5, 7));
+        assert_eq!(outer.values[3][4], a[3] * b[4]);
+
+        // rank 1, so every 2 * 2 minor is 0.
+        for (i, k, j, l) in [(0, 1, 0, 1), (0, 4, 2, 6), (1, 3, 5, 2), (2, 4, 0, 3)] {
+            let minor = Matrix::from_rows(vec![
+                vec![outer.values[i][j], outer.values[i][l]],
+                vec![outer.values[k][j], outer.values[k][l]],
+            ]);
+            assert_eq!(minor.determinant(), Scalar::zero());
+        }
+        // uᵀ * (a * bᵀ) * w = <u, a> * <b, w>
+        let (u, w) = (gen_x(OsRng, 5), gen_x(OsRng, 7));
+        assert_eq!(
+            outer.bilinear(&u, &w),
+            Matrix::vec_mul(&u, &a) * Matrix::vec_mul(&b, &w)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "(1 * 3) vs (2 * 4)")]
+    fn test_vec_mul_matrix_shape_mismatch() {
+        Matrix::vec_mul_matrix(&gen_x(OsRng, 3), &Matrix::random(2, 4));
+    }
+
+    #[test]
+    #[should_panic(expected = "(1 * 2) vs (2 * 4) vs (3 * 1)")]
+    fn test_bilinear_shape_mismatch() {
+        Matrix::random(2, 4).bilinear(&gen_x(OsRng, 2), &gen_x(OsRng, 3));
+    }
+
+    #[test]
+    fn test_add_sub_scale() {
+        let n = 4;
+        let a = Matrix::random(n, n);
+        let b = Matrix::random(n, n);
+        let zero = Matrix::random(n, n).map(|_| Scalar::zero());
+
+        // A + (-1)*A = 0
+        assert_eq!(&a + &(&a * Scalar::one().neg()), zero);
+        // A - A = 0
+        assert_eq!(&a - &a, zero);
+        // (A + B) - B = A
+        assert_eq!(&(&a + &b) - &b, a);
+
+        // (A + B)*x = A*x + B*x
+        let x = gen_x(OsRng, n);
+        let lhs = (&a + &b).matrix_mul_vec(&x);
+        let rhs = a
+            .matrix_mul_vec(&x)
+            .iter()
+            .zip(b.matrix_mul_vec(&x))
+            .map(|(a, b)| a + b)
+            .collect::<Vec<_>>();
+        assert_eq!(lhs, rhs);
+    }
+
+    #[test]
+    #[should_panic(expected = "(2 * 3) vs (3 * 2)")]
+    fn test_add_shape_mismatch() {
+        let _ = &Matrix::random(2, 3) + &Matrix::random(3, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "(2 * 3) vs (2 * 2)")]
+    fn test_sub_shape_mismatch() {
+        let _ = &Matrix::random(2, 3) - &Matrix::random(2, 2);
+    }
+
+    #[test]
+    fn test_to_mle() {
+        let a = Matrix::random(2, 4);
+        let mle = a.to_mle();
+        assert_eq!(mle.var_num, 3);
+        // Ã(1, 1, 0) = A[1][2]
+        let point = [Scalar::one(), Scalar::one(), Scalar::zero()];
+        assert_eq!(mle.evaluate(&point), a.values[1][2]);
+    }
+
+    #[test]
+    fn test_frobenius_dot() {
+        let a = Matrix::random(3, 4);
+        let b = Matrix::random(3, 4);
+        let zero = a.map(|_| Scalar::zero());
+        assert_eq!(zero.frobenius_dot(&zero), Scalar::zero());
+        assert_ne!(a.frobenius_dot(&a), Scalar::zero());
+
+        // <A, B> = <B, A>, <2A, B> = 2<A, B>
+        assert_eq!(a.frobenius_dot(&b), b.frobenius_dot(&a));
+        assert_eq!(
+            (&a * Scalar::from_u128(2)).frobenius_dot(&b),
+            a.frobenius_dot(&b).double()
+        );
+
+        // a single non-zero entry
+        let mut c = zero.clone();
+        c.values[1][2] = Scalar::from_u128(5);
+        assert_eq!(c.frobenius_dot(&c), Scalar::from_u128(25));
+    }
+
+    #[test]
+    fn test_trace() {
+        for n in [1, 3, 8] {
+            assert_eq!(Matrix::identity(n).trace(), Scalar::from_u128(n as u128));
+        }
+        // tr(A + B) = tr(A) + tr(B)
+        let (a, b) = (Matrix::random(4, 4), Matrix::random(4, 4));
+        assert_eq!((&a + &b).trace(), a.trace() + b.trace());
+    }
+
+    #[test]
+    fn test_inverse() {
+        let n = 6;
+        let identity = Matrix::identity(n);
+        for _ in 0..3 {
+            let a = Matrix::random_invertible(n, OsRng);
+            let a_inv = a.inverse().unwrap();
+            assert_eq!(Matrix::mul(&a, &a_inv), identity);
+            assert_eq!(Matrix::mul(&a_inv, &a), identity);
+        }
+    }
+
+    #[test]
+    fn test_zero_pivot() {
+        // [[0, 1], [1, 0]] needs a row swap on the first pivot.
+        let swap = Matrix::from_rows(vec![
+            vec![Scalar::zero(), Scalar::one()],
+            vec![Scalar::one(), Scalar::zero()],
+        ]);
+        assert_eq!(swap.determinant(), Scalar::one().neg());
+        assert_eq!(swap.inverse(), Some(swap.clone()));
+    }
+
+    #[test]
+    fn test_determinant_of_triangular() {
+        let n = 5;
+        let a = Matrix::random(n, n);
+        let mut upper = a.clone();
+        for i in 0..n {
+            for j in 0..i {
+                upper.values[i][j] = Scalar::zero();
+            }
+        }
+        let diagonal = (0..n).fold(Scalar::one(), |acc, i| acc * a.values[i][i]);
+        assert_eq!(upper.determinant(), diagonal);
+
+        // the lower one, with a zero on the diagonal.
+        let mut lower = a.map(|_| Scalar::zero());
+        for i in 0..n {
+            for j in 0..i {
+                lower.values[i][j] = a.values[i][j];
+            }
+        }
+        assert_eq!(lower.determinant(), Scalar::zero());
+    }
+
+    #[test]
+    fn test_singular() {
+        // row_2 = row_0 + 3 * row_1
+        let n = 4;
+        let mut a = Matrix::random(n, n);
+        for j in 0..n {
+            a.values[2][j] = a.values[0][j] + a.values[1][j] * Scalar::from_u128(3);
+        }
+        assert_eq!(a.determinant(), Scalar::zero());
+        assert_eq!(a.inverse(), None);
+        assert_eq!(a.map(|_| Scalar::zero()).inverse(), None);
+    }
+
+    #[test]
+    fn test_determinant_is_multiplicative() {
+        let n = 4;
+        for _ in 0..3 {
+            let a = Matrix::random(n, n);
+            let b = Matrix::random(n, n);
+            assert_eq!(
+                Matrix::mul(&a, &b).determinant(),
+                a.determinant() * b.determinant()
+            );
+        }
+        // det(A^(-1)) = 1 / det(A)
+        let a = Matrix::random_invertible(n, OsRng);
+        let det_inv = a.inverse().unwrap().determinant();
+        assert_eq!(det_inv * a.determinant(), Scalar::one());
+    }
+
+    #[test]
+    #[should_panic(expected = "matrix must be square, got (2 * 3)")]
+    fn test_determinant_not_square() {
+        Matrix::random(2, 3).determinant();
+    }
+
+    // The same values, multiplied by the generic way.
+    fn general(m: &Matrix) -> Matrix {
+        Matrix::from_rows(m.values.clone())
+    }
+
+    #[test]
+    fn test_permutation() {
+        let sigma = [2, 0, 3, 1];
+        let tau = [1, 3, 0, 2];
+        let p_sigma = Matrix::permutation(&sigma).unwrap();
+        let p_tau = Matrix::permutation(&tau).unwrap();
+        assert!(p_sigma.is_permutation());
+
+        // P_σ * P_τ = P_(τ∘σ)
+        let product = Matrix::mul(&p_sigma, &p_tau);
+        assert!(product.is_permutation());
+        let composed = sigma.iter().map(|i| tau[*i]).collect::<Vec<_>>();
+        assert_eq!(product, Matrix::permutation(&composed).unwrap());
+        assert_eq!(product.kind, Kind::Permutation(composed));
+
+        // P_σ * A moves the row σ[i] of A to the row i.
+        let a = Matrix::random(4, 3);
+        let moved = Matrix::mul(&p_sigma, &a);
+        for (i, j) in sigma.iter().enumerate() {
+            assert_eq!(moved.row(i), a.row(*j));
+        }
+
+        assert_eq!(
+            Matrix::permutation(&[0, 2]),
+            Err(PermutationError::OutOfRange { index: 1, value: 2 })
+        );
+        assert_eq!(
+            Matrix::permutation(&[1, 0, 1]),
+            Err(PermutationError::Repeated { index: 2, value: 1 })
+        );
+        assert!(!Matrix::random(3, 3).is_permutation());
+        assert!(!Matrix::diagonal(&[Scalar::one(), Scalar::from_u128(2)]).is_permutation());
+        assert!(Matrix::identity(3).is_permutation());
+    }
+
+    #[test]
+    fn test_diagonal_scales_rows() {
+        let entries = gen_x(OsRng, 4);
+        let d = Matrix::diagonal(&entries);
+        let a = Matrix::random(4, 5);
+        let scaled = Matrix::from_rows(
+            entries
+                .iter()
+                .enumerate()
+                .map(|(i, e)| a.row(i).iter().map(|x| e * x).collect())
+                .collect(),
+        );
+        assert_eq!(Matrix::mul(&d, &a), scaled);
+        assert_eq!(d.density(), 0.25);
+    }
+
+    #[test]
+    fn test_random_sparse_density() {
+        assert_eq!(Matrix::random_sparse(8, 8, 0.0, OsRng).density(), 0.0);
+        assert_eq!(Matrix::random_sparse(8, 8, 1.0, OsRng).density(), 1.0);
+        // the std of the density of 32 * 32 entries is about 0.014.
+        for _ in 0..10 {
+            let density = Matrix::random_sparse(32, 32, 0.25, OsRng).density();
+            assert!((density - 0.25).abs() < 0.1, "density {}", density);
+        }
+    }
+
+    #[test]
+    fn test_structured_mul() {
+        let n = 5;
+        let fixtures = [
+            Matrix::diagonal(&gen_x(OsRng, n)),
+            Matrix::identity(n),
+            Matrix::permutation(&[3, 0, 4, 1, 2]).unwrap(),
+            Matrix::permutation(&[1, 2, 3, 4, 0]).unwrap(),
+            Matrix::random_sparse(n, n, 0.3, OsRng),
+            Matrix::random(n, n),
+        ];
+        for a in fixtures.iter() {
+            for b in fixtures.iter() {
+                assert_eq!(Matrix::mul(a, b), Matrix::mul(&general(a), &general(b)));
+            }
+        }
+    }
+
+    fn from_ints(rows: &[&[u64]]) -> Matrix {
+        Matrix::from_rows(
+            rows.iter()
+                .map(|r| r.iter().map(|v| Scalar::from(*v)).collect())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_row_times_column() {
+        // (1, 2, 3) * (4, 5, 6)ᵀ = 4 + 10 + 18
+        let (row, column) = (from_ints(&[&[1, 2, 3]]), from_ints(&[&[4], &[5], &[6]]));
+        let product = Matrix::mul(&row, &column);
+        assert_eq!(product, from_ints(&[&[32]]));
+        assert_eq!(
+            product.row(0)[0],
+            Matrix::vec_mul(row.row(0), column.transpose().row(0))
+        );
+    }
+
+    #[test]
+    fn test_column_times_row() {
+        let (column, row) = (from_ints(&[&[1], &[2]]), from_ints(&[&[3, 4, 5]]));
+        let product = Matrix::mul(&column, &row);
+        assert_eq!(product, from_ints(&[&[3, 4, 5], &[6, 8, 10]]));
+        assert_eq!(
+            product,
+            Matrix::outer(column.transpose().row(0), row.row(0))
+        );
+
+        let (a, b) = (gen_x(OsRng, 4), gen_x(OsRng, 6));
+        let (column, row) = (
+            Matrix::from_rows(vec![a.clone()]).transpose(),
+            Matrix::from_rows(vec![b.clone()]),
+        );
+        assert_eq!(Matrix::mul(&column, &row), Matrix::outer(&a, &b));
+    }
+
+    #[test]
+    fn test_one_by_one() {
+        let (a, b) = (from_ints(&[&[3]]), from_ints(&[&[5]]));
+        assert_eq!(Matrix::mul(&a, &b), from_ints(&[&[15]]));
+        // the structured ones of (1 * 1).
+        let d = Matrix::diagonal(&[Scalar::from(7)]);
+        assert_eq!(Matrix::mul(&d, &a), from_ints(&[&[21]]));
+        assert_eq!(Matrix::mul(&a, &d), from_ints(&[&[21]]));
+        let p = Matrix::permutation(&[0]).unwrap();
+        assert_eq!(Matrix::mul(&p, &a), a);
+        assert_eq!(Matrix::mul(&a, &p), a);
+        assert_eq!(Matrix::mul(&p, &Matrix::identity(1)), Matrix::identity(1));
+    }
+
+    #[test]
+    fn test_identity_and_zero() {
+        let a = from_ints(&[&[1, 2, 3, 4], &[5, 6, 7, 8], &[9, 10, 11, 12]]);
+        assert_eq!(Matrix::mul(&a, &Matrix::identity(4)), a);
+        assert_eq!(Matrix::mul(&Matrix::identity(3), &a), a);
+        assert_eq!(Matrix::mul(&general(&Matrix::identity(3)), &a), a);
+
+        // A * e_j is the column j of A, and e_iᵀ * A is the row i.
+        let e = |n: usize, j: usize| general(&Matrix::identity(n)).row(j).to_vec();
+        let e_2 = Matrix::from_rows(vec![e(4, 2)]).transpose();
+        assert_eq!(Matrix::mul(&a, &e_2), from_ints(&[&[3], &[7], &[11]]));
+        let e_1 = Matrix::from_rows(vec![e(3, 1)]);
+        assert_eq!(Matrix::mul(&e_1, &a), from_ints(&[&[5, 6, 7, 8]]));
+
+        // 0 is absorbing, whatever the shape.
+        let zero = |rows, cols| Matrix::random(rows, cols).map(|_| Scalar::zero());
+        assert_eq!(Matrix::mul(&a, &zero(4, 1)), zero(3, 1));
+        assert_eq!(Matrix::mul(&zero(1, 3), &a), zero(1, 4));
+        assert_eq!(Matrix::mul(&zero(2, 3), &a), zero(2, 4));
+    }
+
+    #[test]
+    #[should_panic(expected = "matrix b is empty, got (3 * 0)")]
+    fn test_mul_empty() {
+        Matrix::mul(&Matrix::random(2, 3), &Matrix::random(3, 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "matrix a is empty, got (0 * 0)")]
+    fn test_mul_empty_square() {
+        Matrix::mul(&Matrix::from_rows(vec![]), &Matrix::from_rows(vec![]));
+    }
+
+    #[test]
+    #[should_panic(expected = "shape mismatch: (2 * 3) vs (2 * 3)")]
+    fn test_mul_shape_mismatch() {
+        Matrix::mul(&Matrix::random(2, 3), &Matrix::random(2, 3));
+    }
+
+    #[test]
+    fn test_empty_matrix_mul_vec() {
+        let empty = Matrix::random(3, 0);
+        assert_eq!((empty.rows(), empty.cols()), (3, 0));
+        assert_eq!(empty.matrix_mul_vec(&vec![]), vec![Scalar::zero(); 3]);
+        assert_eq!(Matrix::from_rows(vec![]).matrix_mul_vec(&vec![]), vec![]);
+        assert_eq!(Matrix::identity(0), Matrix::from_rows(vec![]));
+        assert_eq!(empty.transpose(), Matrix::random(0, 3));
+    }
+
+    #[test]
+    #[should_panic(expected = "shape mismatch: (3 * 2) vs (0 * 1)")]
+    fn test_matrix_mul_vec_empty_vector() {
+        Matrix::random(3, 2).matrix_mul_vec(&vec![]);
+    }
 }
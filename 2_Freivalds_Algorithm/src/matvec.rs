@@ -0,0 +1,350 @@
+// Matrix-vector product with a committed matrix: V holds the root of B̃(m * n) committed by
+// `ni_sumcheck::mlpc` (see `CommittedMatrix::commit_mle`) and x, P claims y = B · x.
+//
+// By Lemma 3.8, for r ∈ F^(log m):
+//
+//     ỹ(r) = ∑_{c ∈ {0,1}^(log n)} B̃(r,c)·x̃(c)
+//
+// V derives r from the transcript and computes ỹ(r) from y by itself, then P runs the product
+// sum-check over c between the B̃(r,·) table and x. The challenge r'_j of each round is a scalar
+// of the transcript, so the sum-check ends at the claim B̃(r,r')·x̃(r'), where V computes
+//
+//     x̃(r') = ∑_c eq̃(r',c)·x_c
+//
+// and P opens B̃ at (r, r') against the root, see `mlpc::open`.
+//
+// So V does O(m + n) work plus one opening of B̃, rather than reading all of B. n must be a power
+// of 2, m is padded with zero rows.
+//
+// The transcript order is: (root, x, y), r, then per round: g_j, r'_j. The opening of B̃(r,r')
+// binds the point and the value on a transcript of its own, see `mlpc`.
+use crate::committed::{CommittedMatrix, MleRoot};
+use crate::freivalds::encode_mle_root;
+use crate::matrix::Matrix;
+use bls12_381::Scalar;
+use ni_sumcheck::mlpc::{self, MlOpening, MlpcError};
+use std::fmt;
+use sumcheck::poly::univar_poly::Polynomial;
+use Fiat_Shamir::codec::encode_scalars;
+use Fiat_Shamir::default::Keccak256Transcript;
+use Fiat_Shamir::Transcript;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatVecProof {
+    // y = B · x
+    pub y: Vec<Scalar>,
+    // g_1, ..., g_(log n)
+    pub rounds: Vec<Polynomial>,
+    // B̃(r,r'), and its opening against the root.
+    pub value: Scalar,
+    pub opening: MlOpening,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatVecError {
+    ShapeMismatch,
+    WrongRoundNum,
+    DegreeTooLarge { round: usize },
+    RoundCheckFailed { round: usize },
+    // the opening of B̃(r,r') is rejected.
+    OpeningFailed(MlpcError),
+    // B̃(r,r')·x̃(r') != the last claim
+    FinalCheckFailed,
+}
+
+impl fmt::Display for MatVecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatVecError::ShapeMismatch => write!(f, "The shapes of B and x mismatch"),
+            MatVecError::WrongRoundNum => write!(f, "Wrong number of rounds"),
+            MatVecError::DegreeTooLarge { round } => write!(f, "g_{} is of degree above 2", round),
+            MatVecError::RoundCheckFailed { round } => write!(f, "Not-equal in round_{}", round),
+            MatVecError::OpeningFailed(e) => write!(f, "The opening of B̃ failed: {}", e),
+            MatVecError::FinalCheckFailed => write!(f, "Verifier rejected the last claim"),
+        }
+    }
+}
+
+impl std::error::Error for MatVecError {}
+
+pub(crate) fn log2(n: usize) -> usize {
+    n.next_power_of_two().trailing_zeros() as usize
+}
+
+// eq̃(r,i) for i ∈ {0,1}^k, MSB first.
+pub(crate) fn eq_table(r: &[Scalar]) -> Vec<Scalar> {
+    r.iter().fold(vec![Scalar::one()], |table, r_j| {
+        table
+            .iter()
+            .flat_map(|t| [t * (Scalar::one() - r_j), t * r_j])
+            .collect()
+    })
+}
+
+// ∑_i eq̃(r,i)·v_i, v is padded with zeros.
+fn evaluate_mle(eq: &[Scalar], v: &[Scalar]) -> Scalar {
+    Matrix::vec_mul(&eq[..v.len()], v)
+}
+
+// The table with its first variable bound to r: f(r, ·) = f(0, ·) + r·(f(1, ·) - f(0, ·)).
+pub(crate) fn bind(table: &[Scalar], r: Scalar) -> Vec<Scalar> {
+    let half = table.len() / 2;
+    (0..half)
+        .map(|i| table[i] + r * (table[i + half] - table[i]))
+        .collect()
+}
+
+// Bind the statement, and derive r.
+fn init_transcript(
+    root: &MleRoot,
+    x: &[Scalar],
+    y: &[Scalar],
+) -> (Keccak256Transcript, Vec<Scalar>) {
+    let mut transcript = Keccak256Transcript::default();
+    let mut message = encode_mle_root(root);
+    message.extend(encode_scalars(x));
+    message.extend(encode_scalars(y));
+    transcript.append_labeled("matvec.statement", &message);
+
+    let r = (0..log2(root.rows))
+        .map(|_| transcript.challenge_scalar())
+        .collect();
+    (transcript, r)
+}
+
+// The challenge r'_j of round j.
+fn challenge_round(g_j: &Polynomial, transcript: &mut Keccak256Transcript) -> Scalar {
+    transcript.append_labeled("matvec.round", &encode_scalars(&g_j.coeffs()));
+    transcript.challenge_scalar()
+}
+
+// g_j(t) = ∑_{c} (f_0 + t·(f_1 - f_0))·(x_0 + t·(x_1 - x_0)), where f_0/f_1 are the low/high
+// halves of the table, which has degree 2.
+pub(crate) fn round_poly(f: &[Scalar], x: &[Scalar]) -> Polynomial {
+    let half = f.len() / 2;
+    let evals = (0..3u64)
+        .map(|t| {
+            let t = Scalar::from(t);
+            (0..half)
+                .map(|c| {
+                    let f_t = f[c] + t * (f[c + half] - f[c]);
+                    let x_t = x[c] + t * (x[c + half] - x[c]);
+                    f_t * x_t
+                })
+                .fold(Scalar::zero(), |acc, v| acc + v)
+        })
+        .collect();
+    Polynomial::lagrange_interpolate((0..3u64).map(Scalar::from).collect(), evals)
+}
+pub fn prove_matvec(b: &Matrix, x: &[Scalar]) -> MatVecProof {
+    assert_eq!(b.cols(), x.len(), "x must have b.cols() entries");
+    let (root, state) = CommittedMatrix::commit_mle(b);
+    let y = b.matrix_mul_vec(&x.to_vec());
+    let (mut transcript, r) = init_transcript(&root, x, &y);
+
+    // f(c) = B̃(r,c) = (eqᵀ · B)_c, eq is cut to the rows of B.
+    let eq = eq_table(&r);
+    let mut f = Matrix::vec_mul_matrix(&eq[..b.rows()], b);
+    let mut x = x.to_vec();
+
+    let mut rounds = vec![];
+    let mut point = r;
+    while f.len() > 1 {
+        let g_j = round_poly(&f, &x);
+        let r_j = challenge_round(&g_j, &mut transcript);
+        rounds.push(g_j);
+
+        f = bind(&f, r_j);
+        x = bind(&x, r_j);
+        point.push(r_j);
+    }
+
+    MatVecProof {
+        y,
+        rounds,
+        value: f[0],
+        opening: mlpc::open(&state, &point),
+    }
+}
+
+// Check the claim y = B · x against the root of `commit_mle(B)`.
+pub fn verify_matvec(
+    root: &MleRoot,
+    x: &[Scalar],
+    claimed_y: &[Scalar],
+    proof: &MatVecProof,
+) -> Result<(), MatVecError> {
+    let (m, n) = (root.rows, root.cols);
+    if !n.is_power_of_two() || x.len() != n || claimed_y.len() != m {
+        return Err(MatVecError::ShapeMismatch);
+    }
+    if proof.rounds.len() != log2(n) {
+        return Err(MatVecError::WrongRoundNum);
+    }
+
+    // 1. ỹ(r) by V itself
+    let (mut transcript, r) = init_transcript(root, x, claimed_y);
+    let mut claim = evaluate_mle(&eq_table(&r), claimed_y);
+
+    // 2. the sum-check, which ends at the claim B̃(r,r')·x̃(r')
+    let mut r_prime = Vec::with_capacity(proof.rounds.len());
+    for (j, g_j) in proof.rounds.iter().enumerate() {
+        if g_j.num_coeffs() > 3 {
+            return Err(MatVecError::DegreeTooLarge { round: j + 1 });
+        }
+        if g_j.evaluate(Scalar::zero()) + g_j.evaluate(Scalar::one()) != claim {
+            return Err(MatVecError::RoundCheckFailed { round: j + 1 });
+        }
+        let r_j = challenge_round(g_j, &mut transcript);
+        claim = g_j.evaluate(r_j);
+        r_prime.push(r_j);
+    }
+
+    // 3. B̃(r,r') is the one of the committed B
+    let point = [r, r_prime.clone()].concat();
+    mlpc::verify(&root.table, &point, proof.value, &proof.opening)
+        .map_err(MatVecError::OpeningFailed)?;
+
+    // 4. B̃(r,r')·x̃(r')
+    if proof.value * evaluate_mle(&eq_table(&r_prime), x) != claim {
+        return Err(MatVecError::FinalCheckFailed);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::committed::CommittedMatrix;
+    use crate::matrix::Matrix;
+    use crate::matvec::{
+        bind, challenge_round, eq_table, evaluate_mle, init_transcript, prove_matvec, round_poly,
+        verify_matvec, MatVecError, MatVecProof,
+    };
+    use bls12_381::Scalar;
+    use ff::Field;
+    use ni_sumcheck::mlpc::{self, MlpcError};
+    use rand_core::OsRng;
+    use sumcheck::poly::univar_poly::Polynomial;
+
+    fn random_scalars(n: usize) -> Vec<Scalar> {
+        (0..n).map(|_| Scalar::random(OsRng)).collect()
+    }
+
+    #[test]
+    fn test_honest_matvec() {
+        let b = Matrix::random(16, 16);
+        let x = random_scalars(16);
+        let (root, _) = CommittedMatrix::commit_mle(&b);
+
+        let proof = prove_matvec(&b, &x);
+        assert_eq!(proof.y, b.matrix_mul_vec(&x));
+        assert_eq!(verify_matvec(&root, &x, &proof.y, &proof), Ok(()));
+
+        // one opening of B̃ at (r, r'), over the log 16 + log 16 variables.
+        assert_eq!(proof.rounds.len(), 4);
+        assert_eq!(proof.opening.rounds.len(), 8);
+    }
+
+    // ỹ(r) = eq(r)ᵀ · B · x, the claim the sum-check starts from.
+    #[test]
+    fn test_bilinear_claim() {
+        let b = Matrix::random(5, 4);
+        let x = random_scalars(4);
+        let (root, _) = CommittedMatrix::commit_mle(&b);
+        let y = b.matrix_mul_vec(&x);
+        let (_, r) = init_transcript(&root, &x, &y);
+        let eq = eq_table(&r);
+        assert_eq!(b.bilinear(&eq[..5], &x), evaluate_mle(&eq, &y));
+    }
+
+    #[test]
+    fn test_padded_rows() {
+        // 5 rows are padded to 8, and 1 column has no rounds.
+        for cols in [1, 4] {
+            let b = Matrix::random(5, cols);
+            let x = random_scalars(cols);
+            let (root, _) = CommittedMatrix::commit_mle(&b);
+            let proof = prove_matvec(&b, &x);
+            assert_eq!(verify_matvec(&root, &x, &proof.y, &proof), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_wrong_y() {
+        let b = Matrix::random(16, 16);
+        let x = random_scalars(16);
+        let (root, _) = CommittedMatrix::commit_mle(&b);
+        let proof = prove_matvec(&b, &x);
+
+        let mut y = proof.y.clone();
+        y[3] += Scalar::one();
+        assert_eq!(
+            verify_matvec(&root, &x, &y, &proof),
+            Err(MatVecError::RoundCheckFailed { round: 1 })
+        );
+    }
+
+    #[test]
+    fn test_wrong_leaf() {
+        let b = Matrix::random(16, 16);
+        let x = random_scalars(16);
+        let (root, _) = CommittedMatrix::commit_mle(&b);
+
+        let mut proof = prove_matvec(&b, &x);
+        proof.opening.columns[0][0] += Scalar::one();
+        assert_eq!(
+            verify_matvec(&root, &x, &proof.y, &proof),
+            Err(MatVecError::OpeningFailed(MlpcError::MerkleCheckFailed))
+        );
+    }
+
+    // P claims a wrong y, and shifts each g_j by a constant so it sums to the claim, with the
+    // challenges squeezed after the shifted g_j. It passes every round, but ends at a claim other
+    // than B̃(r,r')·x̃(r'), so either the value it sends or the one of B̃ is rejected.
+    #[test]
+    fn test_lying_prover() {
+        let b = Matrix::random(16, 16);
+        let x = random_scalars(16);
+        let (root, state) = CommittedMatrix::commit_mle(&b);
+
+        let mut y = b.matrix_mul_vec(&x);
+        y[3] += Scalar::one();
+        let (mut transcript, r) = init_transcript(&root, &x, &y);
+        let mut claim = evaluate_mle(&eq_table(&r), &y);
+        let (mut f, mut x_t) = (Matrix::vec_mul_matrix(&eq_table(&r), &b), x.clone());
+        let (mut rounds, mut point) = (vec![], r);
+        let two_inv = Scalar::from(2).invert().unwrap();
+        while f.len() > 1 {
+            let honest = round_poly(&f, &x_t);
+            let sum = honest.evaluate(Scalar::zero()) + honest.evaluate(Scalar::one());
+            let g_j = &honest + &Polynomial::constant((claim - sum) * two_inv);
+            let r_j = challenge_round(&g_j, &mut transcript);
+            claim = g_j.evaluate(r_j);
+            rounds.push(g_j);
+            f = bind(&f, r_j);
+            x_t = bind(&x_t, r_j);
+            point.push(r_j);
+        }
+        assert_ne!(claim, f[0] * x_t[0]);
+
+        // the true B̃(r,r'), which fails the last claim.
+        let opening = mlpc::open(&state, &point);
+        let mut proof = MatVecProof {
+            y: y.clone(),
+            rounds,
+            value: f[0],
+            opening,
+        };
+        assert_eq!(
+            verify_matvec(&root, &x, &y, &proof),
+            Err(MatVecError::FinalCheckFailed)
+        );
+
+        // the value the last claim asks for, which the opening doesn't back.
+        proof.value = claim * x_t[0].invert().unwrap();
+        assert!(matches!(
+            verify_matvec(&root, &x, &y, &proof),
+            Err(MatVecError::OpeningFailed(_))
+        ));
+    }
+}
@@ -4,9 +4,32 @@ use ff::Field;
 use rand_core::RngCore;
 use std::ops::MulAssign;
 
+/// Where the challenge r is sampled from, the soundness error is at most (n-1)/|S|.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChallengeDistribution {
+    /// S = {0, 1}
+    Binary,
+    /// S = {0, 1, ..., k-1}, to simulate a small field.
+    SmallRange(u64),
+    /// S = Fp
+    FullField,
+}
+
 /// x=(1,r,r2,...,rn−1)
 pub fn gen_x(rng: impl RngCore, n: usize) -> Vec<Scalar> {
-    let r: Scalar = Scalar::random(rng);
+    gen_x_with(rng, n, ChallengeDistribution::FullField)
+}
+
+/// x=(1,r,r2,...,rn−1), r is sampled from `dist`.
+pub fn gen_x_with(mut rng: impl RngCore, n: usize, dist: ChallengeDistribution) -> Vec<Scalar> {
+    let r: Scalar = match dist {
+        ChallengeDistribution::Binary => Scalar::from(rng.next_u32() as u64 & 1),
+        ChallengeDistribution::SmallRange(k) => {
+            assert!(k > 0, "the range is empty");
+            Scalar::from(rng.next_u64() % k)
+        }
+        ChallengeDistribution::FullField => Scalar::random(rng),
+    };
 
     let mut cur_r = Scalar::one();
     (0..n)
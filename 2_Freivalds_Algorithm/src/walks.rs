@@ -0,0 +1,430 @@
+// The walks of length k from s to t by sum-check: (A^k)[s][t] for the adjacency A of a graph, see
+// `graph`. As the triangles, it's a sum over the vertices in between, of k factors of Ã:
+//
+//     (A^k)[s][t] = ∑_{y_1, ..., y_(k-1) ∈ {0,1}^l} Ã(s, y_1)·Ã(y_1, y_2)···Ã(y_(k-1), t)
+//
+// n = 2^l, and a single sum-check runs over the (k-1)·l variables, the block y_1 first, each
+// block MSB first. The factors are a chain: y_b is in Ã(y_(b-1), y_b) and Ã(y_b, y_(b+1)) only,
+// so each round is of degree 2, see `round_degrees`.
+//
+// The chain reduces to a product of 2 tables per block. With y_1, ..., y_(b-1) bound to
+// r_1, ..., r_(b-1), the rest of the sum is
+//
+//     c_b · ∑_{y_b} Ã(r_(b-1), y_b) · (A^(k-b)·e_t)[y_b],   c_b = Ã(s, r_1)···Ã(r_(b-2), r_(b-1))
+//
+// as the sum of the rest of the chain over y_(b+1), ..., y_(k-1) is the column t of A^(k-b),
+// which is multilinear in y_b. So P computes the columns A^m·e_t by k - 1 mat-vecs, never A^k.
+//
+// V checks the rounds, then evaluates the k factors at the derived points by itself:
+//
+//     Ã(r_(b-1), r_b) = eq̃(r_(b-1))ᵀ · A · eq̃(r_b),   r_0 = s, r_k = t
+//
+// which is O(k·n^2) with A in the clear, eg: A^k is O(n^3·log k). With a commitment to A
+// instead, they are k openings of Ã, see `matvec` for the opening of B̃(r, r').
+//
+// A is padded to a power of 2 with zero rows and columns, the vertices without edges, so the
+// walks are the same. (s, t) are fixed by the caller, or squeezed from the transcript, see
+// `challenge_endpoints`.
+//
+// The transcript order is: (A, k, s, t, claimed), then per round: g_j, r_j.
+use crate::freivalds::write_matrix;
+use crate::matrix::Matrix;
+use crate::matvec::{bind, eq_table, log2, round_poly};
+use bls12_381::Scalar;
+use std::fmt;
+use sumcheck::poly::univar_poly::Polynomial;
+use Fiat_Shamir::codec::{encode_scalars, encode_u64};
+use Fiat_Shamir::stream::MessageWriter;
+use Fiat_Shamir::{challenge_to_index, Transcript};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WalkProof {
+    // (s, t), the vertices the walks are from and to.
+    pub endpoints: (usize, usize),
+    // g_1, ..., g_((k-1)·l)
+    pub rounds: Vec<Polynomial>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalkError {
+    // A is not square, or s or t is not one of its vertices.
+    ShapeMismatch,
+    // the walks of length 0, which are I.
+    EmptyWalk,
+    // the proof is of other endpoints than the ones asked for.
+    EndpointMismatch,
+    WrongRoundNum,
+    // g_j is of a degree above the factors touching its block.
+    DegreeTooHigh {
+        round: usize,
+        degree: usize,
+        bound: usize,
+    },
+    RoundCheckFailed {
+        round: usize,
+    },
+    // Ã(s, r_1)···Ã(r_(k-1), t) != the last claim
+    FinalCheckFailed,
+}
+
+impl fmt::Display for WalkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WalkError::ShapeMismatch => write!(f, "A is not square, or (s, t) is out of it"),
+            WalkError::EmptyWalk => write!(f, "The walks must be of length >= 1"),
+            WalkError::EndpointMismatch => write!(f, "The proof is of other endpoints"),
+            WalkError::WrongRoundNum => write!(f, "Wrong number of rounds"),
+            WalkError::DegreeTooHigh {
+                round,
+                degree,
+                bound,
+            } => write!(
+                f,
+                "g_{} is of degree {}, above the {} factors of its block",
+                round, degree, bound
+            ),
+            WalkError::RoundCheckFailed { round } => write!(f, "Not-equal in round_{}", round),
+            WalkError::FinalCheckFailed => write!(f, "Verifier rejected the last claim"),
+        }
+    }
+}
+
+impl std::error::Error for WalkError {}
+
+// The degree of g_j in each of the (k-1)·l rounds: the number of factors Ã(y_f, y_(f+1)) of the
+// chain touching the block of the round, y_0 = s and y_k = t. eg: 2 in every round.
+pub fn round_degrees(k: usize, l: usize) -> Vec<usize> {
+    (1..k)
+        .flat_map(|b| {
+            let touching = (0..k).filter(|f| *f == b || f + 1 == b).count();
+            vec![touching; l]
+        })
+        .collect()
+}
+
+// (s, t) squeezed from the transcript after A and k, for P and V alike.
+pub fn challenge_endpoints(
+    adj: &Matrix,
+    k: usize,
+    transcript: &mut dyn Transcript,
+) -> (usize, usize) {
+    let mut writer = MessageWriter::new(transcript, "walks.endpoints");
+    write_matrix(&mut writer, adj);
+    writer.push_bytes(&encode_u64(k as u64));
+    writer.finish();
+    let n = adj.rows();
+    let s = challenge_to_index(&transcript.challenge(), n);
+    (s, challenge_to_index(&transcript.challenge(), n))
+}
+
+// A, k, s, t, claimed
+fn absorb_statement(
+    transcript: &mut dyn Transcript,
+    adj: &Matrix,
+    k: usize,
+    (s, t): (usize, usize),
+    claimed: Scalar,
+) {
+    let mut writer = MessageWriter::new(transcript, "walks.statement");
+    write_matrix(&mut writer, adj);
+    for v in [k, s, t] {
+        writer.push_bytes(&encode_u64(v as u64));
+    }
+    writer.push_bytes(&encode_scalars(&[claimed]));
+    writer.finish();
+}
+
+// The challenge r_j of round j.
+fn challenge_round(g_j: &Polynomial, transcript: &mut dyn Transcript) -> Scalar {
+    transcript.append_labeled("walks.round", &encode_scalars(&g_j.coeffs()));
+    transcript.challenge_scalar()
+}
+
+// A padded to 2^l * 2^l with zeros.
+fn pad(adj: &Matrix) -> Matrix {
+    let (n, size) = (adj.rows(), adj.rows().next_power_of_two());
+    let rows = (0..size)
+        .map(|i| {
+            let mut row = if i < n { adj.row(i).to_vec() } else { vec![] };
+            row.resize(size, Scalar::zero());
+            row
+        })
+        .collect();
+    Matrix::from_rows(rows)
+}
+
+// e_i of the size.
+fn unit(size: usize, i: usize) -> Vec<Scalar> {
+    let mut e = vec![Scalar::zero(); size];
+    e[i] = Scalar::one();
+    e
+}
+
+// (A^k)[s][t] for the (s, t) squeezed from the transcript, see `challenge_endpoints`.
+pub fn prove_walk_count(
+    adj: &Matrix,
+    k: usize,
+    transcript: &mut dyn Transcript,
+) -> (Scalar, WalkProof) {
+    let endpoints = challenge_endpoints(adj, k, transcript);
+    prove_walk_count_at(adj, k, endpoints, transcript)
+}
+
+// (A^k)[s][t] for the (s, t) of the caller.
+pub fn prove_walk_count_at(
+    adj: &Matrix,
+    k: usize,
+    (s, t): (usize, usize),
+    transcript: &mut dyn Transcript,
+) -> (Scalar, WalkProof) {
+    assert!(k >= 1, "the walks must be of length >= 1");
+    let n = adj.rows();
+    assert_eq!(n, adj.cols(), "the adjacency must be square");
+    assert!(
+        s < n && t < n,
+        "({}, {}) is out of the {} vertices",
+        s,
+        t,
+        n
+    );
+    let a = pad(adj);
+
+    // columns[m] = A^m · e_t, for m in [0, k).
+    let mut columns = vec![unit(a.rows(), t)];
+    for m in 1..k {
+        columns.push(a.matrix_mul_vec(&columns[m - 1]));
+    }
+    let claimed = Matrix::vec_mul(a.row(s), &columns[k - 1]);
+    absorb_statement(transcript, adj, k, (s, t), claimed);
+
+    // c_b and the table of Ã(r_(b-1), ·), from c_1 = 1 and Ã(s, ·), the row s.
+    let (mut c, mut left) = (Scalar::one(), a.row(s).to_vec());
+    let mut rounds = vec![];
+    for b in 1..k {
+        let mut right = columns[k - b].clone();
+        let mut r_b = vec![];
+        while left.len() > 1 {
+            let mut g_j = round_poly(&left, &right);
+            g_j.scale_in_place(c);
+            let r_j = challenge_round(&g_j, transcript);
+            rounds.push(g_j);
+            left = bind(&left, r_j);
+            right = bind(&right, r_j);
+            r_b.push(r_j);
+        }
+        // c_(b+1) = c_b · Ã(r_(b-1), r_b), then Ã(r_b, ·) = eq̃(r_b)ᵀ · A.
+        c *= left[0];
+        left = Matrix::vec_mul_matrix(&eq_table(&r_b), &a);
+    }
+
+    let proof = WalkProof {
+        endpoints: (s, t),
+        rounds,
+    };
+    (claimed, proof)
+}
+
+// Check the claim (A^k)[s][t] = claimed, with the transcript in the state P started from, eg:
+// after `challenge_endpoints` if (s, t) are squeezed.
+pub fn verify_walk_count(
+    adj: &Matrix,
+    k: usize,
+    (s, t): (usize, usize),
+    claimed: Scalar,
+    proof: &WalkProof,
+    transcript: &mut dyn Transcript,
+) -> Result<(), WalkError> {
+    let n = adj.rows();
+    if adj.cols() != n || s >= n || t >= n {
+        return Err(WalkError::ShapeMismatch);
+    }
+    if k == 0 {
+        return Err(WalkError::EmptyWalk);
+    }
+    if proof.endpoints != (s, t) {
+        return Err(WalkError::EndpointMismatch);
+    }
+    let l = log2(n);
+    let degrees = round_degrees(k, l);
+    if proof.rounds.len() != degrees.len() {
+        return Err(WalkError::WrongRoundNum);
+    }
+
+    // 1. the sum-check, which ends at the claim Ã(s, r_1)···Ã(r_(k-1), t)
+    absorb_statement(transcript, adj, k, (s, t), claimed);
+    let mut claim = claimed;
+    let mut r = Vec::with_capacity(degrees.len());
+    for (j, (g_j, bound)) in proof.rounds.iter().zip(degrees).enumerate() {
+        if g_j.degree() > bound {
+            return Err(WalkError::DegreeTooHigh {
+                round: j + 1,
+                degree: g_j.degree(),
+                bound,
+            });
+        }
+        if g_j.evaluate(Scalar::zero()) + g_j.evaluate(Scalar::one()) != claim {
+            return Err(WalkError::RoundCheckFailed { round: j + 1 });
+        }
+        let r_j = challenge_round(g_j, transcript);
+        claim = g_j.evaluate(r_j);
+        r.push(r_j);
+    }
+
+    // 2. the k factors by V itself, eq̃(s) = e_s and eq̃(t) = e_t.
+    let a = pad(adj);
+    let mut points = vec![unit(a.rows(), s)];
+    points.extend((0..k - 1).map(|b| eq_table(&r[b * l..(b + 1) * l])));
+    points.push(unit(a.rows(), t));
+    let product = points
+        .windows(2)
+        .fold(Scalar::one(), |acc, w| acc * a.bilinear(&w[0], &w[1]));
+    if product != claim {
+        return Err(WalkError::FinalCheckFailed);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::Graph;
+    use Fiat_Shamir::default::Keccak256Transcript;
+
+    // two triangles {0, 1, 2} and {2, 3, 4} sharing the vertex 2, see `graph`.
+    const BOWTIE: [(usize, usize); 6] = [(0, 1), (1, 2), (0, 2), (2, 3), (3, 4), (2, 4)];
+
+    fn graphs() -> [Matrix; 2] {
+        let bowtie = Graph::from_edges(5, &BOWTIE, false).unwrap();
+        // a directed 3-cycle with a self-loop, 3 is not a power of 2.
+        let cycle = [[0, 1, 0], [0, 0, 1], [1, 0, 1]];
+        let cycle = Matrix::from_rows(cycle.map(|r| r.map(Scalar::from).to_vec()).to_vec());
+        [bowtie.adjacency(), cycle]
+    }
+
+    fn prove(adj: &Matrix, k: usize, endpoints: (usize, usize)) -> (Scalar, WalkProof) {
+        prove_walk_count_at(adj, k, endpoints, &mut Keccak256Transcript::default())
+    }
+
+    fn verify(
+        adj: &Matrix,
+        k: usize,
+        endpoints: (usize, usize),
+        claimed: Scalar,
+        proof: &WalkProof,
+    ) -> Result<(), WalkError> {
+        let mut transcript = Keccak256Transcript::default();
+        verify_walk_count(adj, k, endpoints, claimed, proof, &mut transcript)
+    }
+
+    #[test]
+    fn test_walk_counts() {
+        for adj in graphs() {
+            let l = log2(adj.rows());
+            for k in [1, 2, 3] {
+                let power = adj.pow(k);
+                for (s, t) in (0..adj.rows()).flat_map(|s| (0..adj.rows()).map(move |t| (s, t))) {
+                    let (claimed, proof) = prove(&adj, k, (s, t));
+                    assert_eq!(claimed, power.row(s)[t], "k = {}, ({}, {})", k, s, t);
+                    assert_eq!(proof.rounds.len(), (k - 1) * l);
+                    assert_eq!(verify(&adj, k, (s, t), claimed, &proof), Ok(()));
+                }
+            }
+        }
+        // the walks 2 -> 0 -> 2, 2 -> 1 -> 2, 2 -> 3 -> 2, 2 -> 4 -> 2 of the bowtie.
+        let bowtie = &graphs()[0];
+        assert_eq!(prove(bowtie, 2, (2, 2)).0, Scalar::from(4));
+    }
+
+    #[test]
+    fn test_endpoints_from_transcript() {
+        // K_4, whose walks of length 3 are 6 or 7 for any (s, t): the proof of a zero count is
+        // zero whatever the challenges, so it passes any transcript, eg: a padded vertex.
+        let k4 = [(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)];
+        let adj = &Graph::from_edges(4, &k4, false).unwrap().adjacency();
+        let mut transcript = Keccak256Transcript::default();
+        let (claimed, proof) = prove_walk_count(adj, 3, &mut transcript);
+        let (s, t) = proof.endpoints;
+        assert_eq!(claimed, adj.pow(3).row(s)[t]);
+
+        // V squeezes the same endpoints, and not the ones of another k.
+        let mut transcript = Keccak256Transcript::default();
+        let endpoints = challenge_endpoints(adj, 3, &mut transcript);
+        assert_eq!(endpoints, (s, t));
+        assert_eq!(
+            verify_walk_count(adj, 3, endpoints, claimed, &proof, &mut transcript),
+            Ok(())
+        );
+        let mut transcript = Keccak256Transcript::default();
+        challenge_endpoints(adj, 2, &mut transcript);
+        assert!(verify_walk_count(adj, 3, endpoints, claimed, &proof, &mut transcript).is_err());
+
+        // a proof of the fixed endpoints is of another transcript.
+        let (claimed, proof) = prove(adj, 3, (s, t));
+        let mut transcript = Keccak256Transcript::default();
+        challenge_endpoints(adj, 3, &mut transcript);
+        assert!(verify_walk_count(adj, 3, (s, t), claimed, &proof, &mut transcript).is_err());
+    }
+
+    #[test]
+    fn test_wrong_count() {
+        for adj in graphs() {
+            for k in [2, 3] {
+                let (claimed, proof) = prove(&adj, k, (0, 2));
+                assert_eq!(
+                    verify(&adj, k, (0, 2), claimed + Scalar::one(), &proof),
+                    Err(WalkError::RoundCheckFailed { round: 1 })
+                );
+                assert_eq!(
+                    verify(&adj, k, (0, 1), claimed, &proof),
+                    Err(WalkError::EndpointMismatch)
+                );
+            }
+        }
+        // k = 1 has no rounds, the claim is checked against A[s][t] alone.
+        let adj = &graphs()[1];
+        let (claimed, proof) = prove(adj, 1, (2, 2));
+        assert!(proof.rounds.is_empty());
+        assert_eq!(claimed, Scalar::one());
+        assert_eq!(
+            verify(adj, 1, (2, 2), Scalar::zero(), &proof),
+            Err(WalkError::FinalCheckFailed)
+        );
+        assert_eq!(
+            verify(adj, 0, (2, 2), claimed, &proof),
+            Err(WalkError::EmptyWalk)
+        );
+    }
+
+    #[test]
+    fn test_degree_bound() {
+        // y_b is in Ã(y_(b-1), y_b) and Ã(y_b, y_(b+1)) only, whatever k is.
+        assert_eq!(round_degrees(2, 3), vec![2; 3]);
+        assert_eq!(round_degrees(4, 2), vec![2; 6]);
+        assert!(round_degrees(1, 3).is_empty());
+
+        // g_1 + X^2·(X - 1) has the same g_1(0) + g_1(1), but is of degree 3.
+        let adj = &graphs()[0];
+        let (claimed, mut proof) = prove(adj, 3, (0, 4));
+        let g_1 = &proof.rounds[0];
+        let x2_x1 = Polynomial::from_coeffs(vec![Scalar::zero(), -Scalar::one(), Scalar::one()]);
+        let tampered = g_1 + &(&x2_x1 * &Polynomial::x());
+        let sum = |g: &Polynomial| g.evaluate(Scalar::zero()) + g.evaluate(Scalar::one());
+        assert_eq!(sum(&tampered), sum(g_1));
+        proof.rounds[0] = tampered;
+        assert_eq!(
+            verify(adj, 3, (0, 4), claimed, &proof),
+            Err(WalkError::DegreeTooHigh {
+                round: 1,
+                degree: 3,
+                bound: 2
+            })
+        );
+
+        let (claimed, mut proof) = prove(adj, 3, (0, 4));
+        proof.rounds.pop();
+        assert_eq!(
+            verify(adj, 3, (0, 4), claimed, &proof),
+            Err(WalkError::WrongRoundNum)
+        );
+    }
+}
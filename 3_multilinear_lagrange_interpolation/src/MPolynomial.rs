@@ -50,8 +50,51 @@ use bls12_381::Scalar;
 use ff::Field;
 use std::collections::HashMap;
 use std::env::var;
+use std::fmt;
 use std::ops::AddAssign;
 
+// The var_num of the dense form, as 1 << var_num must fit in an index.
+pub const MAX_VAR_NUM: usize = usize::BITS as usize - 1;
+
+// The result would have degree > 1 in x_{var+1}, which can't be represented by `MPolynomial`, the
+// same as `NotMultilinear` of the sumcheck crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotMultilinear {
+    pub var: usize,
+}
+
+// The exponent map isn't the one of a multilinear poly in the dense form, see
+// `MPolynomial::from_exponent_map`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversionError {
+    // 1 << var_num doesn't fit in an index.
+    TooManyVars { var_num: usize },
+    // the exponent vector doesn't have var_num entries.
+    WrongLength { expected: usize, found: usize },
+    // x_var has an exponent more than 1, the vars are indexed from 0.
+    NotMultilinear { var: usize, exp: u8 },
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConversionError::TooManyVars { var_num } => {
+                write!(f, "{} variables are more than {}", var_num, MAX_VAR_NUM)
+            }
+            ConversionError::WrongLength { expected, found } => write!(
+                f,
+                "The exponent vector has {} entries, expected {}",
+                found, expected
+            ),
+            ConversionError::NotMultilinear { var, exp } => {
+                write!(f, "Variable {} has the exponent {}, more than 1", var, exp)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
 // A multivariate polynomial g is multilinear if the degree of the polynomial in each variable is at most one.
 // For example, the polynomial g(x1,x2) = x_1*x_2 +4x_1 +3x_2 is multilinear, but the polynomial
 // h(x1,x2) = x2 + 4x1 + 3x2 is not.
@@ -140,7 +183,8 @@ impl MPolynomial {
                 continue;
             }
             let factor = gen_X_wi(i, w_i.clone());
-            product = expand_factor_for_mpoly(var_num, product, factor);
+            // the factors are variable-disjoint, so the product is always multilinear.
+            product = expand_factor_for_mpoly(var_num, product, factor).unwrap();
         }
 
         product
@@ -192,6 +236,49 @@ impl MPolynomial {
         }
         sum_of_term
     }
+
+    // The exponent vectors of the non-zero terms, see Impl-2 above.
+    // eg: 5 + 2*x3 with var_num = 3 => {[0,0,0] => 5, [0,0,1] => 2}
+    pub fn to_exponent_map(&self) -> HashMap<Vec<u8>, Scalar> {
+        self.coeffs
+            .iter()
+            .enumerate()
+            .filter(|(_, coeff)| !bool::from(coeff.is_zero()))
+            .map(|(index, coeff)| {
+                let exps = convert_to_binary(&self.var_num, index)
+                    .into_iter()
+                    .map(|e| e as u8)
+                    .collect();
+                (exps, *coeff)
+            })
+            .collect()
+    }
+
+    // The dense form of the exponent map, the missing terms are zero. Only the exponents 0 and
+    // 1 are in the dense form, so a higher one is rejected with its variable.
+    pub fn from_exponent_map(
+        var_num: usize,
+        map: &HashMap<Vec<u8>, Scalar>,
+    ) -> Result<Self, ConversionError> {
+        if var_num > MAX_VAR_NUM {
+            return Err(ConversionError::TooManyVars { var_num });
+        }
+        let mut coeffs = vec![Scalar::zero(); 1 << var_num];
+        for (exps, coeff) in map.iter() {
+            if exps.len() != var_num {
+                return Err(ConversionError::WrongLength {
+                    expected: var_num,
+                    found: exps.len(),
+                });
+            }
+            if let Some((var, exp)) = exps.iter().enumerate().find(|(_, exp)| **exp > 1) {
+                return Err(ConversionError::NotMultilinear { var, exp: *exp });
+            }
+            let index = exps.iter().fold(0, |index, e| (index << 1) | *e as usize);
+            coeffs[index] = *coeff;
+        }
+        Ok(Self { var_num, coeffs })
+    }
 }
 
 // TODO impl Fmt for mpoly
@@ -199,31 +286,41 @@ impl MPolynomial {
 #[cfg(test)]
 mod test {
     use crate::utils::*;
-    use crate::MPolynomial::MPolynomial;
+    use crate::MPolynomial::{ConversionError, MPolynomial, MAX_VAR_NUM};
     use bls12_381::Scalar;
-    use ff::PrimeField;
+    use ff::{Field, PrimeField};
+    use rand_core::OsRng;
+    use std::collections::HashMap;
+    use sumcheck::mpoly;
+
+    // The coeffs of a term string, eg: "5 + 2*x3", see `sumcheck::mpoly!`.
+    fn coeffs(s: &str, var_num: usize) -> Vec<Scalar> {
+        mpoly!(s, vars = var_num).coeffs
+    }
 
     #[test]
     fn test_lagrange() {
-        // let row g(x1, x2, x3) = 5 + 2*x3 + 3*x2 +  x1 * x2 * x3
+        let var_num = 3;
+
+        let evals = coeffs("5 + 2*x3 + 3*x2 + x1*x2*x3", var_num);
         // term0: exp: (0,0,0) = 5
         // term1: exp: (0,0,1) = 2*x3
         // term2: exp: (0,1,0) = 3*x2
-        // term3-6: exp: (0,1,0) = 0.
+        // term3-6: exp: (0,1,1)..(1,1,0) = 0.
         // term7: exp: (1,1,1) = x1 * x2 * x3
-
-        let var_num = 3;
-
-        let evals = vec![
-            Scalar::from_u128(5),
-            Scalar::from_u128(2),
-            Scalar::from_u128(3),
-            Scalar::zero(),
-            Scalar::zero(),
-            Scalar::zero(),
-            Scalar::zero(),
-            Scalar::one(),
-        ];
+        assert_eq!(
+            evals,
+            vec![
+                Scalar::from_u128(5),
+                Scalar::from_u128(2),
+                Scalar::from_u128(3),
+                Scalar::zero(),
+                Scalar::zero(),
+                Scalar::zero(),
+                Scalar::zero(),
+                Scalar::one(),
+            ]
+        );
 
         let poly = MPolynomial::lagrange(var_num, &evals);
 
@@ -256,11 +353,13 @@ mod test {
         let var_num = 4;
         let n = 1 << var_num;
         let w = vec![0, 0, 1, 1];
-        let mut target = vec![Scalar::zero(); n];
-        target[3] = Scalar::one();
-        target[7] = Scalar::one().neg();
-        target[11] = Scalar::one().neg();
-        target[15] = Scalar::one();
+        let mut expected = vec![Scalar::zero(); n];
+        expected[3] = Scalar::one();
+        expected[7] = Scalar::one().neg();
+        expected[11] = Scalar::one().neg();
+        expected[15] = Scalar::one();
+        let target = coeffs("x3*x4 - x2*x3*x4 - x1*x3*x4 + x1*x2*x3*x4", var_num);
+        assert_eq!(target, expected);
 
         let actual = MPolynomial::mpoly_langrange_basis(var_num, w);
         assert_eq!(actual, target);
@@ -269,7 +368,7 @@ mod test {
     #[test]
     fn test_2_mpoly_langrange_basis() {
         // eg: if var_num = 2, w=(0,1),
-        // so that X_w(0,0,1,1)=(1−x1) * x2
+        // so that X_w(0,1)=(1−x1) * x2
         //             = x2 - x1*x2
         // term0: exp: (0,0) = 0
         // term1: exp: (0,1) = x2
@@ -277,14 +376,17 @@ mod test {
         // term3: exp: (1,1) = - x1*x2
 
         let var_num = 2;
-        let n = 1 << var_num;
         let w = vec![0, 1];
-        let target = vec![
-            Scalar::zero(),
-            Scalar::one(),
-            Scalar::zero(),
-            Scalar::one().neg(),
-        ];
+        let target = coeffs("x2 - x1*x2", var_num);
+        assert_eq!(
+            target,
+            vec![
+                Scalar::zero(),
+                Scalar::one(),
+                Scalar::zero(),
+                Scalar::one().neg(),
+            ]
+        );
 
         let actual = MPolynomial::mpoly_langrange_basis(var_num, w);
         assert_eq!(actual, target);
@@ -292,27 +394,11 @@ mod test {
 
     #[test]
     fn test_evaluate() {
-        // let g(x1, x2, x3) = 5 + 2*x3 + 3*x2 +  x1 * x2 * x3
-        // term0: exp: (0,0,0) = 5
-        // term1: exp: (0,0,1) = 2*x3
-        // term2: exp: (0,1,0) = 3*x2
-        // term3-6: exp: (0,1,0) = 0.
-        // term7: exp: (1,1,1) = x1 * x2 * x3
-
         let var_num = 3;
 
         let poly = MPolynomial {
             var_num,
-            coeffs: vec![
-                Scalar::from_u128(5),
-                Scalar::from_u128(2),
-                Scalar::from_u128(3),
-                Scalar::zero(),
-                Scalar::zero(),
-                Scalar::zero(),
-                Scalar::zero(),
-                Scalar::one(),
-            ],
+            coeffs: coeffs("5 + 2*x3 + 3*x2 + x1*x2*x3", var_num),
         };
 
         // domain: (0,1,1)
@@ -323,6 +409,130 @@ mod test {
         assert_eq!(target, actual);
     }
 
+    #[test]
+    fn test_exponent_map_round_trip() {
+        let poly = MPolynomial {
+            var_num: 3,
+            coeffs: coeffs("5 + 2*x3 + 3*x2 + x1*x2*x3", 3),
+        };
+        let map = poly.to_exponent_map();
+        let expected = HashMap::from([
+            (vec![0, 0, 0], Scalar::from_u128(5)),
+            (vec![0, 0, 1], Scalar::from_u128(2)),
+            (vec![0, 1, 0], Scalar::from_u128(3)),
+            (vec![1, 1, 1], Scalar::one()),
+        ]);
+        assert_eq!(map, expected);
+        assert_eq!(MPolynomial::from_exponent_map(3, &map), Ok(poly));
+
+        for var_num in 0..=6 {
+            let poly = MPolynomial {
+                var_num,
+                coeffs: (0..1 << var_num).map(|_| Scalar::random(OsRng)).collect(),
+            };
+            let map = poly.to_exponent_map();
+            assert_eq!(map.len(), 1 << var_num);
+            assert_eq!(MPolynomial::from_exponent_map(var_num, &map), Ok(poly));
+        }
+
+        // the zero poly has no term.
+        let zero = MPolynomial {
+            var_num: 2,
+            coeffs: vec![Scalar::zero(); 4],
+        };
+        assert!(zero.to_exponent_map().is_empty());
+        assert_eq!(MPolynomial::from_exponent_map(2, &HashMap::new()), Ok(zero));
+    }
+
+    #[test]
+    fn test_exponent_map_rejects() {
+        // 17 + 2*x1*x2 + 42*x3 - 19*x1^2, x1^2 has no place in the dense form.
+        let map = HashMap::from([
+            (vec![0, 0, 0], Scalar::from_u128(17)),
+            (vec![1, 1, 0], Scalar::from_u128(2)),
+            (vec![0, 0, 1], Scalar::from_u128(42)),
+            (vec![2, 0, 0], Scalar::from_u128(19).neg()),
+        ]);
+        let err = MPolynomial::from_exponent_map(3, &map).unwrap_err();
+        assert_eq!(err, ConversionError::NotMultilinear { var: 0, exp: 2 });
+        assert_eq!(
+            err.to_string(),
+            "Variable 0 has the exponent 2, more than 1"
+        );
+
+        let map = HashMap::from([(vec![0, 1], Scalar::one())]);
+        assert_eq!(
+            MPolynomial::from_exponent_map(3, &map),
+            Err(ConversionError::WrongLength {
+                expected: 3,
+                found: 2
+            })
+        );
+        assert_eq!(
+            MPolynomial::from_exponent_map(MAX_VAR_NUM + 1, &HashMap::new()),
+            Err(ConversionError::TooManyVars {
+                var_num: MAX_VAR_NUM + 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_exponent_map_order() {
+        let terms = [
+            (vec![0, 0, 0, 1], Scalar::from_u128(3)),
+            (vec![1, 0, 1, 0], Scalar::from_u128(5)),
+            (vec![1, 1, 1, 1], Scalar::from_u128(7)),
+            (vec![0, 1, 0, 0], Scalar::from_u128(11)),
+        ];
+        let forward = terms.iter().cloned().collect::<HashMap<_, _>>();
+        let backward = terms.iter().rev().cloned().collect::<HashMap<_, _>>();
+        assert_eq!(
+            MPolynomial::from_exponent_map(4, &forward),
+            MPolynomial::from_exponent_map(4, &backward)
+        );
+    }
+
+    // The exponent map as a JSON object, eg: {"001": "2", "110": "3"}, the coeffs are small
+    // integers, and the keys are sorted so the export is stable.
+    fn to_json(map: &HashMap<Vec<u8>, u64>) -> String {
+        let mut entries = map
+            .iter()
+            .map(|(exps, c)| {
+                let key = exps.iter().map(|e| e.to_string()).collect::<String>();
+                format!("\"{}\": \"{}\"", key, c)
+            })
+            .collect::<Vec<_>>();
+        entries.sort();
+        format!("{{{}}}", entries.join(", "))
+    }
+
+    fn from_json(json: &str) -> HashMap<Vec<u8>, Scalar> {
+        let body = json.trim().trim_start_matches('{').trim_end_matches('}');
+        body.split(',')
+            .filter(|entry| !entry.trim().is_empty())
+            .map(|entry| {
+                let (key, value) = entry.split_once(':').unwrap();
+                let key = key.trim().trim_matches('"');
+                let value = value.trim().trim_matches('"');
+                let exps = key.bytes().map(|b| b - b'0').collect();
+                (exps, Scalar::from(value.parse::<u64>().unwrap()))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_exponent_map_json() {
+        // f(x1, x2, x3) = 17 + 2*x1*x2 + 42*x3
+        let exported =
+            HashMap::from([(vec![0, 0, 0], 17), (vec![1, 1, 0], 2), (vec![0, 0, 1], 42)]);
+        let json = to_json(&exported);
+        assert_eq!(json, r#"{"000": "17", "001": "42", "110": "2"}"#);
+
+        let poly = MPolynomial::from_exponent_map(3, &from_json(&json)).unwrap();
+        assert_eq!(poly.coeffs, coeffs("17 + 2*x1*x2 + 42*x3", 3));
+        assert_eq!(poly.to_exponent_map(), from_json(&json));
+    }
+
     #[test]
     fn test_domain() {
         // g(x1,...,xv) = x1*x2 + 4*x1 + 3*x2 + ... + xv
@@ -1,2 +1,3 @@
-mod MPolynomial;
+// `MPolynomial` and its conversion to and from the exponent-map form, see `from_exponent_map`.
+pub mod MPolynomial;
 mod utils;
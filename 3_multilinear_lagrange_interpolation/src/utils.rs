@@ -1,3 +1,4 @@
+use crate::MPolynomial::NotMultilinear;
 use bls12_381::Scalar;
 
 // convert a num into its binary form
@@ -37,8 +38,14 @@ fn expand_factor_for_upoly(lhs: Vec<Scalar>, rhs: Vec<Scalar>) -> Vec<Scalar> {
 // For now, we'll only support two factorizations to a coeffs.
 // eg: f(x1,x2) = (1+x1) * (1−x2) = 1 + x1 - x2 - x1x2
 //
-// NOTE!!! Only support var_num=2, which mul form such as: (a+bx1) * (c+dx2) = ac + bc*x1 + ad*x2 + bd*x1*x2
-pub fn expand_factor_for_mpoly(var_num: usize, lhs: Vec<Scalar>, rhs: Vec<Scalar>) -> Vec<Scalar> {
+// The index of a term is its exps in binary, so the product of two terms is indexed by n | m,
+// which is right only if they share no variable. Otherwise x_i * x_i = x_i^2 can't be encoded,
+// and the factors are rejected with the first shared variable, eg: (1+x1) * (1-x1).
+pub fn expand_factor_for_mpoly(
+    var_num: usize,
+    lhs: Vec<Scalar>,
+    rhs: Vec<Scalar>,
+) -> Result<Vec<Scalar>, NotMultilinear> {
     let target_len = 1 << var_num;
     assert_eq!(target_len, lhs.len());
     assert_eq!(target_len, rhs.len());
@@ -46,11 +53,23 @@ pub fn expand_factor_for_mpoly(var_num: usize, lhs: Vec<Scalar>, rhs: Vec<Scalar
     let mut product = vec![Scalar::zero(); target_len];
 
     for (n, l) in lhs.iter().enumerate() {
+        if l == &Scalar::zero() {
+            continue;
+        }
         for (m, r) in rhs.iter().enumerate() {
+            if r == &Scalar::zero() {
+                continue;
+            }
+            let shared = n & m;
+            if shared != 0 {
+                // x_1 is the MSB of the index.
+                let var = var_num - 1 - (usize::BITS - 1 - shared.leading_zeros()) as usize;
+                return Err(NotMultilinear { var });
+            }
             product[n | m] += l * r;
         }
     }
-    product
+    Ok(product)
 }
 
 #[cfg(test)]
@@ -58,9 +77,11 @@ mod test {
     use crate::utils::{
         convert_from_binary, convert_to_binary, expand_factor_for_mpoly, expand_factor_for_upoly,
     };
+    use crate::MPolynomial::NotMultilinear;
     use bls12_381::Scalar;
     use ff::PrimeField;
     use rayon::scope;
+    use sumcheck::mpoly;
 
     #[test]
     fn test_expand_factor_for_upoly() {
@@ -81,31 +102,63 @@ mod test {
         // NOTE!!! Only support var_num=2, which mul form such as: (a+bx1) * (c+dx2) = ac + bc*x1 + ad*x2 + bd*x1*x2
         let var_num: usize = 2;
 
+        let poly_one = mpoly!("1 + x1", vars = var_num).coeffs;
+        let poly_two = mpoly!("2 - x2", vars = var_num).coeffs;
+        let target = mpoly!("2 - x2 + 2*x1 - x1*x2", vars = var_num).coeffs;
+
         // f1 = (1+x1)
         //         terms: (0,0) = 1, (0,1)=0, (1,0)=1,  (1,1)=0
-        let poly_one = vec![Scalar::one(), Scalar::zero(), Scalar::one(), Scalar::zero()];
+        assert_eq!(
+            poly_one,
+            vec![Scalar::one(), Scalar::zero(), Scalar::one(), Scalar::zero()]
+        );
         // f2 = (2−x2)
         //         terms: (0,0) = 2, (0,1)=-1, (1,0)=0, (1,1)=0
-        let poly_two = vec![
-            Scalar::from_u128(2),
-            Scalar::one().neg(),
-            Scalar::zero(),
-            Scalar::zero(),
-        ];
+        assert_eq!(
+            poly_two,
+            vec![
+                Scalar::from_u128(2),
+                Scalar::one().neg(),
+                Scalar::zero(),
+                Scalar::zero(),
+            ]
+        );
         // product: F(x1,x2) = f1 * f2 = 2 - x2 + 2x1 - x1x2
         //         terms: (0,0) = 2, (0,1)=-1, (1,0)=2, (1,1)=-1
-        let target = vec![
-            Scalar::from_u128(2),
-            Scalar::one().neg(),
-            Scalar::from_u128(2),
-            Scalar::one().neg(),
-        ];
-
-        let actual = expand_factor_for_mpoly(var_num, poly_one, poly_two);
+        assert_eq!(
+            target,
+            vec![
+                Scalar::from_u128(2),
+                Scalar::one().neg(),
+                Scalar::from_u128(2),
+                Scalar::one().neg(),
+            ]
+        );
+
+        let actual = expand_factor_for_mpoly(var_num, poly_one, poly_two).unwrap();
         assert_eq!(target, actual);
         println!("{:?}", actual);
     }
 
+    #[test]
+    fn test_expand_factor_for_mpoly_overlapping() {
+        // (1+x1) * (2-x1) would need x1^2.
+        let poly_one = mpoly!("1 + x1", vars = 2).coeffs;
+        let poly_two = mpoly!("2 - x1", vars = 2).coeffs;
+        assert_eq!(
+            expand_factor_for_mpoly(2, poly_one, poly_two),
+            Err(NotMultilinear { var: 0 })
+        );
+
+        // x2 * (x1 * x2)
+        let poly_one = mpoly!("x2", vars = 2).coeffs;
+        let poly_two = mpoly!("x1*x2", vars = 2).coeffs;
+        assert_eq!(
+            expand_factor_for_mpoly(2, poly_one, poly_two),
+            Err(NotMultilinear { var: 1 })
+        );
+    }
+
     #[test]
     fn test_convert() {
         let raw: Vec<usize> = vec![1, 2, 3, 4, 8, 17];
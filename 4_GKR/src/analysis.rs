@@ -0,0 +1,89 @@
+// The measured cost of V against the one claimed by the book: O(d * log S) for the sum-checks of
+// d layers of S gates, plus the evaluations of add_i and mult_i, which are O(S_i * log S) here as
+// they iter the gates, and the reads of the inputs and outputs.
+use crate::arithmetic::layered_circuit::Ops::{ADD, MUL};
+use crate::arithmetic::layered_circuit::{CircuitConfig, Layer};
+use crate::gkr::{verify, verify_with_cost, DensityPolicy, GKR};
+use bls12_381::Scalar;
+use sumcheck::cost::{grows_like, CostReport};
+
+const DEPTH: usize = 4;
+const SIZES: [usize; 3] = [2, 4, 8];
+
+// d - 1 layers of 2^k gates on 2^k wires, gate j reads the wires j and j + 1.
+fn circuit(k: usize) -> CircuitConfig {
+    let n = 1 << k;
+    let layer = Layer {
+        gates: (0..n)
+            .map(|j| match j % 2 {
+                0 => ADD(j, (j + 1) % n),
+                _ => MUL(j, (j + 1) % n),
+            })
+            .collect(),
+        var_num: k,
+    };
+    let mut circuit = CircuitConfig {
+        layers: vec![layer; DEPTH - 1],
+        input_var_num: k,
+        depth: DEPTH,
+    };
+    circuit.canonicalize();
+    circuit
+}
+
+// p is W_i+1 on the line l only if u and v differ in one coordinate, see `diagnose`, so the
+// challenges are r_0 = (3, ..., 3), then u = (2, ..., 2), v = (4, 2, ..., 2) and t = 3 per layer.
+fn challenges(k: usize) -> impl FnMut() -> usize {
+    let mut n = 0;
+    move || {
+        let c = match n {
+            n if n < k => 3,
+            n => match (n - k) % (2 * k + 1) {
+                pos if pos == k => 4,
+                pos if pos == 2 * k => 3,
+                _ => 2,
+            },
+        };
+        n += 1;
+        c
+    }
+}
+
+#[test]
+fn test_gkr_cost() {
+    let mut sumchecks = vec![];
+    let mut wirings = vec![];
+    for k in SIZES {
+        let circuit = circuit(k);
+        let inputs = (1..=1u64 << k).map(Scalar::from).collect::<Vec<_>>();
+        let outputs = circuit.evaluate(&inputs);
+        let mut gkr = GKR::init_with_policy(circuit.clone(), DensityPolicy::ForceSparse).unwrap();
+        let proof = gkr.prove(&inputs, challenges(k));
+
+        let (res, cost) = verify_with_cost(&circuit, &inputs, &outputs, &proof);
+        assert_eq!(res, verify(&circuit, &inputs, &outputs, &proof));
+        assert!(res.is_ok(), "{:?}", res);
+        // the id of the circuit and the binding of the proof, the challenges are in the proof.
+        assert_eq!(cost.hash_calls, 3);
+
+        let wiring = circuit
+            .layers
+            .iter()
+            .map(|layer| layer.wiring_mle_cost(k))
+            .fold(CostReport::default(), |acc, c| acc + c);
+        // D(r_0) and W_d(r_d)
+        let io = CostReport::table_evaluate(k).times(2);
+        sumchecks.push(cost.field_mults - wiring.field_mults - io.field_mults);
+        wirings.push(wiring.field_mults);
+    }
+
+    let log_s = |k: usize| k as f64;
+    assert!(
+        grows_like(&SIZES, &sumchecks, log_s, 1.5),
+        "{:?}",
+        sumchecks
+    );
+    assert!(!grows_like(&SIZES, &sumchecks, |k| (1u64 << k) as f64, 1.5));
+    let s_log_s = |k: usize| ((1u64 << k) * k as u64) as f64;
+    assert!(grows_like(&SIZES, &wirings, s_log_s, 1.5), "{:?}", wirings);
+}
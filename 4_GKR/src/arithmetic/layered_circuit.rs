@@ -1,27 +1,276 @@
 use crate::arithmetic::layered_circuit::Ops::{ADD, MUL};
-use crate::poly::MPolynomial;
+use crate::poly::{DenseBudget, MPolynomial, ResourceLimit};
+use crate::utils::{eq_eval_at_index, par_map};
 use bls12_381::Scalar;
 use ff::Field;
+use sha3::{Digest, Keccak256};
 use std::collections::HashMap;
 use std::env::var;
+use std::fmt;
 use std::net::Shutdown::Read;
+use sumcheck::cost::CostReport;
+use sumcheck::scalar_utils::to_u64;
+use sumcheck::secret::Secret;
+use Fiat_Shamir::codec::{encode_u64, CodecError, Decoder};
+
+// The values of the wires in a layer, which are the witness of P, so they're wiped on drop
+// with the `zeroize` feature. Only the outputs leave as a plain Vec.
+pub type WireValues = Secret<Vec<Scalar>>;
 
 // Operators. for now, they are add and mul.
 // Left and right  input index from layer i+1.
-#[derive(Clone, Debug)]
+// The order is the canonical one of the gates: ADD before MUL, then by left, then by right.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Ops {
     ADD(usize, usize),
     MUL(usize, usize),
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Layer {
     pub gates: Vec<Ops>,
-    pub var_num: usize, // 2^var_num = gates.len()
+    pub var_num: usize, // k_i, gates.len() <= 2^var_num, the rest are padded with zero.
+}
+
+impl Layer {
+    // The wire values of the gates, from the ones of layer i+1, padded with zero to 2^var_num.
+    // The padded wires have no gate, so add_i and mult_i are zero there, as W_i is.
+    // The gates of a layer are independent, see `par_map`.
+    pub fn evaluate(&self, layer_i_plus_1: &[Scalar]) -> WireValues {
+        assert!(self.gates.len() <= 1 << self.var_num);
+
+        let mut wires = par_map(&self.gates, |gate| match gate {
+            ADD(left, right) => layer_i_plus_1[*left] + layer_i_plus_1[*right],
+            MUL(left, right) => layer_i_plus_1[*left] * layer_i_plus_1[*right],
+        });
+        wires.resize(1 << self.var_num, Scalar::zero());
+        WireValues::new(wires)
+    }
+
+    // The dense evaluations of (add_i, mult_i) over {0,1}^(k_i + 2*k_{i+1}), where the index of
+    // gate (j, left, right) is: (j << 2k_{i+1}) + (left << k_{i+1}) + right
+    pub fn wiring_evals(&self, var_num_i_plus_1: usize) -> (Vec<Scalar>, Vec<Scalar>) {
+        let n_i_plus_1 = 1 << var_num_i_plus_1;
+        let mpoly_size = 1 << (self.var_num + 2 * var_num_i_plus_1);
+        let mut addi_mpoly_eval = vec![Scalar::zero(); mpoly_size];
+        let mut multi_mpoly_eval = vec![Scalar::zero(); mpoly_size];
+
+        // iter each gate in layer_i
+        for (j, gate) in self.gates.iter().enumerate() {
+            match gate {
+                ADD(left, right) => {
+                    assert!(n_i_plus_1 > *left && n_i_plus_1 > *right);
+                    // turn binary index (0,(0,1),(1,1)) into vec array.
+                    let mpoly_index =
+                        (j << (2 * var_num_i_plus_1)) + (left << var_num_i_plus_1) + right;
+                    addi_mpoly_eval[mpoly_index] = Scalar::one();
+                }
+                MUL(left, right) => {
+                    assert!(n_i_plus_1 > *left && n_i_plus_1 > *right);
+                    let mpoly_index =
+                        (j << (2 * var_num_i_plus_1)) + (left << var_num_i_plus_1) + right;
+                    multi_mpoly_eval[mpoly_index] = Scalar::one();
+                }
+            };
+        }
+        (addi_mpoly_eval, multi_mpoly_eval)
+    }
+
+    // Same as `wiring_evals`, but with the gate label fixed at r ∈ F^k_i, so the dense
+    // evaluations are over {0,1}^(2*k_{i+1}) only, and the index of (left, right) is:
+    // (left << k_{i+1}) + right. eg: the gates of (j, left, right) add up eq(r, j) there.
+    pub fn wiring_evals_at(
+        &self,
+        r: &[Scalar],
+        var_num_i_plus_1: usize,
+    ) -> (Vec<Scalar>, Vec<Scalar>) {
+        assert_eq!(r.len(), self.var_num, "r must have k_i elements");
+        let n_i_plus_1 = 1 << var_num_i_plus_1;
+        let mut add_r_evals = vec![Scalar::zero(); 1 << (2 * var_num_i_plus_1)];
+        let mut mult_r_evals = vec![Scalar::zero(); 1 << (2 * var_num_i_plus_1)];
+
+        for (j, gate) in self.gates.iter().enumerate() {
+            let (evals, left, right) = match gate {
+                ADD(left, right) => (&mut add_r_evals, left, right),
+                MUL(left, right) => (&mut mult_r_evals, left, right),
+            };
+            assert!(n_i_plus_1 > *left && n_i_plus_1 > *right);
+            evals[(left << var_num_i_plus_1) + right] += eq_eval_at_index(r, j);
+        }
+        (add_r_evals, mult_r_evals)
+    }
+
+    // The sparse (add_i, mult_i) with the gate label fixed at r, see `SparseWiring`.
+    pub fn sparse_wiring_at(&self, r: &[Scalar], var_num_i_plus_1: usize) -> SparseWiring {
+        assert_eq!(r.len(), self.var_num, "r must have k_i elements");
+        let n_i_plus_1 = 1 << var_num_i_plus_1;
+        let terms = self
+            .gates
+            .iter()
+            .enumerate()
+            .map(|(j, gate)| {
+                let (left, right) = match gate {
+                    ADD(left, right) | MUL(left, right) => (left, right),
+                };
+                assert!(n_i_plus_1 > *left && n_i_plus_1 > *right);
+                (eq_eval_at_index(r, j), gate.clone())
+            })
+            .collect();
+        SparseWiring {
+            var_num_i_plus_1,
+            terms,
+        }
+    }
+
+    // add_i(r, b, c) = ∑_{add gates (j, left, right)} eq(r, j) * eq(b, left) * eq(c, right)
+    // It only iters the gates, which costs O(S_i * (k_i + 2*k_{i+1})) rather than the dense 2^(k_i + 2*k_{i+1}).
+    pub fn eval_add_mle(&self, r: &[Scalar], b: &[Scalar], c: &[Scalar]) -> Scalar {
+        self.eval_wiring_mle(r, b, c, |gate| matches!(gate, ADD(_, _)))
+    }
+
+    // mult_i(r, b, c) = ∑_{mult gates (j, left, right)} eq(r, j) * eq(b, left) * eq(c, right)
+    pub fn eval_mult_mle(&self, r: &[Scalar], b: &[Scalar], c: &[Scalar]) -> Scalar {
+        self.eval_wiring_mle(r, b, c, |gate| matches!(gate, MUL(_, _)))
+    }
+
+    // The ops of `eval_add_mle` and `eval_mult_mle` together, which visit each gate once:
+    // the 3 eq of the gate, their product and the sum.
+    pub fn wiring_mle_cost(&self, var_num_i_plus_1: usize) -> CostReport {
+        self.gates
+            .iter()
+            .enumerate()
+            .map(|(j, gate)| {
+                let (left, right) = match gate {
+                    ADD(left, right) | MUL(left, right) => (*left, *right),
+                };
+                CostReport::eq_at_index(self.var_num, j)
+                    + CostReport::eq_at_index(var_num_i_plus_1, left)
+                    + CostReport::eq_at_index(var_num_i_plus_1, right)
+                    + CostReport::mults(2)
+                    + CostReport::adds(1)
+            })
+            .fold(CostReport::default(), |acc, c| acc + c)
+    }
+
+    fn eval_wiring_mle(
+        &self,
+        r: &[Scalar],
+        b: &[Scalar],
+        c: &[Scalar],
+        is_selected: impl Fn(&Ops) -> bool,
+    ) -> Scalar {
+        assert_eq!(r.len(), self.var_num, "r must have k_i elements");
+        assert_eq!(b.len(), c.len(), "b and c must have k_i+1 elements");
+
+        self.gates
+            .iter()
+            .enumerate()
+            .filter(|(_, gate)| is_selected(gate))
+            .map(|(j, gate)| {
+                let (left, right) = match gate {
+                    ADD(left, right) | MUL(left, right) => (*left, *right),
+                };
+                eq_eval_at_index(r, j) * eq_eval_at_index(b, left) * eq_eval_at_index(c, right)
+            })
+            .fold(Scalar::zero(), |acc, v| acc + v)
+    }
+}
+
+// (add_i, mult_i) of layer i with the gate label fixed at r_i, kept as the gates only:
+//      add_i(r_i, b, c) = ∑_{add gates (j, left, right)} eq(r_i, j) * [b = left] * [c = right]
+// and the same for mult_i. So it's S_i terms rather than the 2^(2*k_{i+1}) dense evaluations of
+// `wiring_evals_at`, eg: for a layer too wide for the dense add_i and mult_i.
+#[derive(Clone, Debug)]
+pub struct SparseWiring {
+    pub var_num_i_plus_1: usize,
+    pub terms: Vec<(Scalar, Ops)>, // (eq(r_i, j), gate j) of each gate.
 }
 
+// The log-sizes (k_i, k_i+1) of layer i and the layer it reads, which change from layer to layer,
+// eg: k_i+1 > k_i for a fan-out layer. The sum-check of layer i binds (b, c) ∈ F^(2*k_i+1).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LayerDims {
+    pub k_i: usize,
+    pub k_i_plus_1: usize,
+}
+
+impl LayerDims {
+    // the rounds num of the sum-check of layer i.
+    pub fn v_r(&self) -> usize {
+        2 * self.k_i_plus_1
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CircuitError {
+    // depth - 1 != layers.len()
+    DepthMismatch {
+        depth: usize,
+        layers: usize,
+    },
+    // the gates don't fit in 2^var_num wires.
+    TooManyGates {
+        layer: usize,
+        gates: usize,
+        var_num: usize,
+    },
+    // the gate reads a wire beyond the ones of layer i+1.
+    WireOutOfRange {
+        layer: usize,
+        gate: usize,
+        wire: usize,
+        wires: usize,
+    },
+}
+
+impl fmt::Display for CircuitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CircuitError::DepthMismatch { depth, layers } => write!(
+                f,
+                "the circuit of depth {} has {} layers, expect {}",
+                depth,
+                layers,
+                depth.saturating_sub(1)
+            ),
+            CircuitError::TooManyGates {
+                layer,
+                gates,
+                var_num,
+            } => write!(
+                f,
+                "layer {} has {} gates, which don't fit in 2^{} wires",
+                layer, gates, var_num
+            ),
+            CircuitError::WireOutOfRange {
+                layer,
+                gate,
+                wire,
+                wires,
+            } => write!(
+                f,
+                "gate {} of layer {} reads wire {}, but layer {} has {} wires",
+                gate,
+                layer,
+                wire,
+                layer + 1,
+                wires
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CircuitError {}
+
+// The version of `CircuitConfig::to_bytes`.
+pub const CIRCUIT_VERSION: u8 = 1;
+
 // Configure Circuit Constraints. We assume circuit is layered one, whose gates have fan-in-2 and fan-out-1.
-#[derive(Clone, Default)]
+// Each layer has its own k_i, and it's padded by itself, see `Layer::evaluate` and `validate`.
+//
+// GKR proves canonical circuits only, see `canonicalize`: the same circuit built in another
+// order of the gates has the same bytes and digest, so the same `gkr::protocol_id` and proofs.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct CircuitConfig {
     pub layers: Vec<Layer>,   // from layer 0 to d-1.
     pub input_var_num: usize, // input_layer_len = 2^input_var_num. input is layer-d
@@ -34,34 +283,43 @@ impl CircuitConfig {
         let max_n = 1 << self.input_var_num;
         assert_eq!(inputs.len(), max_n);
 
-        // start with layer d(input layer)
-        let mut layer_i_plus_1 = inputs.clone();
+        // from layer d-1 to layer 0(output layer), the wires of layer d are the inputs.
+        let mut wires = WireValues::default();
+        for (i, layer_i) in self.layers.iter().enumerate().rev() {
+            let layer_i_plus_1 = if i == self.depth - 2 { inputs } else { &*wires };
+            wires = layer_i.evaluate(layer_i_plus_1);
+        }
 
-        // from layer d-1 to layer 0(output layer).
-        for i in (0..(self.depth - 1)).rev() {
-            let layer_i = self.layers.get(i).expect("Can't capture layer_i");
-            let gates_num = 1 << layer_i.var_num;
-            let gates = &layer_i.gates;
-            assert_eq!(gates.len(), gates_num);
+        // the outputs are public.
+        wires.to_vec()
+    }
 
-            let mut layer_i_outputs = vec![];
+    // Same as `evaluate`, but keeps the inputs and the output of each gate, eg: to find the
+    // first gate which differs from what's expected.
+    pub fn trace_evaluation(&self, inputs: &[Scalar]) -> EvaluationTrace {
+        assert_eq!(self.layers.len(), self.depth - 1);
+        assert_eq!(inputs.len(), 1 << self.input_var_num);
 
-            // iter each gate in layer_i
-            for gate in gates {
-                let gate_output = match gate {
-                    ADD(left, right) => layer_i_plus_1[*left] + layer_i_plus_1[*right],
-                    MUL(left, right) => layer_i_plus_1[*left] * layer_i_plus_1[*right],
+        let mut trace = EvaluationTrace::default();
+        let mut wires = inputs.to_vec();
+        for (i, layer_i) in self.layers.iter().enumerate().rev() {
+            let outputs = layer_i.evaluate(&wires);
+            for (j, (gate, output)) in layer_i.gates.iter().zip(outputs.iter()).enumerate() {
+                let (left, right) = match gate {
+                    ADD(left, right) | MUL(left, right) => (wires[*left], wires[*right]),
                 };
-                layer_i_outputs.push(gate_output);
+                trace.gates.push(GateTrace {
+                    layer: i,
+                    index: j,
+                    op: gate.clone(),
+                    left,
+                    right,
+                    output: *output,
+                });
             }
-            assert_eq!(layer_i_outputs.len(), gates_num);
-
-            // prepare for next iter.
-            layer_i_plus_1 = layer_i_outputs.clone();
+            wires = outputs.to_vec();
         }
-
-        // after iter, will calculate output.
-        layer_i_plus_1.clone()
+        trace
     }
 
     pub fn witness_to_poly(&self, inputs: &Vec<Scalar>) -> (Vec<MPolynomial>, Vec<Scalar>) {
@@ -69,47 +327,36 @@ impl CircuitConfig {
         let max_n = 1 << self.input_var_num;
         assert_eq!(inputs.len(), max_n);
 
-        // At start, the mpoly in result start from layer d to 0.
-        // However, we'll inverse it to adopt from layer 0 to d.
-        let mut result = vec![];
-
-        // start with layer d(input layer)
-        let input_mpoly = MPolynomial::lagrange(self.input_var_num, inputs);
-        result.push(input_mpoly);
-
-        let mut layer_i_plus_1 = inputs.clone();
-
-        // from layer d-1 to layer 0(output layer).
-        for i in (0..(self.depth - 1)).rev() {
-            let layer_i = self.layers.get(i).expect("Can't capture layer_i");
-            let gates_num = 1 << layer_i.var_num;
-            let gates = &layer_i.gates;
-            assert_eq!(gates.len(), gates_num);
-
-            let mut layer_i_outputs = vec![];
-
-            // iter each gate in layer_i
-            for gate in gates {
-                let gate_output = match gate {
-                    ADD(left, right) => layer_i_plus_1[*left] + layer_i_plus_1[*right],
-                    MUL(left, right) => layer_i_plus_1[*left] * layer_i_plus_1[*right],
-                };
-                layer_i_outputs.push(gate_output);
-            }
-            assert_eq!(layer_i_outputs.len(), gates_num);
-
-            let layer_i_mpoly = MPolynomial::lagrange(layer_i.var_num, &layer_i_outputs);
-            result.push(layer_i_mpoly);
-
-            // prepare for next iter.
-            // Last round -  layer_i_plus_1=output.
-            layer_i_plus_1 = layer_i_outputs.clone();
+        // from layer d-1 to layer 0(output layer), each one from the wires of the last.
+        let mut wires: Vec<WireValues> = Vec::with_capacity(self.layers.len());
+        for (i, layer_i) in self.layers.iter().enumerate().rev() {
+            let layer_i_plus_1 = if i == self.depth - 2 {
+                inputs
+            } else {
+                &**wires.last().unwrap()
+            };
+            let layer_i_outputs = layer_i.evaluate(layer_i_plus_1);
+            wires.push(layer_i_outputs);
         }
+        // from layer 0 to d.
+        wires.reverse();
+
+        // Then the W_i are independent of each other.
+        let tables = self
+            .layers
+            .iter()
+            .zip(wires.iter())
+            .map(|(layer_i, wires_i)| (layer_i.var_num, &**wires_i))
+            .chain([(self.input_var_num, inputs)])
+            .collect::<Vec<_>>();
+        // W_i is already of the size of its values, so it's out of the dense budget.
+        let result = par_map(&tables, |(var_num, evals)| {
+            MPolynomial::lagrange_unchecked(*var_num, evals)
+        });
 
         assert_eq!(result.len(), self.depth);
-        // after iter, will calculate output.
-        result.reverse();
-        (result, layer_i_plus_1.clone())
+        let outputs = wires.first().map_or(vec![], |wires_0| wires_0.to_vec());
+        (result, outputs)
     }
 
     //  A layered arithmetic circuit C with S gates, depth d, and fan-in two (C may have more than one output gate).
@@ -124,54 +371,288 @@ impl CircuitConfig {
     //      to 1 on the following two inputs: (0,(0,0),(0,1)) and (1,(1,0),(1,1)). On all other inputs,
     //      mult0 evaluates to zero.
     pub(crate) fn ops_to_mpoly(&self) -> Vec<(MPolynomial, MPolynomial)> {
-        // result ares vector of (addi_mpoly, multi_mpoly).
-        let mut result = Vec::with_capacity(self.depth - 1);
-
+        // result ares vector of (addi_mpoly, multi_mpoly), the layers are independent.
         // from layer 0(output layer) to layer d-1.
-        for i in 0..self.depth - 1 {
-            let layer_i = self.layers.get(i).expect("Can't capture layer_i");
-            let var_num_i = layer_i.var_num;
-            let var_num_i_plus_1 = if i == self.depth - 2 {
-                // layer d-1's input is input layer.
-                self.input_var_num
-            } else {
-                let layer_i_plus_1 = self
-                    .layers
-                    .get(i + 1)
-                    .expect("Can't capture layer_i_plus_1");
-                layer_i_plus_1.var_num
-            };
-            let n_i_plus_1 = 1 << var_num_i_plus_1;
-
-            let mpoly_var_num = var_num_i + 2 * var_num_i_plus_1;
-            let mpoly_size = 1 << mpoly_var_num;
-            let mut addi_mpoly_eval = vec![Scalar::zero(); mpoly_size];
-            let mut multi_mpoly_eval = vec![Scalar::zero(); mpoly_size];
+        let layers = (0..self.depth - 1).collect::<Vec<_>>();
+        par_map(&layers, |&i| {
+            self.layer_ops(i, &DenseBudget::default())
+                .unwrap_or_else(|e| panic!("layer {}: {}", i, e))
+        })
+    }
 
-            // iter each gate in layer_i
+    // Check the shape of each layer by itself: the gates fit in 2^k_i wires, and they read the
+    // wires of layer i+1 only, which are its gates, or the 2^input_var_num inputs.
+    pub fn validate(&self) -> Result<(), CircuitError> {
+        if self.depth != self.layers.len() + 1 {
+            return Err(CircuitError::DepthMismatch {
+                depth: self.depth,
+                layers: self.layers.len(),
+            });
+        }
+        for (i, layer_i) in self.layers.iter().enumerate() {
+            if layer_i.gates.len() > 1 << layer_i.var_num {
+                return Err(CircuitError::TooManyGates {
+                    layer: i,
+                    gates: layer_i.gates.len(),
+                    var_num: layer_i.var_num,
+                });
+            }
+            let wires = match self.layers.get(i + 1) {
+                Some(layer_i_plus_1) => layer_i_plus_1.gates.len(),
+                None => 1 << self.input_var_num,
+            };
             for (j, gate) in layer_i.gates.iter().enumerate() {
-                match gate {
-                    ADD(left, right) => {
-                        assert!(n_i_plus_1 > *left && n_i_plus_1 > *right);
-                        // turn binary index (0,(0,1),(1,1)) into vec array.
-                        let mpoly_index =
-                            (j << 2 * var_num_i_plus_1) + (left << var_num_i_plus_1) + right;
-                        addi_mpoly_eval[mpoly_index] = Scalar::one();
-                    }
-                    MUL(left, right) => {
-                        assert!(n_i_plus_1 > *left && n_i_plus_1 > *right);
-                        let mpoly_index =
-                            (j << 2 * var_num_i_plus_1) + (left << var_num_i_plus_1) + right;
-                        multi_mpoly_eval[mpoly_index] = Scalar::one();
+                let (left, right) = match gate {
+                    ADD(left, right) | MUL(left, right) => (*left, *right),
+                };
+                if let Some(wire) = [left, right].into_iter().find(|w| *w >= wires) {
+                    return Err(CircuitError::WireOutOfRange {
+                        layer: i,
+                        gate: j,
+                        wire,
+                        wires,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Sort the gates of each layer by (op, left, right), see `Ops`, from layer d-1 up to layer 0:
+    // the gates of layer i+1 are moved first, so the ones of layer i read them at their new index.
+    // The equal gates of a layer have the same value, so the reads of them go to the first one.
+    // The inputs are never moved. Panics if the circuit isn't valid, see `validate`.
+    //
+    // Return the permutation of the outputs, output j of the circuit before is output
+    // permutation[j] after, eg: to move the claims on the outputs.
+    pub fn canonicalize(&mut self) -> Vec<usize> {
+        if let Err(e) = self.validate() {
+            panic!("{}", e);
+        }
+        // the new index of each gate of layer i+1, none for the inputs.
+        let mut moved: Option<Vec<usize>> = None;
+        for layer_i in self.layers.iter_mut().rev() {
+            if let Some(moved) = moved.as_ref() {
+                for gate in layer_i.gates.iter_mut() {
+                    match gate {
+                        ADD(left, right) | MUL(left, right) => {
+                            *left = moved[*left];
+                            *right = moved[*right];
+                        }
                     }
+                }
+            }
+            // the sort is stable, so it's the same for the equal gates.
+            let mut order = (0..layer_i.gates.len()).collect::<Vec<_>>();
+            order.sort_by(|a, b| layer_i.gates[*a].cmp(&layer_i.gates[*b]));
+            let gates = order
+                .iter()
+                .map(|j| layer_i.gates[*j].clone())
+                .collect::<Vec<_>>();
+            let mut new_index = vec![0; gates.len()];
+            for (pos, j) in order.iter().enumerate() {
+                new_index[*j] = match pos {
+                    0 => 0,
+                    _ if gates[pos - 1] == gates[pos] => new_index[order[pos - 1]],
+                    _ => pos,
+                };
+            }
+            layer_i.gates = gates;
+            moved = Some(new_index);
+        }
+        moved.unwrap_or_default()
+    }
+
+    // The circuit is the same after `canonicalize`.
+    pub fn is_canonical(&self) -> bool {
+        let mut canonical = self.clone();
+        canonical.canonicalize();
+        canonical == *self
+    }
+
+    // Layout (version 1), in the encoding of `Fiat_Shamir::codec`:
+    //
+    //      | field         | encoding                                             |
+    //      |---------------|------------------------------------------------------|
+    //      | version       | u8 = 1                                               |
+    //      | input_var_num | u64                                                  |
+    //      | layers        | len: u64, then each layer from 0 to d-1              |
+    //      | layer         | var_num: u64, len(gates): u64, then each gate        |
+    //      | gate          | op: u8 (0: ADD, 1: MUL), left: u64, right: u64       |
+    //
+    // The gates are as they are, not padded to 2^var_num, and depth is len(layers) + 1.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![CIRCUIT_VERSION];
+        bytes.extend(encode_u64(self.input_var_num as u64));
+        bytes.extend(encode_u64(self.layers.len() as u64));
+        for layer_i in self.layers.iter() {
+            bytes.extend(encode_u64(layer_i.var_num as u64));
+            bytes.extend(encode_u64(layer_i.gates.len() as u64));
+            for gate in layer_i.gates.iter() {
+                let (op, left, right) = match gate {
+                    ADD(left, right) => (0, left, right),
+                    MUL(left, right) => (1, left, right),
                 };
+                bytes.push(op);
+                bytes.extend(encode_u64(*left as u64));
+                bytes.extend(encode_u64(*right as u64));
             }
+        }
+        bytes
+    }
 
-            let addi_mpoly = MPolynomial::lagrange(mpoly_var_num, &addi_mpoly_eval);
-            let multi_mpoly = MPolynomial::lagrange(mpoly_var_num, &multi_mpoly_eval);
-            result.push((addi_mpoly, multi_mpoly));
+    // The bytes are untrusted, so the shape is left to `validate`, and the order to `is_canonical`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        let mut decoder = Decoder::new(bytes);
+        decoder.read_version(CIRCUIT_VERSION)?;
+        let input_var_num = decoder.read_log_len()?;
+        // each layer takes 16 bytes at least, and each gate 17.
+        let layers = (0..decoder.read_len(16)?)
+            .map(|_| {
+                let var_num = decoder.read_log_len()?;
+                let gates = (0..decoder.read_len(17)?)
+                    .map(|_| {
+                        let op = decoder.read_tag(2)?;
+                        let (left, right) = (decoder.read_usize()?, decoder.read_usize()?);
+                        Ok(if op == 0 {
+                            ADD(left, right)
+                        } else {
+                            MUL(left, right)
+                        })
+                    })
+                    .collect::<Result<Vec<_>, CodecError>>()?;
+                Ok(Layer { gates, var_num })
+            })
+            .collect::<Result<Vec<_>, CodecError>>()?;
+        decoder.finish()?;
+        Ok(Self {
+            depth: layers.len() + 1,
+            layers,
+            input_var_num,
+        })
+    }
+
+    // H(to_bytes), where H is Keccak256, it's the params digest of `gkr::protocol_id`.
+    pub fn circuit_digest(&self) -> [u8; 32] {
+        Keccak256::digest(self.to_bytes()).into()
+    }
+
+    // k_i, the log-size of layer i, the layer d is the inputs.
+    pub fn layer_var_num(&self, i: usize) -> usize {
+        match self.layers.get(i) {
+            Some(layer_i) => layer_i.var_num,
+            None => {
+                assert_eq!(i, self.layers.len(), "no layer {}", i);
+                self.input_var_num
+            }
         }
-        result
+    }
+
+    // k_0, ..., k_d.
+    pub fn layer_var_nums(&self) -> Vec<usize> {
+        (0..=self.layers.len())
+            .map(|i| self.layer_var_num(i))
+            .collect()
+    }
+
+    // (k_i, k_i+1) of layer i.
+    pub fn layer_dims(&self, i: usize) -> LayerDims {
+        assert!(i < self.layers.len(), "no layer {}", i);
+        LayerDims {
+            k_i: self.layer_var_num(i),
+            k_i_plus_1: self.layer_var_num(i + 1),
+        }
+    }
+
+    // k_{i+1}, layer d-1's input is input layer.
+    pub fn var_num_i_plus_1(&self, i: usize) -> usize {
+        self.layer_dims(i).k_i_plus_1
+    }
+
+    // k_i + 2*k_{i+1}, the var_num of the dense add_i and mult_i.
+    pub fn wiring_var_num(&self, i: usize) -> usize {
+        let layer_i = self.layers.get(i).expect("Can't capture layer_i");
+        layer_i.var_num + 2 * self.var_num_i_plus_1(i)
+    }
+
+    // The dense (add_i, mult_i) of layer i, with 2^(k_i + 2*k_{i+1}) coeffs each. The budget is
+    // checked before the evals of the wiring, which are as large as the polys.
+    pub(crate) fn layer_ops(
+        &self,
+        i: usize,
+        budget: &DenseBudget,
+    ) -> Result<(MPolynomial, MPolynomial), ResourceLimit> {
+        let layer_i = self.layers.get(i).expect("Can't capture layer_i");
+        let mpoly_var_num = self.wiring_var_num(i);
+        budget.check::<Scalar>(mpoly_var_num)?;
+        let (addi_mpoly_eval, multi_mpoly_eval) = layer_i.wiring_evals(self.var_num_i_plus_1(i));
+
+        let addi_mpoly = MPolynomial::lagrange_unchecked(mpoly_var_num, &addi_mpoly_eval);
+        let multi_mpoly = MPolynomial::lagrange_unchecked(mpoly_var_num, &multi_mpoly_eval);
+        Ok((addi_mpoly, multi_mpoly))
+    }
+}
+
+// A gate evaluated with the values of its inputs, see `CircuitConfig::trace_evaluation`.
+#[derive(Clone, Debug)]
+pub struct GateTrace {
+    pub layer: usize,
+    pub index: usize,
+    pub op: Ops,
+    pub left: Scalar,
+    pub right: Scalar,
+    pub output: Scalar,
+}
+
+// The gates in the order of evaluation, from layer d-1 to layer 0(output layer).
+// Note: the wire values are not wrapped as `WireValues`, it's for debugging only.
+#[derive(Clone, Debug, Default)]
+pub struct EvaluationTrace {
+    pub gates: Vec<GateTrace>,
+}
+
+impl EvaluationTrace {
+    // The values of the output gates, the same as `CircuitConfig::evaluate`.
+    pub fn outputs(&self) -> Vec<Scalar> {
+        self.gates
+            .iter()
+            .filter(|gate| gate.layer == 0)
+            .map(|gate| gate.output)
+            .collect()
+    }
+}
+
+// The small values are in decimal, eg: 16 rather than 0x00..10, the others are in the Debug of Scalar.
+fn display_value(value: &Scalar) -> String {
+    match to_u64(value) {
+        Some(v) => v.to_string(),
+        None => format!("{:?}", value),
+    }
+}
+
+// eg: a row for each gate,
+//      layer | gate | op        |  left | right | output
+//          1 |    2 | MUL(1, 2) |     2 |     1 |      2
+impl fmt::Display for EvaluationTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>5} | {:>4} | {:<9} | {:>5} | {:>5} | {:>6}",
+            "layer", "gate", "op", "left", "right", "output"
+        )?;
+        for gate in self.gates.iter() {
+            writeln!(
+                f,
+                "{:>5} | {:>4} | {:<9} | {:>5} | {:>5} | {:>6}",
+                gate.layer,
+                gate.index,
+                format!("{:?}", gate.op),
+                display_value(&gate.left),
+                display_value(&gate.right),
+                display_value(&gate.output)
+            )?;
+        }
+        Ok(())
     }
 }
 
@@ -183,8 +664,11 @@ impl CircuitConfig {
 mod test {
     use super::*;
     use crate::arithmetic::layered_circuit::Ops::MUL;
-    use crate::utils::convert_from_binary;
+    use crate::arithmetic::layered_circuit::Ops::ADD;
+    use crate::utils::{convert_from_binary, convert_to_binary};
     use ff::PrimeField;
+    use rand_core::{OsRng, RngCore};
+    use std::collections::HashSet;
 
     // sample from Figure 4.12.
     fn simple_circuit() -> CircuitConfig {
@@ -225,6 +709,33 @@ mod test {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn test_trace_evaluation() {
+        let inputs = vec![
+            Scalar::one(),
+            Scalar::from_u128(2),
+            Scalar::one(),
+            Scalar::from_u128(4),
+        ];
+
+        let circuit = simple_circuit();
+        let trace = circuit.trace_evaluation(&inputs);
+        assert_eq!(trace.gates.len(), 6);
+        assert_eq!(trace.outputs(), circuit.evaluate(&inputs));
+
+        let expected = [
+            "layer | gate | op        |  left | right | output",
+            "    1 |    0 | MUL(0, 0) |     1 |     1 |      1",
+            "    1 |    1 | MUL(1, 1) |     2 |     2 |      4",
+            "    1 |    2 | MUL(1, 2) |     2 |     1 |      2",
+            "    1 |    3 | MUL(3, 3) |     4 |     4 |     16",
+            "    0 |    0 | MUL(0, 1) |     1 |     4 |      4",
+            "    0 |    1 | MUL(2, 3) |     2 |    16 |     32",
+            "",
+        ];
+        assert_eq!(trace.to_string(), expected.join("\n"));
+    }
+
     #[test]
     fn test_witness_to_poly() {
         let inputs = vec![
@@ -238,20 +749,21 @@ mod test {
         let (actual, _) = circuit.witness_to_poly(&inputs);
 
         // Expect
-        let input_mpoly = MPolynomial::lagrange(2, &inputs);
+        let input_mpoly = MPolynomial::lagrange(2, &inputs).unwrap();
         // layer_1 values: [1, 4, 2, 16]
         let layer_1_mpoly = MPolynomial::lagrange(
             2,
-            &vec![
+            &[
                 Scalar::one(),
                 Scalar::from_u128(4),
                 Scalar::from_u128(2),
                 Scalar::from_u128(16),
             ],
-        );
+        )
+        .unwrap();
         // layer_0 values: [4, 32]
         let layer_0_mpoly =
-            MPolynomial::lagrange(1, &vec![Scalar::from_u128(4), Scalar::from_u128(32)]);
+            MPolynomial::lagrange(1, &[Scalar::from_u128(4), Scalar::from_u128(32)]).unwrap();
 
         let expected = vec![layer_0_mpoly, layer_1_mpoly, input_mpoly];
         assert_eq!(expected, actual);
@@ -273,8 +785,8 @@ mod test {
         mult_1_evals[(3 << 4) + (3 << 2) + 3] = Scalar::one(); // (3, (3,3))
 
         let mpoly_1 = (
-            MPolynomial::lagrange(var_num_1, &add_1_evals),
-            MPolynomial::lagrange(var_num_1, &mult_1_evals),
+            MPolynomial::lagrange(var_num_1, &add_1_evals).unwrap(),
+            MPolynomial::lagrange(var_num_1, &mult_1_evals).unwrap(),
         );
         // test MPolynomial::lagrange.
         assert_eq!(mpoly_1.1.evaluate(&vec![0, 1, 0, 1, 0, 1]), Scalar::one());
@@ -290,8 +802,8 @@ mod test {
         mult_0_evals[1] = Scalar::one(); // (0, (0,1))
         mult_0_evals[(1 << 4) + (2 << 2) + 3] = Scalar::one(); // (1, (2,3))
         let mpoly_0 = (
-            MPolynomial::lagrange(var_num_0, &add_0_evals),
-            MPolynomial::lagrange(var_num_0, &mult_0_evals),
+            MPolynomial::lagrange(var_num_0, &add_0_evals).unwrap(),
+            MPolynomial::lagrange(var_num_0, &mult_0_evals).unwrap(),
         );
 
         let expected = vec![mpoly_0, mpoly_1];
@@ -314,4 +826,400 @@ mod test {
             (a << 4) + (b << 2) + c
         );
     }
+
+    // 1 input -> 4 -> 2 -> 1 output, so k = (0, 1, 2, 0), layer 2 fans the input out.
+    fn fan_out_circuit() -> CircuitConfig {
+        CircuitConfig {
+            layers: vec![
+                Layer {
+                    gates: vec![ADD(0, 1)],
+                    var_num: 0,
+                },
+                Layer {
+                    gates: vec![ADD(0, 1), MUL(2, 3)],
+                    var_num: 1,
+                },
+                Layer {
+                    gates: vec![ADD(0, 0), MUL(0, 0), ADD(0, 0), MUL(0, 0)],
+                    var_num: 2,
+                },
+            ],
+            input_var_num: 0,
+            depth: 4,
+        }
+    }
+
+    #[test]
+    fn test_layer_var_nums() {
+        let circuit = fan_out_circuit();
+        assert_eq!(circuit.validate(), Ok(()));
+        assert_eq!(circuit.layer_var_nums(), vec![0, 1, 2, 0]);
+        assert_eq!(
+            circuit.layer_dims(2),
+            LayerDims {
+                k_i: 2,
+                k_i_plus_1: 0
+            }
+        );
+        assert_eq!(circuit.layer_dims(2).v_r(), 0);
+        assert_eq!(circuit.layer_dims(0).v_r(), 2);
+        assert_eq!(circuit.wiring_var_num(1), 1 + 2 * 2);
+
+        // 3 -> (6, 9, 6, 9) -> (15, 54) -> 69
+        assert_eq!(
+            circuit.evaluate(&vec![Scalar::from(3)]),
+            vec![Scalar::from(69)]
+        );
+    }
+
+    // The layers are padded by themselves, eg: 3 gates take 2^2 wires.
+    #[test]
+    fn test_evaluate_pads_layer() {
+        let layer = Layer {
+            gates: vec![ADD(0, 1), MUL(0, 1), ADD(1, 1)],
+            var_num: 2,
+        };
+        let wires = layer.evaluate(&[Scalar::from(2), Scalar::from(5)]);
+        let expected = [7, 10, 10, 0].map(Scalar::from);
+        assert_eq!(&**wires, &expected[..]);
+
+        let circuit = CircuitConfig {
+            layers: vec![layer],
+            input_var_num: 1,
+            depth: 2,
+        };
+        assert_eq!(circuit.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut circuit = fan_out_circuit();
+        circuit.depth = 3;
+        assert_eq!(
+            circuit.validate(),
+            Err(CircuitError::DepthMismatch {
+                depth: 3,
+                layers: 3
+            })
+        );
+
+        let mut circuit = fan_out_circuit();
+        circuit.layers[1].gates.push(ADD(0, 0));
+        circuit.layers[1].gates.push(ADD(0, 0));
+        assert_eq!(
+            circuit.validate(),
+            Err(CircuitError::TooManyGates {
+                layer: 1,
+                gates: 4,
+                var_num: 1
+            })
+        );
+
+        // layer 2 has 4 gates, and the inputs are a single wire.
+        let mut circuit = fan_out_circuit();
+        circuit.layers[1].gates[1] = MUL(2, 4);
+        circuit.layers[2].gates[3] = MUL(0, 1);
+        assert_eq!(
+            circuit.validate(),
+            Err(CircuitError::WireOutOfRange {
+                layer: 1,
+                gate: 1,
+                wire: 4,
+                wires: 4
+            })
+        );
+        circuit.layers[1].gates[1] = MUL(2, 3);
+        let err = circuit.validate().unwrap_err();
+        assert_eq!(
+            err,
+            CircuitError::WireOutOfRange {
+                layer: 2,
+                gate: 3,
+                wire: 1,
+                wires: 1
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "gate 3 of layer 2 reads wire 1, but layer 3 has 1 wires"
+        );
+    }
+
+    // 6 gates with k_i = 3, k_i+1 = 2.
+    fn mixed_layer() -> Layer {
+        Layer {
+            gates: vec![ADD(0, 1), MUL(1, 2), ADD(3, 3), MUL(0, 0), MUL(2, 1), ADD(1, 3)],
+            var_num: 3,
+        }
+    }
+
+    fn to_scalars(x: &[usize]) -> Vec<Scalar> {
+        x.iter().map(|x| Scalar::from(*x as u64)).collect()
+    }
+
+    // check the sparse evaluation is the same as the dense mpoly at random points.
+    fn check_sparse_with_dense(layer: &Layer, var_num_i_plus_1: usize) {
+        let var_num = layer.var_num + 2 * var_num_i_plus_1;
+        let (add_evals, mult_evals) = layer.wiring_evals(var_num_i_plus_1);
+        let add = MPolynomial::lagrange(var_num, &add_evals).unwrap();
+        let mult = MPolynomial::lagrange(var_num, &mult_evals).unwrap();
+
+        for _ in 0..20 {
+            // the product in `MPolynomial::evaluate` moves to F once it overflows usize.
+            let point = (0..var_num)
+                .map(|_| OsRng.next_u64() as usize)
+                .collect::<Vec<_>>();
+            let (r, bc) = point.split_at(layer.var_num);
+            let (b, c) = bc.split_at(var_num_i_plus_1);
+            let (r, b, c) = (to_scalars(r), to_scalars(b), to_scalars(c));
+
+            assert_eq!(layer.eval_add_mle(&r, &b, &c), add.evaluate(&point));
+            assert_eq!(layer.eval_mult_mle(&r, &b, &c), mult.evaluate(&point));
+        }
+    }
+
+    #[test]
+    fn test_eval_wiring_mle() {
+        check_sparse_with_dense(&mixed_layer(), 2);
+
+        // the layers of Figure 4.12.
+        let circuit = simple_circuit();
+        check_sparse_with_dense(&circuit.layers[0], 2);
+        check_sparse_with_dense(&circuit.layers[1], 2);
+    }
+
+    #[test]
+    fn test_eval_wiring_mle_on_hypercube() {
+        // the endianness of (j, left, right) must be the same as the dense index.
+        let layer = mixed_layer();
+        let (add_evals, mult_evals) = layer.wiring_evals(2);
+        for index in 0..(1 << 7) {
+            let point = to_scalars(&convert_to_binary(&7, index));
+            let (r, bc) = point.split_at(3);
+            let (b, c) = bc.split_at(2);
+            assert_eq!(layer.eval_add_mle(r, b, c), add_evals[index]);
+            assert_eq!(layer.eval_mult_mle(r, b, c), mult_evals[index]);
+        }
+    }
+
+    #[test]
+    fn test_wiring_evals_at() {
+        let layer = mixed_layer();
+        let r = to_scalars(&[3, 5, 2]);
+        let (add_r, mult_r) = layer.wiring_evals_at(&r, 2);
+        for index in 0..(1 << 4) {
+            let bc = to_scalars(&convert_to_binary(&4, index));
+            let (b, c) = bc.split_at(2);
+            assert_eq!(layer.eval_add_mle(&r, b, c), add_r[index]);
+            assert_eq!(layer.eval_mult_mle(&r, b, c), mult_r[index]);
+        }
+
+        // the terms of the gates with the same (left, right) add up.
+        let mut add_sparse = vec![Scalar::zero(); 1 << 4];
+        let mut mult_sparse = vec![Scalar::zero(); 1 << 4];
+        for (eq_r, gate) in layer.sparse_wiring_at(&r, 2).terms {
+            match gate {
+                ADD(left, right) => add_sparse[(left << 2) + right] += eq_r,
+                MUL(left, right) => mult_sparse[(left << 2) + right] += eq_r,
+            }
+        }
+        assert_eq!((add_sparse, mult_sparse), (add_r, mult_r));
+    }
+
+    #[test]
+    fn test_eval_wiring_mle_single_type() {
+        let add_only = Layer {
+            gates: vec![ADD(0, 1), ADD(2, 3), ADD(1, 1), ADD(3, 0)],
+            var_num: 2,
+        };
+        check_sparse_with_dense(&add_only, 2);
+        let r = to_scalars(&[5, 7]);
+        let b = to_scalars(&[11, 13]);
+        let c = to_scalars(&[17, 19]);
+        assert_eq!(add_only.eval_mult_mle(&r, &b, &c), Scalar::zero());
+
+        let mult_only = simple_circuit().layers[1].clone();
+        check_sparse_with_dense(&mult_only, 2);
+        assert_eq!(mult_only.eval_add_mle(&r, &b, &c), Scalar::zero());
+    }
+
+    #[test]
+    fn test_eval_wiring_mle_large_layer() {
+        // k_i = 8, k_i+1 = 8, the dense add/mult needs 2^24 evaluations.
+        let (k, k_next) = (8, 8);
+        let n_next = 1 << k_next;
+        let gates = (0..(1 << k))
+            .map(|j| {
+                let (left, right) = (j % n_next, (j * 7 + 3) % n_next);
+                if j % 2 == 0 {
+                    ADD(left, right)
+                } else {
+                    MUL(left, right)
+                }
+            })
+            .collect::<Vec<_>>();
+        let layer = Layer { gates, var_num: k };
+
+        let r = to_scalars(&convert_to_binary(&k, 5));
+        let b = to_scalars(&convert_to_binary(&k_next, 5));
+        let c = to_scalars(&convert_to_binary(&k_next, 38));
+
+        // gate 5 = MUL(5, 38)
+        assert_eq!(layer.eval_mult_mle(&r, &b, &c), Scalar::one());
+        assert_eq!(layer.eval_add_mle(&r, &b, &c), Scalar::zero());
+    }
+
+    // The outputs of `test_canonicalize`, in another order of the gates.
+    fn unordered_circuit() -> CircuitConfig {
+        let layer_0 = Layer {
+            gates: vec![MUL(0, 1), ADD(2, 3), MUL(1, 2), ADD(0, 3)],
+            var_num: 2,
+        };
+        let layer_1 = Layer {
+            gates: vec![MUL(3, 3), MUL(1, 2), MUL(0, 0), MUL(1, 1)],
+            var_num: 2,
+        };
+        CircuitConfig {
+            layers: vec![layer_0, layer_1],
+            input_var_num: 2,
+            depth: 3,
+        }
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let inputs = [1, 2, 1, 4].map(Scalar::from).to_vec();
+        let circuit = unordered_circuit();
+        assert!(!circuit.is_canonical());
+
+        // layer 1 is moved as (0, 1, 2, 3) -> (3, 2, 0, 1), so layer 0 reads it at the new index.
+        let mut canonical = circuit.clone();
+        let permutation = canonical.canonicalize();
+        assert_eq!(permutation, vec![3, 0, 2, 1]);
+        assert_eq!(
+            canonical.layers[0].gates,
+            vec![ADD(0, 1), ADD(3, 1), MUL(2, 0), MUL(3, 2)]
+        );
+        assert!(canonical.is_canonical());
+        assert_eq!(canonical.clone().canonicalize(), vec![0, 1, 2, 3]);
+
+        // the claims on the outputs move with them.
+        let (outputs, canonical_outputs) = (circuit.evaluate(&inputs), canonical.evaluate(&inputs));
+        for (j, output) in outputs.iter().enumerate() {
+            assert_eq!(canonical_outputs[permutation[j]], *output);
+        }
+
+        // another order of the same gates gives the same bytes.
+        let mut other = circuit.clone();
+        other.layers[1].gates.reverse();
+        for gate in other.layers[0].gates.iter_mut() {
+            match gate {
+                ADD(left, right) | MUL(left, right) => {
+                    *left = 3 - *left;
+                    *right = 3 - *right;
+                }
+            }
+        }
+        other.layers[0].gates.swap(0, 3);
+        assert_eq!(other.evaluate(&inputs), [3, 1, 2, 0].map(|j| outputs[j]));
+        other.canonicalize();
+        assert_eq!(other.to_bytes(), canonical.to_bytes());
+        assert_eq!(other.circuit_digest(), canonical.circuit_digest());
+    }
+
+    // The reads of the equal gates go to the first one.
+    #[test]
+    fn test_canonicalize_equal_gates() {
+        let mut circuit = CircuitConfig {
+            layers: vec![
+                Layer {
+                    gates: vec![ADD(0, 2)],
+                    var_num: 0,
+                },
+                Layer {
+                    gates: vec![MUL(0, 1), ADD(0, 1), MUL(0, 1)],
+                    var_num: 2,
+                },
+            ],
+            input_var_num: 1,
+            depth: 3,
+        };
+        assert_eq!(circuit.canonicalize(), vec![0]);
+        assert_eq!(circuit.layers[0].gates, vec![ADD(1, 1)]);
+        assert_eq!(
+            circuit.layers[1].gates,
+            vec![ADD(0, 1), MUL(0, 1), MUL(0, 1)]
+        );
+    }
+
+    #[test]
+    fn test_circuit_digest() {
+        let circuit = simple_circuit();
+        assert!(circuit.is_canonical());
+        let mut digests = HashSet::from([circuit.circuit_digest()]);
+        for i in 0..circuit.layers.len() {
+            for j in 0..circuit.layers[i].gates.len() {
+                let mut other = circuit.clone();
+                let gate = &mut other.layers[i].gates[j];
+                *gate = match gate {
+                    ADD(left, right) => MUL(*left, *right),
+                    MUL(left, right) => ADD(*left, *right),
+                };
+                assert!(digests.insert(other.circuit_digest()));
+
+                let mut other = circuit.clone();
+                match &mut other.layers[i].gates[j] {
+                    ADD(_, right) | MUL(_, right) => *right ^= 1,
+                };
+                assert!(digests.insert(other.circuit_digest()));
+            }
+        }
+    }
+
+    // Layer 1 has 3 gates in 2^2 wires.
+    #[test]
+    fn test_circuit_bytes() {
+        let circuit = CircuitConfig {
+            layers: vec![
+                Layer {
+                    gates: vec![ADD(0, 2)],
+                    var_num: 0,
+                },
+                Layer {
+                    gates: vec![ADD(0, 1), MUL(0, 1), MUL(1, 1)],
+                    var_num: 2,
+                },
+            ],
+            input_var_num: 1,
+            depth: 3,
+        };
+        let bytes = circuit.to_bytes();
+        assert_eq!(bytes.len(), 1 + 8 + 8 + (8 + 8 + 17) + (8 + 8 + 3 * 17));
+        assert_eq!(CircuitConfig::from_bytes(&bytes), Ok(circuit));
+
+        let mut other = bytes.clone();
+        other[0] = 2;
+        assert_eq!(
+            CircuitConfig::from_bytes(&other),
+            Err(CodecError::UnknownVersion(2))
+        );
+        // the op of the gate of layer 0.
+        other = bytes.clone();
+        other[33] = 2;
+        assert_eq!(
+            CircuitConfig::from_bytes(&other),
+            Err(CodecError::UnknownTag { offset: 33, tag: 2 })
+        );
+        // the 3 gates of layer 1 don't fit in the bytes left.
+        assert_eq!(
+            CircuitConfig::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(CodecError::LengthTooLarge { offset: 58, len: 3 })
+        );
+        other = bytes.clone();
+        other.push(0);
+        assert_eq!(
+            CircuitConfig::from_bytes(&other),
+            Err(CodecError::TrailingBytes(1))
+        );
+    }
 }
@@ -1,36 +1,127 @@
 // Description of the GKR protocol,
 // when applied to a layered arithmetic circuit C of depth d and fan-in two on input x ∈ Fn.
 // Throughout, ki denotes log2(Si) where Si is the number of gates at layer i of C.
+//
+// With the `parallel` feature, the work inside a layer is spread on the rayon pool: the gates in
+// `Layer::evaluate`, the tables of W_i and (add_i, mult_i), and the hypercube sum of each round in
+// `LayerProver`. The rounds and the layers stay sequential, as each challenge depends on all the
+// messages before it, so the challenges are drawn on the calling thread only, in the same order.
+// Each parallel step collects in order or adds up field elements, so the proofs are the same with
+// and without the feature. In the forked mode the layers are proved in parallel too, each one on
+// its own sub-transcript, see `forked`.
 
-use crate::arithmetic::layered_circuit::CircuitConfig;
+#[cfg(feature = "prover")]
+use crate::arithmetic::layered_circuit::{CircuitConfig, Layer, LayerDims};
+#[cfg(feature = "prover")]
 use crate::gkr::prover::Prover;
+#[cfg(feature = "prover")]
 use crate::gkr::verifier::Verifier;
+#[cfg(feature = "prover")]
+use crate::gkr_sumcheck::prover::LayerProver;
+#[cfg(feature = "prover")]
 use crate::gkr_sumcheck::GkrSumCheck;
+#[cfg(feature = "prover")]
+use crate::poly::EvalTable;
+#[cfg(feature = "prover")]
 use bls12_381::Scalar;
+#[cfg(feature = "prover")]
+use ni_sumcheck::sumcheck::DegreeSchedule;
+#[cfg(feature = "prover")]
+use sumcheck::claim::{Claim, ClaimTracker, PolyId};
+#[cfg(feature = "prover")]
+use Fiat_Shamir::protocol::ProtocolId;
 
+mod diagnose;
+mod forked;
+mod output_claims;
+mod proof;
+#[cfg(feature = "prover")]
 mod prover;
+mod schedule;
+mod spot_check;
+mod state;
+// V of the interactive run, which draws the challenges from OsRng.
+#[cfg(feature = "prover")]
 mod verifier;
 
+#[cfg(any(test, feature = "cost"))]
+pub use diagnose::verify_with_cost;
+pub use diagnose::{diagnose, verify, verify_prefix, Detail, Diagnosis};
+#[cfg(feature = "prover")]
+pub use forked::ForkedRun;
+pub use forked::{verify_forked, ForkedGkrProof, LayerOpenings};
+pub use output_claims::{batch_output_claims, batch_tracked_claims};
+pub use proof::{
+    input_root, protocol_id, statement, GkrProof, LayerProof, ProofCompression, GKR_PROOF_VERSION,
+    GKR_PROOF_VERSION_FORKED, GKR_PROOF_VERSION_MINIMAL, INPUTS_LABEL,
+};
+#[cfg(feature = "prover")]
+pub use prover::{DensityPolicy, ProverError, MAX_DENSE_VARS};
+pub use schedule::{layer_schedule, protocol_id_for, protocol_id_with, FactorOrder, ProvingMode};
+pub use spot_check::{
+    spot_check, verify_positions, CommittedTable, Digest, SpotCheck, SpotCheckRequest, TableOpening,
+};
+pub use state::{derive_challenges_only, GkrVerifierState, LayerClaim, VerifiedGkr};
+
+#[cfg(feature = "prover")]
 pub struct GKR {
     prover: Prover,
     verifier: Verifier,
-    d: usize,
     input_var_num: usize,
+    layers: Vec<Layer>,   // the circuit is known by verifier.
+    dims: Vec<LayerDims>, // (k_i, k_i+1) of each layer.
+    protocol: ProtocolId, // of the circuit and the order, in the header of the proofs.
+    order: FactorOrder,   // of the protocol above, see `with_factor_order`.
+    // the degree of g_j in each round of each layer, by the factor order, see `schedule`.
+    schedules: Vec<DegreeSchedule>,
 }
 
+#[cfg(feature = "prover")]
 impl GKR {
-    // Init with layer-circuit
+    // Init with layer-circuit, which must be canonical, see `CircuitConfig::canonicalize`.
     pub fn init(config: CircuitConfig) -> Self {
-        let d = config.depth;
+        // the default policy never builds a dense layer above MAX_DENSE_VARS, so it's the shape.
+        Self::init_with_policy(config, DensityPolicy::default()).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    // Same as `init`, with add_i and mult_i held by P as the policy says, see `DensityPolicy`.
+    pub fn init_with_policy(
+        config: CircuitConfig,
+        policy: DensityPolicy,
+    ) -> Result<Self, ProverError> {
         let input_var_num = config.input_var_num;
-        let mut prover = Prover::init(config);
+        let protocol = protocol_id(&config);
+        let layers = config.layers.clone();
+        let dims = (0..layers.len())
+            .map(|i| config.layer_dims(i))
+            .collect::<Vec<_>>();
+        let schedules = (0..layers.len())
+            .map(|i| layer_schedule(&config, i, FactorOrder::Joint))
+            .collect();
+        let prover = Prover::init_with_policy(config, policy)?;
 
-        Self {
+        Ok(Self {
             prover,
             verifier: Verifier::default(),
-            d,
             input_var_num,
-        }
+            layers,
+            dims,
+            protocol,
+            order: FactorOrder::Joint,
+            schedules,
+        })
+    }
+
+    // Prove with the round polys of the order, see `schedule`. The proofs are of its protocol,
+    // which V reads the order from.
+    pub fn with_factor_order(mut self, order: FactorOrder) -> Self {
+        let config = self.prover.config();
+        self.protocol = protocol_id_for(config, order);
+        self.order = order;
+        self.schedules = (0..self.layers.len())
+            .map(|i| layer_schedule(config, i, order))
+            .collect();
+        self
     }
 
     // $f_{r_i}^{i}(b,c):=\widetilde{add_{i}}(r_{i},b,c)(\widetilde{W_{i+1}}(b)+\widetilde{W_{i+1}}(c))+\widetilde{mult_i}(r_i,b,c)(\widetilde{W_{i+1}}(b)\cdot \widetilde{W_{i+1}}(c))$
@@ -38,49 +129,230 @@ impl GKR {
         // 1. Prepare at the start of the protocol,
         //    The remainder of the protocol is devoted to confirming that $m0 =\widetilde{W^0}(r0)$ .
         //    <==> check $m0 = \sum_{b,c\in{0,1}^{i+1}} f_{r_i}(b,c)$
-        self.prover.synthesize(inputs);
+        self.prover
+            .synthesize(inputs)
+            .unwrap_or_else(|e| panic!("{}", e));
+        let mut tracker = ClaimTracker::new();
+        let wires = self.register_wires(&mut tracker);
         //  1.1 P sends a function $D: {0,1}^k_0 → F$ claimed to equal W_0 (the function mapping output gate labels to output values).
         let D_poly = self.prover.D_poly();
         //  1.2 V pick a challenge r_0( $r0∈Fk0$ ) and let $m_{0}=\widetilde{D}(r_0)$
         let (r_0, m_0) = self.verifier.init(D_poly, inputs, self.input_var_num);
+        tracker.push(Claim::new(wires[0], to_scalars(&r_0), m_0));
 
         // 2. start the d rounds gkr_sumcheck
-        //    check $m_i = \sum_{b,c\in{0,1}^{i+1}} f_{r_i}(b,c)$
-        let mut r_i = r_0;
-        let mut m_i = m_0;
-        for i in 0..self.d {
-            // the ops and witness used in current layer.
-            let (add_i, mult_i) = self.prover.ops.get(i).unwrap();
-            let w_i_plus_1 = self.prover.witness.get(i + 1).unwrap();
-            let g = (add_i.clone(), mult_i.clone(), w_i_plus_1.clone());
+        self.run_layers(&mut tracker, &wires, r_0);
 
-            let mut sumcheck = GkrSumCheck::init(g, r_i.clone(), m_i.clone());
+        // 3. V checks directly that md = W_d(rd ) using Lemma 3.8.
+        self.verifier.check(&mut tracker, wires[self.layers.len()]);
+        tracker.assert_all_discharged();
 
-            // we support the sumcheck prover is from GKR::prover! So does verifier.
-            let (r_i_plus_1, m_i_plus_1) = sumcheck.run_protocol();
+        // print the output
+        let outputs = self.prover.outputs();
+        println!("Output: {:?}", outputs);
+    }
+
+    // Same as `run_protocol`, but V only holds the commitment of the inputs,
+    // and P opens W_d at r_d for the final check, see `ni_sumcheck::mlpc`.
+    fn run_protocol_with_commitment(&mut self, inputs: &Vec<Scalar>) {
+        self.prover
+            .synthesize(inputs)
+            .unwrap_or_else(|e| panic!("{}", e));
+        let root = self.prover.commit_inputs();
+        let mut tracker = ClaimTracker::new();
+        let wires = self.register_wires(&mut tracker);
+
+        let d_poly = self.prover.D_poly();
+        let (r_0, m_0) = self.verifier.init_with_commitment(d_poly, root);
+        tracker.push(Claim::new(wires[0], to_scalars(&r_0), m_0));
+
+        self.run_layers(&mut tracker, &wires, r_0);
+
+        // V checks m_d = W_d(r_d) with the opening.
+        let w_d = wires[self.layers.len()];
+        let r_d = tracker.remaining()[0].point.clone();
+        let opening = self.prover.open_inputs(&r_d);
+        self.verifier
+            .check_with_commitment(&mut tracker, w_d, &opening);
+        tracker.assert_all_discharged();
 
+        let outputs = self.prover.outputs();
+        println!("Output: {:?}", outputs);
+    }
+
+    // W_0, ..., W_d, the polys of the claims of the layers.
+    fn register_wires(&self, tracker: &mut ClaimTracker) -> Vec<PolyId> {
+        (0..=self.layers.len())
+            .map(|i| tracker.register(&format!("W_{}", i)))
+            .collect()
+    }
+
+    // Run P with the challenges drawn from `challenge`, in the order V sends them: r_0, then
+    // r_1, ..., r_v and t for each layer. The messages are kept as a proof, see `diagnose`.
+    // `challenge` is only called from this thread, between the rounds.
+    pub fn prove(&mut self, inputs: &Vec<Scalar>, challenge: impl FnMut() -> usize) -> GkrProof {
+        self.try_prove(inputs, challenge)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    // Same as `prove`, but the inputs which don't match the circuit are an error,
+    // eg: `ProverError::TableSizeMismatch`.
+    pub fn try_prove(
+        &mut self,
+        inputs: &Vec<Scalar>,
+        mut challenge: impl FnMut() -> usize,
+    ) -> Result<GkrProof, ProverError> {
+        self.prover.synthesize(inputs)?;
+        let r_0 = (0..self.layers[0].var_num)
+            .map(|_| challenge())
+            .collect::<Vec<_>>();
+
+        let mut r_i = to_scalars(&r_0);
+        let mut m_i = self.prover.witness[0].evaluate(&r_0);
+        let mut layers = Vec::with_capacity(self.layers.len());
+        for i in 0..self.layers.len() {
+            let prover = self.layer_prover(i, &r_i, m_i);
+            let (layer_proof, r_i_plus_1, m_i_plus_1) = Self::prove_layer(prover, &mut challenge);
             r_i = r_i_plus_1;
             m_i = m_i_plus_1;
+            layers.push(layer_proof);
         }
 
-        // 3. V checks directly that md = W_d(rd ) using Lemma 3.8.
-        let r_d = r_i;
-        let m_d = m_i;
-        self.verifier.check(&r_d, m_d);
+        Ok(GkrProof {
+            protocol: self.protocol.digest(),
+            r_0,
+            layers,
+        })
+    }
 
-        // print the output
-        let outputs = self.prover.outputs();
-        println!("Output: {:?}", outputs);
+    // Same as `prove`, but from the claim on W_0(r_0) for a point r_0 ∈ F^k_0 given by the caller,
+    // eg: the one of `batch_output_claims`, so the proof has no r_0.
+    pub fn prove_from_claim(
+        &mut self,
+        inputs: &Vec<Scalar>,
+        r_0: &[Scalar],
+        mut challenge: impl FnMut() -> usize,
+    ) -> GkrProof {
+        self.prover
+            .synthesize(inputs)
+            .unwrap_or_else(|e| panic!("{}", e));
+        let m_0 = EvalTable::new(self.prover.outputs()).evaluate(r_0);
+
+        let prover = self.layer_prover(0, r_0, m_0);
+        let (layer_proof, mut r_i, mut m_i) = Self::prove_layer(prover, &mut challenge);
+
+        let mut layers = Vec::with_capacity(self.layers.len());
+        layers.push(layer_proof);
+        for i in 1..self.layers.len() {
+            let prover = self.layer_prover(i, &r_i, m_i);
+            let (layer_proof, r_i_plus_1, m_i_plus_1) = Self::prove_layer(prover, &mut challenge);
+            r_i = r_i_plus_1;
+            m_i = m_i_plus_1;
+            layers.push(layer_proof);
+        }
+
+        GkrProof {
+            protocol: self.protocol.digest(),
+            r_0: vec![],
+            layers,
+        }
+    }
+
+    // Commit to the table of W_i of the last proof, eg: to open it for `spot_check`.
+    pub fn commit_wires(&self, i: usize) -> (Digest, CommittedTable) {
+        let w_i = self.prover.witness.get(i).expect("no witness, prove first");
+        CommittedTable::commit(&EvalTable::new(w_i.hypercube_evaluations()))
+    }
+
+    // P of layer i from the claim m_i on W_i(r_i), with the schedule of the layer.
+    fn layer_prover(&self, i: usize, r_i: &[Scalar], m_i: Scalar) -> LayerProver {
+        self.prover
+            .layer_prover(i, r_i, m_i)
+            .with_schedule(self.schedules[i].clone())
+    }
+
+    // The sumcheck of a layer from the claim of `prover`, return its proof and (r_i+1, m_i+1).
+    fn prove_layer(
+        mut prover: LayerProver,
+        challenge: &mut impl FnMut() -> usize,
+    ) -> (LayerProof, Vec<Scalar>, Scalar) {
+        let v_r = prover.v_r();
+        let k_i_plus_1 = prover.dims().k_i_plus_1;
+
+        let claimed_sum = prover.current_claim();
+        let mut round_polys = Vec::with_capacity(v_r);
+        let mut challenges: Vec<usize> = Vec::with_capacity(v_r);
+        for _ in 0..v_r {
+            round_polys.push(prover.next_round(challenges.last().copied()));
+            challenges.push(challenge());
+        }
+        if let Some(r_v) = challenges.last() {
+            prover.bind_final(*r_v);
+        }
+        let (l_polys, p_poly) = prover.evaluate();
+
+        // r_i+1 = l(t) and m_i+1 = p(t), as V does in `GkrSumCheck::run_protocol`.
+        let t = challenge();
+        let r_i_plus_1 = l_polys
+            .iter()
+            .map(|l_i| l_i.evaluate(Scalar::from(t as u64)))
+            .collect::<Vec<_>>();
+        assert_eq!(r_i_plus_1.len(), k_i_plus_1);
+        let m_i_plus_1 = p_poly.evaluate(Scalar::from(t as u64));
+        let layer_proof = LayerProof {
+            var_num: v_r,
+            claimed_sum,
+            degrees: LayerProof::degrees_of(&round_polys),
+            round_polys,
+            challenges,
+            p_poly,
+            t,
+        };
+        (layer_proof, r_i_plus_1, m_i_plus_1)
+    }
+
+    // check $m_i = \sum_{b,c\in{0,1}^{i+1}} f_{r_i}(b,c)$ for each layer: the claim on W_i is
+    // popped from the tracker and the one on W_i+1 is pushed, so the one on W_d is left.
+    // r_i is kept next to its claim, r_0 is the challenges of the interactive run.
+    fn run_layers(&mut self, tracker: &mut ClaimTracker, wires: &[PolyId], r_0: Vec<usize>) {
+        let mut r_i = to_scalars(&r_0);
+        for i in 0..self.layers.len() {
+            let m_i = match tracker.pop(wires[i]) {
+                Some(claim) => claim.value,
+                None => panic!("GKR: no claim on {}", tracker.describe(wires[i])),
+            };
+            // the ops and witness used in current layer, dense or sparse by the policy.
+            // the layer prover wraps its own copy of W_i+1, see `LayerProver::new`.
+            let prover = self.layer_prover(i, &r_i, m_i);
+
+            let layer_i = self.layers.get(i).unwrap().clone();
+            let mut sumcheck =
+                GkrSumCheck::with_prover(prover, layer_i, self.dims[i], r_i.clone(), m_i);
+
+            // we support the sumcheck prover is from GKR::prover! So does verifier.
+            let (r_i_plus_1, m_i_plus_1) = sumcheck.run_protocol();
+
+            tracker.push(Claim::new(wires[i + 1], r_i_plus_1.clone(), m_i_plus_1));
+            r_i = r_i_plus_1;
+        }
     }
 }
 
-#[cfg(test)]
+#[cfg(feature = "prover")]
+fn to_scalars(r: &[usize]) -> Vec<Scalar> {
+    r.iter().map(|r_i| Scalar::from(*r_i as u64)).collect()
+}
+
+#[cfg(all(test, feature = "prover"))]
 mod test {
     use super::*;
     use crate::arithmetic::layered_circuit::Layer;
     use crate::arithmetic::layered_circuit::Ops::MUL;
+    use crate::poly::Polynomial;
     use crate::utils::convert_from_binary;
     use ff::PrimeField;
+    use Fiat_Shamir::default::Keccak256Transcript;
+    use Fiat_Shamir::{challenge_to_index, Transcript};
 
     // sample from Figure 4.12.
     fn simple_circuit() -> CircuitConfig {
@@ -106,7 +378,6 @@ mod test {
 
     #[test]
     fn test_GKR() {
-        // todo-bugfix later.
         let inputs = vec![
             Scalar::one(),
             Scalar::from_u128(2),
@@ -119,4 +390,494 @@ mod test {
         let mut gkr = GKR::init(config);
         gkr.run_protocol(&inputs);
     }
+
+    // The challenges are 1, 2, 3, ..., see `diagnose`.
+    fn simple_proof() -> GkrProof {
+        let inputs = vec![
+            Scalar::one(),
+            Scalar::from_u128(2),
+            Scalar::one(),
+            Scalar::from_u128(4),
+        ];
+        let mut gkr = GKR::init(simple_circuit());
+        let mut last = 0;
+        gkr.prove(&inputs, || {
+            last += 1;
+            last
+        })
+    }
+
+    fn from_i64(x: i64) -> Scalar {
+        let abs = Scalar::from(x.unsigned_abs());
+        if x < 0 {
+            -abs
+        } else {
+            abs
+        }
+    }
+
+    // (g_j(0), g_j(1), g_j(2)) of each round and (W_i+1(u), W_i+1(v)) of each layer in
+    // `simple_proof`. It's the same with and without the `parallel` feature, so the proofs are too:
+    //      cargo test -p GKR && cargo test -p GKR --features parallel
+    const PROOF_SNAPSHOT: [([[i64; 3]; 4], [i64; 2]); 2] = [
+        (
+            [
+                [0, 32, 96],
+                [96, 0, -1696],
+                [0, -4992, -17472],
+                [0, -64896, -247104],
+            ],
+            [78, 240],
+        ),
+        (
+            [
+                [-990, 3360, 13414],
+                [-1092, 150336, 616404],
+                [3190044, 6830208, 11631972],
+                [2845920, 74923200, 181209600],
+            ],
+            [121, 191],
+        ),
+    ];
+
+    #[test]
+    fn test_proof_snapshot() {
+        let proof = simple_proof();
+        assert_eq!(proof, simple_proof());
+        assert_eq!(proof.r_0, vec![1]);
+        assert_eq!(proof.layers.len(), PROOF_SNAPSHOT.len());
+        for ((layer, t), (round_evals, p_evals)) in
+            proof.layers.iter().zip([6, 11]).zip(PROOF_SNAPSHOT)
+        {
+            assert_eq!(layer.round_polys.len(), round_evals.len());
+            for (g_j, evals) in layer.round_polys.iter().zip(round_evals) {
+                for (x, eval) in evals.into_iter().enumerate() {
+                    assert_eq!(g_j.evaluate(Scalar::from(x as u64)), from_i64(eval));
+                }
+            }
+            assert_eq!(layer.p_poly.evaluate(Scalar::zero()), from_i64(p_evals[0]));
+            assert_eq!(layer.p_poly.evaluate(Scalar::one()), from_i64(p_evals[1]));
+            assert_eq!(layer.t, t);
+        }
+    }
+
+    // Run with `--features parallel` and without it to compare, eg:
+    //      cargo test -p GKR --release --features parallel -- --ignored bench_prove
+    // The prover holds the dense add_i and mult_i with 2^(k_i + 2k_i+1) coeffs, so the layers
+    // are of 2^4 gates here, 2^12 gates would need 2^36 coeffs per layer.
+    #[test]
+    #[ignore]
+    fn bench_prove() {
+        use crate::arithmetic::layered_circuit::Ops::ADD;
+        use std::time::Instant;
+
+        let k = 4;
+        let n = 1 << k;
+        let layer = |shift: usize| Layer {
+            gates: (0..n)
+                .map(|j| {
+                    let (left, right) = (j, (j + shift) % n);
+                    if j % 2 == 0 {
+                        ADD(left, right)
+                    } else {
+                        MUL(left, right)
+                    }
+                })
+                .collect(),
+            var_num: k,
+        };
+        let mut config = CircuitConfig {
+            layers: vec![layer(1), layer(3), layer(5)],
+            input_var_num: k,
+            depth: 4,
+        };
+        config.canonicalize();
+        let inputs = (1..=n as u64).map(Scalar::from).collect::<Vec<_>>();
+
+        // the challenges are all 1, so the points of `MPolynomial::evaluate` stay in usize.
+        let mut gkr = GKR::init(config);
+        let start = Instant::now();
+        let proof = gkr.prove(&inputs, || 1);
+        println!(
+            "prove {} layers of {} gates: {:?}, parallel: {}",
+            proof.layers.len(),
+            n,
+            start.elapsed(),
+            cfg!(feature = "parallel")
+        );
+    }
+
+    // A layer of 2^k gates on 2^input_var_num inputs, mixing ADD and MUL.
+    fn single_layer_circuit(k: usize, input_var_num: usize) -> CircuitConfig {
+        use crate::arithmetic::layered_circuit::Ops::ADD;
+
+        let n = 1 << input_var_num;
+        let layer = Layer {
+            gates: (0..1 << k)
+                .map(|j| {
+                    let (left, right) = (j % n, (3 * j + 1) % n);
+                    if j % 3 == 0 {
+                        ADD(left, right)
+                    } else {
+                        MUL(left, right)
+                    }
+                })
+                .collect(),
+            var_num: k,
+        };
+        let mut circuit = CircuitConfig {
+            layers: vec![layer],
+            input_var_num,
+            depth: 2,
+        };
+        circuit.canonicalize();
+        circuit
+    }
+
+    // 2^6 gates on 4 inputs, so add_0 and mult_0 are over 6 + 2*2 variables.
+    #[test]
+    fn test_density_policy_same_proof() {
+        let inputs = (1..=4u64).map(Scalar::from).collect::<Vec<_>>();
+        let r_0 = (3..9u64).map(Scalar::from).collect::<Vec<_>>();
+        let proofs = [DensityPolicy::ForceDense, DensityPolicy::ForceSparse].map(|policy| {
+            let mut gkr = GKR::init_with_policy(single_layer_circuit(6, 2), policy).unwrap();
+            assert_eq!(
+                gkr.prover.ops[0].is_some(),
+                policy == DensityPolicy::ForceDense
+            );
+            let mut last = 0;
+            let proof = gkr.prove(&inputs, || {
+                last += 1;
+                last
+            });
+            let mut last = 0;
+            let proof_from_claim = gkr.prove_from_claim(&inputs, &r_0, || {
+                last += 1;
+                last
+            });
+            (proof.to_bytes(), proof_from_claim.to_bytes())
+        });
+        assert_eq!(proofs[0], proofs[1]);
+    }
+
+    // 2^10 gates on 2^10 inputs, the dense add_0 and mult_0 would be over 30 variables.
+    #[test]
+    fn test_density_policy_wide_layer() {
+        let circuit = single_layer_circuit(10, 10);
+        assert_eq!(circuit.wiring_var_num(0), 30);
+        assert_eq!(
+            GKR::init_with_policy(circuit.clone(), DensityPolicy::ForceDense).err(),
+            Some(ProverError::ResourceLimit {
+                layer: 0,
+                var_num: 30,
+                bytes: (2 * 32) << 30,
+            })
+        );
+
+        let mut gkr = GKR::init(circuit.clone());
+        assert!(gkr.prover.ops[0].is_none());
+
+        // p is linear, so u and v differ in one coordinate, see `diagnose`.
+        let inputs = (1..=1 << 10).map(Scalar::from).collect::<Vec<_>>();
+        let mut challenges = (1..=10).chain([2; 19]).chain([3, 5]);
+        let proof = gkr.prove(&inputs, || challenges.next().unwrap());
+        let outputs = circuit.evaluate(&inputs);
+        assert_eq!(
+            diagnose(&circuit, &inputs, &outputs, &proof),
+            Diagnosis::LooksConsistent
+        );
+    }
+
+    // The Auto policy of a budget builds a layer dense iff `lagrange_within` builds add_i by it.
+    #[test]
+    fn test_density_policy_within_budget() {
+        use crate::poly::{DenseBudget, MPolynomial};
+
+        let budget = DenseBudget::with_max_vars(10);
+        let policy = DensityPolicy::within(budget);
+        assert_eq!(policy.budget(), budget);
+        assert_eq!(
+            DensityPolicy::ForceDense.budget().max_vars(),
+            MAX_DENSE_VARS
+        );
+        for k in [3, 4, 5] {
+            let circuit = single_layer_circuit(k, 3);
+            let var_num = circuit.wiring_var_num(0);
+            let (add_evals, _) = circuit.layers[0].wiring_evals(3);
+            let dense = MPolynomial::lagrange_within(var_num, &add_evals, &budget).is_ok();
+            assert_eq!(dense, var_num <= 10);
+            let gkr = GKR::init_with_policy(circuit, policy).unwrap();
+            assert_eq!(gkr.prover.ops[0].is_some(), dense);
+        }
+    }
+
+    // x -> x^2 -> x^4, layer i+1 is a single wire in each sumcheck, so they have no round.
+    fn single_wire_circuit() -> CircuitConfig {
+        let layer = Layer {
+            gates: vec![MUL(0, 0)],
+            var_num: 0,
+        };
+        CircuitConfig {
+            layers: vec![layer.clone(), layer],
+            input_var_num: 0,
+            depth: 3,
+        }
+    }
+
+    #[test]
+    fn test_single_wire_layers() {
+        let (circuit, inputs) = (single_wire_circuit(), vec![Scalar::from(3)]);
+        let outputs = vec![Scalar::from(81)];
+        assert_eq!(circuit.evaluate(&inputs), outputs);
+        GKR::init(circuit.clone()).run_protocol(&inputs);
+
+        let mut proof = GKR::init(circuit.clone()).prove(&inputs, || 1);
+        assert!(proof.r_0.is_empty());
+        for layer in proof.layers.iter() {
+            assert!(layer.round_polys.is_empty() && layer.challenges.is_empty());
+        }
+        assert_eq!(
+            diagnose(&circuit, &inputs, &outputs, &proof),
+            Diagnosis::LooksConsistent
+        );
+
+        // W_1 = 9 is claimed to be 10, so m_0 = 81 != 10 * 10.
+        proof.layers[0].p_poly = Polynomial::constant(Scalar::from(10));
+        assert_eq!(
+            diagnose(&circuit, &inputs, &outputs, &proof),
+            Diagnosis::ProofInvalid {
+                layer: 0,
+                round: 0,
+                detail: Detail::FinalEval {
+                    claim: Scalar::from(81),
+                    expected: Scalar::from(100),
+                },
+            }
+        );
+    }
+
+    // A single output wire on 2 inputs, so v_r = 2 and round 2 is round v.
+    #[test]
+    fn test_single_output_wire() {
+        use crate::arithmetic::layered_circuit::Ops::ADD;
+
+        let circuit = CircuitConfig {
+            layers: vec![Layer {
+                gates: vec![ADD(0, 1)],
+                var_num: 0,
+            }],
+            input_var_num: 1,
+            depth: 2,
+        };
+        let inputs = vec![Scalar::from(3), Scalar::from(4)];
+
+        let mut challenges = [1, 2, 3].into_iter();
+        let proof = GKR::init(circuit.clone()).prove(&inputs, || challenges.next().unwrap());
+        assert!(proof.r_0.is_empty());
+        assert_eq!(proof.layers[0].round_polys.len(), 2);
+        assert_eq!(
+            diagnose(&circuit, &inputs, &[Scalar::from(7)], &proof),
+            Diagnosis::LooksConsistent
+        );
+    }
+
+    // 1 input -> 4 -> 2 -> 1 output, so (k_i, k_i+1) = (0, 1), (1, 2), (2, 0).
+    fn fan_out_circuit() -> CircuitConfig {
+        use crate::arithmetic::layered_circuit::Ops::ADD;
+
+        CircuitConfig {
+            layers: vec![
+                Layer {
+                    gates: vec![ADD(0, 1)],
+                    var_num: 0,
+                },
+                Layer {
+                    gates: vec![ADD(0, 2), MUL(0, 2)],
+                    var_num: 1,
+                },
+                Layer {
+                    gates: vec![ADD(0, 0), ADD(0, 0), MUL(0, 0), MUL(0, 0)],
+                    var_num: 2,
+                },
+            ],
+            input_var_num: 0,
+            depth: 4,
+        }
+    }
+
+    #[test]
+    fn test_fan_out_circuit() {
+        let circuit = fan_out_circuit();
+        // 3 -> (6, 6, 9, 9) -> (15, 54) -> 69
+        let (inputs, outputs) = (vec![Scalar::from(3)], vec![Scalar::from(69)]);
+
+        // the challenges are squeezed from a transcript, u32 each, so v < u in some coordinate, and
+        // the line l through u and v goes down, which it can only do over F.
+        let mut transcript = Keccak256Transcript::default();
+        transcript.append(b"fan_out_circuit");
+        let proof = GKR::init(circuit.clone()).prove(&inputs, || {
+            challenge_to_index(&transcript.challenge(), 1 << 32)
+        });
+        assert!(proof.r_0.is_empty());
+        let (u, v) = proof.layers[1].challenges.split_at(2);
+        assert!(u.iter().zip(v).any(|(u, v)| v < u));
+        let rounds = proof
+            .layers
+            .iter()
+            .map(|layer| layer.round_polys.len())
+            .collect::<Vec<_>>();
+        assert_eq!(rounds, vec![2, 4, 0]);
+        assert_eq!(
+            diagnose(&circuit, &inputs, &outputs, &proof),
+            Diagnosis::LooksConsistent
+        );
+        assert_eq!(
+            diagnose(&circuit, &inputs, &[Scalar::from(70)], &proof),
+            Diagnosis::OutputsWrong {
+                index: 0,
+                expected: Scalar::from(69),
+                got: Scalar::from(70),
+            }
+        );
+    }
+
+    // `fan_out_circuit` in other orders of the gates, the MUL of layer 1 reads (ADD, MUL) in both.
+    fn unordered_fan_out_circuits() -> [CircuitConfig; 2] {
+        use crate::arithmetic::layered_circuit::Ops::ADD;
+
+        let circuit = |layer_0, layer_1, layer_2| CircuitConfig {
+            layers: vec![
+                Layer {
+                    gates: layer_0,
+                    var_num: 0,
+                },
+                Layer {
+                    gates: layer_1,
+                    var_num: 1,
+                },
+                Layer {
+                    gates: layer_2,
+                    var_num: 2,
+                },
+            ],
+            input_var_num: 0,
+            depth: 4,
+        };
+        [
+            circuit(
+                vec![ADD(0, 1)],
+                vec![ADD(0, 1), MUL(2, 3)],
+                vec![ADD(0, 0), MUL(0, 0), ADD(0, 0), MUL(0, 0)],
+            ),
+            circuit(
+                vec![ADD(1, 0)],
+                vec![MUL(3, 2), ADD(1, 0)],
+                vec![MUL(0, 0), ADD(0, 0), MUL(0, 0), ADD(0, 0)],
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_not_canonical() {
+        for circuit in unordered_fan_out_circuits() {
+            let err = GKR::init_with_policy(circuit, DensityPolicy::default()).err();
+            assert_eq!(err, Some(ProverError::NotCanonical));
+        }
+    }
+
+    // The orders of the gates give the same circuit, so the same proof, byte for byte.
+    #[test]
+    fn test_canonical_same_proof() {
+        let (inputs, outputs) = (vec![Scalar::from(3)], vec![Scalar::from(69)]);
+        let circuit = fan_out_circuit();
+        assert_eq!(
+            protocol_id(&circuit).params_digest,
+            circuit.circuit_digest()
+        );
+        for mut other in unordered_fan_out_circuits() {
+            assert_eq!(other.canonicalize(), vec![0]);
+            assert_eq!(other.to_bytes(), circuit.to_bytes());
+
+            let mut challenges = [2, 5, 3, 2, 5, 2, 7, 4, 1].into_iter();
+            let proof = GKR::init(other).prove(&inputs, || challenges.next().unwrap());
+            let mut challenges = [2, 5, 3, 2, 5, 2, 7, 4, 1].into_iter();
+            let expected = GKR::init(circuit.clone()).prove(&inputs, || challenges.next().unwrap());
+            assert_eq!(proof.to_bytes(), expected.to_bytes());
+            assert_eq!(
+                diagnose(&circuit, &inputs, &outputs, &proof),
+                Diagnosis::LooksConsistent
+            );
+        }
+    }
+
+    // The inputs are W_3, which is declared with k_3 = 0, so it takes a single value.
+    #[test]
+    fn test_table_size_mismatch() {
+        let inputs = vec![Scalar::from(3), Scalar::from(4)];
+        let err = GKR::init(fan_out_circuit())
+            .try_prove(&inputs, || 1)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ProverError::TableSizeMismatch {
+                layer: 3,
+                var_num: 0,
+                len: 2,
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "W_3 has 2 values, but layer 3 is declared with k = 0, which takes 2^0"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "W_3 has 2 values")]
+    fn test_table_size_mismatch_panics() {
+        let inputs = vec![Scalar::from(3), Scalar::from(4)];
+        GKR::init(fan_out_circuit()).prove(&inputs, || 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid circuit: gate 0 of layer 1 reads wire 2")]
+    fn test_invalid_circuit() {
+        let mut circuit = fan_out_circuit();
+        circuit.layers.pop();
+        circuit.depth = 3;
+        GKR::init(circuit);
+    }
+
+    // The layers of the same size give the stored proof of `diagnose::test::test_fixture`, byte
+    // for byte, it's read only here, so it's not regenerated.
+    #[test]
+    fn test_equal_width_same_bytes() {
+        use Fiat_Shamir::fixtures::{assert_same_bytes, fixture_path, from_hex_lines};
+
+        let inputs = vec![
+            Scalar::one(),
+            Scalar::from_u128(2),
+            Scalar::one(),
+            Scalar::from_u128(4),
+        ];
+        let mut challenges = [3, 5, 2, 5, 7, 4, 2, 6, 2, 9, 3].into_iter();
+        let current = GKR::init(simple_circuit())
+            .prove(&inputs, || challenges.next().unwrap())
+            .to_bytes();
+        let hex = std::fs::read_to_string(fixture_path("gkr")).unwrap();
+        let stored = from_hex_lines(&hex).unwrap();
+        assert_same_bytes("gkr", &stored, &current);
+    }
+
+    #[test]
+    fn test_gkr_with_commitment() {
+        let inputs = vec![
+            Scalar::one(),
+            Scalar::from_u128(2),
+            Scalar::one(),
+            Scalar::from_u128(4),
+        ];
+
+        let mut gkr = GKR::init(simple_circuit());
+        gkr.run_protocol_with_commitment(&inputs);
+    }
 }
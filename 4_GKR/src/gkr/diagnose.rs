@@ -0,0 +1,570 @@
+// Find out what's wrong when V rejects: the circuit, the claimed outputs or the proof.
+//
+// The circuit is evaluated first, and the claimed outputs are compared with the actual ones.
+// If they match, the checks of V are replayed on the proof, layer by layer and round by round,
+// and the first one which fails is reported with the values it compares. From a failed layer,
+// `SpotCheckRequest::of_diagnosis` asks P to open its wires, see `spot_check`.
+use crate::arithmetic::layered_circuit::CircuitConfig;
+use crate::gkr::proof::{GkrProof, LayerProof};
+use crate::gkr::state::{GkrVerifierState, LayerClaim, VerifiedGkr};
+use bls12_381::Scalar;
+use ni_sumcheck::mlpc::MlpcError;
+use std::fmt;
+#[cfg(any(test, feature = "cost"))]
+use sumcheck::cost::CostReport;
+use sumcheck::cost::CostSink;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Detail {
+    // The number of round polys or challenges is not the one of the layer.
+    Malformed,
+    // The layer is declared of another number of rounds than the one of the circuit.
+    VarNumMismatch { proof: usize, instance: usize },
+    // The layer has other than var_num round polys or degrees.
+    RoundCountMismatch { var_num: usize, rounds: usize },
+    // The layer is declared to reduce another claim than m_i.
+    ClaimedSumMismatch { claim: Scalar, declared: Scalar },
+    // g_j is declared of a degree above the one of the rounds.
+    DegreeTooHigh { declared: usize, bound: usize },
+    // g_j has other than the d_j + 1 coeffs of its declared degree.
+    DegreeMismatch { declared: usize, len: usize },
+    // g_j(0) + g_j(1) != g_j-1(r_j-1), where g_0(r_0) = m_i.
+    RoundSum { claim: Scalar, sum: Scalar },
+    // g_v(r_v) != add(r_i,u,v)(W_i+1(u) + W_i+1(v)) + mult(r_i,u,v)(W_i+1(u) * W_i+1(v))
+    FinalEval { claim: Scalar, expected: Scalar },
+    // m_d != W_d(r_d)
+    InputEval { claim: Scalar, expected: Scalar },
+    // The forked mode only: r_j, or t in round v_r + 1, isn't the one of the sub-transcript.
+    ChallengeMismatch { proof: usize, derived: usize },
+    // The forked mode only: the claim on W_layer is rejected by its opening, see `forked`.
+    OpeningRejected(MlpcError),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Diagnosis {
+    // The output gate `index` is claimed to be `got`, but it's evaluated to `expected`.
+    OutputsWrong {
+        index: usize,
+        expected: Scalar,
+        got: Scalar,
+    },
+    // layer: from 0 to d, the layer d is the inputs.
+    // round: from 1 to v in the sumcheck of the layer, 0 for the checks out of the rounds.
+    ProofInvalid {
+        layer: usize,
+        round: usize,
+        detail: Detail,
+    },
+    // The proof is of another circuit, see `GkrProof::protocol`.
+    ProtocolMismatch,
+    LooksConsistent,
+}
+
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnosis::OutputsWrong {
+                index,
+                expected,
+                got,
+            } => write!(
+                f,
+                "output {} is claimed to be {:?}, but it's {:?}",
+                index, got, expected
+            ),
+            Diagnosis::ProofInvalid {
+                layer,
+                round,
+                detail,
+            } => write!(f, "layer {}, round {}: {:?}", layer, round, detail),
+            Diagnosis::ProtocolMismatch => write!(f, "the proof is of another circuit"),
+            Diagnosis::LooksConsistent => write!(f, "looks consistent"),
+        }
+    }
+}
+
+impl std::error::Error for Diagnosis {}
+
+pub fn diagnose(
+    circuit: &CircuitConfig,
+    inputs: &[Scalar],
+    claimed_outputs: &[Scalar],
+    proof: &GkrProof,
+) -> Diagnosis {
+    let outputs = circuit.trace_evaluation(inputs).outputs();
+    assert_eq!(
+        claimed_outputs.len(),
+        outputs.len(),
+        "the number of claimed outputs mismatch"
+    );
+    if let Some(index) = (0..outputs.len()).find(|i| claimed_outputs[*i] != outputs[*i]) {
+        return Diagnosis::OutputsWrong {
+            index,
+            expected: outputs[index],
+            got: claimed_outputs[index],
+        };
+    }
+
+    match verify(circuit, inputs, claimed_outputs, proof) {
+        Ok(_) => Diagnosis::LooksConsistent,
+        Err(diagnosis) => diagnosis,
+    }
+}
+
+// The checks of V in `GKR::run_protocol`, without any assertion, see `GkrVerifierState`.
+// Return the point of each layer and m_d on success, and the first failed check otherwise.
+pub fn verify(
+    circuit: &CircuitConfig,
+    inputs: &[Scalar],
+    outputs: &[Scalar],
+    proof: &GkrProof,
+) -> Result<VerifiedGkr, Diagnosis> {
+    verify_with_sink(circuit, inputs, outputs, proof, &mut ())
+}
+
+// The same as `verify`, and return the ops of V along with the result, see `sumcheck::cost`.
+#[cfg(any(test, feature = "cost"))]
+pub fn verify_with_cost(
+    circuit: &CircuitConfig,
+    inputs: &[Scalar],
+    outputs: &[Scalar],
+    proof: &GkrProof,
+) -> (Result<VerifiedGkr, Diagnosis>, CostReport) {
+    let mut cost = CostReport::default();
+    let res = verify_with_sink(circuit, inputs, outputs, proof, &mut cost);
+    (res, cost)
+}
+
+// The checks of V on the layers 0 to `up_to_layer` only, and the claim on W_up_to_layer+1 they
+// are reduced to, eg: to check the rest of the circuit elsewhere. The inputs are only checked
+// after the last layer, so they are not needed. The layers after are not needed either, the
+// proof may be the one of `GkrProof::read_prefix`.
+// `verify` is the same as the prefix of all the layers, then m_d = W_d(r_d).
+pub fn verify_prefix(
+    circuit: &CircuitConfig,
+    outputs: &[Scalar],
+    proof: &GkrProof,
+    up_to_layer: usize,
+) -> Result<LayerClaim, Diagnosis> {
+    assert!(
+        up_to_layer < circuit.layers.len(),
+        "layer {} is out of the {} layers",
+        up_to_layer,
+        circuit.layers.len()
+    );
+    let sink = &mut ();
+    let mut state =
+        GkrVerifierState::new_prefix_with_sink(circuit, outputs, proof, up_to_layer + 1, sink)?;
+    verify_layers(&mut state, &proof.layers[..=up_to_layer], sink)?;
+    Ok(state.layer_claim())
+}
+
+// The point of the claim before and after each layer.
+fn verify_layers<S: CostSink>(
+    state: &mut GkrVerifierState<'_>,
+    layers: &[LayerProof],
+    sink: &mut S,
+) -> Result<Vec<Vec<Scalar>>, Diagnosis> {
+    let mut points = Vec::with_capacity(layers.len() + 1);
+    points.push(state.claim().0.to_vec());
+    for layer_proof in layers.iter() {
+        state.verify_layer_with_sink(layer_proof, sink)?;
+        points.push(state.claim().0.to_vec());
+    }
+    Ok(points)
+}
+
+fn verify_with_sink<S: CostSink>(
+    circuit: &CircuitConfig,
+    inputs: &[Scalar],
+    outputs: &[Scalar],
+    proof: &GkrProof,
+    sink: &mut S,
+) -> Result<VerifiedGkr, Diagnosis> {
+    let mut state = GkrVerifierState::new_with_sink(circuit, outputs, proof, sink)?;
+    let points = verify_layers(&mut state, &proof.layers, sink)?;
+    match state.finish_with_sink(inputs, sink) {
+        Diagnosis::LooksConsistent => Ok(VerifiedGkr {
+            points,
+            input_claim: state.claim().1,
+        }),
+        diagnosis => Err(diagnosis),
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod test {
+    use crate::arithmetic::layered_circuit::Ops::{ADD, MUL};
+    use crate::arithmetic::layered_circuit::{CircuitConfig, Layer};
+    use crate::gkr::{
+        derive_challenges_only, diagnose, protocol_id, verify, verify_prefix, Detail, Diagnosis,
+        GkrProof, ProofCompression, GKR,
+    };
+    use crate::poly::EvalTable;
+    use crate::poly::Polynomial;
+    use bls12_381::Scalar;
+    use ff::PrimeField;
+
+    // sample from Figure 4.12.
+    fn simple_circuit() -> CircuitConfig {
+        let layer_1 = Layer {
+            gates: vec![MUL(0, 0), MUL(1, 1), MUL(1, 2), MUL(3, 3)],
+            var_num: 2,
+        };
+        let output_layer = Layer {
+            gates: vec![MUL(0, 1), MUL(2, 3)],
+            var_num: 1,
+        };
+        CircuitConfig {
+            layers: vec![output_layer, layer_1],
+            input_var_num: 2,
+            depth: 3,
+        }
+    }
+
+    fn inputs() -> Vec<Scalar> {
+        vec![
+            Scalar::one(),
+            Scalar::from_u128(2),
+            Scalar::one(),
+            Scalar::from_u128(4),
+        ]
+    }
+
+    // The challenges are 1, 2, 3, ...
+    fn honest_proof() -> GkrProof {
+        let mut gkr = GKR::init(simple_circuit());
+        let mut last = 0;
+        gkr.prove(&inputs(), || {
+            last += 1;
+            last
+        })
+    }
+
+    #[test]
+    fn test_honest_run() {
+        let outputs = vec![Scalar::from_u128(4), Scalar::from_u128(32)];
+        let diagnosis = diagnose(&simple_circuit(), &inputs(), &outputs, &honest_proof());
+        assert_eq!(diagnosis, Diagnosis::LooksConsistent);
+    }
+
+    // The golden proof in `tests/fixtures/gkr.hex`, see `Fiat_Shamir::fixtures`.
+    // p is the line through W_i+1(u) and W_i+1(v), which is W_i+1 on l(t) only if u and v differ
+    // in one coordinate, so the challenges are picked that way: (5, 2) and (5, 7) in layer 0, then
+    // (2, 6) and (2, 9) in layer 1.
+    #[test]
+    fn test_fixture() {
+        use Fiat_Shamir::fixtures::{assert_same_bytes, load_or_regenerate};
+
+        let mut challenges = [3, 5, 2, 5, 7, 4, 2, 6, 2, 9, 3].into_iter();
+        let current = GKR::init(simple_circuit())
+            .prove(&inputs(), || challenges.next().unwrap())
+            .to_bytes();
+        let stored = load_or_regenerate("gkr", &current);
+
+        let proof = GkrProof::from_bytes_for(&stored, &protocol_id(&simple_circuit()))
+            .unwrap_or_else(|e| panic!("fixture gkr: can't decode: {:?}", e));
+        let outputs = vec![Scalar::from_u128(4), Scalar::from_u128(32)];
+        let diagnosis = diagnose(&simple_circuit(), &inputs(), &outputs, &proof);
+        assert_eq!(
+            diagnosis,
+            Diagnosis::LooksConsistent,
+            "fixture gkr: rejected: {}",
+            diagnosis
+        );
+        assert_eq!(
+            verify(&simple_circuit(), &inputs(), &outputs, &proof).ok(),
+            Some(derive_challenges_only(&simple_circuit(), &proof)),
+            "fixture gkr: the derived challenges differ from the verified ones"
+        );
+        assert_same_bytes("gkr", &stored, &current);
+    }
+
+    // The fixture in the minimal mode: m_1 of layer 1 and a_0 of the 2 * 4 round polys are left
+    // out, 32 bytes each.
+    #[test]
+    fn test_minimal_fixture_size() {
+        let mut challenges = [3, 5, 2, 5, 7, 4, 2, 6, 2, 9, 3].into_iter();
+        let proof = GKR::init(simple_circuit()).prove(&inputs(), || challenges.next().unwrap());
+        let full = proof.to_bytes();
+        let minimal = proof.to_bytes_with(ProofCompression::Minimal);
+        assert_eq!((full.len(), minimal.len()), (1385, 1097));
+        assert_eq!(full.len() - minimal.len(), 32 * (1 + 2 * 4));
+        let decoded = GkrProof::from_bytes_for(&minimal, &protocol_id(&simple_circuit()));
+        assert_eq!(decoded, Ok(proof));
+    }
+
+    // V gets the same proof from both modes, so it accepts or rejects both the same way, and the
+    // claims it reduces them to are the same.
+    #[test]
+    fn test_minimal_verifies_the_same() {
+        let decode = |proof: &GkrProof, compression| {
+            GkrProof::from_bytes(&proof.to_bytes_with(compression)).unwrap()
+        };
+        let deep = deep_circuit();
+        let (_, deep_outputs) = deep.witness_to_poly(&inputs());
+        let simple_outputs = vec![Scalar::from_u128(4), Scalar::from_u128(32)];
+        let cases = [
+            (simple_circuit(), honest_proof(), simple_outputs),
+            (deep.clone(), deep_proof(&deep), deep_outputs),
+        ];
+        for (circuit, proof, outputs) in cases {
+            let (full, minimal) = (
+                decode(&proof, ProofCompression::Full),
+                decode(&proof, ProofCompression::Minimal),
+            );
+            assert_eq!(minimal, full);
+            let verified = verify(&circuit, &inputs(), &outputs, &minimal);
+            assert!(verified.is_ok());
+            assert_eq!(verified, verify(&circuit, &inputs(), &outputs, &full));
+
+            // the same failures, on m_0 and on m_d: D + 1 and W_d + 1 are off by 1 at any point.
+            let plus_one = |values: &[Scalar]| values.iter().map(|x| x + Scalar::one()).collect();
+            let wrong = [
+                (inputs(), plus_one(&outputs)),
+                (plus_one(&inputs()), outputs.clone()),
+            ];
+            for (inputs, outputs) in wrong {
+                let res = verify(&circuit, &inputs, &outputs, &minimal);
+                assert!(res.is_err());
+                assert_eq!(res, verify(&circuit, &inputs, &outputs, &full));
+            }
+
+            // the prefixes are read from the minimal bytes too.
+            let bytes = proof.to_bytes_with(ProofCompression::Minimal);
+            for up_to_layer in 0..circuit.layers.len() {
+                let read = GkrProof::read_prefix(&bytes[..], up_to_layer + 1).unwrap();
+                assert_eq!(
+                    verify_prefix(&circuit, &outputs, &read, up_to_layer),
+                    verify_prefix(&circuit, &outputs, &full, up_to_layer)
+                );
+            }
+        }
+
+        // g_3 of layer 1 + 1 is off in a_0 only, which the minimal mode leaves out, so it's the
+        // honest proof again.
+        let mut proof = honest_proof();
+        let g_3 = &proof.layers[1].round_polys[2];
+        proof.layers[1].round_polys[2] = g_3 + &Polynomial::constant(Scalar::one());
+        assert_eq!(decode(&proof, ProofCompression::Minimal), honest_proof());
+    }
+
+    // r_0, r_1 and r_2 = r_d, where m_d is W_d(r_d).
+    #[test]
+    fn test_verified_claims() {
+        let (circuit, proof) = (simple_circuit(), honest_proof());
+        let outputs = vec![Scalar::from_u128(4), Scalar::from_u128(32)];
+        let verified = verify(&circuit, &inputs(), &outputs, &proof).unwrap();
+        assert_eq!(verified, derive_challenges_only(&circuit, &proof));
+
+        let var_nums = verified.points.iter().map(|r| r.len()).collect::<Vec<_>>();
+        assert_eq!(var_nums, circuit.layer_var_nums());
+        assert_eq!(verified.points[0], vec![Scalar::from(proof.r_0[0] as u64)]);
+        let r_d = verified.points.last().unwrap();
+        assert_eq!(EvalTable::new(inputs()).evaluate(r_d), verified.input_claim);
+
+        // the same claims are derived from a proof V rejects.
+        let mut proof = proof;
+        let g_3 = &proof.layers[1].round_polys[2];
+        proof.layers[1].round_polys[2] = g_3 + &Polynomial::constant(Scalar::one());
+        assert!(verify(&circuit, &inputs(), &outputs, &proof).is_err());
+        assert_eq!(derive_challenges_only(&circuit, &proof), verified);
+    }
+
+    #[test]
+    fn test_outputs_wrong() {
+        let outputs = vec![Scalar::from_u128(4), Scalar::from_u128(33)];
+        let diagnosis = diagnose(&simple_circuit(), &inputs(), &outputs, &honest_proof());
+        assert_eq!(
+            diagnosis,
+            Diagnosis::OutputsWrong {
+                index: 1,
+                expected: Scalar::from_u128(32),
+                got: Scalar::from_u128(33),
+            }
+        );
+    }
+
+    // The metadata of a layer is checked before its rounds, then each g_j against its degree.
+    #[test]
+    fn test_layer_metadata() {
+        let outputs = vec![Scalar::from_u128(4), Scalar::from_u128(32)];
+        let check = |proof: &GkrProof| diagnose(&simple_circuit(), &inputs(), &outputs, proof);
+        let invalid = |layer, round, detail| Diagnosis::ProofInvalid {
+            layer,
+            round,
+            detail,
+        };
+
+        // layer 1 has 2 * 2 rounds, P declares one more.
+        let mut proof = honest_proof();
+        proof.layers[1].var_num += 1;
+        assert_eq!(
+            check(&proof),
+            invalid(
+                1,
+                0,
+                Detail::VarNumMismatch {
+                    proof: 5,
+                    instance: 4
+                }
+            )
+        );
+
+        let mut proof = honest_proof();
+        proof.layers[0].degrees.pop();
+        assert_eq!(
+            check(&proof),
+            invalid(
+                0,
+                0,
+                Detail::RoundCountMismatch {
+                    var_num: 4,
+                    rounds: 3
+                }
+            )
+        );
+
+        let mut proof = honest_proof();
+        let declared = proof.layers[1].claimed_sum + Scalar::one();
+        proof.layers[1].claimed_sum = declared;
+        let claim = honest_proof().layers[1].claimed_sum;
+        assert_eq!(
+            check(&proof),
+            invalid(1, 0, Detail::ClaimedSumMismatch { claim, declared })
+        );
+
+        let mut proof = honest_proof();
+        proof.layers[0].degrees[1] = 3;
+        assert_eq!(
+            check(&proof),
+            invalid(
+                0,
+                2,
+                Detail::DegreeTooHigh {
+                    declared: 3,
+                    bound: 2
+                }
+            )
+        );
+
+        // g_3 of layer 1 is declared of degree 1, but it has 3 coeffs.
+        let mut proof = honest_proof();
+        proof.layers[1].degrees[2] = 1;
+        assert_eq!(
+            check(&proof),
+            invalid(
+                1,
+                3,
+                Detail::DegreeMismatch {
+                    declared: 1,
+                    len: 3
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn test_corrupted_round_poly() {
+        let outputs = vec![Scalar::from_u128(4), Scalar::from_u128(32)];
+        let mut proof = honest_proof();
+        // g_3 of layer 1 + 1, so g_3(0) + g_3(1) is off by 2.
+        let g_3 = &proof.layers[1].round_polys[2];
+        proof.layers[1].round_polys[2] = g_3 + &Polynomial::constant(Scalar::one());
+
+        match diagnose(&simple_circuit(), &inputs(), &outputs, &proof) {
+            Diagnosis::ProofInvalid {
+                layer: 1,
+                round: 3,
+                detail: Detail::RoundSum { claim, sum },
+            } => assert_eq!(sum - claim, Scalar::from_u128(2)),
+            diagnosis => panic!("unexpected diagnosis: {}", diagnosis),
+        }
+    }
+
+    // 5 layers of 4 gates, gate j reads the wires j and j + 1, see `analysis`.
+    fn deep_circuit() -> CircuitConfig {
+        let layer = Layer {
+            gates: (0..4)
+                .map(|j| match j % 2 {
+                    0 => ADD(j, (j + 1) % 4),
+                    _ => MUL(j, (j + 1) % 4),
+                })
+                .collect(),
+            var_num: 2,
+        };
+        let mut circuit = CircuitConfig {
+            layers: vec![layer; 5],
+            input_var_num: 2,
+            depth: 6,
+        };
+        circuit.canonicalize();
+        circuit
+    }
+
+    // r_0 = (3, 3), then u = (2, 2), v = (4, 2) and t = 3 per layer, so r_i+1 = l(3) = (8, 2).
+    fn deep_proof(circuit: &CircuitConfig) -> GkrProof {
+        let mut challenges = [3, 3]
+            .into_iter()
+            .chain([2, 2, 4, 2, 3].into_iter().cycle());
+        GKR::init(circuit.clone()).prove(&inputs(), || challenges.next().unwrap())
+    }
+
+    #[test]
+    fn test_verify_prefix() {
+        let circuit = deep_circuit();
+        let (witness, outputs) = circuit.witness_to_poly(&inputs());
+        let proof = deep_proof(&circuit);
+        let derived = derive_challenges_only(&circuit, &proof);
+        for up_to_layer in 0..circuit.layers.len() {
+            let claim = verify_prefix(&circuit, &outputs, &proof, up_to_layer).unwrap();
+            assert_eq!(claim.layer, up_to_layer + 1);
+            assert_eq!(claim.point, derived.points[up_to_layer + 1]);
+            assert_eq!(claim.value, witness[up_to_layer + 1].evaluate(&vec![8, 2]));
+
+            // the layers after are not needed.
+            let mut prefix = proof.clone();
+            prefix.layers.truncate(up_to_layer + 1);
+            let bytes = proof.to_bytes();
+            let read = GkrProof::read_prefix(&bytes[..], up_to_layer + 1).unwrap();
+            assert_eq!(read, prefix);
+            assert_eq!(
+                verify_prefix(&circuit, &outputs, &read, up_to_layer),
+                Ok(claim)
+            );
+        }
+
+        // the prefix of the last layer is `verify` but the inputs.
+        let claim = verify_prefix(&circuit, &outputs, &proof, circuit.layers.len() - 1).unwrap();
+        let verified = verify(&circuit, &inputs(), &outputs, &proof).unwrap();
+        assert_eq!(
+            (&claim.point, claim.value),
+            (verified.points.last().unwrap(), verified.input_claim)
+        );
+    }
+
+    #[test]
+    fn test_verify_prefix_of_corrupted_layer() {
+        let circuit = deep_circuit();
+        let (_, outputs) = circuit.witness_to_poly(&inputs());
+        let mut proof = deep_proof(&circuit);
+        let g_2 = &proof.layers[3].round_polys[1];
+        proof.layers[3].round_polys[1] = g_2 + &Polynomial::constant(Scalar::one());
+
+        for up_to_layer in 0..circuit.layers.len() {
+            let res = verify_prefix(&circuit, &outputs, &proof, up_to_layer);
+            if up_to_layer < 3 {
+                assert!(res.is_ok(), "prefix to {}: {:?}", up_to_layer, res);
+                continue;
+            }
+            match res {
+                Err(Diagnosis::ProofInvalid {
+                    layer: 3,
+                    round: 2,
+                    detail: Detail::RoundSum { claim, sum },
+                }) => assert_eq!(sum - claim, Scalar::from_u128(2)),
+                res => panic!("prefix to {}: unexpected result: {:?}", up_to_layer, res),
+            }
+        }
+    }
+}
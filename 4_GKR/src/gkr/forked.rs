@@ -0,0 +1,713 @@
+// The forked mode of GKR, where each layer is proved on a sub-transcript of its own, so the
+// sumchecks of the layers run in parallel, and the proof is the same whatever the threads are.
+//
+// In the sequential mode, the claim on W_i+1 is the one layer i is reduced to, r_i+1 = l(t), so
+// layer i+1 can't start before layer i is done: r_i+1 depends on all the round polys of layer i.
+// A sub-transcript per layer doesn't break that chain by itself, so in the forked mode P commits
+// to the layers in between first, and each claim is opened against its commitment rather than
+// reduced to by the layer before:
+//
+//  1. phase one, sequential and cheap, see `GKR::fork_layers`: P commits to W_1, ..., W_d-1 with
+//     `mlpc::commit`. The root transcript absorbs the protocol, the inputs, the claimed outputs
+//     and the roots. Layer i forks it with the label i, see `SpongeTranscript::fork`, squeezes
+//     r_i ∈ F^k_i and absorbs m_i = W_i(r_i), where W_0 = D.
+//  2. phase two, a layer per task, see `GKR::prove_forked_layer`: the sumcheck of
+//     m_i = ∑ f_{r_i}(b, c) on the sub-transcript of the layer, where g_j is absorbed before r_j
+//     is squeezed, then p = W_i+1(l(X)) before t. P opens W_i at r_i for i ≥ 1, and W_i+1 at l(t)
+//     for i + 1 < d. W_d is the inputs, which V holds.
+//  3. merge, see `ForkedRun::merge`: the layers in order.
+//
+// The challenges are in usize, as the ones of `GKR::prove`, so a challenge is a squeeze mapped
+// into [0, 2^32) by `challenge_to_index`. V forks the root transcript the same way, so it checks each layer apart from the
+// others: the challenges of a layer must be the ones of its sub-transcript, and its claims are
+// checked by the openings, or against D and the inputs at the ends.
+//
+// A sub-transcript only depends on the root and its label, and a layer on its sub-transcript and
+// the witness, so the proof is the same on any number of threads, and the same as the one of the
+// layers proved one by one. The openings are the ones of `mlpc`, see there for their soundness;
+// the sumchecks are the ones of `GKR::prove`, with the challenges of a set of 2^32.
+//
+// Layout (version 6) in the encoding of `Fiat_Shamir::codec`:
+//
+//      | field       | encoding                                                |
+//      |-------------|---------------------------------------------------------|
+//      | version     | u8 = 6                                                  |
+//      | protocol    | digest, of `protocol_id_with` in the forked mode        |
+//      | r_0, layers | as in version 4, see `proof`                            |
+//      | roots       | len: u64, then each one as var_num: u64 and digest      |
+//      | openings    | len: u64, then the claim and the reduced one of each    |
+//      |             |   layer: u8 = 0 if there's none, else 1 and the opening |
+use crate::arithmetic::layered_circuit::CircuitConfig;
+use crate::gkr::diagnose::{Detail, Diagnosis};
+use crate::gkr::proof::{GkrProof, ProofCompression, GKR_PROOF_VERSION_FORKED};
+use crate::gkr::schedule::{params_of, protocol_id_with, FactorOrder, ProvingMode};
+use crate::gkr::state::{invalid, to_scalars, GkrVerifierState};
+use crate::poly::{EvalTable, Polynomial};
+use bls12_381::Scalar;
+use ni_sumcheck::mlpc::{self, MlOpening, MlRoot};
+use Fiat_Shamir::checkpoint::proof_binding;
+use Fiat_Shamir::codec::{encode_digest, encode_scalar, encode_u64, CodecError, Decoder};
+use Fiat_Shamir::default::Keccak256Transcript;
+use Fiat_Shamir::protocol::ProtocolId;
+use Fiat_Shamir::{challenge_to_index, Transcript};
+
+#[cfg(feature = "prover")]
+use crate::gkr::proof::LayerProof;
+#[cfg(feature = "prover")]
+use crate::gkr::prover::ProverError;
+#[cfg(feature = "prover")]
+use crate::gkr::GKR;
+#[cfg(feature = "prover")]
+use crate::utils::par_map;
+#[cfg(feature = "prover")]
+use ni_sumcheck::mlpc::ProverState;
+
+// The openings of the claims of a layer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LayerOpenings {
+    pub claim: Option<MlOpening>,   // W_i(r_i) = m_i, none in layer 0
+    pub reduced: Option<MlOpening>, // W_i+1(l(t)) = p(t), none in layer d-1
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ForkedGkrProof {
+    pub proof: GkrProof,    // the layers, of the protocol of the forked mode
+    pub roots: Vec<MlRoot>, // of W_1, ..., W_d-1
+    pub openings: Vec<LayerOpenings>,
+}
+
+fn encode_opening(bytes: &mut Vec<u8>, opening: &Option<MlOpening>) {
+    match opening {
+        None => bytes.push(0),
+        Some(opening) => {
+            bytes.push(1);
+            bytes.extend(opening.to_bytes());
+        }
+    }
+}
+
+fn decode_opening(decoder: &mut Decoder) -> Result<Option<MlOpening>, CodecError> {
+    match decoder.read_tag(2)? {
+        0 => Ok(None),
+        _ => MlOpening::decode(decoder).map(Some),
+    }
+}
+
+impl ForkedGkrProof {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![GKR_PROOF_VERSION_FORKED];
+        bytes.extend(encode_digest(&self.proof.protocol));
+        self.proof.encode_body(&mut bytes, ProofCompression::Full);
+        bytes.extend(encode_u64(self.roots.len() as u64));
+        for root in self.roots.iter() {
+            bytes.extend(encode_u64(root.var_num as u64));
+            bytes.extend(encode_digest(&root.digest));
+        }
+        bytes.extend(encode_u64(self.openings.len() as u64));
+        for openings in self.openings.iter() {
+            encode_opening(&mut bytes, &openings.claim);
+            encode_opening(&mut bytes, &openings.reduced);
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        let mut decoder = Decoder::new(bytes);
+        decoder.read_version(GKR_PROOF_VERSION_FORKED)?;
+        let protocol = decoder.read_digest()?;
+        Self::decode_body(protocol, decoder)
+    }
+
+    // The same as `from_bytes`, but the proof must be of the expected protocol, eg:
+    // `protocol_id_with` of the circuit in the forked mode, which is checked right after the
+    // version. A proof of the sequential mode is rejected by the version already.
+    pub fn from_bytes_for(bytes: &[u8], expected: &ProtocolId) -> Result<Self, CodecError> {
+        let mut decoder = Decoder::new(bytes);
+        decoder.read_version(GKR_PROOF_VERSION_FORKED)?;
+        decoder.read_protocol(expected)?;
+        Self::decode_body(expected.digest(), decoder)
+    }
+
+    fn decode_body(protocol: [u8; 32], mut decoder: Decoder) -> Result<Self, CodecError> {
+        let proof = GkrProof::decode_layers(protocol, ProofCompression::Full, &mut decoder)?;
+        let roots = (0..decoder.read_len(8 + 32)?)
+            .map(|_| {
+                Ok(MlRoot {
+                    var_num: decoder.read_usize()?,
+                    digest: decoder.read_digest()?,
+                })
+            })
+            .collect::<Result<Vec<_>, CodecError>>()?;
+        // the 2 tags of each layer at least.
+        let openings = (0..decoder.read_len(2)?)
+            .map(|_| {
+                Ok(LayerOpenings {
+                    claim: decode_opening(&mut decoder)?,
+                    reduced: decode_opening(&mut decoder)?,
+                })
+            })
+            .collect::<Result<Vec<_>, CodecError>>()?;
+        decoder.finish()?;
+        Ok(Self {
+            proof,
+            roots,
+            openings,
+        })
+    }
+}
+
+// The transcript the layers fork, see step 1.
+fn root_transcript(
+    protocol: &ProtocolId,
+    inputs: &[Scalar],
+    outputs: &[Scalar],
+    roots: &[MlRoot],
+) -> Keccak256Transcript {
+    let mut transcript = Keccak256Transcript::default();
+    transcript.bind_protocol(protocol);
+    transcript.append_scalars(inputs);
+    transcript.append_scalars(outputs);
+    for root in roots.iter() {
+        transcript.append(&encode_u64(root.var_num as u64));
+        transcript.append(&encode_digest(&root.digest));
+    }
+    transcript
+}
+
+// The sub-transcript of layer i and r_i ∈ F^k_i, which m_i is absorbed into next.
+fn fork_layer(
+    root: &Keccak256Transcript,
+    i: usize,
+    k_i: usize,
+) -> (Keccak256Transcript, Vec<usize>) {
+    let mut sub = root.fork(i as u64);
+    let r_i = (0..k_i)
+        .map(|_| challenge_to_index(&sub.challenge(), 1 << 32))
+        .collect();
+    (sub, r_i)
+}
+
+// Absorb a message of P, g_j or p, and squeeze the challenge after it, r_j or t.
+fn next_challenge(transcript: &mut Keccak256Transcript, poly: &Polynomial) -> usize {
+    transcript.append_scalars(&poly.coeffs());
+    challenge_to_index(&transcript.challenge(), 1 << 32)
+}
+
+// The point l(t) on the line through u and v, in F.
+fn line_at(u: &[Scalar], v: &[Scalar], t: usize) -> Vec<Scalar> {
+    let t = Scalar::from(t as u64);
+    u.iter().zip(v).map(|(u, v)| u + (v - u) * t).collect()
+}
+
+// The checks of V in the forked mode: each layer on its own sub-transcript, from its own claim.
+// Return the first failed check, see `Diagnosis`.
+pub fn verify_forked(
+    circuit: &CircuitConfig,
+    inputs: &[Scalar],
+    outputs: &[Scalar],
+    proof: &ForkedGkrProof,
+) -> Result<(), Diagnosis> {
+    let order = match params_of(circuit, &proof.proof.protocol) {
+        Some((order, ProvingMode::Forked)) => order,
+        _ => return Err(Diagnosis::ProtocolMismatch),
+    };
+    let d = circuit.layers.len();
+    let roots_fit = proof.roots.len() == d.saturating_sub(1)
+        && (proof.roots.iter().enumerate())
+            .all(|(j, root)| root.var_num == circuit.layer_var_num(j + 1));
+    if !roots_fit || proof.proof.layers.len() != d || proof.openings.len() != d {
+        return Err(invalid(0, 0, Detail::Malformed));
+    }
+
+    let protocol = protocol_id_with(circuit, order, ProvingMode::Forked);
+    let verifier = ForkedVerifier {
+        circuit,
+        order,
+        binding: proof_binding(&proof.to_bytes()),
+        root: root_transcript(&protocol, inputs, outputs, &proof.roots),
+        inputs,
+        outputs,
+        roots: &proof.roots,
+    };
+    for i in 0..d {
+        verifier.check_layer(i, proof)?;
+    }
+    Ok(())
+}
+
+struct ForkedVerifier<'a> {
+    circuit: &'a CircuitConfig,
+    order: FactorOrder,
+    binding: [u8; 32],
+    root: Keccak256Transcript,
+    inputs: &'a [Scalar],
+    outputs: &'a [Scalar],
+    roots: &'a [MlRoot],
+}
+
+impl ForkedVerifier<'_> {
+    // The claim of layer i, the challenges of its sub-transcript, its sumcheck, then the claim it's
+    // reduced to, in this order.
+    fn check_layer(&self, i: usize, proof: &ForkedGkrProof) -> Result<(), Diagnosis> {
+        let (layer_proof, openings) = (&proof.proof.layers[i], &proof.openings[i]);
+        let (mut sub, r_i) = fork_layer(&self.root, i, self.circuit.layer_var_num(i));
+        let (point, m_i) = (to_scalars(&r_i), layer_proof.claimed_sum);
+
+        // m_i = D(r_0), or the opening of W_i at r_i.
+        match (i, &openings.claim) {
+            (0, None) => {
+                if let Some((proof, derived)) = mismatch(&proof.proof.r_0, &r_i) {
+                    return Err(invalid(0, 0, Detail::ChallengeMismatch { proof, derived }));
+                }
+                let claim = EvalTable::new(self.outputs.to_vec()).evaluate(&point);
+                if m_i != claim {
+                    let detail = Detail::ClaimedSumMismatch {
+                        claim,
+                        declared: m_i,
+                    };
+                    return Err(invalid(0, 0, detail));
+                }
+            }
+            (_, Some(opening)) if i > 0 => {
+                mlpc::verify(&self.roots[i - 1], &point, m_i, opening)
+                    .map_err(|e| invalid(i, 0, Detail::OpeningRejected(e)))?;
+            }
+            _ => return Err(invalid(i, 0, Detail::Malformed)),
+        }
+        sub.append(&encode_scalar(&m_i));
+
+        // r_1, ..., r_v, then t.
+        let rounds = layer_proof.round_polys.len();
+        if layer_proof.challenges.len() != rounds {
+            return Err(invalid(i, 0, Detail::Malformed));
+        }
+        let messages = layer_proof.round_polys.iter().chain([&layer_proof.p_poly]);
+        let sent = layer_proof.challenges.iter().chain([&layer_proof.t]);
+        for (j, (poly, r_j)) in messages.zip(sent).enumerate() {
+            let derived = next_challenge(&mut sub, poly);
+            if derived != *r_j {
+                let detail = Detail::ChallengeMismatch {
+                    proof: *r_j,
+                    derived,
+                };
+                return Err(invalid(i, j + 1, detail));
+            }
+        }
+
+        let claim = (i, point, m_i);
+        let mut state = GkrVerifierState::at_layer(self.circuit, self.order, self.binding, claim);
+        state.verify_layer(layer_proof)?;
+
+        // p(t) = W_i+1(l(t)), by the opening, or against the inputs.
+        let (r, m) = state.claim();
+        match (self.roots.get(i), &openings.reduced) {
+            (Some(root), Some(opening)) => mlpc::verify(root, r, m, opening)
+                .map_err(|e| invalid(i + 1, 0, Detail::OpeningRejected(e))),
+            (None, None) => match state.finish(self.inputs) {
+                Diagnosis::LooksConsistent => Ok(()),
+                diagnosis => Err(diagnosis),
+            },
+            _ => Err(invalid(i, 0, Detail::Malformed)),
+        }
+    }
+}
+
+// The first (sent, derived) pair which differs, the lengths are the same.
+fn mismatch(sent: &[usize], derived: &[usize]) -> Option<(usize, usize)> {
+    sent.iter()
+        .zip(derived)
+        .find(|(s, d)| s != d)
+        .map(|(s, d)| (*s, *d))
+}
+
+// The claims and the sub-transcripts of the layers after phase one, which phase two proves each
+// layer from, apart from the other layers.
+#[cfg(feature = "prover")]
+#[derive(Clone)]
+pub struct ForkedRun {
+    protocol: [u8; 32],
+    roots: Vec<MlRoot>,
+    tables: Vec<ProverState>, // the commitments of W_1, ..., W_d-1
+    layers: Vec<ForkedLayer>,
+}
+
+// The claim W_i(r_i) = m_i of a layer, and its sub-transcript with m_i absorbed.
+#[cfg(feature = "prover")]
+#[derive(Clone)]
+struct ForkedLayer {
+    r_i: Vec<usize>,
+    m_i: Scalar,
+    transcript: Keccak256Transcript,
+}
+
+#[cfg(feature = "prover")]
+impl ForkedRun {
+    // d, the layers phase two proves.
+    pub fn layers(&self) -> usize {
+        self.layers.len()
+    }
+
+    // The layers of phase two, in the order of the layers.
+    pub fn merge(self, layers: Vec<(LayerProof, LayerOpenings)>) -> ForkedGkrProof {
+        assert_eq!(
+            layers.len(),
+            self.layers.len(),
+            "not all the layers are proved"
+        );
+        let (layers, openings) = layers.into_iter().unzip();
+        let r_0 = self.layers[0].r_i.clone();
+        ForkedGkrProof {
+            proof: GkrProof {
+                protocol: self.protocol,
+                r_0,
+                layers,
+            },
+            roots: self.roots,
+            openings,
+        }
+    }
+}
+
+#[cfg(feature = "prover")]
+impl GKR {
+    // The three phases, and phase two on the rayon pool with the `parallel` feature. The proof is
+    // of `protocol_id_with` of the circuit and the factor order in the forked mode.
+    pub fn prove_forked(&mut self, inputs: &Vec<Scalar>) -> Result<ForkedGkrProof, ProverError> {
+        let run = self.fork_layers(inputs)?;
+        let indices = (0..run.layers()).collect::<Vec<_>>();
+        let layers = par_map(&indices, |i| self.prove_forked_layer(&run, *i));
+        Ok(run.merge(layers))
+    }
+
+    // Phase one: the commitments, and the claim and the sub-transcript of each layer. It's an
+    // evaluation of W_i per layer, no sumcheck, see step 1.
+    pub fn fork_layers(&mut self, inputs: &Vec<Scalar>) -> Result<ForkedRun, ProverError> {
+        self.prover.synthesize(inputs)?;
+        let d = self.layers.len();
+        let (roots, tables): (Vec<_>, Vec<_>) = (1..d)
+            .map(|j| mlpc::commit(&self.prover.witness[j].hypercube_evaluations()))
+            .unzip();
+        let config = self.prover.config();
+        let protocol = protocol_id_with(config, self.order, ProvingMode::Forked);
+        let outputs = self.prover.outputs();
+        let root = root_transcript(&protocol, inputs, &outputs, &roots);
+
+        let d_table = EvalTable::new(outputs);
+        let layers = (0..d)
+            .map(|i| {
+                let (mut transcript, r_i) = fork_layer(&root, i, config.layer_var_num(i));
+                let point = to_scalars(&r_i);
+                let m_i = match i {
+                    0 => d_table.evaluate(&point),
+                    _ => tables[i - 1].evaluate(&point),
+                };
+                transcript.append(&encode_scalar(&m_i));
+                ForkedLayer {
+                    r_i,
+                    m_i,
+                    transcript,
+                }
+            })
+            .collect();
+        Ok(ForkedRun {
+            protocol: protocol.digest(),
+            roots,
+            tables,
+            layers,
+        })
+    }
+
+    // Phase two of layer i: its sumcheck on its sub-transcript and the openings of its claims,
+    // see step 2. It only reads the run and the witness, so the layers are proved in any order,
+    // on any thread.
+    pub fn prove_forked_layer(&self, run: &ForkedRun, i: usize) -> (LayerProof, LayerOpenings) {
+        let layer = &run.layers[i];
+        let mut transcript = layer.transcript.clone();
+        let mut prover = self.layer_prover(i, &to_scalars(&layer.r_i), layer.m_i);
+        let v_r = prover.v_r();
+        let mut round_polys = Vec::with_capacity(v_r);
+        let mut challenges: Vec<usize> = Vec::with_capacity(v_r);
+        for _ in 0..v_r {
+            let g_j = prover.next_round(challenges.last().copied());
+            challenges.push(next_challenge(&mut transcript, &g_j));
+            round_polys.push(g_j);
+        }
+        if let Some(r_v) = challenges.last() {
+            prover.bind_final(*r_v);
+        }
+        let p_poly = prover.line_poly();
+        let t = next_challenge(&mut transcript, &p_poly);
+
+        let claim = (i > 0).then(|| mlpc::open(&run.tables[i - 1], &to_scalars(&layer.r_i)));
+        let reduced = run.tables.get(i).map(|table| {
+            let (u, v) = challenges.split_at(self.dims[i].k_i_plus_1);
+            mlpc::open(table, &line_at(&to_scalars(u), &to_scalars(v), t))
+        });
+        let layer_proof = LayerProof {
+            var_num: v_r,
+            claimed_sum: layer.m_i,
+            degrees: LayerProof::degrees_of(&round_polys),
+            round_polys,
+            challenges,
+            p_poly,
+            t,
+        };
+        (layer_proof, LayerOpenings { claim, reduced })
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod test {
+    use super::*;
+    use crate::arithmetic::layered_circuit::Layer;
+    use crate::arithmetic::layered_circuit::Ops::{ADD, MUL};
+    use crate::gkr::diagnose::verify;
+    use crate::gkr::schedule::protocol_id_for;
+    use ni_sumcheck::mlpc::MlpcError;
+    use std::thread;
+
+    // 6 layers of 2^k gates mixing ADD and MUL, as the ones of `bench_prove`.
+    fn six_layer_circuit(k: usize) -> CircuitConfig {
+        let n = 1 << k;
+        let layer = |shift: usize| Layer {
+            gates: (0..n)
+                .map(|j| {
+                    let (left, right) = (j, (j + shift) % n);
+                    if j % 2 == 0 {
+                        ADD(left, right)
+                    } else {
+                        MUL(left, right)
+                    }
+                })
+                .collect(),
+            var_num: k,
+        };
+        let mut circuit = CircuitConfig {
+            layers: (0..6).map(|i| layer(2 * i + 1)).collect(),
+            input_var_num: k,
+            depth: 7,
+        };
+        circuit.canonicalize();
+        circuit
+    }
+
+    fn inputs(k: usize) -> Vec<Scalar> {
+        (1..=1u64 << k).map(Scalar::from).collect()
+    }
+
+    fn forked_proof(
+        order: FactorOrder,
+    ) -> (CircuitConfig, Vec<Scalar>, Vec<Scalar>, ForkedGkrProof) {
+        let (circuit, inputs) = (six_layer_circuit(2), inputs(2));
+        let mut gkr = GKR::init(circuit.clone()).with_factor_order(order);
+        let proof = gkr.prove_forked(&inputs).unwrap();
+        (circuit, inputs, gkr.prover.outputs(), proof)
+    }
+
+    // Phase two on `threads` threads, the layer i on the thread i % threads.
+    fn prove_on_threads(
+        gkr: &GKR,
+        run: &ForkedRun,
+        threads: usize,
+    ) -> Vec<(LayerProof, LayerOpenings)> {
+        let mut layers = thread::scope(|s| {
+            let handles = (0..threads)
+                .map(|t| {
+                    s.spawn(move || {
+                        (t..run.layers())
+                            .step_by(threads)
+                            .map(|i| (i, gkr.prove_forked_layer(run, i)))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        layers.sort_by_key(|(i, _)| *i);
+        layers.into_iter().map(|(_, layer)| layer).collect()
+    }
+
+    #[test]
+    fn test_verify_forked() {
+        for order in [FactorOrder::Joint, FactorOrder::Optimized] {
+            let (circuit, inputs, outputs, proof) = forked_proof(order);
+            assert_eq!(verify_forked(&circuit, &inputs, &outputs, &proof), Ok(()));
+
+            let protocol = protocol_id_with(&circuit, order, ProvingMode::Forked);
+            assert_eq!(proof.proof.protocol, protocol.digest());
+            let bytes = proof.to_bytes();
+            assert_eq!(ForkedGkrProof::from_bytes(&bytes), Ok(proof.clone()));
+            assert_eq!(ForkedGkrProof::from_bytes_for(&bytes, &protocol), Ok(proof));
+        }
+    }
+
+    #[test]
+    fn test_forked_fails_under_sequential_params() {
+        let (circuit, inputs, outputs, proof) = forked_proof(FactorOrder::Joint);
+        let sequential = protocol_id_for(&circuit, FactorOrder::Joint);
+        let bytes = proof.to_bytes();
+        assert_eq!(
+            ForkedGkrProof::from_bytes_for(&bytes, &sequential),
+            Err(CodecError::ProtocolMismatch)
+        );
+        assert_eq!(
+            GkrProof::from_bytes(&bytes),
+            Err(CodecError::UnknownVersion(6))
+        );
+        assert_eq!(
+            verify(&circuit, &inputs, &outputs, &proof.proof).err(),
+            Some(Diagnosis::ProtocolMismatch)
+        );
+
+        // the layers under the sequential protocol: m_1 is W_1 at the r_1 of the sub-transcript,
+        // not at l(t) of layer 0.
+        let mut retagged = proof.clone();
+        retagged.proof.protocol = sequential.digest();
+        match verify(&circuit, &inputs, &outputs, &retagged.proof) {
+            Err(Diagnosis::ProofInvalid {
+                layer: 1,
+                detail: Detail::ClaimedSumMismatch { .. },
+                ..
+            }) => {}
+            res => panic!("{:?}", res.err()),
+        }
+        assert_eq!(
+            verify_forked(&circuit, &inputs, &outputs, &retagged),
+            Err(Diagnosis::ProtocolMismatch)
+        );
+    }
+
+    #[test]
+    fn test_verify_forked_tampered() {
+        let (circuit, inputs, outputs, proof) = forked_proof(FactorOrder::Joint);
+        let check = |proof: &ForkedGkrProof| verify_forked(&circuit, &inputs, &outputs, proof);
+
+        let mut tampered = proof.clone();
+        tampered.proof.layers[1].claimed_sum += Scalar::one();
+        let detail = Detail::OpeningRejected(MlpcError::RoundCheckFailed { round: 1 });
+        assert_eq!(check(&tampered), Err(invalid(1, 0, detail)));
+
+        let mut tampered = proof.clone();
+        let r_1 = tampered.proof.layers[2].challenges[1];
+        tampered.proof.layers[2].challenges[1] += 1;
+        let detail = Detail::ChallengeMismatch {
+            proof: r_1 + 1,
+            derived: r_1,
+        };
+        assert_eq!(check(&tampered), Err(invalid(2, 2, detail)));
+
+        let mut tampered = proof.clone();
+        let v_r = tampered.proof.layers[3].round_polys.len();
+        tampered.proof.layers[3].t += 1;
+        match check(&tampered) {
+            Err(Diagnosis::ProofInvalid {
+                layer: 3,
+                round,
+                detail: Detail::ChallengeMismatch { .. },
+            }) => assert_eq!(round, v_r + 1),
+            res => panic!("{:?}", res),
+        }
+
+        let mut tampered = proof.clone();
+        tampered.proof.r_0[0] += 1;
+        match check(&tampered) {
+            Err(Diagnosis::ProofInvalid {
+                layer: 0,
+                round: 0,
+                detail: Detail::ChallengeMismatch { .. },
+            }) => {}
+            res => panic!("{:?}", res),
+        }
+
+        let mut tampered = proof.clone();
+        let opening = tampered.openings[4].reduced.as_mut().unwrap();
+        opening.columns[0][0] += Scalar::one();
+        match check(&tampered) {
+            Err(Diagnosis::ProofInvalid {
+                layer: 5,
+                round: 0,
+                detail: Detail::OpeningRejected(_),
+            }) => {}
+            res => panic!("{:?}", res),
+        }
+
+        let mut tampered = proof.clone();
+        tampered.openings[1].claim = None;
+        assert_eq!(check(&tampered), Err(invalid(1, 0, Detail::Malformed)));
+        let mut tampered = proof.clone();
+        tampered.openings[5].reduced = tampered.openings[4].reduced.clone();
+        assert_eq!(check(&tampered), Err(invalid(5, 0, Detail::Malformed)));
+        let mut tampered = proof;
+        tampered.roots.pop();
+        assert_eq!(check(&tampered), Err(invalid(0, 0, Detail::Malformed)));
+    }
+
+    // Phase two on any number of threads, in any order, is the layers proved one by one.
+    #[test]
+    fn test_phase_two_is_deterministic() {
+        let (circuit, inputs) = (six_layer_circuit(2), inputs(2));
+        let mut gkr = GKR::init(circuit);
+        let run = gkr.fork_layers(&inputs).unwrap();
+        let one_by_one = (0..run.layers())
+            .map(|i| gkr.prove_forked_layer(&run, i))
+            .collect::<Vec<_>>();
+        let expected = run.clone().merge(one_by_one).to_bytes();
+        for threads in 1..=4 {
+            let layers = prove_on_threads(&gkr, &run, threads);
+            assert_eq!(
+                run.clone().merge(layers).to_bytes(),
+                expected,
+                "{} threads",
+                threads
+            );
+        }
+        assert_eq!(gkr.prove_forked(&inputs).unwrap().to_bytes(), expected);
+
+        #[cfg(feature = "parallel")]
+        for threads in [1, 2, 4] {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            let proof = pool.install(|| gkr.prove_forked(&inputs).unwrap());
+            assert_eq!(proof.to_bytes(), expected, "a pool of {} threads", threads);
+        }
+    }
+
+    // The sequential prover against phase two on 1 thread and on all of them, eg:
+    //      cargo test -p GKR --release -- --ignored bench_forked_prove
+    #[test]
+    #[ignore]
+    fn bench_forked_prove() {
+        use std::time::Instant;
+
+        let (circuit, inputs) = (six_layer_circuit(4), inputs(4));
+        let mut gkr = GKR::init(circuit);
+        let start = Instant::now();
+        gkr.prove(&inputs, || 1);
+        let sequential = start.elapsed();
+
+        let start = Instant::now();
+        let run = gkr.fork_layers(&inputs).unwrap();
+        let phase_one = start.elapsed();
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        let mut times = vec![];
+        for threads in [1, threads] {
+            let start = Instant::now();
+            prove_on_threads(&gkr, &run, threads);
+            times.push(start.elapsed());
+        }
+        println!(
+            "sequential: {:?}, forked phase one: {:?}, phase two on 1 thread: {:?}, on {} threads: {:?}, speedup: {:.2}",
+            sequential,
+            phase_one,
+            times[0],
+            threads,
+            times[1],
+            times[0].as_secs_f64() / times[1].as_secs_f64()
+        );
+    }
+}
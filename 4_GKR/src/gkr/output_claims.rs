@@ -0,0 +1,355 @@
+// Batch the claims W_0(z_j) = v_j on some output wires into a single claim W_0(r) = m, so the
+// layer reductions start from one claim, see `GKR::prove_from_claim` and
+// `GkrVerifierState::from_claim`.
+//
+// D, the table of the claimed outputs, is held by P and V both, so the messages of the reduction
+// are computed from D by either one and absorbed, then each one is shifted to pass the checks of
+// the claims, eg: q(0) = v_1 and q(1) = v_2 below. So a false v_j leaves m off W̃_0(r), and the
+// sumcheck of layer 0 rejects it, except with probability O(k_0 / |F|).
+//
+//  - one claim: it's returned as it is, nothing is absorbed.
+//  - two claims: the line l(X) = z_1 + X·(z_2 - z_1), and q(X) = D̃(l(X)) of degree k_0, sent
+//    as q(0), ..., q(k_0). Then t is squeezed, r = l(t) and m = q(t).
+//  - more: γ is squeezed, and the sum-check of ∑_b E(b)·D(b) = ∑ γ^j·v_j, where
+//    E(b) = ∑ γ^j·eq(z_j, b). It ends at E(r)·D̃(r), and m = claim / E(r).
+//
+// The points on the hypercube, aka the wire indices, are read from D directly and put in E as
+// one entry, rather than by the MLE and the eq table of the point. The result is the same.
+use crate::poly::{EvalTable, Polynomial};
+use crate::utils::eq_table;
+use bls12_381::Scalar;
+use sumcheck::claim::{Claim, ClaimTracker, PolyId};
+use sumcheck::scalar_utils::to_bit;
+use Fiat_Shamir::codec::{encode_scalar, encode_scalars};
+use Fiat_Shamir::Transcript;
+
+// The index of the point, MSB first, if it's on the hypercube.
+fn hypercube_index(point: &[Scalar]) -> Option<usize> {
+    point
+        .iter()
+        .try_fold(0, |index, x| Some((index << 1) | to_bit(x)? as usize))
+}
+
+// D̃(z), a lookup for a wire index.
+fn eval_at(table: &EvalTable, z: &[Scalar], fast: bool) -> Scalar {
+    match hypercube_index(z).filter(|_| fast) {
+        Some(index) => table.evals[index],
+        None => table.evaluate(z),
+    }
+}
+
+// Return (r, m), where W_0(r) = m holds iff W_0(z_j) = v_j for every claim, whp.
+pub fn batch_output_claims<T: Transcript>(
+    claims: &[(Vec<Scalar>, Scalar)],
+    w0_table: &[Scalar],
+    transcript: &mut T,
+) -> (Vec<Scalar>, Scalar) {
+    reduce(claims, w0_table, transcript, true)
+}
+
+// Same as `batch_output_claims`, on the claims of W_0 in the tracker, which are replaced by the
+// batched one. It's returned too, eg: for `GKR::prove_from_claim`.
+pub fn batch_tracked_claims<T: Transcript>(
+    tracker: &mut ClaimTracker,
+    w_0: PolyId,
+    w0_table: &[Scalar],
+    transcript: &mut T,
+) -> Claim {
+    let claims = tracker
+        .pop_all(w_0)
+        .into_iter()
+        .map(|c| (c.point, c.value))
+        .collect::<Vec<_>>();
+    let (r, m) = batch_output_claims(&claims, w0_table, transcript);
+    let claim = Claim::new(w_0, r, m);
+    tracker.push(claim.clone());
+    claim
+}
+
+fn reduce<T: Transcript>(
+    claims: &[(Vec<Scalar>, Scalar)],
+    w0_table: &[Scalar],
+    transcript: &mut T,
+    fast: bool,
+) -> (Vec<Scalar>, Scalar) {
+    assert!(!claims.is_empty(), "no claims to batch");
+    let table = EvalTable::new(w0_table.to_vec());
+    for (z, _) in claims.iter() {
+        assert_eq!(
+            z.len(),
+            table.var_num,
+            "the points must have k_0 coordinates"
+        );
+    }
+    if claims.len() == 1 {
+        return claims[0].clone();
+    }
+
+    for (z, v) in claims.iter() {
+        transcript.append(&encode_scalars(z));
+        transcript.append(&encode_scalar(v));
+    }
+    match claims {
+        [(z_1, v_1), (z_2, v_2)] => {
+            reduce_on_line((z_1, *v_1), (z_2, *v_2), &table, transcript, fast)
+        }
+        _ => reduce_by_sumcheck(claims, table, transcript, fast),
+    }
+}
+
+fn reduce_on_line<T: Transcript>(
+    (z_1, v_1): (&Vec<Scalar>, Scalar),
+    (z_2, v_2): (&Vec<Scalar>, Scalar),
+    table: &EvalTable,
+    transcript: &mut T,
+    fast: bool,
+) -> (Vec<Scalar>, Scalar) {
+    let line =
+        |x: Scalar| -> Vec<Scalar> { z_1.iter().zip(z_2).map(|(a, b)| a + (b - a) * x).collect() };
+
+    // q(x) = D̃(l(x)), shifted by the line through the errors at 0 and 1.
+    let (d_1, d_2) = (
+        v_1 - eval_at(table, z_1, fast),
+        v_2 - eval_at(table, z_2, fast),
+    );
+    let domains = (0..=table.var_num as u64)
+        .map(Scalar::from)
+        .collect::<Vec<_>>();
+    let evals = domains
+        .iter()
+        .map(|x| match x {
+            x if *x == Scalar::zero() => v_1,
+            x if *x == Scalar::one() => v_2,
+            x => table.evaluate(&line(*x)) + (Scalar::one() - x) * d_1 + x * d_2,
+        })
+        .collect::<Vec<_>>();
+    transcript.append(&encode_scalars(&evals));
+
+    let t = transcript.challenge_scalar();
+    let q = Polynomial::lagrange_interpolate(domains, evals);
+    (line(t), q.evaluate(t))
+}
+
+fn reduce_by_sumcheck<T: Transcript>(
+    claims: &[(Vec<Scalar>, Scalar)],
+    mut table: EvalTable,
+    transcript: &mut T,
+    fast: bool,
+) -> (Vec<Scalar>, Scalar) {
+    let gamma = transcript.challenge_scalar();
+    let mut e = vec![Scalar::zero(); table.evals.len()];
+    let mut claim = Scalar::zero();
+    let mut gamma_j = Scalar::one();
+    for (z, v) in claims.iter() {
+        match hypercube_index(z).filter(|_| fast) {
+            Some(index) => e[index] += gamma_j,
+            None => {
+                for (e_b, eq_b) in e.iter_mut().zip(eq_table(z)) {
+                    *e_b += gamma_j * eq_b;
+                }
+            }
+        }
+        claim += gamma_j * v;
+        gamma_j *= gamma;
+    }
+
+    // E·D has degree 2 in each variable, so g_j is sent as g_j(0), g_j(1), g_j(2).
+    let mut e = EvalTable::new(e);
+    let domains = (0..3u64).map(Scalar::from).collect::<Vec<_>>();
+    let mut r = Vec::with_capacity(table.var_num);
+    while table.var_num > 0 {
+        let ((e_lo, e_hi), (d_lo, d_hi)) = (e.split_halves(), table.split_halves());
+        let (mut g_0, mut g_1, mut g_2) = (Scalar::zero(), Scalar::zero(), Scalar::zero());
+        for b in 0..e_lo.len() {
+            g_0 += e_lo[b] * d_lo[b];
+            g_1 += e_hi[b] * d_hi[b];
+            g_2 += (e_hi[b].double() - e_lo[b]) * (d_hi[b].double() - d_lo[b]);
+        }
+        // g_j + (claim - g_j(0) - g_j(1))·X, so g_j(0) + g_j(1) = claim.
+        let shift = claim - g_0 - g_1;
+        let evals = vec![g_0, g_1 + shift, g_2 + shift.double()];
+        transcript.append(&encode_scalars(&evals));
+
+        let r_j = transcript.challenge_scalar();
+        claim = Polynomial::lagrange_interpolate(domains.clone(), evals).evaluate(r_j);
+        e.bind_first(r_j);
+        table.bind_first(r_j);
+        r.push(r_j);
+    }
+
+    // claim = E(r)·D̃(r), and E(r) = 0 with probability m / |F| over r.
+    let e_r_inv = Option::<Scalar>::from(e.evals[0].invert()).expect("E(r) is zero");
+    (r, claim * e_r_inv)
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod test {
+    use super::*;
+    use crate::arithmetic::layered_circuit::Ops::{ADD, MUL};
+    use crate::arithmetic::layered_circuit::{CircuitConfig, Layer};
+    use crate::gkr::{Detail, Diagnosis, GkrVerifierState, GKR};
+    use ff::PrimeField;
+    use Fiat_Shamir::default::Keccak256Transcript;
+
+    // 4 outputs, so k_0 = 2.
+    fn circuit() -> CircuitConfig {
+        let layer_0 = Layer {
+            gates: vec![ADD(0, 3), ADD(2, 3), MUL(0, 1), MUL(1, 2)],
+            var_num: 2,
+        };
+        let layer_1 = Layer {
+            gates: vec![MUL(0, 0), MUL(1, 1), MUL(1, 2), MUL(3, 3)],
+            var_num: 2,
+        };
+        CircuitConfig {
+            layers: vec![layer_0, layer_1],
+            input_var_num: 2,
+            depth: 3,
+        }
+    }
+
+    fn inputs() -> Vec<Scalar> {
+        [1, 2, 1, 4].iter().map(|v| Scalar::from_u128(*v)).collect()
+    }
+
+    fn wire(index: usize) -> Vec<Scalar> {
+        vec![
+            Scalar::from((index >> 1) as u64),
+            Scalar::from((index & 1) as u64),
+        ]
+    }
+
+    // p is linear, so u and v differ in one coordinate in each layer, see `diagnose`.
+    const CHALLENGES: [usize; 10] = [5, 2, 5, 7, 4, 2, 6, 2, 9, 3];
+
+    // P proves the layers from the reduced claim, and V checks them from it.
+    fn prove_and_verify(claims: &[(Vec<Scalar>, Scalar)]) -> Diagnosis {
+        let circuit = circuit();
+        let outputs = circuit.trace_evaluation(&inputs()).outputs();
+        let mut transcript = Keccak256Transcript::default();
+        let (r_0, m_0) = batch_output_claims(claims, &outputs, &mut transcript);
+
+        let mut challenges = CHALLENGES.iter().copied();
+        let proof = GKR::init(circuit.clone())
+            .prove_from_claim(&inputs(), &r_0, || challenges.next().unwrap());
+
+        let mut state = match GkrVerifierState::from_claim(&circuit, &r_0, m_0, &proof) {
+            Ok(state) => state,
+            Err(diagnosis) => return diagnosis,
+        };
+        for layer_proof in proof.layers.iter() {
+            if let Err(diagnosis) = state.verify_layer(layer_proof) {
+                return diagnosis;
+            }
+        }
+        state.finish(&inputs())
+    }
+
+    #[test]
+    fn test_two_wires() {
+        let outputs = circuit().trace_evaluation(&inputs()).outputs();
+        let claims = vec![(wire(1), outputs[1]), (wire(3), outputs[3])];
+
+        let (r, m) = batch_output_claims(&claims, &outputs, &mut Keccak256Transcript::default());
+        assert_eq!(m, EvalTable::new(outputs.clone()).evaluate(&r));
+        assert_eq!(prove_and_verify(&claims), Diagnosis::LooksConsistent);
+    }
+
+    #[test]
+    fn test_tracked_claims() {
+        let outputs = circuit().trace_evaluation(&inputs()).outputs();
+        let mut tracker = ClaimTracker::new();
+        let (w_0, w_1) = (tracker.register("W_0"), tracker.register("W_1"));
+        tracker.push(Claim::new(w_0, wire(1), outputs[1]));
+        tracker.push(Claim::new(w_1, wire(0), Scalar::one()));
+        tracker.push(Claim::new(w_0, wire(3), outputs[3]));
+
+        let claim = batch_tracked_claims(
+            &mut tracker,
+            w_0,
+            &outputs,
+            &mut Keccak256Transcript::default(),
+        );
+        let claims = vec![(wire(1), outputs[1]), (wire(3), outputs[3])];
+        let (r, m) = batch_output_claims(&claims, &outputs, &mut Keccak256Transcript::default());
+        assert_eq!(claim, Claim::new(w_0, r, m));
+
+        // the two claims on W_0 are replaced by the batched one, the one on W_1 is left alone.
+        assert_eq!(tracker.remaining().len(), 2);
+        assert_eq!(tracker.pop(w_0), Some(claim));
+        assert_eq!(tracker.pop(w_1).map(|c| c.point), Some(wire(0)));
+        tracker.assert_all_discharged();
+    }
+
+    #[test]
+    fn test_more_claims() {
+        let outputs = circuit().trace_evaluation(&inputs()).outputs();
+        let table = EvalTable::new(outputs.clone());
+        let z = vec![Scalar::from(3), Scalar::from(5)];
+        let claims = vec![
+            (wire(0), outputs[0]),
+            (z.clone(), table.evaluate(&z)),
+            (wire(2), outputs[2]),
+        ];
+
+        let (r, m) = batch_output_claims(&claims, &outputs, &mut Keccak256Transcript::default());
+        assert_eq!(m, table.evaluate(&r));
+        assert_eq!(prove_and_verify(&claims), Diagnosis::LooksConsistent);
+    }
+
+    #[test]
+    fn test_false_claim() {
+        let outputs = circuit().trace_evaluation(&inputs()).outputs();
+        let z = vec![Scalar::from(3), Scalar::from(5)];
+        let batches = [
+            vec![(wire(1), outputs[1]), (wire(3), outputs[3] + Scalar::one())],
+            vec![
+                (wire(0), outputs[0]),
+                (z, Scalar::from(7)),
+                (wire(2), outputs[2]),
+            ],
+        ];
+        for claims in batches.iter() {
+            // P is honest on W_0(r), so the claim it declares is off the reduced one, which is
+            // caught before g_1(0) + g_1(1) is.
+            assert!(matches!(
+                prove_and_verify(claims),
+                Diagnosis::ProofInvalid {
+                    layer: 0,
+                    round: 0,
+                    detail: Detail::ClaimedSumMismatch { .. },
+                }
+            ));
+        }
+    }
+
+    #[test]
+    fn test_wire_indices_and_field_points() {
+        let table = (1..=8u64).map(Scalar::from).collect::<Vec<_>>();
+        let z = vec![Scalar::from(3), Scalar::from(5), Scalar::from(2)];
+        let wire =
+            |index: usize| (0..3).map(move |i| Scalar::from(((index >> (2 - i)) & 1) as u64));
+        let batches = [
+            vec![
+                (wire(1).collect(), Scalar::from(2)),
+                (wire(6).collect(), Scalar::from(9)),
+            ],
+            vec![
+                (wire(1).collect(), Scalar::from(2)),
+                (z, Scalar::from(4)),
+                (wire(6).collect(), Scalar::from(7)),
+            ],
+        ];
+        for claims in batches.iter() {
+            let (mut fast, mut general) = (
+                Keccak256Transcript::default(),
+                Keccak256Transcript::default(),
+            );
+            assert_eq!(
+                reduce(claims, &table, &mut fast, true),
+                reduce(claims, &table, &mut general, false)
+            );
+            assert_eq!(fast.challenge(), general.challenge());
+        }
+    }
+}
@@ -0,0 +1,670 @@
+// The messages of P and V in a run of GKR, kept so that the run can be replayed, see `diagnose`.
+//
+// Layout (version 4) in the encoding of `Fiat_Shamir::codec`:
+//
+//      | field       | encoding                                            |
+//      |-------------|-----------------------------------------------------|
+//      | version     | u8 = 4                                              |
+//      | protocol    | digest, of `protocol_id` of the circuit             |
+//      | r_0         | len: u64, then each one as u64                      |
+//      | layers      | len: u64, then each layer from 0 to d-1 as bytes:   |
+//      |             |   var_num: u64, the rounds v_r                      |
+//      |             |   claimed_sum: scalar, m_i                          |
+//      |             |   degrees: len: u64, then each d_j as u64           |
+//      |             |   round_polys: len: u64, then each one as scalars   |
+//      |             |   challenges: len: u64, then each one as u64        |
+//      |             |   p_poly: scalars                                   |
+//      |             |   t: u64                                            |
+//
+// The polys are their coeffs, as they are absorbed. No trailing bytes are allowed, in the proof
+// nor in a layer. Each layer is length-prefixed, so the layers after the ones a reader needs are
+// never read, see `read_prefix`.
+//
+// The metadata of a layer is the instance P proved: V checks it against its own one before any
+// round, see `Detail::VarNumMismatch`. A layer has var_num degrees and round polys, and g_j has
+// the d_j + 1 coeffs of its degree, which is checked as it's read.
+//
+// The circuit is the params of GKR, so a proof of another circuit is rejected by `from_bytes_for`
+// right after the version, and by V before any layer. Version 1 had no protocol, version 2 had no
+// length in front of the layers, and version 3 had no metadata.
+//
+// Version 5 is version 4 without the fields V derives from the ones before, see
+// `ProofCompression::Minimal`. The version is the mode, so a proof says which one it's in:
+//
+//      | field       | version 5                                           |
+//      |-------------|-----------------------------------------------------|
+//      | claimed_sum | in layer 0 only, m_i+1 = p_i(t_i) after             |
+//      | round_polys | len: u64, then each one as scalars, [a_1, ..., a_d] |
+//
+// a_0 of g_j is (claim - a_1 - ... - a_d) / 2, the one of g_j(0) + g_j(1) = claim, where the
+// claim is m_i in round 1, then g_j-1(r_j-1). So the decoder rebuilds the layers in the order V
+// checks them, and a layer needs its v_r challenges to be rebuilt. The mode is the format only,
+// so it's not in `protocol_id`, and the decoded proof is the same in both modes.
+//
+// Version 6 is a proof of the forked mode, which is another protocol, so it's not a `GkrProof`,
+// see `forked::ForkedGkrProof`.
+use crate::arithmetic::layered_circuit::CircuitConfig;
+use crate::poly::Polynomial;
+use bls12_381::Scalar;
+use ni_sumcheck::mlpc::MlRoot;
+use std::io::Read;
+use Fiat_Shamir::codec::{
+    encode_bytes, encode_digest, encode_scalar, encode_scalars, encode_u64, CodecError, Decoder,
+};
+use Fiat_Shamir::protocol::ProtocolId;
+use Fiat_Shamir::statement::{Statement, StatementError};
+
+pub const GKR_PROOF_VERSION: u8 = 4;
+pub const GKR_PROOF_VERSION_MINIMAL: u8 = 5;
+pub const GKR_PROOF_VERSION_FORKED: u8 = 6;
+
+// The fields of a proof in its bytes, see the layout.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProofCompression {
+    // All of them, as V checks them, eg: to inspect a rejected proof.
+    #[default]
+    Full,
+    // Without the ones V derives: m_i of the layers after 0 and a_0 of each round poly. A proof
+    // which fails these checks can't be written in this mode, it's decoded to another one.
+    Minimal,
+}
+
+impl ProofCompression {
+    fn version(&self) -> u8 {
+        match self {
+            ProofCompression::Full => GKR_PROOF_VERSION,
+            ProofCompression::Minimal => GKR_PROOF_VERSION_MINIMAL,
+        }
+    }
+
+    fn read(decoder: &mut Decoder) -> Result<Self, CodecError> {
+        match decoder.read_u8()? {
+            GKR_PROOF_VERSION => Ok(ProofCompression::Full),
+            GKR_PROOF_VERSION_MINIMAL => Ok(ProofCompression::Minimal),
+            v => Err(CodecError::UnknownVersion(v)),
+        }
+    }
+}
+
+// The sumcheck of layer i, which reduces the claim m_i on W_i(r_i) to m_i+1 on W_i+1(r_i+1).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LayerProof {
+    pub var_num: usize,               // v_r, the rounds of the layer
+    pub claimed_sum: Scalar,          // m_i
+    pub degrees: Vec<usize>,          // the declared degree d_j of each g_j
+    pub round_polys: Vec<Polynomial>, // g_1, ..., g_v
+    pub challenges: Vec<usize>,       // r_1, ..., r_v, which is (u, v)
+    pub p_poly: Polynomial,           // p(0) = W_i+1(u), p(1) = W_i+1(v)
+    pub t: usize,                     // r_i+1 = l(t), m_i+1 = p(t)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GkrProof {
+    pub protocol: [u8; 32], // the digest of `protocol_id`
+    pub r_0: Vec<usize>,
+    pub layers: Vec<LayerProof>, // from layer 0 to d-1.
+}
+
+// The circuit as the params, in the bytes of `CircuitConfig::to_bytes`, so the params digest is
+// `CircuitConfig::circuit_digest`.
+pub fn protocol_id(circuit: &CircuitConfig) -> ProtocolId {
+    ProtocolId::new("GKR", "gkr", GKR_PROOF_VERSION, &circuit.to_bytes())
+}
+
+// The label of the root of the committed inputs in `statement`.
+pub const INPUTS_LABEL: &str = "inputs";
+
+// The statement of a run on committed inputs: the circuit, and the root of W_d.
+pub fn statement(circuit: &CircuitConfig, inputs: &MlRoot) -> Statement {
+    Statement::new(protocol_id(circuit))
+        .with_circuit(circuit.circuit_digest())
+        .with_mle(INPUTS_LABEL, *inputs)
+}
+
+// The root to open W_d(r_d) against, once the statement is checked to be of this circuit.
+pub fn input_root(
+    circuit: &CircuitConfig,
+    statement: &Statement,
+) -> Result<MlRoot, StatementError> {
+    statement.check_protocol(&protocol_id(circuit))?;
+    if statement.circuit()? != circuit.circuit_digest() {
+        return Err(StatementError::ProtocolMismatch);
+    }
+    Ok(MlRoot::from(statement.mle(INPUTS_LABEL)?))
+}
+
+fn encode_usizes(bytes: &mut Vec<u8>, values: &[usize]) {
+    bytes.extend(encode_u64(values.len() as u64));
+    for v in values.iter() {
+        bytes.extend(encode_u64(*v as u64));
+    }
+}
+
+fn decode_usizes(decoder: &mut Decoder) -> Result<Vec<usize>, CodecError> {
+    let len = decoder.read_len(8)?;
+    (0..len).map(|_| decoder.read_usize()).collect()
+}
+
+fn decode_poly(decoder: &mut Decoder) -> Result<Polynomial, CodecError> {
+    Ok(Polynomial::from_coeffs(decoder.read_scalars()?))
+}
+
+impl LayerProof {
+    // The degrees of the polys as they are, eg: for P to declare them.
+    pub fn degrees_of(round_polys: &[Polynomial]) -> Vec<usize> {
+        round_polys
+            .iter()
+            .map(|g_j| g_j.num_coeffs().saturating_sub(1))
+            .collect()
+    }
+
+    // The claimed sum is left out of a minimal layer after layer 0, see `ProofCompression`.
+    fn to_bytes(&self, compression: ProofCompression, with_sum: bool) -> Vec<u8> {
+        let minimal = compression == ProofCompression::Minimal;
+        let mut bytes = encode_u64(self.var_num as u64).to_vec();
+        if !minimal || with_sum {
+            bytes.extend(encode_scalar(&self.claimed_sum));
+        }
+        encode_usizes(&mut bytes, &self.degrees);
+        bytes.extend(encode_u64(self.round_polys.len() as u64));
+        for g_j in self.round_polys.iter() {
+            let coeffs = g_j.coeffs();
+            let sent = if minimal {
+                coeffs.get(1..).unwrap_or(&[])
+            } else {
+                &coeffs
+            };
+            bytes.extend(encode_scalars(sent));
+        }
+        encode_usizes(&mut bytes, &self.challenges);
+        bytes.extend(encode_scalars(&self.p_poly.coeffs()));
+        bytes.extend(encode_u64(self.t as u64));
+        bytes
+    }
+
+    // m_i+1 = p(t), the claim the next layer reduces.
+    fn reduced_claim(&self) -> Scalar {
+        self.p_poly.evaluate(Scalar::from(self.t as u64))
+    }
+
+    // The bytes of the layer without its length, which must be read to the end. A minimal layer
+    // is rebuilt from `claim`, the reduced claim of the layer before, or from its own claimed sum
+    // in layer 0.
+    fn decode(
+        mut decoder: Decoder,
+        compression: ProofCompression,
+        claim: Option<Scalar>,
+    ) -> Result<Self, CodecError> {
+        let minimal = compression == ProofCompression::Minimal;
+        let var_num = decoder.read_usize()?;
+        let claimed_sum = match claim {
+            Some(claim) if minimal => claim,
+            _ => decoder.read_scalar()?,
+        };
+        let offset = decoder.offset();
+        let degrees = decode_usizes(&mut decoder)?;
+        if degrees.len() != var_num {
+            return Err(CodecError::ShapeMismatch { offset });
+        }
+        let offset = decoder.offset();
+        let rounds = decoder.read_len(8)?;
+        if rounds != var_num {
+            return Err(CodecError::ShapeMismatch { offset });
+        }
+        // the coeffs as sent, without a_0 if minimal.
+        let sent = degrees
+            .iter()
+            .map(|d_j| {
+                let offset = decoder.offset();
+                let coeffs = decoder.read_scalars()?;
+                let len = if minimal { *d_j } else { d_j.saturating_add(1) };
+                if coeffs.len() != len {
+                    return Err(CodecError::ShapeMismatch { offset });
+                }
+                Ok(coeffs)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let offset = decoder.offset();
+        let challenges = decode_usizes(&mut decoder)?;
+        let round_polys = if minimal {
+            if challenges.len() != var_num {
+                return Err(CodecError::ShapeMismatch { offset });
+            }
+            rebuild_rounds(claimed_sum, sent, &challenges)
+        } else {
+            sent.into_iter().map(Polynomial::from_coeffs).collect()
+        };
+        let p_poly = decode_poly(&mut decoder)?;
+        let t = decoder.read_usize()?;
+        decoder.finish()?;
+        Ok(Self {
+            var_num,
+            claimed_sum,
+            degrees,
+            round_polys,
+            challenges,
+            p_poly,
+            t,
+        })
+    }
+}
+
+// The round polys of a minimal layer in the order V checks them: g_j from its a_1, ..., a_d and
+// the claim g_j-1(r_j-1), then the claim moves to g_j(r_j), see `GkrVerifierState::check_rounds`.
+fn rebuild_rounds(claim: Scalar, sent: Vec<Vec<Scalar>>, challenges: &[usize]) -> Vec<Polynomial> {
+    let two_inv = Scalar::from(2).invert().unwrap();
+    let mut claim = claim;
+    sent.into_iter()
+        .zip(challenges)
+        .map(|(tail, r_j)| {
+            let a_0 = (claim - tail.iter().sum::<Scalar>()) * two_inv;
+            let mut coeffs = vec![a_0];
+            coeffs.extend(tail);
+            let g_j = Polynomial::from_coeffs(coeffs);
+            claim = g_j.evaluate(Scalar::from(*r_j as u64));
+            g_j
+        })
+        .collect()
+}
+
+// The bytes of a proof from a reader, which count as they are read, eg: for the offsets of the
+// errors. Any error of the reader is taken as the end of the bytes.
+struct ProofReader<R: Read> {
+    reader: R,
+    offset: usize,
+}
+
+impl<R: Read> ProofReader<R> {
+    fn read_exact<const N: usize>(&mut self) -> Result<[u8; N], CodecError> {
+        let mut buf = [0u8; N];
+        self.reader
+            .read_exact(&mut buf)
+            .map_err(|_| CodecError::UnexpectedEnd)?;
+        self.offset += N;
+        Ok(buf)
+    }
+
+    fn read_u64(&mut self) -> Result<u64, CodecError> {
+        self.read_exact::<8>().map(u64::from_le_bytes)
+    }
+
+    fn read_usize(&mut self) -> Result<usize, CodecError> {
+        let offset = self.offset;
+        let v = self.read_u64()?;
+        usize::try_from(v).map_err(|_| CodecError::LengthTooLarge { offset, len: v })
+    }
+
+    // The bytes of a section, which are never allocated ahead of the ones read, as its length
+    // is untrusted.
+    fn read_section(&mut self) -> Result<Vec<u8>, CodecError> {
+        let len = self.read_u64()?;
+        let mut bytes = vec![];
+        (&mut self.reader)
+            .take(len)
+            .read_to_end(&mut bytes)
+            .map_err(|_| CodecError::UnexpectedEnd)?;
+        if bytes.len() as u64 != len {
+            return Err(CodecError::UnexpectedEnd);
+        }
+        self.offset += bytes.len();
+        Ok(bytes)
+    }
+}
+
+impl GkrProof {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with(ProofCompression::Full)
+    }
+
+    pub fn to_bytes_with(&self, compression: ProofCompression) -> Vec<u8> {
+        let mut bytes = vec![compression.version()];
+        bytes.extend(encode_digest(&self.protocol));
+        self.encode_body(&mut bytes, compression);
+        bytes
+    }
+
+    // r_0 and the layers, which follow the protocol, eg: in a proof of the forked mode too.
+    pub(crate) fn encode_body(&self, bytes: &mut Vec<u8>, compression: ProofCompression) {
+        encode_usizes(bytes, &self.r_0);
+        bytes.extend(encode_u64(self.layers.len() as u64));
+        for (i, layer) in self.layers.iter().enumerate() {
+            bytes.extend(encode_bytes(&layer.to_bytes(compression, i == 0)));
+        }
+    }
+
+    // A proof in either mode, which is read from the version.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        let mut decoder = Decoder::new(bytes);
+        let compression = ProofCompression::read(&mut decoder)?;
+        let protocol = decoder.read_digest()?;
+        Self::decode_body(protocol, compression, decoder)
+    }
+
+    // The same as `from_bytes`, but the proof must be of the expected protocol, eg:
+    // `protocol_id` of the circuit, which is checked right after the version.
+    pub fn from_bytes_for(bytes: &[u8], expected: &ProtocolId) -> Result<Self, CodecError> {
+        let mut decoder = Decoder::new(bytes);
+        let compression = ProofCompression::read(&mut decoder)?;
+        decoder.read_protocol(expected)?;
+        Self::decode_body(expected.digest(), compression, decoder)
+    }
+
+    // The mode of the bytes of a proof, from its version.
+    pub fn compression_of(bytes: &[u8]) -> Result<ProofCompression, CodecError> {
+        ProofCompression::read(&mut Decoder::new(bytes))
+    }
+
+    fn decode_body(
+        protocol: [u8; 32],
+        compression: ProofCompression,
+        mut decoder: Decoder,
+    ) -> Result<Self, CodecError> {
+        let proof = Self::decode_layers(protocol, compression, &mut decoder)?;
+        decoder.finish()?;
+        Ok(proof)
+    }
+
+    // The body of `encode_body`, the bytes after it are left to the caller.
+    pub(crate) fn decode_layers(
+        protocol: [u8; 32],
+        compression: ProofCompression,
+        decoder: &mut Decoder,
+    ) -> Result<Self, CodecError> {
+        let r_0 = decode_usizes(decoder)?;
+
+        // each layer has its length, var_num, 4 lengths and t at least, and m_i if it's sent.
+        let sum = match compression {
+            ProofCompression::Full => 32,
+            ProofCompression::Minimal => 0,
+        };
+        let len = decoder.read_len(7 * 8 + sum)?;
+        let mut layers = Vec::with_capacity(len);
+        let mut claim = None;
+        for _ in 0..len {
+            let layer = LayerProof::decode(decoder.read_section()?, compression, claim)?;
+            claim = Some(layer.reduced_claim());
+            layers.push(layer);
+        }
+
+        Ok(Self {
+            protocol,
+            r_0,
+            layers,
+        })
+    }
+
+    // The header and the first `layers` layers of a proof from a reader, eg: to check a proof
+    // down to a layer with `verify_prefix`. The layers after them are neither read nor checked,
+    // so the reader may be cut short there. A proof with fewer layers is returned whole.
+    pub fn read_prefix<R: Read>(reader: R, layers: usize) -> Result<Self, CodecError> {
+        let mut reader = ProofReader { reader, offset: 0 };
+        let header = reader.read_exact::<33>()?;
+        let mut decoder = Decoder::new(&header);
+        let compression = ProofCompression::read(&mut decoder)?;
+        let protocol = decoder.read_digest()?;
+
+        let mut r_0 = vec![];
+        for _ in 0..reader.read_u64()? {
+            r_0.push(reader.read_usize()?);
+        }
+        let len = reader.read_u64()?.min(layers as u64) as usize;
+        let mut layers = vec![];
+        let mut claim = None;
+        for _ in 0..len {
+            let section = reader.read_section()?;
+            let layer = LayerProof::decode(Decoder::new(&section), compression, claim)?;
+            claim = Some(layer.reduced_claim());
+            layers.push(layer);
+        }
+        Ok(Self {
+            protocol,
+            r_0,
+            layers,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bls12_381::Scalar;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    fn gen_proof() -> GkrProof {
+        let poly = |coeffs: &[u64]| {
+            Polynomial::from_coeffs(coeffs.iter().map(|c| Scalar::from(*c)).collect())
+        };
+        GkrProof {
+            protocol: [7; 32],
+            r_0: vec![3],
+            layers: vec![LayerProof {
+                var_num: 2,
+                claimed_sum: Scalar::from(11),
+                degrees: vec![2, 2],
+                round_polys: vec![poly(&[1, 2, 3]), poly(&[4, 5, 6])],
+                challenges: vec![5, 7],
+                p_poly: poly(&[8, 9]),
+                t: 4,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_to_and_from_bytes() {
+        let proof = gen_proof();
+        let bytes = proof.to_bytes();
+        // version, protocol, r_0, layers, the layer's length, var_num, m_i, 2 degrees, 2 rounds,
+        // challenges, p_poly, t
+        assert_eq!(
+            bytes.len(),
+            1 + 32 + 16 + 8 + 8 + 8 + 32 + 24 + (8 + 2 * (8 + 96)) + 24 + (8 + 64) + 8
+        );
+        assert_eq!(GkrProof::from_bytes(&bytes), Ok(proof));
+    }
+
+    #[test]
+    fn test_from_bytes_rejects() {
+        let bytes = gen_proof().to_bytes();
+        // the section of the layer is longer than the bytes left, see `Decoder::read_section`.
+        assert_eq!(
+            GkrProof::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(CodecError::LengthTooLarge {
+                offset: 57,
+                len: 384
+            })
+        );
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            GkrProof::from_bytes(&trailing),
+            Err(CodecError::TrailingBytes(1))
+        );
+        let mut version = bytes;
+        version[0] = 1;
+        assert_eq!(
+            GkrProof::from_bytes(&version),
+            Err(CodecError::UnknownVersion(1))
+        );
+    }
+
+    // The declared shape of a layer is checked as it's read, at the offset in the layer.
+    #[test]
+    fn test_from_bytes_rejects_shape() {
+        let layer_bytes = |layer: &LayerProof| {
+            let mut proof = gen_proof();
+            proof.layers = vec![layer.clone()];
+            GkrProof::from_bytes(&proof.to_bytes())
+        };
+        let layer = &gen_proof().layers[0];
+
+        // g_2 has 3 coeffs, not the 2 of degree 1.
+        let mut lower = layer.clone();
+        lower.degrees[1] = 1;
+        let g_2 = 8 + 32 + 24 + 8 + (8 + 96);
+        assert_eq!(
+            layer_bytes(&lower),
+            Err(CodecError::ShapeMismatch { offset: g_2 })
+        );
+        // 3 degrees for 2 rounds.
+        let mut longer = layer.clone();
+        longer.degrees.push(2);
+        assert_eq!(
+            layer_bytes(&longer),
+            Err(CodecError::ShapeMismatch { offset: 40 })
+        );
+        // 3 rounds are declared, of which 2 are sent.
+        let mut more = layer.clone();
+        more.var_num = 3;
+        more.degrees.push(2);
+        assert_eq!(
+            layer_bytes(&more),
+            Err(CodecError::ShapeMismatch {
+                offset: 8 + 32 + 32
+            })
+        );
+    }
+
+    // 3 layers of `gen_proof`, rebuilt so that they pass the checks the minimal mode leaves out.
+    fn gen_minimal_proof() -> GkrProof {
+        let mut proof = gen_proof();
+        proof.layers = vec![proof.layers[0].clone(); 3];
+        GkrProof::from_bytes(&proof.to_bytes_with(ProofCompression::Minimal)).unwrap()
+    }
+
+    #[test]
+    fn test_minimal_to_and_from_bytes() {
+        let proof = gen_minimal_proof();
+        let bytes = proof.to_bytes_with(ProofCompression::Minimal);
+        assert_eq!(bytes[0], GKR_PROOF_VERSION_MINIMAL);
+        assert_eq!(GkrProof::from_bytes(&bytes), Ok(proof.clone()));
+        assert_eq!(
+            GkrProof::compression_of(&bytes),
+            Ok(ProofCompression::Minimal)
+        );
+        assert_eq!(
+            GkrProof::compression_of(&proof.to_bytes()),
+            Ok(ProofCompression::Full)
+        );
+        // m_1, m_2 and a_0 of the 3 * 2 rounds.
+        assert_eq!(proof.to_bytes().len() - bytes.len(), 32 * (2 + 3 * 2));
+
+        // g_j(0) + g_j(1) = g_j-1(r_j-1), from m_0, and m_i+1 = p_i(t).
+        for (i, layer) in proof.layers.iter().enumerate() {
+            let mut claim = match i {
+                0 => Scalar::from(11),
+                _ => proof.layers[i - 1].reduced_claim(),
+            };
+            assert_eq!(layer.claimed_sum, claim);
+            for (g_j, r_j) in layer.round_polys.iter().zip(layer.challenges.iter()) {
+                assert_eq!(
+                    g_j.evaluate(Scalar::zero()) + g_j.evaluate(Scalar::one()),
+                    claim
+                );
+                claim = g_j.evaluate(Scalar::from(*r_j as u64));
+            }
+        }
+        for layers in 0..=3 {
+            let prefix = GkrProof::read_prefix(&bytes[..], layers).unwrap();
+            assert_eq!(prefix.layers[..], proof.layers[..layers]);
+        }
+    }
+
+    // A minimal layer is rebuilt from its v_r challenges, so it must have them all.
+    #[test]
+    fn test_minimal_rejects_challenges() {
+        let mut proof = gen_minimal_proof();
+        proof.layers[1].challenges.pop();
+        let bytes = proof.to_bytes_with(ProofCompression::Minimal);
+        let challenges = 8 + 24 + 8 + 2 * (8 + 64);
+        assert_eq!(
+            GkrProof::from_bytes(&bytes),
+            Err(CodecError::ShapeMismatch { offset: challenges })
+        );
+        // the full one is left to V, see `Detail::Malformed`.
+        assert_eq!(GkrProof::from_bytes(&proof.to_bytes()), Ok(proof));
+    }
+
+    // The decoders on malformed minimal bytes: each truncation is an error, and any byte flipped
+    // is an error or a proof which encodes back to the same bytes, never a panic.
+    #[test]
+    fn test_minimal_fuzz() {
+        let decode = |bytes: &[u8]| {
+            catch_unwind(AssertUnwindSafe(|| GkrProof::from_bytes(bytes)))
+                .unwrap_or_else(|_| panic!("from_bytes panicked on {:02x?}", bytes))
+        };
+        let read_prefix = |bytes: &[u8], layers| {
+            catch_unwind(AssertUnwindSafe(|| GkrProof::read_prefix(bytes, layers)))
+                .unwrap_or_else(|_| panic!("read_prefix panicked on {:02x?}", bytes))
+        };
+        let bytes = gen_minimal_proof().to_bytes_with(ProofCompression::Minimal);
+        for len in 0..bytes.len() {
+            assert!(decode(&bytes[..len]).is_err(), "accepted {} bytes", len);
+            assert!(read_prefix(&bytes[..len], 3).is_err(), "read {} bytes", len);
+        }
+        for i in 0..bytes.len() {
+            for mask in [0x01, 0x80, 0xff] {
+                let mut flipped = bytes.clone();
+                flipped[i] ^= mask;
+                let _ = read_prefix(&flipped, 3);
+                if let Ok(proof) = decode(&flipped) {
+                    let compression = GkrProof::compression_of(&flipped).unwrap();
+                    assert_eq!(proof.to_bytes_with(compression), flipped);
+                }
+            }
+        }
+    }
+
+    // Count the bytes read from a slice.
+    struct CountingReader<'a> {
+        bytes: &'a [u8],
+        read: usize,
+    }
+
+    impl Read for CountingReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = (&self.bytes[self.read..]).read(buf)?;
+            self.read += n;
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_read_prefix() {
+        let mut proof = gen_proof();
+        proof.layers = vec![proof.layers[0].clone(); 3];
+        let bytes = proof.to_bytes();
+        let header = 1 + 32 + 16 + 8;
+        let section = 8 + proof.layers[0].to_bytes(ProofCompression::Full, true).len();
+        for layers in 0..=3 {
+            let mut reader = CountingReader {
+                bytes: &bytes,
+                read: 0,
+            };
+            let prefix = GkrProof::read_prefix(&mut reader, layers).unwrap();
+            assert_eq!(prefix.layers[..], proof.layers[..layers]);
+            assert_eq!((prefix.protocol, &prefix.r_0), (proof.protocol, &proof.r_0));
+            // the layers after the prefix are skipped, not even read.
+            assert_eq!(reader.read, header + layers * section);
+        }
+
+        // the bytes may end after the prefix.
+        let cut = &bytes[..header + section];
+        assert_eq!(
+            GkrProof::read_prefix(cut, 1).map(|p| p.layers),
+            Ok(proof.layers[..1].to_vec())
+        );
+        assert_eq!(
+            GkrProof::read_prefix(cut, 2).err(),
+            Some(CodecError::UnexpectedEnd)
+        );
+        // a layer is checked to its end.
+        let mut longer = bytes[..header].to_vec();
+        let mut layer = proof.layers[0].to_bytes(ProofCompression::Full, true);
+        layer.push(0);
+        longer.extend(encode_bytes(&layer));
+        assert_eq!(
+            GkrProof::read_prefix(&longer[..], 1).err(),
+            Some(CodecError::TrailingBytes(1))
+        );
+    }
+}
@@ -1,40 +1,262 @@
-use crate::arithmetic::layered_circuit::CircuitConfig;
-use crate::poly::MPolynomial;
+use crate::arithmetic::layered_circuit::{CircuitConfig, CircuitError, WireValues};
+use crate::gkr_sumcheck::prover::LayerProver;
+use crate::poly::{DenseBudget, EvalTable, MPolynomial};
+use crate::utils::par_map;
 use bls12_381::Scalar;
+use ni_sumcheck::mlpc::{self, MlOpening, MlRoot, ProverState};
+use std::fmt;
+use sumcheck::poly::budget::DEFAULT_MAX_DENSE_VARS;
+use sumcheck::secret::Secret;
+
+// The dense add_i and mult_i of a layer have 2^(k_i + 2*k_i+1) coeffs each, which is 2^30 for
+// a layer of 2^10 gates on 2^10 wires, so they're never built above this, see `DensityPolicy`.
+// It's the max of the default `DenseBudget`, the one of the dense polys of sumcheck.
+pub const MAX_DENSE_VARS: usize = DEFAULT_MAX_DENSE_VARS;
+
+// How P holds add_i and mult_i of each layer, by k_i + 2*k_i+1 of the layer:
+//  - dense: the MLEs, and the round polys are summed up from them, see `LayerProver::new`.
+//  - sparse: the gates only, see `LayerProver::new_sparse`.
+// The proofs are the same either way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DensityPolicy {
+    // dense for every layer, it fails on a layer above `MAX_DENSE_VARS`.
+    ForceDense,
+    ForceSparse,
+    // dense for the layers up to `max_dense_vars`, sparse for the wider ones, eg: a layer is
+    // dense iff the `DenseBudget` of `within` allows it.
+    Auto { max_dense_vars: usize },
+}
+
+impl Default for DensityPolicy {
+    fn default() -> Self {
+        DensityPolicy::Auto { max_dense_vars: 20 }
+    }
+}
+
+impl DensityPolicy {
+    // Auto by the budget, so a layer is dense iff `MPolynomial::lagrange_within` builds it.
+    pub fn within(budget: DenseBudget) -> Self {
+        DensityPolicy::Auto {
+            max_dense_vars: budget.max_vars(),
+        }
+    }
+
+    // The budget of the dense layers: `max_dense_vars` for Auto, which is raised on purpose
+    // above `MAX_DENSE_VARS`, the default one otherwise.
+    pub fn budget(&self) -> DenseBudget {
+        match self {
+            DensityPolicy::Auto { max_dense_vars } => DenseBudget::with_max_vars(*max_dense_vars),
+            _ => DenseBudget::default(),
+        }
+    }
+
+    fn is_dense(&self, var_num: usize) -> bool {
+        match self {
+            DensityPolicy::ForceDense => true,
+            DensityPolicy::ForceSparse => false,
+            DensityPolicy::Auto { max_dense_vars } => var_num <= *max_dense_vars,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProverError {
+    // The dense add_i and mult_i of the layer would take `bytes`, usize::MAX if it overflows.
+    ResourceLimit {
+        layer: usize,
+        var_num: usize,
+        bytes: usize,
+    },
+    // the circuit is malformed, see `CircuitConfig::validate`.
+    InvalidCircuit(CircuitError),
+    // the gates aren't in the canonical order, see `CircuitConfig::canonicalize`.
+    NotCanonical,
+    // W_layer has `len` values, but the circuit declares it over k = `var_num` variables.
+    TableSizeMismatch {
+        layer: usize,
+        var_num: usize,
+        len: usize,
+    },
+}
+
+impl fmt::Display for ProverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProverError::ResourceLimit {
+                layer,
+                var_num,
+                bytes,
+            } => write!(
+                f,
+                "the dense add and mult of layer {} are over {} variables, which take {} bytes",
+                layer, var_num, bytes
+            ),
+            ProverError::InvalidCircuit(e) => write!(f, "invalid circuit: {}", e),
+            ProverError::NotCanonical => write!(
+                f,
+                "the gates of the circuit aren't in the canonical order, canonicalize it first"
+            ),
+            ProverError::TableSizeMismatch {
+                layer,
+                var_num,
+                len,
+            } => write!(
+                f,
+                "W_{} has {} values, but layer {} is declared with k = {}, which takes 2^{}",
+                layer, len, layer, var_num, var_num
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ProverError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProverError::InvalidCircuit(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+// op(r, b, c) with r bound, over the var_num variables of (b, c). r is bound in the table of the
+// evals, x_1 first, and the evals are interpolated back, which is O(2^(|r| + var_num)) as the
+// dense op is.
+fn fix_at(op: &MPolynomial, r: &[Scalar], var_num: usize) -> MPolynomial {
+    let mut table = EvalTable::new(op.hypercube_evaluations());
+    for r_j in r.iter() {
+        table.bind_first(*r_j);
+    }
+    MPolynomial::lagrange_unchecked(var_num, &table.evals)
+}
+
+// 2 dense polys of 2^var_num coeffs.
+fn dense_bytes(var_num: usize) -> usize {
+    u32::try_from(var_num)
+        .ok()
+        .and_then(|var_num| 2usize.checked_pow(var_num))
+        .and_then(|n| n.checked_mul(2 * std::mem::size_of::<Scalar>()))
+        .unwrap_or(usize::MAX)
+}
 
 pub struct Prover {
-    pub inputs: Vec<Scalar>,
-    pub witness: Vec<MPolynomial>, // witness, start from 0 to d (include the input layer(layer_d). len = d+1
+    pub inputs: WireValues,
+    pub witness: Vec<Secret<MPolynomial>>, // witness, start from 0 to d (include the input layer(layer_d). len = d+1
     outputs: Vec<Scalar>,
-    pub ops: Vec<(MPolynomial, MPolynomial)>, // (add,mult) gate mpoly of each layer, start from 0 to d-1. len = d+1
+    // (add,mult) gate mpoly of each layer, start from 0 to d-1, None for the sparse layers.
+    pub ops: Vec<Option<(MPolynomial, MPolynomial)>>,
     depth: usize,
     config: CircuitConfig,
+    committed_inputs: Option<ProverState>,
 }
 
 impl Prover {
     // actual, this is the config.
-    pub fn init(config: CircuitConfig) -> Self {
-        let ops = config.ops_to_mpoly();
-        Self {
-            inputs: vec![],
+    // Check the shape of the circuit, and the size of each dense layer before building any of them.
+    // The circuit must be canonical, P doesn't reorder it, as it would move the outputs.
+    pub fn init_with_policy(
+        config: CircuitConfig,
+        policy: DensityPolicy,
+    ) -> Result<Self, ProverError> {
+        config.validate().map_err(ProverError::InvalidCircuit)?;
+        if !config.is_canonical() {
+            return Err(ProverError::NotCanonical);
+        }
+        let layers = (0..config.depth - 1).collect::<Vec<_>>();
+        let budget = policy.budget();
+        for &i in layers.iter() {
+            let var_num = config.wiring_var_num(i);
+            if policy.is_dense(var_num) && !budget.allows(var_num) {
+                return Err(ProverError::ResourceLimit {
+                    layer: i,
+                    var_num,
+                    bytes: dense_bytes(var_num),
+                });
+            }
+        }
+        let ops = par_map(&layers, |&i| {
+            policy.is_dense(config.wiring_var_num(i)).then(|| {
+                config
+                    .layer_ops(i, &budget)
+                    .expect("the dense layers are in the budget")
+            })
+        });
+
+        Ok(Self {
+            inputs: WireValues::default(),
             witness: vec![],
             outputs: vec![],
             ops,
             depth: config.depth,
             config,
+            committed_inputs: None,
+        })
+    }
+
+    pub(crate) fn config(&self) -> &CircuitConfig {
+        &self.config
+    }
+
+    // P of the sumcheck of layer i from the claim on W_i(r_i), r_i ∈ F^k_i, with the dense add_i
+    // and mult_i if they're built. Either way they're fixed at r_i first, so P's rounds are over
+    // (b, c) only: the dense ones by binding their tables, the sparse ones by the gates, see
+    // `Layer::sparse_wiring_at`.
+    pub(crate) fn layer_prover(&self, i: usize, r_i: &[Scalar], m_i: Scalar) -> LayerProver {
+        let dims = self.config.layer_dims(i);
+        let w_i_plus_1 = (**self.witness.get(i + 1).unwrap()).clone();
+        match self.ops.get(i).unwrap() {
+            Some((add_i, mult_i)) => {
+                let g = (
+                    fix_at(add_i, r_i, dims.v_r()),
+                    fix_at(mult_i, r_i, dims.v_r()),
+                    w_i_plus_1,
+                );
+                LayerProver::new(dims, g, vec![], m_i)
+            }
+            None => {
+                let wiring = self.config.layers[i].sparse_wiring_at(r_i, dims.k_i_plus_1);
+                LayerProver::new_sparse(dims, wiring, w_i_plus_1, m_i)
+            }
         }
     }
 
     // synthesize with inputs to gen witness/advices.
-    pub(crate) fn synthesize(&mut self, input: &Vec<Scalar>) {
+    // The inputs are W_d, so there must be 2^k_d of them, the other layers are padded to 2^k_i.
+    pub(crate) fn synthesize(&mut self, input: &Vec<Scalar>) -> Result<(), ProverError> {
+        let var_num = self.config.input_var_num;
+        if input.len() != 1 << var_num {
+            return Err(ProverError::TableSizeMismatch {
+                layer: self.config.layers.len(),
+                var_num,
+                len: input.len(),
+            });
+        }
         let (witness, outputs) = self.config.witness_to_poly(&input);
-        self.witness = witness;
+        self.inputs = WireValues::copy_of(input);
+        self.witness = witness.into_iter().map(Secret::new).collect();
         self.outputs = outputs;
+        Ok(())
     }
 
     //  P sends a function $D: {0,1}^k_0 → F$ claimed to equal W_0 (the function mapping output gate labels to output values).
+    // W_0 is the outputs, which are public.
     pub fn D_poly(&self) -> MPolynomial {
-        self.witness[0].clone()
+        (*self.witness[0]).clone()
+    }
+
+    // Commit to the inputs, so V needn't hold them.
+    pub fn commit_inputs(&mut self) -> MlRoot {
+        let (root, state) = mlpc::commit(&self.inputs);
+        self.committed_inputs = Some(state);
+        root
+    }
+
+    // Open W_d at r_d, see `mlpc::open`.
+    pub fn open_inputs(&self, r_d: &[Scalar]) -> MlOpening {
+        let state = self
+            .committed_inputs
+            .as_ref()
+            .expect("inputs are not committed");
+        mlpc::open(state, r_d)
     }
 
     pub fn outputs(&self) -> Vec<Scalar> {
@@ -0,0 +1,280 @@
+// The degree of g_j in each round of a layer, which P interpolates g_j at and V bounds d_j by.
+//
+// g_j is of the degree of f_{r_i}(b, c) in the variable of round j, which is the sum of the
+// degrees of the factors in it:
+//      f_{r_i}(b, c) = add(r_i, b, c) * (W(b) + W(c)) + mult(r_i, b, c) * (W(b) * W(c))
+// add and mult are multilinear, so of degree 1 in each variable, and W(b) is of degree 1 in b_m,
+// or 0 if W_i+1 doesn't depend on x_m. The factor orders are:
+//      Joint: f_{r_i} as a whole, of degree 2 in each variable, eg: Uniform(2).
+//      Optimized: each factor by the variables it depends on. W_i+1 doesn't depend on x_m
+//          whatever the inputs are iff the gates g and g ^ bit(x_m) of layer i+1 are the same for
+//          each g, so b_m and c_m are of degree 1. The inputs W_d depend on all of them.
+// eg: layer 1 = [ADD(0, 1), ADD(0, 1), MUL(2, 3), MUL(2, 3)] is the same at g and g ^ 1, so W_1
+// doesn't depend on x_2, the LSB, and layer 0 has the schedule [2, 1, 2, 1] of (b_1, b_2, c_1, c_2).
+//
+// The proofs of the Joint order are the ones of before. The order is in the protocol, see
+// `protocol_id_for`, so V takes the schedule of the proof it's given, and a proof of one order
+// is rejected by the other as of another protocol. So is the proving mode, see `forked`.
+use crate::arithmetic::layered_circuit::CircuitConfig;
+use crate::gkr::proof::{protocol_id, GKR_PROOF_VERSION, GKR_PROOF_VERSION_FORKED};
+use ni_sumcheck::sumcheck::DegreeSchedule;
+use Fiat_Shamir::protocol::ProtocolId;
+
+// f_{r_i}(b, c) is of degree 2 in each variable, so is each g_j of the Joint order.
+pub(crate) const ROUND_DEGREE: usize = 2;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FactorOrder {
+    #[default]
+    Joint,
+    Optimized,
+}
+
+// The protocol of the proofs of the circuit in the order, the one of `protocol_id` for Joint.
+pub fn protocol_id_for(circuit: &CircuitConfig, order: FactorOrder) -> ProtocolId {
+    match order {
+        FactorOrder::Joint => protocol_id(circuit),
+        FactorOrder::Optimized => ProtocolId::new(
+            "GKR",
+            "gkr_optimized",
+            GKR_PROOF_VERSION,
+            &circuit.to_bytes(),
+        ),
+    }
+}
+
+// How the layers are proved: each one from the claim the one before is reduced to, or each one
+// from its own claim on its own sub-transcript, see `forked`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProvingMode {
+    #[default]
+    Sequential,
+    Forked,
+}
+
+// The protocol of the proofs of the circuit in the order and the mode, the one of
+// `protocol_id_for` for Sequential.
+pub fn protocol_id_with(
+    circuit: &CircuitConfig,
+    order: FactorOrder,
+    mode: ProvingMode,
+) -> ProtocolId {
+    let name = match (order, mode) {
+        (_, ProvingMode::Sequential) => return protocol_id_for(circuit, order),
+        (FactorOrder::Joint, ProvingMode::Forked) => "gkr_forked",
+        (FactorOrder::Optimized, ProvingMode::Forked) => "gkr_optimized_forked",
+    };
+    ProtocolId::new("GKR", name, GKR_PROOF_VERSION_FORKED, &circuit.to_bytes())
+}
+
+// The order and the mode of a proof with the protocol digest, None if it's of another circuit.
+pub(crate) fn params_of(
+    circuit: &CircuitConfig,
+    protocol: &[u8; 32],
+) -> Option<(FactorOrder, ProvingMode)> {
+    [ProvingMode::Sequential, ProvingMode::Forked]
+        .into_iter()
+        .flat_map(|mode| [(FactorOrder::Joint, mode), (FactorOrder::Optimized, mode)])
+        .find(|(order, mode)| protocol_id_with(circuit, *order, *mode).digest() == *protocol)
+}
+
+// The order of a sequential proof with the protocol digest, None if it's of another circuit or
+// of the forked mode.
+pub(crate) fn order_of(circuit: &CircuitConfig, protocol: &[u8; 32]) -> Option<FactorOrder> {
+    match params_of(circuit, protocol) {
+        Some((order, ProvingMode::Sequential)) => Some(order),
+        _ => None,
+    }
+}
+
+// The schedule of the 2 * k_i+1 rounds of layer i, (b_1, ..., b_k, c_1, ..., c_k).
+pub fn layer_schedule(circuit: &CircuitConfig, i: usize, order: FactorOrder) -> DegreeSchedule {
+    match order {
+        FactorOrder::Joint => DegreeSchedule::Uniform(ROUND_DEGREE),
+        FactorOrder::Optimized => {
+            let free = free_vars(circuit, i + 1);
+            let degrees = free
+                .iter()
+                .chain(free.iter())
+                .map(|free| if *free { 1 } else { ROUND_DEGREE })
+                .collect();
+            DegreeSchedule::PerRound(degrees)
+        }
+    }
+}
+
+// Whether W_i doesn't depend on x_m, m in [1, k_i], x_1 the MSB of the gate index. A padded gate
+// is zero, so it's the same as another padded one only.
+fn free_vars(circuit: &CircuitConfig, i: usize) -> Vec<bool> {
+    let k = circuit.layer_var_num(i);
+    let layer = match circuit.layers.get(i) {
+        Some(layer) => layer,
+        None => return vec![false; k],
+    };
+    (1..=k)
+        .map(|m| {
+            let bit = 1 << (k - m);
+            (0..1usize << k).all(|g| layer.gates.get(g) == layer.gates.get(g ^ bit))
+        })
+        .collect()
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod test {
+    use super::*;
+    use crate::arithmetic::layered_circuit::Layer;
+    use crate::arithmetic::layered_circuit::Ops::{ADD, MUL};
+    use crate::gkr::{verify, Detail, Diagnosis, GkrProof, GKR};
+    use crate::poly::Polynomial;
+    use bls12_381::Scalar;
+    use ff::PrimeField;
+
+    // layers 1 and 2 are the same at g and g ^ 1, so W_1 and W_2 don't depend on the LSB.
+    fn duplicated_circuit() -> CircuitConfig {
+        let layer_0 = Layer {
+            gates: vec![ADD(0, 2), MUL(0, 2)],
+            var_num: 1,
+        };
+        let layer_1 = Layer {
+            gates: vec![ADD(0, 2), ADD(0, 2), MUL(0, 2), MUL(0, 2)],
+            var_num: 2,
+        };
+        let layer_2 = Layer {
+            gates: vec![ADD(0, 1), ADD(0, 1), MUL(2, 3), MUL(2, 3)],
+            var_num: 2,
+        };
+        CircuitConfig {
+            layers: vec![layer_0, layer_1, layer_2],
+            input_var_num: 2,
+            depth: 4,
+        }
+    }
+
+    fn inputs() -> Vec<Scalar> {
+        [3, 1, 4, 1].iter().map(|v| Scalar::from_u128(*v)).collect()
+    }
+
+    fn prove(order: FactorOrder) -> GkrProof {
+        let mut challenges = (5..).step_by(3);
+        GKR::init(duplicated_circuit())
+            .with_factor_order(order)
+            .prove(&inputs(), || challenges.next().unwrap())
+    }
+
+    fn per_round(degrees: &[usize]) -> DegreeSchedule {
+        DegreeSchedule::PerRound(degrees.to_vec())
+    }
+
+    #[test]
+    fn test_free_vars() {
+        let circuit = duplicated_circuit();
+        assert_eq!(free_vars(&circuit, 1), vec![false, true]);
+        // the inputs
+        assert_eq!(free_vars(&circuit, 3), vec![false, false]);
+        // the same at g and g ^ 2, and a padded gate, which is zero.
+        let mut other = circuit.clone();
+        other.layers[1].gates.swap(1, 2);
+        assert_eq!(free_vars(&other, 1), vec![true, false]);
+        other.layers[1].gates.pop();
+        assert_eq!(free_vars(&other, 1), vec![false, false]);
+    }
+
+    #[test]
+    fn test_layer_schedules() {
+        let circuit = duplicated_circuit();
+        let schedules = (0..3)
+            .map(|i| layer_schedule(&circuit, i, FactorOrder::Optimized))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            schedules,
+            [
+                per_round(&[2, 1, 2, 1]),
+                per_round(&[2, 1, 2, 1]),
+                per_round(&[2, 2, 2, 2])
+            ]
+        );
+        assert_eq!(
+            layer_schedule(&circuit, 0, FactorOrder::Joint),
+            DegreeSchedule::Uniform(2)
+        );
+    }
+
+    #[test]
+    fn test_order_of_protocol() {
+        let circuit = duplicated_circuit();
+        for order in [FactorOrder::Joint, FactorOrder::Optimized] {
+            let digest = protocol_id_for(&circuit, order).digest();
+            assert_eq!(order_of(&circuit, &digest), Some(order));
+            // the forked mode is another protocol of the same order.
+            let forked = protocol_id_with(&circuit, order, ProvingMode::Forked).digest();
+            assert_ne!(forked, digest);
+            assert_eq!(order_of(&circuit, &forked), None);
+            assert_eq!(
+                params_of(&circuit, &forked),
+                Some((order, ProvingMode::Forked))
+            );
+        }
+        let mut other = circuit.clone();
+        other.layers[0].gates.pop();
+        let digest = protocol_id_for(&other, FactorOrder::Optimized).digest();
+        assert_eq!(order_of(&circuit, &digest), None);
+    }
+
+    #[test]
+    fn test_optimized_order() {
+        let circuit = duplicated_circuit();
+        let outputs = circuit.evaluate(&inputs());
+        let (joint, optimized) = (prove(FactorOrder::Joint), prove(FactorOrder::Optimized));
+        for proof in [&joint, &optimized] {
+            assert!(verify(&circuit, &inputs(), &outputs, proof).is_ok());
+        }
+        // the same round polys, each degree-1 one without its zero X^2 coeff.
+        for (i, (j, o)) in joint.layers.iter().zip(optimized.layers.iter()).enumerate() {
+            assert_eq!(j.degrees, vec![2; 4]);
+            let schedule = layer_schedule(&circuit, i, FactorOrder::Optimized);
+            let expected = (1..=4).map(|j| schedule.degree(j)).collect::<Vec<_>>();
+            assert_eq!(o.degrees, expected);
+            for (g_j, g_o) in j.round_polys.iter().zip(o.round_polys.iter()) {
+                assert_eq!(g_j.evaluate(Scalar::from(7)), g_o.evaluate(Scalar::from(7)));
+            }
+        }
+        // 4 degree-1 rounds in layers 0 and 1, a scalar less each.
+        let delta = joint.to_bytes().len() - optimized.to_bytes().len();
+        assert_eq!(delta, 4 * 32);
+    }
+
+    #[test]
+    fn test_degree_above_schedule() {
+        let circuit = duplicated_circuit();
+        let outputs = circuit.evaluate(&inputs());
+        // round 2 of layer 0 is declared of degree 1, P sends it of degree 2 with the same sum.
+        let mut proof = prove(FactorOrder::Optimized);
+        let g_2 = &mut proof.layers[0].round_polys[1];
+        let mut coeffs = g_2.coeffs();
+        coeffs[0] -= Scalar::one();
+        coeffs[1] += Scalar::one();
+        coeffs.push(Scalar::one());
+        *g_2 = Polynomial::from_coeffs(coeffs);
+        proof.layers[0].degrees[1] = 2;
+        let expected = Diagnosis::ProofInvalid {
+            layer: 0,
+            round: 2,
+            detail: Detail::DegreeTooHigh {
+                declared: 2,
+                bound: 1,
+            },
+        };
+        assert_eq!(verify(&circuit, &inputs(), &outputs, &proof), Err(expected));
+
+        // a proof of the Joint order declares degree 2 there, whatever its protocol says.
+        let mut joint = prove(FactorOrder::Joint);
+        joint.protocol = protocol_id_for(&circuit, FactorOrder::Optimized).digest();
+        assert!(matches!(
+            verify(&circuit, &inputs(), &outputs, &joint),
+            Err(Diagnosis::ProofInvalid {
+                detail: Detail::DegreeTooHigh { .. },
+                ..
+            })
+        ));
+    }
+}
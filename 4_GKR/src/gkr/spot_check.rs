@@ -0,0 +1,462 @@
+// Positional openings of the wire tables, to spot-check a few values of W_i when V rejects,
+// eg: is the table of P wrong, or are the claims wrong while the table is right?
+//
+// It's a vector commitment of the table W_i over {0,1}^k_i (MSB first, see `convert_to_binary`),
+// apart from the MLE claims of the protocol, which are never opened this way. The tree is the
+// `MerkleTree` of `Merkle_tree_commitment` over the values, whose root is bound to its height
+// k_i + 1, so the opening of a smaller table can't pass for the one of W_i.
+//
+// A `TableOpening` of several positions has a proof per position, sorted and deduplicated,
+// which V checks as one batch, see `MultiVerifier`: a node the paths share is hashed once, eg:
+// all the positions of a table hash each node once, as the commit does.
+//  - the positions must be in 0..2^k_i, P panics on the others, and V rejects them.
+//  - a position may be asked for twice, its value is returned twice but proved once. V rejects
+//    the openings with two values for the same position.
+//  - no position opens nothing, and V rejects an empty opening, as it binds nothing to the root.
+//
+// The spot check of layer i, see `SpotCheckRequest`: P opens W_i at some gates and W_i+1 at the
+// wires they read, then V computes each gate from its inputs and compares it with W_i.
+//  - a gate differs: the table of P is wrong there, the first one is named with both values.
+//  - all agree: the tables are the ones of the circuit at these points, so the claims of the
+//    layer are off for another reason, eg: the add_i and mult_i P used.
+use crate::arithmetic::layered_circuit::CircuitConfig;
+use crate::arithmetic::layered_circuit::Ops::{ADD, MUL};
+use crate::gkr::diagnose::Diagnosis;
+use crate::poly::EvalTable;
+use crate::utils::convert_to_binary;
+use bls12_381::Scalar;
+use std::fmt;
+pub use Merkle_tree_commitment::merkle_tree::hasher::Digest;
+use Merkle_tree_commitment::merkle_tree::proof::MerkleProof;
+use Merkle_tree_commitment::merkle_tree::{MerkleTree, MultiVerifier};
+
+// The committed table, which P keeps to open it.
+#[derive(Debug, Clone)]
+pub struct CommittedTable {
+    table: EvalTable,
+    tree: MerkleTree,
+}
+
+// The values at the positions, in the order asked for, and the proofs of the positions sorted
+// and deduplicated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableOpening {
+    pub var_num: usize,
+    pub values: Vec<Scalar>,
+    pub proofs: Vec<MerkleProof>,
+}
+
+impl CommittedTable {
+    pub fn commit(table: &EvalTable) -> (Digest, CommittedTable) {
+        let committed = CommittedTable {
+            table: table.clone(),
+            tree: MerkleTree::commit(&table.evals),
+        };
+        (committed.root(), committed)
+    }
+
+    pub fn root(&self) -> Digest {
+        self.tree.root_hash()
+    }
+
+    pub fn table(&self) -> &EvalTable {
+        &self.table
+    }
+
+    pub fn open_positions(&self, positions: &[usize]) -> TableOpening {
+        assert!(!positions.is_empty(), "no position to open");
+        let size = self.table.evals.len();
+        if let Some(position) = positions.iter().find(|p| **p >= size) {
+            panic!("position {} is out of the {} points", position, size);
+        }
+
+        let mut indices = positions.to_vec();
+        indices.sort_unstable();
+        indices.dedup();
+        TableOpening {
+            var_num: self.table.var_num,
+            values: positions.iter().map(|p| self.table.evals[*p]).collect(),
+            proofs: indices
+                .iter()
+                .map(|i| self.tree.open_by_index(*i))
+                .collect(),
+        }
+    }
+}
+
+// The values are the ones of the table committed by root at the positions, eg: the values of
+// the opening itself, or the ones V expects.
+pub fn verify_positions(
+    root: &Digest,
+    positions: &[usize],
+    values: &[Scalar],
+    opening: &TableOpening,
+) -> bool {
+    let var_num = opening.var_num;
+    if positions.is_empty()
+        || positions.len() != values.len()
+        || var_num >= usize::BITS as usize
+        || positions.iter().any(|p| p >> var_num != 0)
+    {
+        return false;
+    }
+
+    let mut leaves = positions
+        .iter()
+        .copied()
+        .zip(values.iter())
+        .collect::<Vec<_>>();
+    leaves.sort_unstable_by_key(|(p, _)| *p);
+    if leaves
+        .windows(2)
+        .any(|w| w[0].0 == w[1].0 && w[0].1 != w[1].1)
+    {
+        return false;
+    }
+    leaves.dedup_by_key(|(p, _)| *p);
+    if leaves.len() != opening.proofs.len()
+        || opening
+            .proofs
+            .iter()
+            .any(|proof| proof.children.len() != var_num)
+    {
+        return false;
+    }
+
+    let mut batch = MultiVerifier::new();
+    for ((p, value), proof) in leaves.into_iter().zip(opening.proofs.iter()) {
+        batch.add(*root, p, value, proof);
+    }
+    batch.check().is_ok()
+}
+
+// The points of layer i to open: the gates of W_i, and the wires of W_i+1 they read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpotCheckRequest {
+    pub layer: usize,
+    pub gates: Vec<usize>,
+    pub wires: Vec<usize>,
+}
+
+impl SpotCheckRequest {
+    pub fn new(circuit: &CircuitConfig, layer: usize, gates: &[usize]) -> Self {
+        let layer_i = circuit.layers.get(layer).expect("no such gate layer");
+        let wires = gates
+            .iter()
+            .flat_map(|j| match layer_i.gates.get(*j) {
+                Some(ADD(left, right) | MUL(left, right)) => [*left, *right],
+                None => panic!("gate {} is out of the {} gates", j, layer_i.gates.len()),
+            })
+            .collect();
+        Self {
+            layer,
+            gates: gates.to_vec(),
+            wires,
+        }
+    }
+
+    // Every gate of the layer V rejects, eg: the one of `diagnose`. None if it's not a layer of
+    // gates, as the inputs are held by V.
+    pub fn of_diagnosis(circuit: &CircuitConfig, diagnosis: &Diagnosis) -> Option<Self> {
+        match diagnosis {
+            Diagnosis::ProofInvalid { layer, .. } if *layer < circuit.layers.len() => {
+                let gates = (0..circuit.layers[*layer].gates.len()).collect::<Vec<_>>();
+                Some(Self::new(circuit, *layer, &gates))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SpotCheck {
+    // The opening of W_table doesn't verify against its root, or it's of another size.
+    OpeningInvalid {
+        table: usize,
+    },
+    // W_layer(point) is `got`, but the gate of its inputs in W_layer+1 is `expected`.
+    TableMismatch {
+        layer: usize,
+        point: usize,
+        var_num: usize,
+        expected: Scalar,
+        got: Scalar,
+    },
+    // The tables agree with the gates at every opened point.
+    TablesConsistent {
+        layer: usize,
+    },
+}
+
+impl fmt::Display for SpotCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpotCheck::OpeningInvalid { table } => {
+                write!(f, "the opening of W_{} doesn't match its root", table)
+            }
+            SpotCheck::TableMismatch {
+                layer,
+                point,
+                var_num,
+                expected,
+                got,
+            } => write!(
+                f,
+                "W_{} at {:?} (gate {}) is {:?} in the table of P, but its gate gives {:?}",
+                layer,
+                convert_to_binary(var_num, *point),
+                point,
+                got,
+                expected
+            ),
+            SpotCheck::TablesConsistent { layer } => write!(
+                f,
+                "W_{} and W_{} agree with the gates at the opened points",
+                layer,
+                layer + 1
+            ),
+        }
+    }
+}
+
+// Check the openings of `request` against the roots of W_i and W_i+1, then each gate.
+pub fn spot_check(
+    circuit: &CircuitConfig,
+    request: &SpotCheckRequest,
+    roots: (&Digest, &Digest),
+    openings: (&TableOpening, &TableOpening),
+) -> SpotCheck {
+    let i = request.layer;
+    assert_eq!(
+        *request,
+        SpotCheckRequest::new(circuit, i, &request.gates),
+        "the wires are not the inputs of the gates"
+    );
+    let (gates, wires) = openings;
+    for (table, root, positions, opening) in [
+        (i, roots.0, &request.gates, gates),
+        (i + 1, roots.1, &request.wires, wires),
+    ] {
+        if opening.var_num != circuit.layer_var_num(table)
+            || !verify_positions(root, positions, &opening.values, opening)
+        {
+            return SpotCheck::OpeningInvalid { table };
+        }
+    }
+
+    let layer_i = &circuit.layers[i];
+    for (k, (j, got)) in request.gates.iter().zip(gates.values.iter()).enumerate() {
+        let (left, right) = (wires.values[2 * k], wires.values[2 * k + 1]);
+        let expected = match layer_i.gates[*j] {
+            ADD(..) => left + right,
+            MUL(..) => left * right,
+        };
+        if expected != *got {
+            return SpotCheck::TableMismatch {
+                layer: i,
+                point: *j,
+                var_num: layer_i.var_num,
+                expected,
+                got: *got,
+            };
+        }
+    }
+    SpotCheck::TablesConsistent { layer: i }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod test {
+    use super::*;
+    use crate::arithmetic::layered_circuit::Layer;
+    use crate::gkr::{diagnose, Detail, GKR};
+    use crate::poly::Polynomial;
+    use ff::PrimeField;
+    use sumcheck::poly::eval_table::random_table;
+
+    #[test]
+    fn test_open_positions() {
+        let table = random_table(10);
+        let (root, committed) = CommittedTable::commit(&table);
+        let all = (0..1 << 10).collect::<Vec<_>>();
+        for positions in [vec![517], vec![0, 1, 2, 511, 512, 1000, 1023], all] {
+            let opening = committed.open_positions(&positions);
+            let values = positions
+                .iter()
+                .map(|p| table.evals[*p])
+                .collect::<Vec<_>>();
+            assert_eq!(opening.values, values);
+            assert!(verify_positions(&root, &positions, &values, &opening));
+
+            // a value, a sibling or the root off by one.
+            let mut values = values;
+            values[positions.len() / 2] += Scalar::one();
+            assert!(!verify_positions(&root, &positions, &values, &opening));
+            let mut tampered = opening.clone();
+            tampered.proofs[0].children[0] += Scalar::one();
+            assert!(!verify_positions(
+                &root,
+                &positions,
+                &opening.values,
+                &tampered
+            ));
+            let (another, _) = CommittedTable::commit(&random_table(10));
+            assert!(!verify_positions(
+                &another,
+                &positions,
+                &opening.values,
+                &opening
+            ));
+        }
+
+        // a proof per distinct position, each one of a full path.
+        let opening = committed.open_positions(&[517, 3, 517]);
+        assert_eq!(opening.proofs.len(), 2);
+        assert!(opening.proofs.iter().all(|p| p.children.len() == 10));
+    }
+
+    #[test]
+    fn test_positions_edge_cases() {
+        let table = random_table(3);
+        let (root, committed) = CommittedTable::commit(&table);
+
+        // a duplicate is proved once, and must have the same value both times.
+        let opening = committed.open_positions(&[5, 2, 5]);
+        assert_eq!(
+            opening,
+            TableOpening {
+                values: vec![table.evals[5], table.evals[2], table.evals[5]],
+                ..committed.open_positions(&[2, 5])
+            }
+        );
+        assert!(verify_positions(
+            &root,
+            &[5, 2, 5],
+            &opening.values,
+            &opening
+        ));
+        let mut values = opening.values.clone();
+        values[2] += Scalar::one();
+        assert!(!verify_positions(&root, &[5, 2, 5], &values, &opening));
+
+        // out of range, empty, or of a table of another size.
+        let opening = committed.open_positions(&[7]);
+        assert!(!verify_positions(&root, &[8], &opening.values, &opening));
+        assert!(!verify_positions(&root, &[], &[], &opening));
+        let mut shorter = opening.clone();
+        shorter.var_num = 2;
+        shorter.proofs[0].children.pop();
+        shorter.proofs[0].index = 3;
+        assert!(!verify_positions(&root, &[3], &opening.values, &shorter));
+
+        // a proof missing, or one of another position.
+        let opening = committed.open_positions(&[2, 5]);
+        let mut missing = opening.clone();
+        missing.proofs.pop();
+        assert!(!verify_positions(&root, &[2, 5], &opening.values, &missing));
+        let mut moved = opening.clone();
+        moved.proofs[1] = committed.open_positions(&[4]).proofs[0].clone();
+        assert!(!verify_positions(&root, &[2, 5], &opening.values, &moved));
+    }
+
+    #[test]
+    #[should_panic(expected = "position 8 is out of the 8 points")]
+    fn test_open_out_of_range() {
+        let (_, committed) = CommittedTable::commit(&random_table(3));
+        committed.open_positions(&[1, 8]);
+    }
+
+    // sample from Figure 4.12, W_1 = [1, 4, 2, 16].
+    fn simple_circuit() -> CircuitConfig {
+        let layer_1 = Layer {
+            gates: vec![MUL(0, 0), MUL(1, 1), MUL(1, 2), MUL(3, 3)],
+            var_num: 2,
+        };
+        let output_layer = Layer {
+            gates: vec![MUL(0, 1), MUL(2, 3)],
+            var_num: 1,
+        };
+        CircuitConfig {
+            layers: vec![output_layer, layer_1],
+            input_var_num: 2,
+            depth: 3,
+        }
+    }
+
+    fn open(committed: &(Digest, CommittedTable), positions: &[usize]) -> (Digest, TableOpening) {
+        (committed.0, committed.1.open_positions(positions))
+    }
+
+    // V rejects layer 1, then asks P to open W_1 at its gates and W_2 at their inputs: the
+    // W_1 of an honest P agrees with the gates, the one which took 3 for gate 2 doesn't.
+    #[test]
+    fn test_spot_check_of_diagnosis() {
+        let circuit = simple_circuit();
+        let inputs = [1, 2, 1, 4].map(Scalar::from_u128).to_vec();
+        let outputs = vec![Scalar::from_u128(4), Scalar::from_u128(32)];
+        let mut gkr = GKR::init(circuit.clone());
+        let mut last = 0;
+        let mut proof = gkr.prove(&inputs, || {
+            last += 1;
+            last
+        });
+        let g_2 = &proof.layers[1].round_polys[1];
+        proof.layers[1].round_polys[1] = g_2 + &Polynomial::constant(Scalar::one());
+        let diagnosis = diagnose(&circuit, &inputs, &outputs, &proof);
+        assert!(matches!(
+            diagnosis,
+            Diagnosis::ProofInvalid {
+                layer: 1,
+                detail: Detail::RoundSum { .. },
+                ..
+            }
+        ));
+
+        let request = SpotCheckRequest::of_diagnosis(&circuit, &diagnosis).unwrap();
+        assert_eq!(request.gates, vec![0, 1, 2, 3]);
+        assert_eq!(request.wires, vec![0, 0, 1, 1, 1, 2, 3, 3]);
+        let check = |w_1: &(Digest, CommittedTable), w_2: &(Digest, CommittedTable)| {
+            let (root_1, gates) = open(w_1, &request.gates);
+            let (root_2, wires) = open(w_2, &request.wires);
+            spot_check(&circuit, &request, (&root_1, &root_2), (&gates, &wires))
+        };
+
+        let (w_1, w_2) = (gkr.commit_wires(1), gkr.commit_wires(2));
+        assert_eq!(w_2.1.table().evals, inputs);
+        assert_eq!(check(&w_1, &w_2), SpotCheck::TablesConsistent { layer: 1 });
+
+        let mut table = w_1.1.table().clone();
+        table.evals[2] = Scalar::from_u128(3);
+        let inconsistent = CommittedTable::commit(&table);
+        let report = check(&inconsistent, &w_2);
+        assert_eq!(
+            report,
+            SpotCheck::TableMismatch {
+                layer: 1,
+                point: 2,
+                var_num: 2,
+                expected: Scalar::from_u128(2),
+                got: Scalar::from_u128(3),
+            }
+        );
+        assert!(report.to_string().starts_with("W_1 at [1, 0] (gate 2) is "));
+
+        // the opening of W_2 against the root of W_1.
+        let (root_1, gates) = open(&w_1, &request.gates);
+        let (_, wires) = open(&w_2, &request.wires);
+        assert_eq!(
+            spot_check(&circuit, &request, (&root_1, &root_1), (&gates, &wires)),
+            SpotCheck::OpeningInvalid { table: 2 }
+        );
+
+        // the inputs are held by V, there's nothing to open.
+        let at_inputs = Diagnosis::ProofInvalid {
+            layer: 2,
+            round: 0,
+            detail: Detail::InputEval {
+                claim: Scalar::one(),
+                expected: Scalar::zero(),
+            },
+        };
+        assert_eq!(SpotCheckRequest::of_diagnosis(&circuit, &at_inputs), None);
+    }
+}
@@ -0,0 +1,759 @@
+// The checks of V layer by layer, so the verification can stop after any layer and resume later
+// from a `StateCheckpoint`, eg: one layer per step of a verifier with a bounded work per step.
+//
+// The state after layer i is the claim m_i+1 on W_i+1(r_i+1), which is all V keeps from the
+// layers before. The challenges of GKR are in the proof, so the checkpoint has no transcript.
+//
+//      | checkpoint | value                                          |
+//      |------------|------------------------------------------------|
+//      | binding    | `proof_binding` of `GkrProof::to_bytes`        |
+//      | round      | the next layer, d when only the inputs are left |
+//      | transcript | empty                                          |
+//      | claims     | m_i, then r_i                                  |
+use crate::arithmetic::layered_circuit::CircuitConfig;
+use crate::gkr::diagnose::{Detail, Diagnosis};
+use crate::gkr::proof::{GkrProof, LayerProof};
+use crate::gkr::schedule::{layer_schedule, order_of, FactorOrder};
+use crate::poly::EvalTable;
+use bls12_381::Scalar;
+use sumcheck::claim::{Claim, ClaimTracker, PolyId};
+use sumcheck::cost::{as_verifier, CostReport, CostSink};
+use Fiat_Shamir::checkpoint::{proof_binding, CheckpointError, StateCheckpoint};
+
+pub(crate) fn to_scalars(r: &[usize]) -> Vec<Scalar> {
+    r.iter().map(|r_i| Scalar::from(*r_i as u64)).collect()
+}
+
+pub(crate) fn invalid(layer: usize, round: usize, detail: Detail) -> Diagnosis {
+    Diagnosis::ProofInvalid {
+        layer,
+        round,
+        detail,
+    }
+}
+
+// The proof must be of this circuit, before any layer is checked, return the factor order it's
+// of, see `schedule`.
+// V takes the circuit as it is, a proof is of the canonical one, see `CircuitConfig::canonicalize`.
+fn check_protocol<S: CostSink>(
+    circuit: &CircuitConfig,
+    proof: &GkrProof,
+    sink: &mut S,
+) -> Result<FactorOrder, Diagnosis> {
+    // the digest of the circuit, and the one of the id.
+    sink.count(CostReport::hashes(2));
+    order_of(circuit, &proof.protocol).ok_or(Diagnosis::ProtocolMismatch)
+}
+
+// The claims V reduced an accepted proof to, eg: to open W_d at r_d with a commitment elsewhere.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerifiedGkr {
+    pub points: Vec<Vec<Scalar>>, // r_0, ..., r_d, the point of the claim on each W_i
+    pub input_claim: Scalar,      // m_d = W_d(r_d)
+}
+
+// The claims of `verify` from the proof only, without any check, eg: to inspect a proof cheaply.
+// The challenges of GKR are in the proof, so it's the reduction r_i+1 = l(t), m_i+1 = p(t) of
+// each layer. The shape of the proof is assumed to be the one of the circuit.
+pub fn derive_challenges_only(circuit: &CircuitConfig, proof: &GkrProof) -> VerifiedGkr {
+    assert_eq!(proof.layers.len(), circuit.layers.len());
+    let mut points = Vec::with_capacity(proof.layers.len() + 1);
+    points.push(to_scalars(&proof.r_0));
+    let mut input_claim = Scalar::zero();
+    for (i, layer_proof) in proof.layers.iter().enumerate() {
+        let challenges = to_scalars(&layer_proof.challenges);
+        let (u, v) = challenges.split_at(circuit.layer_dims(i).k_i_plus_1);
+        let t = Scalar::from(layer_proof.t as u64);
+        points.push(u.iter().zip(v).map(|(u, v)| u + (v - u) * t).collect());
+        input_claim = layer_proof.p_poly.evaluate(t);
+    }
+    VerifiedGkr {
+        points,
+        input_claim,
+    }
+}
+
+// The claim W_layer(point) = value which V reduced the layers before to, see `verify_prefix`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LayerClaim {
+    pub layer: usize,
+    pub point: Vec<Scalar>,
+    pub value: Scalar,
+}
+
+pub struct GkrVerifierState<'a> {
+    circuit: &'a CircuitConfig,
+    order: FactorOrder, // of the proof, which bounds the degrees of its rounds.
+    binding: [u8; 32],
+    layer: usize,
+    r_i: Vec<Scalar>,
+    m_i: Scalar,
+}
+
+impl<'a> GkrVerifierState<'a> {
+    // m_0 = D(r_0), D is the claimed outputs.
+    pub fn new(
+        circuit: &'a CircuitConfig,
+        outputs: &[Scalar],
+        proof: &GkrProof,
+    ) -> Result<Self, Diagnosis> {
+        Self::new_with_sink(circuit, outputs, proof, &mut ())
+    }
+
+    // The same as `new`, and the ops are counted into the sink, see `sumcheck::cost`.
+    pub fn new_with_sink<S: CostSink>(
+        circuit: &'a CircuitConfig,
+        outputs: &[Scalar],
+        proof: &GkrProof,
+        sink: &mut S,
+    ) -> Result<Self, Diagnosis> {
+        Self::new_prefix_with_sink(circuit, outputs, proof, circuit.layers.len(), sink)
+    }
+
+    // The same as `new_with_sink` for a proof of the first `layers` layers at least, eg: the one
+    // of `GkrProof::read_prefix`. The layers after them may be left out of the proof.
+    pub(crate) fn new_prefix_with_sink<S: CostSink>(
+        circuit: &'a CircuitConfig,
+        outputs: &[Scalar],
+        proof: &GkrProof,
+        layers: usize,
+        sink: &mut S,
+    ) -> Result<Self, Diagnosis> {
+        let order = check_protocol(circuit, proof, sink)?;
+        if proof.r_0.len() != circuit.layer_var_num(0)
+            || proof.layers.len() < layers
+            || proof.layers.len() > circuit.layers.len()
+        {
+            return Err(invalid(0, 0, Detail::Malformed));
+        }
+        let r_i = to_scalars(&proof.r_0);
+        let m_i = EvalTable::new(outputs.to_vec()).evaluate(&r_i);
+        sink.count(CostReport::table_evaluate(r_i.len()) + CostReport::hashes(1));
+        Ok(Self {
+            circuit,
+            order,
+            binding: proof_binding(&proof.to_bytes()),
+            layer: 0,
+            r_i,
+            m_i,
+        })
+    }
+
+    // Start from the claim W_0(r_0) = m_0 instead, eg: the one of `batch_output_claims`, for a
+    // proof of `GKR::prove_from_claim`, which has no r_0.
+    pub fn from_claim(
+        circuit: &'a CircuitConfig,
+        r_0: &[Scalar],
+        m_0: Scalar,
+        proof: &GkrProof,
+    ) -> Result<Self, Diagnosis> {
+        let order = check_protocol(circuit, proof, &mut ())?;
+        if !proof.r_0.is_empty()
+            || r_0.len() != circuit.layer_var_num(0)
+            || proof.layers.len() != circuit.layers.len()
+        {
+            return Err(invalid(0, 0, Detail::Malformed));
+        }
+        Ok(Self {
+            circuit,
+            order,
+            binding: proof_binding(&proof.to_bytes()),
+            layer: 0,
+            r_i: r_0.to_vec(),
+            m_i: m_0,
+        })
+    }
+
+    // Start at layer i from the claim W_i(r_i) = m_i of a proof of the order, whatever the layers
+    // before are, eg: a layer of the forked mode, whose claim is opened rather than reduced to.
+    pub(crate) fn at_layer(
+        circuit: &'a CircuitConfig,
+        order: FactorOrder,
+        binding: [u8; 32],
+        (layer, r_i, m_i): (usize, Vec<Scalar>, Scalar),
+    ) -> Self {
+        assert!(
+            layer < circuit.layers.len(),
+            "layer {} is out of the circuit",
+            layer
+        );
+        Self {
+            circuit,
+            order,
+            binding,
+            layer,
+            r_i,
+            m_i,
+        }
+    }
+
+    // The next layer to verify, d when only the inputs are left.
+    pub fn layer(&self) -> usize {
+        self.layer
+    }
+
+    // The claim W_i(r_i) = m_i on the next layer i, W_d(r_d) = m_d when it's done.
+    pub fn claim(&self) -> (&[Scalar], Scalar) {
+        (&self.r_i, self.m_i)
+    }
+
+    pub fn layer_claim(&self) -> LayerClaim {
+        LayerClaim {
+            layer: self.layer,
+            point: self.r_i.clone(),
+            value: self.m_i,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.layer == self.circuit.layers.len()
+    }
+
+    // Check the sumcheck of the next layer i, and reduce the claim to W_i+1(r_i+1). The state is
+    // unchanged on a failure.
+    pub fn verify_layer(&mut self, layer_proof: &LayerProof) -> Result<(), Diagnosis> {
+        self.verify_layer_with_sink(layer_proof, &mut ())
+    }
+
+    // Each round is O(deg g_j), whatever the size of the layer is: V only checks the sum and
+    // moves the claim to g_j(r_j). add_i and mult_i are evaluated once, at the end, from the
+    // gates, see `Layer::eval_add_mle`. The dense wiring polys are never built, see
+    // `sumcheck::cost::as_verifier`.
+    pub fn verify_layer_with_sink<S: CostSink>(
+        &mut self,
+        layer_proof: &LayerProof,
+        sink: &mut S,
+    ) -> Result<(), Diagnosis> {
+        assert!(!self.is_done(), "all the layers are verified");
+        as_verifier(|| self.check_layer(layer_proof, sink))
+    }
+
+    fn check_layer<S: CostSink>(
+        &mut self,
+        layer_proof: &LayerProof,
+        sink: &mut S,
+    ) -> Result<(), Diagnosis> {
+        let i = self.layer;
+        let layer_i = &self.circuit.layers[i];
+        let dims = self.circuit.layer_dims(i);
+        self.check_metadata(layer_proof)?;
+        let challenges = to_scalars(&layer_proof.challenges);
+        let claim = self.check_rounds(layer_proof, &challenges, sink)?;
+
+        // check: g_v(r_v) = add(r_i,u,v)(W(u) + W(v)) + mult(r_i,u,v)(W(u) * W(v)),
+        // where p(0) = W(u) and p(1) = W(v).
+        let (u, v) = challenges.split_at(dims.k_i_plus_1);
+        let p_poly = &layer_proof.p_poly;
+        let (w_u, w_v) = (
+            p_poly.evaluate(Scalar::zero()),
+            p_poly.evaluate(Scalar::one()),
+        );
+        let expected = layer_i.eval_add_mle(&self.r_i, u, v) * (w_u + w_v)
+            + layer_i.eval_mult_mle(&self.r_i, u, v) * (w_u * w_v);
+        sink.count(CostReport::horner(p_poly.num_coeffs()).times(2));
+        sink.count_with(|| layer_i.wiring_mle_cost(dims.k_i_plus_1));
+        sink.count(CostReport::mults(3) + CostReport::adds(2) + CostReport::comparisons(1));
+        if claim != expected {
+            return Err(invalid(
+                i,
+                dims.v_r(),
+                Detail::FinalEval { claim, expected },
+            ));
+        }
+
+        // r_i+1 = l(t), where l(0) = u and l(1) = v, and m_i+1 = p(t).
+        let t = Scalar::from(layer_proof.t as u64);
+        self.r_i = u.iter().zip(v).map(|(u, v)| u + (v - u) * t).collect();
+        self.m_i = p_poly.evaluate(t);
+        let k = dims.k_i_plus_1;
+        sink.count(CostReport::mults(k) + CostReport::adds(2 * k));
+        sink.count(CostReport::horner(p_poly.num_coeffs()));
+        self.layer += 1;
+        Ok(())
+    }
+
+    // The instance P declares is the one of the next layer: v_r rounds of the degrees of the
+    // schedule at most, from the claim m_i. It's checked before any round, eg: a proof of a layer
+    // with another number of variables is rejected here rather than by its final check.
+    fn check_metadata(&self, layer_proof: &LayerProof) -> Result<(), Diagnosis> {
+        let i = self.layer;
+        let v_r = self.circuit.layer_dims(i).v_r();
+        if layer_proof.var_num != v_r {
+            return Err(invalid(
+                i,
+                0,
+                Detail::VarNumMismatch {
+                    proof: layer_proof.var_num,
+                    instance: v_r,
+                },
+            ));
+        }
+        if let Some(rounds) = [layer_proof.round_polys.len(), layer_proof.degrees.len()]
+            .into_iter()
+            .find(|rounds| *rounds != v_r)
+        {
+            return Err(invalid(
+                i,
+                0,
+                Detail::RoundCountMismatch {
+                    var_num: v_r,
+                    rounds,
+                },
+            ));
+        }
+        if layer_proof.claimed_sum != self.m_i {
+            return Err(invalid(
+                i,
+                0,
+                Detail::ClaimedSumMismatch {
+                    claim: self.m_i,
+                    declared: layer_proof.claimed_sum,
+                },
+            ));
+        }
+        let schedule = layer_schedule(self.circuit, i, self.order);
+        for (j, declared) in layer_proof.degrees.iter().enumerate() {
+            let bound = schedule.degree(j + 1);
+            if *declared > bound {
+                let detail = Detail::DegreeTooHigh {
+                    declared: *declared,
+                    bound,
+                };
+                return Err(invalid(i, j + 1, detail));
+            }
+        }
+        Ok(())
+    }
+
+    // The v_r rounds of the next layer, return g_v(r_v), the claim left to the final check.
+    // Each g_j must have the d_j + 1 coeffs of its declared degree.
+    fn check_rounds<S: CostSink>(
+        &self,
+        layer_proof: &LayerProof,
+        challenges: &[Scalar],
+        sink: &mut S,
+    ) -> Result<Scalar, Diagnosis> {
+        let i = self.layer;
+        let v_r = self.circuit.layer_dims(i).v_r();
+        let round_polys = &layer_proof.round_polys;
+        if round_polys.len() != v_r || challenges.len() != v_r {
+            return Err(invalid(i, 0, Detail::Malformed));
+        }
+
+        // check: g_j(0) + g_j(1) = g_j-1(r_j-1)
+        let mut claim = self.m_i;
+        for (j, (g_j, r_j)) in round_polys.iter().zip(challenges.iter()).enumerate() {
+            let declared = layer_proof.degrees[j];
+            if g_j.num_coeffs() != declared + 1 {
+                let len = g_j.num_coeffs();
+                return Err(invalid(i, j + 1, Detail::DegreeMismatch { declared, len }));
+            }
+            let sum = g_j.evaluate(Scalar::zero()) + g_j.evaluate(Scalar::one());
+            let horner = CostReport::horner(g_j.num_coeffs());
+            sink.count(horner.times(2) + CostReport::adds(1) + CostReport::comparisons(1));
+            if sum != claim {
+                return Err(invalid(i, j + 1, Detail::RoundSum { claim, sum }));
+            }
+            claim = g_j.evaluate(*r_j);
+            sink.count(horner);
+        }
+        Ok(claim)
+    }
+
+    // `verify_layer` on the claims of the tracker, where wires[i] is the poly of W_i: the claim on
+    // W_i is popped and the one on W_i+1 is pushed, so the claim on W_d is left once all the
+    // layers are verified, eg: for `ni_sumcheck::mlpc::discharge`. The claim on W_i must be the
+    // one of the state. The tracker is unchanged on a failure.
+    pub fn verify_layer_tracked(
+        &mut self,
+        layer_proof: &LayerProof,
+        tracker: &mut ClaimTracker,
+        wires: &[PolyId],
+    ) -> Result<(), Diagnosis> {
+        let i = self.layer;
+        let claim = match tracker.pop(wires[i]) {
+            Some(claim) => claim,
+            None => panic!("no claim on {}", tracker.describe(wires[i])),
+        };
+        assert!(
+            claim.point == self.r_i && claim.value == self.m_i,
+            "the claim on {} is not the one of layer {}",
+            tracker.describe(wires[i]),
+            i
+        );
+        if let Err(diagnosis) = self.verify_layer(layer_proof) {
+            tracker.push(claim);
+            return Err(diagnosis);
+        }
+        tracker.push(Claim::new(wires[i + 1], self.r_i.clone(), self.m_i));
+        Ok(())
+    }
+
+    // check: m_d = W_d(r_d), after all the layers.
+    pub fn finish(&self, inputs: &[Scalar]) -> Diagnosis {
+        self.finish_with_sink(inputs, &mut ())
+    }
+
+    pub fn finish_with_sink<S: CostSink>(&self, inputs: &[Scalar], sink: &mut S) -> Diagnosis {
+        assert!(self.is_done(), "layer {} is not verified yet", self.layer);
+        let expected = EvalTable::new(inputs.to_vec()).evaluate(&self.r_i);
+        sink.count(CostReport::table_evaluate(self.r_i.len()) + CostReport::comparisons(1));
+        if self.m_i != expected {
+            return invalid(
+                self.layer,
+                0,
+                Detail::InputEval {
+                    claim: self.m_i,
+                    expected,
+                },
+            );
+        }
+        Diagnosis::LooksConsistent
+    }
+
+    pub fn checkpoint(&self) -> StateCheckpoint {
+        let mut claims = Vec::with_capacity(1 + self.r_i.len());
+        claims.push(self.m_i);
+        claims.extend(self.r_i.iter());
+        StateCheckpoint {
+            binding: self.binding,
+            round: self.layer,
+            transcript: vec![],
+            claims,
+        }
+    }
+
+    // Resume from a checkpoint of the same proof, the layers left are `&proof.layers[round..]`.
+    pub fn resume(
+        circuit: &'a CircuitConfig,
+        checkpoint: &StateCheckpoint,
+        proof: &GkrProof,
+    ) -> Result<Self, CheckpointError> {
+        checkpoint.check_binding(&proof_binding(&proof.to_bytes()))?;
+        let layer = checkpoint.round;
+        if layer > circuit.layers.len()
+            || checkpoint.claims.len() != 1 + circuit.layer_var_num(layer)
+            || !checkpoint.transcript.is_empty()
+        {
+            return Err(CheckpointError::WrongShape);
+        }
+        // the order of the proof, a proof of another circuit doesn't fit.
+        let order = order_of(circuit, &proof.protocol).ok_or(CheckpointError::WrongShape)?;
+        Ok(Self {
+            circuit,
+            order,
+            binding: checkpoint.binding,
+            layer,
+            r_i: checkpoint.claims[1..].to_vec(),
+            m_i: checkpoint.claims[0],
+        })
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod test {
+    use super::*;
+    use crate::arithmetic::layered_circuit::Layer;
+    use crate::arithmetic::layered_circuit::Ops::{ADD, MUL};
+    use crate::gkr::{diagnose, protocol_id, DensityPolicy, GKR};
+    use crate::poly::{DenseBudget, Polynomial};
+    use ff::PrimeField;
+    use ni_sumcheck::mlpc;
+
+    // 3 layers, so the verification can be split after each one of them.
+    fn circuit() -> CircuitConfig {
+        let layer_0 = Layer {
+            gates: vec![ADD(3, 1), MUL(0, 2)],
+            var_num: 1,
+        };
+        let layer_1 = Layer {
+            gates: vec![ADD(0, 1), ADD(3, 0), MUL(1, 2), MUL(2, 3)],
+            var_num: 2,
+        };
+        let layer_2 = Layer {
+            gates: vec![MUL(0, 0), MUL(1, 1), MUL(1, 2), MUL(3, 3)],
+            var_num: 2,
+        };
+        CircuitConfig {
+            layers: vec![layer_0, layer_1, layer_2],
+            input_var_num: 2,
+            depth: 4,
+        }
+    }
+
+    fn inputs() -> Vec<Scalar> {
+        [1, 2, 1, 4].iter().map(|v| Scalar::from_u128(*v)).collect()
+    }
+
+    fn prove(challenges: &[usize]) -> (Vec<Scalar>, GkrProof) {
+        let circuit = circuit();
+        let outputs = circuit.trace_evaluation(&inputs()).outputs();
+        let mut challenges = challenges.iter().copied();
+        let proof = GKR::init(circuit).prove(&inputs(), || challenges.next().unwrap());
+        (outputs, proof)
+    }
+
+    // verify the first `split` layers, then resume from the bytes of the checkpoint.
+    fn verify_split(
+        circuit: &CircuitConfig,
+        outputs: &[Scalar],
+        proof: &GkrProof,
+        split: usize,
+    ) -> Diagnosis {
+        let mut state = match GkrVerifierState::new(circuit, outputs, proof) {
+            Ok(state) => state,
+            Err(diagnosis) => return diagnosis,
+        };
+        for layer_proof in proof.layers[..split].iter() {
+            if let Err(diagnosis) = state.verify_layer(layer_proof) {
+                return diagnosis;
+            }
+        }
+        let bytes = state.checkpoint().to_bytes();
+        drop(state);
+
+        let checkpoint = StateCheckpoint::from_bytes(&bytes).unwrap();
+        let mut state = GkrVerifierState::resume(circuit, &checkpoint, proof).unwrap();
+        for layer_proof in proof.layers[split..].iter() {
+            if let Err(diagnosis) = state.verify_layer(layer_proof) {
+                return diagnosis;
+            }
+        }
+        state.finish(&inputs())
+    }
+
+    // p is linear, so u and v differ in one coordinate in each layer, see
+    // `diagnose::test::test_fixture`: r_0, then (u, v, t) of the layers 0, 1 and 2.
+    const CHALLENGES: [usize; 16] = [3, 5, 2, 5, 7, 4, 2, 6, 2, 9, 3, 1, 4, 1, 8, 2];
+
+    #[test]
+    fn test_split_at_every_layer() {
+        let circuit = circuit();
+        let (outputs, proof) = prove(&CHALLENGES);
+        let expected = diagnose(&circuit, &inputs(), &outputs, &proof);
+        assert_eq!(expected, Diagnosis::LooksConsistent);
+        for split in 0..=circuit.layers.len() {
+            assert_eq!(
+                verify_split(&circuit, &outputs, &proof, split),
+                expected,
+                "split at {}",
+                split
+            );
+        }
+    }
+
+    #[test]
+    fn test_corrupted_layer_after_resume() {
+        let circuit = circuit();
+        let (outputs, mut proof) = prove(&CHALLENGES);
+        let g_2 = &proof.layers[2].round_polys[1];
+        proof.layers[2].round_polys[1] = g_2 + &Polynomial::constant(Scalar::one());
+
+        for split in 0..=2 {
+            match verify_split(&circuit, &outputs, &proof, split) {
+                Diagnosis::ProofInvalid {
+                    layer: 2,
+                    round: 2,
+                    detail: Detail::RoundSum { claim, sum },
+                } => assert_eq!(sum - claim, Scalar::from_u128(2)),
+                diagnosis => panic!("split at {}: unexpected diagnosis: {}", split, diagnosis),
+            }
+        }
+    }
+
+    #[test]
+    fn test_checkpoint_of_another_proof() {
+        let circuit = circuit();
+        let (outputs, proof) = prove(&CHALLENGES);
+        let mut another_challenges = CHALLENGES;
+        another_challenges[0] = 2;
+        let (_, another) = prove(&another_challenges);
+
+        let mut state = GkrVerifierState::new(&circuit, &outputs, &proof).unwrap();
+        state.verify_layer(&proof.layers[0]).unwrap();
+        let checkpoint = state.checkpoint();
+        assert!(matches!(
+            GkrVerifierState::resume(&circuit, &checkpoint, &another),
+            Err(CheckpointError::WrongProof)
+        ));
+
+        let mut past_the_end = checkpoint.clone();
+        past_the_end.round = circuit.layers.len() + 1;
+        assert!(matches!(
+            GkrVerifierState::resume(&circuit, &past_the_end, &proof),
+            Err(CheckpointError::WrongShape)
+        ));
+    }
+
+    // The layers leave one claim, on the inputs, which the opening of their commitment discharges.
+    #[test]
+    fn test_claims_left_to_the_inputs() {
+        let circuit = circuit();
+        let (outputs, proof) = prove(&CHALLENGES);
+        let mut tracker = ClaimTracker::new();
+        let wires = (0..=circuit.layers.len())
+            .map(|i| tracker.register(&format!("W_{}", i)))
+            .collect::<Vec<_>>();
+
+        let mut state = GkrVerifierState::new(&circuit, &outputs, &proof).unwrap();
+        tracker.push(Claim::new(wires[0], state.r_i.clone(), state.m_i));
+        for layer_proof in proof.layers.iter() {
+            state
+                .verify_layer_tracked(layer_proof, &mut tracker, &wires)
+                .unwrap();
+            assert_eq!(tracker.remaining().len(), 1);
+        }
+        assert_eq!(tracker.remaining()[0].poly, wires[3]);
+        assert_eq!(tracker.describe(wires[3]), "W_3");
+
+        let (root, committed) = mlpc::commit(&inputs());
+        let opening = mlpc::open(&committed, &tracker.remaining()[0].point);
+        mlpc::discharge(&mut tracker, wires[3], &root, &opening).unwrap();
+        tracker.assert_all_discharged();
+    }
+
+    #[test]
+    fn test_rejected_layer_keeps_its_claim() {
+        let circuit = circuit();
+        let (outputs, mut proof) = prove(&CHALLENGES);
+        proof.layers[1].p_poly = &proof.layers[1].p_poly + &Polynomial::constant(Scalar::one());
+        let mut tracker = ClaimTracker::new();
+        let wires = (0..=circuit.layers.len())
+            .map(|i| tracker.register(&format!("W_{}", i)))
+            .collect::<Vec<_>>();
+
+        let mut state = GkrVerifierState::new(&circuit, &outputs, &proof).unwrap();
+        tracker.push(Claim::new(wires[0], state.r_i.clone(), state.m_i));
+        state
+            .verify_layer_tracked(&proof.layers[0], &mut tracker, &wires)
+            .unwrap();
+        assert!(state
+            .verify_layer_tracked(&proof.layers[1], &mut tracker, &wires)
+            .is_err());
+        assert_eq!(tracker.remaining()[0].poly, wires[1]);
+    }
+
+    // The root of the inputs is looked up in the statement, which must be of this circuit.
+    #[test]
+    fn test_statement_of_the_inputs() {
+        use crate::gkr::proof::{input_root, statement as statement_of};
+        use Fiat_Shamir::statement::{Statement, StatementError};
+
+        let circuit = circuit();
+        let (outputs, proof) = prove(&CHALLENGES);
+        let (root, committed) = mlpc::commit(&inputs());
+        let statement = statement_of(&circuit, &root);
+
+        let mut state = GkrVerifierState::new(&circuit, &outputs, &proof).unwrap();
+        for layer_proof in proof.layers.iter() {
+            state.verify_layer(layer_proof).unwrap();
+        }
+        let (r_d, m_d) = state.claim();
+        let opening = mlpc::open(&committed, r_d);
+        let root_d = input_root(&circuit, &statement).unwrap();
+        assert_eq!(mlpc::verify(&root_d, r_d, m_d, &opening), Ok(()));
+
+        // the root of other inputs.
+        let other_inputs = [1, 2, 1, 5]
+            .iter()
+            .map(|v| Scalar::from_u128(*v))
+            .collect::<Vec<_>>();
+        let (other, _) = mlpc::commit(&other_inputs);
+        let other = input_root(&circuit, &statement_of(&circuit, &other)).unwrap();
+        assert!(mlpc::verify(&other, r_d, m_d, &opening).is_err());
+
+        let without_root =
+            Statement::new(protocol_id(&circuit)).with_circuit(circuit.circuit_digest());
+        assert_eq!(
+            input_root(&circuit, &without_root),
+            Err(StatementError::MissingBinding { label: "inputs" })
+        );
+        let mut another = circuit.clone();
+        another.layers[0].gates[0] = ADD(0, 1);
+        assert_eq!(
+            input_root(&another, &statement),
+            Err(StatementError::ProtocolMismatch)
+        );
+    }
+
+    // One layer of 2^k gates over 2^k inputs, each gate reads two other wires.
+    fn wide_circuit(k: usize) -> CircuitConfig {
+        let n = 1 << k;
+        let gates = (0..n)
+            .map(|j| match j % 2 {
+                0 => ADD(j, (j + 1) % n),
+                _ => MUL(j, (3 * j + 1) % n),
+            })
+            .collect();
+        let mut circuit = CircuitConfig {
+            layers: vec![Layer { gates, var_num: k }],
+            input_var_num: k,
+            depth: 2,
+        };
+        circuit.canonicalize();
+        circuit
+    }
+
+    fn prove_wide(circuit: &CircuitConfig) -> (Vec<Scalar>, Vec<Scalar>, GkrProof) {
+        let inputs = (1..=1u64 << circuit.input_var_num)
+            .map(Scalar::from)
+            .collect::<Vec<_>>();
+        let outputs = circuit.trace_evaluation(&inputs).outputs();
+        let mut last = 0;
+        let mut gkr = GKR::init_with_policy(circuit.clone(), DensityPolicy::ForceSparse).unwrap();
+        let proof = gkr.prove(&inputs, || {
+            last += 1;
+            last
+        });
+        (inputs, outputs, proof)
+    }
+
+    // The rounds cost the same per round from 2^4 to 2^10 gates, only the final check grows with
+    // the gates.
+    #[test]
+    fn test_round_cost_independent_of_layer_size() {
+        // g_j has degree 2: g_j(0), g_j(1) and g_j(r_j) by Horner, the sum and the comparison.
+        let per_round =
+            CostReport::horner(3).times(3) + CostReport::adds(1) + CostReport::comparisons(1);
+        let mut final_checks = vec![];
+        for k in [4, 7, 10] {
+            let circuit = wide_circuit(k);
+            let (inputs, outputs, proof) = prove_wide(&circuit);
+            let layer_proof = &proof.layers[0];
+            let mut state = GkrVerifierState::new(&circuit, &outputs, &proof).unwrap();
+
+            let mut rounds = CostReport::default();
+            let challenges = to_scalars(&layer_proof.challenges);
+            state
+                .check_rounds(layer_proof, &challenges, &mut rounds)
+                .unwrap();
+            let v_r = circuit.layer_dims(0).v_r();
+            assert_eq!(rounds, per_round.times(v_r), "2^{} gates", k);
+
+            let mut layer = CostReport::default();
+            state
+                .verify_layer_with_sink(layer_proof, &mut layer)
+                .unwrap();
+            assert_eq!(state.finish(&inputs), Diagnosis::LooksConsistent);
+            final_checks.push((layer.field_mults - rounds.field_mults) as f64);
+        }
+        // 2^3 and 2^6 times the gates of the first layer.
+        assert!(final_checks[1] > 6.0 * final_checks[0]);
+        assert!(final_checks[2] > 6.0 * final_checks[1]);
+    }
+
+    // add_0(r_0, u, v) of a layer with 5 + 2*6 variables from the dense MLE, which V must not do.
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "dense MPolynomial::evaluate of 131072 coeffs in V")]
+    fn test_dense_final_check_trips() {
+        let mut circuit = wide_circuit(6);
+        circuit.layers[0].gates.truncate(32);
+        circuit.layers[0].var_num = 5;
+        let (_, _, proof) = prove_wide(&circuit);
+
+        let (add_0, _) = circuit.layer_ops(0, &DenseBudget::default()).unwrap();
+        let point = [proof.r_0.clone(), proof.layers[0].challenges.clone()].concat();
+        as_verifier(|| add_0.evaluate(&point));
+    }
+}
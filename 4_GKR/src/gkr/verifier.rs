@@ -1,13 +1,16 @@
-use crate::poly::MPolynomial;
+use crate::poly::{EvalTable, MPolynomial};
 use bls12_381::Scalar;
+use ni_sumcheck::mlpc::{self, MlOpening, MlRoot};
 use rand_core::{OsRng, RngCore};
 use std::env::var;
 use std::os::unix::raw::mode_t;
+use sumcheck::claim::{ClaimTracker, PolyId};
 
 #[derive(Clone, Debug, Default)]
 pub struct Verifier {
     m0: Scalar,
-    w_d: MPolynomial,
+    w_d: EvalTable,
+    root: Option<MlRoot>, // the commitment of inputs, V doesn't hold the inputs in this mode.
 }
 
 impl Verifier {
@@ -22,26 +25,59 @@ impl Verifier {
         // let r_0 = vec![3]; // used for debug
         let m0 = output.evaluate(&r_0);
 
-        // Encode the inputs as Mpoly.
-        let w_d = MPolynomial::lagrange(input_var_num, inputs);
+        // Keep the inputs as the table of W_d, which is evaluated at r_d by folding.
+        let w_d = EvalTable::new(inputs.clone());
+        assert_eq!(w_d.var_num, input_var_num, "Domain is less than var_num");
 
         self.m0 = m0;
         self.w_d = w_d;
         (r_0, m0)
     }
 
-    // generate r1, r2, ..., rv,  $r_i ∈ F^{k_i}$
+    // Same as `init`, but V only holds the commitment of the inputs.
+    pub fn init_with_commitment(
+        &mut self,
+        output: MPolynomial,
+        root: MlRoot,
+    ) -> (Vec<usize>, Scalar) {
+        let r_0 = Self::gen_challenge(output.var_num);
+        let m0 = output.evaluate(&r_0);
+
+        self.m0 = m0;
+        self.root = Some(root);
+        (r_0, m0)
+    }
+
+    // generate r1, ..., rv,  $r_i ∈ F^{k_i}$
     pub fn gen_challenge(var_num: usize) -> Vec<usize> {
-        let randoms = (0..var_num)
-            .map(|_| OsRng.next_u32() % 1000)
-            .collect::<Vec<_>>();
-        randoms.iter().map(|r| *r as usize).collect::<Vec<_>>()
+        (0..var_num)
+            .map(|_| OsRng.next_u64() as usize)
+            .collect::<Vec<_>>()
+    }
+
+    // V checks  m_d = W_d (r_d ), the claim on W_d left in the tracker, and discharges it.
+    pub fn check(&self, tracker: &mut ClaimTracker, w_d: PolyId) {
+        let claim = tracker.pop(w_d).expect("GKR verifier: no claim on W_d");
+        let actual = self.w_d.evaluate(&claim.point);
+        assert_eq!(claim.value, actual, "GKR verifier: final check failed");
+
+        println!("GKR: V accepted the output from P");
     }
 
-    // V checks  m_d = W_d (r_d )
-    pub fn check(&self, r_d: &Vec<usize>, target: Scalar) {
-        let actual = self.w_d.evaluate(r_d);
-        assert_eq!(target, actual, "GKR verifier: final check failed");
+    // V checks the claim m_d = W_d(r_d) with the opening of the committed inputs at r_d.
+    pub fn check_with_commitment(
+        &self,
+        tracker: &mut ClaimTracker,
+        w_d: PolyId,
+        opening: &MlOpening,
+    ) {
+        let root = self
+            .root
+            .as_ref()
+            .expect("GKR verifier: no commitment of inputs");
+        if let Err(e) = mlpc::discharge(tracker, w_d, root, opening) {
+            panic!("GKR verifier: final check failed, {}", e);
+        }
 
         println!("GKR: V accepted the output from P");
     }
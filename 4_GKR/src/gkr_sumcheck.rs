@@ -1,11 +1,15 @@
-use crate::gkr_sumcheck::prover::Prover;
+use crate::arithmetic::layered_circuit::{Layer, LayerDims};
+use crate::gkr_sumcheck::prover::LayerProver;
 use crate::gkr_sumcheck::verifier::Verifier;
 use crate::poly::{MPolynomial, Polynomial};
 use bls12_381::Scalar;
 use ff::PrimeField;
 use std::env::var;
 use std::iter::Sum;
+use sumcheck::cost::as_verifier;
 
+#[cfg(test)]
+mod differential;
 pub mod prover;
 pub mod verifier;
 
@@ -20,67 +24,110 @@ pub mod verifier;
 //      The gkr one evaluted on three polys-add, mult, W_i+1
 pub struct GkrSumCheck {
     // v_l: usize, // the constants_part var_num.  v_l + v_r = ki + 2*k_i_plus_1
-    v_r: usize, // the variable_part var_num. equals to `v` in standard sumcheck.
-    // r_i: Vec<usize>,
+    dims: LayerDims, // (k_i, k_i+1) from the circuit, they differ from layer to layer.
+    v_r: usize,      // the variable_part var_num. equals to `v` in standard sumcheck.
+    r_i: Vec<Scalar>,
+    layer: Layer, // the layer i, V uses its gates to evaluate add_i and mult_i.
     // layer_i: usize, // the gkr layer index. [0,d)
-    prover: Prover,
+    prover: LayerProver,
     verifier: Verifier,
 }
 
 //  (add, mult, w_i_plus_1)
-type F_r_Poly = (MPolynomial, MPolynomial, MPolynomial);
+pub(crate) type FrPoly = (MPolynomial, MPolynomial, MPolynomial);
 
 impl GkrSumCheck {
-    pub fn init(g: F_r_Poly, r_i: Vec<usize>, m_i: Scalar) -> Self {
-        assert_eq!(g.0.var_num, g.1.var_num);
-        let (v_l, v_r) = (r_i.len(), 2 * g.2.var_num);
-        assert_eq!(g.0.var_num, v_l + v_r);
+    // dims are the ones of the layer in the circuit, see `CircuitConfig::layer_dims`.
+    pub fn init(dims: LayerDims, g: FrPoly, layer: Layer, r_i: Vec<usize>, m_i: Scalar) -> Self {
+        let prover = LayerProver::new(dims, g, r_i.clone(), m_i);
+        let r_i = r_i.iter().map(|r| Scalar::from(*r as u64)).collect();
+        Self::with_prover(prover, layer, dims, r_i, m_i)
+    }
 
-        let prover = Prover::new(g, r_i);
+    // Same as `init`, with P built already, eg: by `LayerProver::new_sparse`.
+    pub fn with_prover(
+        prover: LayerProver,
+        layer: Layer,
+        dims: LayerDims,
+        r_i: Vec<Scalar>,
+        m_i: Scalar,
+    ) -> Self {
+        assert_eq!(
+            prover.dims(),
+            dims,
+            "P is built for another shape of the layer"
+        );
+        assert_eq!(layer.var_num, dims.k_i, "the layer must have k_i variables");
+        let v_r = dims.v_r();
         let verifier = Verifier::new(v_r, m_i);
 
         Self {
+            dims,
             v_r,
+            r_i,
+            layer,
             prover,
             verifier,
         }
     }
 
-    pub fn run_protocol(&mut self) -> (Vec<usize>, Scalar) {
-        // round 1
-        let g1 = self.prover.round_1();
-        self.verifier.round_1(g1);
-
-        // round 2 - (v-1)
-        for j in 2..self.v_r {
-            let challenges = self.verifier.challenges();
-            let g_j = self.prover.recursive_round_j(&challenges);
-            self.verifier.recursive_round_j(j, g_j);
-            drop(challenges);
+    // v_r = 0 when layer i+1 is a single wire: there's no round, V checks m_i = f_{r_i}() in the
+    // final check. v_r = 1 has round 1 only, which is round v too.
+    pub fn run_protocol(&mut self) -> (Vec<Scalar>, Scalar) {
+        if self.v_r > 0 {
+            self.run_rounds();
         }
 
-        // round v
-        let challenges = self.verifier.challenges();
-        let g_v = self.prover.round_v(&challenges);
-        self.verifier.round_v(g_v);
-        // drop(challenges);
-
         // finally check
         let challenges = self.verifier.challenges();
-        let (add_value, mult_value, l_polys, p_poly) = self.prover.evaluate(&challenges);
-        self.verifier.check((add_value, mult_value, &p_poly));
+        let (l_polys, p_poly) = self.prover.evaluate();
+        //  V evaluates add_i(r_i,u,v) and mult_i(r_i,u,v) by itself with the sparse gates.
+        let to_scalars = |x: &[usize]| x.iter().map(|x| Scalar::from(*x as u64)).collect::<Vec<_>>();
+        let (u, v) = challenges.split_at(self.dims.k_i_plus_1);
+        let (r, u, v) = (&self.r_i, to_scalars(u), to_scalars(v));
+        as_verifier(|| {
+            let add_value = self.layer.eval_add_mle(r, &u, &v);
+            let mult_value = self.layer.eval_mult_mle(r, &u, &v);
+            self.verifier.check((add_value, mult_value, &p_poly));
+        });
 
         // Prepare for next sumcheck:
         //  V chooses random t and sets r_{i+1} = l(t) and mi+1 = q(r_{i+1})=q(l(t)).
+        //  l is the line through u, v ∈ F^k_i+1, so r_{i+1} has k_i+1 elements, whatever k_i is.
         let t = Verifier::gen_challenge();
         let r_1_plus_1 = l_polys
             .iter()
-            .map(|l_i| l_i.iter().rev().fold(0, |acc, coeff| acc * t + *coeff))
+            .map(|l_i| l_i.evaluate(Scalar::from_u128(t as u128)))
             .collect::<Vec<_>>();
+        assert_eq!(r_1_plus_1.len(), self.dims.k_i_plus_1);
         let m_i_plus_1 = p_poly.evaluate(Scalar::from_u128(t as u128));
 
         (r_1_plus_1, m_i_plus_1)
     }
+
+    // the v_r rounds, v_r > 0. r_v is bound to P at the end.
+    fn run_rounds(&mut self) {
+        // round 1
+        let g1 = self.prover.next_round(None);
+        self.verifier.round_1(g1);
+
+        // round 2 - (v-1)
+        for j in 2..self.v_r {
+            let r_j_minus_1 = self.verifier.challenges().last().copied();
+            let g_j = self.prover.next_round(r_j_minus_1);
+            self.verifier.recursive_round_j(j, g_j);
+        }
+
+        // round v
+        if self.v_r > 1 {
+            let r_v_minus_1 = self.verifier.challenges().last().copied();
+            let g_v = self.prover.next_round(r_v_minus_1);
+            self.verifier.round_v(g_v);
+        }
+
+        let r_v = self.verifier.challenges().last().copied();
+        self.prover.bind_final(r_v.unwrap());
+    }
 }
 
 // #[cfg(test)]
@@ -0,0 +1,274 @@
+// The sum-check of a layer by `LayerProver` against the generic one of the sumcheck crate on the
+// same sum, so a divergence of the two, eg: of the order the variables are bound in or of the
+// degree of g_j, shows up here rather than as a proof of a whole circuit which V rejects.
+//
+//      cargo test -p GKR differential
+//
+// Each case is a random layer i: k_i and k_i+1 in [0, 4], up to 2^k_i random gates on the wires
+// of layer i+1, a random table of W_i+1, r_i and the challenges r_1, ..., r_v. The generic side
+// uses nothing of P nor of the circuit: f_{r_i} is the explicit sum over the gates
+//      f_{r_i}(b, c) = ∑_{gates (j, l, r)} eq(r_i, j) * eq(b, l) * eq(c, r) * (W(b) op W(c))
+// with W the MLE of the table, which `outsource::Server` proves with the rounds streamed from the
+// (b, c) hypercube and `SumCheckVerifierSm` checks. Both sides take the same r_1, ..., r_v, and
+// must agree on
+//  - the claimed sum, which is W_i(r_i) of the layer evaluated on W_i+1,
+//  - g_j at 0, 1, 2 and r_j in each round,
+//  - the final point (u, v) = (r_1, ..., r_v) and the claim g_v(r_v) on f_{r_i} there, which is
+//    the one V of the layer checks with add_i(r_i, u, v), mult_i(r_i, u, v), W(u) and W(v),
+//  - the step to layer i+1 at a random t: r_i+1 = l(t) on the line through u and v, and
+//    m_i+1 = p(t), which is W_i+1(l(t)) of the table.
+// P is run with the sparse wiring, and with the dense one up to `MAX_DENSE_VARS`.
+//
+// The challenges of P are usize, so they're drawn in u64, t too, and a failure reports the seed.
+use crate::arithmetic::layered_circuit::Ops::{ADD, MUL};
+use crate::arithmetic::layered_circuit::{Layer, LayerDims};
+use crate::gkr_sumcheck::prover::LayerProver;
+use crate::poly::{EvalTable, MPolynomial, Polynomial};
+use bls12_381::Scalar;
+use sumcheck::sumcheck::machine::{FixedChallenges, SumCheckVerifierSm, VerifierAction};
+use sumcheck::sumcheck::outsource::{Channel, Request, Response, Server};
+use sumcheck::utils::eq_eval_at_index;
+
+const CASES: u64 = 200;
+const MAX_VAR_NUM: u64 = 4;
+// the dense add_i and mult_i are over k_i + 2*k_i+1 variables, and P evaluates them at each point
+// of the rounds, so the wider ones are sparse only.
+const MAX_DENSE_VARS: usize = 8;
+// f_{r_i} is of degree 2 in each variable, the Joint order of `gkr::layer_schedule`.
+const DEGREE: usize = 2;
+
+// SplitMix64, so that a seed is all it takes to rebuild a case.
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: u64) -> usize {
+        (self.next_u64() % n) as usize
+    }
+}
+
+struct Case {
+    dims: LayerDims,
+    layer: Layer,
+    w: Vec<Scalar>, // the table of W_i+1.
+    r_i: Vec<usize>,
+    challenges: Vec<usize>, // r_1, ..., r_v
+    t: usize,               // the point on the line of V, for r_i+1 = l(t).
+}
+
+impl Case {
+    fn new(seed: u64) -> Self {
+        let mut rng = Rng(seed);
+        let dims = LayerDims {
+            k_i: rng.below(MAX_VAR_NUM + 1),
+            k_i_plus_1: rng.below(MAX_VAR_NUM + 1),
+        };
+        let n = 1 << dims.k_i_plus_1;
+        let gates = (0..=rng.below(1 << dims.k_i))
+            .map(|_| {
+                let (left, right) = (rng.below(n), rng.below(n));
+                match rng.below(2) {
+                    0 => ADD(left, right),
+                    _ => MUL(left, right),
+                }
+            })
+            .collect();
+        let w = (0..n).map(|_| Scalar::from(rng.next_u64())).collect();
+        let r_i = (0..dims.k_i).map(|_| rng.next_u64() as usize).collect();
+        let challenges = (0..dims.v_r()).map(|_| rng.next_u64() as usize).collect();
+        let t = rng.next_u64() as usize;
+
+        Self {
+            dims,
+            layer: Layer {
+                gates,
+                var_num: dims.k_i,
+            },
+            w,
+            r_i,
+            challenges,
+            t,
+        }
+    }
+
+    // W_i(r_i), the claim P starts from.
+    fn claim(&self) -> Scalar {
+        let w_i = self.layer.evaluate(&self.w);
+        EvalTable::new(w_i.to_vec()).evaluate(&to_scalars(&self.r_i))
+    }
+
+    fn w_poly(&self) -> MPolynomial {
+        MPolynomial::lagrange_unchecked(self.dims.k_i_plus_1, &self.w)
+    }
+
+    fn sparse_prover(&self) -> LayerProver {
+        let wiring = self
+            .layer
+            .sparse_wiring_at(&to_scalars(&self.r_i), self.dims.k_i_plus_1);
+        LayerProver::new_sparse(self.dims, wiring, self.w_poly(), self.claim())
+    }
+
+    fn dense_prover(&self) -> LayerProver {
+        let var_num = self.dims.k_i + self.dims.v_r();
+        let (add, mult) = self.layer.wiring_evals(self.dims.k_i_plus_1);
+        let g = (
+            MPolynomial::lagrange_unchecked(var_num, &add),
+            MPolynomial::lagrange_unchecked(var_num, &mult),
+            self.w_poly(),
+        );
+        LayerProver::new(self.dims, g, self.r_i.clone(), self.claim())
+    }
+
+    // f_{r_i}(b, c) from the gates and the table only.
+    fn f_r(&self) -> impl Fn(&[Scalar]) -> Scalar + Sync + '_ {
+        let (r_i, w) = (to_scalars(&self.r_i), EvalTable::new(self.w.clone()));
+        move |bc: &[Scalar]| {
+            let (b, c) = bc.split_at(self.dims.k_i_plus_1);
+            let (w_b, w_c) = (w.evaluate(b), w.evaluate(c));
+            (self.layer.gates.iter().enumerate())
+                .map(|(j, gate)| {
+                    let (left, right) = match gate {
+                        ADD(left, right) | MUL(left, right) => (*left, *right),
+                    };
+                    let eq = eq_eval_at_index(&r_i, j)
+                        * eq_eval_at_index(b, left)
+                        * eq_eval_at_index(c, right);
+                    eq * match gate {
+                        ADD(_, _) => w_b + w_c,
+                        MUL(_, _) => w_b * w_c,
+                    }
+                })
+                .sum()
+        }
+    }
+
+    // f_{r_i}(u, v) as V of the layer evaluates it at the end.
+    fn final_check(&self, point: &[Scalar]) -> Scalar {
+        let (u, v) = point.split_at(self.dims.k_i_plus_1);
+        let w = EvalTable::new(self.w.clone());
+        let (w_u, w_v) = (w.evaluate(u), w.evaluate(v));
+        let r_i = to_scalars(&self.r_i);
+        self.layer.eval_add_mle(&r_i, u, v) * (w_u + w_v)
+            + self.layer.eval_mult_mle(&r_i, u, v) * (w_u * w_v)
+    }
+
+    // (r_i+1, m_i+1) = (l(t), W_i+1(l(t))), with l(t) = u + t * (v - u) in F.
+    fn next_claim(&self, point: &[Scalar]) -> (Vec<Scalar>, Scalar) {
+        let (u, v) = point.split_at(self.dims.k_i_plus_1);
+        let t = Scalar::from(self.t as u64);
+        let r_i_plus_1 = (u.iter().zip(v))
+            .map(|(u_j, v_j)| u_j + t * (v_j - u_j))
+            .collect::<Vec<_>>();
+        let m_i_plus_1 = EvalTable::new(self.w.clone()).evaluate(&r_i_plus_1);
+        (r_i_plus_1, m_i_plus_1)
+    }
+}
+
+// What a side sends in a run: the claimed sum, g_j at (0, 1, 2, r_j) of each round, the final
+// claim g_v(r_v) at (r_1, ..., r_v), and the claim (r_i+1, m_i+1) on W_i+1 it's reduced to.
+#[derive(Debug, PartialEq)]
+struct Run {
+    claimed_sum: Scalar,
+    rounds: Vec<[Scalar; 4]>,
+    point: Vec<Scalar>,
+    final_claim: Scalar,
+    next: (Vec<Scalar>, Scalar),
+}
+
+fn at(g_j: &Polynomial, r_j: Scalar) -> [Scalar; 4] {
+    [Scalar::zero(), Scalar::one(), Scalar::from(2), r_j].map(|x| g_j.evaluate(x))
+}
+
+fn to_scalars(r: &[usize]) -> Vec<Scalar> {
+    r.iter().map(|r| Scalar::from(*r as u64)).collect()
+}
+
+// P of the layer, the claimed sum is g_1(0) + g_1(1), or m_i if there's no round.
+// r_i+1 and m_i+1 are l(t) and p(t) as in `GkrSumCheck::run_protocol`.
+fn run_layer(case: &Case, mut prover: LayerProver) -> Run {
+    let mut claimed_sum = prover.current_claim();
+    let mut rounds = vec![];
+    let mut prev = None;
+    for (j, r_j) in case.challenges.iter().enumerate() {
+        let g_j = prover.next_round(prev);
+        if j == 0 {
+            claimed_sum = g_j.evaluate(Scalar::zero()) + g_j.evaluate(Scalar::one());
+        }
+        rounds.push(at(&g_j, Scalar::from(*r_j as u64)));
+        prev = Some(*r_j);
+    }
+    if let Some(r_v) = prev {
+        prover.bind_final(r_v);
+    }
+    let (l_polys, p_poly) = prover.evaluate();
+    let t = Scalar::from(case.t as u64);
+    let r_i_plus_1 = l_polys.iter().map(|l_j| l_j.evaluate(t)).collect();
+    Run {
+        claimed_sum,
+        rounds,
+        point: to_scalars(&case.challenges),
+        final_claim: prover.current_claim(),
+        next: (r_i_plus_1, p_poly.evaluate(t)),
+    }
+}
+
+// The generic P and V on the explicit sum, V must accept.
+fn run_generic(case: &Case, seed: u64) -> Run {
+    let var_num = case.dims.v_r();
+    let mut server = Server::new(case.f_r(), var_num, DEGREE);
+    let Response::Claim(claimed_sum) = server.request(Request::Claim) else {
+        panic!("no claimed sum");
+    };
+    let challenges = to_scalars(&case.challenges);
+    let source = FixedChallenges(challenges.clone().into_iter());
+    let mut verifier = SumCheckVerifierSm::new(var_num, DEGREE, claimed_sum, source);
+    let mut rounds = vec![];
+    let mut prev = None;
+    for r_j in challenges {
+        let Response::Round(g_j) = server.request(Request::Round(prev)) else {
+            panic!("no round poly");
+        };
+        rounds.push(at(&g_j, r_j));
+        verifier.on_round_polynomial(g_j).unwrap();
+        prev = Some(r_j);
+    }
+    let Some(VerifierAction::Accept(claim)) = verifier.outcome() else {
+        panic!("seed {}: rejected {:?}", seed, verifier.outcome());
+    };
+    Run {
+        claimed_sum,
+        rounds,
+        next: case.next_claim(&claim.point),
+        point: claim.point.clone(),
+        final_claim: claim.value,
+    }
+}
+
+#[test]
+fn test_layer_prover_same_as_generic() {
+    for seed in 0..CASES {
+        let case = Case::new(seed);
+        let generic = run_generic(&case, seed);
+        assert_eq!(generic.claimed_sum, case.claim(), "seed {}", seed);
+        assert_eq!(
+            generic.final_claim,
+            case.final_check(&generic.point),
+            "seed {}",
+            seed
+        );
+
+        let sparse = run_layer(&case, case.sparse_prover());
+        assert_eq!(sparse, generic, "seed {}: sparse {:?}", seed, case.dims);
+        if case.dims.k_i + case.dims.v_r() <= MAX_DENSE_VARS {
+            let dense = run_layer(&case, case.dense_prover());
+            assert_eq!(dense, generic, "seed {}: dense {:?}", seed, case.dims);
+        }
+    }
+}
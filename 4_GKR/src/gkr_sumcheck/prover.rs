@@ -1,190 +1,756 @@
-use crate::gkr_sumcheck::F_r_Poly;
-use crate::poly::{MPolynomial, Polynomial};
-use crate::utils::convert_to_binary;
+use crate::arithmetic::layered_circuit::Ops::{ADD, MUL};
+use crate::arithmetic::layered_circuit::{LayerDims, SparseWiring};
+use crate::gkr_sumcheck::FrPoly;
+use crate::poly::{EvalTable, MPolynomial, Polynomial};
+use crate::utils::{convert_to_binary, eq_eval_at_index, par_sum};
 use bls12_381::Scalar;
-use std::ops::{Add, Mul};
+use ni_sumcheck::sumcheck::DegreeSchedule;
+use std::fmt;
 use std::path::Iter;
+use sumcheck::secret::{Secret, ZeroizingEvalTable};
 
-pub struct Prover {
-    v_l: usize, // the constants_part var_num.  v_l + v_r = ki + 2*k_i_plus_1
-    v_r: usize, // the variable_part var_num. equals to `v` in standard sumcheck.
-    add: MPolynomial,
-    mult: MPolynomial,
-    w_i_plus_1: MPolynomial,
-    r_i: Vec<usize>, // the constant var part.
+// add and mult of the layer, as P holds them.
+enum Wiring {
+    // The dense ones over (r_i, b, c), r_i is the constant var part.
+    Dense {
+        add: MPolynomial,
+        mult: MPolynomial,
+        r_i: Vec<usize>,
+    },
+    // The gates with r_i bound already, and the table of W_i+1 to look up W_i+1(b), W_i+1(c).
+    Sparse {
+        wiring: SparseWiring,
+        w_table: ZeroizingEvalTable,
+    },
 }
 
-impl Prover {
-    pub fn new((add, mult, w_i_plus_1): F_r_Poly, r_i: Vec<usize>) -> Self {
-        let (v_l, v_r) = (r_i.len(), 2 * w_i_plus_1.var_num);
+// The prover of the sumcheck for a layer, it keeps the challenges and the running claim,
+// so that the driver only passes each challenge once.
+pub struct LayerProver {
+    dims: LayerDims, // (k_i, k_i+1) of the layer, from the circuit.
+    v_r: usize,      // the variable_part var_num. equals to `v` in standard sumcheck.
+    wiring: Wiring,
+    w_i_plus_1: Secret<MPolynomial>,
+    rounds: usize, // the num of g_j sent.
+    challenges: Vec<usize>,
+    last_poly: Option<Polynomial>,
+    claim: Scalar,
+    schedule: DegreeSchedule, // the degree of g_j, Uniform(2) unless the driver says otherwise.
+}
 
-        Self {
-            v_l,
-            v_r,
-            add,
-            mult,
-            w_i_plus_1,
-            r_i,
+// An input of `LayerProver::try_new` is of another shape than the layer, by the input:
+// `expected` is the one of the dims of the layer, `got` the one given.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GkrSetupError {
+    // W_i+1 must be over the k_i+1 variables the circuit declares, or the rounds bind the wrong
+    // number of variables.
+    WVarNumMismatch { expected: usize, got: usize },
+    // r_i has k_i elements, or none if add and mult are fixed at r_i already.
+    RiLenMismatch { expected: usize, got: usize },
+    // add_i and mult_i are over (r_i, b, c), so over v_l + v_r variables.
+    AddVarNumMismatch { expected: usize, got: usize },
+    MultVarNumMismatch { expected: usize, got: usize },
+    // the sparse gates are over another k_i+1.
+    WiringVarNumMismatch { expected: usize, got: usize },
+}
+
+impl fmt::Display for GkrSetupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GkrSetupError::WVarNumMismatch { expected, got } => write!(
+                f,
+                "W_i+1 is over {} variables, but the layer declares k_i+1 = {}",
+                got, expected
+            ),
+            GkrSetupError::RiLenMismatch { expected, got } => write!(
+                f,
+                "r_i has {} elements, but the layer declares k_i = {}",
+                got, expected
+            ),
+            GkrSetupError::AddVarNumMismatch { expected, got } => write!(
+                f,
+                "add_i is over {} variables, but (r_i, b, c) are {}",
+                got, expected
+            ),
+            GkrSetupError::MultVarNumMismatch { expected, got } => write!(
+                f,
+                "mult_i is over {} variables, but (r_i, b, c) are {}",
+                got, expected
+            ),
+            GkrSetupError::WiringVarNumMismatch { expected, got } => write!(
+                f,
+                "the sparse wiring is over k_i+1 = {}, but the layer declares k_i+1 = {}",
+                got, expected
+            ),
         }
     }
+}
 
-    // obtain m0 by $\sum_{b,c \in (0,1)^{k_{i+1}}}f_{r_i} = m_i $ , m1 means C1.
-    #[deprecated]
-    pub fn proof(&self) -> Scalar {
-        let k_i_plus_1 = self.w_i_plus_1.var_num;
+impl std::error::Error for GkrSetupError {}
 
-        let mut res = Scalar::zero();
-        for i in 0..k_i_plus_1 {
-            let a = convert_to_binary(&k_i_plus_1, i);
-            let w_a = self.w_i_plus_1.evaluate(&a);
+fn check_w_i_plus_1(dims: LayerDims, w_i_plus_1: &MPolynomial) -> Result<(), GkrSetupError> {
+    if w_i_plus_1.var_num != dims.k_i_plus_1 {
+        return Err(GkrSetupError::WVarNumMismatch {
+            expected: dims.k_i_plus_1,
+            got: w_i_plus_1.var_num,
+        });
+    }
+    Ok(())
+}
 
-            // ops_domain = (ri, a, b)
-            let mut ops_domain = self.r_i.clone();
-            ops_domain.append(&mut a.clone());
+// The checks of `try_new`, in the order of the args.
+fn check_dense(
+    dims: LayerDims,
+    (add, mult, w_i_plus_1): (&MPolynomial, &MPolynomial, &MPolynomial),
+    r_i: &[usize],
+) -> Result<(), GkrSetupError> {
+    check_w_i_plus_1(dims, w_i_plus_1)?;
+    let v_l = r_i.len();
+    if v_l != 0 && v_l != dims.k_i {
+        return Err(GkrSetupError::RiLenMismatch {
+            expected: dims.k_i,
+            got: v_l,
+        });
+    }
+    let expected = v_l + dims.v_r();
+    if add.var_num != expected {
+        return Err(GkrSetupError::AddVarNumMismatch {
+            expected,
+            got: add.var_num,
+        });
+    }
+    if mult.var_num != expected {
+        return Err(GkrSetupError::MultVarNumMismatch {
+            expected,
+            got: mult.var_num,
+        });
+    }
+    Ok(())
+}
 
-            for j in 0..k_i_plus_1 {
-                let b = convert_to_binary(&k_i_plus_1, j);
+impl LayerProver {
+    // r_i is empty if add and mult are fixed at r_i already, eg: by `Layer::wiring_evals_at`.
+    // It panics on the inputs `try_new` rejects.
+    pub fn new(dims: LayerDims, g: FrPoly, r_i: Vec<usize>, m_i: Scalar) -> Self {
+        Self::try_new(dims, g, r_i, m_i).unwrap_or_else(|e| panic!("{}", e))
+    }
 
-                let w_b = self.w_i_plus_1.evaluate(&b);
+    // Same as `new`, but the shapes of W_i+1, r_i, add and mult are checked against the layer
+    // first, so a wrong one is named here rather than by an assert of `evaluate` in a round.
+    pub fn try_new(
+        dims: LayerDims,
+        (add, mult, w_i_plus_1): FrPoly,
+        r_i: Vec<usize>,
+        m_i: Scalar,
+    ) -> Result<Self, GkrSetupError> {
+        check_dense(dims, (&add, &mult, &w_i_plus_1), &r_i)?;
+        let v_r = dims.v_r();
 
-                ops_domain.clone().append(&mut b.clone());
-                let add_i = self.add.evaluate(&ops_domain);
-                let multi = self.mult.evaluate(&ops_domain);
+        Ok(Self {
+            dims,
+            v_r,
+            wiring: Wiring::Dense { add, mult, r_i },
+            w_i_plus_1: Secret::new(w_i_plus_1),
+            rounds: 0,
+            challenges: vec![],
+            last_poly: None,
+            claim: m_i,
+            schedule: DegreeSchedule::Uniform(2),
+        })
+    }
 
-                res += add_i * (w_a + w_b) + multi * (w_a * w_b);
-            }
-        }
-        res
+    // Same as `new`, but with the sparse add and mult, so nothing of size 2^(k_i + 2*k_i+1) is
+    // allocated. The round polys are the same as the dense ones, so are the proofs.
+    pub fn new_sparse(
+        dims: LayerDims,
+        wiring: SparseWiring,
+        w_i_plus_1: MPolynomial,
+        m_i: Scalar,
+    ) -> Self {
+        Self::try_new_sparse(dims, wiring, w_i_plus_1, m_i).unwrap_or_else(|e| panic!("{}", e))
     }
 
-    // Return g1(X) = sum g(X, x_2, ..., x_v)
-    // obtain  $g1(X) =  = m_i $ , m1 means C1.
-    // Return g1(x) = add(r_i, (X, a2, ...,a_k_1), (b1, ..., b_k_1) * (W(X, a2, ...,a_k_1) + W(b1,...,b_k_1))
-    //              + mult(r_i, (X, a2, ...,a_k_1), (b1, ..., b_k_1) * (W(X, a2, ...,a_k_1) * W(b1,...,b_k_1))
-    //              = poly_add * (poly_w_a + w_b) + poly_mult * (poly_w_a * w_b)
-    //              = poly_add * poly_w_a + poly_add * w_b + poly_mult * (poly_w_a * w_b)
-    pub fn round_1(&self) -> Polynomial {
-        let poly_add = self.add.partial_evaluate(&self.r_i);
-        let poly_mult = self.mult.partial_evaluate(&self.r_i);
+    // Same as `new_sparse`, with the checks of `try_new` on W_i+1 and the wiring.
+    pub fn try_new_sparse(
+        dims: LayerDims,
+        wiring: SparseWiring,
+        w_i_plus_1: MPolynomial,
+        m_i: Scalar,
+    ) -> Result<Self, GkrSetupError> {
+        check_w_i_plus_1(dims, &w_i_plus_1)?;
+        if wiring.var_num_i_plus_1 != dims.k_i_plus_1 {
+            return Err(GkrSetupError::WiringVarNumMismatch {
+                expected: dims.k_i_plus_1,
+                got: wiring.var_num_i_plus_1,
+            });
+        }
+        let w_table = Secret::new(EvalTable::new(w_i_plus_1.hypercube_evaluations()));
+        debug_assert_eq!(w_i_plus_1.agrees_with_table(&w_table), Ok(()));
 
-        let poly_w_a = self.w_i_plus_1.partial_evaluate(&vec![]);
-        let w_b = self.w_i_plus_1.sum_all_evals();
+        Ok(Self {
+            dims,
+            v_r: dims.v_r(),
+            wiring: Wiring::Sparse { wiring, w_table },
+            w_i_plus_1: Secret::new(w_i_plus_1),
+            rounds: 0,
+            challenges: vec![],
+            last_poly: None,
+            claim: m_i,
+            schedule: DegreeSchedule::Uniform(2),
+        })
+    }
 
-        assert_eq!(
-            w_b,
-            poly_w_a.evaluate(Scalar::one()) + poly_w_a.evaluate(Scalar::zero())
+    // g_j is interpolated at d_j + 1 points, where d_j is the degree of round j in the schedule,
+    // which must be the one of f_{r_i} in the variable, see `gkr::layer_schedule`.
+    pub fn with_schedule(mut self, schedule: DegreeSchedule) -> Self {
+        assert!(
+            schedule.fits(self.v_r),
+            "the schedule is of {:?} rounds, but the layer has {}",
+            schedule.rounds(),
+            self.v_r
         );
+        self.schedule = schedule;
+        self
+    }
+
+    // obtain m0 by $\sum_{b,c \in (0,1)^{k_{i+1}}}f_{r_i} = m_i $ , m1 means C1.
+    // It's g_1(0) + g_1(1), see `round`.
+    #[deprecated]
+    pub fn proof(&self) -> Scalar {
+        let g_1 = self.round(&[]);
+        g_1.evaluate(Scalar::zero()) + g_1.evaluate(Scalar::one())
+    }
 
-        // poly_add * poly_w_a + poly_add * w_b + poly_mult * (poly_w_a * w_b)
-        poly_add
-            .mul(&poly_w_a)
-            .add(&poly_add.mul(&w_b).add(&poly_mult.mul(&poly_w_a).mul(&w_b)))
+    // Return g_j(X) = sum f_{r_i}(r_1, ..., r_j-1, X, x_j+1, ..., x_v), j = challenges.len() + 1, where
+    //      f_{r_i}(b, c) = add(r_i, b, c) * (W(b) + W(c)) + mult(r_i, b, c) * (W(b) * W(c))
+    // f_{r_i} has degree 2 in each variable, so g_j is interpolated from g_j(0), g_j(1), g_j(2),
+    // or from g_j(0), g_j(1) in a degree-1 round of the schedule.
+    // Note: the sum is over the products, add, mult and W can't be summed up separately.
+    // The hypercube is summed up in chunks, see `par_sum`, the rounds stay in order.
+    fn round(&self, challenges: &[usize]) -> Polynomial {
+        assert!(challenges.len() < self.v_r);
+
+        let rest = self.v_r - challenges.len() - 1;
+        let points = self.schedule.degree(challenges.len() + 1) + 1;
+        let domains = (0..points as u64).map(Scalar::from).collect::<Vec<_>>();
+        let evals = match &self.wiring {
+            Wiring::Dense { add, mult, r_i } => (0..points)
+                .map(|x| {
+                    par_sum(1 << rest, |i| {
+                        let mut bc = challenges.to_vec();
+                        bc.push(x);
+                        bc.extend(convert_to_binary(&rest, i));
+                        self.f_r((add, mult, r_i), &bc)
+                    })
+                })
+                .collect(),
+            Wiring::Sparse { wiring, w_table } => (0..points)
+                .map(|x| Self::sparse_sum(wiring, w_table, challenges, x))
+                .collect(),
+        };
+        Polynomial::lagrange_interpolate(domains, evals)
     }
 
-    // 1 < j < v_r, total v_r-2 rounds
-    // Return g_j = (r1, ..., r_j-1, X, x_j+1, ..., x_v)
-    pub fn recursive_round_j(&self, challenges: &Vec<usize>) -> Polynomial {
-        assert!(self.v_r > challenges.len() || challenges.len() >= 1);
+    // f_{r_i}(b, c), bc = (b, c).
+    fn f_r(
+        &self,
+        (add, mult, r_i): (&MPolynomial, &MPolynomial, &Vec<usize>),
+        bc: &[usize],
+    ) -> Scalar {
+        let (b, c) = bc.split_at(self.dims.k_i_plus_1);
+        let w_b = self.w_i_plus_1.evaluate(&b.to_vec());
+        let w_c = self.w_i_plus_1.evaluate(&c.to_vec());
+
+        let mut ops_domain = r_i.clone();
+        ops_domain.extend_from_slice(bc);
+        add.evaluate(&ops_domain) * (w_b + w_c) + mult.evaluate(&ops_domain) * (w_b * w_c)
+    }
 
-        // partial_evaluate with (r_i, challenge, X, x_i)
-        let mut ops_challenge_domain = self.r_i.clone();
-        ops_challenge_domain.append(&mut challenges.clone());
-        let poly_add = self.add.partial_evaluate(&ops_challenge_domain);
-        let poly_mult = self.mult.partial_evaluate(&ops_challenge_domain);
+    // g_j(x) with the sparse add and mult. Let p = (r_1, ..., r_j-1, x), then (p, rest) is
+    // non-zero only at the rest which is the low bits of (left, right) of a gate, so
+    //      g_j(x) = ∑_{gates} eq(r_i, j) * eq(p, the high bits of (left, right)) * (W(b) op W(c))
+    // where W(b) and W(c) are looked up in the table of W bound with the challenges of b, or c.
+    // It costs O(S_i * j + 2^k_i+1) rather than O(2^(k_i + 2*k_i+1)).
+    fn sparse_sum(
+        wiring: &SparseWiring,
+        w_table: &EvalTable,
+        challenges: &[usize],
+        x: usize,
+    ) -> Scalar {
+        let k = wiring.var_num_i_plus_1;
+        let mut p = challenges
+            .iter()
+            .map(|r| Scalar::from(*r as u64))
+            .collect::<Vec<_>>();
+        p.push(Scalar::from(x as u64));
+        let rest = 2 * k - p.len();
 
-        let (poly_w, w_value) = if challenges.len() < self.v_r / 2 {
-            //challenges only support partial of a
-            let poly_w_a = self.w_i_plus_1.partial_evaluate(challenges);
-            let w_b = self.w_i_plus_1.sum_all_evals();
-            (poly_w_a, w_b)
+        // b is bound in the first k rounds, then W(b) = W(u) and c is bound.
+        let (w_u, bound) = if p.len() <= k {
+            (None, &p[..])
         } else {
-            //challenges support all a and partial b
-            let mut c = challenges.chunks(self.v_r / 2);
-            // evaluate all with challenge
-            let w_a = self.w_i_plus_1.evaluate(&Vec::from(c.next().unwrap()));
-            let poly_w_b = self
-                .w_i_plus_1
-                .partial_evaluate(&Vec::from(c.next().unwrap()));
-            (poly_w_b, w_a)
+            (Some(w_table.evaluate(&p[..k])), &p[k..])
         };
+        let mut w_bound = Secret::copy_of(w_table);
+        for r in bound {
+            w_bound.bind_first(*r);
+        }
+        let mask = (1 << w_bound.var_num) - 1;
 
-        // poly_add * poly_w_a + poly_add * w_b + poly_mult * (poly_w_a * w_b)
-        poly_add.mul(&poly_w).add(
-            &poly_add
-                .mul(&w_value)
-                .add(&poly_mult.mul(&poly_w).mul(&w_value)),
-        )
+        let mut sum = Scalar::zero();
+        for (eq_r, gate) in wiring.terms.iter() {
+            let (left, right) = match gate {
+                ADD(left, right) | MUL(left, right) => (*left, *right),
+            };
+            let eq_p = eq_eval_at_index(&p, ((left << k) + right) >> rest);
+            let (w_b, w_c) = match w_u {
+                None => (w_bound.evals[left & mask], w_table.evals[right]),
+                Some(w_u) => (w_u, w_bound.evals[right & mask]),
+            };
+            sum += eq_r
+                * eq_p
+                * match gate {
+                    ADD(_, _) => w_b + w_c,
+                    MUL(_, _) => w_b * w_c,
+                };
+        }
+        sum
     }
 
-    // Return g_v = (r1, r2, ..., r_v-1, X_v)
-    pub fn round_v(&self, challenges: &Vec<usize>) -> Polynomial {
-        assert_eq!(self.v_r - 1, challenges.len());
+    // Return g_j for j = 1, ..., v_r in order. The challenge r_j-1 of the previous round
+    // is bound first, and it's None only in round 1.
+    // The claim is updated only here and in `bind_final`: claim_j = g_j-1(r_j-1).
+    pub fn next_round(&mut self, prev_challenge: Option<usize>) -> Polynomial {
+        assert!(self.rounds < self.v_r, "all rounds are done");
+        match prev_challenge {
+            None => assert_eq!(self.rounds, 0, "the challenge of last round is missing"),
+            Some(r) => self.bind(r),
+        }
 
-        // partial_evaluate with (r_i, challenge, X, x_i)
-        let mut ops_challenge_domain = self.r_i.clone();
-        ops_challenge_domain.append(&mut challenges.clone());
-        let poly_add = self.add.partial_evaluate(&ops_challenge_domain);
-        let poly_mult = self.mult.partial_evaluate(&ops_challenge_domain);
+        let g_j = self.round(&self.challenges);
+        self.last_poly = Some(g_j.clone());
+        self.rounds += 1;
+        g_j
+    }
 
-        //challenges support all a and partial b
-        let mut c = challenges.chunks(self.v_r / 2);
-        // evaluate all with challenge
-        let w_a = self.w_i_plus_1.evaluate(&Vec::from(c.next().unwrap()));
-        let poly_w_b = self
-            .w_i_plus_1
-            .partial_evaluate(&Vec::from(c.next().unwrap()));
+    // Bind r_v after the last round, then the claim is g_v(r_v) for the final check.
+    // It isn't called when v_r = 0, as there's no r_v, and the claim stays m_i.
+    pub fn bind_final(&mut self, r_v: usize) {
+        assert_eq!(self.rounds, self.v_r, "not all rounds are done");
+        self.bind(r_v);
+    }
 
-        // poly_add * poly_w_a + poly_add * w_b + poly_mult * (poly_w_a * w_b)
-        poly_add
-            .mul(&poly_w_b)
-            .add(&poly_add.mul(&w_a).add(&poly_mult.mul(&poly_w_b).mul(&w_a)))
+    fn bind(&mut self, r: usize) {
+        assert_eq!(
+            self.challenges.len() + 1,
+            self.rounds,
+            "the challenge of last round is bound already"
+        );
+        let g = self.last_poly.as_ref().unwrap();
+        self.claim = g.evaluate(Scalar::from(r as u64));
+        self.challenges.push(r);
     }
 
-    // challenges include (u, v), here we need (r,u,v)
-    pub fn evaluate(
-        &self,
-        challenges: &Vec<usize>,
-    ) -> (Scalar, Scalar, Vec<Vec<usize>>, Polynomial) {
-        assert_eq!(self.v_r, challenges.len());
+    // 2*k_i+1, the number of rounds.
+    pub fn v_r(&self) -> usize {
+        self.v_r
+    }
+
+    pub fn dims(&self) -> LayerDims {
+        self.dims
+    }
 
-        // 1. evaluate add/mult at (r,u,v)
-        let mut ops_challenge_domain = self.r_i.clone();
-        ops_challenge_domain.append(&mut challenges.clone());
-        let add_value = self.add.evaluate(&ops_challenge_domain);
-        let mult_value = self.mult.evaluate(&ops_challenge_domain);
+    // m_i before round 1, then g_j-1(r_j-1) after r_j-1 is bound.
+    pub fn current_claim(&self) -> Scalar {
+        self.claim
+    }
 
-        // 2 Obtain W_i_1(u) and W_i_1(v) for verifier's final check and prepare for the `r_i_plus_1` used in next round.
-        let mut c = challenges.chunks(self.v_r / 2);
-        let u = Vec::from(c.next().unwrap());
-        let v = Vec::from(c.next().unwrap());
+    // challenges include (u, v), all of them should be bound.
+    // Note: add(r,u,v) and mult(r,u,v) are evaluated by verifier itself, see `Layer::eval_add_mle`.
+    pub fn evaluate(&self) -> (Vec<Polynomial>, Polynomial) {
+        let challenges = &self.challenges;
+        assert_eq!(self.v_r, challenges.len());
 
-        // 2.1 Obtain the values: W_i_1(u) and W_i_1(v)
-        let w_u_value = self.w_i_plus_1.evaluate(&u);
-        let w_v_value = self.w_i_plus_1.evaluate(&v);
+        // Obtain W_i_1(u) and W_i_1(v) for verifier's final check and prepare for the `r_i_plus_1` used in next round.
+        // u and v are empty when layer i+1 is a single wire.
+        let (u, v) = challenges.split_at(self.dims.k_i_plus_1);
 
         // 2.2 Let l be the unique poly satisfying l(0)=u and l(1)=v
         //      As u,v are arrays, so that, l can be a set of poly_i.
         //      The poly_i satisfying poly_i(0)=u[i] and poly_i(1)=v[i]
+        // l is over F as p is, so v[i] < u[i] is a line as any other one, and l(t) = r_i+1.
         let l_polys = u
             .iter()
-            .zip(v)
+            .zip(v.iter())
             .map(|(ui, vi)| {
                 // l_i(x) = (vi-ui)x + ui <--> l(0)=u and l(1)=v
-                // As the Polynomail only support the Scalar. So we use the arrays as a usize-poly:
-                //      p(x) = = a_0 + a_1 * X + ... + a_n * X^(n-1)
-                //      coeffs: [a_0, a_1, ..., a_n]
-                vec![*ui, vi - *ui]
+                let (ui, vi) = (Scalar::from(*ui as u64), Scalar::from(*vi as u64));
+                Polynomial::from_coeffs(vec![ui, vi - ui])
             })
             .collect::<Vec<_>>();
 
-        // 2.3 Let q be the unique poly satisfying p(0)=W_i_1(u) and p(1)=W_i_1(v).
-        //      Note: Here we regard domain(0,1) on Scalar
-        let p_poly = Polynomial::lagrange_interpolate(
-            vec![Scalar::zero(), Scalar::one()],
-            vec![w_u_value, w_v_value],
+        // 2.3 Let q = W_i_1(l(x)), so p(0)=W_i_1(u) and p(1)=W_i_1(v).
+        (l_polys, self.line_poly())
+    }
+
+    // q = W_i+1(l(X)) alone, where l(0) = u and l(1) = v, eg: for the forked mode, where the next
+    // claim is opened at l(t) rather than proved.
+    // l is a line, so q has degree k_i+1, and it's interpolated from q(0), ..., q(k_i+1).
+    // Two points are enough only if u and v differ in one coordinate, else m_i+1 = q(t) isn't
+    // W_i+1(l(t)) and the next layer is rejected.
+    pub fn line_poly(&self) -> Polynomial {
+        let challenges = &self.challenges;
+        assert_eq!(self.v_r, challenges.len());
+        let (u, v) = challenges.split_at(self.dims.k_i_plus_1);
+        let w_table =
+            ZeroizingEvalTable::new(EvalTable::new(self.w_i_plus_1.hypercube_evaluations()));
+        let domain = (0..=self.dims.k_i_plus_1.max(1))
+            .map(|x| Scalar::from(x as u64))
+            .collect::<Vec<_>>();
+        let evals = domain
+            .iter()
+            .map(|x| {
+                let l_x = u
+                    .iter()
+                    .zip(v.iter())
+                    .map(|(ui, vi)| {
+                        let (ui, vi) = (Scalar::from(*ui as u64), Scalar::from(*vi as u64));
+                        ui + (vi - ui) * x
+                    })
+                    .collect::<Vec<_>>();
+                w_table.evaluate(&l_x)
+            })
+            .collect::<Vec<_>>();
+        Polynomial::lagrange_interpolate(domain, evals)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::arithmetic::layered_circuit::Ops::MUL;
+    use crate::arithmetic::layered_circuit::{CircuitConfig, Layer, LayerDims};
+    use crate::gkr_sumcheck::prover::{GkrSetupError, LayerProver};
+    use crate::gkr_sumcheck::FrPoly;
+    use crate::poly::{EvalTable, MPolynomial, Polynomial};
+    use bls12_381::Scalar;
+    use ff::PrimeField;
+    use rand_core::{OsRng, RngCore};
+
+    // the output layer of 2 gates on the 4 gates of layer 1.
+    const OUTPUT_LAYER: LayerDims = LayerDims {
+        k_i: 1,
+        k_i_plus_1: 2,
+    };
+
+    // sample from Figure 4.12
+    fn figure_4_12() -> (CircuitConfig, Vec<Scalar>) {
+        let layer_1 = Layer {
+            gates: vec![MUL(0, 0), MUL(1, 1), MUL(1, 2), MUL(3, 3)],
+            var_num: 2,
+        };
+        let output_layer = Layer {
+            gates: vec![MUL(0, 1), MUL(2, 3)],
+            var_num: 1,
+        };
+        let config = CircuitConfig {
+            layers: vec![output_layer, layer_1],
+            input_var_num: 2,
+            depth: 3,
+        };
+        let inputs = vec![
+            Scalar::one(),
+            Scalar::from_u128(2),
+            Scalar::one(),
+            Scalar::from_u128(4),
+        ];
+        (config, inputs)
+    }
+
+    // the sumcheck of the output layer of Figure 4.12.
+    fn output_layer_poly() -> FrPoly {
+        let (config, inputs) = figure_4_12();
+        let (add, mult) = config.ops_to_mpoly()[0].clone();
+        let (witness, _) = config.witness_to_poly(&inputs);
+        (add, mult, witness[1].clone())
+    }
+
+    // ∑ f_{r_i}(b, c) = W_0(r_i), the output gates are 1 * 4 and 2 * 16 on the hypercube.
+    #[test]
+    #[allow(deprecated)]
+    fn test_proof() {
+        let m_0 =
+            LayerProver::new(OUTPUT_LAYER, output_layer_poly(), vec![0], Scalar::zero()).proof();
+        assert_eq!(m_0, Scalar::from_u128(4));
+        let m_1 =
+            LayerProver::new(OUTPUT_LAYER, output_layer_poly(), vec![1], Scalar::zero()).proof();
+        assert_eq!(m_1, Scalar::from_u128(32));
+    }
+
+    #[test]
+    fn test_claims_chain() {
+        let r_i = vec![3];
+        let m_i = Scalar::from_u128(7);
+        let mut prover = LayerProver::new(OUTPUT_LAYER, output_layer_poly(), r_i, m_i);
+        let challenges = [5, 11, 2, 9];
+
+        let mut g_j = prover.next_round(None);
+        assert_eq!(prover.current_claim(), m_i);
+        for r_j in challenges[..3].iter() {
+            let g_j_plus_1 = prover.next_round(Some(*r_j));
+            // claim_{j+1} = g_j(r_j)
+            assert_eq!(
+                prover.current_claim(),
+                g_j.evaluate(Scalar::from(*r_j as u64))
+            );
+            g_j = g_j_plus_1;
+        }
+        prover.bind_final(challenges[3]);
+        assert_eq!(
+            prover.current_claim(),
+            g_j.evaluate(Scalar::from(challenges[3] as u64))
         );
+    }
+
+    #[test]
+    #[should_panic(expected = "all rounds are done")]
+    fn test_next_round_after_last() {
+        let mut prover =
+            LayerProver::new(OUTPUT_LAYER, output_layer_poly(), vec![3], Scalar::one());
+        prover.next_round(None);
+        for r_j in [5, 11, 2, 9] {
+            prover.next_round(Some(r_j));
+        }
+    }
+
+    #[test]
+    fn test_rounds_are_consistent() {
+        // W_0 = [4, 32], so m_i = W_0(3) = 4 * (1 - 3) + 32 * 3
+        let m_i = Scalar::from_u128(88);
+        let mut prover = LayerProver::new(OUTPUT_LAYER, output_layer_poly(), vec![3], m_i);
+
+        // g_j(0) + g_j(1) = g_j-1(r_j-1), where g_0(r_0) = m_i.
+        let mut claim = m_i;
+        let mut prev = None;
+        for r_j in [5, 11, 2, 9] {
+            let g_j = prover.next_round(prev);
+            assert!(g_j.degree() <= 2);
+            assert_eq!(
+                g_j.evaluate(Scalar::zero()) + g_j.evaluate(Scalar::one()),
+                claim
+            );
+            claim = g_j.evaluate(Scalar::from(r_j as u64));
+            prev = Some(r_j);
+        }
+    }
+
+    // g_1 is interpolated from g_1(0), g_1(1), g_1(2), so it's the same poly as the Newton form
+    //      g_1(0) + X * Δ1 + X * (X - 1) * Δ2 / 2
+    // built by the operators, which trim their results.
+    #[test]
+    fn test_round_1_poly() {
+        let m_i = Scalar::from_u128(88);
+        let mut prover = LayerProver::new(OUTPUT_LAYER, output_layer_poly(), vec![3], m_i);
+        let g_1 = prover.next_round(None);
+        assert_eq!(g_1.coeffs().len(), 3);
+
+        let [e_0, e_1, e_2] = [0u128, 1, 2].map(|x| g_1.evaluate(Scalar::from_u128(x)));
+        let x = Polynomial::x();
+        let x_minus_1 = &x - &Polynomial::constant(Scalar::one());
+        let half = Scalar::from_u128(2).invert().unwrap();
+        let newton = Polynomial::constant(e_0)
+            + &x * (e_1 - e_0)
+            + &x * &x_minus_1 * ((e_2 - e_1 - e_1 + e_0) * half);
+
+        assert_eq!(&g_1 - &newton, Polynomial::zero());
+        assert_eq!(e_0 + e_1, m_i);
+    }
+
+    // u = (2, 9) and v = (5, 11) differ in both coordinates, so W_i+1(l(x)) has degree 2, and
+    // the line through W_i+1(u) and W_i+1(v) isn't W_i+1 on l.
+    #[test]
+    fn test_p_poly_on_the_line() {
+        let (_, _, w_i_plus_1) = output_layer_poly();
+        let w_table = EvalTable::new(w_i_plus_1.hypercube_evaluations());
+        let m_i = Scalar::from_u128(88);
+        let mut prover = LayerProver::new(OUTPUT_LAYER, output_layer_poly(), vec![3], m_i);
+        let mut prev = None;
+        for r_j in [2, 9, 5, 11] {
+            prover.next_round(prev);
+            prev = Some(r_j);
+        }
+        prover.bind_final(11);
+        let (_, p_poly) = prover.evaluate();
+
+        assert_eq!(p_poly.degree(), OUTPUT_LAYER.k_i_plus_1);
+        assert_eq!(
+            p_poly.evaluate(Scalar::zero()),
+            w_i_plus_1.evaluate(&vec![2, 9])
+        );
+        assert_eq!(
+            p_poly.evaluate(Scalar::one()),
+            w_i_plus_1.evaluate(&vec![5, 11])
+        );
+        for t in [2u64, 7, 100] {
+            let t = Scalar::from(t);
+            let l_t = [
+                Scalar::from(2) + Scalar::from(3) * t,
+                Scalar::from(9) + Scalar::from(2) * t,
+            ];
+            assert_eq!(p_poly.evaluate(t), w_table.evaluate(&l_t));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "W_i+1 is over 2 variables, but the layer declares k_i+1 = 1")]
+    fn test_dims_mismatch() {
+        let dims = LayerDims {
+            k_i: 1,
+            k_i_plus_1: 1,
+        };
+        LayerProver::new(dims, output_layer_poly(), vec![3], Scalar::one());
+    }
+
+    fn zero_poly(var_num: usize) -> MPolynomial {
+        MPolynomial {
+            var_num,
+            coeffs: vec![Scalar::zero(); 1 << var_num],
+        }
+    }
 
-        // return the add(r_i,u,v), mult(r_i,u,v), l_poly, p_poly.
-        (add_value, mult_value, l_polys, p_poly)
+    // The error of the inputs of the dims, with zero polys over the var_nums.
+    fn setup(
+        dims: LayerDims,
+        (add, mult, w): (usize, usize, usize),
+        r_i_len: usize,
+    ) -> Result<(), GkrSetupError> {
+        let g = (zero_poly(add), zero_poly(mult), zero_poly(w));
+        LayerProver::try_new(dims, g, vec![3; r_i_len], Scalar::zero()).map(|_| ())
+    }
+
+    #[test]
+    fn test_setup_errors() {
+        // (k_i, k_i+1) = (1, 2), so add and mult are over 1 + 4 variables, or 4 if fixed at r_i.
+        let dims = OUTPUT_LAYER;
+        assert_eq!(setup(dims, (5, 5, 2), 1), Ok(()));
+        assert_eq!(setup(dims, (4, 4, 2), 0), Ok(()));
+        assert_eq!(
+            setup(dims, (5, 5, 1), 1),
+            Err(GkrSetupError::WVarNumMismatch {
+                expected: 2,
+                got: 1
+            })
+        );
+        assert_eq!(
+            setup(dims, (5, 5, 2), 2),
+            Err(GkrSetupError::RiLenMismatch {
+                expected: 1,
+                got: 2
+            })
+        );
+        assert_eq!(
+            setup(dims, (4, 5, 2), 1),
+            Err(GkrSetupError::AddVarNumMismatch {
+                expected: 5,
+                got: 4
+            })
+        );
+        assert_eq!(
+            setup(dims, (5, 5, 2), 0),
+            Err(GkrSetupError::AddVarNumMismatch {
+                expected: 4,
+                got: 5
+            })
+        );
+        assert_eq!(
+            setup(dims, (5, 6, 2), 1),
+            Err(GkrSetupError::MultVarNumMismatch {
+                expected: 5,
+                got: 6
+            })
+        );
+
+        let (config, _) = figure_4_12();
+        let wiring = config.layers[0].sparse_wiring_at(&[Scalar::from(3)], 3);
+        let res = LayerProver::try_new_sparse(dims, wiring, zero_poly(2), Scalar::zero());
+        assert_eq!(
+            res.err(),
+            Some(GkrSetupError::WiringVarNumMismatch {
+                expected: 2,
+                got: 3
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "add_i is over 4 variables, but (r_i, b, c) are 5")]
+    fn test_new_panics_with_setup_error() {
+        let (_, mult, w) = output_layer_poly();
+        LayerProver::new(
+            OUTPUT_LAYER,
+            (zero_poly(4), mult, w),
+            vec![3],
+            Scalar::one(),
+        );
+    }
+
+    // The dense and sparse layers of the circuit, as P of `gkr` builds them.
+    #[test]
+    fn test_circuit_layers_pass() {
+        let (config, inputs) = figure_4_12();
+        let (witness, _) = config.witness_to_poly(&inputs);
+        for (i, (add, mult)) in config.ops_to_mpoly().into_iter().enumerate() {
+            let dims = config.layer_dims(i);
+            let r_i = vec![3; dims.k_i];
+            let g = (add, mult, witness[i + 1].clone());
+            assert!(LayerProver::try_new(dims, g, r_i.clone(), Scalar::zero()).is_ok());
+
+            let r_i = r_i
+                .iter()
+                .map(|r| Scalar::from(*r as u64))
+                .collect::<Vec<_>>();
+            let wiring = config.layers[i].sparse_wiring_at(&r_i, dims.k_i_plus_1);
+            let w = witness[i + 1].clone();
+            assert!(LayerProver::try_new_sparse(dims, wiring, w, Scalar::zero()).is_ok());
+        }
+    }
+
+    // Random consistent shapes pass, and a shape with any one input off by one is named.
+    #[test]
+    fn test_random_setups() {
+        let rand = |n: u32| (OsRng.next_u32() % n) as usize;
+        for _ in 0..100 {
+            let dims = LayerDims {
+                k_i: rand(4),
+                k_i_plus_1: rand(4),
+            };
+            let r_i_len = if rand(2) == 0 { 0 } else { dims.k_i };
+            let v = r_i_len + dims.v_r();
+            let mut shape = [v, v, dims.k_i_plus_1, r_i_len];
+            let res = setup(dims, (shape[0], shape[1], shape[2]), shape[3]);
+            assert_eq!(res, Ok(()), "{:?}", dims);
+
+            let field = rand(4);
+            // r_i of k_i + 1 elements is neither none nor k_i.
+            shape[field] = if field == 3 {
+                dims.k_i + 1
+            } else {
+                shape[field] + 1
+            };
+            let got = shape[field];
+            let expected = [
+                GkrSetupError::AddVarNumMismatch { expected: v, got },
+                GkrSetupError::MultVarNumMismatch { expected: v, got },
+                GkrSetupError::WVarNumMismatch {
+                    expected: dims.k_i_plus_1,
+                    got,
+                },
+                GkrSetupError::RiLenMismatch {
+                    expected: dims.k_i,
+                    got,
+                },
+            ][field];
+            let res = setup(dims, (shape[0], shape[1], shape[2]), shape[3]);
+            assert_eq!(res, Err(expected), "{:?}", dims);
+        }
     }
 }
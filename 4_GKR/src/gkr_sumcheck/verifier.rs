@@ -24,10 +24,9 @@ impl Verifier {
         self.challenges.clone()
     }
 
-    // generate r1, r2, ..., rv
+    // generate r1, r2, ..., rv, over the whole u64 as P takes them in F, see `LayerProver::bind`.
     pub(super) fn gen_challenge() -> usize {
-        let k = OsRng.next_u32() % 1000;
-        k as usize
+        OsRng.next_u64() as usize
     }
     fn prepare_next_round(&mut self, g_i: Polynomial) {
         assert_eq!(self.challenges.len(), self.cached_g_j.len());
@@ -38,6 +37,7 @@ impl Verifier {
 
     // Check: C1 = g_1(0) + g_1(1)
     pub fn round_1(&mut self, g1: Polynomial) {
+        assert!(self.v > 0, "no rounds when v = 0");
         let actual = g1.evaluate(Scalar::one()) + g1.evaluate(Scalar::zero());
 
         assert_eq!(actual, self.proof, "No-equal in round_1");
@@ -56,7 +56,9 @@ impl Verifier {
     }
 
     // Check: gv−1 (rv−1 ) = gv (0) + gv (1).
+    // When v = 1, round v is round 1, so it's checked by `round_1` only.
     pub fn round_v(&mut self, g_v: Polynomial) {
+        assert!(self.v > 1, "round v is round 1 when v = 1");
         self.check_round_j(self.v, &g_v);
 
         // prepare r_v and store g_v.
@@ -104,9 +106,11 @@ impl Verifier {
         let target = add_value * (p_poly.evaluate(Scalar::zero()) + p_poly.evaluate(Scalar::one()))
             + mult_value * (p_poly.evaluate(Scalar::zero()) * p_poly.evaluate(Scalar::one()));
 
-        let r_v = self.challenges.last().unwrap().clone() as u128;
-        let g_v = self.cached_g_j.last().unwrap().clone();
-        let actual = g_v.evaluate(Scalar::from_u128(r_v));
+        // v = 0: no round, so m_i is checked against f_{r_i}() itself.
+        let actual = match (self.challenges.last(), self.cached_g_j.last()) {
+            (Some(r_v), Some(g_v)) => g_v.evaluate(Scalar::from_u128(*r_v as u128)),
+            _ => self.proof,
+        };
 
         assert_eq!(actual, target, "Verifier rejected the proof");
         println!("Verifier accepted the proof");
@@ -1,5 +1,14 @@
+// The `verifier` feature alone is the verifier-only build: `gkr::GkrVerifierState` and
+// `gkr::diagnose` over a recorded proof, with the circuit of `arithmetic`. It has no OsRng nor
+// rayon, so `GKR`, the interactive `gkr_sumcheck` and the `parallel` feature are out of it.
+#[cfg(all(feature = "verifier", not(feature = "prover"), feature = "parallel"))]
+compile_error!("the verifier-only build of GKR can't enable `parallel`, it brings rayon");
+
+#[cfg(all(test, feature = "prover"))]
+mod analysis;
 pub mod arithmetic;
 pub mod gkr;
+#[cfg(feature = "prover")]
 pub mod gkr_sumcheck;
 pub mod poly;
 pub mod utils;
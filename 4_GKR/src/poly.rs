@@ -1,2 +1,4 @@
+pub use sumcheck::poly::budget::{DenseBudget, ResourceLimit};
+pub use sumcheck::poly::eval_table::EvalTable;
 pub use sumcheck::poly::multivar_poly::MPolynomial;
 pub use sumcheck::poly::univar_poly::Polynomial;
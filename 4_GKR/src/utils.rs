@@ -1,2 +1,43 @@
 pub use sumcheck::utils::convert_from_binary;
 pub use sumcheck::utils::convert_to_binary;
+pub use sumcheck::utils::eq_eval;
+pub use sumcheck::utils::eq_eval_at_index;
+pub use sumcheck::utils::eq_table;
+
+use bls12_381::Scalar;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+// The number of terms summed up by a rayon task at least, so a small hypercube isn't split.
+#[cfg(feature = "parallel")]
+const PAR_MIN_LEN: usize = 64;
+
+// f of each item, in parallel with the `parallel` feature. The results are in the order of the
+// items either way.
+pub(crate) fn par_map<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> R + Sync + Send) -> Vec<R> {
+    #[cfg(feature = "parallel")]
+    {
+        items.par_iter().map(f).collect()
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        items.iter().map(f).collect()
+    }
+}
+
+// ∑ term(i) for i in [0, n), in chunks of PAR_MIN_LEN terms at least with the `parallel` feature.
+// The field addition is associative and commutative, so the sum doesn't depend on the chunks.
+pub(crate) fn par_sum(n: usize, term: impl Fn(usize) -> Scalar + Sync + Send) -> Scalar {
+    #[cfg(feature = "parallel")]
+    {
+        (0..n)
+            .into_par_iter()
+            .with_min_len(PAR_MIN_LEN)
+            .map(term)
+            .sum()
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        (0..n).map(term).sum()
+    }
+}
@@ -0,0 +1,201 @@
+// The evaluation claims passed between the reductions, eg: GKR reduces a claim on W̃_i to one on
+// W̃_i+1, the batching of the output claims merges several claims on W̃_0 into one, and the
+// commitment of the inputs discharges the last claim on W̃_d with an opening.
+//
+// A claim is `poly(point) = value`, where `poly` is a `PolyId` of a `PolyRegistry`, which only
+// keeps a description of it, eg: "W_2". `ClaimTracker` holds the claims still to be checked: a
+// reduction pops the claims it takes and pushes the ones it leaves, so what's left at the end of
+// a composition is `remaining()`, which the caller checks by other means, eg: an opening.
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use ff::Field;
+
+// The polys are told apart by their id only, see `PolyRegistry::describe` for their name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PolyId(u32);
+
+impl PolyId {
+    pub fn index(&self) -> u32 {
+        self.0
+    }
+}
+
+// The descriptions of the polys, by their id.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PolyRegistry {
+    descriptions: Vec<String>,
+}
+
+impl PolyRegistry {
+    pub fn register(&mut self, description: &str) -> PolyId {
+        self.descriptions.push(description.into());
+        PolyId(self.descriptions.len() as u32 - 1)
+    }
+
+    // The first poly of the description, eg: to read the claims on "W_0" from another tool.
+    pub fn find(&self, description: &str) -> Option<PolyId> {
+        let i = self.descriptions.iter().position(|d| d == description)?;
+        Some(PolyId(i as u32))
+    }
+
+    // None if the id is of another registry.
+    pub fn describe(&self, poly: PolyId) -> Option<&str> {
+        self.descriptions.get(poly.0 as usize).map(|d| d.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.descriptions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.descriptions.is_empty()
+    }
+}
+
+// poly(point) = value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClaimOver<F: Field> {
+    pub poly: PolyId,
+    pub point: Vec<F>,
+    pub value: F,
+}
+
+pub type Claim = ClaimOver<bls12_381::Scalar>;
+
+impl<F: Field> ClaimOver<F> {
+    pub fn new(poly: PolyId, point: Vec<F>, value: F) -> Self {
+        Self { poly, point, value }
+    }
+}
+
+// The claims to be checked, in the order they were pushed, and the registry of their polys.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClaimTrackerOver<F: Field> {
+    registry: PolyRegistry,
+    pending: Vec<ClaimOver<F>>,
+}
+
+pub type ClaimTracker = ClaimTrackerOver<bls12_381::Scalar>;
+
+impl<F: Field> Default for ClaimTrackerOver<F> {
+    fn default() -> Self {
+        Self {
+            registry: PolyRegistry::default(),
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl<F: Field> ClaimTrackerOver<F> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, description: &str) -> PolyId {
+        self.registry.register(description)
+    }
+
+    pub fn registry(&self) -> &PolyRegistry {
+        &self.registry
+    }
+
+    // The description of the poly, or its id for one of another registry.
+    pub fn describe(&self, poly: PolyId) -> String {
+        match self.registry.describe(poly) {
+            Some(description) => description.into(),
+            None => format!("poly #{}", poly.0),
+        }
+    }
+
+    pub fn push(&mut self, claim: ClaimOver<F>) {
+        self.pending.push(claim);
+    }
+
+    // The first claim on the poly, which the caller is about to reduce or check.
+    pub fn pop(&mut self, poly: PolyId) -> Option<ClaimOver<F>> {
+        let i = self.pending.iter().position(|c| c.poly == poly)?;
+        Some(self.pending.remove(i))
+    }
+
+    // All the claims on the poly, in the order they were pushed, eg: to batch them.
+    pub fn pop_all(&mut self, poly: PolyId) -> Vec<ClaimOver<F>> {
+        let (taken, kept) = core::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|c| c.poly == poly);
+        self.pending = kept;
+        taken
+    }
+
+    // The claims still to be checked by other means than the reductions.
+    pub fn remaining(&self) -> &[ClaimOver<F>] {
+        &self.pending
+    }
+
+    pub fn assert_all_discharged(&self) {
+        if !self.pending.is_empty() {
+            let polys = self
+                .pending
+                .iter()
+                .map(|c| self.describe(c.poly))
+                .collect::<Vec<_>>();
+            panic!("claims not discharged on: {}", polys.join(", "));
+        }
+    }
+}
+
+impl<F: Field> fmt::Display for ClaimTrackerOver<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} claims pending", self.pending.len())?;
+        for claim in self.pending.iter() {
+            write!(
+                f,
+                "\n  {} at {} coordinates",
+                self.describe(claim.poly),
+                claim.point.len()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bls12_381::Scalar;
+
+    #[test]
+    fn test_pop_in_push_order() {
+        let mut tracker = ClaimTracker::new();
+        let (w_1, w_2) = (tracker.register("W_1"), tracker.register("W_2"));
+        tracker.push(Claim::new(w_1, vec![Scalar::one()], Scalar::from(3)));
+        tracker.push(Claim::new(w_2, vec![Scalar::zero()], Scalar::from(4)));
+        tracker.push(Claim::new(w_1, vec![Scalar::zero()], Scalar::from(5)));
+
+        assert_eq!(tracker.pop(w_1).unwrap().value, Scalar::from(3));
+        assert_eq!(tracker.pop_all(w_1).len(), 1);
+        assert_eq!(tracker.pop(w_1), None);
+        assert_eq!(tracker.remaining().len(), 1);
+        assert_eq!(tracker.pop(w_2).unwrap().value, Scalar::from(4));
+        tracker.assert_all_discharged();
+    }
+
+    #[test]
+    #[should_panic(expected = "claims not discharged on: the inputs")]
+    fn test_forgotten_claim() {
+        let mut tracker = ClaimTracker::new();
+        let inputs = tracker.register("the inputs");
+        tracker.push(Claim::new(inputs, vec![], Scalar::one()));
+        tracker.assert_all_discharged();
+    }
+
+    #[test]
+    fn test_describe_other_registry() {
+        let mut other = PolyRegistry::default();
+        other.register("W_0");
+        let w_1 = other.register("W_1");
+        let tracker = ClaimTracker::new();
+        assert_eq!(tracker.describe(w_1), "poly #1");
+    }
+}
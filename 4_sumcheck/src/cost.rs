@@ -0,0 +1,333 @@
+// The arithmetic and hashing of a verifier, counted as it runs, eg: to check the verifier costs
+// claimed by the book (O(n^2) of Freivalds, O(v * deg) of the sum-check, ...) against the
+// measured ones.
+//
+// The verifiers report their ops to a `CostSink` next to the ops themselves. The no-op sink `()`
+// of the plain `verify` counts nothing and is inlined away, and `CostReport` adds them up. The
+// count of an op is the one of its algorithm here, whatever the values are, eg: Horner's rule
+// over n coeffs is n mults and n adds, even at x = 0.
+use core::ops::{Add, AddAssign};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CostReport {
+    pub field_mults: u64, // the squarings too
+    pub field_adds: u64,  // the subs and negations too
+    pub field_inversions: u64,
+    pub hash_calls: u64, // the digests of Keccak256, eg: 2 per scalar challenge
+    pub scalar_comparisons: u64,
+}
+
+impl CostReport {
+    pub const fn mults(n: usize) -> Self {
+        Self {
+            field_mults: n as u64,
+            field_adds: 0,
+            field_inversions: 0,
+            hash_calls: 0,
+            scalar_comparisons: 0,
+        }
+    }
+
+    pub const fn adds(n: usize) -> Self {
+        Self {
+            field_adds: n as u64,
+            ..Self::mults(0)
+        }
+    }
+
+    pub const fn inversions(n: usize) -> Self {
+        Self {
+            field_inversions: n as u64,
+            ..Self::mults(0)
+        }
+    }
+
+    pub const fn hashes(n: usize) -> Self {
+        Self {
+            hash_calls: n as u64,
+            ..Self::mults(0)
+        }
+    }
+
+    pub const fn comparisons(n: usize) -> Self {
+        Self {
+            scalar_comparisons: n as u64,
+            ..Self::mults(0)
+        }
+    }
+
+    // c * self, eg: the same op over the n elements.
+    pub const fn times(self, c: usize) -> Self {
+        let c = c as u64;
+        Self {
+            field_mults: self.field_mults * c,
+            field_adds: self.field_adds * c,
+            field_inversions: self.field_inversions * c,
+            hash_calls: self.hash_calls * c,
+            scalar_comparisons: self.scalar_comparisons * c,
+        }
+    }
+
+    // p(x) by Horner's rule from zero, p has len coeffs, see `Polynomial::evaluate`: a mult and
+    // an add per coeff. The split of a large p into rayon jobs isn't counted.
+    pub const fn horner(len: usize) -> Self {
+        Self {
+            field_adds: len as u64,
+            ..Self::mults(len)
+        }
+    }
+
+    // eq(x, b) at the k bits of the index, see `utils::eq_eval_at_index`: the product of x_i,
+    // or 1 - x_i at the zero bits.
+    pub const fn eq_at_index(k: usize, index: usize) -> Self {
+        Self {
+            field_adds: (k - index.count_ones() as usize) as u64,
+            ..Self::mults(k)
+        }
+    }
+
+    // W̃(r) of a table with v variables, see `EvalTable::evaluate`: each bind folds half of the
+    // table with a sub, a mult and an add, so it's 2^v - 1 folds in all.
+    pub const fn table_evaluate(v: usize) -> Self {
+        let folds = (1 << v) - 1;
+        Self {
+            field_adds: 2 * folds as u64,
+            ..Self::mults(folds)
+        }
+    }
+
+    // [eq(r, w) for w in {0,1}^v], see `utils::eq_table`: a mult and a sub per new entry.
+    pub const fn eq_table(v: usize) -> Self {
+        let entries = (1 << v) - 1;
+        Self {
+            field_adds: entries as u64,
+            ..Self::mults(entries)
+        }
+    }
+
+    // ∑ x_i·y_i over n entries from zero, see `utils::inner_product`.
+    pub const fn inner_product(n: usize) -> Self {
+        Self {
+            field_adds: n as u64,
+            ..Self::mults(n)
+        }
+    }
+
+    // The inverses of n values by Montgomery's trick, see `utils::batch_invert`: a zero check
+    // and 3 mults per value, and a single inversion.
+    pub const fn batch_invert(n: usize) -> Self {
+        Self {
+            field_inversions: 1,
+            scalar_comparisons: n as u64,
+            ..Self::mults(3 * n)
+        }
+    }
+
+    // x^e by square-and-multiply over the 64-bit limbs of e, see `Field::pow_vartime`.
+    pub fn pow_vartime(exp: &[u64]) -> Self {
+        let ones = exp.iter().map(|e| e.count_ones() as usize).sum::<usize>();
+        Self::mults(64 * exp.len() + ones)
+    }
+
+    // The poly through (0, y_0), ..., (n-1, y_n-1), see `Polynomial::lagrange_interpolate`:
+    // - the n(n-1) divisors x_j - x_k, which are batch inverted,
+    // - L_j = ∏(X - x_k) / (x_j - x_k), the k-th factor costs 3 mults and 2 adds per coeff,
+    // - ∑ y_j * L_j.
+    pub const fn interpolate(n: usize) -> Self {
+        let divisors = n * n.saturating_sub(1);
+        // ∑_{s=1}^{n-1} (s + 1) coeffs
+        let coeffs = n.saturating_sub(1) * (n + 2) / 2;
+        Self {
+            field_mults: (3 * divisors + 3 * n * coeffs + n * n) as u64,
+            field_adds: (divisors + 2 * n * coeffs + n * n) as u64,
+            field_inversions: (divisors > 0) as u64,
+            hash_calls: 0,
+            scalar_comparisons: 0,
+        }
+    }
+}
+
+impl Add for CostReport {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            field_mults: self.field_mults + rhs.field_mults,
+            field_adds: self.field_adds + rhs.field_adds,
+            field_inversions: self.field_inversions + rhs.field_inversions,
+            hash_calls: self.hash_calls + rhs.hash_calls,
+            scalar_comparisons: self.scalar_comparisons + rhs.scalar_comparisons,
+        }
+    }
+}
+
+impl AddAssign for CostReport {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+// A sink only counts: a verifier returns the same result whatever the sink is, so its `_with_cost`
+// variant and the plain one on `()` accept and reject the same proofs, and the analysis tests of
+// the crates check it on each measured run.
+pub trait CostSink {
+    fn count(&mut self, cost: CostReport);
+
+    // The same as `count`, for a cost which takes a loop to count, eg: over the gates of a layer.
+    fn count_with<F: FnOnce() -> CostReport>(&mut self, cost: F) {
+        self.count(cost());
+    }
+}
+
+// The no-op sink, which never runs the closures of `count_with`.
+impl CostSink for () {
+    #[inline(always)]
+    fn count(&mut self, _cost: CostReport) {}
+
+    #[inline(always)]
+    fn count_with<F: FnOnce() -> CostReport>(&mut self, _cost: F) {}
+}
+
+impl CostSink for CostReport {
+    fn count(&mut self, cost: CostReport) {
+        *self += cost;
+    }
+}
+
+// The dense `MPolynomial::evaluate` is O(2^v), which V must never run on a wiring predicate or
+// any other poly of the statement, eg: add_i of GKR has 2^(k_i + 2k_i+1) coeffs, and V evaluates
+// it from the gates instead. In the debug builds with std, a dense evaluation of more than
+// DENSE_EVAL_LIMIT coeffs inside `as_verifier` panics, so a test catches the regression. It's a
+// no-op otherwise.
+pub const DENSE_EVAL_LIMIT: usize = 1 << 16;
+
+#[cfg(all(feature = "std", debug_assertions))]
+std::thread_local! {
+    static VERIFYING: core::cell::Cell<bool> = const { core::cell::Cell::new(false) };
+}
+
+// Run f as V, see `DENSE_EVAL_LIMIT`. It may be nested, eg: a layer of `verify`.
+pub fn as_verifier<R>(f: impl FnOnce() -> R) -> R {
+    #[cfg(all(feature = "std", debug_assertions))]
+    {
+        // restore the outer scope on a panic too, eg: of a should_panic test.
+        struct Restore(bool);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                VERIFYING.with(|v| v.set(self.0));
+            }
+        }
+        let _restore = Restore(VERIFYING.with(|v| v.replace(true)));
+        f()
+    }
+    #[cfg(not(all(feature = "std", debug_assertions)))]
+    f()
+}
+
+// Called by the dense evaluations with the number of coeffs.
+#[inline]
+pub(crate) fn check_dense_eval(coeffs: usize) {
+    #[cfg(all(feature = "std", debug_assertions))]
+    if coeffs > DENSE_EVAL_LIMIT && VERIFYING.with(|v| v.get()) {
+        panic!(
+            "dense MPolynomial::evaluate of {} coeffs in V, the limit is {}",
+            coeffs, DENSE_EVAL_LIMIT
+        );
+    }
+    #[cfg(not(all(feature = "std", debug_assertions)))]
+    let _ = coeffs;
+}
+
+// The measured costs grow with the sizes as `expected` does: between each two sizes, the ratio
+// of the costs is within a `tolerance` factor of the expected one, eg: to tell v from 2^v.
+pub fn grows_like(
+    sizes: &[usize],
+    costs: &[u64],
+    expected: impl Fn(usize) -> f64,
+    tolerance: f64,
+) -> bool {
+    assert_eq!(sizes.len(), costs.len());
+    assert!(tolerance >= 1.0);
+    sizes.windows(2).zip(costs.windows(2)).all(|(n, c)| {
+        let measured = c[1] as f64 / c[0] as f64;
+        let predicted = expected(n[1]) / expected(n[0]);
+        measured <= predicted * tolerance && measured * tolerance >= predicted
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cost::{as_verifier, grows_like, CostReport, CostSink, DENSE_EVAL_LIMIT};
+    use crate::poly::multivar_poly::MPolynomial;
+    use bls12_381::Scalar;
+
+    #[test]
+    fn test_cost_report() {
+        let mut report = CostReport::default();
+        report.count(CostReport::horner(4));
+        report.count(CostReport::comparisons(1).times(2));
+        assert_eq!(
+            report,
+            CostReport {
+                field_mults: 4,
+                field_adds: 4,
+                field_inversions: 0,
+                hash_calls: 0,
+                scalar_comparisons: 2,
+            }
+        );
+        // the no-op sink.
+        ().count(report);
+
+        // 0b101 of 3 bits: x_1 * (1 - x_2) * x_3
+        assert_eq!(
+            CostReport::eq_at_index(3, 5),
+            CostReport::mults(3) + CostReport::adds(1)
+        );
+
+        // 4 + 2 + 1 folds of a table with 3 variables.
+        assert_eq!(
+            CostReport::table_evaluate(3),
+            CostReport::mults(7) + CostReport::adds(14)
+        );
+
+        // 2 points: one divisor pair, L_0 and L_1 with 2 coeffs each.
+        assert_eq!(CostReport::interpolate(2).field_inversions, 1);
+        assert_eq!(CostReport::interpolate(1).field_inversions, 0);
+    }
+
+    #[test]
+    fn test_grows_like() {
+        let sizes = [4, 8, 12];
+        let linear = [40, 81, 119];
+        assert!(grows_like(&sizes, &linear, |v| v as f64, 1.5));
+        assert!(!grows_like(&sizes, &linear, |v| (1u64 << v) as f64, 1.5));
+        assert!(!grows_like(&sizes, &[16, 256, 4096], |v| v as f64, 1.5));
+    }
+
+    // The small polys are fine in V, and the large ones outside of it.
+    #[test]
+    fn test_dense_eval_outside_verifier() {
+        let large = MPolynomial {
+            var_num: 17,
+            coeffs: vec![Scalar::one(); 2 * DENSE_EVAL_LIMIT],
+        };
+        assert_eq!(large.evaluate(&vec![0; 17]), Scalar::one());
+        let small = MPolynomial {
+            var_num: 2,
+            coeffs: vec![Scalar::one(); 4],
+        };
+        assert_eq!(as_verifier(|| small.evaluate(&vec![1, 1])), Scalar::from(4));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "dense MPolynomial::evaluate of 131072 coeffs in V")]
+    fn test_dense_eval_in_verifier() {
+        let large = MPolynomial {
+            var_num: 17,
+            coeffs: vec![Scalar::one(); 2 * DENSE_EVAL_LIMIT],
+        };
+        as_verifier(|| large.evaluate(&vec![0; 17]));
+    }
+}
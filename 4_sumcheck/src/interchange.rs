@@ -0,0 +1,659 @@
+// The JSON exchanged with the tools out of this workspace, eg: a Python prover which computes the
+// witness MLEs and their claimed evaluations, while V runs here. Each value is checked as it's
+// read, and an error names the path of the value it's about, eg:
+//      claims[3].point[1]: non-canonical scalar
+//
+// A document is a top-level object with the schema version and one or more sections, each of
+// them read by its own `*_from_json`, which ignores the others. So a single file may carry a
+// table, the claims on it and the statement they're about.
+//
+//      {
+//        "version": 1,
+//        "table": { "len": 4, "values": [<scalar>, ...] },
+//        "claims": [ { "poly": "W_0", "point": [<scalar>, ...], "value": <scalar> }, ... ],
+//        "statement": {
+//          "protocol": <digest>,
+//          "bindings": { "<label>": <binding>, ... }
+//        },
+//        "outputs": [<scalar>, ...]
+//      }
+//
+//      | value     | JSON                                                                    |
+//      |-----------|-------------------------------------------------------------------------|
+//      | scalar    | "0x" and 64 hex digits, the integer < r in big-endian, eg: "0x00..05"   |
+//      | digest    | "0x" and 64 hex digits, the 32 bytes in order                           |
+//      | table     | len: 2^v, then the len values, W(w) with x_1 as the MSB of w            |
+//      | claims    | poly(point) = value, the claims on a poly have points of the same len   |
+//      | statement | the protocol digest, then the bindings by label, see `Statement`        |
+//      | outputs   | the claimed outputs of a circuit, eg: of `GKR::verify`                  |
+//
+//      | binding   | JSON                                                             |
+//      |-----------|------------------------------------------------------------------|
+//      | matrix    | { "kind": "matrix", "rows": n, "cols": m, "digest": <digest> }   |
+//      | mle       | { "kind": "mle", "var_num": v, "digest": <digest> }              |
+//      | circuit   | { "kind": "circuit", "digest": <digest> }, under "circuit" only  |
+//      | scalars   | { "kind": "scalars", "values": [<scalar>, ...] }                 |
+//
+// The errors, after the path:
+//      | error                                   | eg                                            |
+//      |-----------------------------------------|-----------------------------------------------|
+//      | not JSON: ..                            | a truncated file, with no path                |
+//      | unknown schema version n                | version: unknown schema version 2             |
+//      | missing                                 | claims[0].value: missing                      |
+//      | expected a string / an array / ..       | table.len: expected an unsigned integer       |
+//      | not 0x and 64 hex digits                | outputs[1]: not 0x and 64 hex digits          |
+//      | non-canonical scalar                    | claims[3].point[1]: non-canonical scalar      |
+//      | declared n values, found m              | table.values: declared 4 values, found 3      |
+//      | n values, not a power of 2              | table.len: 3 values, not a power of 2         |
+//      | unknown binding kind "k"                | statement.bindings.A.kind: unknown binding .. |
+//      | a circuit is bound under "circuit" only | statement.bindings.c: a circuit is bound ..   |
+//      | of another protocol                     | statement.protocol: of another protocol       |
+// The var_num of an MLE binding is below 64, or it's "expected a var_num below 64".
+use crate::claim::{Claim, ClaimTracker, PolyRegistry};
+use crate::poly::eval_table::EvalTable;
+use crate::scalar_utils::from_le_bytes;
+use bls12_381::Scalar;
+use serde_json::{json, Map, Value};
+use std::fmt;
+use Fiat_Shamir::protocol::ProtocolId;
+use Fiat_Shamir::statement::{Binding, MatrixBinding, MleBinding, Statement, CIRCUIT_LABEL};
+
+pub const SCHEMA_VERSION: u64 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    Syntax(String),
+    UnknownVersion(u64),
+    Missing,
+    WrongType(&'static str),
+    NotHex,
+    NonCanonical,
+    LengthMismatch { declared: usize, found: usize },
+    NotPowerOfTwo(usize),
+    UnknownKind(String),
+    CircuitLabel,
+    ProtocolMismatch,
+}
+
+// The error of the value at `path`, see above.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterchangeError {
+    pub path: String,
+    pub kind: ErrorKind,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::Syntax(e) => write!(f, "not JSON: {}", e),
+            ErrorKind::UnknownVersion(v) => write!(f, "unknown schema version {}", v),
+            ErrorKind::Missing => write!(f, "missing"),
+            ErrorKind::WrongType(expected) => write!(f, "expected {}", expected),
+            ErrorKind::NotHex => write!(f, "not 0x and 64 hex digits"),
+            ErrorKind::NonCanonical => write!(f, "non-canonical scalar"),
+            ErrorKind::LengthMismatch { declared, found } => {
+                write!(f, "declared {} values, found {}", declared, found)
+            }
+            ErrorKind::NotPowerOfTwo(len) => write!(f, "{} values, not a power of 2", len),
+            ErrorKind::UnknownKind(kind) => write!(f, "unknown binding kind \"{}\"", kind),
+            ErrorKind::CircuitLabel => {
+                write!(f, "a circuit is bound under \"{}\" only", CIRCUIT_LABEL)
+            }
+            ErrorKind::ProtocolMismatch => write!(f, "of another protocol"),
+        }
+    }
+}
+
+impl fmt::Display for InterchangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.path.is_empty() {
+            true => write!(f, "{}", self.kind),
+            false => write!(f, "{}: {}", self.path, self.kind),
+        }
+    }
+}
+
+impl std::error::Error for InterchangeError {}
+
+// A value of the document with its path, eg: claims[3].point[1].
+struct Node<'a> {
+    value: &'a Value,
+    path: String,
+}
+
+impl<'a> Node<'a> {
+    fn err<T>(&self, kind: ErrorKind) -> Result<T, InterchangeError> {
+        Err(InterchangeError {
+            path: self.path.clone(),
+            kind,
+        })
+    }
+
+    fn child(&self, value: &'a Value, name: &str) -> Node<'a> {
+        let path = match self.path.is_empty() {
+            true => name.into(),
+            false => format!("{}.{}", self.path, name),
+        };
+        Node { value, path }
+    }
+
+    fn object(&self) -> Result<&'a Map<String, Value>, InterchangeError> {
+        match self.value.as_object() {
+            Some(object) => Ok(object),
+            None => self.err(ErrorKind::WrongType("an object")),
+        }
+    }
+
+    fn field(&self, name: &str) -> Result<Node<'a>, InterchangeError> {
+        match self.object()?.get(name) {
+            Some(value) => Ok(self.child(value, name)),
+            None => self.child(&Value::Null, name).err(ErrorKind::Missing),
+        }
+    }
+
+    // The fields in the order of their names.
+    fn fields(&self) -> Result<Vec<(&'a str, Node<'a>)>, InterchangeError> {
+        let object = self.object()?;
+        Ok(object
+            .iter()
+            .map(|(name, value)| (name.as_str(), self.child(value, name)))
+            .collect())
+    }
+
+    fn items(&self) -> Result<Vec<Node<'a>>, InterchangeError> {
+        match self.value.as_array() {
+            Some(items) => Ok(items
+                .iter()
+                .enumerate()
+                .map(|(i, value)| Node {
+                    value,
+                    path: format!("{}[{}]", self.path, i),
+                })
+                .collect()),
+            None => self.err(ErrorKind::WrongType("an array")),
+        }
+    }
+
+    fn str(&self) -> Result<&'a str, InterchangeError> {
+        match self.value.as_str() {
+            Some(s) => Ok(s),
+            None => self.err(ErrorKind::WrongType("a string")),
+        }
+    }
+
+    fn usize(&self) -> Result<usize, InterchangeError> {
+        match self.value.as_u64().and_then(|v| usize::try_from(v).ok()) {
+            Some(v) => Ok(v),
+            None => self.err(ErrorKind::WrongType("an unsigned integer")),
+        }
+    }
+
+    fn hex(&self) -> Result<[u8; 32], InterchangeError> {
+        let digits = self.str()?.strip_prefix("0x").unwrap_or("");
+        let mut bytes = [0u8; 32];
+        if digits.len() != 64 || !digits.is_ascii() {
+            return self.err(ErrorKind::NotHex);
+        }
+        for (byte, pair) in bytes.iter_mut().zip(digits.as_bytes().chunks(2)) {
+            let pair = core::str::from_utf8(pair).unwrap();
+            *byte = match u8::from_str_radix(pair, 16) {
+                Ok(b) => b,
+                Err(_) => return self.err(ErrorKind::NotHex),
+            };
+        }
+        Ok(bytes)
+    }
+
+    fn scalar(&self) -> Result<Scalar, InterchangeError> {
+        let mut le = self.hex()?;
+        le.reverse();
+        match from_le_bytes(&le) {
+            Ok(s) => Ok(s),
+            Err(_) => self.err(ErrorKind::NonCanonical),
+        }
+    }
+
+    fn scalars(&self) -> Result<Vec<Scalar>, InterchangeError> {
+        self.items()?.iter().map(|item| item.scalar()).collect()
+    }
+}
+
+fn scalar_json(s: &Scalar) -> Value {
+    let mut be = s.to_bytes();
+    be.reverse();
+    digest_json(&be)
+}
+
+fn scalars_json(scalars: &[Scalar]) -> Value {
+    Value::Array(scalars.iter().map(scalar_json).collect())
+}
+
+fn digest_json(bytes: &[u8; 32]) -> Value {
+    let hex = bytes
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    Value::String(format!("0x{}", hex))
+}
+
+fn document(section: &str, value: Value) -> String {
+    let mut object = Map::new();
+    object.insert("version".into(), json!(SCHEMA_VERSION));
+    object.insert(section.into(), value);
+    serde_json::to_string_pretty(&Value::Object(object)).unwrap()
+}
+
+// Parse the document, check its version, and read the section.
+fn read_section<T>(
+    json: &str,
+    section: &str,
+    read: impl FnOnce(&Node) -> Result<T, InterchangeError>,
+) -> Result<T, InterchangeError> {
+    let value: Value = serde_json::from_str(json).map_err(|e| InterchangeError {
+        path: String::new(),
+        kind: ErrorKind::Syntax(e.to_string()),
+    })?;
+    let root = Node {
+        value: &value,
+        path: String::new(),
+    };
+    let version = root.field("version")?;
+    match version.value.as_u64() {
+        Some(SCHEMA_VERSION) => {}
+        Some(v) => return version.err(ErrorKind::UnknownVersion(v)),
+        None => return version.err(ErrorKind::WrongType("an unsigned integer")),
+    }
+    read(&root.field(section)?)
+}
+
+pub fn table_to_json(table: &EvalTable) -> String {
+    document(
+        "table",
+        json!({ "len": table.evals.len(), "values": scalars_json(&table.evals) }),
+    )
+}
+
+pub fn table_from_json(json: &str) -> Result<EvalTable, InterchangeError> {
+    read_section(json, "table", |table| {
+        let len = table.field("len")?;
+        let declared = len.usize()?;
+        if !declared.is_power_of_two() {
+            return len.err(ErrorKind::NotPowerOfTwo(declared));
+        }
+        let values = table.field("values")?;
+        let evals = values.scalars()?;
+        if evals.len() != declared {
+            return values.err(ErrorKind::LengthMismatch {
+                declared,
+                found: evals.len(),
+            });
+        }
+        Ok(EvalTable::new(evals))
+    })
+}
+
+// The poly of each claim is written by its description in the registry, eg: "W_0".
+pub fn claims_to_json(claims: &[Claim], registry: &PolyRegistry) -> String {
+    let claims = claims
+        .iter()
+        .map(|c| {
+            json!({
+                "poly": registry.describe(c.poly).expect("the poly is of another registry"),
+                "point": scalars_json(&c.point),
+                "value": scalar_json(&c.value),
+            })
+        })
+        .collect();
+    document("claims", Value::Array(claims))
+}
+
+// The polys are looked up by their description in the tracker, and registered if they're not.
+pub fn claims_from_json(
+    json: &str,
+    tracker: &mut ClaimTracker,
+) -> Result<Vec<Claim>, InterchangeError> {
+    read_section(json, "claims", |claims| {
+        let mut read = Vec::<Claim>::new();
+        for claim in claims.items()? {
+            let label = claim.field("poly")?.str()?;
+            let point = claim.field("point")?;
+            let values = point.scalars()?;
+            let value = claim.field("value")?.scalar()?;
+            let poly = match tracker.registry().find(label) {
+                Some(poly) => poly,
+                None => tracker.register(label),
+            };
+            if let Some(other) = read.iter().find(|c| c.poly == poly) {
+                if other.point.len() != values.len() {
+                    return point.err(ErrorKind::LengthMismatch {
+                        declared: other.point.len(),
+                        found: values.len(),
+                    });
+                }
+            }
+            read.push(Claim::new(poly, values, value));
+        }
+        Ok(read)
+    })
+}
+
+pub fn statement_to_json(statement: &Statement) -> String {
+    let bindings = statement
+        .bindings()
+        .map(|(label, binding)| {
+            let binding = match binding {
+                Binding::Matrix(m) => json!({
+                    "kind": "matrix",
+                    "rows": m.rows,
+                    "cols": m.cols,
+                    "digest": digest_json(&m.digest),
+                }),
+                Binding::Mle(m) => json!({
+                    "kind": "mle",
+                    "var_num": m.var_num,
+                    "digest": digest_json(&m.digest),
+                }),
+                Binding::Circuit(digest) => json!({
+                    "kind": "circuit",
+                    "digest": digest_json(digest),
+                }),
+                Binding::Scalars(values) => json!({
+                    "kind": "scalars",
+                    "values": scalars_json(values),
+                }),
+            };
+            (label.into(), binding)
+        })
+        .collect::<Map<_, _>>();
+    document(
+        "statement",
+        json!({
+            "protocol": digest_json(&statement.protocol().digest()),
+            "bindings": bindings,
+        }),
+    )
+}
+
+// The statement must be of the protocol V runs, as for `Statement::from_bytes_for`.
+pub fn statement_from_json(
+    json: &str,
+    protocol: &ProtocolId,
+) -> Result<Statement, InterchangeError> {
+    read_section(json, "statement", |statement| {
+        let digest = statement.field("protocol")?;
+        if digest.hex()? != protocol.digest() {
+            return digest.err(ErrorKind::ProtocolMismatch);
+        }
+        let mut read = Statement::new(*protocol);
+        for (label, binding) in statement.field("bindings")?.fields()? {
+            let kind = binding.field("kind")?;
+            read = match kind.str()? {
+                "matrix" => read.with_matrix(
+                    label,
+                    MatrixBinding {
+                        rows: binding.field("rows")?.usize()?,
+                        cols: binding.field("cols")?.usize()?,
+                        digest: binding.field("digest")?.hex()?,
+                    },
+                ),
+                "mle" => {
+                    let var_num = binding.field("var_num")?;
+                    let digest = binding.field("digest")?.hex()?;
+                    let var_num = match var_num.usize()? {
+                        v if v < usize::BITS as usize => v,
+                        _ => return var_num.err(ErrorKind::WrongType("a var_num below 64")),
+                    };
+                    read.with_mle(label, MleBinding { var_num, digest })
+                }
+                "circuit" if label == CIRCUIT_LABEL => {
+                    read.with_circuit(binding.field("digest")?.hex()?)
+                }
+                "circuit" => return binding.err(ErrorKind::CircuitLabel),
+                "scalars" => read.with_public_scalars(label, &binding.field("values")?.scalars()?),
+                other => return kind.err(ErrorKind::UnknownKind(other.into())),
+            };
+        }
+        Ok(read)
+    })
+}
+
+// The claimed outputs of a circuit, eg: the ones GKR verifies the proof for.
+pub fn outputs_to_json(outputs: &[Scalar]) -> String {
+    document("outputs", scalars_json(outputs))
+}
+
+pub fn outputs_from_json(json: &str) -> Result<Vec<Scalar>, InterchangeError> {
+    read_section(json, "outputs", |outputs| outputs.scalars())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ff::Field;
+    use rand_core::OsRng;
+
+    // The document of the Python side, written by hand, see `tests/fixtures/README.md`.
+    const FIXTURE: &str = include_str!("../../tests/fixtures/interchange.json");
+
+    fn protocol() -> ProtocolId {
+        ProtocolId::new("sumcheck", "interchange_fixture", 1, &[])
+    }
+
+    fn random_scalars(n: usize) -> Vec<Scalar> {
+        (0..n).map(|_| Scalar::random(OsRng)).collect()
+    }
+
+    fn err(json: &str) -> String {
+        table_from_json(json).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_round_trips() {
+        let table = EvalTable::new(random_scalars(8));
+        assert_eq!(table_from_json(&table_to_json(&table)), Ok(table));
+
+        let mut tracker = ClaimTracker::new();
+        let (w_0, w_1) = (tracker.register("W_0"), tracker.register("W_1"));
+        let claims = vec![
+            Claim::new(w_0, random_scalars(2), Scalar::random(OsRng)),
+            Claim::new(w_1, random_scalars(3), -Scalar::one()),
+            Claim::new(w_0, random_scalars(2), Scalar::zero()),
+        ];
+        let json = claims_to_json(&claims, tracker.registry());
+        assert_eq!(claims_from_json(&json, &mut tracker), Ok(claims.clone()));
+        assert_eq!(tracker.registry().len(), 2);
+        // the labels are registered in a tracker of their own.
+        let mut another = ClaimTracker::new();
+        another.register("D");
+        let read = claims_from_json(&json, &mut another).unwrap();
+        assert_eq!(another.describe(read[1].poly), "W_1");
+        assert_eq!(read[0].poly, read[2].poly);
+
+        let statement = Statement::new(protocol())
+            .with_circuit([3; 32])
+            .with_matrix(
+                "A",
+                MatrixBinding {
+                    rows: 3,
+                    cols: 5,
+                    digest: [1; 32],
+                },
+            )
+            .with_mle(
+                "inputs",
+                MleBinding {
+                    var_num: 4,
+                    digest: [2; 32],
+                },
+            )
+            .with_public_scalars("public", &random_scalars(3));
+        let json = statement_to_json(&statement);
+        assert_eq!(statement_from_json(&json, &protocol()), Ok(statement));
+
+        let outputs = random_scalars(5);
+        assert_eq!(outputs_from_json(&outputs_to_json(&outputs)), Ok(outputs));
+    }
+
+    #[test]
+    fn test_fixture() {
+        // the claims hold on the table built here.
+        let rust_table = EvalTable::new([1, 2, 3, 4].map(Scalar::from).to_vec());
+        assert_eq!(table_from_json(FIXTURE), Ok(rust_table.clone()));
+        let mut tracker = ClaimTracker::new();
+        let claims = claims_from_json(FIXTURE, &mut tracker).unwrap();
+        assert_eq!(claims.len(), 3);
+        assert_eq!(claims[1].point[0], -Scalar::one());
+        for claim in claims.iter() {
+            assert_eq!(tracker.describe(claim.poly), "W");
+            assert_eq!(rust_table.evaluate(&claim.point), claim.value);
+        }
+
+        let statement = statement_from_json(FIXTURE, &protocol()).unwrap();
+        assert_eq!(statement.circuit(), Ok([0x11; 32]));
+        assert_eq!(statement.mle("inputs").unwrap().var_num, 2);
+        assert_eq!(
+            statement.public_scalars("public"),
+            Ok(&[Scalar::from(5), Scalar::from(7)][..])
+        );
+        assert_eq!(
+            outputs_from_json(FIXTURE),
+            Ok(vec![Scalar::from(4), Scalar::from(32)])
+        );
+    }
+
+    #[test]
+    fn test_error_paths() {
+        let one = format!("\"0x{:064x}\"", 1);
+        let r = "\"0x73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001\"";
+        let table = |len: &str, values: &str| {
+            format!(
+                "{{\"version\": 1, \"table\": {{\"len\": {}, \"values\": [{}]}}}}",
+                len, values
+            )
+        };
+        let ones = |n: usize| vec![one.as_str(); n].join(", ");
+
+        assert!(err("{\"version\": 1,").starts_with("not JSON: "));
+        assert_eq!(
+            err("{\"version\": 2, \"table\": {}}"),
+            "version: unknown schema version 2"
+        );
+        assert_eq!(err("{\"table\": {}}"), "version: missing");
+        assert_eq!(err("{\"version\": 1}"), "table: missing");
+        assert_eq!(
+            err(&table("\"4\"", &ones(4))),
+            "table.len: expected an unsigned integer"
+        );
+        assert_eq!(
+            err(&table("3", &ones(3))),
+            "table.len: 3 values, not a power of 2"
+        );
+        assert_eq!(
+            err(&table("4", &ones(3))),
+            "table.values: declared 4 values, found 3"
+        );
+        let values = format!("{}, {}, \"0x01\", {}", one, one, one);
+        assert_eq!(
+            err(&table("4", &values)),
+            "table.values[2]: not 0x and 64 hex digits"
+        );
+        let values = format!("{}, {}, {}", one, one, one.replace('1', "g"));
+        assert_eq!(
+            err(&table("4", &format!("{}, {}", values, one))),
+            "table.values[2]: not 0x and 64 hex digits"
+        );
+        let values = format!("{}, {}, {}, 1", one, one, one);
+        assert_eq!(
+            err(&table("4", &values)),
+            "table.values[3]: expected a string"
+        );
+
+        // the point of the 4th claim, and the claims of "W" of two lens.
+        let claim = |point: &str| {
+            format!(
+                "{{\"poly\": \"W\", \"point\": [{}], \"value\": {}}}",
+                point, one
+            )
+        };
+        let claims = |claims: &[String]| {
+            let json = format!("{{\"version\": 1, \"claims\": [{}]}}", claims.join(", "));
+            claims_from_json(&json, &mut ClaimTracker::new())
+                .unwrap_err()
+                .to_string()
+        };
+        let pair = format!("{}, {}", one, one);
+        let mut list = vec![claim(&pair); 3];
+        list.push(claim(&format!("{}, {}", one, r)));
+        assert_eq!(claims(&list), "claims[3].point[1]: non-canonical scalar");
+        list[3] = claim(&one);
+        assert_eq!(claims(&list), "claims[3].point: declared 2 values, found 1");
+        list[3] = format!("{{\"poly\": \"W\", \"point\": [{}]}}", pair);
+        assert_eq!(claims(&list), "claims[3].value: missing");
+
+        let statement = |bindings: &str| {
+            let json = format!(
+                "{{\"version\": 1, \"statement\": {{\"protocol\": {}, \"bindings\": {{{}}}}}}}",
+                digest_json(&protocol().digest()),
+                bindings
+            );
+            statement_from_json(&json, &protocol())
+                .unwrap_err()
+                .to_string()
+        };
+        let digest = digest_json(&[1; 32]);
+        assert_eq!(
+            statement(&format!(
+                "\"A\": {{\"kind\": \"tree\", \"digest\": {}}}",
+                digest
+            )),
+            "statement.bindings.A.kind: unknown binding kind \"tree\""
+        );
+        assert_eq!(
+            statement(&format!(
+                "\"c\": {{\"kind\": \"circuit\", \"digest\": {}}}",
+                digest
+            )),
+            "statement.bindings.c: a circuit is bound under \"circuit\" only"
+        );
+        assert_eq!(
+            statement(&format!(
+                "\"inputs\": {{\"kind\": \"mle\", \"var_num\": 64, \"digest\": {}}}",
+                digest
+            )),
+            "statement.bindings.inputs.var_num: expected a var_num below 64"
+        );
+        let another = ProtocolId::new("sumcheck", "interchange_fixture", 2, &[]);
+        assert_eq!(
+            statement_from_json(FIXTURE, &another)
+                .unwrap_err()
+                .to_string(),
+            "statement.protocol: of another protocol"
+        );
+        assert_eq!(
+            outputs_from_json("{\"version\": 1, \"outputs\": {}}")
+                .unwrap_err()
+                .to_string(),
+            "outputs: expected an array"
+        );
+    }
+
+    // A document of another version is rejected before any section is read.
+    #[test]
+    fn test_unknown_version() {
+        let fixture = FIXTURE.replacen("\"version\": 1", "\"version\": 2", 1);
+        let e = outputs_from_json(&fixture).unwrap_err();
+        assert_eq!(
+            e,
+            InterchangeError {
+                path: "version".into(),
+                kind: ErrorKind::UnknownVersion(2),
+            }
+        );
+        let fixture = FIXTURE.replacen("\"version\": 1", "\"version\": \"1\"", 1);
+        assert_eq!(
+            table_from_json(&fixture).unwrap_err().to_string(),
+            "version: expected an unsigned integer"
+        );
+        assert!(statement_from_json(&fixture, &protocol()).is_err());
+    }
+}
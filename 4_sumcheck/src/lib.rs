@@ -1,3 +1,59 @@
+//! Polys in this crate:
+//!     - `Polynomial`: uni-variate poly in coeffs form.
+//!     - `SparsePolynomial`: uni-variate poly with the nonzero terms only, eg: X^n - 1.
+//!     - `MPolynomial`: multilinear poly, aka the degree of each variable is at most one. It's
+//!       enforced by the arithmetic, which returns `NotMultilinear` instead of creating x_i^2.
+//!     - `DenseUVPerVarPolynomial`: multivariate poly with a per-variable degree bound.
+//!     - `EvalTable`: the evaluations of a multilinear poly over the hypercube, which is folded
+//!       in half when a variable is bound.
+//!
+//! `Polynomial`, `MPolynomial`, `EvalTable` and `sumcheck` are generic over `ff::PrimeField`,
+//! the names above are their aliases over `Scalar`, eg: `MPolynomialOver<F>`.
+//!
+//! The witness held by the provers is wrapped in `secret::Secret`, which is wiped on drop with
+//! the `zeroize` feature.
+//!
+//! Everything but `sumcheck` builds without std, see the `alloc` feature.
+//!
+//! The `verifier` feature alone is the verifier-only build, which has no `rand_core::OsRng` nor
+//! rayon: they come with `std`, so the two are rejected together. `eq_eval` and `batch_invert`
+//! of `utils` are in this build.
+//!
+//! `claim` holds the evaluation claims passed between the reductions of the other crates, see
+//! `ClaimTracker`.
+//!
+//! `cost` counts the ops of the verifiers in the other crates, see their `verify_with_cost`.
+//!
+//! `trace` replays their checks and challenges in order, see their `verify_with_trace`.
+//!
+//! `interchange` reads and writes the tables, claims and statements as JSON, for the tools out
+//! of this workspace, with the `json` feature.
+//!
+//! `rlc` is the random linear combination shared by the batched checks of the other crates, eg:
+//! Freivalds, the batched LDT and the batched sum-check, with their `SoundnessBudget`.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(not(feature = "alloc"))]
+compile_error!("sumcheck requires the `std` or `alloc` feature");
+
+#[cfg(all(feature = "verifier", not(feature = "prover"), feature = "std"))]
+compile_error!("the verifier-only build of sumcheck can't enable `std`, it brings OsRng and rayon");
+
+// `scalars!` expands to `$crate::alloc::vec!`.
+#[doc(hidden)]
+pub extern crate alloc;
+
+pub mod claim;
+pub mod cost;
+#[cfg(feature = "json")]
+pub mod interchange;
 pub mod poly;
+pub mod rlc;
+pub mod scalar_utils;
+pub mod secret;
+#[cfg(test)]
+mod small_field;
+#[cfg(all(feature = "std", feature = "prover"))]
 pub mod sumcheck;
+pub mod trace;
 pub mod utils;
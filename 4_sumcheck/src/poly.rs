@@ -1,2 +1,8 @@
+pub mod budget;
+pub mod eval_table;
 pub mod multivar_poly;
+pub mod per_var_poly;
+#[cfg(all(test, feature = "proptests"))]
+mod proptests;
+pub mod sparse_poly;
 pub mod univar_poly;
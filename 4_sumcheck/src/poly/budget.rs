@@ -0,0 +1,109 @@
+// The size of a dense MPolynomial, which has 2^v coeffs: a typo in var_num, eg: 30 instead of
+// 20, asks for 2^30 * 32 bytes. The dense constructors check the budget before they allocate,
+// so it's an Err instead of an OOM, see `MPolynomialOver::lagrange_within`.
+//
+// GKR holds add_i and mult_i of a layer dense by the same budget, see `DensityPolicy::budget`.
+use core::fmt;
+use core::mem::size_of;
+
+// 2^26 coeffs, eg: 2 GiB of Scalars.
+pub const DEFAULT_MAX_DENSE_VARS: usize = 26;
+
+// The dense poly would take `requested_bytes`, usize::MAX if it overflows, above the
+// `limit_bytes` of the budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceLimit {
+    pub requested_bytes: usize,
+    pub limit_bytes: usize,
+}
+
+impl fmt::Display for ResourceLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The dense poly takes {} bytes, above the limit of {} bytes",
+            self.requested_bytes, self.limit_bytes
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ResourceLimit {}
+
+// The max var_num of a dense poly, `DEFAULT_MAX_DENSE_VARS` unless it's raised on purpose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DenseBudget {
+    max_vars: usize,
+}
+
+impl Default for DenseBudget {
+    fn default() -> Self {
+        Self::with_max_vars(DEFAULT_MAX_DENSE_VARS)
+    }
+}
+
+impl DenseBudget {
+    pub const fn with_max_vars(max_vars: usize) -> Self {
+        Self { max_vars }
+    }
+
+    pub fn max_vars(&self) -> usize {
+        self.max_vars
+    }
+
+    pub fn allows(&self, var_num: usize) -> bool {
+        var_num <= self.max_vars
+    }
+
+    // The 2^var_num coeffs of F fit in the budget. The peak is the output only, the
+    // constructors don't hold a temporary of its size.
+    pub fn check<F>(&self, var_num: usize) -> Result<(), ResourceLimit> {
+        if self.allows(var_num) {
+            return Ok(());
+        }
+        Err(ResourceLimit {
+            requested_bytes: dense_bytes::<F>(var_num),
+            limit_bytes: dense_bytes::<F>(self.max_vars),
+        })
+    }
+}
+
+// 2^var_num coeffs of F.
+fn dense_bytes<F>(var_num: usize) -> usize {
+    u32::try_from(var_num)
+        .ok()
+        .and_then(|var_num| 2usize.checked_pow(var_num))
+        .and_then(|n| n.checked_mul(size_of::<F>()))
+        .unwrap_or(usize::MAX)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bls12_381::Scalar;
+
+    #[test]
+    fn test_budget() {
+        let budget = DenseBudget::default();
+        assert_eq!(budget.max_vars(), DEFAULT_MAX_DENSE_VARS);
+        assert_eq!(budget.check::<Scalar>(26), Ok(()));
+        assert_eq!(
+            budget.check::<Scalar>(30),
+            Err(ResourceLimit {
+                requested_bytes: 32 << 30,
+                limit_bytes: 32 << 26,
+            })
+        );
+        // 2^64 overflows.
+        assert_eq!(
+            budget.check::<Scalar>(64).unwrap_err().requested_bytes,
+            usize::MAX
+        );
+        let raised = DenseBudget::with_max_vars(30);
+        assert!(raised.allows(30) && !raised.allows(31));
+        assert_eq!(
+            budget.check::<Scalar>(27).unwrap_err().to_string(),
+            "The dense poly takes 4294967296 bytes, above the limit of 2147483648 bytes"
+        );
+    }
+}
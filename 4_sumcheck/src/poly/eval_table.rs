@@ -0,0 +1,575 @@
+use crate::cost::{CostReport, CostSink};
+use crate::poly::multivar_poly::MPolynomialOver;
+use crate::poly::univar_poly::PolynomialOver;
+use crate::secret::Secret;
+use crate::utils::{
+    axpy, convert_to_binary, eq_table, first_mismatch, fold_and_sum, inner_product, scale,
+};
+use alloc::vec;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use core::fmt;
+use ff::PrimeField;
+
+// The first point of the hypercube where two forms of a multilinear poly differ, see
+// `EvalTable::agrees_with` and `MPolynomial::agrees_with_table`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Disagreement<F = Scalar> {
+    // left and right have these numbers of evals, so they are not over the same hypercube.
+    LengthMismatch {
+        left: usize,
+        right: usize,
+    },
+    // left(point) != right(point), where point is the bits of index, see `convert_to_binary`.
+    Value {
+        index: usize,
+        point: Vec<usize>,
+        left: F,
+        right: F,
+    },
+}
+
+impl<F: fmt::Debug> fmt::Display for Disagreement<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Disagreement::LengthMismatch { left, right } => {
+                write!(f, "{} evals against {}", left, right)
+            }
+            Disagreement::Value {
+                index,
+                point,
+                left,
+                right,
+            } => write!(
+                f,
+                "at {} {:?}: {:?} against {:?}",
+                index, point, left, right
+            ),
+        }
+    }
+}
+
+// The evals of the same hypercube agree, or the smallest index where they don't.
+pub(crate) fn check_agreement<F: PrimeField>(
+    left: &[F],
+    right: &[F],
+) -> Result<(), Disagreement<F>> {
+    if left.len() != right.len() {
+        return Err(Disagreement::LengthMismatch {
+            left: left.len(),
+            right: right.len(),
+        });
+    }
+    match first_mismatch(left, right) {
+        None => Ok(()),
+        Some(index) => {
+            let var_num = left.len().next_power_of_two().trailing_zeros() as usize;
+            Err(Disagreement::Value {
+                index,
+                point: convert_to_binary(&var_num, index),
+                left: left[index],
+                right: right[index],
+            })
+        }
+    }
+}
+
+// The evaluations of a multilinear poly W over the hypercube {0,1}^v, aka. the table of W̃.
+//
+// The index follows `MPolynomial::lagrange`: evals[i] = W(bits(i)) with x_1 as the MSB,
+// see `convert_to_binary`. eg: v = 2, evals = [W(0,0), W(0,1), W(1,0), W(1,1)].
+// So x_1 splits the table into halves, and x_v interleaves it.
+//
+// Binding a variable to r halves the table by W̃(.., r, ..) = (1-r)·W̃(.., 0, ..) + r·W̃(.., 1, ..):
+//      bind_first(r): x_1 = r, new[k] = lo[k] + r·(hi[k] - lo[k]), the order of sum-check rounds.
+//      bind_last(r):  x_v = r, new[k] = evals[2k] + r·(evals[2k+1] - evals[2k]).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct EvalTableOver<F> {
+    pub var_num: usize,
+    pub evals: Vec<F>,
+}
+
+// The tables of the protocol crates, over the scalar field of BLS12-381.
+pub type EvalTable = EvalTableOver<Scalar>;
+
+impl<F: PrimeField> Default for EvalTableOver<F> {
+    // The constant 0 with no variable.
+    fn default() -> Self {
+        Self {
+            var_num: 0,
+            evals: vec![F::ZERO],
+        }
+    }
+}
+
+impl<F: PrimeField> EvalTableOver<F> {
+    pub fn new(evals: Vec<F>) -> Self {
+        assert!(
+            evals.len().is_power_of_two(),
+            "the table size must be 2^v, got {}",
+            evals.len()
+        );
+        Self {
+            var_num: evals.len().trailing_zeros() as usize,
+            evals,
+        }
+    }
+
+    // (W(0, x_2, ..., x_v), W(1, x_2, ..., x_v))
+    pub fn split_halves(&self) -> (&[F], &[F]) {
+        assert!(self.var_num > 0, "no variable to split");
+        self.evals.split_at(self.evals.len() / 2)
+    }
+
+    // Bind x_1 = r, the remaining variables are x_2, ..., x_v.
+    // Both binds fold in place, so the table keeps its allocation, see `secret::Secret`.
+    pub fn bind_first(&mut self, r: F) {
+        assert!(self.var_num > 0, "no variable to bind");
+        let half = self.evals.len() / 2;
+        let (lo, hi) = self.evals.split_at_mut(half);
+        for (l, h) in lo.iter_mut().zip(hi.iter()) {
+            *l += r * (*h - *l);
+        }
+        self.evals.truncate(half);
+        self.var_num -= 1;
+    }
+
+    // Bind x_v = r, the remaining variables are x_1, ..., x_v-1.
+    pub fn bind_last(&mut self, r: F) {
+        assert!(self.var_num > 0, "no variable to bind");
+        let half = self.evals.len() / 2;
+        // new[k] only reads evals[2k] and evals[2k+1], which are not overwritten yet.
+        for k in 0..half {
+            self.evals[k] = self.evals[2 * k] + r * (self.evals[2 * k + 1] - self.evals[2 * k]);
+        }
+        self.evals.truncate(half);
+        self.var_num -= 1;
+    }
+
+    // The round poly of x_1: g(X) = ∑ W(X, x_2, ..., x_v) = ∑lo + X·(∑hi - ∑lo), with the coeffs
+    // as they are, eg: [s, 0] rather than [s]. It's the first round, or with `bind_first`, the
+    // two-pass round which `bind_and_next_round` fuses.
+    pub fn first_round_poly(&self) -> PolynomialOver<F> {
+        let (lo, hi) = self.split_halves();
+        let (sum_lo, sum_hi) = (lo.iter().sum::<F>(), hi.iter().sum::<F>());
+        PolynomialOver::from_coeffs(vec![sum_lo, sum_hi - sum_lo])
+    }
+
+    // `bind_first(r_prev)` then `first_round_poly`, in one pass over the table rather than two:
+    // the folded pairs of each quarter are summed as they are written, so they are not read again.
+    // The table past the last round has no variable, so it's the constant of the final claim.
+    //
+    //      | lo_0 | lo_1 | hi_0 | hi_1 |  -> | lo_0 + r·(hi_0 - lo_0) | lo_1 + r·(hi_1 - lo_1) |
+    //                                                 ∑ is g(0)               ∑ is g(1)
+    pub fn bind_and_next_round(&mut self, r_prev: F) -> PolynomialOver<F> {
+        assert!(self.var_num > 0, "no variable to bind");
+        if self.var_num == 1 {
+            self.bind_first(r_prev);
+            return PolynomialOver::constant(self.evals[0]);
+        }
+        let half = self.evals.len() / 2;
+        let quarter = half / 2;
+        let (lo, hi) = self.evals.split_at_mut(half);
+        let (lo_0, lo_1) = lo.split_at_mut(quarter);
+        let (hi_0, hi_1) = hi.split_at(quarter);
+        let sum_lo = fold_and_sum(lo_0, r_prev, hi_0);
+        let sum_hi = fold_and_sum(lo_1, r_prev, hi_1);
+        self.evals.truncate(half);
+        self.var_num -= 1;
+        PolynomialOver::from_coeffs(vec![sum_lo, sum_hi - sum_lo])
+    }
+
+    // W̃(point), point = (x_1, ..., x_v).
+    pub fn evaluate(&self, point: &[F]) -> F {
+        assert_eq!(point.len(), self.var_num, "Domain is less than var_num");
+        let mut table = Secret::copy_of(self);
+        for r in point.iter() {
+            table.bind_first(*r);
+        }
+        table.evals[0]
+    }
+
+    // W = c·W in place.
+    pub fn scale_in_place(&mut self, c: F) {
+        scale(&mut self.evals, c);
+    }
+
+    // W += c·V in place, eg: the γ-combination of the tables.
+    pub fn axpy(&mut self, c: F, other: &Self) {
+        debug_assert_eq!(self.var_num, other.var_num, "var_num mismatch");
+        axpy(&mut self.evals, c, &other.evals);
+    }
+
+    // ∑_{w ∈ {0,1}^v} W(w)
+    pub fn sum(&self) -> F {
+        self.evals.iter().sum()
+    }
+
+    // The tables are of the same poly, or the first point where they differ, eg: the dense and
+    // the sparse paths. The evals are compared in chunks with `parallel`, see `first_mismatch`.
+    pub fn agrees_with(&self, other: &Self) -> Result<(), Disagreement<F>> {
+        check_agreement(&self.evals, &other.evals)
+    }
+}
+
+// [W̃_1(point), ..., W̃_k(point)] of the tables with the same var_num, by
+//      W̃_i(point) = ∑_w eq(point, w)·W_i(w)
+// where the eq table is built once for all of them. Each W̃_i(point) is then an inner product
+// over the table as it is: 2^v mults and adds, where `evaluate` copies the table and folds it
+// with 2^v - 1 mults and twice the adds.
+pub fn batch_eval_mles<F: PrimeField>(tables: &[&EvalTableOver<F>], point: &[F]) -> Vec<F> {
+    batch_eval_mles_with_sink(tables, point, &mut ())
+}
+
+pub fn batch_eval_mles_with_sink<F: PrimeField, S: CostSink>(
+    tables: &[&EvalTableOver<F>],
+    point: &[F],
+    sink: &mut S,
+) -> Vec<F> {
+    if tables.is_empty() {
+        return Vec::new();
+    }
+    for (i, table) in tables.iter().enumerate() {
+        assert_eq!(
+            table.var_num,
+            point.len(),
+            "table {} has var_num {}, the point has {} coords",
+            i,
+            table.var_num,
+            point.len()
+        );
+    }
+    let weights = eq_table(point);
+    sink.count(
+        CostReport::eq_table(point.len())
+            + CostReport::inner_product(weights.len()).times(tables.len()),
+    );
+    tables
+        .iter()
+        .map(|table| inner_product(&weights, &table.evals))
+        .collect()
+}
+
+// A table of 2^var_num random evals, for the tests of this crate and of the ones built on it.
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub fn random_table(var_num: usize) -> EvalTable {
+    use ff::Field;
+    EvalTable::new(
+        (0..1 << var_num)
+            .map(|_| Scalar::random(rand_core::OsRng))
+            .collect(),
+    )
+}
+
+impl<F: PrimeField> From<&MPolynomialOver<F>> for EvalTableOver<F> {
+    fn from(poly: &MPolynomialOver<F>) -> Self {
+        Self {
+            var_num: poly.var_num,
+            evals: poly.hypercube_evaluations(),
+        }
+    }
+}
+
+// The table always determines a multilinear poly, so it's infallible
+// and `TryInto<MPolynomial>` comes with it. The table is already of the size of the coeffs, so
+// it's out of the dense budget, see `DenseBudget`.
+impl<F: PrimeField> From<&EvalTableOver<F>> for MPolynomialOver<F> {
+    fn from(table: &EvalTableOver<F>) -> Self {
+        MPolynomialOver::lagrange_unchecked(table.var_num, &table.evals)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cost::CostReport;
+    use crate::poly::eval_table::{
+        batch_eval_mles, batch_eval_mles_with_sink, random_table, Disagreement, EvalTable,
+    };
+    use crate::poly::multivar_poly::MPolynomial;
+    use crate::poly::univar_poly::Polynomial;
+    use crate::scalars;
+    use crate::utils::convert_to_binary;
+    use bls12_381::Scalar;
+    use ff::Field;
+    use rand_core::OsRng;
+
+    #[test]
+    fn test_bind_first_is_evaluate() {
+        let table = random_table(5);
+        let point = (0..5).map(|_| Scalar::random(OsRng)).collect::<Vec<_>>();
+
+        let mut bound = table.clone();
+        for r in point.iter() {
+            bound.bind_first(*r);
+        }
+        assert_eq!(bound.var_num, 0);
+        assert_eq!(bound.evals, vec![table.evaluate(&point)]);
+
+        // the same as the coeffs form
+        let poly = MPolynomial::from(&table);
+        assert_eq!(
+            table.evaluate(&scalars![3, 1, 4, 1, 5]),
+            poly.evaluate(&vec![3, 1, 4, 1, 5])
+        );
+    }
+
+    #[test]
+    fn test_bind_orders() {
+        // W(x1, x2) = 1 + x2 + 2*x1, evals = [W(0,0), W(0,1), W(1,0), W(1,1)]
+        let table = EvalTable::new(scalars![1, 2, 3, 4]);
+
+        // x1 = 5: W(5, x2) = 11 + x2
+        let mut first = table.clone();
+        first.bind_first(Scalar::from(5));
+        assert_eq!(first, EvalTable::new(scalars![11, 12]));
+
+        // x2 = 5: W(x1, 5) = 6 + 2*x1
+        let mut last = table.clone();
+        last.bind_last(Scalar::from(5));
+        assert_eq!(last, EvalTable::new(scalars![6, 8]));
+
+        let (lo, hi) = table.split_halves();
+        assert_eq!((lo.to_vec(), hi.to_vec()), (scalars![1, 2], scalars![3, 4]));
+        assert_eq!(table.sum(), Scalar::from(10));
+    }
+
+    #[test]
+    fn test_bind_in_place() {
+        let mut table = random_table(4);
+        let ptr = table.evals.as_ptr();
+        table.bind_first(Scalar::random(OsRng));
+        table.bind_last(Scalar::random(OsRng));
+        assert_eq!(table.evals.len(), 4);
+        assert_eq!(table.evals.as_ptr(), ptr);
+    }
+
+    // The rounds of x_1, ..., x_v and the final claim, by `first_round_poly` and `bind_first`.
+    fn two_pass_rounds(mut table: EvalTable, challenges: &[Scalar]) -> (Vec<Polynomial>, Scalar) {
+        let mut rounds = vec![];
+        for r in challenges.iter() {
+            rounds.push(table.first_round_poly());
+            table.bind_first(*r);
+        }
+        (rounds, table.evals[0])
+    }
+
+    // The same, by `bind_and_next_round` after the first round.
+    fn fused_rounds(mut table: EvalTable, challenges: &[Scalar]) -> (Vec<Polynomial>, Scalar) {
+        let mut rounds = vec![table.first_round_poly()];
+        for r in challenges.iter() {
+            rounds.push(table.bind_and_next_round(*r));
+        }
+        // the constant of the final claim
+        let last = rounds.pop().unwrap();
+        assert_eq!(last, Polynomial::constant(table.evals[0]));
+        (rounds, table.evals[0])
+    }
+
+    #[test]
+    fn test_fused_rounds_match_two_pass() {
+        // over 2 chunks at v = 14 with `parallel`
+        for var_num in 1..=14 {
+            let table = random_table(var_num);
+            let challenges = (0..var_num)
+                .map(|_| Scalar::random(OsRng))
+                .collect::<Vec<_>>();
+            let (rounds, claim) = fused_rounds(table.clone(), &challenges);
+            assert_eq!(
+                (rounds.clone(), claim),
+                two_pass_rounds(table.clone(), &challenges)
+            );
+
+            // g_1(0) + g_1(1) is the sum, and the claim is W̃(r)
+            let g_1 = &rounds[0];
+            assert_eq!(
+                g_1.evaluate(Scalar::zero()) + g_1.evaluate(Scalar::one()),
+                table.sum()
+            );
+            assert_eq!(claim, table.evaluate(&challenges));
+        }
+    }
+
+    #[test]
+    fn test_fused_round_is_in_place() {
+        let mut table = random_table(4);
+        let ptr = table.evals.as_ptr();
+        table.bind_and_next_round(Scalar::random(OsRng));
+        assert_eq!((table.var_num, table.evals.len()), (3, 8));
+        assert_eq!(table.evals.as_ptr(), ptr);
+    }
+
+    // cargo test --release -p sumcheck test_fused_rounds_bench -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn test_fused_rounds_bench() {
+        use std::time::Instant;
+        let var_num = 22;
+        let table = random_table(var_num);
+        let challenges = (0..var_num)
+            .map(|_| Scalar::random(OsRng))
+            .collect::<Vec<_>>();
+
+        let (t, u) = (table.clone(), table);
+        let start = Instant::now();
+        let two_pass = two_pass_rounds(t, &challenges);
+        let two_pass_time = start.elapsed();
+        let start = Instant::now();
+        let fused = fused_rounds(u, &challenges);
+        let fused_time = start.elapsed();
+        assert_eq!(fused, two_pass);
+        println!(
+            "v = {}: two-pass {:?}, fused {:?}",
+            var_num, two_pass_time, fused_time
+        );
+    }
+
+    #[test]
+    fn test_mpoly_round_trip() {
+        for var_num in 1..=8 {
+            let table = random_table(var_num);
+            let poly = MPolynomial::from(&table);
+            assert_eq!(EvalTable::from(&poly), table);
+        }
+    }
+
+    #[test]
+    fn test_axpy_and_scale() {
+        let (a, b) = (random_table(6), random_table(6));
+        let c = Scalar::random(OsRng);
+        let point = (0..6).map(|_| Scalar::random(OsRng)).collect::<Vec<_>>();
+
+        // (a + c·b)~ = ã + c·b̃, and the same for each entry
+        let mut res = a.clone();
+        res.axpy(c, &b);
+        assert_eq!(
+            res.evaluate(&point),
+            a.evaluate(&point) + c * b.evaluate(&point)
+        );
+        for ((r, a), b) in res.evals.iter().zip(a.evals.iter()).zip(b.evals.iter()) {
+            assert_eq!(*r, a + c * b);
+        }
+        assert_eq!(res.sum(), a.sum() + c * b.sum());
+
+        res.scale_in_place(c);
+        assert_eq!(res.sum(), c * (a.sum() + c * b.sum()));
+        res.scale_in_place(Scalar::zero());
+        assert_eq!(res, EvalTable::new(vec![Scalar::zero(); 1 << 6]));
+    }
+
+    #[test]
+    fn test_batch_eval_mles() {
+        let v = 12;
+        let tables = (0..10).map(|_| random_table(v)).collect::<Vec<_>>();
+        let refs = tables.iter().collect::<Vec<_>>();
+        let point = (0..v).map(|_| Scalar::random(OsRng)).collect::<Vec<_>>();
+
+        let mut cost = CostReport::default();
+        let evals = batch_eval_mles_with_sink(&refs, &point, &mut cost);
+        for (table, eval) in tables.iter().zip(evals.iter()) {
+            assert_eq!(table.evaluate(&point), *eval);
+        }
+
+        // the eq table is built once for the k tables, and each fold of `evaluate` has 2 adds.
+        let k = tables.len();
+        assert_eq!(
+            cost,
+            CostReport::eq_table(v) + CostReport::inner_product(1 << v).times(k)
+        );
+        assert!(cost.field_adds < CostReport::table_evaluate(v).times(k).field_adds * 3 / 5);
+    }
+
+    #[test]
+    fn test_batch_eval_mles_edges() {
+        let point = (0..4).map(|_| Scalar::random(OsRng)).collect::<Vec<_>>();
+        assert_eq!(batch_eval_mles::<Scalar>(&[], &point), vec![]);
+
+        let table = random_table(4);
+        assert_eq!(
+            batch_eval_mles(&[&table], &point),
+            vec![table.evaluate(&point)]
+        );
+
+        // the constant of no variable
+        let constant = EvalTable::new(scalars![7]);
+        assert_eq!(batch_eval_mles(&[&constant], &[]), scalars![7]);
+    }
+
+    #[test]
+    #[should_panic(expected = "table 1 has var_num 5")]
+    fn test_batch_eval_mles_rejects_var_num() {
+        let (a, b) = (random_table(4), random_table(5));
+        let point = (0..4).map(|_| Scalar::random(OsRng)).collect::<Vec<_>>();
+        batch_eval_mles(&[&a, &b], &point);
+    }
+
+    fn random_mpoly(var_num: usize) -> MPolynomial {
+        MPolynomial {
+            var_num,
+            coeffs: (0..1 << var_num).map(|_| Scalar::random(OsRng)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_agrees_with_converted() {
+        for var_num in 0..=12 {
+            let poly = random_mpoly(var_num);
+            let table = EvalTable::from(&poly);
+            assert_eq!(poly.agrees_with_table(&table), Ok(()));
+            assert_eq!(table.agrees_with(&table.clone()), Ok(()));
+        }
+        // and the other way.
+        for var_num in 0..=12 {
+            let table = random_table(var_num);
+            assert_eq!(MPolynomial::from(&table).agrees_with_table(&table), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_disagreement_is_located() {
+        let var_num = 8;
+        let poly = random_mpoly(var_num);
+        let table = EvalTable::from(&poly);
+        for index in [0, 77, (1 << var_num) - 1] {
+            let mut wrong = table.clone();
+            wrong.evals[index] += Scalar::one();
+            let expected = Disagreement::Value {
+                index,
+                point: convert_to_binary(&var_num, index),
+                left: table.evals[index],
+                right: wrong.evals[index],
+            };
+            assert_eq!(poly.agrees_with_table(&wrong), Err(expected.clone()));
+            assert_eq!(table.agrees_with(&wrong), Err(expected));
+        }
+
+        // f(1, 0) = 3, while the table has 4 there.
+        let poly = MPolynomial {
+            var_num: 2,
+            coeffs: scalars![1, 1, 2, 0],
+        };
+        let table = EvalTable::new(scalars![1, 2, 4, 4]);
+        let err = poly.agrees_with_table(&table).unwrap_err();
+        assert_eq!(
+            err,
+            Disagreement::Value {
+                index: 2,
+                point: vec![1, 0],
+                left: Scalar::from(3),
+                right: Scalar::from(4),
+            }
+        );
+    }
+
+    #[test]
+    fn test_disagreement_of_lengths() {
+        let (poly, table) = (random_mpoly(3), random_table(4));
+        let expected = Disagreement::LengthMismatch { left: 8, right: 16 };
+        assert_eq!(poly.agrees_with_table(&table), Err(expected.clone()));
+        assert_eq!(EvalTable::from(&poly).agrees_with(&table), Err(expected));
+        assert_eq!(
+            table.agrees_with(&EvalTable::from(&poly)),
+            Err(Disagreement::LengthMismatch { left: 16, right: 8 })
+        );
+    }
+}
@@ -1,46 +1,143 @@
-use crate::poly::univar_poly::Polynomial;
-use crate::utils::{convert_to_binary, expand_factor_for_mpoly};
+use crate::cost::check_dense_eval;
+use crate::poly::budget::{DenseBudget, ResourceLimit};
+use crate::poly::eval_table::{check_agreement, Disagreement, EvalTableOver};
+use crate::poly::univar_poly::PolynomialOver;
+use crate::utils::{axpy, expand_factor_for_mpoly, scale};
+use alloc::vec;
+use alloc::vec::Vec;
 use bls12_381::Scalar;
-use ff::Field;
-use log::{debug, log};
-use std::collections::HashMap;
-use std::hash::Hash;
-use std::ops::{Add, AddAssign};
+use core::fmt;
+use ff::PrimeField;
+
+mod term_string;
+pub use term_string::TermParseError;
+
+// The result would have degree > 1 in x_{var+1}, which can't be represented by `MPolynomial`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotMultilinear {
+    pub var: usize,
+}
+
+// The term at index has x_{var+1}^degree, beyond the degree bound of each variable, eg: a coeff
+// past the 2^v ones of `MPolynomial`, see `try_partial_evaluate`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DegreeBoundExceeded {
+    pub index: usize,
+    pub var: usize,
+    pub degree: usize,
+    pub bound: usize,
+}
+
+// The exps (e_1, ..., e_v) of a term, which are the bits of its index with x_1 as the MSB, the
+// same as `convert_to_binary`. They're decoded on access, so it's Copy and allocates nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmallExpVec {
+    var_num: usize,
+    index: usize,
+}
+
+impl SmallExpVec {
+    pub fn len(&self) -> usize {
+        self.var_num
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.var_num == 0
+    }
+
+    // e_{i+1}, the exp of x_{i+1}.
+    pub fn get(&self, i: usize) -> usize {
+        assert!(i < self.var_num, "the term has {} variables", self.var_num);
+        (self.index >> (self.var_num - 1 - i)) & 1
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = usize> {
+        let exps = *self;
+        (0..exps.var_num).map(move |i| exps.get(i))
+    }
+
+    // The i of each x_{i+1} in the term, eg: [0, 2] for x1 * x3.
+    pub fn vars(&self) -> impl Iterator<Item = usize> {
+        let exps = *self;
+        (0..exps.var_num).filter(move |i| exps.get(*i) == 1)
+    }
+
+    pub fn to_vec(&self) -> Vec<usize> {
+        self.iter().collect()
+    }
+}
+
+// A nonzero term coeff * x_1^e_1 * ... * x_v^e_v of `MPolynomial`, index is its place in coeffs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Term<'a, F = Scalar> {
+    pub coeff: &'a F,
+    pub exponents: SmallExpVec,
+    pub index: usize,
+}
 
 // A multivariate polynomial g is multilinear if the degree of the polynomial in each variable is at most one.
 // For example, the polynomial g(x1,x2) = x_1*x_2 +4x_1 +3x_2 is multilinear, but the polynomial
 // h(x1,x2) = x2 + 4x1 + 3x2 is not.
+//
+// The representation relies on it: the exps of a term are packed into the bits of its index,
+// so any operation which would raise a variable to degree 2 returns `NotMultilinear` instead of
+// silently dropping the degree. Use `DenseUVPerVarPolynomial` for the higher degree cases.
 #[derive(Debug, Clone, Eq, PartialEq, Default)]
-pub struct MPolynomial {
+pub struct MPolynomialOver<F> {
     pub var_num: usize,
     // The index (with binary form) is the exponent values.
-    pub coeffs: Vec<Scalar>,
+    pub coeffs: Vec<F>,
 }
 
-impl MPolynomial {
+// The multilinear polys of the protocol crates, over the scalar field of BLS12-381.
+pub type MPolynomial = MPolynomialOver<Scalar>;
+
+impl<F: PrimeField> MPolynomialOver<F> {
     // w: {0,1}^v
     // F(x_1,...,x_v) = ∑f(w)·X_w(x_1,...,x_v),
     // X_w(x1,...,xv) := ∏(xiwi +(1−xi)(1−wi)).
-    pub fn lagrange(var_num: usize, evals: &Vec<Scalar>) -> Self {
-        let n: usize = 1 << var_num;
-        assert_eq!(evals.len(), n, "Domain is less than var_num");
+    //
+    // The 2^v coeffs are checked against the default budget before they're allocated, eg:
+    // var_num = 30 is an Err rather than an OOM, see `DenseBudget`.
+    pub fn lagrange(var_num: usize, evals: &[F]) -> Result<Self, ResourceLimit> {
+        Self::lagrange_within(var_num, evals, &DenseBudget::default())
+    }
 
-        let mut F = vec![Scalar::zero(); n];
+    // Same as `lagrange`, with the budget, eg: a raised one for a poly above the default.
+    pub fn lagrange_within(
+        var_num: usize,
+        evals: &[F],
+        budget: &DenseBudget,
+    ) -> Result<Self, ResourceLimit> {
+        budget.check::<F>(var_num)?;
+        Ok(Self::lagrange_unchecked(var_num, evals))
+    }
 
-        // compute f_i = f_w * X_w
-        for (i, f_w) in evals.iter().enumerate() {
-            let w_i = convert_to_binary(&var_num, i);
-            // X_w(x1,...,xv) := ∏(xiwi +(1−xi)(1−wi)).
-            let X_w = Self::mpoly_langrange_basis(var_num, w_i);
-            // f_i = f(w)·X_w
-            let f_i = X_w.iter().map(|X_w_i| X_w_i * f_w).collect::<Vec<_>>();
+    // Same as `lagrange`, without the budget, eg: the evals are already of the size, so the
+    // coeffs take as much again.
+    //
+    // The sum of f(w)·X_w is the inverse of `hypercube_evaluations`: for each variable, the
+    // points with x_i = 1 subtract the ones with x_i = 0, which is O(v * 2^v) in place of the
+    // O(v * 4^v) of summing up the bases. The coeffs are the only allocation, but for the check
+    // of the debug builds.
+    pub fn lagrange_unchecked(var_num: usize, evals: &[F]) -> Self {
+        let n: usize = 1 << var_num;
+        assert_eq!(evals.len(), n, "Domain is less than var_num");
 
-            // F = ∑f_j
-            for i in 0..n {
-                F[i].add_assign(f_i[i]);
+        let mut coeffs = evals.to_vec();
+        for i in 0..var_num {
+            let bit = 1 << i;
+            for w in (0..n).filter(|w| w & bit != 0) {
+                let lo = coeffs[w ^ bit];
+                coeffs[w] -= lo;
             }
         }
-        Self { var_num, coeffs: F }
+        let poly = Self { var_num, coeffs };
+        debug_assert_eq!(
+            check_agreement(&poly.hypercube_evaluations(), evals),
+            Ok(())
+        );
+        poly
     }
 
     // X_w(x1,...,xv) := ∏(xiwi +(1−xi)(1−wi)).
@@ -55,7 +152,7 @@ impl MPolynomial {
     //      wi = 0, (xiwi +(1−xi)(1−wi))= (1 - xi) ;
     // So it's easy to obtain the factorization form of X_w.
     // eg: if var_num = 4, w=(0, 0, 1, 1), so that X_w(0,0,1,1)=(1-x_1)(1-x_2) * x_3 * x_4
-    pub fn mpoly_langrange_basis(var_num: usize, w: Vec<usize>) -> Vec<Scalar> {
+    pub fn mpoly_langrange_basis(var_num: usize, w: Vec<usize>) -> Vec<F> {
         assert_eq!(var_num, w.len());
         let poly_len = 1 << var_num;
 
@@ -67,18 +164,18 @@ impl MPolynomial {
         //      (i=2, w3 = 1) => x3 => (0, 0, 1, 0) = 1
         //      (i=3, w4 = 1) => x4 => (0, 0, 0, 1) = 1
         let gen_X_wi = |i: usize, w_i: usize| {
-            let mut factor = vec![Scalar::zero(); poly_len];
+            let mut factor = vec![F::ZERO; poly_len];
 
             // For (i=0, w1 = 0) => x1, whose coeff exp is (1, 0, 0, 0).
             // We need to encode it into index for coeff vector.
             let index: usize = 1 << (var_num - 1 - i);
             match w_i {
                 0 => {
-                    factor[0] = Scalar::one();
-                    factor[index] = Scalar::one().neg();
+                    factor[0] = F::ONE;
+                    factor[index] = -F::ONE;
                 }
                 1 => {
-                    factor[index] = Scalar::one();
+                    factor[index] = F::ONE;
                 }
                 _ => panic!("Only support (0,1)^v hypercube"),
             }
@@ -87,67 +184,137 @@ impl MPolynomial {
             factor
         };
 
-        // init with w[0].
-        let mut product = gen_X_wi(0, w[0]);
+        // init with the empty product 1, which is X_w of var_num = 0.
+        let mut product = vec![F::ZERO; poly_len];
+        product[0] = F::ONE;
 
         for (i, w_i) in w.iter().enumerate() {
-            if i == 0 {
-                continue;
-            }
             let factor = gen_X_wi(i, w_i.clone());
-            product = expand_factor_for_mpoly(var_num, product, factor);
+            // the factors are variable-disjoint, so the product is always multilinear.
+            product = expand_factor_for_mpoly(var_num, product, factor).unwrap();
         }
 
         product
     }
 
-    pub fn evaluate(&self, domain: &Vec<usize>) -> Scalar {
+    // The terms in the ascending order of index. Only the exact zeros are skipped, it's the
+    // one place which decodes the exps of a term.
+    pub fn terms(&self) -> impl Iterator<Item = Term<'_, F>> {
+        let var_num = self.var_num;
+        self.coeffs
+            .iter()
+            .enumerate()
+            .filter(|(_, coeff)| **coeff != F::ZERO)
+            .map(move |(index, coeff)| Term {
+                coeff,
+                exponents: SmallExpVec { var_num, index },
+                index,
+            })
+    }
+
+    pub fn num_nonzero_terms(&self) -> usize {
+        self.terms().count()
+    }
+
+    // The index of the last nonzero term, None for the zero poly.
+    pub fn leading_term_index(&self) -> Option<usize> {
+        self.terms().last().map(|term| term.index)
+    }
+
+    // f * g, which is multilinear only if f and g share no variable.
+    pub fn mul(&self, rhs: &Self) -> Result<Self, NotMultilinear> {
+        assert_eq!(self.var_num, rhs.var_num, "var_num mismatch");
+        let coeffs = expand_factor_for_mpoly(self.var_num, self.coeffs.clone(), rhs.coeffs.clone())?;
+        Ok(Self {
+            var_num: self.var_num,
+            coeffs,
+        })
+    }
+
+    pub fn evaluate(&self, domain: &[usize]) -> F {
         assert_eq!(domain.len(), self.var_num, "Domain is less than var_num");
+        check_dense_eval(self.coeffs.len());
 
-        let mut sum_of_term = Scalar::zero();
+        let mut sum_of_term = F::ZERO;
 
         // compute each term_i: coeff * product_x
-        for (index, coeff) in self.coeffs.iter().enumerate() {
-            // if the coeff is 0, then skip it.
-            if coeff.eq(&Scalar::zero()) {
-                continue;
-            }
-
-            // if index is 0, then term = coeff.
-            if index == 0 {
-                sum_of_term += coeff;
-            } else {
-                // x_0^exps[0] * x_1^exps[1] * x_2^exps[2]+ ...
-                let exps = convert_to_binary(&self.var_num, index);
-
-                // compute product of x , eg: product_x = (x_1^exp1) * (x_2^exp2)
-                let mut product = 1;
-                for (x_i, exp_i) in domain.into_iter().zip(exps) {
-                    let x = x_i.clone();
-
-                    // Note, as the definition, the exp is in [0, 1]
-                    // if exp != 0 && x != 0 {
-                    product *= x.pow(exp_i as u32);
-
-                    // once product, the computation of product is over. As zero multiple anything is zero.
-                    if 0 == product {
-                        break;
-                    }
+        for term in self.terms() {
+            // product_x = (x_1^exp1) * (x_2^exp2) * ..., as the definition, the exp is in [0, 1].
+            // once the product is zero, it's over. As zero multiple anything is zero.
+            // It's in usize while it fits, and in F once it overflows, rather than wrapping.
+            let product = term
+                .exponents
+                .vars()
+                .try_fold(1usize, |acc, i| acc.checked_mul(domain[i]));
+
+            match product {
+                Some(0) => continue,
+                Some(1) => sum_of_term += term.coeff,
+                Some(product) => {
+                    let term_i = *term.coeff * F::from(product as u64);
+                    sum_of_term.add_assign(term_i);
                 }
-
-                match product {
-                    0 => continue,
-                    1 => sum_of_term += coeff,
-                    _ => {
-                        let term_i = coeff.mul(&Scalar::from(product as u64));
-                        sum_of_term.add_assign(term_i);
-                    }
+                None => {
+                    let product = term
+                        .exponents
+                        .vars()
+                        .fold(F::ONE, |acc, i| acc * F::from(domain[i] as u64));
+                    sum_of_term.add_assign(*term.coeff * product);
                 }
             }
         }
         sum_of_term
     }
 
+    // f at the hypercube point with the bits of point_bits, x_1 is the MSB as in
+    // `convert_to_binary`. A term is 1 on the point if its exps are a submask of the point, and 0
+    // otherwise, so f = ∑ coeffs[idx] over the submasks idx, without any multiplication.
+    // eg: f(1, 0, 1) = c_000 + c_001 + c_100 + c_101.
+    // It's O(2^|point_bits|), see `hypercube_evaluations` for all the points at once.
+    pub fn evaluate_binary(&self, point_bits: usize) -> F {
+        assert!(
+            point_bits < self.coeffs.len(),
+            "point is out of the hypercube"
+        );
+        let mut sum = F::ZERO;
+        let mut idx = point_bits;
+        loop {
+            sum += self.coeffs[idx];
+            if idx == 0 {
+                return sum;
+            }
+            idx = (idx - 1) & point_bits;
+        }
+    }
+
+    // [f(w) for w in {0,1}^v], indexed by w as in `convert_to_binary`, eg: the evals of EvalTable.
+    // It's the zeta transform of the coeffs over the subsets: for each variable, the points with
+    // x_i = 1 add up the ones with x_i = 0, so it's v * 2^(v-1) additions in all.
+    pub fn hypercube_evaluations(&self) -> Vec<F> {
+        let mut evals = self.coeffs.clone();
+        for i in 0..self.var_num {
+            let bit = 1 << i;
+            for w in (0..evals.len()).filter(|w| w & bit != 0) {
+                let lo = evals[w ^ bit];
+                evals[w] += lo;
+            }
+        }
+        evals
+    }
+
+    // f is the poly of the table, or the first point where they differ, eg: after a conversion.
+    // The coeffs are zeta transformed to the evals as above, which is O(v * 2^v), and the evals
+    // are compared up to the first mismatch.
+    pub fn agrees_with_table(&self, table: &EvalTableOver<F>) -> Result<(), Disagreement<F>> {
+        if self.coeffs.len() != table.evals.len() {
+            return Err(Disagreement::LengthMismatch {
+                left: self.coeffs.len(),
+                right: table.evals.len(),
+            });
+        }
+        check_agreement(&self.hypercube_evaluations(), &table.evals)
+    }
+
     // Convert a multi-poly into a uni-poly:
     //      f(x1, x2, x3, x4) , x1,x2,x3,x4 in hypercube
     //      With inputs(r1,r2,X,x4), the multi-poly become a uni-poly p(X)
@@ -156,174 +323,214 @@ impl MPolynomial {
     //        (x_j+1, ..., x_v} in hypercube{0,1}^v
     //
     // This is useful in sum-check protocol when obtaining g_i(X)
-    pub fn partial_evaluate(&self, challenge_domain: &Vec<usize>) -> Polynomial {
+    //
+    // With all the variables bound (eg: var_num = 0, or j = var_num), there's no X left,
+    // so it's the constant polynomial g(r1, ..., r_v).
+    pub fn partial_evaluate(&self, challenge_domain: &Vec<usize>) -> PolynomialOver<F> {
+        self.try_partial_evaluate(challenge_domain, 1)
+            .expect("a term out of the 2^var_num coeffs")
+    }
+
+    // The same as `partial_evaluate`, with the degree bound of each variable, which is 1 for a
+    // multilinear poly, so g_j(X) has degree_bound + 1 coeffs at most. The result is trimmed, eg:
+    // the zero poly is [0].
+    //
+    // A term c * x_1^e_1 * ... * x_v^e_v is summed up over the hypercube of x_j+1, ..., x_v at
+    // once: it's 0 unless the x_i with e_i = 1 are 1, and the others are free, so it adds
+    //      c * r_1^e_1 * ... * r_j-1^e_j-1 * 2^(the free ones)
+    // to the coeff of X^e_j.
+    pub fn try_partial_evaluate(
+        &self,
+        challenge_domain: &Vec<usize>,
+        degree_bound: usize,
+    ) -> Result<PolynomialOver<F>, DegreeBoundExceeded> {
         // the X = x_j, others has values.
         // Note here, x start with x_0, as the array index start with 0.
         let j = challenge_domain.len();
-        assert!(j < self.var_num);
-
-        // <k,v>: k is the exp of X, v is the coeff, aka. <exp, coeff>
-        let mut map: HashMap<usize, Scalar> = HashMap::new();
-
-        // var_num = challenger_len + 1 + extra_len
-        let extra_var_num = self.var_num - j - 1;
-        let extra_n = 1 << extra_var_num;
-        let extra_domain = (0..extra_n)
-            .into_iter()
-            .map(|n| convert_to_binary(&extra_var_num, n))
-            .collect::<Vec<_>>();
-        debug!(
-            "extra domain {:?}, j {:?}, var_num:{:?}, extra_var_num: {:?}, extra_n: {:?}",
-            extra_domain, j, self.var_num, extra_var_num, extra_n
-        );
+        assert!(j <= self.var_num, "more challenges than var_num");
+        self.check_degree_bound(degree_bound)?;
+        if j == self.var_num {
+            return Ok(PolynomialOver::constant(self.evaluate(challenge_domain)));
+        }
 
-        // compute each term_i: coeff * product_x * X(x_j)
-        for (index, coeff) in self.coeffs.iter().enumerate() {
-            // if the coeff is 0, then skip it.
-            if coeff.eq(&Scalar::zero()) {
-                continue;
+        let mut coeffs = vec![F::ZERO; degree_bound + 1];
+        for term in self.terms() {
+            let exps = self.exps_of(term.index);
+            let mut product = *term.coeff;
+            for (r_i, e_i) in challenge_domain.iter().zip(exps.clone()) {
+                product *= F::from(*r_i as u64).pow([e_i as u64]);
             }
+            let free = exps.clone().skip(j + 1).filter(|e_i| *e_i == 0).count();
+            product *= F::from(2).pow([free as u64]);
+            coeffs[exps.clone().nth(j).unwrap()] += product;
+        }
+        while coeffs.len() > 1 && coeffs.last() == Some(&F::ZERO) {
+            coeffs.pop();
+        }
+        Ok(PolynomialOver { coeffs })
+    }
 
-            // x_0^exps[0] * x_1^exps[1] * x_2^exps[2]+ ...
-            let exps = convert_to_binary(&self.var_num, index);
-
-            // compute product_x on challenge_domain + hypercube_domain[i]
-            for extra in extra_domain.clone() {
-                // if index is 0, then term = coeff.
-                if index == 0 {
-                    map.entry(0)
-                        .and_modify(|v| v.add_assign(&coeff.clone()))
-                        .or_insert(coeff.clone());
-
-                    continue;
-                }
+    // The exps of the term at index, the bits of the index past var_num are read as the exp of
+    // x_1, the MSB, eg: 2 for the index 2^v of a corrupted coeffs.
+    fn exps_of(&self, index: usize) -> impl Iterator<Item = usize> + Clone {
+        let exponents = SmallExpVec {
+            var_num: self.var_num,
+            index,
+        };
+        let top = index >> self.var_num.saturating_sub(1);
+        core::iter::once(top).chain((1..self.var_num).map(move |i| exponents.get(i)))
+    }
 
-                // compute product of x , eg: product_x = (x_1^exp1) * (x_2^exp2), except x_j
-                let mut key = 0;
-                let mut product = 1;
-
-                // evaluate on domain + hypercube_i
-                let mut domain = challenge_domain.clone();
-                domain.push(0);
-                domain.extend(extra.clone());
-                debug!(
-                    "coeff:{:?}, domain:{:?}, j: {:?}, exps: {:?}",
-                    coeff, domain, j, exps
-                );
-                for (index, (xi, exp)) in domain.iter().zip(exps.clone()).enumerate() {
-                    if index == j {
-                        key = exp.clone();
-                    } else {
-                        let pro = xi.pow(exp.clone() as u32);
-                        debug!("x_{:?}^exp: {:?}^{:?}={:?}", index + 1, xi, exp, pro);
-                        product *= pro;
-                        // product *= xi.pow(exp.clone() as u32);
-                    }
-                    // once product, the computation of product is over. As zero multiple anything is zero.
-                    if 0 == product {
-                        break;
-                    }
-                }
-                if 0 == product {
-                    continue;
-                } else {
-                    let term_i = coeff.mul(&Scalar::from(product as u64));
-                    debug!("k:{:?}, v:{:?}", key, term_i);
-                    map.entry(key)
-                        .and_modify(|v| v.add_assign(&term_i))
-                        .or_insert(term_i);
-                }
+    fn check_degree_bound(&self, bound: usize) -> Result<(), DegreeBoundExceeded> {
+        for term in self.terms() {
+            if let Some((var, degree)) = self
+                .exps_of(term.index)
+                .enumerate()
+                .find(|(_, e)| *e > bound)
+            {
+                return Err(DegreeBoundExceeded {
+                    index: term.index,
+                    var,
+                    degree,
+                    bound,
+                });
             }
         }
+        Ok(())
+    }
 
-        // map -> poly
-        let coeffs = if map.is_empty() {
-            vec![Scalar::zero()]
-        } else {
-            let max_key = map.keys().max().unwrap().clone();
-            let coeffs = (0..=max_key)
-                .map(|i: usize| {
-                    if map.contains_key(&i) {
-                        map.get(&i).unwrap().clone()
-                    } else {
-                        Scalar::zero()
-                    }
-                })
-                .collect::<Vec<_>>();
-            coeffs
-        };
-        Polynomial { coeffs }
+    // f = c·f in place.
+    pub fn scale_in_place(&mut self, c: F) {
+        scale(&mut self.coeffs, c);
+    }
+
+    // f += c·g in place, which is always multilinear.
+    pub fn axpy(&mut self, c: F, other: &Self) {
+        debug_assert_eq!(self.var_num, other.var_num, "var_num mismatch");
+        axpy(&mut self.coeffs, c, &other.coeffs);
     }
 
     // sum all the evaluations on hypercube of a mpoly
     // Porting from sumcheck::Prover::proof()
-    pub fn sum_all_evals(&self) -> Scalar {
-        let n = 1 << self.var_num;
-        (0..n)
-            .map(|i| {
-                let domain = convert_to_binary(&self.var_num, i);
-                self.evaluate(&domain)
-            })
-            .sum()
+    pub fn sum_all_evals(&self) -> F {
+        self.hypercube_evaluations().iter().sum()
+    }
+}
+
+// eg: 0x..05 + 0x..02*x3 + 0x..03*x2 + 0x..01*x1*x2*x3, the coeffs are in the Debug of Scalar.
+// See `to_term_string` for the readable one.
+impl fmt::Display for MPolynomial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_terms(f, |f, k, coeff, _| {
+            if k > 0 {
+                write!(f, " + ")?;
+            }
+            write!(f, "{:?}", coeff).map(|_| true)
+        })
+    }
+}
+
+impl MPolynomial {
+    // The terms loop of the Display and `to_term_string`: coeff writes the separator and the
+    // coeff of the k-th term, and returns false if the coeff is omitted, eg: the 1 of 1*x1.
+    fn write_terms(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        coeff: impl Fn(&mut fmt::Formatter<'_>, usize, &Scalar, bool) -> Result<bool, fmt::Error>,
+    ) -> fmt::Result {
+        let mut terms = self.terms().peekable();
+        if terms.peek().is_none() {
+            return write!(f, "0");
+        }
+        for (k, term) in terms.enumerate() {
+            let mut star = coeff(f, k, term.coeff, term.index == 0)?;
+            for i in term.exponents.vars() {
+                if star {
+                    write!(f, "*")?;
+                }
+                write!(f, "x{}", i + 1)?;
+                star = true;
+            }
+        }
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::poly::multivar_poly::MPolynomial;
+    use crate::poly::budget::{DenseBudget, ResourceLimit, DEFAULT_MAX_DENSE_VARS};
+    use crate::poly::multivar_poly::{DegreeBoundExceeded, MPolynomial, NotMultilinear};
     use crate::poly::univar_poly::Polynomial;
     use crate::utils::convert_to_binary;
+    use crate::{mpoly, scalars};
     use bls12_381::Scalar;
-    use ff::PrimeField;
+    use ff::{Field, PrimeField};
+    use rand_core::{OsRng, RngCore};
 
     fn gen_mpoly() -> MPolynomial {
-        // let g(x1, x2, x3) = 5 + 2*x3 + 3*x2 +  x1 * x2 * x3
-        // term0: exp: (0,0,0) = 5
-        // term1: exp: (0,0,1) = 2*x3
-        // term2: exp: (0,1,0) = 3*x2
-        // term3-6: exp: (0,1,0) = 0.
-        // term7: exp: (1,1,1) = x1 * x2 * x3
-
-        let var_num = 3;
-
-        MPolynomial {
-            var_num,
-            coeffs: vec![
-                Scalar::from_u128(5),
-                Scalar::from_u128(2),
-                Scalar::from_u128(3),
-                Scalar::zero(),
-                Scalar::zero(),
-                Scalar::zero(),
-                Scalar::zero(),
-                Scalar::one(),
-            ],
-        }
+        mpoly!("5 + 2*x3 + 3*x2 + x1*x2*x3", vars = 3)
     }
 
+    // The term strings of the fixtures are the same as the coeffs by the hypercube index.
     #[test]
-    fn test_lagrange() {
-        // let row g(x1, x2, x3) = 5 + 2*x3 + 3*x2 +  x1 * x2 * x3
+    fn test_fixtures() {
         // term0: exp: (0,0,0) = 5
         // term1: exp: (0,0,1) = 2*x3
         // term2: exp: (0,1,0) = 3*x2
-        // term3-6: exp: (0,1,0) = 0.
+        // term3-6: exp: (0,1,1)..(1,1,0) = 0.
         // term7: exp: (1,1,1) = x1 * x2 * x3
+        assert_eq!(
+            gen_mpoly(),
+            MPolynomial {
+                var_num: 3,
+                coeffs: scalars![5, 2, 3, 0, 0, 0, 0, 1],
+            }
+        );
+        assert_eq!(
+            mpoly!("4 + 28*x1", vars = 1),
+            MPolynomial {
+                var_num: 1,
+                coeffs: scalars![4, 28],
+            }
+        );
+        assert_eq!(
+            mpoly!("1 + x1", vars = 2),
+            MPolynomial {
+                var_num: 2,
+                coeffs: scalars![1, 0, 1, 0],
+            }
+        );
+        assert_eq!(
+            mpoly!("2 + x2", vars = 2),
+            MPolynomial {
+                var_num: 2,
+                coeffs: scalars![2, 1, 0, 0],
+            }
+        );
+        assert_eq!(
+            mpoly!("2 + x2 + 2*x1 + x1*x2", vars = 2),
+            MPolynomial {
+                var_num: 2,
+                coeffs: scalars![2, 1, 2, 1],
+            }
+        );
+        assert_eq!(
+            mpoly!("0", vars = 3),
+            MPolynomial {
+                var_num: 3,
+                coeffs: scalars![0, 0, 0, 0, 0, 0, 0, 0],
+            }
+        );
+    }
 
+    #[test]
+    fn test_lagrange() {
+        // the evals are the coeffs of 5 + 2*x3 + 3*x2 + x1*x2*x3
         let var_num = 3;
 
-        let evals = vec![
-            Scalar::from_u128(5),
-            Scalar::from_u128(2),
-            Scalar::from_u128(3),
-            Scalar::zero(),
-            Scalar::zero(),
-            Scalar::zero(),
-            Scalar::zero(),
-            Scalar::one(),
-        ];
+        let evals = gen_mpoly().coeffs;
 
-        let poly = MPolynomial::lagrange(var_num, &evals);
+        let poly = MPolynomial::lagrange(var_num, &evals).unwrap();
 
         // all domains
         let max_num: usize = 1 << var_num;
@@ -351,7 +558,7 @@ mod test {
 
         // expect t(x) = 12 + 16x
         let target = Polynomial {
-            coeffs: vec![Scalar::from_u128(12), Scalar::from_u128(16)],
+            coeffs: scalars![12, 16],
         };
         assert_eq!(actual, target);
 
@@ -362,29 +569,130 @@ mod test {
     }
 
     #[test]
-    fn test_evaluate() {
-        // let g(x1, x2, x3) = 5 + 2*x3 + 3*x2 +  x1 * x2 * x3
-        // term0: exp: (0,0,0) = 5
-        // term1: exp: (0,0,1) = 2*x3
-        // term2: exp: (0,1,0) = 3*x2
-        // term3-6: exp: (0,1,0) = 0.
-        // term7: exp: (1,1,1) = x1 * x2 * x3
+    fn test_partial_evaluate_degenerate() {
+        // var_num = 0: g = 7, there's nothing to sum up.
+        let constant = mpoly!("7", vars = 0);
+        assert_eq!(constant.coeffs.len(), 1);
+        assert_eq!(MPolynomial::lagrange(0, &scalars![7]), Ok(constant.clone()));
+        assert_eq!(constant.evaluate(&vec![]), Scalar::from(7));
+        assert_eq!(constant.sum_all_evals(), Scalar::from(7));
+        assert_eq!(
+            constant.partial_evaluate(&vec![]),
+            Polynomial::constant(Scalar::from(7))
+        );
 
-        let var_num = 3;
+        // var_num = 1: g_1(X) = g(X), and g(r1) once x1 is bound.
+        let uni = mpoly!("4 + 28*x1", vars = 1);
+        assert_eq!(uni.partial_evaluate(&vec![]).coeffs, scalars![4, 28]);
+        assert_eq!(
+            uni.partial_evaluate(&vec![10]),
+            Polynomial::constant(Scalar::from(284))
+        );
 
-        let poly = MPolynomial {
-            var_num,
-            coeffs: vec![
-                Scalar::from_u128(5),
-                Scalar::from_u128(2),
-                Scalar::from_u128(3),
-                Scalar::zero(),
-                Scalar::zero(),
-                Scalar::zero(),
-                Scalar::zero(),
-                Scalar::one(),
-            ],
+        // fully bound: g(r1, r2, r3).
+        let mpoly = gen_mpoly();
+        let r = vec![3, 2, 1];
+        assert_eq!(
+            mpoly.partial_evaluate(&r),
+            Polynomial::constant(mpoly.evaluate(&r))
+        );
+    }
+
+    #[test]
+    fn test_partial_evaluate_vanishing() {
+        // no term at all, so nothing is accumulated.
+        let zero = MPolynomial {
+            var_num: 3,
+            coeffs: vec![Scalar::zero(); 8],
+        };
+        for j in 0..3 {
+            assert_eq!(zero.partial_evaluate(&vec![1; j]), Polynomial::zero());
+        }
+        // 4*x1*x2 with x1 = 0, the terms are there but all of them vanish.
+        let mpoly = mpoly!("4*x1*x2", vars = 2);
+        assert_eq!(mpoly.partial_evaluate(&vec![0]), Polynomial::zero());
+        // X vanishes alone, so it's trimmed: 3 + 4*x1*x2 with x1 = 0 is 3 + 3 over x3.
+        let mpoly = mpoly!("3 + 4*x1*x2", vars = 3);
+        assert_eq!(mpoly.partial_evaluate(&vec![0]).coeffs, scalars![6]);
+    }
+
+    #[test]
+    fn test_partial_evaluate_degree_bound() {
+        // a coeff past the 2^2 ones, its index 4 is read as x1^2.
+        let corrupted = MPolynomial {
+            var_num: 2,
+            coeffs: scalars![1, 2, 3, 4, 5],
+        };
+        let expected = DegreeBoundExceeded {
+            index: 4,
+            var: 0,
+            degree: 2,
+            bound: 1,
         };
+        for challenges in [vec![], vec![3], vec![3, 4]] {
+            assert_eq!(
+                corrupted.try_partial_evaluate(&challenges, 1),
+                Err(expected)
+            );
+        }
+        // within a larger bound, it's x1^2 indeed.
+        assert_eq!(
+            corrupted.try_partial_evaluate(&vec![], 2).unwrap().coeffs,
+            scalars![4, 10, 10]
+        );
+
+        // x2 has degree 1, beyond the bound 0.
+        let mpoly = mpoly!("1 + x2", vars = 2);
+        assert_eq!(
+            mpoly.try_partial_evaluate(&vec![], 0),
+            Err(DegreeBoundExceeded {
+                index: 1,
+                var: 1,
+                degree: 1,
+                bound: 0,
+            })
+        );
+    }
+
+    // g_j(t) is the sum of f(r_1, ..., r_j-1, t, b) over b in the hypercube.
+    #[test]
+    fn test_partial_evaluate_at_t() {
+        for _ in 0..100 {
+            let var_num = 1 + (OsRng.next_u32() % 5) as usize;
+            let f = MPolynomial {
+                var_num,
+                coeffs: (0..1 << var_num).map(|_| Scalar::random(OsRng)).collect(),
+            };
+            let j = (OsRng.next_u32() as usize) % var_num;
+            let r = (0..j)
+                .map(|_| (OsRng.next_u32() % 1000) as usize)
+                .collect::<Vec<_>>();
+            let t = (OsRng.next_u32() % 1000) as usize;
+
+            let rest = var_num - j - 1;
+            let expected = (0..1 << rest)
+                .map(|b| {
+                    let mut point = r.clone();
+                    point.push(t);
+                    point.extend(convert_to_binary(&rest, b));
+                    f.evaluate(&point)
+                })
+                .sum::<Scalar>();
+            let g_j = f.partial_evaluate(&r);
+            assert_eq!(g_j.evaluate(Scalar::from(t as u64)), expected);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "more challenges than var_num")]
+    fn test_partial_evaluate_too_many_challenges() {
+        mpoly!("4 + 28*x1", vars = 1).partial_evaluate(&vec![1, 2]);
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let var_num = 3;
+        let poly = mpoly!("5 + 2*x3 + 3*x2 + x1*x2*x3", vars = var_num);
 
         // domain: (0,1,1)
         let domain = convert_to_binary(&var_num, 3);
@@ -399,16 +707,7 @@ mod test {
 
     #[test]
     fn test_evaluate_uni_var() {
-        // let g(x1) = 4 + 28*x1
-        // term0: exp: (0) = 4
-        // term1: exp: (1) = 28*x
-
-        let var_num = 1;
-
-        let poly = MPolynomial {
-            var_num,
-            coeffs: vec![Scalar::from_u128(4), Scalar::from_u128(28)],
-        };
+        let poly = mpoly!("4 + 28*x1", vars = 1);
 
         println!("{:?}", poly);
 
@@ -418,4 +717,200 @@ mod test {
         let actual = poly.evaluate(&vec![3]);
         assert_eq!(target, actual);
     }
+
+    #[test]
+    fn test_mul() {
+        // (1 + x1) * (2 + x2) = 2 + x2 + 2x1 + x1x2
+        let f = mpoly!("1 + x1", vars = 2);
+        let g = mpoly!("2 + x2", vars = 2);
+        let product = f.mul(&g).unwrap();
+        assert_eq!(product, mpoly!("2 + x2 + 2*x1 + x1*x2", vars = 2));
+
+        // squaring any variable is rejected
+        assert_eq!(f.mul(&f), Err(NotMultilinear { var: 0 }));
+        assert_eq!(g.mul(&g), Err(NotMultilinear { var: 1 }));
+        assert_eq!(product.mul(&f), Err(NotMultilinear { var: 0 }));
+    }
+
+    #[test]
+    fn test_terms() {
+        let poly = gen_mpoly();
+        let terms = poly.terms().collect::<Vec<_>>();
+        assert_eq!(terms.len(), 4);
+        assert_eq!(
+            terms.iter().map(|t| t.index).collect::<Vec<_>>(),
+            vec![0, 1, 2, 7]
+        );
+        assert_eq!(
+            terms
+                .iter()
+                .map(|t| t.exponents.to_vec())
+                .collect::<Vec<_>>(),
+            vec![vec![0, 0, 0], vec![0, 0, 1], vec![0, 1, 0], vec![1, 1, 1]]
+        );
+        assert_eq!(terms[3].coeff, &Scalar::one());
+        assert_eq!(terms[2].exponents.vars().collect::<Vec<_>>(), vec![1]);
+        assert_eq!(poly.num_nonzero_terms(), 4);
+        assert_eq!(poly.leading_term_index(), Some(7));
+
+        let zero = mpoly!("0", vars = 3);
+        assert_eq!(zero.terms().count(), 0);
+        assert_eq!(zero.leading_term_index(), None);
+        assert_eq!(zero.to_string(), "0");
+    }
+
+    #[test]
+    fn test_terms_order() {
+        let var_num = 6;
+        // the zeros at the even index, and 1 or -1 is not zero.
+        let coeffs = (0..1 << var_num)
+            .map(|i| match i % 4 {
+                0 | 2 => Scalar::zero(),
+                1 => -Scalar::one(),
+                _ => Scalar::random(OsRng),
+            })
+            .collect::<Vec<_>>();
+        let poly = MPolynomial { var_num, coeffs };
+
+        let indices = poly.terms().map(|t| t.index).collect::<Vec<_>>();
+        assert_eq!(indices, (1..1 << var_num).step_by(2).collect::<Vec<_>>());
+        for term in poly.terms() {
+            assert_eq!(term.coeff, &poly.coeffs[term.index]);
+            assert_eq!(
+                term.exponents.to_vec(),
+                convert_to_binary(&var_num, term.index)
+            );
+        }
+        assert_eq!(poly.leading_term_index(), Some((1 << var_num) - 1));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            gen_mpoly().to_string(),
+            concat!(
+                "0x0000000000000000000000000000000000000000000000000000000000000005",
+                " + 0x0000000000000000000000000000000000000000000000000000000000000002*x3",
+                " + 0x0000000000000000000000000000000000000000000000000000000000000003*x2",
+                " + 0x0000000000000000000000000000000000000000000000000000000000000001*x1*x2*x3",
+            )
+        );
+    }
+
+    #[test]
+    fn test_lagrange_budget() {
+        // var_num 30 is refused before the evals are read, so they can be of any length.
+        assert_eq!(
+            MPolynomial::lagrange(30, &[]),
+            Err(ResourceLimit {
+                requested_bytes: 32 << 30,
+                limit_bytes: 32 << DEFAULT_MAX_DENSE_VARS,
+            })
+        );
+        let budget = DenseBudget::with_max_vars(21);
+        assert_eq!(
+            MPolynomial::lagrange_within(22, &[], &budget),
+            Err(ResourceLimit {
+                requested_bytes: 32 << 22,
+                limit_bytes: 32 << 21,
+            })
+        );
+
+        // a raised budget, eg: 2^22 coeffs, the evals of x1 + ... + x22.
+        let var_num = 22;
+        let evals = (0..1u32 << var_num)
+            .map(|w| Scalar::from(w.count_ones() as u64))
+            .collect::<Vec<_>>();
+        let budget = DenseBudget::with_max_vars(var_num);
+        let poly = MPolynomial::lagrange_within(var_num, &evals, &budget).unwrap();
+        assert_eq!(poly.num_nonzero_terms(), var_num);
+        assert!((0..var_num).all(|i| poly.coeffs[1 << i] == Scalar::one()));
+    }
+
+    #[test]
+    fn test_lagrange_is_multilinear() {
+        // every basis X_w is a product of variable-disjoint factors.
+        let var_num = 4;
+        for i in 0..(1 << var_num) {
+            let w = convert_to_binary(&var_num, i);
+            let basis = MPolynomial {
+                var_num,
+                coeffs: MPolynomial::mpoly_langrange_basis(var_num, w.clone()),
+            };
+            assert_eq!(basis.evaluate(&w), Scalar::one());
+        }
+    }
+
+    #[test]
+    fn test_axpy_and_scale() {
+        let random = || MPolynomial {
+            var_num: 4,
+            coeffs: (0..1 << 4).map(|_| Scalar::random(OsRng)).collect(),
+        };
+        let (f, g) = (random(), random());
+        let c = Scalar::random(OsRng);
+
+        // (f + c·g)(x) = f(x) + c·g(x) on the whole hypercube
+        let mut res = f.clone();
+        res.axpy(c, &g);
+        for i in 0..1 << 4 {
+            let x = convert_to_binary(&4, i);
+            assert_eq!(res.evaluate(&x), f.evaluate(&x) + c * g.evaluate(&x));
+        }
+
+        let mut scaled = f.clone();
+        scaled.scale_in_place(c);
+        assert_eq!(
+            scaled.evaluate(&vec![1, 0, 1, 1]),
+            c * f.evaluate(&vec![1, 0, 1, 1])
+        );
+        scaled.scale_in_place(Scalar::zero());
+        assert_eq!(scaled, mpoly!("0", vars = 4));
+        assert_eq!(scaled.num_nonzero_terms(), 0);
+    }
+
+    #[test]
+    fn test_hypercube_evaluations() {
+        let poly = gen_mpoly();
+        // g(x1, x2, x3) = 5 + 2*x3 + 3*x2 +  x1 * x2 * x3
+        assert_eq!(
+            poly.hypercube_evaluations(),
+            scalars![5, 7, 8, 10, 5, 7, 8, 11]
+        );
+        assert_eq!(poly.evaluate_binary(0b111), Scalar::from_u128(11));
+
+        for var_num in 0..=10 {
+            let poly = MPolynomial {
+                var_num,
+                coeffs: (0..1 << var_num).map(|_| Scalar::random(OsRng)).collect(),
+            };
+            let evals = poly.hypercube_evaluations();
+            for (i, eval) in evals.iter().enumerate() {
+                assert_eq!(*eval, poly.evaluate(&convert_to_binary(&var_num, i)));
+                assert_eq!(*eval, poly.evaluate_binary(i));
+            }
+            assert_eq!(poly.sum_all_evals(), evals.iter().sum());
+        }
+    }
+
+    #[test]
+    fn test_hypercube_evaluations_time() {
+        use std::time::Instant;
+
+        let var_num = 16;
+        let poly = MPolynomial {
+            var_num,
+            coeffs: (0..1 << var_num).map(|i| Scalar::from(i as u64)).collect(),
+        };
+        let start = Instant::now();
+        let evals = poly.hypercube_evaluations();
+        assert!(start.elapsed().as_millis() < 1000);
+
+        // f(1, ..., 1) is the sum of the coeffs, 0 + 1 + ... + (2^16 - 1).
+        assert_eq!(evals.len(), 1 << var_num);
+        assert_eq!(
+            evals[(1 << var_num) - 1],
+            Scalar::from((1u64 << 15) * ((1 << 16) - 1))
+        );
+    }
 }
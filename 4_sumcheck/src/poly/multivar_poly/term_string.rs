@@ -0,0 +1,354 @@
+// The term strings of `MPolynomial`, which are the readable fixtures of the tests, eg:
+//      5 + 2*x3 + 3*x2 + x1*x2*x3
+//      2 - x2 + 2*x1 - x1*x2
+// rather than the coeffs by the hypercube index, see `MPolynomial::parse` and `mpoly!`.
+//
+// A term is a product of the integer coeffs and the distinct variables x1..xv, with `+` or `-`
+// between the terms. The negative coeffs are mapped to p - |x|, and the same variables in two
+// terms are summed up. The coeffs which don't fit in a u64 are written as 0x<hex>, the 64 hex
+// chars of `scalar_utils::to_hex`, so that `to_term_string` always parses back.
+use crate::poly::multivar_poly::MPolynomial;
+use crate::scalar_utils::{from_hex, to_hex, to_u64};
+use alloc::string::{String, ToString};
+use alloc::vec;
+use bls12_381::Scalar;
+use core::fmt;
+
+// pos is the byte offset in the term string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TermParseError {
+    // eg: x1*x1, which is not multilinear.
+    SquaredVar {
+        pos: usize,
+        var: usize,
+    },
+    // eg: x0, or x without the index.
+    UnknownVar {
+        pos: usize,
+    },
+    // eg: x5 with var_num 3.
+    VarNumMismatch {
+        pos: usize,
+        var: usize,
+        var_num: usize,
+    },
+    // the integer overflows a u64, or the hex is not a Scalar.
+    InvalidNumber {
+        pos: usize,
+    },
+    UnexpectedToken {
+        pos: usize,
+    },
+    UnexpectedEnd {
+        pos: usize,
+    },
+}
+
+impl TermParseError {
+    pub fn pos(&self) -> usize {
+        match self {
+            TermParseError::SquaredVar { pos, .. }
+            | TermParseError::UnknownVar { pos }
+            | TermParseError::VarNumMismatch { pos, .. }
+            | TermParseError::InvalidNumber { pos }
+            | TermParseError::UnexpectedToken { pos }
+            | TermParseError::UnexpectedEnd { pos } => *pos,
+        }
+    }
+}
+
+impl fmt::Display for TermParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at {}: ", self.pos())?;
+        match self {
+            TermParseError::SquaredVar { var, .. } => {
+                write!(
+                    f,
+                    "x{} appears twice in a term, which is not multilinear",
+                    var
+                )
+            }
+            TermParseError::UnknownVar { .. } => write!(f, "unknown variable, expect x1..xv"),
+            TermParseError::VarNumMismatch { var, var_num, .. } => {
+                write!(f, "x{} is out of var_num {}", var, var_num)
+            }
+            TermParseError::InvalidNumber { .. } => {
+                write!(f, "the coeff is neither a u64 nor a hex Scalar")
+            }
+            TermParseError::UnexpectedToken { .. } => write!(f, "unexpected token"),
+            TermParseError::UnexpectedEnd { .. } => write!(f, "unexpected end"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TermParseError {}
+
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize,
+    var_num: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&mut self) -> Option<u8> {
+        while self.s.as_bytes().get(self.pos) == Some(&b' ') {
+            self.pos += 1;
+        }
+        self.s.as_bytes().get(self.pos).cloned()
+    }
+
+    // the bytes from pos while f holds, pos is moved after them.
+    fn take_while(&mut self, f: impl Fn(u8) -> bool) -> &'a str {
+        let start = self.pos;
+        while self.s.as_bytes().get(self.pos).is_some_and(|b| f(*b)) {
+            self.pos += 1;
+        }
+        &self.s[start..self.pos]
+    }
+
+    fn unexpected(&mut self) -> TermParseError {
+        match self.peek() {
+            Some(_) => TermParseError::UnexpectedToken { pos: self.pos },
+            None => TermParseError::UnexpectedEnd { pos: self.pos },
+        }
+    }
+
+    // term = factor ('*' factor)*, returns (coeff, index) of the term.
+    fn term(&mut self) -> Result<(Scalar, usize), TermParseError> {
+        let mut coeff = Scalar::one();
+        let mut index = 0;
+        loop {
+            let pos = match self.peek() {
+                Some(_) => self.pos,
+                None => return Err(self.unexpected()),
+            };
+            if self.s[pos..].starts_with("0x") {
+                self.pos += 2;
+                let hex = self.take_while(|b| b.is_ascii_hexdigit());
+                coeff *= from_hex(hex).map_err(|_| TermParseError::InvalidNumber { pos })?;
+            } else if self.s.as_bytes()[pos].is_ascii_digit() {
+                let digits = self.take_while(|b| b.is_ascii_digit());
+                let n = digits
+                    .parse::<u64>()
+                    .map_err(|_| TermParseError::InvalidNumber { pos })?;
+                coeff *= Scalar::from(n);
+            } else if self.s.as_bytes()[pos] == b'x' {
+                self.pos += 1;
+                let var = self
+                    .take_while(|b| b.is_ascii_digit())
+                    .parse::<usize>()
+                    .map_err(|_| TermParseError::UnknownVar { pos })?;
+                if var == 0 {
+                    return Err(TermParseError::UnknownVar { pos });
+                }
+                if var > self.var_num {
+                    return Err(TermParseError::VarNumMismatch {
+                        pos,
+                        var,
+                        var_num: self.var_num,
+                    });
+                }
+                // x1 is the MSB of the index, the same as `SmallExpVec`.
+                let bit = 1 << (self.var_num - var);
+                if index & bit != 0 {
+                    return Err(TermParseError::SquaredVar { pos, var });
+                }
+                index |= bit;
+            } else {
+                return Err(self.unexpected());
+            }
+
+            if self.peek() != Some(b'*') {
+                return Ok((coeff, index));
+            }
+            self.pos += 1;
+        }
+    }
+}
+
+impl MPolynomial {
+    // Parse the term string, eg: "5 + 2*x3 + 3*x2 + x1*x2*x3" with var_num 3 is
+    //      coeffs = [5, 2, 3, 0, 0, 0, 0, 1]
+    pub fn parse(s: &str, var_num: usize) -> Result<Self, TermParseError> {
+        let mut parser = Parser { s, pos: 0, var_num };
+        let mut coeffs = vec![Scalar::zero(); 1 << var_num];
+
+        let mut neg = parser.peek() == Some(b'-');
+        if neg {
+            parser.pos += 1;
+        }
+        loop {
+            let (coeff, index) = parser.term()?;
+            if neg {
+                coeffs[index] -= coeff;
+            } else {
+                coeffs[index] += coeff;
+            }
+            neg = match parser.peek() {
+                None => break,
+                Some(b'+') => false,
+                Some(b'-') => true,
+                Some(_) => return Err(parser.unexpected()),
+            };
+            parser.pos += 1;
+        }
+        Ok(Self { var_num, coeffs })
+    }
+
+    // The readable Display, which `parse` takes back, eg: "2 - x2 + 2*x1 - x1*x2".
+    pub fn to_term_string(&self) -> String {
+        TermString(self).to_string()
+    }
+}
+
+struct TermString<'a>(&'a MPolynomial);
+
+impl fmt::Display for TermString<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.write_terms(f, |f, k, coeff, is_const| {
+            let (neg, abs) = match to_u64(coeff) {
+                Some(n) => (false, Some(n)),
+                None => match to_u64(&-coeff) {
+                    Some(n) => (true, Some(n)),
+                    None => (false, None),
+                },
+            };
+            match (k, neg) {
+                (0, true) => write!(f, "-")?,
+                (0, false) => {}
+                (_, true) => write!(f, " - ")?,
+                (_, false) => write!(f, " + ")?,
+            }
+            match abs {
+                // the 1 of 1*x1 is dropped
+                Some(1) if !is_const => Ok(false),
+                Some(n) => write!(f, "{}", n).map(|_| true),
+                None => write!(f, "0x{}", to_hex(coeff)).map(|_| true),
+            }
+        })
+    }
+}
+
+/// Build a `MPolynomial` from the term string, and panic with the position of a parse error.
+///
+/// ```
+/// use sumcheck::{mpoly, scalars};
+///
+/// let g = mpoly!("5 + 2*x3 + 3*x2 + x1*x2*x3", vars = 3);
+/// assert_eq!(g.coeffs, scalars![5, 2, 3, 0, 0, 0, 0, 1]);
+/// assert_eq!(mpoly!("-x1*x2 + 2", vars = 2).coeffs, scalars![2, 0, 0, -1]);
+/// ```
+#[macro_export]
+macro_rules! mpoly {
+    ($s:expr, vars = $var_num:expr) => {
+        $crate::poly::multivar_poly::MPolynomial::parse($s, $var_num)
+            .unwrap_or_else(|e| panic!("invalid term string {:?}, {}", $s, e))
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use crate::poly::multivar_poly::{MPolynomial, TermParseError};
+    use crate::scalars;
+    use bls12_381::Scalar;
+    use ff::Field;
+    use rand_core::OsRng;
+
+    #[test]
+    fn test_parse() {
+        // the same coeffs as by the hypercube index
+        assert_eq!(
+            mpoly!("5 + 2*x3 + 3*x2 + x1*x2*x3", vars = 3).coeffs,
+            scalars![5, 2, 3, 0, 0, 0, 0, 1]
+        );
+        assert_eq!(
+            mpoly!("2 - x2 + 2*x1 - x1*x2", vars = 2).coeffs,
+            scalars![2, -1, 2, -1]
+        );
+        // the order of the factors and the terms doesn't matter, and the same terms are summed
+        assert_eq!(
+            mpoly!("x2*x1*3 + 1 + x1*x2", vars = 2),
+            mpoly!("1 + 4*x1*x2", vars = 2)
+        );
+        assert_eq!(mpoly!("-3", vars = 0).coeffs, scalars![-3]);
+        assert_eq!(mpoly!("x1 - x1", vars = 1).coeffs, scalars![0, 0]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let parse = |s: &str, var_num: usize| MPolynomial::parse(s, var_num).unwrap_err();
+
+        assert_eq!(
+            parse("x1*x1", 2),
+            TermParseError::SquaredVar { pos: 3, var: 1 }
+        );
+        assert_eq!(
+            parse("1 + x5", 3),
+            TermParseError::VarNumMismatch {
+                pos: 4,
+                var: 5,
+                var_num: 3
+            }
+        );
+        assert_eq!(parse("5 + x0", 3), TermParseError::UnknownVar { pos: 4 });
+        // a stray token, a missing factor and a missing term
+        assert_eq!(
+            parse("5 + 2*x3 $ x1", 3),
+            TermParseError::UnexpectedToken { pos: 9 }
+        );
+        assert_eq!(
+            parse("5 + * x1", 3),
+            TermParseError::UnexpectedToken { pos: 4 }
+        );
+        assert_eq!(parse("5 + 2*", 3), TermParseError::UnexpectedEnd { pos: 6 });
+        assert_eq!(parse("", 3), TermParseError::UnexpectedEnd { pos: 0 });
+        assert_eq!(
+            parse("18446744073709551616*x1", 3),
+            TermParseError::InvalidNumber { pos: 0 }
+        );
+
+        assert_eq!(
+            parse("5 + 2*x3 $ x1", 3).to_string(),
+            "at 9: unexpected token"
+        );
+    }
+
+    #[test]
+    fn test_to_term_string() {
+        assert_eq!(
+            mpoly!("5 + 2*x3 + 3*x2 + x1*x2*x3", vars = 3).to_term_string(),
+            "5 + 2*x3 + 3*x2 + x1*x2*x3"
+        );
+        assert_eq!(
+            mpoly!("-1 - x2 + 2*x1 - 7*x1*x2", vars = 2).to_term_string(),
+            "-1 - x2 + 2*x1 - 7*x1*x2"
+        );
+        assert_eq!(mpoly!("1 - 1", vars = 2).to_term_string(), "0");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let fixtures = [
+            "5 + 2*x3 + 3*x2 + x1*x2*x3",
+            "9 + 2*x3 + 3*x2 + 2*x1*x2 + 4*x1*x2*x3",
+            "-x1 + 1",
+            "0",
+        ];
+        for s in fixtures {
+            let poly = mpoly!(s, vars = 3);
+            let printed = poly.to_term_string();
+            assert_eq!(mpoly!(&printed, vars = 3), poly);
+            assert_eq!(mpoly!(&printed, vars = 3).to_term_string(), printed);
+        }
+
+        // the random coeffs are printed in hex
+        let var_num = 4;
+        let poly = MPolynomial {
+            var_num,
+            coeffs: (0..1 << var_num).map(|_| Scalar::random(OsRng)).collect(),
+        };
+        let printed = poly.to_term_string();
+        assert_eq!(MPolynomial::parse(&printed, var_num), Ok(poly));
+    }
+}
@@ -0,0 +1,209 @@
+use crate::poly::multivar_poly::MPolynomial;
+use alloc::vec;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+
+// A dense multivariate polynomial whose degree in each variable is at most `degree`, which covers
+// the cases `MPolynomial` can't represent, eg: g(x1) = x1^2 + 1.
+//
+// The exps (e_1, ..., e_v) of a term are the digits of its index in base (degree + 1), and x_1 is
+// the most significant one, the same order as the binary index of `MPolynomial`.
+//      index = ∑ e_i * (degree + 1)^(v - i)
+// So `MPolynomial` is the special case degree = 1.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DenseUVPerVarPolynomial {
+    pub var_num: usize,
+    pub degree: usize, // the max degree of each variable
+    pub coeffs: Vec<Scalar>,
+}
+
+impl DenseUVPerVarPolynomial {
+    pub fn zero(var_num: usize, degree: usize) -> Self {
+        Self {
+            var_num,
+            degree,
+            coeffs: vec![Scalar::zero(); (degree + 1).pow(var_num as u32)],
+        }
+    }
+
+    pub fn from_coeffs(var_num: usize, degree: usize, coeffs: Vec<Scalar>) -> Self {
+        assert_eq!(
+            coeffs.len(),
+            (degree + 1).pow(var_num as u32),
+            "coeffs len must be (degree + 1)^var_num"
+        );
+        Self {
+            var_num,
+            degree,
+            coeffs,
+        }
+    }
+
+    // Only the nonzero terms are moved, by their exps.
+    pub fn from_mpoly(poly: &MPolynomial) -> Self {
+        let mut res = Self::zero(poly.var_num, 1);
+        for term in poly.terms() {
+            let index = res.index(&term.exponents.to_vec());
+            res.coeffs[index] = *term.coeff;
+        }
+        res
+    }
+
+    // the exps (e_1, ..., e_v) of the term at index
+    fn exps(&self, index: usize) -> Vec<usize> {
+        let base = self.degree + 1;
+        let mut exps = (0..self.var_num)
+            .scan(index, |rest, _| {
+                let e = *rest % base;
+                *rest /= base;
+                Some(e)
+            })
+            .collect::<Vec<_>>();
+        exps.reverse();
+        exps
+    }
+
+    fn index(&self, exps: &[usize]) -> usize {
+        let base = self.degree + 1;
+        exps.iter().fold(0, |acc, e| {
+            assert!(*e <= self.degree);
+            acc * base + e
+        })
+    }
+
+    // Re-encode with a larger per-variable degree.
+    pub fn with_degree(&self, degree: usize) -> Self {
+        assert!(degree >= self.degree);
+        let mut res = Self::zero(self.var_num, degree);
+        for (i, c) in self.coeffs.iter().enumerate() {
+            let index = res.index(&self.exps(i));
+            res.coeffs[index] = *c;
+        }
+        res
+    }
+
+    pub fn add(&self, rhs: &Self) -> Self {
+        assert_eq!(self.var_num, rhs.var_num, "var_num mismatch");
+        let degree = self.degree.max(rhs.degree);
+        let mut res = self.with_degree(degree);
+        for (l, r) in res.coeffs.iter_mut().zip(rhs.with_degree(degree).coeffs) {
+            *l += r;
+        }
+        res
+    }
+
+    // The degrees of each variable are added, so it never fails.
+    pub fn mul(&self, rhs: &Self) -> Self {
+        assert_eq!(self.var_num, rhs.var_num, "var_num mismatch");
+        let mut res = Self::zero(self.var_num, self.degree + rhs.degree);
+        for (n, l) in self.coeffs.iter().enumerate() {
+            if l == &Scalar::zero() {
+                continue;
+            }
+            let l_exps = self.exps(n);
+            for (m, r) in rhs.coeffs.iter().enumerate() {
+                if r == &Scalar::zero() {
+                    continue;
+                }
+                let exps = l_exps
+                    .iter()
+                    .zip(rhs.exps(m))
+                    .map(|(a, b)| a + b)
+                    .collect::<Vec<_>>();
+                let index = res.index(&exps);
+                res.coeffs[index] += l * r;
+            }
+        }
+        res
+    }
+
+    pub fn evaluate(&self, point: &[Scalar]) -> Scalar {
+        assert_eq!(point.len(), self.var_num, "Domain is less than var_num");
+        // Horner on x_1 first, as x_1 is the most significant digit:
+        //      g = ∑ x_1^e * g_e(x_2, ..., x_v)
+        let mut evals = self.coeffs.clone();
+        for x in point.iter().rev() {
+            evals = evals
+                .chunks(self.degree + 1)
+                .map(|chunk| chunk.iter().rev().fold(Scalar::zero(), |acc, c| acc * x + c))
+                .collect();
+        }
+        evals[0]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mpoly;
+    use crate::poly::multivar_poly::MPolynomial;
+    use crate::poly::per_var_poly::DenseUVPerVarPolynomial;
+    use bls12_381::Scalar;
+    use ff::PrimeField;
+
+    #[test]
+    fn test_square_plus_one() {
+        // x^2 + 1, from x * x
+        let x_mpoly = mpoly!("x1", vars = 1);
+        assert_eq!(
+            x_mpoly,
+            MPolynomial {
+                var_num: 1,
+                coeffs: vec![Scalar::zero(), Scalar::one()],
+            }
+        );
+        let x = DenseUVPerVarPolynomial::from_mpoly(&x_mpoly);
+        let one = DenseUVPerVarPolynomial::from_coeffs(1, 0, vec![Scalar::one()]);
+        let poly = x.mul(&x).add(&one);
+
+        assert_eq!(poly.degree, 2);
+        assert_eq!(poly.coeffs, vec![Scalar::one(), Scalar::zero(), Scalar::one()]);
+        for v in [0u128, 1, 2, 5, 1000] {
+            assert_eq!(
+                poly.evaluate(&[Scalar::from_u128(v)]),
+                Scalar::from_u128(v * v + 1)
+            );
+        }
+    }
+
+    #[test]
+    fn test_mul_and_evaluate() {
+        // f(x1, x2) = 5 + 2*x2 + 3*x1*x2, g(x1, x2) = 1 + x1
+        let (f_mpoly, g_mpoly) = (
+            mpoly!("5 + 2*x2 + 3*x1*x2", vars = 2),
+            mpoly!("1 + x1", vars = 2),
+        );
+        assert_eq!(
+            f_mpoly.coeffs,
+            vec![
+                Scalar::from_u128(5),
+                Scalar::from_u128(2),
+                Scalar::zero(),
+                Scalar::from_u128(3),
+            ]
+        );
+        assert_eq!(
+            g_mpoly.coeffs,
+            vec![Scalar::one(), Scalar::zero(), Scalar::one(), Scalar::zero()]
+        );
+        let f = DenseUVPerVarPolynomial::from_mpoly(&f_mpoly);
+        let g = DenseUVPerVarPolynomial::from_mpoly(&g_mpoly);
+
+        // f * g * g has degree 3 in x1
+        let product = f.mul(&g).mul(&g);
+        for (x1, x2) in [(0u128, 0u128), (1, 0), (2, 3), (7, 11)] {
+            let point = [Scalar::from_u128(x1), Scalar::from_u128(x2)];
+            let f_v = 5 + 2 * x2 + 3 * x1 * x2;
+            let g_v = 1 + x1;
+            assert_eq!(
+                product.evaluate(&point),
+                Scalar::from_u128(f_v * g_v * g_v)
+            );
+        }
+
+        // MPolynomial is the special case degree = 1
+        assert_eq!(
+            f.evaluate(&[Scalar::from_u128(3), Scalar::from_u128(2)]),
+            Scalar::from_u128(5 + 4 + 18)
+        );
+    }
+}
@@ -0,0 +1,416 @@
+// The algebraic identities of the polys on random inputs, which catch the bugs between the
+// operations, eg: the trailing zeros left by a sub break a later div_rem.
+//
+//      cargo test -p sumcheck --features proptests
+//
+// Each identity runs on its own seeds, and a failure reports the seed, which reruns alone by
+//      POLY_SEED=<seed> cargo test -p sumcheck --features proptests <identity>
+//
+// The random polys have degree in [0, 64) and var_num in [0, 8), with zero coeffs and trailing
+// zeros on purpose, so the constant (var_num = 0) and single-variable cases are covered too.
+// The lagrange round trip stops at var_num 5 too.
+use crate::poly::eval_table::EvalTable;
+use crate::poly::multivar_poly::MPolynomial;
+use crate::poly::univar_poly::Polynomial;
+use bls12_381::Scalar;
+
+const CASES: u64 = 256;
+// for the identities on MPolynomial, which are O(4^v).
+const MPOLY_CASES: u64 = 64;
+const MAX_DEGREE: u64 = 64;
+const MAX_VAR_NUM: u64 = 8;
+
+macro_rules! ensure {
+    ($cond:expr, $($arg:tt)+) => {
+        if !$cond {
+            return Err(format!($($arg)+));
+        }
+    };
+}
+
+// SplitMix64, so that a seed is all it takes to rebuild a case.
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+
+    // 0 and the small ones are more likely than uniform, as they hit the edge cases.
+    fn scalar(&mut self) -> Scalar {
+        match self.below(4) {
+            0 => Scalar::zero(),
+            1 => Scalar::from(self.below(16)),
+            _ => Scalar::from_raw([
+                self.next_u64(),
+                self.next_u64(),
+                self.next_u64(),
+                self.next_u64(),
+            ]),
+        }
+    }
+
+    fn nonzero_scalar(&mut self) -> Scalar {
+        let s = self.scalar();
+        if s == Scalar::zero() {
+            Scalar::one()
+        } else {
+            s
+        }
+    }
+
+    // a poly of degree < 64, a quarter of them has some trailing zeros.
+    fn poly(&mut self) -> Polynomial {
+        let len = 1 + self.below(MAX_DEGREE) as usize;
+        let mut coeffs = (0..len).map(|_| self.scalar()).collect::<Vec<_>>();
+        if self.below(4) == 0 {
+            coeffs.resize(len + 1 + self.below(4) as usize, Scalar::zero());
+        }
+        Polynomial::from_coeffs(coeffs)
+    }
+
+    // a nonzero divisor, half of them untrimmed: with 1 to 4 zeros above the leading term, eg:
+    // as left by a sub which cancels the leading terms.
+    fn divisor(&mut self) -> Polynomial {
+        let mut d = self.poly();
+        let lead = d.coeffs.len() - 1;
+        d.coeffs[lead] = self.nonzero_scalar();
+        if self.below(2) == 0 {
+            let len = d.coeffs.len() + 1 + self.below(4) as usize;
+            d.coeffs.resize(len, Scalar::zero());
+        }
+        d
+    }
+
+    fn var_num(&mut self, min: u64, max: u64) -> usize {
+        (min + self.below(max - min)) as usize
+    }
+
+    // about half of the coeffs are zero, so that some products are multilinear.
+    fn mpoly(&mut self, var_num: usize) -> MPolynomial {
+        let coeffs = (0..1 << var_num)
+            .map(|_| match self.below(2) {
+                0 => Scalar::zero(),
+                _ => self.scalar(),
+            })
+            .collect();
+        MPolynomial { var_num, coeffs }
+    }
+
+    // the small points only, as `MPolynomial::evaluate` computes the products in usize.
+    fn point(&mut self, var_num: usize) -> Vec<usize> {
+        (0..var_num).map(|_| self.below(4) as usize).collect()
+    }
+}
+
+// Run the identity on `cases` seeds, or on POLY_SEED only if it's set.
+fn check(name: &str, cases: u64, identity: impl Fn(&mut Rng) -> Result<(), String>) {
+    // FNV-1a of the name, so each identity has its own seeds.
+    let base = name.bytes().fold(0xcbf29ce484222325u64, |h, b| {
+        (h ^ b as u64).wrapping_mul(0x100000001b3)
+    });
+    let seeds =
+        match std::env::var("POLY_SEED") {
+            Ok(seed) => vec![u64::from_str_radix(seed.trim_start_matches("0x"), 16)
+                .expect("POLY_SEED must be hex")],
+            Err(_) => (0..cases).map(|i| base.wrapping_add(i)).collect(),
+        };
+    for seed in seeds {
+        if let Err(e) = identity(&mut Rng(seed)) {
+            panic!(
+                "{} failed with seed {:#x}: {}\n\trerun it by POLY_SEED={:#x}",
+                name, seed, e, seed
+            );
+        }
+    }
+}
+
+// The coeffs without the trailing zeros, the zero poly is [].
+fn normalize(p: &Polynomial) -> Vec<Scalar> {
+    let mut coeffs = p.coeffs.clone();
+    while coeffs.last() == Some(&Scalar::zero()) {
+        coeffs.pop();
+    }
+    coeffs
+}
+
+fn same(a: &Polynomial, b: &Polynomial) -> bool {
+    normalize(a) == normalize(b)
+}
+
+fn prop_div_rem(
+    rng: &mut Rng,
+    div_rem: fn(&Polynomial, &Polynomial) -> (Polynomial, Polynomial),
+) -> Result<(), String> {
+    let (a, d) = (rng.poly(), rng.divisor());
+    let (q, r) = div_rem(&a, &d);
+    let (a_len, d_len) = (a.coeffs.len(), d.coeffs.len());
+    ensure!(
+        same(&(&(&q * &d) + &r), &a),
+        "q * d + r != a, len(a) = {}, len(d) = {}",
+        a_len,
+        d_len
+    );
+    // deg(r) < deg(d), or r = 0 for a constant d.
+    ensure!(
+        normalize(&r).len() < normalize(&d).len(),
+        "deg(r) >= deg(d), len(a) = {}, len(d) = {}",
+        a_len,
+        d_len
+    );
+    Ok(())
+}
+
+#[test]
+fn add_is_associative() {
+    check("add_is_associative", CASES, |rng| {
+        let (a, b, c) = (rng.poly(), rng.poly(), rng.poly());
+        ensure!(
+            same(&(&(&a + &b) + &c), &(&a + &(&b + &c))),
+            "(a+b)+c != a+(b+c)"
+        );
+        ensure!(same(&(&(&a - &b) + &b), &a), "(a-b)+b != a");
+        Ok(())
+    });
+}
+
+#[test]
+fn mul_distributes_over_add() {
+    check("mul_distributes_over_add", CASES, |rng| {
+        let (a, b, c) = (rng.poly(), rng.poly(), rng.poly());
+        ensure!(
+            same(&(&a * &(&b + &c)), &(&(&a * &b) + &(&a * &c))),
+            "a*(b+c) != a*b + a*c"
+        );
+        let s = rng.scalar();
+        ensure!(
+            same(&(&a * s), &(&a * &Polynomial::constant(s))),
+            "a*s != a*[s]"
+        );
+        Ok(())
+    });
+}
+
+#[test]
+fn evaluate_is_homomorphic() {
+    check("evaluate_is_homomorphic", CASES, |rng| {
+        let (a, b, x) = (rng.poly(), rng.poly(), rng.scalar());
+        let (a_x, b_x) = (a.evaluate(x), b.evaluate(x));
+        ensure!((&a * &b).evaluate(x) == a_x * b_x, "(a*b)(x) != a(x)*b(x)");
+        ensure!((&a + &b).evaluate(x) == a_x + b_x, "(a+b)(x) != a(x)+b(x)");
+        ensure!((&a - &b).evaluate(x) == a_x - b_x, "(a-b)(x) != a(x)-b(x)");
+        Ok(())
+    });
+}
+
+#[test]
+fn div_rem_reconstructs() {
+    check("div_rem_reconstructs", CASES, |rng| {
+        prop_div_rem(rng, Polynomial::div_rem)
+    });
+}
+
+// The suite must catch a div_rem, which takes the len of the divisor before trimming it.
+#[test]
+#[should_panic(expected = "div_rem_catches_untrimmed_divisor failed with seed")]
+fn div_rem_catches_untrimmed_divisor() {
+    fn buggy_div_rem(a: &Polynomial, divisor: &Polynomial) -> (Polynomial, Polynomial) {
+        let d = normalize(divisor);
+        let lead_inv = d.last().unwrap().invert().unwrap();
+        let mut rem = a.coeffs.clone();
+        // the bug: the trailing zeros of the divisor shorten the quotient.
+        let q_len = (rem.len() + 1).saturating_sub(divisor.coeffs.len());
+        let mut quotient = vec![Scalar::zero(); q_len.max(1)];
+        for i in (0..q_len).rev() {
+            let q_i = rem[i + d.len() - 1] * lead_inv;
+            quotient[i] = q_i;
+            for (j, d_j) in d.iter().enumerate() {
+                rem[i + j] -= q_i * d_j;
+            }
+        }
+        rem.truncate(core::cmp::max(d.len() - 1, 1));
+        (
+            Polynomial::from_coeffs(quotient),
+            Polynomial::from_coeffs(rem),
+        )
+    }
+
+    check("div_rem_catches_untrimmed_divisor", CASES, |rng| {
+        prop_div_rem(rng, buggy_div_rem)
+    });
+}
+
+#[test]
+fn compose_round_trips() {
+    check("compose_round_trips", CASES, |rng| {
+        let (a, c, x) = (rng.poly(), rng.nonzero_scalar(), rng.scalar());
+        // a(c * X) and a(X + c)
+        let scaled = a.scale_arg(c);
+        let shifted = a.shift_arg(c);
+        ensure!(
+            scaled.evaluate(x) == a.evaluate(c * x),
+            "a(cX) at x != a(c * x)"
+        );
+        ensure!(
+            shifted.evaluate(x) == a.evaluate(x + c),
+            "a(X+c) at x != a(x + c)"
+        );
+        ensure!(
+            same(&scaled.scale_arg(c.invert().unwrap()), &a),
+            "a(c * X / c) != a"
+        );
+        ensure!(same(&shifted.shift_arg(-c), &a), "a(X + c - c) != a");
+
+        // X^d * a(1/X) twice is a, if a(0) != 0.
+        let bound = a.coeffs.len() + rng.below(4) as usize;
+        if a.coeffs[0] != Scalar::zero() {
+            ensure!(
+                same(&a.reverse(bound).reverse(bound), &a),
+                "reverse twice != a"
+            );
+        }
+        Ok(())
+    });
+}
+
+#[test]
+fn mpoly_mul_is_product_or_not_multilinear() {
+    check(
+        "mpoly_mul_is_product_or_not_multilinear",
+        MPOLY_CASES,
+        |rng| {
+            let var_num = rng.var_num(0, MAX_VAR_NUM);
+            let (f, g) = (rng.mpoly(var_num), rng.mpoly(var_num));
+            // half of them splits the variables, so the product is multilinear.
+            let (f, g) = if rng.below(2) == 0 {
+                let mask = rng.below(1 << var_num) as usize;
+                let restrict = |p: MPolynomial, keep: usize| MPolynomial {
+                    var_num,
+                    coeffs: p
+                        .coeffs
+                        .iter()
+                        .enumerate()
+                        .map(|(i, c)| if i & !keep == 0 { *c } else { Scalar::zero() })
+                        .collect(),
+                };
+                (restrict(f, mask), restrict(g, !mask))
+            } else {
+                (f, g)
+            };
+
+            let nonzero = |p: &MPolynomial| {
+                p.coeffs
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, c)| **c != Scalar::zero())
+                    .map(|(i, _)| i)
+                    .collect::<Vec<_>>()
+            };
+            let (f_terms, g_terms) = (nonzero(&f), nonzero(&g));
+            let shared = f_terms.iter().any(|n| g_terms.iter().any(|m| n & m != 0));
+
+            match f.mul(&g) {
+                Ok(fg) => {
+                    ensure!(!shared, "f*g is multilinear with a shared variable");
+                    let x = rng.point(var_num);
+                    ensure!(
+                        fg.evaluate(&x) == f.evaluate(&x) * g.evaluate(&x),
+                        "(f*g)(x) != f(x)*g(x), var_num = {}",
+                        var_num
+                    );
+                }
+                Err(_) => ensure!(shared, "NotMultilinear without a shared variable"),
+            }
+            Ok(())
+        },
+    );
+}
+
+#[test]
+fn mpoly_partial_evaluate_is_consistent() {
+    check("mpoly_partial_evaluate_is_consistent", MPOLY_CASES, |rng| {
+        let var_num = rng.var_num(0, MAX_VAR_NUM);
+        let f = rng.mpoly(var_num);
+        let r = rng.point(var_num);
+
+        // g_1(0) + g_1(1) = H, and g_j(0) + g_j(1) = g_j-1(r_j-1), see the sum-check.
+        let mut claim = f.sum_all_evals();
+        for j in 0..var_num {
+            let g_j = f.partial_evaluate(&r[..j].to_vec());
+            ensure!(
+                g_j.evaluate(Scalar::zero()) + g_j.evaluate(Scalar::one()) == claim,
+                "round {} is not consistent, var_num = {}",
+                j + 1,
+                var_num
+            );
+            claim = g_j.evaluate(Scalar::from(r[j] as u64));
+        }
+        ensure!(
+            claim == f.evaluate(&r),
+            "g_v(r_v) != f(r), var_num = {}",
+            var_num
+        );
+        // all bound, it's the constant f(r).
+        ensure!(
+            f.partial_evaluate(&r) == Polynomial::constant(claim),
+            "f(r) isn't a constant poly, var_num = {}",
+            var_num
+        );
+        Ok(())
+    });
+}
+
+#[test]
+fn table_binds_commute() {
+    check("table_binds_commute", MPOLY_CASES, |rng| {
+        let var_num = rng.var_num(0, MAX_VAR_NUM);
+        let f = rng.mpoly(var_num);
+        let table = EvalTable::from(&f);
+        let x = rng.point(var_num);
+        let point = x
+            .iter()
+            .map(|x_i| Scalar::from(*x_i as u64))
+            .collect::<Vec<_>>();
+        ensure!(
+            table.evaluate(&point) == f.evaluate(&x),
+            "the table and f differ at x, var_num = {}",
+            var_num
+        );
+
+        // x_1 = a then x_v = b, or x_v = b then x_1 = a.
+        if var_num >= 2 {
+            let (a, b) = (rng.scalar(), rng.scalar());
+            let mut first_last = table.clone();
+            first_last.bind_first(a);
+            first_last.bind_last(b);
+            let mut last_first = table.clone();
+            last_first.bind_last(b);
+            last_first.bind_first(a);
+            ensure!(first_last == last_first, "the binds don't commute");
+        }
+        Ok(())
+    });
+}
+
+#[test]
+fn lagrange_round_trips() {
+    check("lagrange_round_trips", MPOLY_CASES, |rng| {
+        let var_num = rng.var_num(0, 6);
+        let f = rng.mpoly(var_num);
+        let table = EvalTable::from(&f);
+        ensure!(
+            MPolynomial::from(&table) == f,
+            "lagrange(evals(f)) != f, var_num = {}",
+            var_num
+        );
+        Ok(())
+    });
+}
@@ -0,0 +1,281 @@
+use crate::poly::univar_poly::Polynomial;
+use alloc::vec;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use core::fmt;
+
+// p(X) = ∑ c_i * X^(d_i), only the nonzero terms are kept, sorted by the degree d_i.
+//
+// eg: the vanishing poly of a subgroup of size n, X^n - 1, has 2 terms instead of n + 1 coeffs.
+// The zero polynomial has no term.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SparsePolynomial {
+    pub(crate) terms: Vec<(usize, Scalar)>,
+}
+
+// The sparse poly doesn't fit in the dense poly with the degree bound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DegreeTooLarge {
+    pub degree: usize,
+    pub bound: usize,
+}
+
+impl fmt::Display for DegreeTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "degree {} is larger than the bound {}",
+            self.degree, self.bound
+        )
+    }
+}
+
+impl SparsePolynomial {
+    // The terms are (degree, coeff) in any order, the same degrees are summed up.
+    pub fn from_terms(mut terms: Vec<(usize, Scalar)>) -> Self {
+        terms.sort_by_key(|(d, _)| *d);
+        let mut res: Vec<(usize, Scalar)> = Vec::with_capacity(terms.len());
+        for (d, c) in terms {
+            match res.last_mut() {
+                Some((last, sum)) if *last == d => *sum += c,
+                _ => res.push((d, c)),
+            }
+        }
+        res.retain(|(_, c)| c != &Scalar::zero());
+        Self { terms: res }
+    }
+
+    // coeff * X^degree
+    pub fn monomial(degree: usize, coeff: Scalar) -> Self {
+        Self::from_terms(vec![(degree, coeff)])
+    }
+
+    // X^n - 1, which is zero on the subgroup of size n.
+    pub fn vanishing(n: usize) -> Self {
+        assert!(n > 0, "the subgroup must not be empty");
+        Self::from_terms(vec![(0, -Scalar::one()), (n, Scalar::one())])
+    }
+
+    pub fn terms(&self) -> &[(usize, Scalar)] {
+        &self.terms
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    // The degree of the polynomial, which is 0 for the zero polynomial as the dense one.
+    pub fn degree(&self) -> usize {
+        self.terms.last().map_or(0, |(d, _)| *d)
+    }
+
+    // Each power is by squaring from the last one: x^(d_i) = x^(d_{i-1}) * x^(d_i - d_{i-1}).
+    pub fn evaluate(&self, x: Scalar) -> Scalar {
+        let (mut last, mut power) = (0, Scalar::one());
+        self.terms.iter().fold(Scalar::zero(), |acc, (d, c)| {
+            power *= x.pow_vartime(&[(d - last) as u64, 0, 0, 0]);
+            last = *d;
+            acc + c * power
+        })
+    }
+
+    // The dense coeffs with deg <= degree_bound.
+    pub fn to_dense(&self, degree_bound: usize) -> Result<Polynomial, DegreeTooLarge> {
+        let degree = self.degree();
+        if degree > degree_bound {
+            return Err(DegreeTooLarge {
+                degree,
+                bound: degree_bound,
+            });
+        }
+        let mut coeffs = vec![Scalar::zero(); degree + 1];
+        for (d, c) in self.terms.iter() {
+            coeffs[*d] = *c;
+        }
+        Ok(Polynomial::from_coeffs(coeffs))
+    }
+
+    // a * X^n + b with a != 0 and n > 0, eg: X^n - c.
+    pub(crate) fn as_binomial(&self) -> Option<(usize, Scalar, Scalar)> {
+        match self.terms[..] {
+            [(n, a)] if n > 0 => Some((n, a, Scalar::zero())),
+            [(0, b), (n, a)] => Some((n, a, b)),
+            _ => None,
+        }
+    }
+}
+
+// The zero coeffs are dropped.
+impl From<&Polynomial> for SparsePolynomial {
+    fn from(p: &Polynomial) -> Self {
+        let terms = p
+            .coeffs
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| *c != &Scalar::zero())
+            .map(|(d, c)| (d, *c))
+            .collect();
+        Self { terms }
+    }
+}
+
+impl core::ops::Add<&SparsePolynomial> for &SparsePolynomial {
+    type Output = SparsePolynomial;
+
+    fn add(self, rhs: &SparsePolynomial) -> Self::Output {
+        let mut terms = self.terms.clone();
+        terms.extend(rhs.terms.iter());
+        SparsePolynomial::from_terms(terms)
+    }
+}
+
+impl core::ops::Mul<&SparsePolynomial> for &SparsePolynomial {
+    type Output = SparsePolynomial;
+
+    fn mul(self, rhs: &SparsePolynomial) -> Self::Output {
+        let terms = self
+            .terms
+            .iter()
+            .flat_map(|(n, a)| rhs.terms.iter().map(move |(m, b)| (n + m, a * b)))
+            .collect();
+        SparsePolynomial::from_terms(terms)
+    }
+}
+
+// The product is dense, as each term shifts the whole dense poly.
+impl core::ops::Mul<&Polynomial> for &SparsePolynomial {
+    type Output = Polynomial;
+
+    fn mul(self, rhs: &Polynomial) -> Self::Output {
+        let mut coeffs = vec![Scalar::zero(); self.degree() + rhs.coeffs.len()];
+        for (n, a) in self.terms.iter() {
+            for (m, b) in rhs.coeffs.iter().enumerate() {
+                coeffs[n + m] += a * b;
+            }
+        }
+        Polynomial::from_coeffs(coeffs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ff::{Field, PrimeField};
+    use rand_core::{OsRng, RngCore};
+
+    fn random_sparse(max_degree: u32, num_terms: usize) -> SparsePolynomial {
+        let terms = (0..num_terms)
+            .map(|_| {
+                let d = (OsRng.next_u32() % (max_degree + 1)) as usize;
+                (d, Scalar::random(OsRng))
+            })
+            .collect();
+        SparsePolynomial::from_terms(terms)
+    }
+
+    fn is_normalized(p: &SparsePolynomial) -> bool {
+        p.terms.windows(2).all(|w| w[0].0 < w[1].0)
+            && p.terms.iter().all(|(_, c)| c != &Scalar::zero())
+    }
+
+    #[test]
+    fn test_evaluate() {
+        for _ in 0..20 {
+            let p = random_sparse(200, 6);
+            let dense = p.to_dense(200).unwrap();
+            let x = Scalar::random(OsRng);
+            assert_eq!(p.evaluate(x), dense.evaluate(x));
+            assert_eq!(SparsePolynomial::from(&dense), p);
+        }
+        assert_eq!(
+            SparsePolynomial::default().evaluate(Scalar::from(3)),
+            Scalar::zero()
+        );
+    }
+
+    #[test]
+    fn test_to_dense() {
+        let p = SparsePolynomial::monomial(5, Scalar::from(2));
+        assert_eq!(
+            p.to_dense(4),
+            Err(DegreeTooLarge {
+                degree: 5,
+                bound: 4
+            })
+        );
+        let mut coeffs = vec![Scalar::zero(); 6];
+        coeffs[5] = Scalar::from(2);
+        assert_eq!(p.to_dense(5), Ok(Polynomial::from_coeffs(coeffs)));
+    }
+
+    #[test]
+    fn test_vanishing() {
+        let (k, n) = (16, 1 << 16);
+        // ω = ROOT_OF_UNITY^(2^(S - k)), which is a n-th root of unity.
+        let mut omega = Scalar::ROOT_OF_UNITY;
+        for _ in k..Scalar::S {
+            omega = omega.square();
+        }
+        let z_h = SparsePolynomial::vanishing(n);
+        assert_eq!(z_h.terms().len(), 2);
+        assert_eq!(z_h.evaluate(omega), Scalar::zero());
+        assert_eq!(
+            z_h.evaluate(omega.pow_vartime(&[12345, 0, 0, 0])),
+            Scalar::zero()
+        );
+        assert_ne!(
+            z_h.evaluate(Scalar::MULTIPLICATIVE_GENERATOR),
+            Scalar::zero()
+        );
+    }
+
+    #[test]
+    fn test_normalized() {
+        let (a, b, c) = (Scalar::from(3), Scalar::from(5), Scalar::from(7));
+        let p = SparsePolynomial::from_terms(vec![(5, a), (1, b), (5, c), (3, Scalar::zero())]);
+        assert_eq!(p.terms(), &[(1, b), (5, a + c)]);
+
+        // X^n - 1 + 1 - X^n = 0
+        let z_h = SparsePolynomial::vanishing(8);
+        let neg = &z_h * &SparsePolynomial::monomial(0, -Scalar::one());
+        assert!((&z_h + &neg).is_zero());
+
+        for _ in 0..20 {
+            let (p, q) = (random_sparse(30, 5), random_sparse(30, 5));
+            assert!(is_normalized(&p));
+            assert!(is_normalized(&(&p + &q)));
+            assert!(is_normalized(&(&p * &q)));
+
+            // the products agree on a random point, and with the dense product
+            let x = Scalar::random(OsRng);
+            assert_eq!((&p * &q).evaluate(x), p.evaluate(x) * q.evaluate(x));
+            assert_eq!((&p + &q).evaluate(x), p.evaluate(x) + q.evaluate(x));
+            let dense = q.to_dense(30).unwrap();
+            assert_eq!((&p * &dense).evaluate(x), p.evaluate(x) * q.evaluate(x));
+        }
+    }
+
+    #[test]
+    fn test_div_rem_binomial() {
+        let f = Polynomial::from_coeffs((0..40).map(|_| Scalar::random(OsRng)).collect());
+        let c = Scalar::random(OsRng);
+        for divisor in [
+            SparsePolynomial::vanishing(8),
+            SparsePolynomial::from_terms(vec![(16, Scalar::one()), (0, -c)]),
+            SparsePolynomial::from_terms(vec![(3, Scalar::from(3)), (0, Scalar::from(5))]),
+            SparsePolynomial::monomial(7, c),
+            // deg(f) < n
+            SparsePolynomial::vanishing(64),
+        ] {
+            let dense = divisor.to_dense(64).unwrap();
+            assert_eq!(f.div_rem_sparse(&divisor), f.div_rem(&dense));
+        }
+
+        // not a binomial, which falls back to the generic div_rem.
+        let divisor = SparsePolynomial::from_terms(vec![(0, c), (2, c), (5, Scalar::one())]);
+        assert_eq!(
+            f.div_rem_sparse(&divisor),
+            f.div_rem(&divisor.to_dense(5).unwrap())
+        );
+    }
+}
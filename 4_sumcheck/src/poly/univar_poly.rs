@@ -1,31 +1,69 @@
+use crate::poly::sparse_poly::SparsePolynomial;
+use crate::utils::{axpy, scale};
+use alloc::vec;
+use alloc::vec::Vec;
 use bls12_381::Scalar;
-use ff::BatchInvert;
+use ff::{BatchInvert, PrimeField};
+#[cfg(feature = "std")]
 use rayon::{current_num_threads, scope};
 
 // p(x) = = a_0 + a_1 * X + ... + a_n * X^(n-1)
 //
 // coeffs: [a_0, a_1, ..., a_n]
 // basis: X^[n-1]
+//
+// The zero poly is [0], and the operators always return the trimmed coeffs, which have no
+// trailing zeros, eg: (1 + X) + (1 - X) = [2] rather than [2, 0].
 #[derive(Debug, Clone, Eq, PartialEq)]
-pub struct Polynomial {
-    pub(crate) coeffs: Vec<Scalar>,
+pub struct PolynomialOver<F> {
+    pub(crate) coeffs: Vec<F>,
 }
 
-impl Polynomial {
-    pub fn from_coeffs(coeffs: Vec<Scalar>) -> Self {
+// The polys of the protocol crates, which are over the scalar field of BLS12-381.
+pub type Polynomial = PolynomialOver<Scalar>;
+
+impl<F: PrimeField> PolynomialOver<F> {
+    pub fn from_coeffs(coeffs: Vec<F>) -> Self {
         Self { coeffs }
     }
 
+    // The canonical zero poly: [0].
+    pub fn zero() -> Self {
+        Self::constant(F::ZERO)
+    }
+
+    // p(X) = c
+    pub fn constant(c: F) -> Self {
+        Self { coeffs: vec![c] }
+    }
+
+    // p(X) = X
+    pub fn x() -> Self {
+        Self {
+            coeffs: vec![F::ZERO, F::ONE],
+        }
+    }
+
+    // It's zero whatever the trailing zeros, eg: [0, 0] from `from_coeffs`.
+    pub fn is_zero(&self) -> bool {
+        self.coeffs.iter().all(|c| c == &F::ZERO)
+    }
+
     // The degree of the polynomial
     pub fn degree(&self) -> usize {
         assert!(self.coeffs.len() > 0);
         self.coeffs.len() - 1
     }
 
-    pub fn coeffs(&self) -> Vec<Scalar> {
+    pub fn coeffs(&self) -> Vec<F> {
         self.coeffs.clone()
     }
 
+    // The coeffs as stored, untrimmed and maybe none, eg: of a decoded proof.
+    pub fn num_coeffs(&self) -> usize {
+        self.coeffs.len()
+    }
+
     // p(x)=∑y_j⋅L_j(X), where
     // y_j: [a_0, a_1, ..., a_n].
     // basis: L_j(X)=∏(X−x_k)/(x_j−x_k)
@@ -35,7 +73,7 @@ impl Polynomial {
     //
     // we can use encode points as (domain, eval) to polynomials
     // the poly
-    pub fn lagrange_interpolate(domains: Vec<Scalar>, evals: Vec<Scalar>) -> Self {
+    pub fn lagrange_interpolate(domains: Vec<F>, evals: Vec<F>) -> Self {
         assert_eq!(domains.len(), evals.len());
 
         if evals.len() == 1 {
@@ -70,11 +108,11 @@ impl Polynomial {
                 .batch_invert();
 
             // 2. Calculate  L_j(X) : L_j(X)=∏(X−x_k) divisors_j
-            let mut L_j_vec: Vec<Vec<Scalar>> = Vec::with_capacity(poly_size);
+            let mut l_j_vec: Vec<Vec<F>> = Vec::with_capacity(poly_size);
 
             for (j, divisor_j) in divisors.into_iter().enumerate() {
-                let mut L_j: Vec<Scalar> = Vec::with_capacity(poly_size);
-                L_j.push(Scalar::one());
+                let mut l_j: Vec<F> = Vec::with_capacity(poly_size);
+                l_j.push(F::ONE);
 
                 // (X−x_k) * divisors_j
                 let mut product = Vec::with_capacity(lag_basis_poly_size);
@@ -87,33 +125,33 @@ impl Polynomial {
                     .map(|(_, x)| x)
                     .zip(divisor_j.into_iter())
                 {
-                    product.resize(L_j.len() + 1, Scalar::zero());
+                    product.resize(l_j.len() + 1, F::ZERO);
 
                     // loop (poly_size + 1) round
                     // calculate L_j(X)=∏(X−x_k) divisors_j with coefficient form.
-                    for ((a, b), product) in L_j
+                    for ((a, b), product) in l_j
                         .iter()
-                        .chain(std::iter::once(&Scalar::zero()))
-                        .zip(std::iter::once(&Scalar::zero()).chain(L_j.iter()))
+                        .chain(core::iter::once(&F::ZERO))
+                        .zip(core::iter::once(&F::ZERO).chain(l_j.iter()))
                         .zip(product.iter_mut())
                     {
                         *product = *a * (-divisor * x_k) + *b * divisor;
                     }
-                    std::mem::swap(&mut L_j, &mut product);
+                    core::mem::swap(&mut l_j, &mut product);
                 }
 
-                assert_eq!(L_j.len(), poly_size);
+                assert_eq!(l_j.len(), poly_size);
                 assert_eq!(product.len(), poly_size - 1);
 
-                L_j_vec.push(L_j);
+                l_j_vec.push(l_j);
             }
 
             // p(x)=∑y_j⋅L_j(X) in coefficients
-            let mut final_poly = vec![Scalar::zero(); poly_size];
+            let mut final_poly = vec![F::ZERO; poly_size];
             // 3. p(x)=∑y_j⋅L_j(X)
-            for (L_j, y_j) in L_j_vec.iter().zip(evals) {
-                for (final_coeff, L_j_coeff) in final_poly.iter_mut().zip(L_j.into_iter()) {
-                    *final_coeff += L_j_coeff * y_j;
+            for (l_j, y_j) in l_j_vec.iter().zip(evals) {
+                for (final_coeff, l_j_coeff) in final_poly.iter_mut().zip(l_j.into_iter()) {
+                    *final_coeff += *l_j_coeff * y_j;
                 }
             }
             Self { coeffs: final_poly }
@@ -121,93 +159,267 @@ impl Polynomial {
     }
 
     // This evaluates a polynomial (in coefficient form) at `x`.
-    pub fn evaluate(&self, x: Scalar) -> Scalar {
-        let coeffs = self.coeffs.clone();
-        let poly_size = self.coeffs.len();
-
+    pub fn evaluate(&self, x: F) -> F {
         // p(x) = = a_0 + a_1 * X + ... + a_n * X^(n-1), revert it and fold sum it
-        fn eval(poly: &[Scalar], point: Scalar) -> Scalar {
+        fn eval<F: PrimeField>(poly: &[F], point: F) -> F {
             poly.iter()
                 .rev()
-                .fold(Scalar::zero(), |acc, coeff| acc * point + coeff)
+                .fold(F::ZERO, |acc, coeff| acc * point + coeff)
         }
 
-        let num_threads = current_num_threads();
-        if poly_size * 2 < num_threads {
-            eval(&coeffs, x)
-        } else {
-            let chunk_size = (poly_size + num_threads - 1) / num_threads;
-            let mut parts = vec![Scalar::zero(); num_threads];
-            scope(|scope| {
-                for (chunk_idx, (out, c)) in parts
-                    .chunks_mut(1)
-                    .zip(coeffs.chunks(chunk_size))
-                    .enumerate()
-                {
-                    scope.spawn(move |_| {
-                        let start = chunk_idx * chunk_size;
-                        out[0] = eval(c, x) * x.pow_vartime(&[start as u64, 0, 0, 0]);
-                    });
-                }
-            });
-            parts.iter().fold(Scalar::zero(), |acc, coeff| acc + coeff)
+        // rayon needs std, so the no_std build always takes the serial path.
+        #[cfg(feature = "std")]
+        {
+            let poly_size = self.coeffs.len();
+            let num_threads = current_num_threads();
+            if poly_size * 2 >= num_threads {
+                let chunk_size = poly_size.div_ceil(num_threads);
+                let mut parts = vec![F::ZERO; num_threads];
+                scope(|scope| {
+                    for (chunk_idx, (out, c)) in parts
+                        .chunks_mut(1)
+                        .zip(self.coeffs.chunks(chunk_size))
+                        .enumerate()
+                    {
+                        scope.spawn(move |_| {
+                            let start = chunk_idx * chunk_size;
+                            out[0] = eval(c, x) * x.pow_vartime(&[start as u64, 0, 0, 0]);
+                        });
+                    }
+                });
+                return parts.iter().fold(F::ZERO, |acc, coeff| acc + coeff);
+            }
+        }
+        eval(&self.coeffs, x)
+    }
+    // Long division of polynomials: self = q * divisor + r, with deg(r) < deg(divisor).
+    // Returns (q, r). The leading zero coeffs of divisor are ignored.
+    pub fn div_rem(&self, divisor: &Self) -> (Self, Self) {
+        let mut d = divisor.coeffs.clone();
+        while d.len() > 1 && d.last() == Some(&F::ZERO) {
+            d.pop();
+        }
+        let lead = *d.last().expect("divisor must not be empty");
+        assert!(lead != F::ZERO, "Can't divide by the zero polynomial");
+
+        if self.coeffs.len() < d.len() {
+            return (Self::zero(), self.clone());
+        }
+
+        let lead_inv = lead.invert().unwrap();
+        let mut rem = self.coeffs.clone();
+        let mut quotient = vec![F::ZERO; rem.len() - d.len() + 1];
+        // eliminate the highest term of rem in each round.
+        for i in (0..quotient.len()).rev() {
+            let q_i = rem[i + d.len() - 1] * lead_inv;
+            quotient[i] = q_i;
+            for (j, d_j) in d.iter().enumerate() {
+                rem[i + j] -= q_i * d_j;
+            }
         }
+        rem.truncate(core::cmp::max(d.len() - 1, 1));
+
+        (Self::from_coeffs(quotient), Self::from_coeffs(rem))
+    }
+
+    // Remove the trailing zero coeffs, the zero polynomial is kept as [0].
+    fn trimmed(mut coeffs: Vec<F>) -> Self {
+        while coeffs.len() > 1 && coeffs.last() == Some(&F::ZERO) {
+            coeffs.pop();
+        }
+        if coeffs.is_empty() {
+            coeffs.push(F::ZERO);
+        }
+        Self { coeffs }
+    }
+
+    // p(c * X): a_i -> a_i * c^i
+    // eg: move a poly between the subgroup H and its coset c * H.
+    pub fn scale_arg(&self, c: F) -> Self {
+        let mut power = F::ONE;
+        let coeffs = self
+            .coeffs
+            .iter()
+            .map(|a_i| {
+                let res = *a_i * power;
+                power *= c;
+                res
+            })
+            .collect::<Vec<_>>();
+        Self::trimmed(coeffs)
+    }
+
+    // p(X + c) = ∑ a_i * (X + c)^i
+    pub fn shift_arg(&self, c: F) -> Self {
+        Self::trimmed(shift_coeffs(&self.coeffs, c))
+    }
+
+    // X^d * p(1/X), where d = degree_bound >= deg(p):
+    // pad the coeffs to d + 1, then reverse them.
+    pub fn reverse(&self, degree_bound: usize) -> Self {
+        let mut coeffs = Self::trimmed(self.coeffs.clone()).coeffs;
+        assert!(
+            coeffs.len() <= degree_bound + 1,
+            "poly degree is larger than the degree bound"
+        );
+        coeffs.resize(degree_bound + 1, F::ZERO);
+        coeffs.reverse();
+        Self::trimmed(coeffs)
+    }
+
+    // p = c·p in place, the coeffs are trimmed like `Mul<&F>`.
+    pub fn scale_in_place(&mut self, c: F) {
+        scale(&mut self.coeffs, c);
+        *self = Self::trimmed(core::mem::take(&mut self.coeffs));
+    }
+
+    // p += c·q in place, p is extended with zeros if q is longer.
+    pub fn axpy(&mut self, c: F, other: &Self) {
+        if self.coeffs.len() < other.coeffs.len() {
+            self.coeffs.resize(other.coeffs.len(), F::ZERO);
+        }
+        axpy(&mut self.coeffs[..other.coeffs.len()], c, &other.coeffs);
     }
 }
 
-impl std::ops::Mul<&Polynomial> for &Polynomial {
-    type Output = Polynomial;
-    fn mul(self, rhs: &Polynomial) -> Self::Output {
-        let mut coeffs: Vec<Scalar> =
-            vec![Scalar::zero(); self.coeffs.len() + rhs.coeffs.len() - 1];
+// The sparse polys are over Scalar only, eg: the vanishing poly of the LDT domain.
+impl Polynomial {
+    // The same as `div_rem`, with a fast path for the binomial divisor a * X^n + b,
+    // eg: the vanishing poly X^n - 1, which costs O(deg) instead of O(deg * n).
+    pub fn div_rem_sparse(&self, divisor: &SparsePolynomial) -> (Polynomial, Polynomial) {
+        let (n, a, b) = match divisor.as_binomial() {
+            Some(binomial) => binomial,
+            None => {
+                let dense = divisor.to_dense(divisor.degree()).unwrap();
+                return self.div_rem(&dense);
+            }
+        };
+        if self.coeffs.len() < n + 1 {
+            return (Polynomial::zero(), self.clone());
+        }
+
+        // eliminate the highest term of rem in each round, which only touches rem[i].
+        let lead_inv = a.invert().unwrap();
+        let mut rem = self.coeffs.clone();
+        let mut quotient = vec![Scalar::zero(); rem.len() - n];
+        for i in (0..quotient.len()).rev() {
+            let q_i = rem[i + n] * lead_inv;
+            quotient[i] = q_i;
+            rem[i] -= q_i * b;
+        }
+        rem.truncate(n);
+
+        (Polynomial::from_coeffs(quotient), Polynomial::from_coeffs(rem))
+    }
+}
+
+// The coeffs of p(X + c), by Horner's rule on (X + c):
+//      p(X + c) = (...((a_n * (X + c) + a_{n-1}) * (X + c) + ...) + a_0
+// which costs O(d^2). It's the only place to replace with the FFT-based Taylor shift.
+fn shift_coeffs<F: PrimeField>(coeffs: &[F], c: F) -> Vec<F> {
+    let mut res = vec![F::ZERO; coeffs.len()];
+    for (k, a_k) in coeffs.iter().enumerate().rev() {
+        // res = res * (X + c) + a_k, where deg(res) < len - k - 1
+        let len = coeffs.len() - k;
+        for j in (1..len).rev() {
+            res[j] = res[j - 1] + res[j] * c;
+        }
+        res[0] = res[0] * c + a_k;
+    }
+    res
+}
+
+impl<F: PrimeField> core::ops::Mul<&PolynomialOver<F>> for &PolynomialOver<F> {
+    type Output = PolynomialOver<F>;
+    fn mul(self, rhs: &PolynomialOver<F>) -> Self::Output {
+        // eg: the empty coeffs from `from_coeffs(vec![])`, which would underflow the len.
+        if self.coeffs.is_empty() || rhs.coeffs.is_empty() {
+            return PolynomialOver::zero();
+        }
+        let mut coeffs: Vec<F> = vec![F::ZERO; self.coeffs.len() + rhs.coeffs.len() - 1];
         for n in 0..self.coeffs.len() {
             for m in 0..rhs.coeffs.len() {
                 coeffs[n + m] += self.coeffs[n] * rhs.coeffs[m];
             }
         }
-        Self::Output { coeffs }
+        PolynomialOver::trimmed(coeffs)
     }
 }
 
-impl std::ops::Mul<&Scalar> for &Polynomial {
-    type Output = Polynomial;
-    fn mul(self, rhs: &Scalar) -> Self::Output {
-        let coeffs = if rhs == &Scalar::zero() {
-            vec![Scalar::zero()]
-        } else {
-            self.coeffs.iter().map(|c| c * rhs).collect::<Vec<Scalar>>()
-        };
-        Self::Output { coeffs }
+impl<F: PrimeField> core::ops::Mul<&F> for &PolynomialOver<F> {
+    type Output = PolynomialOver<F>;
+    fn mul(self, rhs: &F) -> Self::Output {
+        PolynomialOver::trimmed(self.coeffs.iter().map(|c| *c * rhs).collect::<Vec<F>>())
     }
 }
 
-impl std::ops::Add<&Polynomial> for &Polynomial {
-    type Output = Polynomial;
+impl<F: PrimeField> core::ops::Add<&PolynomialOver<F>> for &PolynomialOver<F> {
+    type Output = PolynomialOver<F>;
 
-    fn add(self, rhs: &Polynomial) -> Self::Output {
-        let max_len = std::cmp::max(self.coeffs.len(), rhs.coeffs.len());
+    fn add(self, rhs: &PolynomialOver<F>) -> Self::Output {
+        let max_len = core::cmp::max(self.coeffs.len(), rhs.coeffs.len());
         let coeffs = (0..max_len)
-            .into_iter()
             .map(|n| {
-                if n >= self.coeffs.len() {
-                    rhs.coeffs[n]
-                } else if n >= rhs.coeffs.len() {
-                    self.coeffs[n]
-                } else {
-                    // n < self.0.len() && n < rhs.0.len()
-                    self.coeffs[n] + rhs.coeffs[n]
-                }
+                let lhs = self.coeffs.get(n).cloned().unwrap_or(F::ZERO);
+                let rhs = rhs.coeffs.get(n).cloned().unwrap_or(F::ZERO);
+                lhs + rhs
             })
-            .collect::<Vec<Scalar>>();
-        Self::Output { coeffs }
+            .collect::<Vec<F>>();
+        PolynomialOver::trimmed(coeffs)
     }
 }
 
+impl<F: PrimeField> core::ops::Sub<&PolynomialOver<F>> for &PolynomialOver<F> {
+    type Output = PolynomialOver<F>;
+
+    fn sub(self, rhs: &PolynomialOver<F>) -> Self::Output {
+        let max_len = core::cmp::max(self.coeffs.len(), rhs.coeffs.len());
+        let coeffs = (0..max_len)
+            .map(|n| {
+                let lhs = self.coeffs.get(n).cloned().unwrap_or(F::ZERO);
+                let rhs = rhs.coeffs.get(n).cloned().unwrap_or(F::ZERO);
+                lhs - rhs
+            })
+            .collect::<Vec<F>>();
+        PolynomialOver::trimmed(coeffs)
+    }
+}
+
+// The owned operands forward to the `&a op &b` impls above, eg: `p * q`, `p + &q`, `&p - q`.
+macro_rules! forward_binop {
+    ($op:ident, $method:ident, $rhs:ty) => {
+        impl<F: PrimeField> core::ops::$op<$rhs> for PolynomialOver<F> {
+            type Output = PolynomialOver<F>;
+            fn $method(self, rhs: $rhs) -> Self::Output {
+                core::ops::$op::$method(&self, &rhs)
+            }
+        }
+
+        impl<F: PrimeField> core::ops::$op<&$rhs> for PolynomialOver<F> {
+            type Output = PolynomialOver<F>;
+            fn $method(self, rhs: &$rhs) -> Self::Output {
+                core::ops::$op::$method(&self, rhs)
+            }
+        }
+
+        impl<F: PrimeField> core::ops::$op<$rhs> for &PolynomialOver<F> {
+            type Output = PolynomialOver<F>;
+            fn $method(self, rhs: $rhs) -> Self::Output {
+                core::ops::$op::$method(self, &rhs)
+            }
+        }
+    };
+}
+
+forward_binop!(Add, add, PolynomialOver<F>);
+forward_binop!(Sub, sub, PolynomialOver<F>);
+forward_binop!(Mul, mul, PolynomialOver<F>);
+forward_binop!(Mul, mul, F);
+
 #[cfg(test)]
 mod test {
     use super::*;
     use ff::PrimeField;
-    use std::ops::{Add, Mul};
 
     #[test]
     fn test_mul_poly() {
@@ -221,17 +433,243 @@ mod test {
         };
 
         assert_eq!(
-            p.mul(&q).coeffs,
+            (&p * &q).coeffs,
             vec![Scalar::one(), Scalar::zero(), Scalar::one().neg()]
         );
 
-        // add
-        assert_eq!(p.add(&q).coeffs, vec![Scalar::from_u128(2), Scalar::zero()]);
+        // add, the trailing zero is trimmed
+        assert_eq!((&p + &q).coeffs, vec![Scalar::from_u128(2)]);
 
         // poly.mul(scalar)
         assert_eq!(
-            p.mul(&Scalar::from_u128(5)).coeffs,
+            (&p * Scalar::from_u128(5)).coeffs,
             vec![Scalar::from_u128(5), Scalar::from_u128(5).neg()]
         );
     }
+
+    #[test]
+    fn test_div_rem() {
+        // p = (x^2 + 1) * (x - 3) + 5
+        let divisor = Polynomial::from_coeffs(vec![Scalar::from_u128(3).neg(), Scalar::one()]);
+        let q = Polynomial::from_coeffs(vec![Scalar::one(), Scalar::zero(), Scalar::one()]);
+        let r = Polynomial::from_coeffs(vec![Scalar::from_u128(5)]);
+        let p = &(&q * &divisor) + &r;
+
+        let (actual_q, actual_r) = p.div_rem(&divisor);
+        assert_eq!(actual_q, q);
+        assert_eq!(actual_r, r);
+
+        // exact division leaves a zero remainder.
+        let (actual_q, actual_r) = (&q * &divisor).div_rem(&divisor);
+        assert_eq!(actual_q, q);
+        assert_eq!(actual_r.coeffs, vec![Scalar::zero()]);
+
+        // p - q * divisor == r
+        assert_eq!((&p - &(&q * &divisor)).coeffs[0], r.coeffs[0]);
+    }
+
+    fn random_poly(degree: usize) -> Polynomial {
+        use ff::Field;
+        use rand_core::OsRng;
+        let mut coeffs = (0..=degree)
+            .map(|_| Scalar::random(OsRng))
+            .collect::<Vec<_>>();
+        // make sure the degree is exact
+        if coeffs[degree] == Scalar::zero() {
+            coeffs[degree] = Scalar::one();
+        }
+        Polynomial::from_coeffs(coeffs)
+    }
+
+    #[test]
+    fn test_scale_and_shift_arg() {
+        use ff::Field;
+        use rand_core::OsRng;
+
+        let p = random_poly(10);
+        let c = Scalar::random(OsRng);
+        let scaled = p.scale_arg(c);
+        let shifted = p.shift_arg(c);
+        for _ in 0..20 {
+            let x = Scalar::random(OsRng);
+            assert_eq!(scaled.evaluate(x), p.evaluate(c * x));
+            assert_eq!(shifted.evaluate(x), p.evaluate(x + c));
+        }
+
+        // (X + 1)^2 = 1 + 2X + X^2
+        let p = Polynomial::from_coeffs(vec![Scalar::zero(), Scalar::zero(), Scalar::one()]);
+        assert_eq!(
+            p.shift_arg(Scalar::one()).coeffs,
+            vec![Scalar::one(), Scalar::from_u128(2), Scalar::one()]
+        );
+
+        // p(0 * X) = p(0)
+        let p = random_poly(10);
+        assert_eq!(p.scale_arg(Scalar::zero()).coeffs, vec![p.coeffs[0]]);
+
+        // the zero poly, and the trailing zeros are trimmed.
+        let zero = Polynomial::from_coeffs(vec![Scalar::zero(); 3]);
+        assert_eq!(zero.scale_arg(c).coeffs, vec![Scalar::zero()]);
+        assert_eq!(zero.shift_arg(c).coeffs, vec![Scalar::zero()]);
+    }
+
+    #[test]
+    fn test_reverse() {
+        let p = random_poly(10);
+        assert_eq!(p.reverse(10).reverse(10), p);
+
+        // X^3 * (1 + 2X) at 1/X, with degree bound 3: X^3 + 2X^2
+        let p = Polynomial::from_coeffs(vec![Scalar::one(), Scalar::from_u128(2), Scalar::zero()]);
+        assert_eq!(
+            p.reverse(3).coeffs,
+            vec![
+                Scalar::zero(),
+                Scalar::zero(),
+                Scalar::from_u128(2),
+                Scalar::one()
+            ]
+        );
+
+        let zero = Polynomial::from_coeffs(vec![Scalar::zero()]);
+        assert_eq!(zero.reverse(4), zero);
+    }
+
+    #[test]
+    fn test_axpy_and_scale() {
+        use ff::Field;
+        use rand_core::OsRng;
+
+        let c = Scalar::random(OsRng);
+
+        // the same as p + c·q, with p extended when q is longer
+        for (n, m) in [(4, 4), (4, 2), (2, 4)] {
+            let (p, q) = (random_poly(n), random_poly(m));
+            let mut res = p.clone();
+            res.axpy(c, &q);
+            assert_eq!(res, &p + &(&q * &c));
+        }
+
+        let mut p = random_poly(4);
+        let expected = &p * &c;
+        p.scale_in_place(c);
+        assert_eq!(p, expected);
+        p.scale_in_place(Scalar::zero());
+        assert_eq!(p, Polynomial::zero());
+    }
+
+    // p * q, p + q, p - q on the coeffs as u64, then trimmed and mapped to Scalar.
+    fn model(p: &[u64], q: &[u64], op: &str) -> Polynomial {
+        let len = match op {
+            "*" => p.len() + q.len() - 1,
+            _ => core::cmp::max(p.len(), q.len()),
+        };
+        let mut res = vec![Scalar::zero(); len];
+        for (n, res_n) in res.iter_mut().enumerate() {
+            let (a, b) = (
+                p.get(n).cloned().unwrap_or(0),
+                q.get(n).cloned().unwrap_or(0),
+            );
+            *res_n = match op {
+                "+" => Scalar::from(a + b),
+                "-" => Scalar::from(a) - Scalar::from(b),
+                _ => (0..=n)
+                    .filter(|m| *m < p.len() && n - m < q.len())
+                    .map(|m| Scalar::from(p[m] * q[n - m]))
+                    .sum(),
+            };
+        }
+        while res.len() > 1 && res.last() == Some(&Scalar::zero()) {
+            res.pop();
+        }
+        Polynomial::from_coeffs(res)
+    }
+
+    // all the coeffs in {0, 1, 2} up to degree 2, with the trailing zeros, eg: [1, 0, 0].
+    fn small_coeffs() -> Vec<Vec<u64>> {
+        let mut all = vec![];
+        for len in 1..=3 {
+            for i in 0..3u64.pow(len as u32) {
+                all.push((0..len).map(|j| i / 3u64.pow(j as u32) % 3).collect());
+            }
+        }
+        all
+    }
+
+    #[test]
+    fn test_ops_table() {
+        let to_poly =
+            |c: &[u64]| Polynomial::from_coeffs(c.iter().map(|c| Scalar::from(*c)).collect());
+        let all = small_coeffs();
+        for p in all.iter() {
+            for q in all.iter() {
+                let (a, b) = (to_poly(p), to_poly(q));
+                assert_eq!(&a + &b, model(p, q, "+"), "{:?} + {:?}", p, q);
+                assert_eq!(&a - &b, model(p, q, "-"), "{:?} - {:?}", p, q);
+                assert_eq!(&a * &b, model(p, q, "*"), "{:?} * {:?}", p, q);
+            }
+            // the scalar is the constant poly
+            for c in 0..3u64 {
+                assert_eq!(&to_poly(p) * Scalar::from(c), model(p, &[c], "*"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_zero_and_constant() {
+        let zero = Polynomial::zero();
+        let two = Polynomial::constant(Scalar::from_u128(2));
+        let p = random_poly(4);
+
+        assert_eq!(&p - &p, zero);
+        assert_eq!(&p * &zero, zero);
+        assert_eq!(&zero * &p, zero);
+        assert_eq!(&p + &zero, p);
+        assert_eq!(&p * Scalar::zero(), zero);
+        // the untrimmed and the empty zero
+        assert_eq!(&p * &Polynomial::from_coeffs(vec![]), zero);
+        assert_eq!(
+            &Polynomial::from_coeffs(vec![Scalar::zero(); 3]) + &zero,
+            zero
+        );
+        assert!(Polynomial::from_coeffs(vec![Scalar::zero(); 3]).is_zero());
+
+        // degree 0 stays degree 0
+        assert_eq!((&two * &two).coeffs, vec![Scalar::from_u128(4)]);
+        assert_eq!((&two - &two).degree(), 0);
+
+        // 1 + 2X + X^2 = (X + 1)^2
+        let x_plus_1 = Polynomial::x() + Polynomial::constant(Scalar::one());
+        assert_eq!(
+            (&x_plus_1 * &x_plus_1).coeffs,
+            vec![Scalar::one(), Scalar::from_u128(2), Scalar::one()]
+        );
+    }
+
+    // every operand is taken both owned and borrowed, so the borrows are the point.
+    #[test]
+    #[allow(clippy::op_ref)]
+    fn test_ownership_combos() {
+        let (p, q) = (random_poly(3), random_poly(5));
+        let c = Scalar::from_u128(7);
+
+        let sum = &p + &q;
+        assert_eq!(p.clone() + q.clone(), sum);
+        assert_eq!(p.clone() + &q, sum);
+        assert_eq!(&p + q.clone(), sum);
+
+        let diff = &p - &q;
+        assert_eq!(p.clone() - q.clone(), diff);
+        assert_eq!(p.clone() - &q, diff);
+        assert_eq!(&p - q.clone(), diff);
+
+        let prod = &p * &q;
+        assert_eq!(p.clone() * q.clone(), prod);
+        assert_eq!(p.clone() * &q, prod);
+        assert_eq!(&p * q.clone(), prod);
+
+        let scaled = &p * &c;
+        assert_eq!(p.clone() * c, scaled);
+        assert_eq!(p.clone() * &c, scaled);
+        assert_eq!(&p * c, scaled);
+    }
 }
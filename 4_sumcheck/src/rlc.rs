@@ -0,0 +1,348 @@
+// The random linear combination: to check many equalities a_k == b_k at once, V picks the coeffs
+// c_k and checks ∑ c_k·a_k == ∑ c_k·b_k, eg: the γ-combination of the codewords of the batched
+// LDT, of the claims of the batched sum-check, and x of Freivalds, which combines the columns.
+//
+// The coeffs are squeezed from the transcript in one of two modes, see `CoeffMode`:
+//      Powers:      c_k = γ^k of a single γ. The diffs e_k = a_k - b_k make ∑ γ^k·e_k a poly
+//                   of deg ≤ n-1 in γ, so a wrong batch passes with probability ≤ (n-1)/|F|.
+//      Independent: c_k are n scalars expanded from one seed, see
+//                   `Fiat_Shamir::Transcript::challenge_vector`. ∑ c_k·e_k is a nonzero linear
+//                   form in c, so a wrong batch passes with probability ≤ 1/|F|.
+// The powers cost one squeeze and no expansion, and let P and V compute the coeffs on the fly,
+// so both are kept, eg: the existing proofs of the batched LDT and sum-check use the powers.
+//
+// `SoundnessBudget` adds up the error bounds of the checks in a flow by the union bound, eg: the
+// (n-1)/|F| above, d/|F| of a sum-check round, and the queries of the LDT.
+use crate::poly::eval_table::EvalTable;
+use crate::utils::axpy;
+use alloc::vec;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use core::fmt;
+use Fiat_Shamir::Transcript;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RlcError {
+    // the items and the coeffs are of different lengths.
+    LengthMismatch { items: usize, coeffs: usize },
+    // the item has another length (or var_num) from the first one.
+    WidthMismatch { index: usize },
+    // no item, so the length of the combination is unknown.
+    Empty,
+}
+
+impl fmt::Display for RlcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RlcError::LengthMismatch { items, coeffs } => {
+                write!(f, "{} items against {} coeffs", items, coeffs)
+            }
+            RlcError::WidthMismatch { index } => {
+                write!(f, "Item {} has another length from the first one", index)
+            }
+            RlcError::Empty => write!(f, "Nothing to combine"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RlcError {}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CoeffMode {
+    // [1, γ, ..., γ^(n-1)], the error is (n-1)/|F|.
+    #[default]
+    Powers,
+    // n independent scalars, the error is 1/|F|.
+    Independent,
+}
+
+impl CoeffMode {
+    // The numerator of the error over |F| for n items, see the modes above.
+    pub fn error_numerator(&self, n: usize) -> u64 {
+        match self {
+            CoeffMode::Powers => n.saturating_sub(1) as u64,
+            CoeffMode::Independent => 1,
+        }
+    }
+}
+
+// The squeezes of the coeffs. Every `Fiat_Shamir::Transcript` is one, and the transcripts of the
+// other crates which wrap it forward to it, so the coeffs are the same bytes in every crate.
+pub trait CoeffSource {
+    fn squeeze_scalar(&mut self) -> Scalar;
+    fn squeeze_vector(&mut self, label: &str, n: usize) -> Vec<Scalar>;
+}
+
+impl<T: Transcript + ?Sized> CoeffSource for T {
+    fn squeeze_scalar(&mut self) -> Scalar {
+        self.challenge_scalar()
+    }
+
+    fn squeeze_vector(&mut self, label: &str, n: usize) -> Vec<Scalar> {
+        self.challenge_vector(label, n)
+    }
+}
+
+// n coeffs from the transcript. The powers squeeze γ as it is, which is bound by the absorbed
+// items already, so the label is absorbed by the independent coeffs only, eg: "freivalds.x".
+pub fn coeffs_from_transcript<T: CoeffSource + ?Sized>(
+    transcript: &mut T,
+    label: &str,
+    n: usize,
+    mode: CoeffMode,
+) -> Vec<Scalar> {
+    let challenges = squeeze_challenges(transcript, label, n, mode);
+    coeffs_from_challenges(mode, &challenges, n)
+}
+
+// The squeezed challenges of the coeffs: [γ] of the powers, or the n coeffs themselves, eg: what
+// a trace or a checkpoint of V records.
+pub fn squeeze_challenges<T: CoeffSource + ?Sized>(
+    transcript: &mut T,
+    label: &str,
+    n: usize,
+    mode: CoeffMode,
+) -> Vec<Scalar> {
+    match mode {
+        CoeffMode::Powers => vec![transcript.squeeze_scalar()],
+        CoeffMode::Independent => transcript.squeeze_vector(label, n),
+    }
+}
+
+// The n coeffs of the challenges of `squeeze_challenges`.
+pub fn coeffs_from_challenges(mode: CoeffMode, challenges: &[Scalar], n: usize) -> Vec<Scalar> {
+    match mode {
+        CoeffMode::Powers => powers(challenges[0], n),
+        CoeffMode::Independent => {
+            assert_eq!(challenges.len(), n, "a challenge per coeff");
+            challenges.to_vec()
+        }
+    }
+}
+
+// The number of challenges of n coeffs.
+pub fn challenges_len(mode: CoeffMode, n: usize) -> usize {
+    match mode {
+        CoeffMode::Powers => 1,
+        CoeffMode::Independent => n,
+    }
+}
+
+// [1, γ, ..., γ^(n-1)]
+pub fn powers(gamma: Scalar, n: usize) -> Vec<Scalar> {
+    let mut cur = Scalar::one();
+    (0..n)
+        .map(|_| {
+            let c = cur;
+            cur *= gamma;
+            c
+        })
+        .collect()
+}
+
+fn check_len(items: usize, coeffs: usize) -> Result<(), RlcError> {
+    if items != coeffs {
+        return Err(RlcError::LengthMismatch { items, coeffs });
+    }
+    Ok(())
+}
+
+// ∑ c_k·a_k
+pub fn combine_scalars(items: &[Scalar], coeffs: &[Scalar]) -> Result<Scalar, RlcError> {
+    check_len(items.len(), coeffs.len())?;
+    Ok(items.iter().zip(coeffs.iter()).map(|(a, c)| a * c).sum())
+}
+
+// ∑ c_k·v_k, entry by entry, eg: of `Vec<Scalar>` or the borrowed codewords.
+pub fn combine_vectors<V: AsRef<[Scalar]>>(
+    items: &[V],
+    coeffs: &[Scalar],
+) -> Result<Vec<Scalar>, RlcError> {
+    check_len(items.len(), coeffs.len())?;
+    let first = items.first().ok_or(RlcError::Empty)?.as_ref();
+    if let Some(index) = items.iter().position(|v| v.as_ref().len() != first.len()) {
+        return Err(RlcError::WidthMismatch { index });
+    }
+    let mut combined = vec![Scalar::zero(); first.len()];
+    for (v, c) in items.iter().zip(coeffs.iter()) {
+        axpy(&mut combined, *c, v.as_ref());
+    }
+    Ok(combined)
+}
+
+// ∑ c_k·W_k, the tables must share the var_num.
+pub fn combine_tables(items: &[EvalTable], coeffs: &[Scalar]) -> Result<EvalTable, RlcError> {
+    check_len(items.len(), coeffs.len())?;
+    let first = items.first().ok_or(RlcError::Empty)?;
+    if let Some(index) = items.iter().position(|t| t.var_num != first.var_num) {
+        return Err(RlcError::WidthMismatch { index });
+    }
+    let mut combined = EvalTable::new(vec![Scalar::zero(); first.evals.len()]);
+    for (t, c) in items.iter().zip(coeffs.iter()) {
+        combined.axpy(*c, t);
+    }
+    Ok(combined)
+}
+
+// p of BLS12-381, about 2^254.86, eg: the 1/|F| of a challenge which hits a root.
+pub const FIELD_SIZE: f64 = 5.243_587_517_512_619e76;
+
+// The soundness error of a flow, eg: the γ of the batched LDT, its folds and its queries. Each
+// check adds its bound, and the flow is sound except with probability `total()` by the union
+// bound.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SoundnessBudget {
+    terms: Vec<(&'static str, f64)>,
+}
+
+impl SoundnessBudget {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // A bound of any form, eg: ((1 + ρ)/2)^q of the LDT queries.
+    pub fn add(&mut self, name: &'static str, error: f64) {
+        assert!(
+            (0.0..=1.0).contains(&error),
+            "{} is not a probability",
+            error
+        );
+        self.terms.push((name, error));
+    }
+
+    // numerator / |F|, eg: d/|F| of a sum-check round.
+    pub fn add_field_error(&mut self, name: &'static str, numerator: u64) {
+        self.add(name, numerator as f64 / FIELD_SIZE);
+    }
+
+    // The combination of n items, see `CoeffMode::error_numerator`.
+    pub fn add_rlc(&mut self, name: &'static str, n: usize, mode: CoeffMode) {
+        self.add_field_error(name, mode.error_numerator(n));
+    }
+
+    pub fn terms(&self) -> &[(&'static str, f64)] {
+        &self.terms
+    }
+
+    // ∑ errors, at most 1.
+    pub fn total(&self) -> f64 {
+        self.terms.iter().map(|(_, e)| e).sum::<f64>().min(1.0)
+    }
+
+    // -log2(total), eg: 100 for 2^-100.
+    #[cfg(feature = "std")]
+    pub fn bits(&self) -> f64 {
+        -self.total().log2()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ff::Field;
+    use rand_core::OsRng;
+    use Fiat_Shamir::default::Keccak256Transcript;
+
+    fn random_scalars(n: usize) -> Vec<Scalar> {
+        (0..n).map(|_| Scalar::random(OsRng)).collect()
+    }
+
+    #[test]
+    fn test_combine_agrees_with_axpy() {
+        let items = (0..3).map(|_| random_scalars(8)).collect::<Vec<_>>();
+        let coeffs = powers(Scalar::from(7u64), 3);
+
+        // the hand-rolled loop of the batched LDT
+        let mut expected = vec![Scalar::zero(); 8];
+        let mut gamma_k = Scalar::one();
+        for v in items.iter() {
+            for (f, x) in expected.iter_mut().zip(v.iter()) {
+                *f += gamma_k * x;
+            }
+            gamma_k *= Scalar::from(7u64);
+        }
+        assert_eq!(combine_vectors(&items, &coeffs).unwrap(), expected);
+
+        let tables = items
+            .iter()
+            .cloned()
+            .map(EvalTable::new)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            combine_tables(&tables, &coeffs).unwrap(),
+            EvalTable::new(expected.clone())
+        );
+        for j in 0..8 {
+            let column = items.iter().map(|v| v[j]).collect::<Vec<_>>();
+            assert_eq!(combine_scalars(&column, &coeffs).unwrap(), expected[j]);
+        }
+    }
+
+    #[test]
+    fn test_length_mismatch() {
+        let coeffs = powers(Scalar::from(3u64), 2);
+        let items = vec![random_scalars(4); 3];
+        let mismatch = RlcError::LengthMismatch {
+            items: 3,
+            coeffs: 2,
+        };
+        assert_eq!(combine_scalars(&items[0][..3], &coeffs), Err(mismatch));
+        assert_eq!(combine_vectors(&items, &coeffs), Err(mismatch));
+        let tables = items
+            .iter()
+            .cloned()
+            .map(EvalTable::new)
+            .collect::<Vec<_>>();
+        assert_eq!(combine_tables(&tables, &coeffs), Err(mismatch));
+
+        let ragged = vec![random_scalars(4), random_scalars(2)];
+        assert_eq!(
+            combine_vectors(&ragged, &coeffs),
+            Err(RlcError::WidthMismatch { index: 1 })
+        );
+        let tables = ragged.into_iter().map(EvalTable::new).collect::<Vec<_>>();
+        assert_eq!(
+            combine_tables(&tables, &coeffs),
+            Err(RlcError::WidthMismatch { index: 1 })
+        );
+        assert_eq!(
+            combine_vectors::<Vec<Scalar>>(&[], &[]),
+            Err(RlcError::Empty)
+        );
+    }
+
+    #[test]
+    fn test_coeff_modes() {
+        let squeeze = |mode| {
+            let mut transcript = Keccak256Transcript::default();
+            coeffs_from_transcript(&mut transcript, "test.coeffs", 4, mode)
+        };
+        let (p, i) = (squeeze(CoeffMode::Powers), squeeze(CoeffMode::Independent));
+        assert_eq!(p, powers(p[1], 4));
+        assert_eq!(challenges_len(CoeffMode::Powers, 4), 1);
+        assert_ne!(i[0], Scalar::one());
+        assert_ne!(p, i);
+
+        // the same squeezes as the transcript itself.
+        let mut transcript = Keccak256Transcript::default();
+        assert_eq!(p[1], transcript.challenge_scalar());
+        let mut transcript = Keccak256Transcript::default();
+        assert_eq!(i, transcript.challenge_vector("test.coeffs", 4));
+    }
+
+    #[test]
+    fn test_soundness_budget() {
+        let mut budget = SoundnessBudget::new();
+        budget.add_rlc("powers", 1 << 20, CoeffMode::Powers);
+        budget.add_rlc("independent", 1 << 20, CoeffMode::Independent);
+        budget.add_field_error("round", 2);
+        assert_eq!(budget.terms().len(), 3);
+        // (2^20 - 1 + 1 + 2) / 2^254.86
+        assert!((budget.bits() - (254.857 - 20.0)).abs() < 0.01);
+
+        budget.add("queries", 0.5);
+        budget.add("queries", 0.75);
+        assert_eq!(budget.total(), 1.0);
+    }
+}
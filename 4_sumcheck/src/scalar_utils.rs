@@ -0,0 +1,223 @@
+// Readable Scalars for the tests: the Debug of Scalar is hard to compare against hand computations.
+//
+// The hex is the canonical 32 bytes in little-endian, the same as `Scalar::to_bytes`,
+// eg: 1 is "0100...00" and -1 = p - 1 is "00000000ffffffff...73".
+use alloc::format;
+use alloc::string::String;
+use bls12_381::Scalar;
+use core::fmt;
+use core::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+    WrongLength(usize), // the hex len, which should be 64
+    InvalidHex { offset: usize },
+    NonCanonical,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::WrongLength(len) => write!(f, "expect 64 hex chars, got {}", len),
+            ParseError::InvalidHex { offset } => write!(f, "invalid hex at {}", offset),
+            ParseError::NonCanonical => write!(f, "the value is not less than p"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+pub fn to_hex(s: &Scalar) -> String {
+    s.to_bytes().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn from_hex(hex: &str) -> Result<Scalar, ParseError> {
+    if hex.len() != 64 {
+        return Err(ParseError::WrongLength(hex.len()));
+    }
+    let mut bytes = [0u8; 32];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        let offset = 2 * i;
+        *byte = hex
+            .get(offset..offset + 2)
+            .and_then(|b| u8::from_str_radix(b, 16).ok())
+            .ok_or(ParseError::InvalidHex { offset })?;
+    }
+    from_le_bytes(&bytes)
+}
+
+// The canonical little-endian bytes, the same as `Scalar::to_bytes`.
+pub fn from_le_bytes(bytes: &[u8; 32]) -> Result<Scalar, ParseError> {
+    Option::from(Scalar::from_bytes(bytes)).ok_or(ParseError::NonCanonical)
+}
+
+// Scalar is a foreign type, so the wrapper carries the Display and FromStr in hex,
+// eg: `"01...00".parse::<HexScalar>()?.0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HexScalar(pub Scalar);
+
+impl fmt::Display for HexScalar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", to_hex(&self.0))
+    }
+}
+
+impl FromStr for HexScalar {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        from_hex(s).map(HexScalar)
+    }
+}
+
+// -x is mapped to p - x.
+pub fn from_i64(x: i64) -> Scalar {
+    let abs = Scalar::from(x.unsigned_abs());
+    if x < 0 {
+        -abs
+    } else {
+        abs
+    }
+}
+
+// The canonical value as a u64 if it fits, eg: 5, but not 2^64 or p - 5. The Scalar is never
+// truncated, so a value out of range is an error of the caller, eg: an index from a proof.
+pub fn to_u64(s: &Scalar) -> Option<u64> {
+    let bytes = s.to_bytes();
+    if bytes[8..].iter().any(|b| *b != 0) {
+        return None;
+    }
+    let mut low = [0u8; 8];
+    low.copy_from_slice(&bytes[..8]);
+    Some(u64::from_le_bytes(low))
+}
+
+// Same as `to_u64`, and it must fit in usize too, eg: a hypercube index.
+pub fn to_usize(s: &Scalar) -> Option<usize> {
+    to_u64(s).and_then(|v| usize::try_from(v).ok())
+}
+
+// A coordinate of a hypercube point, which is exactly 0 or 1.
+pub fn to_bit(s: &Scalar) -> Option<bool> {
+    match to_u64(s) {
+        Some(0) => Some(false),
+        Some(1) => Some(true),
+        _ => None,
+    }
+}
+
+/// Build a Vec<Scalar> from integers, the negatives are mapped to p - |x|.
+///
+/// ```
+/// use bls12_381::Scalar;
+/// use sumcheck::scalars;
+///
+/// let v = scalars![1, -2, 0];
+/// assert_eq!(v, vec![Scalar::one(), -Scalar::from(2), Scalar::zero()]);
+/// ```
+#[macro_export]
+macro_rules! scalars {
+    ($($x:expr),* $(,)?) => {
+        $crate::alloc::vec![$($crate::scalar_utils::from_i64($x)),*]
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use crate::scalar_utils::{
+        from_hex, from_i64, to_bit, to_hex, to_u64, to_usize, HexScalar, ParseError,
+    };
+    use bls12_381::Scalar;
+    use ff::Field;
+    use rand_core::OsRng;
+
+    #[test]
+    fn test_hex_round_trip() {
+        let p_minus_1 = Scalar::zero() - Scalar::one();
+        for s in [
+            Scalar::zero(),
+            Scalar::one(),
+            p_minus_1,
+            Scalar::random(OsRng),
+        ] {
+            assert_eq!(from_hex(&to_hex(&s)), Ok(s));
+        }
+        assert_eq!(
+            to_hex(&Scalar::one()),
+            "0100000000000000000000000000000000000000000000000000000000000000"
+        );
+        assert_eq!(
+            to_hex(&p_minus_1),
+            "00000000fffffffffe5bfeff02a4bd5305d8a10908d83933487d9d2953a7ed73"
+        );
+    }
+
+    #[test]
+    fn test_hex_scalar() {
+        let s = HexScalar(-Scalar::from(2));
+        assert_eq!(s.to_string().parse::<HexScalar>(), Ok(s));
+        assert_eq!("".parse::<HexScalar>(), Err(ParseError::WrongLength(0)));
+    }
+
+    #[test]
+    fn test_from_hex_rejects() {
+        assert_eq!(from_hex("01"), Err(ParseError::WrongLength(2)));
+        assert_eq!(from_hex(&"0".repeat(66)), Err(ParseError::WrongLength(66)));
+
+        let mut invalid = "0".repeat(64);
+        invalid.replace_range(6..8, "zz");
+        assert_eq!(
+            from_hex(&invalid),
+            Err(ParseError::InvalidHex { offset: 6 })
+        );
+
+        // p and 2^256 - 1
+        let p = "01000000fffffffffe5bfeff02a4bd5305d8a10908d83933487d9d2953a7ed73";
+        assert_eq!(from_hex(p), Err(ParseError::NonCanonical));
+        assert_eq!(from_hex(&"f".repeat(64)), Err(ParseError::NonCanonical));
+    }
+
+    #[test]
+    fn test_from_i64() {
+        assert_eq!(from_i64(-1), Scalar::zero() - Scalar::one());
+        assert_eq!(from_i64(0), Scalar::zero());
+        assert_eq!(from_i64(7), Scalar::from(7));
+        assert_eq!(from_i64(i64::MIN), -Scalar::from(1u64 << 63));
+    }
+
+    #[test]
+    fn test_to_u64() {
+        for v in [0, 1, 1u64 << 63, u64::MAX] {
+            assert_eq!(to_u64(&Scalar::from(v)), Some(v));
+        }
+        assert_eq!(to_usize(&Scalar::from(7)), Some(7));
+
+        // 2^64 and p - 1 are not truncated
+        let two_64 = Scalar::from(u64::MAX) + Scalar::one();
+        let p_minus_1 = Scalar::zero() - Scalar::one();
+        for s in [two_64, p_minus_1] {
+            assert_eq!(to_u64(&s), None);
+            assert_eq!(to_usize(&s), None);
+        }
+    }
+
+    #[test]
+    fn test_to_bit() {
+        assert_eq!(to_bit(&Scalar::zero()), Some(false));
+        assert_eq!(to_bit(&Scalar::one()), Some(true));
+        assert_eq!(to_bit(&Scalar::from(2)), None);
+        assert_eq!(to_bit(&-Scalar::one()), None);
+    }
+
+    #[test]
+    fn test_scalars_macro() {
+        assert_eq!(
+            scalars![5, -3, 0],
+            vec![Scalar::from(5), -Scalar::from(3), Scalar::zero()]
+        );
+        assert_eq!(scalars![1,], vec![Scalar::one()]);
+        let empty: Vec<Scalar> = scalars![];
+        assert!(empty.is_empty());
+    }
+}
@@ -0,0 +1,217 @@
+// The witness of the provers, eg: the wire values of a circuit, the tables being folded in the
+// sum-check and the codewords before they are opened.
+//
+// `Secret<T>` owns such a buffer, and with the `zeroize` feature it's `Zeroize` and
+// `ZeroizeOnDrop`: the bytes of the whole allocation (the spare capacity too, which holds the rest
+// of a table folded in place) are zeroized by the `zeroize` crate when it's dropped.
+// The proofs, commitments and the public inputs/outputs are never wrapped.
+//
+// Copies of the witness are explicit: `Secret::copy_of` (or `clone`) is the only way to duplicate
+// one, and it's counted in std builds by `witness_copies`, so the provers can be checked for
+// gratuitous clones. Moving the buffer out is not allowed, use it through `Deref` instead.
+use crate::poly::eval_table::{EvalTable, EvalTableOver};
+use crate::poly::multivar_poly::MPolynomialOver;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+use ff::Field;
+#[cfg(feature = "zeroize")]
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+// The types which keep the witness in a single Vec<F>.
+pub trait WitnessBuffer {
+    type Field: Field;
+
+    fn buffer_mut(&mut self) -> &mut Vec<Self::Field>;
+}
+
+impl<F: Field> WitnessBuffer for Vec<F> {
+    type Field = F;
+
+    fn buffer_mut(&mut self) -> &mut Vec<F> {
+        self
+    }
+}
+
+impl<F: Field> WitnessBuffer for EvalTableOver<F> {
+    type Field = F;
+
+    fn buffer_mut(&mut self) -> &mut Vec<F> {
+        &mut self.evals
+    }
+}
+
+impl<F: Field> WitnessBuffer for MPolynomialOver<F> {
+    type Field = F;
+
+    fn buffer_mut(&mut self) -> &mut Vec<F> {
+        &mut self.coeffs
+    }
+}
+
+pub struct Secret<T: WitnessBuffer>(T);
+
+// The table folded by the sum-check provers.
+pub type ZeroizingEvalTable = Secret<EvalTable>;
+
+impl<T: WitnessBuffer> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    pub fn copy_of(value: &T) -> Self
+    where
+        T: Clone,
+    {
+        #[cfg(feature = "std")]
+        COPIES.with(|copies| copies.set(copies.get() + 1));
+        Self(value.clone())
+    }
+}
+
+impl<T: WitnessBuffer + Clone> Clone for Secret<T> {
+    fn clone(&self) -> Self {
+        Self::copy_of(&self.0)
+    }
+}
+
+impl<T: WitnessBuffer + Default> Default for Secret<T> {
+    fn default() -> Self {
+        Self(T::default())
+    }
+}
+
+impl<T: WitnessBuffer> Deref for Secret<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: WitnessBuffer> DerefMut for Secret<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+// Never print the witness, eg: in the Debug of a prover state.
+impl<T: WitnessBuffer> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(..)")
+    }
+}
+
+// The buffer is left empty, as `Vec::zeroize` leaves it: all zero bytes needn't be a valid F,
+// eg: a Montgomery form with a nonzero offset.
+#[cfg(feature = "zeroize")]
+impl<T: WitnessBuffer> Zeroize for Secret<T> {
+    fn zeroize(&mut self) {
+        let buffer = self.0.buffer_mut();
+        allocation_bytes(buffer).zeroize();
+        buffer.clear();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<T: WitnessBuffer> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.zeroize();
+        #[cfg(test)]
+        test_hook::capture(allocation_bytes(self.0.buffer_mut()));
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<T: WitnessBuffer> ZeroizeOnDrop for Secret<T> {}
+
+// The bytes of the allocation of the buffer, up to its capacity.
+#[cfg(feature = "zeroize")]
+fn allocation_bytes<F: Field>(buffer: &mut Vec<F>) -> &mut [u8] {
+    let len = buffer.capacity() * core::mem::size_of::<F>();
+    // The allocation is len bytes, and u8 has no alignment nor invalid values. F is Copy, so it
+    // has no drop glue which would read the bytes as F.
+    unsafe { core::slice::from_raw_parts_mut(buffer.as_mut_ptr() as *mut u8, len) }
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    static COPIES: core::cell::Cell<usize> = const { core::cell::Cell::new(0) };
+}
+
+// The witness copies made by `Secret::copy_of` in the current thread.
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub fn witness_copies() -> usize {
+    COPIES.with(|copies| copies.get())
+}
+
+// Keeps what's left in the allocation right before it's freed.
+#[cfg(all(test, feature = "zeroize"))]
+mod test_hook {
+    use std::cell::RefCell;
+
+    std::thread_local! {
+        static DROPPED: RefCell<Vec<Vec<u8>>> = const { RefCell::new(Vec::new()) };
+    }
+
+    pub(super) fn capture(bytes: &[u8]) {
+        DROPPED.with(|dropped| dropped.borrow_mut().push(bytes.to_vec()));
+    }
+
+    pub(super) fn take() -> Vec<Vec<u8>> {
+        DROPPED.with(|dropped| dropped.take())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::poly::eval_table::EvalTable;
+    use crate::scalars;
+    use crate::secret::ZeroizingEvalTable;
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn test_table_is_cleared_on_drop() {
+        use crate::secret::test_hook;
+        use bls12_381::Scalar;
+
+        let mut table = ZeroizingEvalTable::new(EvalTable::new(scalars![1, 2, 3, 4, 5, 6, 7, 8]));
+        table.bind_first(Scalar::from(3));
+        // the folded half is still in the spare capacity.
+        assert_eq!((table.evals.len(), table.evals.capacity()), (4, 8));
+        test_hook::take();
+        drop(table);
+
+        let dropped = test_hook::take();
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(dropped[0], vec![0; 8 * core::mem::size_of::<Scalar>()]);
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn test_zeroize_leaves_it_empty() {
+        use zeroize::Zeroize;
+
+        let mut table = ZeroizingEvalTable::new(EvalTable::new(scalars![1, 2, 3, 4]));
+        table.zeroize();
+        assert!(table.evals.is_empty());
+        assert_eq!(table.evals.capacity(), 4);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_copies_are_counted() {
+        use crate::secret::{witness_copies, Secret};
+
+        let table = ZeroizingEvalTable::new(EvalTable::new(scalars![1, 2, 3, 4]));
+        let before = witness_copies();
+        let copy = table.clone();
+        let _ = Secret::copy_of(&table.evals);
+        assert_eq!(witness_copies(), before + 2);
+
+        // the same table, and the witness is not printed.
+        assert_eq!(*copy, *table);
+        assert_eq!(format!("{:?}", copy), "Secret(..)");
+    }
+}
@@ -0,0 +1,203 @@
+// F_101 for the tests, so small that the sum-check can be run over every challenge.
+//
+// It's a plain u64 < 101, nothing is constant time: only the generic code is under test.
+use core::fmt;
+use core::iter::{Product, Sum};
+use core::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use ff::{Field, PrimeField};
+use rand_core::RngCore;
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq, CtOption};
+
+const P: u64 = 101;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct F101(u64);
+
+impl F101 {
+    pub const fn new(v: u64) -> Self {
+        Self(v % P)
+    }
+}
+
+impl fmt::Debug for F101 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "F101({})", self.0)
+    }
+}
+
+impl From<u64> for F101 {
+    fn from(v: u64) -> Self {
+        Self::new(v)
+    }
+}
+
+impl Neg for F101 {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(P - self.0)
+    }
+}
+
+// op, op_assign and the &rhs forms of each.
+macro_rules! impl_binop {
+    ($op:ident, $f:ident, $op_assign:ident, $f_assign:ident, |$a:ident, $b:ident| $body:expr) => {
+        impl $op for F101 {
+            type Output = Self;
+
+            fn $f(self, rhs: Self) -> Self {
+                let ($a, $b) = (self.0, rhs.0);
+                Self::new($body)
+            }
+        }
+
+        impl<'a> $op<&'a F101> for F101 {
+            type Output = Self;
+
+            fn $f(self, rhs: &'a Self) -> Self {
+                self.$f(*rhs)
+            }
+        }
+
+        impl $op_assign for F101 {
+            fn $f_assign(&mut self, rhs: Self) {
+                *self = self.$f(rhs);
+            }
+        }
+
+        impl<'a> $op_assign<&'a F101> for F101 {
+            fn $f_assign(&mut self, rhs: &'a Self) {
+                *self = self.$f(*rhs);
+            }
+        }
+    };
+}
+
+impl_binop!(Add, add, AddAssign, add_assign, |a, b| a + b);
+impl_binop!(Sub, sub, SubAssign, sub_assign, |a, b| a + P - b);
+impl_binop!(Mul, mul, MulAssign, mul_assign, |a, b| a * b);
+
+impl Sum for F101 {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, |acc, x| acc + x)
+    }
+}
+
+impl<'a> Sum<&'a F101> for F101 {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+impl Product for F101 {
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ONE, |acc, x| acc * x)
+    }
+}
+
+impl<'a> Product<&'a F101> for F101 {
+    fn product<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.copied().product()
+    }
+}
+
+impl ConstantTimeEq for F101 {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl ConditionallySelectable for F101 {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        Self(u64::conditional_select(&a.0, &b.0, choice))
+    }
+}
+
+impl Field for F101 {
+    const ZERO: Self = Self(0);
+    const ONE: Self = Self(1);
+
+    fn random(mut rng: impl RngCore) -> Self {
+        Self::new(rng.next_u64())
+    }
+
+    fn square(&self) -> Self {
+        *self * self
+    }
+
+    fn double(&self) -> Self {
+        *self + self
+    }
+
+    // a^(p-2) = a^-1
+    fn invert(&self) -> CtOption<Self> {
+        CtOption::new(self.pow_vartime([P - 2]), !self.is_zero())
+    }
+
+    // By brute force, see `Field::sqrt_ratio` for the cases.
+    fn sqrt_ratio(num: &Self, div: &Self) -> (Choice, Self) {
+        let root = |a: Self| (0..P).map(Self).find(|x| x.square() == a);
+        if num.0 == 0 {
+            return (Choice::from(1), Self::ZERO);
+        }
+        if div.0 == 0 {
+            return (Choice::from(0), Self::ZERO);
+        }
+        let a = *num * div.invert().unwrap();
+        match root(a) {
+            Some(x) => (Choice::from(1), x),
+            None => (Choice::from(0), root(Self::ROOT_OF_UNITY * a).unwrap()),
+        }
+    }
+}
+
+impl PrimeField for F101 {
+    type Repr = [u8; 8];
+
+    fn from_repr(repr: Self::Repr) -> CtOption<Self> {
+        let v = u64::from_le_bytes(repr);
+        CtOption::new(Self(v % P), Choice::from((v < P) as u8))
+    }
+
+    fn to_repr(&self) -> Self::Repr {
+        self.0.to_le_bytes()
+    }
+
+    fn is_odd(&self) -> Choice {
+        Choice::from((self.0 & 1) as u8)
+    }
+
+    const MODULUS: &'static str = "0x65";
+    const NUM_BITS: u32 = 7;
+    const CAPACITY: u32 = 6;
+    const TWO_INV: Self = Self(51);
+    // 2 generates F_101^*, and p - 1 = 2^2 * 25.
+    const MULTIPLICATIVE_GENERATOR: Self = Self(2);
+    const S: u32 = 2;
+    // 2^25
+    const ROOT_OF_UNITY: Self = Self(10);
+    const ROOT_OF_UNITY_INV: Self = Self(91);
+    // 2^(2^S)
+    const DELTA: Self = Self(16);
+}
+
+#[cfg(test)]
+mod test {
+    use crate::small_field::F101;
+    use ff::{Field, PrimeField};
+
+    #[test]
+    fn test_f101() {
+        assert_eq!(F101::from(100) + F101::from(3), F101::from(2));
+        assert_eq!(F101::from(2) - F101::from(3), F101::from(100));
+        assert_eq!(F101::from_u128(101 * 7 + 5), F101::from(5));
+        for a in 1..101 {
+            let a = F101::from(a);
+            assert_eq!(a * a.invert().unwrap(), F101::ONE);
+        }
+        assert_eq!(F101::TWO_INV.double(), F101::ONE);
+        assert_eq!(F101::ROOT_OF_UNITY.pow_vartime([4]), F101::ONE);
+        assert_eq!(F101::ROOT_OF_UNITY * F101::ROOT_OF_UNITY_INV, F101::ONE);
+        assert_eq!(F101::from(4).sqrt().unwrap().square(), F101::from(4));
+    }
+}
@@ -1,22 +1,26 @@
-use crate::poly::multivar_poly::MPolynomial;
+use crate::poly::multivar_poly::MPolynomialOver;
 use crate::poly::univar_poly::Polynomial;
 use crate::sumcheck::prover::Prover;
 use crate::sumcheck::verifier::Verifier;
 use bls12_381::Scalar;
+use ff::PrimeField;
 use std::env::var;
 use std::iter::Sum;
 
+pub mod machine;
+pub mod outsource;
 pub mod prover;
 pub mod verifier;
 
-pub struct SumCheck {
+// Over any prime field, eg: a small one to count the cheating provers V accepts.
+pub struct SumCheck<F: PrimeField = Scalar> {
     v: usize,
-    prover: Prover,
-    verifier: Verifier,
+    prover: Prover<F>,
+    verifier: Verifier<F>,
 }
 
-impl SumCheck {
-    pub fn new(g: MPolynomial) -> Self {
+impl<F: PrimeField> SumCheck<F> {
+    pub fn new(g: MPolynomialOver<F>) -> Self {
         let var_num = g.var_num;
 
         let prover = Prover::new(g);
@@ -30,10 +34,27 @@ impl SumCheck {
         }
     }
 
+    // v = 0 is the trivial protocol: no rounds, V checks C1 = g() in the final check.
+    // v = 1 has round 1 only, which is round v too.
     pub fn run_protocol(&mut self) {
+        if self.v > 0 {
+            self.run_rounds();
+        }
+
+        // finally check
+        let challenges = self.verifier.challenges();
+        let target = self.prover.evaluate(&challenges);
+        self.verifier.check(target);
+    }
+
+    // the v rounds, v > 0.
+    fn run_rounds(&mut self) {
         // round 1
         let g1 = self.prover.round_1();
         self.verifier.round_1(g1);
+        if self.v == 1 {
+            return;
+        }
 
         // round 2 - (v-1)
         for j in 2..self.v {
@@ -48,33 +69,36 @@ impl SumCheck {
         let g_v = self.prover.round_v(&challenges);
         self.verifier.round_v(g_v);
         // drop(challenges);
-
-        // finally check
-        let challenges = self.verifier.challenges();
-        let target = self.prover.evaluate(&challenges);
-        self.verifier.check(target);
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::poly::multivar_poly::MPolynomial;
+    use crate::mpoly;
+    use crate::poly::multivar_poly::{MPolynomial, MPolynomialOver};
+    use crate::poly::univar_poly::PolynomialOver;
+    use crate::scalar_utils::to_u64;
+    use crate::small_field::F101;
+    use crate::sumcheck::verifier::Verifier;
     use crate::sumcheck::SumCheck;
     use bls12_381::Scalar;
-    use ff::PrimeField;
+    use ff::{Field, PrimeField};
 
     fn gen_mpoly() -> MPolynomial {
-        // let g(x1, x2, x3) = 9 + 2*x3 + 3*x2 + 2 * x1 * x2 + 4* x1 * x2 * x3
+        mpoly!("9 + 2*x3 + 3*x2 + 2*x1*x2 + 4*x1*x2*x3", vars = 3)
+    }
+
+    #[test]
+    fn test_fixture() {
         // term0: exp: (0,0,0) = 9
         // term1: exp: (0,0,1) = 2*x3
         // term2: exp: (0,1,0) = 3*x2
-        // term3-6: exp: (0,1,0) = 0.
+        // term3-5: exp: (0,1,1)..(1,0,1) = 0.
         // term6: exp: (1,1,0) = 2 * x1 * x2
         // term7: exp: (1,1,1) = 4 * x1 * x2 * x3
-
         let var_num = 3;
 
-        MPolynomial {
+        let expected = MPolynomial {
             var_num,
             coeffs: vec![
                 Scalar::from_u128(9),
@@ -86,7 +110,8 @@ mod test {
                 Scalar::from_u128(2),
                 Scalar::from_u128(4),
             ],
-        }
+        };
+        assert_eq!(gen_mpoly(), expected);
     }
 
     #[test]
@@ -97,4 +122,110 @@ mod test {
 
         sumcheck.run_protocol();
     }
+
+    #[test]
+    fn test_sumcheck_zero_vars() {
+        let mut sumcheck = SumCheck::new(mpoly!("7", vars = 0));
+        sumcheck.run_protocol();
+        assert!(sumcheck.verifier.challenges().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Verifier rejected the proof")]
+    fn test_sumcheck_zero_vars_rejects() {
+        let mut sumcheck = SumCheck::new(mpoly!("7", vars = 0));
+        sumcheck.verifier = Verifier::new(0, Scalar::from(8));
+        sumcheck.run_protocol();
+    }
+
+    #[test]
+    fn test_sumcheck_one_var() {
+        // H = g(0) + g(1) = 4 + 32
+        let mut sumcheck = SumCheck::new(mpoly!("4 + 28*x1", vars = 1));
+        assert_eq!(sumcheck.prover.proof(), Scalar::from(36));
+        sumcheck.run_protocol();
+        assert_eq!(sumcheck.verifier.challenges().len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "No-equal in round_1")]
+    fn test_sumcheck_one_var_rejects() {
+        let mut sumcheck = SumCheck::new(mpoly!("4 + 28*x1", vars = 1));
+        sumcheck.verifier = Verifier::new(1, Scalar::from(35));
+        sumcheck.run_protocol();
+    }
+
+    #[test]
+    fn test_sumcheck_two_vars() {
+        let mut sumcheck = SumCheck::new(mpoly!("1 + 2*x1 + 3*x1*x2", vars = 2));
+        sumcheck.run_protocol();
+        assert_eq!(sumcheck.verifier.challenges().len(), 2);
+    }
+
+    // The small integers of the fixtures, mod 101.
+    fn to_f101(s: &Scalar) -> F101 {
+        F101::from(to_u64(s).expect("not a small integer"))
+    }
+
+    #[test]
+    fn test_generic_same_as_scalar() {
+        let g = gen_mpoly();
+        let g_101 = MPolynomialOver {
+            var_num: g.var_num,
+            coeffs: g.coeffs.iter().map(to_f101).collect(),
+        };
+
+        assert_eq!(g_101.sum_all_evals(), to_f101(&g.sum_all_evals()));
+        let point = vec![99, 7, 42];
+        assert_eq!(g_101.evaluate(&point), to_f101(&g.evaluate(&point)));
+        for challenges in [vec![], vec![3], vec![99, 7]] {
+            let g_r = g.partial_evaluate(&challenges);
+            let expected = g_r.coeffs.iter().map(to_f101);
+            assert!(g_101
+                .partial_evaluate(&challenges)
+                .coeffs
+                .into_iter()
+                .eq(expected));
+        }
+
+        SumCheck::new(g_101).run_protocol();
+    }
+
+    // P claims H + 1 for g(x1, x2) over F_101, and sends g_j + e_j·(1 - X) in round j, so that
+    // each round check holds. V is run over all the 101^2 challenges (r1, r2):
+    //      g_2'(r2) - g(r1, r2) = (1 - r1)(1 - r2),
+    // so the final check passes iff r1 = 1 or r2 = 1, which is 201 pairs. The soundness error of
+    // the sum-check is v·d/|F| = 2/101, that's at most 202 pairs.
+    #[test]
+    fn test_cheating_prover_over_f101() {
+        let g = MPolynomialOver {
+            var_num: 2,
+            coeffs: [3, 5, 7, 11].map(F101::from).to_vec(),
+        };
+        let delta = F101::ONE;
+        let claim = g.sum_all_evals() + delta;
+        let tilt = |e: F101| PolynomialOver::from_coeffs(vec![e, -e]);
+
+        let p = 101;
+        let mut accepted = 0;
+        for r1 in 0..p {
+            for r2 in 0..p {
+                let mut challenges = [r1, r2].into_iter();
+                let mut verifier =
+                    Verifier::with_challenges(2, claim, move || challenges.next().unwrap());
+
+                verifier.round_1(&g.partial_evaluate(&vec![]) + &tilt(delta));
+                let e = delta * (F101::ONE - F101::from(r1 as u64));
+                verifier.round_v(&g.partial_evaluate(&vec![r1]) + &tilt(e));
+
+                if verifier.claim() == g.evaluate(&verifier.challenges()) {
+                    assert!(r1 == 1 || r2 == 1);
+                    accepted += 1;
+                }
+            }
+        }
+        let (v, d) = (2, 1);
+        assert_eq!(accepted, 2 * p - 1);
+        assert!(accepted <= v * d * p);
+    }
 }
@@ -0,0 +1,438 @@
+// The sum-check as sans-io state machines, driven by the messages of the other party: nothing
+// blocks and no I/O is done inside, eg: to run V in a network service. The challenges come from
+// a `ChallengeSource`, an rng in the interactive protocol or a transcript in the non-interactive
+// one.
+//
+//      P: first_round()            -> Round(g_1)
+//      V: on_round_polynomial(g_j) -> SendChallenge(r_j) | Accept(claim) | Reject(error)
+//      P: on_challenge(r_j)        -> Round(g_j+1) | FinalEval(g(r_1, ..., r_v))
+//
+// V accepts after the check of g_v with the claim g(r_1, ..., r_v) = g_v(r_v), which is left to
+// the caller, eg: `outsource::Client` evaluates g by itself.
+//
+// Once done, a state machine takes no more messages: a call out of order, eg: a round poly after
+// Accept, is an `OrderError`, and the state is left as it is.
+use crate::poly::eval_table::EvalTable;
+use crate::poly::univar_poly::Polynomial;
+use bls12_381::Scalar;
+use ff::Field;
+use rand_core::RngCore;
+use std::fmt;
+
+pub trait ChallengeSource {
+    // r_j, once g_j is checked.
+    fn challenge(&mut self, g_j: &Polynomial) -> Scalar;
+}
+
+impl<C: ChallengeSource + ?Sized> ChallengeSource for &mut C {
+    fn challenge(&mut self, g_j: &Polynomial) -> Scalar {
+        (**self).challenge(g_j)
+    }
+}
+
+// The interactive V: r_j is uniform, whatever g_j is.
+pub struct RngChallenges<R>(pub R);
+
+impl<R: RngCore> ChallengeSource for RngChallenges<R> {
+    fn challenge(&mut self, _g_j: &Polynomial) -> Scalar {
+        Scalar::random(&mut self.0)
+    }
+}
+
+// The given r_1, r_2, ..., eg: to replay a run in the tests.
+pub struct FixedChallenges<I>(pub I);
+
+impl<I: Iterator<Item = Scalar>> ChallengeSource for FixedChallenges<I> {
+    fn challenge(&mut self, _g_j: &Polynomial) -> Scalar {
+        self.0.next().expect("no challenge left")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SumCheckError {
+    DegreeTooHigh { round: usize },
+    RoundCheckFailed { round: usize },
+}
+
+impl fmt::Display for SumCheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SumCheckError::DegreeTooHigh { round } => {
+                write!(f, "Degree too high in round_{}", round)
+            }
+            SumCheckError::RoundCheckFailed { round } => write!(f, "Not-equal in round_{}", round),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SumCheckError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderError {
+    // P got a challenge before its first round.
+    NotStarted,
+    // P was asked for its first round twice.
+    AlreadyStarted,
+    // V has accepted or rejected, or P has sent its final eval.
+    Finished,
+}
+
+impl fmt::Display for OrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderError::NotStarted => write!(f, "The first round is not sent yet"),
+            OrderError::AlreadyStarted => write!(f, "The first round is sent already"),
+            OrderError::Finished => write!(f, "The protocol is over"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for OrderError {}
+
+// P claims g(point) = value, which V checks with its oracle of g.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedClaim {
+    pub point: Vec<Scalar>, // (r_1, ..., r_v)
+    pub value: Scalar,      // g_v(r_v)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifierAction {
+    SendChallenge(Scalar),
+    Accept(VerifiedClaim),
+    Reject(SumCheckError),
+}
+
+pub struct SumCheckVerifierSm<C> {
+    var_num: usize,
+    degree_bound: usize,
+    // C1 before round 1, g_j-1(r_j-1) after.
+    target: Scalar,
+    round_polys: Vec<Polynomial>,
+    challenges: Vec<Scalar>,
+    source: C,
+    // Accept or Reject, once done.
+    outcome: Option<VerifierAction>,
+}
+
+impl<C: ChallengeSource> SumCheckVerifierSm<C> {
+    // degree_bound: the degree of g in each variable.
+    // v = 0 has no round, it's accepted at once with the claim g() = C1.
+    pub fn new(var_num: usize, degree_bound: usize, claimed_sum: Scalar, source: C) -> Self {
+        let outcome = (var_num == 0).then(|| {
+            VerifierAction::Accept(VerifiedClaim {
+                point: vec![],
+                value: claimed_sum,
+            })
+        });
+        Self {
+            var_num,
+            degree_bound,
+            target: claimed_sum,
+            round_polys: vec![],
+            challenges: vec![],
+            source,
+            outcome,
+        }
+    }
+
+    // The round of the next g_j, from 1.
+    pub fn round(&self) -> usize {
+        self.round_polys.len() + 1
+    }
+
+    // Accept or Reject, None while the rounds go on.
+    pub fn outcome(&self) -> Option<&VerifierAction> {
+        self.outcome.as_ref()
+    }
+
+    // The checked g_1, g_2, ...
+    pub fn round_polys(&self) -> &[Polynomial] {
+        &self.round_polys
+    }
+
+    pub fn challenges(&self) -> &[Scalar] {
+        &self.challenges
+    }
+
+    // check: g_j-1(r_j-1) = g_j(0) + g_j(1), where g_0(r_0) is the claimed sum.
+    pub fn on_round_polynomial(&mut self, g_j: Polynomial) -> Result<VerifierAction, OrderError> {
+        if self.outcome.is_some() {
+            return Err(OrderError::Finished);
+        }
+        let round = self.round();
+        let action = if g_j.coeffs.len() > self.degree_bound + 1 {
+            VerifierAction::Reject(SumCheckError::DegreeTooHigh { round })
+        } else if g_j.evaluate(Scalar::zero()) + g_j.evaluate(Scalar::one()) != self.target {
+            VerifierAction::Reject(SumCheckError::RoundCheckFailed { round })
+        } else {
+            let r_j = self.source.challenge(&g_j);
+            self.target = g_j.evaluate(r_j);
+            self.round_polys.push(g_j);
+            self.challenges.push(r_j);
+            if round < self.var_num {
+                return Ok(VerifierAction::SendChallenge(r_j));
+            }
+            VerifierAction::Accept(VerifiedClaim {
+                point: self.challenges.clone(),
+                value: self.target,
+            })
+        };
+        self.outcome = Some(action.clone());
+        Ok(action)
+    }
+}
+
+// What P knows of g.
+pub trait RoundOracle {
+    fn var_num(&self) -> usize;
+
+    // H = ∑ g(x) over the hypercube, the claim of P.
+    fn sum(&self) -> Scalar;
+
+    // g_j(X) = ∑ g(r_1, ..., r_j-1, X, x_j+1, ..., x_v), j = challenges.len() + 1.
+    fn round_poly(&self, challenges: &[Scalar]) -> Polynomial;
+
+    fn evaluate(&self, point: &[Scalar]) -> Scalar;
+}
+
+// A multilinear g by its table, each g_j is folded from the table by the challenges.
+impl RoundOracle for EvalTable {
+    fn var_num(&self) -> usize {
+        self.var_num
+    }
+
+    fn sum(&self) -> Scalar {
+        EvalTable::sum(self)
+    }
+
+    // g_j(X) = sum(lo) + X·(sum(hi) - sum(lo)), see `sumcheck::batched` of ni_sumcheck.
+    fn round_poly(&self, challenges: &[Scalar]) -> Polynomial {
+        let mut table = self.clone();
+        for r in challenges.iter() {
+            table.bind_first(*r);
+        }
+        table.first_round_poly()
+    }
+
+    fn evaluate(&self, point: &[Scalar]) -> Scalar {
+        EvalTable::evaluate(self, point)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProverMessage {
+    Round(Polynomial),
+    // g(r_1, ..., r_v), after r_v.
+    FinalEval(Scalar),
+}
+
+pub struct SumCheckProverSm<O> {
+    oracle: O,
+    challenges: Vec<Scalar>,
+    started: bool,
+    finished: bool,
+}
+
+impl<O: RoundOracle> SumCheckProverSm<O> {
+    pub fn new(oracle: O) -> Self {
+        Self {
+            oracle,
+            challenges: vec![],
+            started: false,
+            finished: false,
+        }
+    }
+
+    pub fn oracle(&self) -> &O {
+        &self.oracle
+    }
+
+    pub fn claimed_sum(&self) -> Scalar {
+        self.oracle.sum()
+    }
+
+    pub fn challenges(&self) -> &[Scalar] {
+        &self.challenges
+    }
+
+    // g_1, or g() at once when v = 0.
+    pub fn first_round(&mut self) -> Result<ProverMessage, OrderError> {
+        if self.started {
+            return Err(OrderError::AlreadyStarted);
+        }
+        self.started = true;
+        Ok(self.next_message())
+    }
+
+    pub fn on_challenge(&mut self, r_j: Scalar) -> Result<ProverMessage, OrderError> {
+        if !self.started {
+            return Err(OrderError::NotStarted);
+        }
+        if self.finished {
+            return Err(OrderError::Finished);
+        }
+        self.challenges.push(r_j);
+        Ok(self.next_message())
+    }
+
+    fn next_message(&mut self) -> ProverMessage {
+        if self.challenges.len() < self.oracle.var_num() {
+            return ProverMessage::Round(self.oracle.round_poly(&self.challenges));
+        }
+        self.finished = true;
+        ProverMessage::FinalEval(self.oracle.evaluate(&self.challenges))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::poly::eval_table::{random_table, EvalTable};
+    use crate::poly::univar_poly::Polynomial;
+    use crate::scalars;
+    use crate::sumcheck::machine::{
+        FixedChallenges, OrderError, ProverMessage, RngChallenges, RoundOracle, SumCheckError,
+        SumCheckProverSm, SumCheckVerifierSm, VerifiedClaim, VerifierAction,
+    };
+    use bls12_381::Scalar;
+    use rand_core::OsRng;
+
+    // Run P and V against each other, return the outcome of V and the final eval of P.
+    fn run(table: &EvalTable) -> (VerifierAction, Scalar) {
+        let mut prover = SumCheckProverSm::new(table.clone());
+        let mut verifier =
+            SumCheckVerifierSm::new(table.var_num, 1, prover.claimed_sum(), RngChallenges(OsRng));
+        let mut message = prover.first_round().unwrap();
+        loop {
+            match message {
+                ProverMessage::Round(g_j) => {
+                    let r_j = match verifier.on_round_polynomial(g_j).unwrap() {
+                        VerifierAction::SendChallenge(r_j) => r_j,
+                        // P gets r_v too, for its final eval.
+                        VerifierAction::Accept(claim) => *claim.point.last().unwrap(),
+                        VerifierAction::Reject(e) => panic!("{}", e),
+                    };
+                    message = prover.on_challenge(r_j).unwrap();
+                }
+                ProverMessage::FinalEval(value) => {
+                    return (verifier.outcome().unwrap().clone(), value);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_state_machines() {
+        for v in 0..=6 {
+            let table = random_table(v);
+            let (outcome, value) = run(&table);
+            let VerifierAction::Accept(claim) = outcome else {
+                panic!("rejected: {:?}", outcome);
+            };
+            assert_eq!(claim.point.len(), v);
+            assert_eq!(claim.value, value);
+            assert_eq!(table.evaluate(&claim.point), value);
+        }
+    }
+
+    #[test]
+    fn test_verifier_order() {
+        // g(x1, x2) = 1 + x2 + 2*x1, H = 10, g_1(X) = 3 + 4X
+        let table = EvalTable::new(scalars![1, 2, 3, 4]);
+        let challenges = scalars![5, 7];
+        let mut verifier =
+            SumCheckVerifierSm::new(2, 1, table.sum(), FixedChallenges(challenges.into_iter()));
+        assert_eq!(verifier.outcome(), None);
+        let g_1 = Polynomial::from_coeffs(scalars![3, 4]);
+        assert_eq!(
+            verifier.on_round_polynomial(g_1),
+            Ok(VerifierAction::SendChallenge(Scalar::from(5)))
+        );
+
+        // g_2(X) = g(5, X) = 11 + X, g_1(5) = 23
+        let g_2 = Polynomial::from_coeffs(scalars![11, 1]);
+        let claim = VerifiedClaim {
+            point: scalars![5, 7],
+            value: Scalar::from(18),
+        };
+        assert_eq!(
+            verifier.on_round_polynomial(g_2.clone()),
+            Ok(VerifierAction::Accept(claim.clone()))
+        );
+        assert_eq!(verifier.outcome(), Some(&VerifierAction::Accept(claim)));
+        assert_eq!(verifier.on_round_polynomial(g_2), Err(OrderError::Finished));
+        assert_eq!(verifier.round_polys().len(), 2);
+    }
+
+    #[test]
+    fn test_verifier_rejects() {
+        let table = random_table(3);
+        let g_1 = table.round_poly(&[]);
+
+        // a wrong g_1, then nothing is taken.
+        let mut verifier = SumCheckVerifierSm::new(3, 1, table.sum(), RngChallenges(OsRng));
+        let mut wrong = g_1.clone();
+        wrong.coeffs[0] += Scalar::one();
+        let reject = VerifierAction::Reject(SumCheckError::RoundCheckFailed { round: 1 });
+        assert_eq!(verifier.on_round_polynomial(wrong), Ok(reject.clone()));
+        assert_eq!(
+            verifier.on_round_polynomial(g_1.clone()),
+            Err(OrderError::Finished)
+        );
+        assert_eq!(verifier.outcome(), Some(&reject));
+        assert!(verifier.challenges().is_empty());
+
+        // g_1 of degree 2
+        let mut verifier = SumCheckVerifierSm::new(3, 1, table.sum(), RngChallenges(OsRng));
+        let mut high = g_1;
+        high.coeffs.push(Scalar::one());
+        assert_eq!(
+            verifier.on_round_polynomial(high),
+            Ok(VerifierAction::Reject(SumCheckError::DegreeTooHigh {
+                round: 1
+            }))
+        );
+    }
+
+    #[test]
+    fn test_prover_order() {
+        let table = random_table(2);
+        let mut prover = SumCheckProverSm::new(table.clone());
+        assert_eq!(
+            prover.on_challenge(Scalar::one()),
+            Err(OrderError::NotStarted)
+        );
+        assert!(matches!(prover.first_round(), Ok(ProverMessage::Round(_))));
+        assert_eq!(prover.first_round(), Err(OrderError::AlreadyStarted));
+
+        let point = scalars![3, 5];
+        assert!(matches!(
+            prover.on_challenge(point[0]),
+            Ok(ProverMessage::Round(_))
+        ));
+        assert_eq!(
+            prover.on_challenge(point[1]),
+            Ok(ProverMessage::FinalEval(table.evaluate(&point)))
+        );
+        assert_eq!(prover.on_challenge(point[1]), Err(OrderError::Finished));
+        assert_eq!(prover.challenges(), point.as_slice());
+
+        // v = 0: g() at once, V accepts C1 = g() with no round.
+        let constant = EvalTable::new(scalars![7]);
+        let mut prover = SumCheckProverSm::new(constant);
+        assert_eq!(
+            prover.first_round(),
+            Ok(ProverMessage::FinalEval(Scalar::from(7)))
+        );
+        let verifier = SumCheckVerifierSm::new(0, 1, Scalar::from(7), RngChallenges(OsRng));
+        assert_eq!(
+            verifier.outcome(),
+            Some(&VerifierAction::Accept(VerifiedClaim {
+                point: vec![],
+                value: Scalar::from(7),
+            }))
+        );
+    }
+}
@@ -0,0 +1,486 @@
+// Outsourcing a huge sum to an untrusted server, the motivating story of the sum-check.
+//
+// The `Server` holds g as a closure and claims H = sum g(x) over the hypercube {0,1}^v. It plays
+// the prover by streaming: each round poly g_j is interpolated from its evaluations at
+// 0, 1, ..., degree_bound, which are sums of g over the rest of the hypercube, so no table of g
+// is kept. The `Client` holds its own copy of g, the claimed H and the transcript, it checks
+// each round with a random challenge and finally evaluates g once, at (r_1, ..., r_v).
+//
+// They talk through a `Channel`, eg: the `Server` itself in-process, or `Remote` to a server
+// running in another thread, see `spawn`. Both sides are driven by the state machines of
+// `machine`, the channel only carries their messages.
+use crate::poly::univar_poly::Polynomial;
+use crate::sumcheck::machine::{
+    ChallengeSource, OrderError, ProverMessage, RngChallenges, RoundOracle, SumCheckError,
+    SumCheckProverSm, SumCheckVerifierSm, VerifierAction,
+};
+use bls12_381::Scalar;
+use rand_core::OsRng;
+use rayon::prelude::*;
+use std::fmt;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Request {
+    Claim,
+    // The challenge of the previous round, None in round 1.
+    Round(Option<Scalar>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Response {
+    Claim(Scalar),
+    Round(Polynomial),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyError {
+    UnexpectedResponse,
+    DegreeTooHigh { round: usize },
+    RoundCheckFailed { round: usize },
+    FinalCheckFailed,
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::UnexpectedResponse => write!(f, "Unexpected response"),
+            VerifyError::DegreeTooHigh { round } => write!(f, "Degree too high in round_{}", round),
+            VerifyError::RoundCheckFailed { round } => write!(f, "Not-equal in round_{}", round),
+            VerifyError::FinalCheckFailed => write!(f, "Verifier rejected the proof"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for VerifyError {}
+
+impl From<SumCheckError> for VerifyError {
+    fn from(e: SumCheckError) -> Self {
+        match e {
+            SumCheckError::DegreeTooHigh { round } => VerifyError::DegreeTooHigh { round },
+            SumCheckError::RoundCheckFailed { round } => VerifyError::RoundCheckFailed { round },
+        }
+    }
+}
+
+pub trait Channel {
+    fn request(&mut self, request: Request) -> Response;
+}
+
+// The messages seen by the client: g_1, ..., g_v and r_1, ..., r_v.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Transcript {
+    pub round_polys: Vec<Polynomial>,
+    pub challenges: Vec<Scalar>,
+}
+
+// The answer of P to a request, see `Client::run`: the challenge of the previous round is
+// passed on to P before its next round.
+fn respond<O: RoundOracle>(prover: &mut SumCheckProverSm<O>, request: Request) -> Response {
+    match request {
+        Request::Claim => Response::Claim(prover.claimed_sum()),
+        Request::Round(challenge) => {
+            let message = match challenge {
+                None => prover.first_round(),
+                Some(r) => prover.on_challenge(r),
+            };
+            match message {
+                Ok(ProverMessage::Round(g_j)) => Response::Round(g_j),
+                Ok(ProverMessage::FinalEval(_)) | Err(OrderError::Finished) => {
+                    panic!("no round left")
+                }
+                Err(e) => panic!("{}", e),
+            }
+        }
+    }
+}
+
+// g as a closure, each round poly is streamed from the evaluations of g.
+struct Streamed<G> {
+    g: G,
+    var_num: usize,
+    degree_bound: usize,
+}
+
+impl<G: Fn(&[Scalar]) -> Scalar + Sync> RoundOracle for Streamed<G> {
+    fn var_num(&self) -> usize {
+        self.var_num
+    }
+
+    fn sum(&self) -> Scalar {
+        hypercube_sum(&self.g, &[], self.var_num)
+    }
+
+    fn round_poly(&self, challenges: &[Scalar]) -> Polynomial {
+        streamed_round_poly(&self.g, self.var_num, self.degree_bound, challenges)
+    }
+
+    fn evaluate(&self, point: &[Scalar]) -> Scalar {
+        (self.g)(point)
+    }
+}
+
+// g_j(X) = sum g(r_1, ..., r_j-1, X, x_j+1, ..., x_v)
+fn streamed_round_poly<G: Fn(&[Scalar]) -> Scalar + Sync>(
+    g: &G,
+    var_num: usize,
+    degree_bound: usize,
+    challenges: &[Scalar],
+) -> Polynomial {
+    let rest = var_num - challenges.len() - 1;
+    let domains: Vec<Scalar> = (0..=degree_bound as u64).map(Scalar::from).collect();
+    let evals = domains
+        .iter()
+        .map(|t| {
+            let mut prefix = challenges.to_vec();
+            prefix.push(*t);
+            hypercube_sum(g, &prefix, rest)
+        })
+        .collect();
+    Polynomial::lagrange_interpolate(domains, evals)
+}
+
+// sum g(prefix, x) over x in {0,1}^rest, x_1 is the MSB of the index as in `convert_to_binary`.
+fn hypercube_sum<G: Fn(&[Scalar]) -> Scalar + Sync>(
+    g: &G,
+    prefix: &[Scalar],
+    rest: usize,
+) -> Scalar {
+    (0..1usize << rest)
+        .into_par_iter()
+        .map(|i| {
+            let mut point = prefix.to_vec();
+            point.extend((0..rest).rev().map(|k| Scalar::from(((i >> k) & 1) as u64)));
+            g(&point)
+        })
+        .sum()
+}
+
+pub struct Server<G> {
+    prover: SumCheckProverSm<Streamed<G>>,
+}
+
+impl<G: Fn(&[Scalar]) -> Scalar + Sync> Server<G> {
+    // degree_bound: the degree of g in each variable.
+    pub fn new(g: G, var_num: usize, degree_bound: usize) -> Self {
+        Self {
+            prover: SumCheckProverSm::new(Streamed {
+                g,
+                var_num,
+                degree_bound,
+            }),
+        }
+    }
+}
+
+impl<G: Fn(&[Scalar]) -> Scalar + Sync> Channel for Server<G> {
+    fn request(&mut self, request: Request) -> Response {
+        respond(&mut self.prover, request)
+    }
+}
+
+// g off by one at a single point of the hypercube.
+struct OffByOne<G> {
+    streamed: Streamed<G>,
+    point: Vec<Scalar>,
+}
+
+impl<G: Fn(&[Scalar]) -> Scalar + Sync> OffByOne<G> {
+    fn eval(&self, x: &[Scalar]) -> Scalar {
+        let g_x = (self.streamed.g)(x);
+        if x == self.point.as_slice() {
+            g_x + Scalar::one()
+        } else {
+            g_x
+        }
+    }
+}
+
+impl<G: Fn(&[Scalar]) -> Scalar + Sync> RoundOracle for OffByOne<G> {
+    fn var_num(&self) -> usize {
+        self.streamed.var_num
+    }
+
+    fn sum(&self) -> Scalar {
+        hypercube_sum(&|x: &[Scalar]| self.eval(x), &[], self.streamed.var_num)
+    }
+
+    fn round_poly(&self, challenges: &[Scalar]) -> Polynomial {
+        let (var_num, degree_bound) = (self.streamed.var_num, self.streamed.degree_bound);
+        streamed_round_poly(
+            &|x: &[Scalar]| self.eval(x),
+            var_num,
+            degree_bound,
+            challenges,
+        )
+    }
+
+    fn evaluate(&self, point: &[Scalar]) -> Scalar {
+        self.eval(point)
+    }
+}
+
+// A server which runs the protocol honestly, but on a g which is off by one at a single point
+// of the hypercube, so it claims a wrong sum.
+pub struct DishonestServer<G> {
+    prover: SumCheckProverSm<OffByOne<G>>,
+}
+
+impl<G: Fn(&[Scalar]) -> Scalar + Sync> DishonestServer<G> {
+    // index: the modified point of the hypercube, x_1 is the MSB.
+    pub fn new(g: G, var_num: usize, degree_bound: usize, index: usize) -> Self {
+        assert!(index < 1 << var_num);
+        let point = (0..var_num)
+            .rev()
+            .map(|k| Scalar::from(((index >> k) & 1) as u64))
+            .collect();
+        Self {
+            prover: SumCheckProverSm::new(OffByOne {
+                streamed: Streamed {
+                    g,
+                    var_num,
+                    degree_bound,
+                },
+                point,
+            }),
+        }
+    }
+}
+
+impl<G: Fn(&[Scalar]) -> Scalar + Sync> Channel for DishonestServer<G> {
+    fn request(&mut self, request: Request) -> Response {
+        respond(&mut self.prover, request)
+    }
+}
+
+// The client side of a server running in another thread.
+pub struct Remote {
+    requests: Sender<Request>,
+    responses: Receiver<Response>,
+}
+
+impl Channel for Remote {
+    fn request(&mut self, request: Request) -> Response {
+        self.requests.send(request).expect("server is gone");
+        self.responses.recv().expect("server is gone")
+    }
+}
+
+// Run the server in a new thread, it stops once the `Remote` is dropped.
+pub fn spawn<C: Channel + Send + 'static>(mut server: C) -> (Remote, JoinHandle<()>) {
+    let (requests, incoming) = channel();
+    let (outgoing, responses) = channel();
+    let handle = thread::spawn(move || {
+        for request in incoming {
+            if outgoing.send(server.request(request)).is_err() {
+                break;
+            }
+        }
+    });
+    (
+        Remote {
+            requests,
+            responses,
+        },
+        handle,
+    )
+}
+
+pub struct Client<G, C = RngChallenges<OsRng>> {
+    g: G,
+    var_num: usize,
+    degree_bound: usize,
+    claimed_sum: Option<Scalar>,
+    transcript: Transcript,
+    source: C,
+}
+
+impl<G: Fn(&[Scalar]) -> Scalar> Client<G> {
+    pub fn new(g: G, var_num: usize, degree_bound: usize) -> Self {
+        Self::with_challenges(g, var_num, degree_bound, RngChallenges(OsRng))
+    }
+}
+
+impl<G: Fn(&[Scalar]) -> Scalar, C: ChallengeSource> Client<G, C> {
+    // The client with its own challenges, eg: fixed ones to replay a run.
+    pub fn with_challenges(g: G, var_num: usize, degree_bound: usize, source: C) -> Self {
+        Self {
+            g,
+            var_num,
+            degree_bound,
+            claimed_sum: None,
+            transcript: Transcript::default(),
+            source,
+        }
+    }
+
+    // The sum received from the server.
+    pub fn claimed_sum(&self) -> Option<Scalar> {
+        self.claimed_sum
+    }
+
+    pub fn transcript(&self) -> &Transcript {
+        &self.transcript
+    }
+
+    // Return the claimed sum once all the checks pass.
+    pub fn run<C2: Channel>(&mut self, channel: &mut C2) -> Result<Scalar, VerifyError> {
+        let claim = match channel.request(Request::Claim) {
+            Response::Claim(claim) => claim,
+            _ => return Err(VerifyError::UnexpectedResponse),
+        };
+        self.claimed_sum = Some(claim);
+
+        // the rounds, until V accepts or rejects.
+        let mut verifier =
+            SumCheckVerifierSm::new(self.var_num, self.degree_bound, claim, &mut self.source);
+        let mut last = None;
+        let outcome = loop {
+            if let Some(outcome) = verifier.outcome() {
+                break outcome.clone();
+            }
+            let g_j = match channel.request(Request::Round(last)) {
+                Response::Round(g_j) => g_j,
+                _ => return Err(VerifyError::UnexpectedResponse),
+            };
+            if let Ok(VerifierAction::SendChallenge(r_j)) = verifier.on_round_polynomial(g_j) {
+                last = Some(r_j);
+            }
+        };
+        self.transcript = Transcript {
+            round_polys: verifier.round_polys().to_vec(),
+            challenges: verifier.challenges().to_vec(),
+        };
+
+        match outcome {
+            // check: g_v(r_v) = g(r_1, ..., r_v), the only evaluation of g by the client.
+            VerifierAction::Accept(final_claim) => {
+                if (self.g)(&final_claim.point) != final_claim.value {
+                    return Err(VerifyError::FinalCheckFailed);
+                }
+                Ok(claim)
+            }
+            VerifierAction::Reject(e) => Err(e.into()),
+            VerifierAction::SendChallenge(_) => unreachable!("V is done"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sumcheck::machine::{
+        FixedChallenges, ProverMessage, SumCheckProverSm, SumCheckVerifierSm, VerifierAction,
+    };
+    use crate::sumcheck::outsource::{
+        spawn, Client, DishonestServer, Server, Streamed, VerifyError,
+    };
+    use bls12_381::Scalar;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // g(x) = x_1 * x_2 * x_3 + sum (i + 1) * x_i^2, the degree of each variable is at most 2.
+    fn g(x: &[Scalar]) -> Scalar {
+        let cube = x.iter().take(3).product::<Scalar>();
+        x.iter().enumerate().fold(cube, |acc, (i, x_i)| {
+            acc + Scalar::from(i as u64 + 1) * x_i.square()
+        })
+    }
+
+    #[test]
+    fn test_honest_server() {
+        let var_num = 12;
+        let calls = AtomicUsize::new(0);
+        let counted = |x: &[Scalar]| {
+            calls.fetch_add(1, Ordering::Relaxed);
+            g(x)
+        };
+
+        let mut server = Server::new(g, var_num, 2);
+        let mut client = Client::new(counted, var_num, 2);
+        assert!(client.run(&mut server).is_ok());
+
+        // one evaluation of g, and v round polys with at most degree_bound + 1 coeffs each.
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+        let transcript = client.transcript();
+        assert_eq!(transcript.challenges.len(), var_num);
+        assert_eq!(transcript.round_polys.len(), var_num);
+        assert!(transcript
+            .round_polys
+            .iter()
+            .all(|g_j| g_j.coeffs.len() <= 3));
+    }
+
+    #[test]
+    fn test_client_is_the_state_machines() {
+        let var_num = 5;
+        let challenges = (1..=var_num as u64)
+            .map(|i| Scalar::from(7 * i))
+            .collect::<Vec<_>>();
+        let source = FixedChallenges(challenges.clone().into_iter());
+        let mut client = Client::with_challenges(g, var_num, 2, source);
+        let expected = client.run(&mut Server::new(g, var_num, 2)).unwrap();
+
+        // the same run, step by step
+        let mut prover = SumCheckProverSm::new(Streamed {
+            g,
+            var_num,
+            degree_bound: 2,
+        });
+        let source = FixedChallenges(challenges.into_iter());
+        let mut verifier = SumCheckVerifierSm::new(var_num, 2, prover.claimed_sum(), source);
+        let mut message = prover.first_round().unwrap();
+        while let ProverMessage::Round(g_j) = message {
+            let r_j = match verifier.on_round_polynomial(g_j).unwrap() {
+                VerifierAction::SendChallenge(r_j) => r_j,
+                VerifierAction::Accept(claim) => *claim.point.last().unwrap(),
+                VerifierAction::Reject(e) => panic!("{}", e),
+            };
+            message = prover.on_challenge(r_j).unwrap();
+        }
+
+        assert_eq!(prover.claimed_sum(), expected);
+        let transcript = client.transcript();
+        assert_eq!(verifier.round_polys(), transcript.round_polys.as_slice());
+        assert_eq!(verifier.challenges(), transcript.challenges.as_slice());
+        let Some(VerifierAction::Accept(claim)) = verifier.outcome() else {
+            panic!("rejected: {:?}", verifier.outcome());
+        };
+        assert_eq!(message, ProverMessage::FinalEval(claim.value));
+        assert_eq!(g(&claim.point), claim.value);
+    }
+
+    #[test]
+    fn test_dishonest_server() {
+        let var_num = 6;
+        let mut server = DishonestServer::new(g, var_num, 2, 0b101101);
+        let mut client = Client::new(g, var_num, 2);
+        // round 1 is consistent with the wrong sum, it's caught afterwards.
+        assert!(matches!(
+            client.run(&mut server),
+            Err(VerifyError::RoundCheckFailed { round }) if round > 1
+        ));
+    }
+
+    #[test]
+    fn test_claimed_sum() {
+        let var_num = 4;
+        let expected: Scalar = (0..1usize << var_num)
+            .map(|i| {
+                let x: Vec<Scalar> = (0..var_num)
+                    .rev()
+                    .map(|k| Scalar::from(((i >> k) & 1) as u64))
+                    .collect();
+                g(&x)
+            })
+            .sum();
+
+        // across threads
+        let (mut remote, handle) = spawn(Server::new(g, var_num, 2));
+        let mut client = Client::new(g, var_num, 2);
+        assert_eq!(client.run(&mut remote), Ok(expected));
+        assert_eq!(client.claimed_sum(), Some(expected));
+        drop(remote);
+        handle.join().unwrap();
+    }
+}
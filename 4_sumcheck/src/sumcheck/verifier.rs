@@ -1,23 +1,31 @@
-use crate::poly::univar_poly::Polynomial;
+use crate::poly::univar_poly::PolynomialOver;
 use bls12_381::Scalar;
-use ff::{Field, PrimeField};
+use ff::PrimeField;
 use rand_core::{OsRng, RngCore};
 
-pub struct Verifier {
-    proof: Scalar,
+pub struct Verifier<F: PrimeField = Scalar> {
+    proof: F,
     // C1
     v: usize,
-    cached_g_j: Vec<Polynomial>,
+    cached_g_j: Vec<PolynomialOver<F>>,
     challenges: Vec<usize>, // challenges: r1, r2, ..., rv. (In implement, r1 is a random usize, which is easy to construct a Field)
+    // draws r_j, eg: a fixed sequence in the tests over a small field.
+    draw: Box<dyn FnMut() -> usize>,
 }
 
-impl Verifier {
-    pub fn new(v: usize, proof: Scalar) -> Self {
+impl<F: PrimeField> Verifier<F> {
+    pub fn new(v: usize, proof: F) -> Self {
+        Self::with_challenges(v, proof, Self::gen_challenge)
+    }
+
+    // V with its own challenges, eg: to run over all (r1, r2) of F_101.
+    pub fn with_challenges(v: usize, proof: F, draw: impl FnMut() -> usize + 'static) -> Self {
         Self {
             proof,
             v,
             cached_g_j: vec![],
             challenges: vec![],
+            draw: Box::new(draw),
         }
     }
 
@@ -25,23 +33,32 @@ impl Verifier {
         self.challenges.clone()
     }
 
+    // The claim the next g_j is checked against: C1 before round 1, g_j-1(r_j-1) after.
+    pub fn claim(&self) -> F {
+        match (self.challenges.last(), self.cached_g_j.last()) {
+            (Some(r), Some(g)) => g.evaluate(F::from_u128(*r as u128)),
+            _ => self.proof,
+        }
+    }
+
     // generate r1, r2, ..., rv
     fn gen_challenge() -> usize {
         let k = OsRng.next_u32() % 1000;
         k as usize
     }
-    fn prepare_next_round(&mut self, g_i: Polynomial) {
+    fn prepare_next_round(&mut self, g_i: PolynomialOver<F>) {
         assert_eq!(self.challenges.len(), self.cached_g_j.len());
 
         self.cached_g_j.push(g_i);
-        self.challenges.push(Verifier::gen_challenge());
+        self.challenges.push((self.draw)());
     }
 
     // Check: C1 = g_1(0) + g_1(1)
-    pub fn round_1(&mut self, g1: Polynomial) {
-        let actual = g1.evaluate(Scalar::one()) + g1.evaluate(Scalar::zero());
+    pub fn round_1(&mut self, g1: PolynomialOver<F>) {
+        assert!(self.v > 0, "no rounds when v = 0");
+        let actual = g1.evaluate(F::ONE) + g1.evaluate(F::ZERO);
 
-        assert_eq!(actual, self.proof, "No-equal in round_1");
+        assert_eq!(actual, self.claim(), "No-equal in round_1");
 
         // prepare for next round
         self.prepare_next_round(g1);
@@ -49,7 +66,7 @@ impl Verifier {
 
     // 1 < j < v, total v-2 rounds
     // check: g_j-1(r_j-1) = g_j(0) + g_j(1)
-    pub fn recursive_round_j(&mut self, round_num: usize, g_j: Polynomial) {
+    pub fn recursive_round_j(&mut self, round_num: usize, g_j: PolynomialOver<F>) {
         // check
         self.check_round_j(round_num, &g_j);
         // prepare for next round
@@ -57,7 +74,9 @@ impl Verifier {
     }
 
     // Check: gv−1 (rv−1 ) = gv (0) + gv (1).
-    pub fn round_v(&mut self, g_v: Polynomial) {
+    // When v = 1, round v is round 1, so it's checked by `round_1` only.
+    pub fn round_v(&mut self, g_v: PolynomialOver<F>) {
+        assert!(self.v > 1, "round v is round 1 when v = 1");
         self.check_round_j(self.v, &g_v);
 
         // prepare r_v and store g_v.
@@ -65,7 +84,7 @@ impl Verifier {
     }
 
     // check: g_j-1(r_j-1) = g_j(0) + g_j(1),  1<j<=v
-    fn check_round_j(&mut self, j: usize, g_j: &Polynomial) {
+    fn check_round_j(&mut self, j: usize, g_j: &PolynomialOver<F>) {
         assert_eq!(
             j - 1,
             self.cached_g_j.len(),
@@ -77,19 +96,13 @@ impl Verifier {
             "length of challenges != (j-1)"
         );
 
-        let r = self.challenges.last().unwrap().clone() as u128;
-        let actual = g_j.evaluate(Scalar::zero()) + g_j.evaluate(Scalar::one());
-        let target = self
-            .cached_g_j
-            .last()
-            .unwrap()
-            .evaluate(Scalar::from_u128(r));
+        let actual = g_j.evaluate(F::ZERO) + g_j.evaluate(F::ONE);
 
-        assert_eq!(actual, target, "Not-equal in round_{}", j);
+        assert_eq!(actual, self.claim(), "Not-equal in round_{}", j);
     }
 
     // 2. gv (rv ) = g(r1 , . . . , rv )
-    pub fn check(&self, target: Scalar) {
+    pub fn check(&self, target: F) {
         assert_eq!(
             self.v,
             self.cached_g_j.len(),
@@ -100,11 +113,8 @@ impl Verifier {
             self.challenges.len(),
             "length of challenges != (j-1)"
         );
-        let r_v = self.challenges.last().unwrap().clone() as u128;
-        let g_v = self.cached_g_j.last().unwrap().clone();
-        let actual = g_v.evaluate(Scalar::from_u128(r_v));
-
-        assert_eq!(actual, target, "Verifier rejected the proof");
+        // v = 0: g is a constant, there's no round, so C1 is g() itself.
+        assert_eq!(self.claim(), target, "Verifier rejected the proof");
         println!("Verifier accepted the proof");
     }
 }
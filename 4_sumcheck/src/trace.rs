@@ -0,0 +1,135 @@
+// The replay of a verification, to explain step by step why it failed (or passed), eg: of the
+// sum-check of `ni_sumcheck` or the batched LDT, which re-export this module.
+//
+// The verifier reports each check and challenge to a `TraceSink` in order. The plain `verify`
+// uses the no-op sink `()`, and `verify_with_trace` collects them into a `VerificationTrace`,
+// so both go through the same code path.
+//
+// The ops of the verifier are reported to `count` the same way, see `cost`.
+use crate::cost::CostReport;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use core::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceEvent {
+    ChallengeDerived {
+        name: &'static str,
+        round: usize,
+        value: Scalar,
+    },
+    QueryDerived {
+        query: usize,
+        index: usize,
+        ok: bool, // equal to the index claimed by the proof
+    },
+    RoundChecked {
+        round: usize,
+        lhs: Scalar,
+        rhs: Scalar,
+        ok: bool,
+    },
+    MerkleChecked {
+        round: usize,
+        index: usize,
+        ok: bool,
+    },
+    FinalChecked {
+        lhs: Scalar,
+        rhs: Scalar,
+        ok: bool,
+    },
+}
+
+impl TraceEvent {
+    pub fn is_ok(&self) -> bool {
+        match self {
+            TraceEvent::ChallengeDerived { .. } => true,
+            TraceEvent::QueryDerived { ok, .. }
+            | TraceEvent::RoundChecked { ok, .. }
+            | TraceEvent::MerkleChecked { ok, .. }
+            | TraceEvent::FinalChecked { ok, .. } => *ok,
+        }
+    }
+}
+
+// Scalars are rendered by their canonical hex (big-endian), the same as `{:?}` of Scalar.
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cmp = |ok: &bool| if *ok { "==" } else { "!=" };
+        match self {
+            TraceEvent::ChallengeDerived { name, round, value } => {
+                write!(f, "challenge {name}_{round} = {value:?}")
+            }
+            TraceEvent::QueryDerived { query, index, ok } => {
+                let res = if *ok { "ok" } else { "mismatch" };
+                write!(f, "query {query}: index {index} {res}")
+            }
+            TraceEvent::RoundChecked { round, lhs, rhs, ok } => {
+                write!(f, "round {round}: {lhs:?} {} {rhs:?}", cmp(ok))
+            }
+            TraceEvent::MerkleChecked { round, index, ok } => {
+                let res = if *ok { "ok" } else { "failed" };
+                write!(f, "round {round}: merkle path of leaf {index} {res}")
+            }
+            TraceEvent::FinalChecked { lhs, rhs, ok } => {
+                write!(f, "final: {lhs:?} {} {rhs:?}", cmp(ok))
+            }
+        }
+    }
+}
+
+pub trait TraceSink {
+    fn record(&mut self, event: TraceEvent);
+
+    // The ops done by the verifier, which are dropped by default.
+    #[inline(always)]
+    fn count(&mut self, _cost: CostReport) {}
+}
+
+// The no-op sink.
+impl TraceSink for () {
+    fn record(&mut self, _event: TraceEvent) {}
+}
+
+// The cost only, the events are dropped.
+impl TraceSink for CostReport {
+    fn record(&mut self, _event: TraceEvent) {}
+
+    fn count(&mut self, cost: CostReport) {
+        *self += cost;
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerificationTrace {
+    pub events: Vec<TraceEvent>,
+}
+
+impl VerificationTrace {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            events: Vec::with_capacity(capacity),
+        }
+    }
+
+    // The first failed check, which is the reason of the rejection.
+    pub fn first_failure(&self) -> Option<&TraceEvent> {
+        self.events.iter().find(|e| !e.is_ok())
+    }
+}
+
+impl TraceSink for VerificationTrace {
+    fn record(&mut self, event: TraceEvent) {
+        self.events.push(event);
+    }
+}
+
+impl fmt::Display for VerificationTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for event in self.events.iter() {
+            writeln!(f, "{event}")?;
+        }
+        Ok(())
+    }
+}
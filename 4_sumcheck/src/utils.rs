@@ -1,10 +1,19 @@
+use crate::poly::multivar_poly::NotMultilinear;
+use alloc::vec;
+use alloc::vec::Vec;
 use bls12_381::Scalar;
+use ff::Field;
 
 // convert a num into its binary form
 // eg: 8 -> 1000, will output [1, 0, 0, 0]
+// num must fit in bit_len bits, so with bit_len = 0 only 0 is allowed, which outputs [].
 pub fn convert_to_binary(bit_len: &usize, num: usize) -> Vec<usize> {
-    let a: usize = 1 << bit_len;
-    assert!(a >= num);
+    assert!(
+        num < 1 << bit_len,
+        "{} doesn't fit in {} bits",
+        num,
+        bit_len
+    );
     (0..*bit_len)
         .map(|n| (num >> n) & 1)
         .rev()
@@ -17,6 +26,186 @@ pub fn convert_from_binary(num: &Vec<usize>) -> usize {
     num.iter().rev().enumerate().map(|(i, n)| n << i).sum()
 }
 
+// eq(x, y) = ∏(x_i * y_i + (1 − x_i) * (1 − y_i)), the MLE of the equality predicate.
+// On the hypercube, eq(x, y) = 1 iff x = y, otherwise 0.
+pub fn eq_eval(x: &[Scalar], y: &[Scalar]) -> Scalar {
+    assert_eq!(x.len(), y.len());
+    x.iter()
+        .zip(y.iter())
+        .map(|(x_i, y_i)| x_i * y_i + (Scalar::one() - x_i) * (Scalar::one() - y_i))
+        .fold(Scalar::one(), |acc, v| acc * v)
+}
+
+// eq(x, bits(num)), where bits(num) is the binary form of num with x.len() bits, MSB first,
+// which is the same as `convert_to_binary`.
+pub fn eq_eval_at_index(x: &[Scalar], num: usize) -> Scalar {
+    let bits = convert_to_binary(&x.len(), num);
+    x.iter()
+        .zip(bits)
+        .map(|(x_i, b)| if b == 1 { *x_i } else { Scalar::one() - x_i })
+        .fold(Scalar::one(), |acc, v| acc * v)
+}
+
+// The value at `index` is zero, which has no inverse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ZeroValue {
+    pub index: usize,
+}
+
+// Invert the values in place with Montgomery's trick: one inversion and 3(n-1) multiplications.
+//
+// A zero is an error rather than skipped, as it's a bug of the caller in most cases, eg: (x - z)
+// is zero iff the opening point z is in the domain, which needs a different formula anyway.
+// On error the values are left untouched, and `index` is the first zero.
+pub fn batch_invert(values: &mut [Scalar]) -> Result<(), ZeroValue> {
+    // prefix[i] = v_0 * ... * v_{i-1}
+    let mut prefix = Vec::with_capacity(values.len());
+    let mut acc = Scalar::one();
+    for (index, v) in values.iter().enumerate() {
+        if v == &Scalar::zero() {
+            return Err(ZeroValue { index });
+        }
+        prefix.push(acc);
+        acc *= v;
+    }
+
+    // acc = (v_0 * ... * v_i)^-1, then v_i^-1 = acc * prefix[i], from the last one.
+    let mut acc = acc.invert().unwrap();
+    for (v, p) in values.iter_mut().zip(prefix).rev() {
+        let inv = acc * p;
+        acc *= *v;
+        *v = inv;
+    }
+    Ok(())
+}
+
+// The slices shorter than this are folded serially, the rayon jobs cost more than they save.
+#[cfg(feature = "parallel")]
+const PAR_MIN_LEN: usize = 1 << 12;
+#[cfg(feature = "parallel")]
+const PAR_CHUNK_LEN: usize = 1 << 10;
+
+// y += c·x in place, eg: the γ-combinations of tables and codewords, which allocate nothing.
+pub fn axpy<F: Field>(y: &mut [F], c: F, x: &[F]) {
+    debug_assert_eq!(y.len(), x.len(), "length mismatch");
+    #[cfg(feature = "parallel")]
+    if y.len() >= PAR_MIN_LEN {
+        use rayon::prelude::*;
+        y.par_chunks_mut(PAR_CHUNK_LEN)
+            .zip(x.par_chunks(PAR_CHUNK_LEN))
+            .for_each(|(y, x)| axpy_serial(y, c, x));
+        return;
+    }
+    axpy_serial(y, c, x);
+}
+
+fn axpy_serial<F: Field>(y: &mut [F], c: F, x: &[F]) {
+    for (y, x) in y.iter_mut().zip(x.iter()) {
+        *y += c * *x;
+    }
+}
+
+// y = c·y in place.
+pub fn scale<F: Field>(y: &mut [F], c: F) {
+    #[cfg(feature = "parallel")]
+    if y.len() >= PAR_MIN_LEN {
+        use rayon::prelude::*;
+        y.par_chunks_mut(PAR_CHUNK_LEN)
+            .for_each(|y| y.iter_mut().for_each(|y| *y *= c));
+        return;
+    }
+    y.iter_mut().for_each(|y| *y *= c);
+}
+
+// The smallest index where x and y differ, eg: the first wrong entry of a table. The chunks are
+// compared in parallel, and the first chunk with a mismatch wins, so it's the index of the
+// serial one, wherever the other mismatches are.
+pub fn first_mismatch<F: Field>(x: &[F], y: &[F]) -> Option<usize> {
+    debug_assert_eq!(x.len(), y.len(), "length mismatch");
+    #[cfg(feature = "parallel")]
+    if x.len() >= PAR_MIN_LEN {
+        return first_mismatch_par(x, y);
+    }
+    first_mismatch_serial(x, y)
+}
+
+#[cfg(feature = "parallel")]
+fn first_mismatch_par<F: Field>(x: &[F], y: &[F]) -> Option<usize> {
+    use rayon::prelude::*;
+    x.par_chunks(PAR_CHUNK_LEN)
+        .zip(y.par_chunks(PAR_CHUNK_LEN))
+        .enumerate()
+        .find_map_first(|(k, (x, y))| first_mismatch_serial(x, y).map(|i| k * PAR_CHUNK_LEN + i))
+}
+
+fn first_mismatch_serial<F: Field>(x: &[F], y: &[F]) -> Option<usize> {
+    x.iter().zip(y.iter()).position(|(x, y)| x != y)
+}
+
+// [eq(r, w) for w in {0,1}^v], indexed by w in binary with MSB first, see `convert_to_binary`.
+// Each r_i doubles the table with t·r_i and t - t·r_i, so it's 2^v - 1 mults and subs in all.
+pub fn eq_table<F: Field>(r: &[F]) -> Vec<F> {
+    let mut table = Vec::with_capacity(1 << r.len());
+    table.push(F::ONE);
+    for r_i in r.iter() {
+        // from the last entry, so t_k is read before [2k, 2k+1] are written.
+        let len = table.len();
+        table.resize(2 * len, F::ZERO);
+        for k in (0..len).rev() {
+            let hi = table[k] * r_i;
+            table[2 * k] = table[k] - hi;
+            table[2 * k + 1] = hi;
+        }
+    }
+    table
+}
+
+// ∑ x_i·y_i, summed by chunks in parallel.
+pub fn inner_product<F: Field>(x: &[F], y: &[F]) -> F {
+    debug_assert_eq!(x.len(), y.len(), "length mismatch");
+    #[cfg(feature = "parallel")]
+    if x.len() >= PAR_MIN_LEN {
+        use rayon::prelude::*;
+        return x
+            .par_chunks(PAR_CHUNK_LEN)
+            .zip(y.par_chunks(PAR_CHUNK_LEN))
+            .map(|(x, y)| inner_product_serial(x, y))
+            .sum();
+    }
+    inner_product_serial(x, y)
+}
+
+fn inner_product_serial<F: Field>(x: &[F], y: &[F]) -> F {
+    x.iter()
+        .zip(y.iter())
+        .fold(F::ZERO, |acc, (x, y)| acc + *x * y)
+}
+
+// y += r·(x - y) in place, and return ∑ y_i of the folded y, in one pass over both, eg: the fold
+// of a table and the sum of the next round, see `EvalTable::bind_and_next_round`. Each chunk
+// folds and sums its own part in parallel, and the field adds are exact, so the sum is the serial
+// one whatever order the parts are added in.
+pub fn fold_and_sum<F: Field>(y: &mut [F], r: F, x: &[F]) -> F {
+    debug_assert_eq!(y.len(), x.len(), "length mismatch");
+    #[cfg(feature = "parallel")]
+    if y.len() >= PAR_MIN_LEN {
+        use rayon::prelude::*;
+        return y
+            .par_chunks_mut(PAR_CHUNK_LEN)
+            .zip(x.par_chunks(PAR_CHUNK_LEN))
+            .map(|(y, x)| fold_and_sum_serial(y, r, x))
+            .sum();
+    }
+    fold_and_sum_serial(y, r, x)
+}
+
+fn fold_and_sum_serial<F: Field>(y: &mut [F], r: F, x: &[F]) -> F {
+    y.iter_mut().zip(x.iter()).fold(F::ZERO, |acc, (y, x)| {
+        *y += r * (*x - *y);
+        acc + *y
+    })
+}
+
 // try to expand factorization form to coeffs form for `uni-variable poly`
 // For now, we'll only support two factorizations to a coeffs.
 // eg: (4x^2 + 1)(x + 4) = 4x^3 + 4x^2 + x + 4
@@ -37,30 +226,56 @@ fn expand_factor_for_upoly(lhs: Vec<Scalar>, rhs: Vec<Scalar>) -> Vec<Scalar> {
 // For now, we'll only support two factorizations to a coeffs.
 // eg: f(x1,x2) = (1+x1) * (1−x2) = 1 + x1 - x2 - x1x2
 //
-// NOTE!!! Only support var_num=2, which mul form such as: (a+bx1) * (c+dx2) = ac + bc*x1 + ad*x2 + bd*x1*x2
-pub fn expand_factor_for_mpoly(var_num: usize, lhs: Vec<Scalar>, rhs: Vec<Scalar>) -> Vec<Scalar> {
+// The index of a term is its exps in binary, so the product of two terms is indexed by n | m,
+// which is right only if they share no variable. Otherwise x_i * x_i = x_i^2 can't be encoded,
+// and the factors are rejected with the first shared variable, eg: (1+x1) * (1-x1).
+pub fn expand_factor_for_mpoly<F: Field>(
+    var_num: usize,
+    lhs: Vec<F>,
+    rhs: Vec<F>,
+) -> Result<Vec<F>, NotMultilinear> {
     let target_len = 1 << var_num;
     assert_eq!(target_len, lhs.len());
     assert_eq!(target_len, rhs.len());
 
-    let mut product = vec![Scalar::zero(); target_len];
+    let mut product = vec![F::ZERO; target_len];
 
     for (n, l) in lhs.iter().enumerate() {
+        if l == &F::ZERO {
+            continue;
+        }
         for (m, r) in rhs.iter().enumerate() {
-            product[n | m] += l * r;
+            if r == &F::ZERO {
+                continue;
+            }
+            let shared = n & m;
+            if shared != 0 {
+                // x_1 is the MSB of the index.
+                let var = var_num - 1 - (usize::BITS - 1 - shared.leading_zeros()) as usize;
+                return Err(NotMultilinear { var });
+            }
+            product[n | m] += *l * r;
         }
     }
-    product
+    Ok(product)
 }
 
 #[cfg(test)]
 mod test {
+    use crate::poly::eval_table::EvalTable;
+    use crate::poly::multivar_poly::{MPolynomial, NotMultilinear};
+    use crate::poly::univar_poly::Polynomial;
     use crate::utils::{
-        convert_from_binary, convert_to_binary, expand_factor_for_mpoly, expand_factor_for_upoly,
+        axpy, batch_invert, convert_from_binary, convert_to_binary, eq_eval, eq_eval_at_index,
+        eq_table, expand_factor_for_mpoly, expand_factor_for_upoly, first_mismatch,
+        first_mismatch_serial, fold_and_sum, fold_and_sum_serial, inner_product, scale,
+        ZeroValue,
     };
     use bls12_381::Scalar;
-    use ff::PrimeField;
-    use rayon::scope;
+    use ff::{Field, PrimeField};
+    use rand_core::OsRng;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
 
     #[test]
     fn test_expand_factor_for_upoly() {
@@ -101,11 +316,37 @@ mod test {
             Scalar::one().neg(),
         ];
 
-        let actual = expand_factor_for_mpoly(var_num, poly_one, poly_two);
+        let actual = expand_factor_for_mpoly(var_num, poly_one, poly_two).unwrap();
         assert_eq!(target, actual);
         println!("{:?}", actual);
     }
 
+    #[test]
+    fn test_expand_factor_for_mpoly_overlapping() {
+        let var_num: usize = 2;
+
+        // (1+x1) * (2-x1) = 2 + x1 - x1^2, x1^2 is out of the multilinear form.
+        let poly_one = vec![Scalar::one(), Scalar::zero(), Scalar::one(), Scalar::zero()];
+        let poly_two = vec![
+            Scalar::from_u128(2),
+            Scalar::zero(),
+            Scalar::one().neg(),
+            Scalar::zero(),
+        ];
+        assert_eq!(
+            expand_factor_for_mpoly(var_num, poly_one, poly_two),
+            Err(NotMultilinear { var: 0 })
+        );
+
+        // x2 * (x1 * x2)
+        let poly_one = vec![Scalar::zero(), Scalar::one(), Scalar::zero(), Scalar::zero()];
+        let poly_two = vec![Scalar::zero(), Scalar::zero(), Scalar::zero(), Scalar::one()];
+        assert_eq!(
+            expand_factor_for_mpoly(var_num, poly_one, poly_two),
+            Err(NotMultilinear { var: 1 })
+        );
+    }
+
     #[test]
     fn test_convert() {
         let raw: Vec<usize> = vec![1, 2, 3, 4, 8, 17];
@@ -123,6 +364,25 @@ mod test {
         assert_eq!(new_nums, raw);
     }
 
+    #[test]
+    fn test_convert_zero_width() {
+        assert_eq!(convert_to_binary(&0, 0), Vec::<usize>::new());
+        assert_eq!(convert_from_binary(&vec![]), 0);
+        assert_eq!(eq_eval_at_index(&[], 0), Scalar::one());
+    }
+
+    #[test]
+    #[should_panic(expected = "1 doesn't fit in 0 bits")]
+    fn test_convert_zero_width_rejects() {
+        convert_to_binary(&0, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "8 doesn't fit in 3 bits")]
+    fn test_convert_overflow_rejects() {
+        convert_to_binary(&3, 8);
+    }
+
     #[test]
     fn test_convert_into_binary() {
         for i in [1, 2, 3, 4, 8, 17] {
@@ -137,4 +397,196 @@ mod test {
         let raw = convert_from_binary(&binary);
         println!("{:?} ->  {:?}", raw, binary);
     }
+
+    #[test]
+    fn test_eq_eval() {
+        let bit_len = 3;
+        let to_scalars = |bits: Vec<usize>| {
+            bits.iter()
+                .map(|b| Scalar::from(*b as u64))
+                .collect::<Vec<_>>()
+        };
+        for i in 0..(1 << bit_len) {
+            let x = to_scalars(convert_to_binary(&bit_len, i));
+            for j in 0..(1 << bit_len) {
+                let y = to_scalars(convert_to_binary(&bit_len, j));
+                let expected = if i == j { Scalar::one() } else { Scalar::zero() };
+                assert_eq!(eq_eval(&x, &y), expected);
+                assert_eq!(eq_eval_at_index(&x, j), expected);
+            }
+        }
+
+        // eq(x, bits(num)) on a random point
+        let x = vec![Scalar::from(5), Scalar::from(7), Scalar::from(11)];
+        let bits = to_scalars(convert_to_binary(&bit_len, 6));
+        assert_eq!(eq_eval(&x, &bits), eq_eval_at_index(&x, 6));
+    }
+
+    #[test]
+    fn test_eq_table() {
+        assert_eq!(eq_table::<Scalar>(&[]), vec![Scalar::one()]);
+        let r = (0..5).map(|_| Scalar::random(OsRng)).collect::<Vec<_>>();
+        let table = eq_table(&r);
+        assert_eq!(table.len(), 1 << 5);
+        for (index, e) in table.iter().enumerate() {
+            assert_eq!(*e, eq_eval_at_index(&r, index));
+        }
+        assert_eq!(table.iter().sum::<Scalar>(), Scalar::one());
+    }
+
+    #[test]
+    fn test_inner_product() {
+        // over 2 chunks and a half with `parallel`
+        let n = 5 << 10;
+        let x = (0..n).map(|_| Scalar::random(OsRng)).collect::<Vec<_>>();
+        let y = (0..n).map(|_| Scalar::random(OsRng)).collect::<Vec<_>>();
+        let expected = x.iter().zip(y.iter()).map(|(x, y)| x * y).sum::<Scalar>();
+        assert_eq!(inner_product(&x, &y), expected);
+        assert_eq!(inner_product::<Scalar>(&[], &[]), Scalar::zero());
+    }
+
+    #[test]
+    fn test_fold_and_sum() {
+        // over 2 chunks and a half with `parallel`, which folds and sums as the serial one.
+        let n = 5 << 10;
+        let x = (0..n).map(|_| Scalar::random(OsRng)).collect::<Vec<_>>();
+        let y = (0..n).map(|_| Scalar::random(OsRng)).collect::<Vec<_>>();
+        let r = Scalar::random(OsRng);
+        let expected = y
+            .iter()
+            .zip(x.iter())
+            .map(|(y, x)| y + r * (x - y))
+            .collect::<Vec<_>>();
+
+        let (mut folded, mut serial) = (y.clone(), y);
+        let sum = fold_and_sum(&mut folded, r, &x);
+        assert_eq!(sum, fold_and_sum_serial(&mut serial, r, &x));
+        assert_eq!((&folded, &serial), (&expected, &expected));
+        assert_eq!(sum, expected.iter().sum::<Scalar>());
+        assert_eq!(fold_and_sum::<Scalar>(&mut [], r, &[]), Scalar::zero());
+    }
+
+    #[test]
+    fn test_batch_invert() {
+        let values = (0..100).map(|_| Scalar::random(OsRng)).collect::<Vec<_>>();
+        let mut inverses = values.clone();
+        batch_invert(&mut inverses).unwrap();
+        for (v, inv) in values.iter().zip(inverses.iter()) {
+            assert_eq!(*inv, v.invert().unwrap());
+        }
+
+        let mut empty: Vec<Scalar> = vec![];
+        assert_eq!(batch_invert(&mut empty), Ok(()));
+    }
+
+    #[test]
+    fn test_batch_invert_zero() {
+        let mut values = vec![
+            Scalar::from(3),
+            Scalar::zero(),
+            Scalar::from(5),
+            Scalar::zero(),
+        ];
+        let before = values.clone();
+        assert_eq!(batch_invert(&mut values), Err(ZeroValue { index: 1 }));
+        assert_eq!(values, before);
+    }
+
+    // Counts the allocations of each thread, so the tests running in parallel don't interfere.
+    struct CountingAlloc;
+
+    thread_local! {
+        static ALLOCS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCS.try_with(|n| n.set(n.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: CountingAlloc = CountingAlloc;
+
+    fn allocs_of(f: impl FnOnce()) -> usize {
+        let before = ALLOCS.with(|n| n.get());
+        f();
+        ALLOCS.with(|n| n.get()) - before
+    }
+
+    #[test]
+    fn test_axpy() {
+        let x = (0..100).map(|_| Scalar::random(OsRng)).collect::<Vec<_>>();
+        let mut y = (0..100).map(|_| Scalar::random(OsRng)).collect::<Vec<_>>();
+        let c = Scalar::random(OsRng);
+        let expected = y
+            .iter()
+            .zip(x.iter())
+            .map(|(y, x)| c * (y + c * x))
+            .collect::<Vec<_>>();
+        axpy(&mut y, c, &x);
+        scale(&mut y, c);
+        assert_eq!(y, expected);
+    }
+
+    #[test]
+    fn test_axpy_allocates_nothing() {
+        // smaller than the parallel threshold, so it's the serial path with `parallel` too.
+        let random = |n: usize| (0..n).map(|_| Scalar::random(OsRng)).collect::<Vec<_>>();
+        let c = Scalar::random(OsRng);
+
+        let (mut t, u) = (
+            EvalTable::new(random(1 << 10)),
+            EvalTable::new(random(1 << 10)),
+        );
+        assert_eq!(allocs_of(|| t.axpy(c, &u)), 0);
+        assert_eq!(allocs_of(|| t.scale_in_place(c)), 0);
+
+        let mpoly = |coeffs| MPolynomial {
+            var_num: 10,
+            coeffs,
+        };
+        let (mut f, g) = (mpoly(random(1 << 10)), mpoly(random(1 << 10)));
+        assert_eq!(allocs_of(|| f.axpy(c, &g)), 0);
+        assert_eq!(allocs_of(|| f.scale_in_place(c)), 0);
+
+        // p is only extended when q is longer
+        let (mut p, q) = (
+            Polynomial::from_coeffs(random(1 << 10)),
+            Polynomial::from_coeffs(random(1 << 9)),
+        );
+        assert_eq!(allocs_of(|| p.axpy(c, &q)), 0);
+        assert_eq!(allocs_of(|| p.scale_in_place(c)), 0);
+
+        // while the operators allocate the intermediates and the result
+        assert_eq!(
+            allocs_of(|| {
+                let _ = &p + &(&q * &c);
+            }),
+            2
+        );
+    }
+
+    #[test]
+    fn test_first_mismatch() {
+        let x = (0..1 << 13)
+            .map(|_| Scalar::random(OsRng))
+            .collect::<Vec<_>>();
+        assert_eq!(first_mismatch(&x, &x), None);
+        // in 2 chunks, then in the same chunk, the later chunk may be done first.
+        for (a, b) in [(5000, 1500), (1100, 1030), (0, 1 << 12)] {
+            let mut y = x.clone();
+            y[a] += Scalar::one();
+            y[b] += Scalar::one();
+            assert_eq!(first_mismatch_serial(&x, &y), Some(a.min(b)));
+            assert_eq!(first_mismatch(&x, &y), Some(a.min(b)));
+            #[cfg(feature = "parallel")]
+            assert_eq!(super::first_mismatch_par(&x, &y), Some(a.min(b)));
+        }
+    }
 }
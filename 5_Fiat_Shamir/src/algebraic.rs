@@ -0,0 +1,208 @@
+// A toy algebraic sponge over the scalars of BLS12-381, for the experiments of a verifier whose
+// transcript is expressed as an arithmetic circuit, which Keccak is too costly for.
+//
+// NOT PRODUCTION-PARAMETERIZED: the shape is the one of Poseidon, but the round numbers, the
+// constants and the matrix are picked here without any security analysis, so the challenges are
+// only as sound as a sponge nobody has attacked. Use `Keccak256Sponge` for real proofs.
+//
+// `ToyPoseidon` permutes a state of WIDTH = 3 scalars, RATE = 2 of them take the input and the
+// last one is the capacity. Each of its R_F + R_P rounds is:
+//      1. add the round constants: s_i += c_{round, i}
+//      2. the S-box x^5, on every s_i in the R_F/2 full rounds at each end, on s_0 only in the
+//         R_P partial rounds between them. x^5 is a permutation as gcd(5, r - 1) = 1.
+//      3. mix by the MDS matrix: s = M · s, where M_ij = 1/(i + j + 3), a Cauchy matrix.
+// The constants are `expand_challenge_vector` of the seed Keccak256("toy_poseidon.constants"),
+// so they are fixed, see `test_permutation_kat`.
+//
+// `AlgebraicSponge` is the duplex over it: each absorbed scalar is added into the next rate
+// element, and the state is permuted once the rate is full. A squeeze pads the input with a 1
+// (the 10* padding), permutes, and returns s_0. Each message starts with a header scalar, the
+// length with the kind of the message, so the stream of messages is unambiguous:
+//      scalars: n + 2^64, then the n scalars as they are
+//      bytes:   len, then the bytes in chunks of 31, each read as a little-endian scalar
+// The scalars are absorbed natively, so absorbing a scalar differs from absorbing its canonical
+// bytes, see `test_native_absorb`.
+use crate::codec::encode_scalars;
+use crate::expand_challenge_vector;
+use crate::sponge::Sponge;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use sha3::{Digest, Keccak256};
+
+pub const WIDTH: usize = 3;
+pub const RATE: usize = 2;
+
+// The permutation of an algebraic sponge, eg: `ToyPoseidon`.
+pub trait Permutation: Clone + Default {
+    fn permute(&self, state: &mut [Scalar; WIDTH]);
+}
+
+const FULL_ROUNDS: usize = 8;
+const PARTIAL_ROUNDS: usize = 57;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ToyPoseidon {
+    constants: Vec<[Scalar; WIDTH]>, // c_round, of R_F + R_P rounds
+    mds: [[Scalar; WIDTH]; WIDTH],
+}
+
+impl Default for ToyPoseidon {
+    fn default() -> Self {
+        let seed: [u8; 32] = Keccak256::digest(b"toy_poseidon.constants").into();
+        let constants = expand_challenge_vector(&seed, (FULL_ROUNDS + PARTIAL_ROUNDS) * WIDTH)
+            .chunks(WIDTH)
+            .map(|c| [c[0], c[1], c[2]])
+            .collect();
+        let mut mds = [[Scalar::zero(); WIDTH]; WIDTH];
+        for (i, row) in mds.iter_mut().enumerate() {
+            for (j, m) in row.iter_mut().enumerate() {
+                *m = Scalar::from((i + j + WIDTH) as u64).invert().unwrap();
+            }
+        }
+        Self { constants, mds }
+    }
+}
+
+fn sbox(x: &mut Scalar) {
+    *x *= x.square().square();
+}
+
+impl Permutation for ToyPoseidon {
+    fn permute(&self, state: &mut [Scalar; WIDTH]) {
+        let half = FULL_ROUNDS / 2;
+        for (round, constants) in self.constants.iter().enumerate() {
+            for (s, c) in state.iter_mut().zip(constants.iter()) {
+                *s += c;
+            }
+            if round < half || round >= half + PARTIAL_ROUNDS {
+                state.iter_mut().for_each(sbox);
+            } else {
+                sbox(&mut state[0]);
+            }
+            let mixed = self.mds.map(|row| {
+                row.iter()
+                    .zip(state.iter())
+                    .map(|(m, s)| m * s)
+                    .sum::<Scalar>()
+            });
+            *state = mixed;
+        }
+    }
+}
+
+// The duplex sponge over the permutation, see above.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AlgebraicSponge<P: Permutation = ToyPoseidon> {
+    permutation: P,
+    state: [Scalar; WIDTH],
+    pos: usize, // the next rate element to absorb into
+}
+
+// 2^64, the header of the scalars is n + 2^64, and the one of the bytes is len < 2^64.
+fn scalars_tag() -> Scalar {
+    Scalar::from(u64::MAX) + Scalar::one()
+}
+
+impl<P: Permutation> AlgebraicSponge<P> {
+    fn absorb_one(&mut self, x: Scalar) {
+        self.state[self.pos] += x;
+        self.pos += 1;
+        if self.pos == RATE {
+            self.permutation.permute(&mut self.state);
+            self.pos = 0;
+        }
+    }
+}
+
+impl<P: Permutation> Sponge for AlgebraicSponge<P> {
+    const FIELD_NATIVE: bool = true;
+
+    fn absorb(&mut self, bytes: &[u8]) {
+        self.absorb_one(Scalar::from(bytes.len() as u64));
+        for chunk in bytes.chunks(31) {
+            let mut le = [0u8; 32];
+            le[..chunk.len()].copy_from_slice(chunk);
+            // 31 bytes are less than 2^248 < r, so it's always canonical.
+            self.absorb_one(Scalar::from_bytes(&le).unwrap());
+        }
+    }
+
+    fn absorb_scalars(&mut self, scalars: &[Scalar]) {
+        self.absorb_one(Scalar::from(scalars.len() as u64) + scalars_tag());
+        for s in scalars.iter() {
+            self.absorb_one(*s);
+        }
+    }
+
+    fn squeeze_scalar(&mut self) -> Scalar {
+        self.state[self.pos] += Scalar::one();
+        self.permutation.permute(&mut self.state);
+        self.pos = 0;
+        self.state[0]
+    }
+
+    // The canonical bytes of the squeezed scalar, eg: for an index of the queries.
+    fn squeeze(&mut self) -> [u8; 32] {
+        self.squeeze_scalar().to_bytes()
+    }
+
+    // The state and the position.
+    fn state_bytes(&self) -> Vec<u8> {
+        let mut bytes = encode_scalars(&self.state);
+        bytes.push(self.pos as u8);
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::default::PoseidonTranscript;
+    use crate::test_vectors::to_hex;
+    use crate::Transcript;
+
+    #[test]
+    fn test_permutation_kat() {
+        let mut state = [Scalar::zero(), Scalar::one(), Scalar::from(2u64)];
+        ToyPoseidon::default().permute(&mut state);
+        let hex = state.map(|s| to_hex(&s.to_bytes()));
+        assert_eq!(hex, PERMUTATION_HEX);
+    }
+
+    // The sponge absorbs the scalars as they are, which is not the same as their bytes.
+    #[test]
+    fn test_native_absorb() {
+        let s = Scalar::from(7u64);
+        let mut native = PoseidonTranscript::default();
+        native.append_scalars(&[s]);
+        let mut bytes = PoseidonTranscript::default();
+        bytes.append(&s.to_bytes());
+        let r = native.challenge_scalar();
+        assert_ne!(r, bytes.challenge_scalar());
+        assert_eq!(to_hex(&r.to_bytes()), NATIVE_ABSORB_HEX);
+    }
+
+    // The header tells the messages apart, eg: [x] then [] is not [x, 1].
+    #[test]
+    fn test_messages_are_framed() {
+        let squeeze = |messages: &[&[Scalar]]| {
+            let mut sponge = AlgebraicSponge::<ToyPoseidon>::default();
+            for m in messages {
+                sponge.absorb_scalars(m);
+            }
+            sponge.squeeze_scalar()
+        };
+        let (x, one) = (Scalar::from(5u64), Scalar::one());
+        assert_ne!(squeeze(&[&[x], &[]]), squeeze(&[&[x, one]]));
+        assert_ne!(squeeze(&[&[x]]), squeeze(&[&[x, Scalar::zero()]]));
+        assert_ne!(squeeze(&[&[]]), squeeze(&[]));
+    }
+
+    const PERMUTATION_HEX: [&str; 3] = [
+        "edb79be7fbffa85f4124b09422472ccc85be162bc6871c069ea4c08bac7e3b1b",
+        "a1111626db0be69960bc905dd0bcf43d45e7a1c46710e4f7f449b6d338dc4618",
+        "e2bbda8b53004f44de5eae9d4e8c0fc16d6937fb63f6025020949a84ff5f1a25",
+    ];
+    const NATIVE_ABSORB_HEX: &str =
+        "0567ce91768fbe4d89145a6a71098cf7520036c417a5ac757ee8a8d2e96a806b";
+}
@@ -0,0 +1,343 @@
+// The audit log of a transcript, to tell a transcript divergence from a bad proof when P and V
+// run apart, eg: as two processes exchanging files.
+//
+// With `Keccak256Transcript::with_audit`, each append and challenge records an entry:
+//
+//      | field | encoding                                                               |
+//      |-------|------------------------------------------------------------------------|
+//      | op    | 1 byte, 0 for append and 1 for challenge                               |
+//      | label | bytes, the label of `append_labeled`, empty for `append` and challenge |
+//      | len   | u64, the length of the message, or 32 for a challenge                  |
+//      | state | digest, keccak(labeled("audit.state", absorbed)) after the op          |
+//
+// and the log is the version byte AUDIT_LOG_VERSION followed by len: u64 and the entries.
+//
+// The state digest is hashed from the absorbed bytes as they are, it never touches the transcript,
+// so the challenges are the same with or without the audit. P sends its log along with the proof,
+// and V compares it with its own log, see `compare_audits`:
+//      Identical: the same messages in the same order, a failed verification is a bad proof.
+//      Truncated: the same messages until one of them stops, eg: V at a failed check.
+//      Diverged:  the first entry where the label, the length or the state differ.
+use crate::codec::{encode_bytes, encode_digest, encode_labeled, encode_u64};
+use crate::codec::{CodecError, Decoder};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use sha3::{Digest, Keccak256};
+
+pub const AUDIT_LOG_VERSION: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditOp {
+    Append,
+    Challenge,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEntry {
+    pub op: AuditOp,
+    pub label: String,
+    pub len: usize,
+    pub state: [u8; 32],
+}
+
+impl fmt::Display for AuditEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.op {
+            AuditOp::Append => write!(f, "append `{}` ({} bytes)", self.label, self.len),
+            AuditOp::Challenge => write!(f, "challenge"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditLog {
+    pub entries: Vec<AuditEntry>,
+}
+
+// keccak(labeled("audit.state", absorbed)), a digest of the state rather than the next challenge.
+pub(crate) fn state_digest(absorbed: &[u8]) -> [u8; 32] {
+    Keccak256::digest(encode_labeled("audit.state", absorbed)).into()
+}
+
+impl AuditLog {
+    pub(crate) fn record(&mut self, op: AuditOp, label: &str, len: usize, absorbed: &[u8]) {
+        self.entries.push(AuditEntry {
+            op,
+            label: label.to_string(),
+            len,
+            state: state_digest(absorbed),
+        });
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(1 + 8 + self.entries.len() * (1 + 8 + 8 + 32));
+        bytes.push(AUDIT_LOG_VERSION);
+        bytes.extend(encode_u64(self.entries.len() as u64));
+        for e in self.entries.iter() {
+            bytes.push(match e.op {
+                AuditOp::Append => 0,
+                AuditOp::Challenge => 1,
+            });
+            bytes.extend(encode_bytes(e.label.as_bytes()));
+            bytes.extend(encode_u64(e.len as u64));
+            bytes.extend(encode_digest(&e.state));
+        }
+        bytes
+    }
+
+    // The label is read as utf-8 lossily, it's for the report only.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        let mut decoder = Decoder::new(bytes);
+        decoder.read_version(AUDIT_LOG_VERSION)?;
+        // op, len(label), len and state.
+        let len = decoder.read_len(1 + 8 + 8 + 32)?;
+        let mut entries = Vec::with_capacity(len);
+        for _ in 0..len {
+            let op = match decoder.read_u8()? {
+                0 => AuditOp::Append,
+                1 => AuditOp::Challenge,
+                // an unknown op, as the log is of another version.
+                v => return Err(CodecError::UnknownVersion(v)),
+            };
+            entries.push(AuditEntry {
+                op,
+                label: String::from_utf8_lossy(&decoder.read_bytes()?).to_string(),
+                len: decoder.read_usize()?,
+                state: decoder.read_digest()?,
+            });
+        }
+        decoder.finish()?;
+        Ok(Self { entries })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditDiff {
+    Identical,
+    // the shorter log stops at len, and the longer one goes on with the same entries before.
+    Truncated {
+        len: usize,
+    },
+    // the first entry which differs.
+    Diverged {
+        index: usize,
+        prover: AuditEntry,
+        verifier: AuditEntry,
+    },
+}
+
+impl AuditDiff {
+    // P and V absorbed different messages, or the same ones in a different order.
+    pub fn is_divergence(&self) -> bool {
+        matches!(self, AuditDiff::Diverged { .. })
+    }
+}
+
+impl fmt::Display for AuditDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuditDiff::Identical => write!(f, "The transcripts are identical"),
+            AuditDiff::Truncated { len } => {
+                write!(
+                    f,
+                    "The transcripts are identical until one stops at {}",
+                    len
+                )
+            }
+            AuditDiff::Diverged {
+                index,
+                prover,
+                verifier,
+            } => write!(
+                f,
+                "The transcripts diverge at {}: prover {}, verifier {}",
+                index, prover, verifier
+            ),
+        }
+    }
+}
+
+// The first index where the op, the label, the length or the state differ.
+pub fn compare_audits(prover_log: &AuditLog, verifier_log: &AuditLog) -> AuditDiff {
+    let (p, v) = (&prover_log.entries, &verifier_log.entries);
+    match p.iter().zip(v.iter()).position(|(a, b)| a != b) {
+        Some(index) => AuditDiff::Diverged {
+            index,
+            prover: p[index].clone(),
+            verifier: v[index].clone(),
+        },
+        None if p.len() == v.len() => AuditDiff::Identical,
+        None => AuditDiff::Truncated {
+            len: p.len().min(v.len()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::audit::{compare_audits, AuditDiff, AuditLog, AuditOp};
+    use crate::codec::{encode_scalar, encode_scalars, CodecError};
+    use crate::default::Keccak256Transcript;
+    use crate::Transcript;
+    use bls12_381::Scalar;
+    use ff::Field;
+    use rand_core::OsRng;
+
+    fn random_table(var_num: usize) -> Vec<Scalar> {
+        (0..1 << var_num).map(|_| Scalar::random(OsRng)).collect()
+    }
+
+    // bind the MSB to r, eg: the table of the next round.
+    fn fold(table: &[Scalar], r: Scalar) -> Vec<Scalar> {
+        let (lo, hi) = table.split_at(table.len() / 2);
+        lo.iter()
+            .zip(hi.iter())
+            .map(|(l, h)| l + r * (h - l))
+            .collect()
+    }
+
+    // A toy sum-check of the table, P sends g_j = (g_j(0), g_j(1)) in each round.
+    // A buggy P adds one to g_j(0) in the round `bug`, after which the proof is wrong.
+    fn prove(
+        table: &[Scalar],
+        bug: Option<usize>,
+        transcript: &mut Keccak256Transcript,
+    ) -> (Scalar, Vec<Vec<Scalar>>) {
+        let sum = table.iter().sum::<Scalar>();
+        transcript.append_labeled("toy.claim", &encode_scalar(&sum));
+        let mut cur = table.to_vec();
+        let mut rounds = vec![];
+        while cur.len() > 1 {
+            let (lo, hi) = cur.split_at(cur.len() / 2);
+            let mut g = vec![lo.iter().sum(), hi.iter().sum()];
+            if bug == Some(rounds.len()) {
+                g[0] += Scalar::one();
+            }
+            transcript.append_labeled("toy.round", &encode_scalars(&g));
+            cur = fold(&cur, transcript.challenge_scalar());
+            rounds.push(g);
+        }
+        (sum, rounds)
+    }
+
+    // V squeezes all the challenges before the checks, so it absorbs the whole proof even if the
+    // proof is rejected.
+    fn verify(
+        table: &[Scalar],
+        sum: Scalar,
+        rounds: &[Vec<Scalar>],
+        transcript: &mut Keccak256Transcript,
+    ) -> bool {
+        transcript.append_labeled("toy.claim", &encode_scalar(&sum));
+        let challenges = rounds
+            .iter()
+            .map(|g| {
+                transcript.append_labeled("toy.round", &encode_scalars(g));
+                transcript.challenge_scalar()
+            })
+            .collect::<Vec<_>>();
+
+        let mut claim = sum;
+        let mut cur = table.to_vec();
+        for (g, r) in rounds.iter().zip(challenges) {
+            if g[0] + g[1] != claim {
+                return false;
+            }
+            claim = g[0] + r * (g[1] - g[0]);
+            cur = fold(&cur, r);
+        }
+        cur[0] == claim
+    }
+
+    #[test]
+    fn test_identical_runs() {
+        let table = random_table(4);
+        let mut p_1 = Keccak256Transcript::default().with_audit();
+        let mut p_2 = Keccak256Transcript::default().with_audit();
+        let (sum, rounds) = prove(&table, None, &mut p_1);
+        assert_eq!(prove(&table, None, &mut p_2), (sum, rounds.clone()));
+        assert_eq!(p_1.audit(), p_2.audit());
+
+        let mut v = Keccak256Transcript::default().with_audit();
+        assert!(verify(&table, sum, &rounds, &mut v));
+        let (p_log, v_log) = (p_1.audit().unwrap(), v.audit().unwrap());
+        assert_eq!(compare_audits(p_log, v_log), AuditDiff::Identical);
+        // the claim, then an append and the two squeezes of `challenge_scalar` per round.
+        assert_eq!(p_log.entries.len(), 1 + 3 * 4);
+        assert_eq!(p_log.entries[1].label, "toy.round");
+        assert_eq!(p_log.entries[2].op, AuditOp::Challenge);
+        assert_eq!(p_log.entries[3].op, AuditOp::Challenge);
+
+        // P sends the log with the proof.
+        let bytes = p_log.to_bytes();
+        assert_eq!(AuditLog::from_bytes(&bytes).as_ref(), Ok(p_log));
+        assert_eq!(
+            AuditLog::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(CodecError::UnexpectedEnd)
+        );
+
+        // the audit doesn't change the challenges.
+        let mut plain = Keccak256Transcript::default();
+        assert_eq!(prove(&table, None, &mut plain), (sum, rounds));
+        assert_eq!(plain.audit(), None);
+        assert_eq!(plain.challenge(), p_1.challenge());
+    }
+
+    #[test]
+    fn test_reordered_absorbs() {
+        let (a, b) = (Scalar::random(OsRng), Scalar::random(OsRng));
+        let mut p = Keccak256Transcript::default().with_audit();
+        let mut v = Keccak256Transcript::default().with_audit();
+        for t in [&mut p, &mut v] {
+            t.append_labeled("toy.claim", &encode_scalar(&a));
+            t.challenge();
+        }
+        p.append_labeled("toy.alpha", &encode_scalar(&a));
+        p.append_labeled("toy.beta", &encode_scalars(&[b]));
+        v.append_labeled("toy.beta", &encode_scalars(&[b]));
+        v.append_labeled("toy.alpha", &encode_scalar(&a));
+
+        let diff = compare_audits(p.audit().unwrap(), v.audit().unwrap());
+        assert!(diff.is_divergence());
+        match &diff {
+            AuditDiff::Diverged {
+                index,
+                prover,
+                verifier,
+            } => {
+                assert_eq!(*index, 2);
+                assert_eq!((prover.label.as_str(), prover.len), ("toy.alpha", 32));
+                assert_eq!((verifier.label.as_str(), verifier.len), ("toy.beta", 40));
+            }
+            _ => panic!("expect a divergence, got {:?}", diff),
+        }
+        assert_eq!(
+            format!("{}", diff),
+            "The transcripts diverge at 2: prover append `toy.alpha` (32 bytes), \
+            verifier append `toy.beta` (40 bytes)"
+        );
+    }
+
+    #[test]
+    fn test_bad_round_poly() {
+        let table = random_table(4);
+        let mut p = Keccak256Transcript::default().with_audit();
+        let (sum, rounds) = prove(&table, Some(2), &mut p);
+
+        // the same transcript, so it's the proof which is wrong.
+        let mut v = Keccak256Transcript::default().with_audit();
+        assert!(!verify(&table, sum, &rounds, &mut v));
+        let diff = compare_audits(p.audit().unwrap(), v.audit().unwrap());
+        assert_eq!(diff, AuditDiff::Identical);
+        assert!(!diff.is_divergence());
+
+        // V stops after the round 2.
+        let mut log = v.audit().unwrap().clone();
+        log.entries.truncate(1 + 2 * 3);
+        assert_eq!(
+            compare_audits(p.audit().unwrap(), &log),
+            AuditDiff::Truncated { len: 7 }
+        );
+    }
+}
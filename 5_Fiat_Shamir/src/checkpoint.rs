@@ -0,0 +1,153 @@
+// The state of a verifier between two rounds, so a long verification can be split, eg: one
+// round per step of a recursive or an on-chain verifier, and resumed from the bytes later.
+//
+//      | field      | encoding                                                   |
+//      |------------|------------------------------------------------------------|
+//      | version    | 1 byte, CHECKPOINT_VERSION                                 |
+//      | binding    | digest, `proof_binding` of the proof being verified        |
+//      | round      | u64, the next round to verify                              |
+//      | transcript | bytes, eg: `Keccak256Transcript::snapshot`, maybe empty    |
+//      | claims     | scalars, the running claims of the verifier                |
+//
+// The binding ties a checkpoint to one proof: resuming with the rest of another proof is
+// rejected, rather than mixing the challenges of a proof with the rounds of another one.
+// The checkpoint itself is not authenticated, it's trusted as the verifier's own state.
+use crate::codec::{encode_bytes, encode_digest, encode_labeled, encode_scalars, encode_u64};
+use crate::codec::{CodecError, Decoder};
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use core::fmt;
+use sha3::{Digest, Keccak256};
+
+pub const CHECKPOINT_VERSION: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckpointError {
+    Decode(CodecError),
+    // The checkpoint is of another proof.
+    WrongProof,
+    // The round or the claims don't fit the verifier, eg: a round after the last one.
+    WrongShape,
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::Decode(_) => write!(f, "Can't decode the checkpoint"),
+            CheckpointError::WrongProof => write!(f, "The checkpoint is of another proof"),
+            CheckpointError::WrongShape => write!(f, "The checkpoint doesn't fit the verifier"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CheckpointError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CheckpointError::Decode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<CodecError> for CheckpointError {
+    fn from(e: CodecError) -> Self {
+        CheckpointError::Decode(e)
+    }
+}
+
+// keccak(labeled("checkpoint.proof", proof)), eg: of the encoded proof.
+pub fn proof_binding(proof: &[u8]) -> [u8; 32] {
+    Keccak256::digest(encode_labeled("checkpoint.proof", proof)).into()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateCheckpoint {
+    pub binding: [u8; 32],
+    pub round: usize,
+    pub transcript: Vec<u8>,
+    pub claims: Vec<Scalar>,
+}
+
+impl StateCheckpoint {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(1 + 32 + 8 + 8 + self.transcript.len());
+        bytes.push(CHECKPOINT_VERSION);
+        bytes.extend(encode_digest(&self.binding));
+        bytes.extend(encode_u64(self.round as u64));
+        bytes.extend(encode_bytes(&self.transcript));
+        bytes.extend(encode_scalars(&self.claims));
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        let mut decoder = Decoder::new(bytes);
+        decoder.read_version(CHECKPOINT_VERSION)?;
+        let checkpoint = Self {
+            binding: decoder.read_digest()?,
+            round: decoder.read_usize()?,
+            transcript: decoder.read_bytes()?,
+            claims: decoder.read_scalars()?,
+        };
+        decoder.finish()?;
+        Ok(checkpoint)
+    }
+
+    // The checkpoint is of the proof with this binding.
+    pub fn check_binding(&self, binding: &[u8; 32]) -> Result<(), CheckpointError> {
+        match self.binding == *binding {
+            true => Ok(()),
+            false => Err(CheckpointError::WrongProof),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::default::Keccak256Transcript;
+    use crate::test_vectors::fixture_scalars;
+    use crate::Transcript;
+
+    #[test]
+    fn test_to_and_from_bytes() {
+        let mut transcript = Keccak256Transcript::default();
+        transcript.append(b"round 0");
+        transcript.challenge();
+        let checkpoint = StateCheckpoint {
+            binding: proof_binding(b"proof"),
+            round: 3,
+            transcript: transcript.snapshot(),
+            claims: fixture_scalars(),
+        };
+        let bytes = checkpoint.to_bytes();
+        assert_eq!(StateCheckpoint::from_bytes(&bytes), Ok(checkpoint.clone()));
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            StateCheckpoint::from_bytes(&trailing),
+            Err(CodecError::TrailingBytes(1))
+        );
+        // the claims are cut, so their length is too large for the bytes left.
+        assert!(matches!(
+            StateCheckpoint::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(CodecError::LengthTooLarge { len: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_binding() {
+        let checkpoint = StateCheckpoint {
+            binding: proof_binding(b"proof"),
+            round: 0,
+            transcript: vec![],
+            claims: vec![],
+        };
+        assert_eq!(checkpoint.check_binding(&proof_binding(b"proof")), Ok(()));
+        assert_eq!(
+            checkpoint.check_binding(&proof_binding(b"another proof")),
+            Err(CheckpointError::WrongProof)
+        );
+    }
+}
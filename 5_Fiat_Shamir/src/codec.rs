@@ -0,0 +1,397 @@
+// The byte-level encoding of every message absorbed by the transcripts in the protocol crates.
+// P and V must absorb only through these functions, otherwise their challenges may diverge.
+//
+//      | message            | encoding                                          |
+//      |--------------------|---------------------------------------------------|
+//      | scalar             | 32 bytes, the canonical little-endian form        |
+//      | scalars            | len: u64, then each scalar                        |
+//      | digest             | 32 bytes as it is, eg: the merkle root            |
+//      | u64                | 8 bytes little-endian                             |
+//      | bytes              | len: u64, then the bytes as they are              |
+//      | labeled(label, m)  | len(label): u64, label, len(m): u64, m            |
+//      | message(label, m)  | len(label): u64, label, m, len(m): u64            |
+//
+// The length prefix makes the encoding unambiguous, eg: the scalars [a, b] + [c] can't be
+// confused with [a] + [b, c]. A message is sealed by its length rather than prefixed, as it's
+// written before its length is known, see `stream::MessageWriter`.
+//
+// The proofs are written with the same encoding, and read back by `Decoder`.
+use crate::protocol::ProtocolId;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use core::fmt;
+
+pub fn encode_scalar(s: &Scalar) -> [u8; 32] {
+    s.to_bytes()
+}
+
+pub fn encode_scalars(scalars: &[Scalar]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(8 + 32 * scalars.len());
+    bytes.extend(encode_u64(scalars.len() as u64));
+    for s in scalars.iter() {
+        bytes.extend(encode_scalar(s));
+    }
+    bytes
+}
+
+pub fn encode_digest(digest: &[u8; 32]) -> [u8; 32] {
+    *digest
+}
+
+pub fn encode_u64(v: u64) -> [u8; 8] {
+    v.to_le_bytes()
+}
+
+pub fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+    let mut res = Vec::with_capacity(8 + bytes.len());
+    res.extend(encode_u64(bytes.len() as u64));
+    res.extend(bytes);
+    res
+}
+
+// A composite message with a label for domain separation, eg: labeled("fri.roots", scalars).
+pub fn encode_labeled(label: &str, message: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(16 + label.len() + message.len());
+    bytes.extend(encode_u64(label.len() as u64));
+    bytes.extend(label.as_bytes());
+    bytes.extend(encode_u64(message.len() as u64));
+    bytes.extend(message);
+    bytes
+}
+
+// The frame of a streamed message, eg: message("fri.codeword", codeword), see `stream`.
+pub fn encode_message(label: &str, message: &[u8]) -> Vec<u8> {
+    let mut bytes = encode_bytes(label.as_bytes());
+    bytes.extend(message);
+    bytes.extend(encode_u64(message.len() as u64));
+    bytes
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodecError {
+    UnknownVersion(u8),
+    UnexpectedEnd,
+    NonCanonicalScalar { offset: usize },
+    // The length at the offset claims more items than the bytes left, or doesn't fit in usize.
+    LengthTooLarge { offset: usize, len: u64 },
+    TrailingBytes(usize),
+    // The proof is of another protocol or params, see `protocol`.
+    ProtocolMismatch,
+    // The tag at the offset is none of the known ones, eg: the op of a gate.
+    UnknownTag { offset: usize, tag: u8 },
+    // The item at the offset is out of the canonical order, eg: a repeated or unsorted label.
+    NonCanonicalOrder { offset: usize },
+    // The item at the offset isn't of the shape declared before it, eg: a round poly of another
+    // degree than its declared one.
+    ShapeMismatch { offset: usize },
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecError::UnknownVersion(v) => write!(f, "Unknown version {}", v),
+            CodecError::UnexpectedEnd => write!(f, "Unexpected end of the bytes"),
+            CodecError::NonCanonicalScalar { offset } => {
+                write!(f, "Non-canonical scalar at {}", offset)
+            }
+            CodecError::LengthTooLarge { offset, len } => {
+                write!(
+                    f,
+                    "Length {} at {} is larger than the bytes left",
+                    len, offset
+                )
+            }
+            CodecError::TrailingBytes(n) => write!(f, "{} trailing bytes", n),
+            CodecError::ProtocolMismatch => write!(f, "The bytes are of another protocol"),
+            CodecError::UnknownTag { offset, tag } => {
+                write!(f, "Unknown tag {} at {}", tag, offset)
+            }
+            CodecError::NonCanonicalOrder { offset } => {
+                write!(f, "Out of the canonical order at {}", offset)
+            }
+            CodecError::ShapeMismatch { offset } => {
+                write!(f, "Not of the declared shape at {}", offset)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CodecError {}
+
+// Read the messages of `codec` from untrusted bytes, it never panics: every length is checked
+// against the bytes left before anything is allocated.
+pub struct Decoder<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.bytes.len().saturating_sub(self.offset)
+    }
+
+    // The bytes read so far, eg: for the offset of an error found after the item is read.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn read(&mut self, n: usize) -> Result<&'a [u8], CodecError> {
+        let end = self
+            .offset
+            .checked_add(n)
+            .ok_or(CodecError::UnexpectedEnd)?;
+        let res = self
+            .bytes
+            .get(self.offset..end)
+            .ok_or(CodecError::UnexpectedEnd)?;
+        self.offset = end;
+        Ok(res)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, CodecError> {
+        Ok(self.read(1)?[0])
+    }
+
+    // The version byte in front of a proof.
+    pub fn read_version(&mut self, version: u8) -> Result<(), CodecError> {
+        match self.read_u8()? {
+            v if v == version => Ok(()),
+            v => Err(CodecError::UnknownVersion(v)),
+        }
+    }
+
+    // The digest of the `ProtocolId` in the header of a proof, which must be the expected one.
+    pub fn read_protocol(&mut self, expected: &ProtocolId) -> Result<(), CodecError> {
+        if self.read_digest()? != expected.digest() {
+            return Err(CodecError::ProtocolMismatch);
+        }
+        Ok(())
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, CodecError> {
+        let bytes: [u8; 8] = self
+            .read(8)?
+            .try_into()
+            .map_err(|_| CodecError::UnexpectedEnd)?;
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    // A u64 as usize, eg: an index.
+    pub fn read_usize(&mut self) -> Result<usize, CodecError> {
+        let offset = self.offset;
+        let v = self.read_u64()?;
+        usize::try_from(v).map_err(|_| CodecError::LengthTooLarge { offset, len: v })
+    }
+
+    // The log2 of a size as usize, eg: the k_i of a layer, so 2^v must fit in usize too.
+    pub fn read_log_len(&mut self) -> Result<usize, CodecError> {
+        let offset = self.offset;
+        match self.read_u64()? {
+            v if v < usize::BITS as u64 => Ok(v as usize),
+            v => Err(CodecError::LengthTooLarge { offset, len: v }),
+        }
+    }
+
+    // The tag of an enum, which is one of 0..tags, eg: the op of a gate.
+    pub fn read_tag(&mut self, tags: u8) -> Result<u8, CodecError> {
+        let offset = self.offset;
+        match self.read_u8()? {
+            tag if tag < tags => Ok(tag),
+            tag => Err(CodecError::UnknownTag { offset, tag }),
+        }
+    }
+
+    // The length of a list, each item takes min_size bytes at least, so the list must fit in
+    // the bytes left, eg: 32 for the scalars.
+    pub fn read_len(&mut self, min_size: usize) -> Result<usize, CodecError> {
+        let offset = self.offset;
+        let len = self.read_u64()?;
+        let fits = usize::try_from(len)
+            .ok()
+            .and_then(|n| n.checked_mul(min_size.max(1)))
+            .is_some_and(|size| size <= self.remaining());
+        if !fits {
+            return Err(CodecError::LengthTooLarge { offset, len });
+        }
+        Ok(len as usize)
+    }
+
+    pub fn read_scalar(&mut self) -> Result<Scalar, CodecError> {
+        let offset = self.offset;
+        let repr: [u8; 32] = self
+            .read(32)?
+            .try_into()
+            .map_err(|_| CodecError::UnexpectedEnd)?;
+        Option::from(Scalar::from_bytes(&repr)).ok_or(CodecError::NonCanonicalScalar { offset })
+    }
+
+    pub fn read_scalars(&mut self) -> Result<Vec<Scalar>, CodecError> {
+        let len = self.read_len(32)?;
+        (0..len).map(|_| self.read_scalar()).collect()
+    }
+
+    pub fn read_digest(&mut self) -> Result<[u8; 32], CodecError> {
+        self.read(32)?
+            .try_into()
+            .map_err(|_| CodecError::UnexpectedEnd)
+    }
+
+    pub fn read_bytes(&mut self) -> Result<Vec<u8>, CodecError> {
+        let len = self.read_len(1)?;
+        Ok(self.read(len)?.to_vec())
+    }
+
+    // A message of `encode_bytes` as a decoder of its own, so its items can't run past its end,
+    // eg: a layer of a GKR proof, which a reader can skip by the length. The offsets of its
+    // errors are from the start of the section.
+    pub fn read_section(&mut self) -> Result<Decoder<'a>, CodecError> {
+        let len = self.read_len(1)?;
+        Ok(Decoder::new(self.read(len)?))
+    }
+
+    // No trailing bytes are allowed.
+    pub fn finish(self) -> Result<(), CodecError> {
+        match self.remaining() {
+            0 => Ok(()),
+            remaining => Err(CodecError::TrailingBytes(remaining)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_vectors::*;
+
+    #[test]
+    fn test_encode() {
+        let scalars = fixture_scalars();
+        assert_eq!(to_hex(&encode_scalar(&scalars[2])), SCALAR_HEX);
+        assert_eq!(to_hex(&encode_scalars(&scalars)), SCALARS_HEX);
+        assert_eq!(to_hex(&encode_digest(&FIXTURE_DIGEST)), DIGEST_HEX);
+        assert_eq!(to_hex(&encode_u64(FIXTURE_U64)), U64_HEX);
+        assert_eq!(
+            to_hex(&encode_labeled(FIXTURE_LABEL, &encode_u64(FIXTURE_U64))),
+            LABELED_HEX
+        );
+    }
+
+    #[test]
+    fn test_length_prefix() {
+        let scalars = fixture_scalars();
+        let mut lhs = encode_scalars(&scalars[..2]);
+        lhs.extend(encode_scalars(&scalars[2..]));
+        let mut rhs = encode_scalars(&scalars[..1]);
+        rhs.extend(encode_scalars(&scalars[1..]));
+        assert_ne!(lhs, rhs);
+        assert_ne!(encode_labeled("ab", b"c"), encode_labeled("a", b"bc"));
+        assert_ne!(encode_message("ab", b"c"), encode_message("a", b"bc"));
+
+        // the length after the message.
+        let mut sealed = encode_bytes(b"ab");
+        sealed.extend(b"c");
+        sealed.extend(encode_u64(1));
+        assert_eq!(encode_message("ab", b"c"), sealed);
+    }
+
+    #[test]
+    fn test_decode() {
+        let scalars = fixture_scalars();
+        let mut bytes = vec![1];
+        bytes.extend(encode_scalars(&scalars));
+        bytes.extend(encode_u64(FIXTURE_U64));
+        bytes.extend(encode_digest(&FIXTURE_DIGEST));
+        bytes.extend(encode_bytes(b"abc"));
+
+        let mut decoder = Decoder::new(&bytes);
+        assert_eq!(decoder.read_version(1), Ok(()));
+        assert_eq!(decoder.read_scalars(), Ok(scalars));
+        assert_eq!(decoder.read_usize(), Ok(FIXTURE_U64 as usize));
+        assert_eq!(decoder.read_digest(), Ok(FIXTURE_DIGEST));
+        assert_eq!(decoder.read_bytes(), Ok(b"abc".to_vec()));
+        assert_eq!(decoder.finish(), Ok(()));
+    }
+
+    #[test]
+    fn test_decode_rejects() {
+        assert_eq!(
+            Decoder::new(&[2]).read_version(1),
+            Err(CodecError::UnknownVersion(2))
+        );
+        assert_eq!(
+            Decoder::new(&[0; 7]).read_u64(),
+            Err(CodecError::UnexpectedEnd)
+        );
+        // 2^64 - 1 scalars are claimed, nothing is allocated.
+        assert_eq!(
+            Decoder::new(&[0xff; 40]).read_scalars(),
+            Err(CodecError::LengthTooLarge {
+                offset: 0,
+                len: u64::MAX
+            })
+        );
+        assert_eq!(
+            Decoder::new(&[0xff; 32]).read_scalar(),
+            Err(CodecError::NonCanonicalScalar { offset: 0 })
+        );
+        assert_eq!(
+            Decoder::new(&[0; 3]).finish(),
+            Err(CodecError::TrailingBytes(3))
+        );
+    }
+
+    #[test]
+    fn test_decode_tag_and_log_len() {
+        let mut decoder = Decoder::new(&[1, 2]);
+        assert_eq!(decoder.read_tag(2), Ok(1));
+        assert_eq!(
+            decoder.read_tag(2),
+            Err(CodecError::UnknownTag { offset: 1, tag: 2 })
+        );
+
+        let mut bytes = encode_u64(63).to_vec();
+        bytes.extend(encode_u64(64));
+        let mut decoder = Decoder::new(&bytes);
+        assert_eq!(decoder.read_log_len(), Ok(63));
+        assert_eq!(
+            decoder.read_log_len(),
+            Err(CodecError::LengthTooLarge { offset: 8, len: 64 })
+        );
+    }
+
+    #[test]
+    fn test_decode_section() {
+        let mut bytes = encode_bytes(&encode_u64(FIXTURE_U64));
+        bytes.push(1);
+        let mut decoder = Decoder::new(&bytes);
+        let mut section = decoder.read_section().unwrap();
+        assert_eq!(section.read_usize(), Ok(FIXTURE_U64 as usize));
+        // the items of the section end with it.
+        assert_eq!(section.read_u8(), Err(CodecError::UnexpectedEnd));
+        assert_eq!(decoder.read_u8(), Ok(1));
+        assert_eq!(decoder.finish(), Ok(()));
+
+        // the section claims more bytes than there are.
+        let mut decoder = Decoder::new(&bytes[..12]);
+        assert_eq!(
+            decoder.read_section().err(),
+            Some(CodecError::LengthTooLarge { offset: 0, len: 8 })
+        );
+    }
+
+    #[test]
+    fn test_transcript() {
+        assert_eq!(
+            fixture_challenges()
+                .iter()
+                .map(|c| to_hex(c))
+                .collect::<Vec<_>>(),
+            CHALLENGES_HEX
+        );
+    }
+}
@@ -1,28 +1,131 @@
-use crate::Transcript;
-use sha3::{Digest, Keccak256};
+use crate::algebraic::AlgebraicSponge;
+use crate::audit::{AuditLog, AuditOp};
+use crate::codec::{encode_labeled, encode_scalars, encode_u64};
+use crate::sponge::{Keccak256Sponge, Sponge};
+use crate::{challenge_to_scalar, expand_challenge_vector, Transcript};
+use alloc::vec::Vec;
+use bls12_381::Scalar;
 
-pub struct Keccak256Transcript {
-    hasher: Keccak256,
+// The transcript over a sponge, see `sponge`. The backend is the type parameter, which defaults
+// to Keccak256, so `Keccak256Transcript` is the transcript of every proof so far.
+// The audit log is off by default, see `with_audit`.
+#[derive(Clone, Debug, Default)]
+pub struct SpongeTranscript<S: Sponge = Keccak256Sponge> {
+    sponge: S,
+    audit: Option<AuditLog>,
 }
 
-impl Transcript for Keccak256Transcript {
+pub type Keccak256Transcript = SpongeTranscript<Keccak256Sponge>;
+// The toy algebraic backend, which is NOT production-parameterized, see `algebraic`.
+pub type PoseidonTranscript = SpongeTranscript<AlgebraicSponge>;
+
+impl<S: Sponge> Transcript for SpongeTranscript<S> {
     fn append(&mut self, new_data: &[u8]) {
-        self.hasher.update(&mut new_data.to_owned());
+        self.sponge.absorb(new_data);
+        self.record(AuditOp::Append, "", new_data.len());
+    }
+
+    // The same bytes as the default one, but the label goes to the audit log.
+    fn append_labeled(&mut self, label: &str, message: &[u8]) {
+        self.sponge.absorb(&encode_labeled(label, message));
+        self.record(AuditOp::Append, label, message.len());
     }
 
     fn challenge(&mut self) -> [u8; 32] {
-        let mut result_hash = [0_u8; 32];
-        result_hash.copy_from_slice(&self.hasher.finalize_reset());
-        result_hash.reverse();
-        self.hasher.update(result_hash);
+        let result_hash = self.sponge.squeeze();
+        self.record(AuditOp::Challenge, "", result_hash.len());
         result_hash
     }
+
+    // The scalars of a field-native sponge never go through bytes, the ones of a byte sponge are
+    // the same as the defaults.
+    fn append_scalars(&mut self, scalars: &[Scalar]) {
+        if !S::FIELD_NATIVE {
+            return self.append(&encode_scalars(scalars));
+        }
+        self.sponge.absorb_scalars(scalars);
+        self.record(AuditOp::Append, "", 32 * scalars.len());
+    }
+
+    fn challenge_scalar(&mut self) -> Scalar {
+        if !S::FIELD_NATIVE {
+            let bytes = self.challenge();
+            let extra = self.challenge();
+            return challenge_to_scalar(&bytes, &extra);
+        }
+        let s = self.sponge.squeeze_scalar();
+        self.record(AuditOp::Challenge, "", 32);
+        s
+    }
+
+    // A squeeze per scalar of a field-native sponge, rather than the Keccak512 PRG of the seed.
+    fn challenge_vector(&mut self, label: &str, n: usize) -> Vec<Scalar> {
+        self.append_labeled(label, &encode_u64(n as u64));
+        if !S::FIELD_NATIVE {
+            let seed = self.challenge();
+            return expand_challenge_vector(&seed, n);
+        }
+        (0..n).map(|_| self.challenge_scalar()).collect()
+    }
+
+    fn begin_stream(&mut self) -> bool {
+        self.sponge.begin_stream()
+    }
 }
 
-impl Default for Keccak256Transcript {
-    fn default() -> Self {
+// The audit, see `audit` for the entries.
+impl<S: Sponge> SpongeTranscript<S> {
+    // Record every append and challenge from now on.
+    pub fn with_audit(mut self) -> Self {
+        self.audit = Some(AuditLog::default());
+        self
+    }
+
+    // None if the audit is off.
+    pub fn audit(&self) -> Option<&AuditLog> {
+        self.audit.as_ref()
+    }
+
+    fn record(&mut self, op: AuditOp, label: &str, len: usize) {
+        if let Some(log) = self.audit.as_mut() {
+            log.record(op, label, len, &self.sponge.state_bytes());
+        }
+    }
+}
+
+// A sub-transcript of the state so far, domain-separated by the label, eg: one per layer of GKR,
+// which squeezes its own challenges apart from the other forks. The transcript itself is left as
+// it is, so it's forked the same way any number of times, in any order.
+impl<S: Sponge> SpongeTranscript<S> {
+    pub fn fork(&self, label: u64) -> Self {
+        let mut sub = self.clone();
+        sub.append_labeled("fork", &encode_u64(label));
+        sub
+    }
+}
+
+// An extension of the Keccak backend only, not of `Transcript`: the state can be exported and
+// imported, eg: to resume a verifier from a checkpoint. The snapshot is the absorbed bytes of
+// `Keccak256Sponge`, it's specific to this transcript and not a standard Keccak state. The audit
+// log is not in it, the restored one has the audit off.
+// The bytes of a streamed message are hashed as they come, so there's no snapshot until the
+// challenge after it, see `stream`.
+impl SpongeTranscript<Keccak256Sponge> {
+    pub fn snapshot(&self) -> Vec<u8> {
+        assert!(
+            self.sponge.streamed.is_none(),
+            "no snapshot of a streamed message before the challenge after it"
+        );
+        self.sponge.absorbed.clone()
+    }
+
+    pub fn restore(snapshot: &[u8]) -> Self {
         Self {
-            hasher: Keccak256::new(),
+            sponge: Keccak256Sponge {
+                absorbed: snapshot.to_vec(),
+                streamed: None,
+            },
+            audit: None,
         }
     }
 }
@@ -54,4 +157,40 @@ mod tests {
 
         assert_eq!(challenge_1, challenge_2);
     }
+
+    #[test]
+    fn test_snapshot_and_restore() {
+        let s = Scalar::random(OsRng);
+        let mut transcript = Keccak256Transcript::default();
+        transcript.append(&s.to_bytes());
+        transcript.challenge();
+        transcript.append(&[1, 2, 3]);
+
+        // the restored one squeezes the same challenges, and absorbs the same way after.
+        let mut restored = Keccak256Transcript::restore(&transcript.snapshot());
+        assert_eq!(restored.challenge(), transcript.challenge());
+        restored.append(&s.to_bytes());
+        transcript.append(&s.to_bytes());
+        assert_eq!(restored.challenge(), transcript.challenge());
+    }
+
+    #[test]
+    fn test_fork() {
+        let mut transcript = Keccak256Transcript::default();
+        transcript.append(&[1, 2, 3]);
+        let snapshot = transcript.snapshot();
+
+        let (mut first, mut second) = (transcript.fork(0), transcript.fork(1));
+        assert_eq!(transcript.snapshot(), snapshot);
+        let (c_0, c_1) = (first.challenge(), second.challenge());
+        assert_ne!(c_0, c_1);
+        // the same label, after the other fork squeezed, and from a restored parent.
+        assert_eq!(transcript.fork(1).challenge(), c_1);
+        let restored = Keccak256Transcript::restore(&snapshot);
+        assert_eq!(restored.fork(0).challenge(), c_0);
+        // a fork is not the parent with the label appended unlabeled.
+        let mut plain = transcript.clone();
+        plain.append(&encode_u64(0));
+        assert_ne!(plain.challenge(), transcript.fork(0).challenge());
+    }
 }
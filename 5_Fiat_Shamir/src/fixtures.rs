@@ -0,0 +1,158 @@
+// The golden proofs of the protocol crates, stored as hex under `tests/fixtures` of the workspace.
+//
+// Each crate has a `test_fixture` which proves a fixed instance with fixed challenges, then
+//      1. decodes and verifies the stored proof with the current code,
+//      2. compares the stored bytes with the fresh encoding.
+// So a change in a transcript, an encoding or a fold breaks the fixture test of the protocol,
+// rather than a cross-implementation test weeks later.
+//
+// For an intended format change, regenerate them and review the diff of the hex files:
+//      REGENERATE_FIXTURES=1 cargo test --workspace fixture
+// see `tests/fixtures/README.md`.
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+pub const REGENERATE_ENV: &str = "REGENERATE_FIXTURES";
+
+// 32 bytes per line, so a changed scalar is a changed line in the diff.
+const BYTES_PER_LINE: usize = 32;
+
+pub fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("tests")
+        .join("fixtures")
+        .join(format!("{}.hex", name))
+}
+
+pub fn is_regenerating() -> bool {
+    env::var_os(REGENERATE_ENV).is_some_and(|v| !v.is_empty() && v != "0")
+}
+
+pub fn to_hex_lines(bytes: &[u8]) -> String {
+    bytes
+        .chunks(BYTES_PER_LINE)
+        .map(|line| format!("{}\n", crate::test_vectors::to_hex(line)))
+        .collect()
+}
+
+// The whitespace is ignored, eg: the line breaks.
+pub fn from_hex_lines(hex: &str) -> Result<Vec<u8>, String> {
+    let digits = hex
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<Vec<_>>();
+    if digits.len() % 2 != 0 {
+        return Err(format!("odd number of hex digits: {}", digits.len()));
+    }
+    digits
+        .chunks(2)
+        .enumerate()
+        .map(|(i, pair)| {
+            let s = pair.iter().collect::<String>();
+            u8::from_str_radix(&s, 16).map_err(|_| format!("invalid hex {:?} at byte {}", s, i))
+        })
+        .collect()
+}
+
+// The stored bytes of the fixture. In the regenerate mode, the fixture is written with `current`
+// first, eg: the fresh proof.
+pub fn load_or_regenerate(name: &str, current: &[u8]) -> Vec<u8> {
+    let path = fixture_path(name);
+    if is_regenerating() {
+        fs::write(&path, to_hex_lines(current))
+            .unwrap_or_else(|e| panic!("fixture {}: can't write {}: {}", name, path.display(), e));
+        println!("fixture {}: regenerated {}", name, path.display());
+    }
+    let hex = fs::read_to_string(&path).unwrap_or_else(|e| {
+        panic!(
+            "fixture {}: can't read {}: {}\nrun `{}=1 cargo test --workspace fixture` to create it",
+            name,
+            path.display(),
+            e,
+            REGENERATE_ENV
+        )
+    });
+    from_hex_lines(&hex).unwrap_or_else(|e| panic!("fixture {}: {}", name, e))
+}
+
+// The first differing line of the two in `to_hex_lines`, with both lines, or None if equal.
+pub fn first_diff(stored: &[u8], current: &[u8]) -> Option<String> {
+    if stored == current {
+        return None;
+    }
+    let (stored_hex, current_hex) = (to_hex_lines(stored), to_hex_lines(current));
+    let (mut stored_lines, mut current_lines) = (stored_hex.lines(), current_hex.lines());
+    let mut line = 1;
+    loop {
+        match (stored_lines.next(), current_lines.next()) {
+            (Some(s), Some(c)) if s == c => line += 1,
+            (s, c) => {
+                return Some(format!(
+                    "first diff at line {} (byte {}):\n  stored:  {}\n  current: {}",
+                    line,
+                    (line - 1) * BYTES_PER_LINE,
+                    s.unwrap_or("<end>"),
+                    c.unwrap_or("<end>")
+                ))
+            }
+        }
+    }
+}
+
+pub fn assert_same_bytes(name: &str, stored: &[u8], current: &[u8]) {
+    if let Some(diff) = first_diff(stored, current) {
+        panic!(
+            "fixture {}: the proof bytes changed, {} bytes stored, {} bytes now\n{}\n\
+             if the format change is intended, run `{}=1 cargo test --workspace fixture` \
+             and review the diff of {}",
+            name,
+            stored.len(),
+            current.len(),
+            diff,
+            REGENERATE_ENV,
+            fixture_path(name).display()
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_hex_lines() {
+        let bytes = (0..70u8).collect::<Vec<_>>();
+        let hex = to_hex_lines(&bytes);
+        assert_eq!(hex.lines().count(), 3);
+        assert!(hex.lines().take(2).all(|l| l.len() == 2 * BYTES_PER_LINE));
+        assert_eq!(from_hex_lines(&hex), Ok(bytes));
+        assert!(from_hex_lines("0g").is_err());
+        assert!(from_hex_lines("012").is_err());
+    }
+
+    #[test]
+    fn test_first_diff() {
+        let stored = (0..70u8).collect::<Vec<_>>();
+        assert_eq!(first_diff(&stored, &stored), None);
+
+        // a flipped byte in the 2nd line
+        let mut flipped = stored.clone();
+        flipped[40] ^= 1;
+        let diff = first_diff(&stored, &flipped).unwrap();
+        assert!(
+            diff.starts_with("first diff at line 2 (byte 32)"),
+            "{}",
+            diff
+        );
+
+        // a truncated proof
+        let diff = first_diff(&stored, &stored[..64]).unwrap();
+        assert!(
+            diff.contains("line 3") && diff.contains("<end>"),
+            "{}",
+            diff
+        );
+    }
+}
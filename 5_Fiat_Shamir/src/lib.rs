@@ -1,7 +1,338 @@
 #![allow(non_snake_case)]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(not(feature = "alloc"))]
+compile_error!("Fiat_Shamir requires the `std` or `alloc` feature");
+
+extern crate alloc;
+
+use crate::codec::{encode_labeled, encode_message, encode_scalars, encode_u64};
+use crate::protocol::ProtocolId;
+use crate::stream::MessageWriter;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use ff::PrimeField;
+use sha3::{Digest, Keccak512};
+
+pub mod algebraic;
+pub mod audit;
+pub mod checkpoint;
+pub mod codec;
 pub mod default;
+#[cfg(feature = "std")]
+pub mod fixtures;
+pub mod protocol;
+pub mod sponge;
+pub mod statement;
+pub mod stream;
+pub mod test_vectors;
 
 pub trait Transcript {
     fn append(&mut self, new_data: &[u8]);
     fn challenge(&mut self) -> [u8; 32];
+
+    // Absorb the message with a label, see `codec::encode_labeled`.
+    fn append_labeled(&mut self, label: &str, message: &[u8]) {
+        self.append(&encode_labeled(label, message));
+    }
+
+    // Absorb the message sealed by its length, see `codec::encode_message`. The same as writing
+    // it to `begin_message`.
+    fn append_message(&mut self, label: &str, message: &[u8]) {
+        self.append(&encode_message(label, message));
+    }
+
+    // Absorb a message as it's written, see `stream`. Not for the `dyn Transcript`, which
+    // takes `MessageWriter::new`.
+    fn begin_message(&mut self, label: &str) -> MessageWriter<'_, Self>
+    where
+        Self: Sized,
+    {
+        MessageWriter::new(self, label)
+    }
+
+    // Absorb the appends from now on to the next challenge as they come, as one append of their
+    // concatenation, see `stream`. False if the backend can't, so the writer keeps the message
+    // until it's sealed.
+    fn begin_stream(&mut self) -> bool {
+        false
+    }
+
+    // Absorb the protocol and its params, which must be the very first message, see `protocol`.
+    fn bind_protocol(&mut self, id: &ProtocolId) {
+        self.append_labeled("protocol", &id.to_bytes());
+    }
+
+    // Absorb the scalars, as their canonical bytes unless the backend absorbs them natively, see
+    // `default::SpongeTranscript`.
+    fn append_scalars(&mut self, scalars: &[Scalar]) {
+        self.append(&encode_scalars(scalars));
+    }
+
+    // Squeeze two challenges, and map them into a scalar, see `challenge_to_scalar`.
+    fn challenge_scalar(&mut self) -> Scalar {
+        let bytes = self.challenge();
+        let extra = self.challenge();
+        challenge_to_scalar(&bytes, &extra)
+    }
+
+    // n scalars at the cost of a single squeeze, see `expand_challenge_vector`: the label and n
+    // are absorbed, then the seed is squeezed, so it depends on everything absorbed so far, and
+    // on n. The state moves on once whatever n is, eg: even for n = 0.
+    fn challenge_vector(&mut self, label: &str, n: usize) -> Vec<Scalar> {
+        self.append_labeled(label, &encode_u64(n as u64));
+        let seed = self.challenge();
+        expand_challenge_vector(&seed, n)
+    }
+
+    // The same over any prime field, see `challenge_to_field`. Not for the `dyn Transcript`.
+    fn challenge_field<F: PrimeField>(&mut self) -> F
+    where
+        Self: Sized,
+    {
+        let bytes = self.challenge();
+        let extra = self.challenge();
+        challenge_to_field(&bytes, &extra)
+    }
+}
+
+// The only map from the challenges of a transcript to a scalar, the protocol crates must not
+// convert the bytes by themselves.
+//
+// The 64 bytes (bytes || extra) are read as a little-endian integer, and reduced modulo the order
+// r of the scalar field, which is `Scalar::from_bytes_wide`. As 2^512 > 2^257 * r, the result is
+// within 2^-256 of uniform. 32 bytes are not enough, eg: reducing them modulo r (~2^255) makes the
+// small scalars twice as likely, and summing them up leaves only 8161 values.
+pub fn challenge_to_scalar(bytes: &[u8; 32], extra: &[u8; 32]) -> Scalar {
+    let mut wide = [0u8; 64];
+    wide[..32].copy_from_slice(bytes);
+    wide[32..].copy_from_slice(extra);
+    Scalar::from_bytes_wide(&wide)
+}
+
+// The PRG of `Transcript::challenge_vector`, Keccak512 in counter mode over the 32 bytes of the
+// seed:
+//
+//      s_i = challenge_to_scalar(K[..32], K[32..]), K = Keccak512("challenge_vector" || seed || i)
+//
+// i as u64 little-endian, for i in [0, n). The 56 bytes of the input fit in one block, so it's a
+// single permutation per scalar, rather than the 2 digests of a `challenge_scalar`. It's
+// deterministic, and the prefix keeps K apart from the digests of `Keccak256Transcript`.
+pub fn expand_challenge_vector(seed: &[u8; 32], n: usize) -> Vec<Scalar> {
+    (0..n as u64)
+        .map(|i| {
+            let wide = Keccak512::new()
+                .chain_update(b"challenge_vector")
+                .chain_update(seed)
+                .chain_update(encode_u64(i))
+                .finalize();
+            challenge_to_scalar(
+                wide[..32].try_into().unwrap(),
+                wide[32..].try_into().unwrap(),
+            )
+        })
+        .collect()
+}
+
+// The only map from the challenges of a transcript to an index in [0, n), eg: the queries of a
+// commitment. The low 8 bytes are read as a little-endian u64, and reduced modulo n, so it's
+// within n/2^64 of uniform.
+pub fn challenge_to_index(bytes: &[u8; 32], n: usize) -> usize {
+    assert!(n > 0, "no index in an empty range");
+    let low = bytes[..8]
+        .iter()
+        .rev()
+        .fold(0u64, |acc, b| (acc << 8) | *b as u64);
+    (low % n as u64) as usize
+}
+
+// `challenge_to_scalar` over any prime field F: the same 64 little-endian bytes reduced modulo
+// the order of F, by Horner's rule from the most significant byte. It's uniform enough only if
+// 2^512 is much larger than the order, eg: not for a field over 2^384.
+pub fn challenge_to_field<F: PrimeField>(bytes: &[u8; 32], extra: &[u8; 32]) -> F {
+    let radix = F::from(256);
+    extra
+        .iter()
+        .rev()
+        .chain(bytes.iter().rev())
+        .fold(F::ZERO, |acc, b| acc * radix + F::from(*b as u64))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::codec::encode_scalars;
+    use crate::default::Keccak256Transcript;
+    use crate::test_vectors::{
+        fixture_scalars, to_hex, AFTER_SCALARS_HEX, AFTER_VECTOR_HEX, CHALLENGE_VECTOR_HEX,
+        WIDE_CHALLENGE_HEX,
+    };
+    use std::collections::BTreeSet;
+    use std::fs;
+    use std::path::Path;
+    use std::time::Instant;
+
+    #[test]
+    fn test_challenge_to_scalar() {
+        // bytes = [0, 1, ..., 31], extra = [32, 33, ..., 63]
+        let bytes: [u8; 32] = core::array::from_fn(|i| i as u8);
+        let extra: [u8; 32] = core::array::from_fn(|i| i as u8 + 32);
+        let s = challenge_to_scalar(&bytes, &extra);
+        assert_eq!(to_hex(&s.to_bytes()), WIDE_CHALLENGE_HEX);
+
+        // the first 32 bytes are the low ones.
+        assert_eq!(challenge_to_scalar(&[0; 32], &[0; 32]), Scalar::zero());
+        let mut one = [0; 32];
+        one[0] = 1;
+        assert_eq!(challenge_to_scalar(&one, &[0; 32]), Scalar::one());
+        let two_256 = challenge_to_scalar(&[0; 32], &one);
+        assert_eq!(two_256, Scalar::from(1 << 32).pow_vartime(&[8, 0, 0, 0]));
+    }
+
+    #[test]
+    fn test_challenge_to_index() {
+        // bytes = [0, 1, ..., 31], the low 8 bytes are 0x0706050403020100.
+        let bytes: [u8; 32] = core::array::from_fn(|i| i as u8);
+        assert_eq!(
+            challenge_to_index(&bytes, 1000),
+            506_097_522_914_230_528 % 1000
+        );
+        assert_eq!(challenge_to_index(&bytes, 1 << 16), 0x0100);
+        assert_eq!(challenge_to_index(&bytes, 1), 0);
+        // the bytes above the low 8 don't move it.
+        let mut high = bytes;
+        high[8] = 0xff;
+        assert_eq!(
+            challenge_to_index(&high, 1000),
+            challenge_to_index(&bytes, 1000)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "no index in an empty range")]
+    fn test_challenge_to_empty_range() {
+        challenge_to_index(&[7; 32], 0);
+    }
+
+    #[test]
+    fn test_challenge_to_field() {
+        let bytes: [u8; 32] = core::array::from_fn(|i| i as u8);
+        let extra: [u8; 32] = core::array::from_fn(|i| i as u8 + 32);
+        let s: Scalar = challenge_to_field(&bytes, &extra);
+        assert_eq!(s, challenge_to_scalar(&bytes, &extra));
+        assert_eq!(to_hex(&s.to_bytes()), WIDE_CHALLENGE_HEX);
+
+        // the transcript squeezes the same scalar either way.
+        let mut transcript = Keccak256Transcript::default();
+        transcript.append(&encode_scalars(&fixture_scalars()));
+        let mut copy = transcript.clone();
+        assert_eq!(
+            transcript.challenge_field::<Scalar>(),
+            copy.challenge_scalar()
+        );
+    }
+
+    #[test]
+    fn test_challenge_vector() {
+        // seed = [0, 1, ..., 31]
+        let seed: [u8; 32] = core::array::from_fn(|i| i as u8);
+        let scalars = expand_challenge_vector(&seed, 4);
+        for (s, hex) in scalars.iter().zip(CHALLENGE_VECTOR_HEX) {
+            assert_eq!(to_hex(&s.to_bytes()), hex);
+        }
+        // a prefix of the longer ones.
+        assert_eq!(expand_challenge_vector(&seed, 1), scalars[..1]);
+        assert!(expand_challenge_vector(&seed, 0).is_empty());
+
+        // one squeeze for n scalars, even for none.
+        let fixture = || {
+            let mut transcript = Keccak256Transcript::default();
+            transcript.append(&encode_scalars(&fixture_scalars()));
+            transcript
+        };
+        let mut empty = fixture();
+        assert!(empty.challenge_vector("fixture", 0).is_empty());
+        let mut one = fixture();
+        assert_eq!(one.challenge_vector("fixture", 1).len(), 1);
+        assert_ne!(empty.challenge(), one.challenge());
+
+        // the vector and the n `challenge_scalar` differ, both pinned by the challenge after.
+        let mut vector = fixture();
+        let mut singles = fixture();
+        let v = vector.challenge_vector("fixture", 4);
+        let s = (0..4)
+            .map(|_| singles.challenge_scalar())
+            .collect::<Vec<_>>();
+        assert_ne!(v, s);
+        assert_eq!(to_hex(&vector.challenge()), AFTER_VECTOR_HEX);
+        assert_eq!(to_hex(&singles.challenge()), AFTER_SCALARS_HEX);
+    }
+
+    // A sanity check of a single permutation per scalar, the deps are unoptimized in debug, so
+    //      cargo test --release -p Fiat_Shamir -- --ignored test_challenge_vector_is_fast
+    #[test]
+    #[ignore]
+    fn test_challenge_vector_is_fast() {
+        let start = Instant::now();
+        let scalars = expand_challenge_vector(&[7; 32], 100_000);
+        assert_eq!(scalars.len(), 100_000);
+        assert!(start.elapsed().as_secs_f64() < 1.0, "{:?}", start.elapsed());
+    }
+
+    #[test]
+    fn test_challenge_scalars_vary() {
+        let mut transcript = Keccak256Transcript::default();
+        transcript.append(&encode_scalars(&fixture_scalars()));
+        let challenges = (0..64)
+            .map(|_| transcript.challenge_scalar().to_bytes())
+            .collect::<Vec<_>>();
+
+        // 64 uniform bytes take ~56 distinct values.
+        let low_bytes = challenges.iter().map(|c| c[0]).collect::<BTreeSet<_>>();
+        assert!(low_bytes.len() >= 48);
+        // none of them is small, eg: a sum of the bytes.
+        assert!(challenges.iter().all(|c| c[8..].iter().any(|b| *b != 0)));
+    }
+
+    // The protocol crates squeeze through `Transcript::challenge_scalar` (or `challenge_to_scalar`),
+    // rather than converting the challenge bytes by themselves.
+    #[test]
+    fn test_no_ad_hoc_challenge_conversion() {
+        const CRATES: [&str; 4] = ["4_sumcheck", "4_GKR", "5_ni_sumcheck", "7_low_degree_test"];
+        // the canonical scalars of a proof are decoded here.
+        const DECODERS: [&str; 2] = ["scalar_utils.rs", "proof.rs"];
+        const FORBIDDEN: [&str; 4] = [
+            "Scalar::from_bytes(",
+            "finalize_reset",
+            "challenge() as ",
+            "challenge().iter()",
+        ];
+
+        fn visit(dir: &Path, violations: &mut Vec<String>) {
+            for entry in fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    visit(&path, violations);
+                    continue;
+                }
+                let name = path.file_name().unwrap().to_str().unwrap();
+                if !name.ends_with(".rs") || DECODERS.contains(&name) {
+                    continue;
+                }
+                let source = fs::read_to_string(&path).unwrap();
+                for (i, line) in source.lines().enumerate() {
+                    if FORBIDDEN.iter().any(|p| line.contains(p)) {
+                        violations.push(format!("{}:{}: {}", path.display(), i + 1, line.trim()));
+                    }
+                }
+            }
+        }
+
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+        let mut violations = vec![];
+        for name in CRATES {
+            visit(&root.join(name).join("src"), &mut violations);
+        }
+        assert!(violations.is_empty(), "{:#?}", violations);
+    }
 }
@@ -0,0 +1,108 @@
+// The protocol and the parameter set which a transcript and a proof belong to.
+//
+//      | field         | encoding                                              |
+//      |---------------|-------------------------------------------------------|
+//      | crate_name    | bytes, eg: "low_degree_test"                          |
+//      | protocol      | bytes, eg: "batch_ldt"                                |
+//      | version       | u64                                                   |
+//      | params_digest | digest, H(the serialized params), eg: the query count |
+//
+// where H is Keccak256. Every prover and verifier absorbs it as the very first message, see
+// `Transcript::bind_protocol`, so changing any parameter changes the whole challenge stream, and
+// a proof can't be replayed in another protocol, eg: a sum-check proof in GKR.
+//
+// The proofs carry it in their header, so a proof of other params is rejected by the decoder
+// before the rest is read, see `Decoder::read_protocol`.
+use crate::codec::{encode_bytes, encode_digest, encode_u64};
+use alloc::vec::Vec;
+use sha3::{Digest, Keccak256};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProtocolId {
+    pub crate_name: &'static str,
+    pub protocol: &'static str,
+    pub version: u8,
+    pub params_digest: [u8; 32],
+}
+
+impl ProtocolId {
+    // params: the serialized params, in the encoding of `codec`.
+    pub fn new(
+        crate_name: &'static str,
+        protocol: &'static str,
+        version: u8,
+        params: &[u8],
+    ) -> Self {
+        Self {
+            crate_name,
+            protocol,
+            version,
+            params_digest: Keccak256::digest(params).into(),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = encode_bytes(self.crate_name.as_bytes());
+        bytes.extend(encode_bytes(self.protocol.as_bytes()));
+        bytes.extend(encode_u64(self.version as u64));
+        bytes.extend(encode_digest(&self.params_digest));
+        bytes
+    }
+
+    // H(to_bytes), which is the header of the proofs.
+    pub fn digest(&self) -> [u8; 32] {
+        Keccak256::digest(self.to_bytes()).into()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::codec::{encode_u64, CodecError, Decoder};
+    use crate::default::Keccak256Transcript;
+    use crate::protocol::ProtocolId;
+    use crate::Transcript;
+
+    fn ldt_id(num_queries: u64) -> ProtocolId {
+        ProtocolId::new("low_degree_test", "batch_ldt", 1, &encode_u64(num_queries))
+    }
+
+    #[test]
+    fn test_params_change_digest() {
+        assert_eq!(ldt_id(5), ldt_id(5));
+        assert_ne!(ldt_id(5).digest(), ldt_id(6).digest());
+
+        let mut other = ldt_id(5);
+        other.protocol = "ldt";
+        assert_ne!(ldt_id(5).digest(), other.digest());
+        other = ldt_id(5);
+        other.version = 2;
+        assert_ne!(ldt_id(5).digest(), other.digest());
+    }
+
+    #[test]
+    fn test_bind_protocol() {
+        // the same absorbs under two ids
+        let challenge = |id: &ProtocolId| {
+            let mut transcript = Keccak256Transcript::default();
+            transcript.bind_protocol(id);
+            transcript.append(b"the same message");
+            transcript.challenge_scalar()
+        };
+        assert_eq!(challenge(&ldt_id(5)), challenge(&ldt_id(5)));
+        assert_ne!(challenge(&ldt_id(5)), challenge(&ldt_id(6)));
+    }
+
+    #[test]
+    fn test_read_protocol() {
+        let bytes = ldt_id(5).digest();
+        assert_eq!(Decoder::new(&bytes).read_protocol(&ldt_id(5)), Ok(()));
+        assert_eq!(
+            Decoder::new(&bytes).read_protocol(&ldt_id(6)),
+            Err(CodecError::ProtocolMismatch)
+        );
+        assert_eq!(
+            Decoder::new(&bytes[..31]).read_protocol(&ldt_id(5)),
+            Err(CodecError::UnexpectedEnd)
+        );
+    }
+}
@@ -0,0 +1,91 @@
+// The hashing backend of `default::SpongeTranscript`: a sponge which absorbs the messages and
+// squeezes the challenges, so the transcript is the same over any of them, eg:
+//      Keccak256Sponge: the bytes absorbed since the last squeeze, hashed by Keccak256. It's the
+//                       default backend, the one of every proof and fixture so far.
+//      AlgebraicSponge: a permutation over the scalars, see `algebraic`, which absorbs and
+//                       squeezes scalars natively, eg: for a verifier expressed as a circuit.
+//
+// A byte sponge leaves `absorb_scalars` and `squeeze_scalar` to the canonical bytes and
+// `challenge_to_scalar`, so the scalars of the Keccak transcript are the same bytes as before.
+use crate::challenge_to_scalar;
+use crate::codec::encode_scalars;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use sha3::{Digest, Keccak256};
+
+pub trait Sponge: Clone + Default {
+    // The scalars are absorbed and squeezed natively, rather than as bytes, see `SpongeTranscript`.
+    const FIELD_NATIVE: bool = false;
+
+    fn absorb(&mut self, bytes: &[u8]);
+    fn squeeze(&mut self) -> [u8; 32];
+
+    fn absorb_scalars(&mut self, scalars: &[Scalar]) {
+        self.absorb(&encode_scalars(scalars));
+    }
+
+    // Two squeezes, see `challenge_to_scalar`.
+    fn squeeze_scalar(&mut self) -> Scalar {
+        let bytes = self.squeeze();
+        let extra = self.squeeze();
+        challenge_to_scalar(&bytes, &extra)
+    }
+
+    // The state as bytes, eg: for the digests of the audit log, see `audit`.
+    fn state_bytes(&self) -> Vec<u8>;
+
+    // Absorb the bytes as they come until the next squeeze, rather than keep them, see
+    // `stream::MessageWriter`. False if the sponge can't, eg: one which frames each absorb by its
+    // length, so absorbing a then b isn't absorbing a || b.
+    fn begin_stream(&mut self) -> bool {
+        false
+    }
+}
+
+// The state is the bytes absorbed since the last squeeze, which start with the last challenge,
+// so they are kept rather than a running hasher. Until a message is streamed: from then on to the
+// squeeze, they are hashed as they come, and the state is the running hasher.
+#[derive(Clone, Debug, Default)]
+pub struct Keccak256Sponge {
+    pub(crate) absorbed: Vec<u8>,
+    pub(crate) streamed: Option<Keccak256>,
+}
+
+impl Sponge for Keccak256Sponge {
+    fn absorb(&mut self, bytes: &[u8]) {
+        match self.streamed.as_mut() {
+            Some(hasher) => hasher.update(bytes),
+            None => self.absorbed.extend_from_slice(bytes),
+        }
+    }
+
+    // The digest is reversed, and becomes the first bytes of the next state.
+    fn squeeze(&mut self) -> [u8; 32] {
+        let mut result_hash = [0_u8; 32];
+        match self.streamed.take() {
+            Some(hasher) => result_hash.copy_from_slice(&hasher.finalize()),
+            None => result_hash.copy_from_slice(&Keccak256::digest(&self.absorbed)),
+        }
+        result_hash.reverse();
+        self.absorbed.clear();
+        self.absorbed.extend_from_slice(&result_hash);
+        result_hash
+    }
+
+    // The bytes are gone once streamed, so the state is the digest of them so far.
+    fn state_bytes(&self) -> Vec<u8> {
+        match self.streamed.as_ref() {
+            Some(hasher) => hasher.clone().finalize().to_vec(),
+            None => self.absorbed.clone(),
+        }
+    }
+
+    // The bytes so far go to the hasher, the same as if they were hashed at the squeeze.
+    fn begin_stream(&mut self) -> bool {
+        if self.streamed.is_none() {
+            self.streamed = Some(Keccak256::new_with_prefix(&self.absorbed));
+            self.absorbed.clear();
+        }
+        true
+    }
+}
@@ -0,0 +1,457 @@
+// The public statement of a composed proof: the commitments and the public values it's about,
+// each under a label, eg: the roots of A, B and C of a committed Freivalds claim, or the root of
+// the inputs and the digest of the circuit of GKR.
+//
+// The bindings are kept sorted by label, so the encoding doesn't depend on the order they are
+// added in, and `bind` absorbs the whole statement through `Transcript::bind_protocol`, as the
+// params of the protocol, see `bound_protocol`. So P and V can't disagree on the order of the
+// roots, and a proof of one statement is rejected for any other.
+//
+//      | field     | encoding                                         |
+//      |-----------|--------------------------------------------------|
+//      | protocol  | digest, `ProtocolId::digest`                     |
+//      | bindings  | count: u64, then each one sorted by its label    |
+//      | binding   | label: bytes, kind: u8, then the payload below   |
+//
+//      | kind        | payload                          |
+//      |-------------|----------------------------------|
+//      | 0 matrix    | rows: u64, cols: u64, digest     |
+//      | 1 mle       | var_num: u64, digest             |
+//      | 2 circuit   | digest                           |
+//      | 3 scalars   | scalars                          |
+//
+// V looks the roots up by label, and a root of another kind is an error, eg: asking for a matrix
+// under the label of an MLE. The labels V asks for are its own, so the errors name them.
+use crate::codec::{encode_digest, encode_scalar, encode_u64, CodecError, Decoder};
+use crate::protocol::ProtocolId;
+use crate::Transcript;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use core::fmt;
+use sha3::{Digest, Keccak256};
+
+#[cfg(test)]
+mod fuzz;
+
+// The label of `with_circuit`.
+pub const CIRCUIT_LABEL: &str = "circuit";
+
+// A matrix committed row by row, eg: `MatrixRoot` of the Freivalds crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatrixBinding {
+    pub rows: usize,
+    pub cols: usize,
+    pub digest: [u8; 32],
+}
+
+// A table of 2^var_num evaluations, eg: `MlRoot` of ni_sumcheck.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MleBinding {
+    pub var_num: usize,
+    pub digest: [u8; 32],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Binding {
+    Matrix(MatrixBinding),
+    Mle(MleBinding),
+    Circuit([u8; 32]),
+    Scalars(Vec<Scalar>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingKind {
+    Matrix,
+    Mle,
+    Circuit,
+    Scalars,
+}
+
+impl Binding {
+    pub fn kind(&self) -> BindingKind {
+        match self {
+            Binding::Matrix(_) => BindingKind::Matrix,
+            Binding::Mle(_) => BindingKind::Mle,
+            Binding::Circuit(_) => BindingKind::Circuit,
+            Binding::Scalars(_) => BindingKind::Scalars,
+        }
+    }
+
+    // The encoding in parts, eg: into a hasher, so a binding of many scalars isn't in memory
+    // twice.
+    fn write_to(&self, out: &mut impl FnMut(&[u8])) {
+        out(&[self.kind() as u8]);
+        match self {
+            Binding::Matrix(m) => {
+                out(&encode_u64(m.rows as u64));
+                out(&encode_u64(m.cols as u64));
+                out(&encode_digest(&m.digest));
+            }
+            Binding::Mle(m) => {
+                out(&encode_u64(m.var_num as u64));
+                out(&encode_digest(&m.digest));
+            }
+            Binding::Circuit(digest) => out(&encode_digest(digest)),
+            Binding::Scalars(scalars) => {
+                out(&encode_u64(scalars.len() as u64));
+                for s in scalars.iter() {
+                    out(&encode_scalar(s));
+                }
+            }
+        }
+    }
+
+    fn read(decoder: &mut Decoder) -> Result<Self, CodecError> {
+        match decoder.read_tag(4)? {
+            0 => Ok(Binding::Matrix(MatrixBinding {
+                rows: decoder.read_usize()?,
+                cols: decoder.read_usize()?,
+                digest: decoder.read_digest()?,
+            })),
+            1 => Ok(Binding::Mle(MleBinding {
+                var_num: decoder.read_log_len()?,
+                digest: decoder.read_digest()?,
+            })),
+            2 => Ok(Binding::Circuit(decoder.read_digest()?)),
+            _ => Ok(Binding::Scalars(decoder.read_scalars()?)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatementError {
+    // V needs the label, but the statement has no binding under it.
+    MissingBinding {
+        label: &'static str,
+    },
+    // the binding under the label is of another kind, eg: an MLE root where V expects a matrix.
+    WrongKind {
+        label: &'static str,
+        expected: BindingKind,
+        found: BindingKind,
+    },
+    // the statement is of another protocol than the one V runs.
+    ProtocolMismatch,
+}
+
+impl fmt::Display for StatementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StatementError::MissingBinding { label } => {
+                write!(f, "The statement has no binding \"{}\"", label)
+            }
+            StatementError::WrongKind {
+                label,
+                expected,
+                found,
+            } => write!(
+                f,
+                "The binding \"{}\" is a {:?}, not a {:?}",
+                label, found, expected
+            ),
+            StatementError::ProtocolMismatch => write!(f, "The statement is of another protocol"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for StatementError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Statement {
+    protocol: ProtocolId,
+    bindings: BTreeMap<String, Binding>,
+}
+
+impl Statement {
+    pub fn new(protocol: ProtocolId) -> Self {
+        Self {
+            protocol,
+            bindings: BTreeMap::new(),
+        }
+    }
+
+    // A label is bound once only, a second binding under it is a bug of the caller.
+    fn with(mut self, label: &str, binding: Binding) -> Self {
+        let old = self.bindings.insert(label.into(), binding);
+        assert!(old.is_none(), "the label \"{}\" is bound twice", label);
+        self
+    }
+
+    pub fn with_matrix(self, label: &str, root: impl Into<MatrixBinding>) -> Self {
+        self.with(label, Binding::Matrix(root.into()))
+    }
+
+    pub fn with_mle(self, label: &str, root: impl Into<MleBinding>) -> Self {
+        self.with(label, Binding::Mle(root.into()))
+    }
+
+    pub fn with_circuit(self, digest: [u8; 32]) -> Self {
+        self.with(CIRCUIT_LABEL, Binding::Circuit(digest))
+    }
+
+    pub fn with_public_scalars(self, label: &str, scalars: &[Scalar]) -> Self {
+        self.with(label, Binding::Scalars(scalars.to_vec()))
+    }
+
+    pub fn protocol(&self) -> &ProtocolId {
+        &self.protocol
+    }
+
+    // The statement must be of the protocol V runs, before any root is looked up.
+    pub fn check_protocol(&self, expected: &ProtocolId) -> Result<(), StatementError> {
+        if self.protocol != *expected {
+            return Err(StatementError::ProtocolMismatch);
+        }
+        Ok(())
+    }
+
+    // (label, binding), sorted by label.
+    pub fn bindings(&self) -> impl Iterator<Item = (&str, &Binding)> {
+        self.bindings.iter().map(|(label, b)| (label.as_str(), b))
+    }
+
+    pub fn get(&self, label: &'static str) -> Result<&Binding, StatementError> {
+        self.bindings
+            .get(label)
+            .ok_or(StatementError::MissingBinding { label })
+    }
+
+    fn wrong_kind(label: &'static str, expected: BindingKind, found: &Binding) -> StatementError {
+        StatementError::WrongKind {
+            label,
+            expected,
+            found: found.kind(),
+        }
+    }
+
+    pub fn matrix(&self, label: &'static str) -> Result<MatrixBinding, StatementError> {
+        match self.get(label)? {
+            Binding::Matrix(m) => Ok(*m),
+            other => Err(Self::wrong_kind(label, BindingKind::Matrix, other)),
+        }
+    }
+
+    pub fn mle(&self, label: &'static str) -> Result<MleBinding, StatementError> {
+        match self.get(label)? {
+            Binding::Mle(m) => Ok(*m),
+            other => Err(Self::wrong_kind(label, BindingKind::Mle, other)),
+        }
+    }
+
+    pub fn circuit(&self) -> Result<[u8; 32], StatementError> {
+        match self.get(CIRCUIT_LABEL)? {
+            Binding::Circuit(digest) => Ok(*digest),
+            other => Err(Self::wrong_kind(CIRCUIT_LABEL, BindingKind::Circuit, other)),
+        }
+    }
+
+    pub fn public_scalars(&self, label: &'static str) -> Result<&[Scalar], StatementError> {
+        match self.get(label)? {
+            Binding::Scalars(scalars) => Ok(scalars),
+            other => Err(Self::wrong_kind(label, BindingKind::Scalars, other)),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.write_to(&mut |part| bytes.extend_from_slice(part));
+        bytes
+    }
+
+    // The parts of `to_bytes` in order.
+    fn write_to(&self, out: &mut impl FnMut(&[u8])) {
+        out(&encode_digest(&self.protocol.digest()));
+        out(&encode_u64(self.bindings.len() as u64));
+        for (label, binding) in self.bindings.iter() {
+            out(&encode_u64(label.len() as u64));
+            out(label.as_bytes());
+            binding.write_to(out);
+        }
+    }
+
+    // The statement of the protocol from untrusted bytes. Only the canonical encoding is read:
+    // the labels are UTF-8 and strictly increasing, so each statement has a single encoding.
+    pub fn from_bytes_for(bytes: &[u8], protocol: &ProtocolId) -> Result<Self, CodecError> {
+        let mut decoder = Decoder::new(bytes);
+        decoder.read_protocol(protocol)?;
+        // a binding takes 10 bytes at least: an empty label, the kind and an empty list.
+        let count = decoder.read_len(10)?;
+        let mut bindings = BTreeMap::new();
+        let mut last: Option<String> = None;
+        for _ in 0..count {
+            let offset = bytes.len() - decoder.remaining();
+            let label = String::from_utf8(decoder.read_bytes()?)
+                .map_err(|_| CodecError::NonCanonicalOrder { offset })?;
+            if last.as_ref().is_some_and(|last| *last >= label) {
+                return Err(CodecError::NonCanonicalOrder { offset });
+            }
+            bindings.insert(label.clone(), Binding::read(&mut decoder)?);
+            last = Some(label);
+        }
+        decoder.finish()?;
+        Ok(Self {
+            protocol: *protocol,
+            bindings,
+        })
+    }
+
+    // H(to_bytes), which changes with the protocol and with any binding. The bytes are hashed as
+    // they are written, so the public scalars of the statement aren't encoded in full.
+    pub fn digest(&self) -> [u8; 32] {
+        let mut hasher = Keccak256::new();
+        self.write_to(&mut |part| hasher.update(part));
+        hasher.finalize().into()
+    }
+
+    // The protocol with the statement as its params, which is what `bind` absorbs.
+    pub fn bound_protocol(&self) -> ProtocolId {
+        ProtocolId {
+            params_digest: self.digest(),
+            ..self.protocol
+        }
+    }
+
+    // The first message of P and V, in place of `bind_protocol` of the protocol alone.
+    pub fn bind<T: Transcript + ?Sized>(&self, transcript: &mut T) {
+        transcript.bind_protocol(&self.bound_protocol());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::default::Keccak256Transcript;
+
+    fn protocol() -> ProtocolId {
+        ProtocolId::new("Fiat_Shamir", "statement_test", 1, &[])
+    }
+
+    fn matrix(seed: u8) -> MatrixBinding {
+        MatrixBinding {
+            rows: 3,
+            cols: 4,
+            digest: [seed; 32],
+        }
+    }
+
+    fn mle(seed: u8) -> MleBinding {
+        MleBinding {
+            var_num: 5,
+            digest: [seed; 32],
+        }
+    }
+
+    pub(super) fn statement() -> Statement {
+        Statement::new(protocol())
+            .with_matrix("A", matrix(1))
+            .with_mle("witness", mle(2))
+            .with_circuit([3; 32])
+            .with_public_scalars("outputs", &[Scalar::from(7), Scalar::from(9)])
+    }
+
+    #[test]
+    fn test_order_independent() {
+        let other = Statement::new(protocol())
+            .with_public_scalars("outputs", &[Scalar::from(7), Scalar::from(9)])
+            .with_circuit([3; 32])
+            .with_mle("witness", mle(2))
+            .with_matrix("A", matrix(1));
+        assert_eq!(other, statement());
+        assert_eq!(other.to_bytes(), statement().to_bytes());
+        assert_eq!(other.digest(), statement().digest());
+        // the digest is hashed as the bytes are written.
+        let bytes: [u8; 32] = Keccak256::digest(statement().to_bytes()).into();
+        assert_eq!(statement().digest(), bytes);
+    }
+
+    #[test]
+    fn test_typed_lookups() {
+        let statement = statement();
+        assert_eq!(statement.matrix("A"), Ok(matrix(1)));
+        assert_eq!(statement.mle("witness"), Ok(mle(2)));
+        assert_eq!(statement.circuit(), Ok([3; 32]));
+        assert_eq!(statement.public_scalars("outputs").unwrap().len(), 2);
+
+        assert_eq!(
+            statement.matrix("B"),
+            Err(StatementError::MissingBinding { label: "B" })
+        );
+        assert_eq!(
+            statement.matrix("witness"),
+            Err(StatementError::WrongKind {
+                label: "witness",
+                expected: BindingKind::Matrix,
+                found: BindingKind::Mle,
+            })
+        );
+        assert!(StatementError::MissingBinding { label: "B" }
+            .to_string()
+            .contains("\"B\""));
+    }
+
+    #[test]
+    #[should_panic(expected = "the label \"A\" is bound twice")]
+    fn test_bound_twice() {
+        statement().with_mle("A", mle(4));
+    }
+
+    #[test]
+    fn test_any_change_changes_digest() {
+        let digest = statement().digest();
+        let changed = [
+            Statement::new(protocol())
+                .with_matrix("A", matrix(9))
+                .with_mle("witness", mle(2))
+                .with_circuit([3; 32])
+                .with_public_scalars("outputs", &[Scalar::from(7), Scalar::from(9)]),
+            Statement::new(protocol())
+                .with_matrix("A", matrix(1))
+                .with_mle("witness", mle(2))
+                .with_circuit([4; 32])
+                .with_public_scalars("outputs", &[Scalar::from(7), Scalar::from(9)]),
+            Statement::new(protocol())
+                .with_matrix("B", matrix(1))
+                .with_mle("witness", mle(2))
+                .with_circuit([3; 32])
+                .with_public_scalars("outputs", &[Scalar::from(7), Scalar::from(9)]),
+            Statement::new(ProtocolId::new("Fiat_Shamir", "statement_test", 2, &[]))
+                .with_matrix("A", matrix(1))
+                .with_mle("witness", mle(2))
+                .with_circuit([3; 32])
+                .with_public_scalars("outputs", &[Scalar::from(7), Scalar::from(9)]),
+        ];
+        for other in changed.iter() {
+            assert_ne!(other.digest(), digest);
+        }
+
+        // and so the challenges of a transcript bound to it.
+        let challenge = |s: &Statement| {
+            let mut transcript = Keccak256Transcript::default();
+            s.bind(&mut transcript);
+            transcript.challenge()
+        };
+        assert_ne!(challenge(&changed[0]), challenge(&statement()));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let bytes = statement().to_bytes();
+        assert_eq!(
+            Statement::from_bytes_for(&bytes, &protocol()),
+            Ok(statement())
+        );
+        let other = ProtocolId::new("Fiat_Shamir", "statement_test", 2, &[]);
+        assert_eq!(
+            Statement::from_bytes_for(&bytes, &other),
+            Err(CodecError::ProtocolMismatch)
+        );
+        let empty = Statement::new(protocol());
+        assert_eq!(
+            Statement::from_bytes_for(&empty.to_bytes(), &protocol()),
+            Ok(empty)
+        );
+    }
+}
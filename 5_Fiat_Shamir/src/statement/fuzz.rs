@@ -0,0 +1,82 @@
+// `Statement::from_bytes_for` on malformed bytes: it must return Ok or a CodecError, and never
+// panic, the same as the fuzzing of `SumCheckProof::from_bytes` in ni_sumcheck.
+//
+// The inputs are (a) random bytes behind a valid header or not, (b) the truncations of a valid
+// encoding at every position, and (c) a valid encoding with a single byte flipped. A decoded
+// statement must encode to the same bytes, so only the canonical encoding is accepted.
+use crate::codec::CodecError;
+use crate::protocol::ProtocolId;
+use crate::statement::test::statement;
+use crate::statement::Statement;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+const RANDOM_CASES: u64 = 2048;
+
+// SplitMix64, the cases are the same in every run.
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    fn bytes(&mut self, len: usize) -> Vec<u8> {
+        (0..len).map(|_| self.next_u64() as u8).collect()
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Panics with the input if the decoder panics, or if a decoded statement doesn't encode back.
+fn decode(bytes: &[u8], protocol: &ProtocolId) -> Result<Statement, CodecError> {
+    let res = catch_unwind(AssertUnwindSafe(|| {
+        Statement::from_bytes_for(bytes, protocol)
+    }))
+    .unwrap_or_else(|_| panic!("from_bytes_for panicked on {}", to_hex(bytes)));
+    if let Ok(statement) = res.as_ref() {
+        assert_eq!(statement.to_bytes(), bytes, "not a canonical encoding");
+    }
+    res
+}
+
+#[test]
+fn test_random_bytes() {
+    let protocol = *statement().protocol();
+    let header = protocol.digest();
+    let mut rng = Rng(0x5eed);
+    for i in 0..RANDOM_CASES {
+        let len = (rng.next_u64() % 512) as usize;
+        let mut bytes = rng.bytes(len);
+        // half of them pass the header, so the bindings are reached.
+        if i % 2 == 0 {
+            bytes.splice(0..0, header);
+        }
+        let _ = decode(&bytes, &protocol);
+    }
+}
+
+#[test]
+fn test_truncations() {
+    let bytes = statement().to_bytes();
+    for len in 0..bytes.len() {
+        assert!(decode(&bytes[..len], statement().protocol()).is_err());
+    }
+}
+
+#[test]
+fn test_single_byte_flips() {
+    let bytes = statement().to_bytes();
+    for i in 0..bytes.len() {
+        for mask in [0x01, 0x80, 0xff] {
+            let mut flipped = bytes.clone();
+            flipped[i] ^= mask;
+            let _ = decode(&flipped, statement().protocol());
+        }
+    }
+}
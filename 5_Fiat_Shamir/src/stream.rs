@@ -0,0 +1,319 @@
+// A message absorbed as it's written, eg: a codeword or an evaluation table of 2^20 scalars,
+// without its bytes in memory. The frame is the one of `codec::encode_message`:
+//
+//      len(label): u64, label, the writes in order, len: u64
+//
+// The length is known once the last write is done, so `finish` seals the message with it. The
+// transcript is then the same as after one `append_message` of the concatenation of the writes,
+// whatever they are split into, eg: 0 bytes are the label and a zero length.
+//
+// A backend which absorbs its appends as they come, see `Transcript::begin_stream`, holds a
+// block of the hash at most, eg: the Keccak256 transcript. Any other one gets the frame in a
+// single append at `finish`, eg: the algebraic one, which frames each append by its length.
+//
+// A writer dropped without `finish` is a bug of the caller, it panics in debug builds. Either
+// way the message is sealed by the length u64::MAX, which no finished message has, so the
+// transcript is poisoned: its challenges from then on differ from the ones of any transcript
+// which finished the message.
+use crate::codec::{encode_bytes, encode_scalar, encode_u64};
+use crate::Transcript;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+
+// The length of a message dropped before it's finished.
+const POISONED_LEN: u64 = u64::MAX;
+
+pub struct MessageWriter<'a, T: Transcript + ?Sized> {
+    transcript: &'a mut T,
+    // the frame so far, if the backend can't absorb the writes as they come.
+    buffer: Option<Vec<u8>>,
+    len: u64,
+    sealed: bool,
+}
+
+impl<'a, T: Transcript + ?Sized> MessageWriter<'a, T> {
+    // The label is absorbed at once, eg: `MessageWriter::new(transcript, "fri.codeword")` for a
+    // `dyn Transcript`, or `transcript.begin_message("fri.codeword")`.
+    pub fn new(transcript: &'a mut T, label: &str) -> Self {
+        let header = encode_bytes(label.as_bytes());
+        let buffer = if transcript.begin_stream() {
+            transcript.append(&header);
+            None
+        } else {
+            Some(header)
+        };
+        Self {
+            transcript,
+            buffer,
+            len: 0,
+            sealed: false,
+        }
+    }
+
+    pub fn push_bytes(&mut self, bytes: &[u8]) {
+        self.len += bytes.len() as u64;
+        match self.buffer.as_mut() {
+            Some(buffer) => buffer.extend_from_slice(bytes),
+            None => self.transcript.append(bytes),
+        }
+    }
+
+    // The canonical bytes of the scalar, see `codec::encode_scalar`.
+    pub fn push_scalar(&mut self, s: &Scalar) {
+        self.push_bytes(&encode_scalar(s));
+    }
+
+    // The bytes written so far.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn finish(mut self) {
+        self.seal(self.len);
+    }
+
+    fn seal(&mut self, len: u64) {
+        self.sealed = true;
+        match self.buffer.take() {
+            Some(mut buffer) => {
+                buffer.extend(encode_u64(len));
+                self.transcript.append(&buffer);
+            }
+            None => self.transcript.append(&encode_u64(len)),
+        }
+    }
+}
+
+impl<T: Transcript + ?Sized> Drop for MessageWriter<'_, T> {
+    fn drop(&mut self) {
+        if self.sealed {
+            return;
+        }
+        self.seal(POISONED_LEN);
+        // not a second panic while unwinding, eg: from a panic between the writes.
+        #[cfg(feature = "std")]
+        let unwinding = std::thread::panicking();
+        #[cfg(not(feature = "std"))]
+        let unwinding = false;
+        debug_assert!(
+            unwinding,
+            "the message is dropped before it's finished, the transcript is poisoned"
+        );
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: Transcript + ?Sized> std::io::Write for MessageWriter<'_, T> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.push_bytes(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::codec::{encode_message, encode_scalars};
+    use crate::default::{Keccak256Transcript, PoseidonTranscript};
+    use crate::test_vectors::fixture_scalars;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+    use std::io::Write;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    // The bytes in use by the current thread, and the peak of them, so the tests running in
+    // parallel don't add up to the ones of each other.
+    struct CountingAlloc;
+
+    thread_local! {
+        static IN_USE: Cell<usize> = const { Cell::new(0) };
+        static PEAK: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = IN_USE.try_with(|n| {
+                n.set(n.get() + layout.size());
+                let _ = PEAK.try_with(|peak| peak.set(peak.get().max(n.get())));
+            });
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            let _ = IN_USE.try_with(|n| n.set(n.get().saturating_sub(layout.size())));
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: CountingAlloc = CountingAlloc;
+
+    // The peak of the bytes f allocates above the ones in use before it, and its result.
+    fn peak_bytes<R>(f: impl FnOnce() -> R) -> (usize, R) {
+        let before = IN_USE.with(|n| n.get());
+        PEAK.with(|peak| peak.set(before));
+        let res = f();
+        (PEAK.with(|peak| peak.get()) - before, res)
+    }
+
+    // 10 MB, not a multiple of the 136 bytes of a Keccak256 block.
+    const LARGE: usize = 10 << 20;
+
+    // The byte i of the large payload, so it's written without being kept.
+    fn large_byte(i: usize) -> u8 {
+        (i % 251) as u8
+    }
+
+    // A transcript with a challenge before the message, and a message after it.
+    fn absorb<T: Transcript>(mut transcript: T, f: impl FnOnce(&mut T)) -> [u8; 32] {
+        transcript.append(&encode_scalars(&fixture_scalars()));
+        transcript.challenge();
+        f(&mut transcript);
+        transcript.append_labeled("after", b"message");
+        transcript.challenge()
+    }
+
+    fn assert_framing<T: Transcript + Default>(payload: &[u8]) {
+        let one_shot = absorb(T::default(), |t| t.append_message("payload", payload));
+        let framed = absorb(T::default(), |t| {
+            t.append(&encode_message("payload", payload))
+        });
+        assert_eq!(one_shot, framed);
+        // whatever the writes are split into.
+        for chunk in [1, 7, 136, 4096] {
+            let streamed = absorb(T::default(), |t| {
+                let mut writer = t.begin_message("payload");
+                for part in payload.chunks(chunk) {
+                    writer.push_bytes(part);
+                }
+                assert_eq!(writer.len(), payload.len() as u64);
+                writer.finish();
+            });
+            assert_eq!(streamed, one_shot);
+        }
+    }
+
+    #[test]
+    fn test_framing() {
+        let block = (0..136).map(large_byte).collect::<Vec<_>>();
+        for payload in [&[][..], &[7], &block] {
+            assert_framing::<Keccak256Transcript>(payload);
+            assert_framing::<PoseidonTranscript>(payload);
+        }
+        // the label and the length are absorbed, even for nothing.
+        let empty = absorb(Keccak256Transcript::default(), |t| {
+            t.begin_message("payload").finish()
+        });
+        let none = absorb(Keccak256Transcript::default(), |_| {});
+        assert_ne!(empty, none);
+    }
+
+    #[test]
+    fn test_large_framing() {
+        let payload = (0..LARGE).map(large_byte).collect::<Vec<_>>();
+        assert_framing::<Keccak256Transcript>(&payload);
+    }
+
+    #[test]
+    fn test_push_scalar_and_write() {
+        let scalars = fixture_scalars();
+        let one_shot = absorb(Keccak256Transcript::default(), |t| {
+            let mut message = encode_scalars(&scalars)[8..].to_vec();
+            message.extend(b"tail");
+            t.append_message("scalars", &message)
+        });
+        let streamed = absorb(Keccak256Transcript::default(), |t| {
+            let mut writer = t.begin_message("scalars");
+            for s in scalars.iter() {
+                writer.push_scalar(s);
+            }
+            writer.write_all(b"tail").unwrap();
+            writer.finish();
+        });
+        assert_eq!(streamed, one_shot);
+
+        // a `dyn Transcript` too.
+        let dynamic = absorb(Keccak256Transcript::default(), |t| {
+            let t: &mut dyn Transcript = t;
+            let mut writer = MessageWriter::new(t, "scalars");
+            for s in scalars.iter() {
+                writer.push_scalar(s);
+            }
+            writer.write_all(b"tail").unwrap();
+            writer.finish();
+        });
+        assert_eq!(dynamic, one_shot);
+    }
+
+    #[test]
+    fn test_dropped_writer_poisons() {
+        let finished = absorb(Keccak256Transcript::default(), |t| {
+            t.append_message("payload", b"abc")
+        });
+        let mut transcript = Keccak256Transcript::default();
+        transcript.append(&encode_scalars(&fixture_scalars()));
+        transcript.challenge();
+        let dropped = catch_unwind(AssertUnwindSafe(|| {
+            let mut writer = transcript.begin_message("payload");
+            writer.push_bytes(b"abc");
+            drop(writer);
+        }));
+        // a panic in debug builds, the transcript is poisoned either way.
+        assert_eq!(dropped.is_err(), cfg!(debug_assertions));
+        transcript.append_labeled("after", b"message");
+        let poisoned = transcript.challenge();
+
+        let mut frame = encode_bytes(b"payload");
+        frame.extend(b"abc");
+        frame.extend(encode_u64(POISONED_LEN));
+        let expected = absorb(Keccak256Transcript::default(), |t| t.append(&frame));
+        assert_eq!(poisoned, expected);
+        assert_ne!(poisoned, finished);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "the message is dropped before it's finished")]
+    fn test_dropped_writer_panics() {
+        let mut transcript = Keccak256Transcript::default();
+        let mut writer = transcript.begin_message("payload");
+        writer.push_bytes(b"abc");
+    }
+
+    #[test]
+    fn test_streaming_memory() {
+        // the chunks of the payload are windows of one buffer, as the bytes repeat every 251.
+        const CHUNK: usize = 4096;
+        let window = (0..CHUNK + 251).map(large_byte).collect::<Vec<_>>();
+        let (peak, streamed) = peak_bytes(|| {
+            let mut transcript = Keccak256Transcript::default();
+            let mut writer = transcript.begin_message("payload");
+            for part in 0..LARGE / CHUNK {
+                let offset = part * CHUNK % 251;
+                writer.push_bytes(&window[offset..offset + CHUNK]);
+            }
+            writer.finish();
+            transcript.challenge()
+        });
+        // a block of the hash, not the payload.
+        assert!(peak <= 1024, "peak of {} bytes", peak);
+
+        // the same payload at once, which is in memory in full.
+        let payload = (0..LARGE).map(large_byte).collect::<Vec<_>>();
+        let (peak, one_shot) = peak_bytes(|| {
+            let mut transcript = Keccak256Transcript::default();
+            transcript.append_message("payload", &payload);
+            transcript.challenge()
+        });
+        assert!(peak >= LARGE);
+        assert_eq!(streamed, one_shot);
+    }
+}
@@ -0,0 +1,65 @@
+// The measured cost of the verifier against the one claimed by the book: O(deg) per round, so
+// O(v * deg) in all, plus a single evaluation of g by the oracle, which isn't counted here.
+use crate::poly::multivar_poly::MPolynomial;
+use crate::sumcheck::{verify_with_cost, verify_with_params, SumCheck, SumCheckParams};
+use crate::sumcheck::{SumCheckProof, VerifyError};
+use bls12_381::Scalar;
+use ff::Field;
+use rand_core::OsRng;
+use sumcheck::cost::{grows_like, CostReport};
+
+const SIZES: [usize; 3] = [4, 8, 12];
+
+fn random_proof(v: usize, params: SumCheckParams) -> SumCheckProof {
+    let g = MPolynomial {
+        var_num: v,
+        coeffs: (0..1 << v).map(|_| Scalar::random(OsRng)).collect(),
+    };
+    let bytes = SumCheck::with_params(g, params).prove_non_interactive();
+    SumCheckProof::from_bytes(&bytes).unwrap()
+}
+
+fn costs(params: SumCheckParams) -> Vec<CostReport> {
+    SIZES
+        .iter()
+        .map(|&v| {
+            let proof = random_proof(v, params.clone());
+            let (res, cost) = verify_with_cost(&proof, params.clone());
+            assert_eq!(res, verify_with_params(&proof, params.clone()));
+            assert!(res.is_ok());
+            cost
+        })
+        .collect()
+}
+
+#[test]
+fn test_sumcheck_cost_is_linear_in_v() {
+    for params in [
+        SumCheckParams::default(),
+        SumCheckParams::default().with_compressed_rounds(),
+    ] {
+        let costs = costs(params);
+        let mults = costs.iter().map(|c| c.field_mults).collect::<Vec<_>>();
+        assert!(grows_like(&SIZES, &mults, |v| v as f64, 1.5), "{:?}", mults);
+        assert!(!grows_like(&SIZES, &mults, |v| (1u64 << v) as f64, 1.5));
+
+        for (v, cost) in SIZES.iter().zip(costs.iter()) {
+            // a challenge per round, and the digest of the params.
+            assert_eq!(cost.hash_calls, 2 * *v as u64 + 1);
+            // a check per round, the claimed sum and the final check.
+            assert_eq!(cost.scalar_comparisons, *v as u64 + 2);
+        }
+    }
+}
+
+#[test]
+fn test_sumcheck_cost_of_rejection() {
+    let params = SumCheckParams::default();
+    let mut proof = random_proof(8, params.clone());
+    proof.final_eval += Scalar::ONE;
+    let (res, cost) = verify_with_cost(&proof, params.clone());
+    assert_eq!(res, Err(VerifyError::FinalCheckFailed));
+    assert_eq!(res, verify_with_params(&proof, params));
+    // all the rounds are done before the final check.
+    assert_eq!(cost.scalar_comparisons, 8 + 2);
+}
@@ -1,6 +1,22 @@
 #![allow(non_snake_case)]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
-mod poly;
-mod sumcheck;
-mod transcript;
+#[cfg(not(feature = "alloc"))]
+compile_error!("ni_sumcheck requires the `std` or `alloc` feature");
+
+// The verifier-only build has no OsRng nor rayon, see `sumcheck` of the interactive crate.
+#[cfg(all(feature = "verifier", not(feature = "prover"), feature = "std"))]
+compile_error!(
+    "the verifier-only build of ni_sumcheck can't enable `std`, it brings OsRng and rayon"
+);
+
+extern crate alloc;
+
+#[cfg(all(test, feature = "prover"))]
+mod analysis;
+pub mod mlpc;
+pub mod poly;
+pub mod sumcheck;
+pub use ::sumcheck::trace;
+pub mod transcript;
 mod utils;
@@ -0,0 +1,776 @@
+// Multilinear polynomial commitment over the evaluation table, eg: the input layer of GKR.
+//
+// Commit: the table W(w), w ∈ {0,1}^v (MSB first, see `convert_to_binary`), is the matrix M of
+// 2^v_row rows and 2^v_col columns, v_row = ⌊v/2⌋, so W(row || col) = M[row][col]. Each row is
+// encoded as the values of the poly with the row as coeffs at 0, 1, ..., BLOWUP·2^v_col - 1, and
+// the columns of the encoded rows are the leaves of the `MerkleTree` of `Merkle_tree_commitment`,
+// see `Leaf for Vec<Scalar>`.
+//
+// Open at r ∈ F^v: by Lemma 3.8, W̃(r) = ∑_w eq̃(r,w)·W(w), so P runs the product sum-check
+// between the eq̃(r,·) table (see `eq_table`) and the committed table. Each round polynomial
+// g_j has degree 2, and the challenge r'_j of round j is a scalar of the transcript. So the
+// sum-check ends at the claim eq̃(r,r')·W̃(r'), where V computes eq̃(r,r') by itself, and W̃(r')
+// is opened as the tensor product eq̃(r'_row,·)·M·eq̃(r'_col,·):
+//
+//     1. V squeezes β, P sends u = ∑_i β^i·M[i] and y = ∑_i eq̃(r'_row,i)·M[i],
+//     2. V squeezes QUERIES columns j, P opens them with their Merkle paths, which V checks as
+//        one batch, see `MultiVerifier`,
+//     3. V checks Enc(u)[j] = ∑_i β^i·C[i][j] and Enc(y)[j] = ∑_i eq̃(r'_row,i)·C[i][j] for each
+//        opened column C[·][j], then W̃(r') = ⟨y, eq̃(r'_col,·)⟩.
+//
+// Open at r_1, ..., r_k at once: V squeezes γ after all the points and values, and P runs a
+// single product sum-check for ∑_k γ^k·W̃(r_k) = ∑_w (∑_k γ^k·eq̃(r_k,w))·W(w), so it ends with
+// one tensor opening for all the claims, where V computes ∑_k γ^k·eq̃(r_k,r') in O(k·v).
+//
+// Soundness: the encoded rows are codewords of rate 1/BLOWUP, so any two differ in 3/4 of the
+// columns at least. If the committed rows are 1/4-far from codewords, so is Enc(u) from the
+// combined columns, but with probability 2^v_row·|Enc(u)|/|S|, and each query misses them with
+// probability 3/4 at most. Else they decode to a unique M, and a y other than the one of M
+// differs from it in 3/4 of the columns, 1/4 of which at most the commitment can patch. So
+// QUERIES = 246 leaves (3/4)^246 < 2^-102, and the sum-check 2v/|S| more, where S is the set of
+// the challenges of the transcript, see Ligero [AHIV17].
+use crate::poly::univar_poly::Polynomial;
+use crate::transcript::default::Keccak256Transcript;
+use crate::transcript::{poly_to_bytes, Transcript};
+use crate::utils::eq_table;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use core::fmt;
+use ff::PrimeField;
+use sumcheck::claim::{Claim, ClaimTracker, PolyId};
+use sumcheck::utils::eq_eval;
+use Fiat_Shamir::challenge_to_index;
+use Fiat_Shamir::codec::{
+    encode_digest, encode_scalar, encode_scalars, encode_u64, CodecError, Decoder,
+};
+use Fiat_Shamir::protocol::ProtocolId;
+use Fiat_Shamir::statement::MleBinding;
+use Merkle_tree_commitment::merkle_tree::hasher::Digest;
+use Merkle_tree_commitment::merkle_tree::proof::MerkleProof;
+use Merkle_tree_commitment::merkle_tree::MultiVerifier;
+
+#[cfg(feature = "prover")]
+mod prover;
+#[cfg(feature = "prover")]
+pub use prover::{commit, open, open_batch, ProverState};
+
+pub type Hash = [u8; 32];
+
+// The encoded rows are BLOWUP times the length of the rows.
+pub const BLOWUP: usize = 4;
+
+// The columns opened by each opening, see the soundness above.
+pub const QUERIES: usize = 246;
+
+/// The commitment of the table, which is all the verifier holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MlRoot {
+    pub var_num: usize,
+    pub digest: Hash, // the root of the tree over the columns
+}
+
+// The root as a binding of a `Statement`, and back.
+impl From<MlRoot> for MleBinding {
+    fn from(root: MlRoot) -> Self {
+        MleBinding {
+            var_num: root.var_num,
+            digest: root.digest,
+        }
+    }
+}
+
+impl From<MleBinding> for MlRoot {
+    fn from(binding: MleBinding) -> Self {
+        MlRoot {
+            var_num: binding.var_num,
+            digest: binding.digest,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MlOpening {
+    pub rounds: Vec<Polynomial>,   // g_1, ..., g_v
+    pub combined_row: Vec<Scalar>, // u = ∑_i β^i·M[i]
+    pub eval_row: Vec<Scalar>,     // y = ∑_i eq̃(r'_row,i)·M[i]
+    pub columns: Vec<Vec<Scalar>>, // the queried columns of the encoded rows
+    pub paths: Vec<Vec<Digest>>,   // from each column's sibling up to the root's children
+}
+
+// The opening of several points, the rounds are the ones of the γ-combined sum-check.
+pub type MlBatchOpening = MlOpening;
+
+// The bytes a proof takes on the wire, eg: to compare a batched opening with single ones.
+pub trait ProofSize {
+    fn size_in_bytes(&self) -> usize;
+}
+
+impl ProofSize for MlOpening {
+    fn size_in_bytes(&self) -> usize {
+        let coeffs = self.rounds.iter().map(|g| g.coeffs.len()).sum::<usize>();
+        let rows = self.combined_row.len() + self.eval_row.len();
+        let columns = self.columns.iter().map(|c| c.len()).sum::<usize>();
+        let paths = self.paths.iter().map(|p| p.len()).sum::<usize>();
+        (coeffs + rows + columns + paths) * 32
+    }
+}
+
+// The opening in the encoding of `Fiat_Shamir::codec`, eg: in a proof of GKR in the forked mode:
+//
+//      | field        | encoding                                        |
+//      |--------------|-------------------------------------------------|
+//      | rounds       | len: u64, then each g_j as scalars              |
+//      | combined_row | scalars                                         |
+//      | eval_row     | scalars                                         |
+//      | columns      | len: u64, then each column as scalars           |
+//      | paths        | len: u64, then each path as len: u64, digests   |
+impl MlOpening {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = encode_u64(self.rounds.len() as u64).to_vec();
+        for g_j in self.rounds.iter() {
+            bytes.extend(encode_scalars(&g_j.coeffs));
+        }
+        bytes.extend(encode_scalars(&self.combined_row));
+        bytes.extend(encode_scalars(&self.eval_row));
+        bytes.extend(encode_u64(self.columns.len() as u64));
+        for column in self.columns.iter() {
+            bytes.extend(encode_scalars(column));
+        }
+        bytes.extend(encode_u64(self.paths.len() as u64));
+        for path in self.paths.iter() {
+            bytes.extend(encode_u64(path.len() as u64));
+            for sibling in path.iter() {
+                bytes.extend(encode_scalar(sibling));
+            }
+        }
+        bytes
+    }
+
+    // The opening at the decoder, which is left right after it.
+    pub fn decode(decoder: &mut Decoder) -> Result<Self, CodecError> {
+        // each g_j, column and path has its length at least.
+        let rounds = (0..decoder.read_len(8)?)
+            .map(|_| {
+                let coeffs = decoder.read_scalars()?;
+                Ok(Polynomial { coeffs })
+            })
+            .collect::<Result<Vec<_>, CodecError>>()?;
+        let combined_row = decoder.read_scalars()?;
+        let eval_row = decoder.read_scalars()?;
+        let columns = (0..decoder.read_len(8)?)
+            .map(|_| decoder.read_scalars())
+            .collect::<Result<Vec<_>, _>>()?;
+        let paths = (0..decoder.read_len(8)?)
+            .map(|_| {
+                (0..decoder.read_len(32)?)
+                    .map(|_| decoder.read_scalar())
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            rounds,
+            combined_row,
+            eval_row,
+            columns,
+            paths,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MlpcError {
+    WrongRoundNum,
+    RoundCheckFailed { round: usize },
+    DegreeTooLarge { round: usize },
+    WrongShape,
+    MerkleCheckFailed,
+    ColumnCheckFailed { column: usize },
+    FinalCheckFailed,
+    WrongClaimNum,
+    PointLenMismatch,
+    ConflictingClaims,
+    NoClaim,
+}
+
+impl fmt::Display for MlpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MlpcError::WrongRoundNum => write!(f, "Wrong number of rounds"),
+            MlpcError::RoundCheckFailed { round } => write!(f, "Not-equal in round_{}", round),
+            MlpcError::DegreeTooLarge { round } => write!(f, "g_{} is of degree above 2", round),
+            MlpcError::WrongShape => write!(f, "The rows or columns are of the wrong size"),
+            MlpcError::MerkleCheckFailed => write!(f, "Merkle path of an opened column failed"),
+            MlpcError::ColumnCheckFailed { column } => {
+                write!(f, "Column {} doesn't match the encoded rows", column)
+            }
+            MlpcError::FinalCheckFailed => write!(f, "Verifier rejected the opening"),
+            MlpcError::WrongClaimNum => write!(f, "Not one claimed value per point"),
+            MlpcError::PointLenMismatch => write!(f, "The points have different lengths"),
+            MlpcError::ConflictingClaims => write!(f, "Two values claimed at the same point"),
+            MlpcError::NoClaim => write!(f, "No claim on the committed poly to open"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MlpcError {}
+
+// The variables of the rows and of the columns, the rows take the first half, the smaller one.
+fn split_vars(var_num: usize) -> (usize, usize) {
+    (var_num / 2, var_num - var_num / 2)
+}
+
+// Enc(row)[j], the poly with the row as coeffs at j.
+fn encode_at(row: &[Scalar], j: usize) -> Scalar {
+    let x = Scalar::from(j as u64);
+    row.iter().rev().fold(Scalar::zero(), |acc, c| acc * x + c)
+}
+
+fn inner_product(a: &[Scalar], b: &[Scalar]) -> Scalar {
+    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+}
+
+// The protocol of the openings of a table with var_num variables.
+pub fn protocol_id(var_num: usize) -> ProtocolId {
+    ProtocolId::new("ni_sumcheck", "mlpc", 1, &encode_u64(var_num as u64))
+}
+
+// Bind the protocol and the statement, so an opening can't be replayed for another root, point
+// or value.
+fn init_transcript(root: &MlRoot, r: &[Scalar], value: &Scalar) -> Keccak256Transcript {
+    let mut transcript = Keccak256Transcript::default();
+    transcript.bind_protocol(&protocol_id(root.var_num));
+    transcript.append(&encode_digest(&root.digest));
+    transcript.append(&encode_scalars(r));
+    transcript.append(&encode_scalar(value));
+    transcript
+}
+
+// The protocol of the batched openings, which differ from the single one in the statement.
+pub fn batch_protocol_id(var_num: usize) -> ProtocolId {
+    ProtocolId::new("ni_sumcheck", "mlpc_batch", 1, &encode_u64(var_num as u64))
+}
+
+fn init_batch_transcript(
+    root: &MlRoot,
+    points: &[Vec<Scalar>],
+    values: &[Scalar],
+) -> Keccak256Transcript {
+    let mut transcript = Keccak256Transcript::default();
+    transcript.bind_protocol(&batch_protocol_id(root.var_num));
+    transcript.append(&encode_digest(&root.digest));
+    transcript.append(&encode_u64(points.len() as u64));
+    for r in points.iter() {
+        transcript.append(&encode_scalars(r));
+    }
+    transcript.append(&encode_scalars(values));
+    transcript
+}
+
+// The claims with each point once, in the order of their first occurrence. The points must
+// have the same length, and the values at a repeated point must agree, eg: the b* and c* of a
+// GKR layer may be the same point.
+pub fn dedup_claims(
+    points: &[Vec<Scalar>],
+    values: &[Scalar],
+) -> Result<(Vec<Vec<Scalar>>, Vec<Scalar>), MlpcError> {
+    if points.is_empty() || points.len() != values.len() {
+        return Err(MlpcError::WrongClaimNum);
+    }
+    if points.iter().any(|r| r.len() != points[0].len()) {
+        return Err(MlpcError::PointLenMismatch);
+    }
+    let mut unique: Vec<Vec<Scalar>> = Vec::with_capacity(points.len());
+    let mut unique_values = Vec::with_capacity(points.len());
+    for (r, value) in points.iter().zip(values.iter()) {
+        match unique.iter().position(|u| u == r) {
+            Some(i) if unique_values[i] != *value => return Err(MlpcError::ConflictingClaims),
+            Some(_) => {}
+            None => {
+                unique.push(r.clone());
+                unique_values.push(*value);
+            }
+        }
+    }
+    Ok((unique, unique_values))
+}
+
+// γ^0, γ^1, ..., γ^{k-1}
+fn powers(gamma: Scalar, k: usize) -> Vec<Scalar> {
+    core::iter::successors(Some(Scalar::one()), |p| Some(p * gamma))
+        .take(k)
+        .collect()
+}
+
+// The challenge r'_j of round j.
+fn challenge_round(g_j: &Polynomial, transcript: &mut Keccak256Transcript) -> Scalar {
+    transcript.append(&poly_to_bytes(g_j));
+    transcript.challenge()
+}
+
+// The columns to open, squeezed after u and y are absorbed.
+fn challenge_columns(
+    combined_row: &[Scalar],
+    eval_row: &[Scalar],
+    transcript: &mut Keccak256Transcript,
+) -> Vec<usize> {
+    transcript.append(&encode_scalars(combined_row));
+    transcript.append(&encode_scalars(eval_row));
+    let n = BLOWUP * combined_row.len();
+    (0..QUERIES)
+        .map(|_| challenge_to_index(&transcript.challenge().to_bytes(), n))
+        .collect()
+}
+
+// Check the claim W̃(r) = claimed_value against the root.
+pub fn verify(
+    root: &MlRoot,
+    r: &[Scalar],
+    claimed_value: Scalar,
+    opening: &MlOpening,
+) -> Result<(), MlpcError> {
+    if r.len() != root.var_num || opening.rounds.len() != root.var_num {
+        return Err(MlpcError::WrongRoundNum);
+    }
+
+    // 1. the sum-check, which ends at the claim eq̃(r,r')·W̃(r') = claim
+    let mut transcript = init_transcript(root, r, &claimed_value);
+    let (claim, r_prime) = verify_rounds(opening, claimed_value, &mut transcript)?;
+
+    // 2. W̃(r') of the committed table
+    let value = verify_tensor(root, &r_prime, opening, &mut transcript)?;
+
+    // 3. V computes eq̃(r,r') by itself
+    if eq_eval(r, &r_prime) * value != claim {
+        return Err(MlpcError::FinalCheckFailed);
+    }
+    Ok(())
+}
+
+// Check the claim on the committed poly of the tracker, eg: the one on W_d left by the layers of
+// GKR. It's discharged, aka popped and returned, only if the opening is accepted, else it stays.
+pub fn discharge(
+    tracker: &mut ClaimTracker,
+    poly: PolyId,
+    root: &MlRoot,
+    opening: &MlOpening,
+) -> Result<Claim, MlpcError> {
+    let claim = tracker.pop(poly).ok_or(MlpcError::NoClaim)?;
+    match verify(root, &claim.point, claim.value, opening) {
+        Ok(()) => Ok(claim),
+        Err(e) => {
+            tracker.push(claim);
+            Err(e)
+        }
+    }
+}
+
+// Check the claims W̃(r_k) = values[k] against the root with a single opening, see
+// `open_batch`. A repeated point is checked once.
+pub fn verify_batch(
+    root: &MlRoot,
+    points: &[Vec<Scalar>],
+    values: &[Scalar],
+    opening: &MlBatchOpening,
+) -> Result<(), MlpcError> {
+    let (points, values) = dedup_claims(points, values)?;
+    if points[0].len() != root.var_num || opening.rounds.len() != root.var_num {
+        return Err(MlpcError::WrongRoundNum);
+    }
+
+    // 1. the sum-check of the γ-combined claim
+    let mut transcript = init_batch_transcript(root, &points, &values);
+    let gamma_k = powers(transcript.challenge(), points.len());
+    let combined = values
+        .iter()
+        .zip(gamma_k.iter())
+        .fold(Scalar::zero(), |acc, (v, g)| acc + g * v);
+    let (claim, r_prime) = verify_rounds(opening, combined, &mut transcript)?;
+
+    // 2. W̃(r') of the committed table
+    let value = verify_tensor(root, &r_prime, opening, &mut transcript)?;
+
+    // 3. V computes ∑_k γ^k·eq̃(r_k,r') by itself, in O(k·v)
+    let eq = points
+        .iter()
+        .zip(gamma_k.iter())
+        .fold(Scalar::zero(), |acc, (r, g)| acc + g * eq_eval(r, &r_prime));
+    if eq * value != claim {
+        return Err(MlpcError::FinalCheckFailed);
+    }
+    Ok(())
+}
+
+// The rounds of the sum-check from the claim, which end at the claim of r'.
+fn verify_rounds(
+    opening: &MlOpening,
+    claimed_value: Scalar,
+    transcript: &mut Keccak256Transcript,
+) -> Result<(Scalar, Vec<Scalar>), MlpcError> {
+    let mut claim = claimed_value;
+    let mut r_prime = Vec::with_capacity(opening.rounds.len());
+    for (j, g_j) in opening.rounds.iter().enumerate() {
+        if g_j.coeffs.len() > 3 {
+            return Err(MlpcError::DegreeTooLarge { round: j + 1 });
+        }
+        if g_j.evaluate(Scalar::zero()) + g_j.evaluate(Scalar::one()) != claim {
+            return Err(MlpcError::RoundCheckFailed { round: j + 1 });
+        }
+        let r_j = challenge_round(g_j, transcript);
+        claim = g_j.evaluate(r_j);
+        r_prime.push(r_j);
+    }
+    Ok((claim, r_prime))
+}
+
+// W̃(r') = ⟨y, eq̃(r'_col,·)⟩, once the opened columns agree with u and y.
+fn verify_tensor(
+    root: &MlRoot,
+    r_prime: &[Scalar],
+    opening: &MlOpening,
+    transcript: &mut Keccak256Transcript,
+) -> Result<Scalar, MlpcError> {
+    let (row_vars, col_vars) = split_vars(root.var_num);
+    let beta_i = powers(transcript.challenge(), 1 << row_vars);
+    let eq_row = eq_table(&r_prime[..row_vars]);
+    if opening.combined_row.len() != 1 << col_vars
+        || opening.eval_row.len() != 1 << col_vars
+        || opening.columns.len() != QUERIES
+        || opening.paths.len() != QUERIES
+        || opening.columns.iter().any(|c| c.len() != 1 << row_vars)
+    {
+        return Err(MlpcError::WrongShape);
+    }
+
+    let queries = challenge_columns(&opening.combined_row, &opening.eval_row, transcript);
+    if !check_paths(root, &queries, opening) {
+        return Err(MlpcError::MerkleCheckFailed);
+    }
+    for (j, column) in queries.iter().zip(&opening.columns) {
+        if encode_at(&opening.combined_row, *j) != inner_product(&beta_i, column)
+            || encode_at(&opening.eval_row, *j) != inner_product(&eq_row, column)
+        {
+            return Err(MlpcError::ColumnCheckFailed { column: *j });
+        }
+    }
+    Ok(inner_product(
+        &opening.eval_row,
+        &eq_table(&r_prime[row_vars..]),
+    ))
+}
+
+// The Merkle paths of the queried columns against the root as one batch, see `MultiVerifier`:
+// the tree has BLOWUP·2^v_col leaves, and the nodes the paths share are hashed once.
+fn check_paths(root: &MlRoot, queries: &[usize], opening: &MlOpening) -> bool {
+    let (_, col_vars) = split_vars(root.var_num);
+    let levels = col_vars + BLOWUP.trailing_zeros() as usize;
+    let digest: Option<Digest> = Digest::from_repr(root.digest).into();
+    let Some(digest) = digest else {
+        return false;
+    };
+    if opening.paths.iter().any(|path| path.len() != levels) {
+        return false;
+    }
+    let proofs = queries
+        .iter()
+        .zip(&opening.paths)
+        .map(|(j, path)| MerkleProof::new(path.clone(), digest, *j))
+        .collect::<Vec<_>>();
+    let mut batch = MultiVerifier::new();
+    for ((j, column), proof) in queries.iter().zip(&opening.columns).zip(&proofs) {
+        batch.add(digest, *j, column, proof);
+    }
+    batch.check().is_ok()
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod test {
+    use crate::mlpc::prover::{open_columns, prove_product};
+    use crate::mlpc::{
+        challenge_columns, challenge_round, commit, dedup_claims, discharge, init_transcript, open,
+        open_batch, verify, verify_batch, MlOpening, MlRoot, MlpcError, ProofSize, QUERIES,
+    };
+    use crate::transcript::Transcript;
+    use crate::utils::eq_table;
+    use bls12_381::Scalar;
+    use ff::Field;
+    use rand_core::OsRng;
+    use sumcheck::claim::{Claim, ClaimTracker};
+    use sumcheck::poly::eval_table::EvalTable;
+
+    fn random_scalars(n: usize) -> Vec<Scalar> {
+        (0..n).map(|_| Scalar::random(OsRng)).collect()
+    }
+
+    #[test]
+    fn test_open_honest() {
+        let v = 8;
+        let evals = random_scalars(1 << v);
+        let (root, state) = commit(&evals);
+        assert_eq!(root.var_num, v);
+
+        let r = random_scalars(v);
+        let value = state.evaluate(&r);
+        let opening = open(&state, &r);
+        assert_eq!(verify(&root, &r, value, &opening), Ok(()));
+    }
+
+    #[test]
+    fn test_evaluate_on_hypercube() {
+        // W̃ agrees with W on {0,1}^v, x_1 is the MSB.
+        let evals = random_scalars(8);
+        let (_, state) = commit(&evals);
+        let r = vec![Scalar::one(), Scalar::zero(), Scalar::one()];
+        assert_eq!(state.evaluate(&r), evals[5]);
+    }
+
+    #[test]
+    fn test_wrong_value() {
+        let v = 8;
+        let (root, state) = commit(&random_scalars(1 << v));
+        let r = random_scalars(v);
+        let value = state.evaluate(&r);
+        let opening = open(&state, &r);
+        assert_eq!(
+            verify(&root, &r, value + Scalar::one(), &opening),
+            Err(MlpcError::RoundCheckFailed { round: 1 })
+        );
+    }
+
+    #[test]
+    fn test_opening_to_and_from_bytes() {
+        use Fiat_Shamir::codec::{CodecError, Decoder};
+
+        let v = 4;
+        let (_, state) = commit(&random_scalars(1 << v));
+        let opening = open(&state, &random_scalars(v));
+        let bytes = opening.to_bytes();
+        // 4 rounds of 3 coeffs, u and y of 4 scalars, then the columns of 4 scalars and their
+        // paths of 4 siblings, with their lengths.
+        let vectors = 8 + QUERIES * (8 + 4 * 32);
+        assert_eq!(
+            bytes.len(),
+            8 + 4 * (8 + 3 * 32) + 2 * (8 + 4 * 32) + 2 * vectors
+        );
+        let mut decoder = Decoder::new(&bytes);
+        assert_eq!(MlOpening::decode(&mut decoder), Ok(opening));
+        assert_eq!(decoder.finish(), Ok(()));
+
+        let mut decoder = Decoder::new(&bytes[..bytes.len() - 1]);
+        assert_eq!(
+            MlOpening::decode(&mut decoder),
+            Err(CodecError::LengthTooLarge {
+                offset: bytes.len() - 8 - 4 * 32,
+                len: 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_small_tables() {
+        // a single row, and a single value, which the sum-check has no round for.
+        for v in 0..3 {
+            let (root, state) = commit(&random_scalars(1 << v));
+            let r = random_scalars(v);
+            let opening = open(&state, &r);
+            assert_eq!(verify(&root, &r, state.evaluate(&r), &opening), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_tampered_column() {
+        let v = 8;
+        let (root, state) = commit(&random_scalars(1 << v));
+        let r = random_scalars(v);
+        let value = state.evaluate(&r);
+        let mut opening = open(&state, &r);
+        opening.columns[0][0] += Scalar::one();
+        assert_eq!(
+            verify(&root, &r, value, &opening),
+            Err(MlpcError::MerkleCheckFailed)
+        );
+    }
+
+    // The columns V asks for after the rounds and rows of the opening, so a P which sends other
+    // rows can open the right columns for them.
+    fn replay_queries(
+        root: &MlRoot,
+        r: &[Scalar],
+        value: Scalar,
+        opening: &MlOpening,
+    ) -> Vec<usize> {
+        let mut transcript = init_transcript(root, r, &value);
+        for g_j in opening.rounds.iter() {
+            challenge_round(g_j, &mut transcript);
+        }
+        // β
+        transcript.challenge();
+        challenge_columns(&opening.combined_row, &opening.eval_row, &mut transcript)
+    }
+
+    #[test]
+    fn test_tampered_rows() {
+        let v = 8;
+        let (root, state) = commit(&random_scalars(1 << v));
+        let r = random_scalars(v);
+        let value = state.evaluate(&r);
+        for tamper in [0, 1] {
+            let mut opening = open(&state, &r);
+            if tamper == 0 {
+                opening.combined_row[0] += Scalar::one();
+            } else {
+                opening.eval_row[0] += Scalar::one();
+            }
+            // the queries move with the rows, so the old columns fail their paths, or the check.
+            assert!(verify(&root, &r, value, &opening).is_err());
+
+            let queries = replay_queries(&root, &r, value, &opening);
+            (opening.columns, opening.paths) = open_columns(&state, &queries);
+            assert_eq!(
+                verify(&root, &r, value, &opening),
+                Err(MlpcError::ColumnCheckFailed { column: queries[0] })
+            );
+        }
+    }
+
+    #[test]
+    fn test_open_other_table() {
+        // P runs the rounds on a table other than the committed one in a single value, in the
+        // transcript of the committed root, so only the columns are left to catch it.
+        let v = 8;
+        let evals = random_scalars(1 << v);
+        let (root, _) = commit(&evals);
+        let mut other = evals.clone();
+        other[77] += Scalar::one();
+        let (_, cheat) = commit(&other);
+        let r = random_scalars(v);
+        let value = cheat.evaluate(&r);
+        let transcript = init_transcript(&root, &r, &value);
+        let opening = prove_product(&cheat, EvalTable::new(eq_table(&r)), transcript);
+        assert_eq!(
+            verify(&root, &r, value, &opening),
+            Err(MlpcError::MerkleCheckFailed)
+        );
+    }
+
+    #[test]
+    fn test_wrong_shape() {
+        let v = 6;
+        let (root, state) = commit(&random_scalars(1 << v));
+        let r = random_scalars(v);
+        let value = state.evaluate(&r);
+        let mut opening = open(&state, &r);
+        opening.columns.pop();
+        assert_eq!(
+            verify(&root, &r, value, &opening),
+            Err(MlpcError::WrongShape)
+        );
+
+        let mut opening = open(&state, &r);
+        opening.rounds[2].coeffs.push(Scalar::zero());
+        assert_eq!(
+            verify(&root, &r, value, &opening),
+            Err(MlpcError::DegreeTooLarge { round: 3 })
+        );
+    }
+
+    #[test]
+    fn test_open_batch() {
+        let v = 10;
+        let (root, state) = commit(&random_scalars(1 << v));
+        for k in [2, 5] {
+            let points = (0..k).map(|_| random_scalars(v)).collect::<Vec<_>>();
+            let values = points.iter().map(|r| state.evaluate(r)).collect::<Vec<_>>();
+            let opening = open_batch(&state, &points);
+            assert_eq!(verify_batch(&root, &points, &values, &opening), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_open_batch_wrong_value() {
+        let v = 10;
+        let (root, state) = commit(&random_scalars(1 << v));
+        let points = (0..5).map(|_| random_scalars(v)).collect::<Vec<_>>();
+        let mut values = points.iter().map(|r| state.evaluate(r)).collect::<Vec<_>>();
+        let opening = open_batch(&state, &points);
+        values[3] += Scalar::one();
+        assert_eq!(
+            verify_batch(&root, &points, &values, &opening),
+            Err(MlpcError::RoundCheckFailed { round: 1 })
+        );
+    }
+
+    #[test]
+    fn test_open_batch_size() {
+        let v = 10;
+        let (_, state) = commit(&random_scalars(1 << v));
+        let points = (0..2).map(|_| random_scalars(v)).collect::<Vec<_>>();
+        let batched = open_batch(&state, &points).size_in_bytes();
+        let single = points
+            .iter()
+            .map(|r| open(&state, r).size_in_bytes())
+            .sum::<usize>();
+        assert!(batched < single, "{} >= {}", batched, single);
+    }
+
+    #[test]
+    fn test_open_batch_duplicates() {
+        let v = 10;
+        let (root, state) = commit(&random_scalars(1 << v));
+        let (r, s) = (random_scalars(v), random_scalars(v));
+        let (value_r, value_s) = (state.evaluate(&r), state.evaluate(&s));
+
+        // the same point twice is opened once.
+        let points = vec![r.clone(), s.clone(), r.clone()];
+        let opening = open_batch(&state, &points);
+        assert_eq!(opening, open_batch(&state, &points[..2]));
+        let values = vec![value_r, value_s, value_r];
+        assert_eq!(verify_batch(&root, &points, &values, &opening), Ok(()));
+
+        // the conflict is found from the claims alone, before any sum-check.
+        let values = vec![value_r, value_s, value_r + Scalar::one()];
+        assert_eq!(
+            dedup_claims(&points, &values),
+            Err(MlpcError::ConflictingClaims)
+        );
+        assert_eq!(
+            verify_batch(&root, &points, &values, &opening),
+            Err(MlpcError::ConflictingClaims)
+        );
+
+        let points = vec![r, s[1..].to_vec()];
+        assert_eq!(
+            verify_batch(&root, &points, &[value_r, value_s], &opening),
+            Err(MlpcError::PointLenMismatch)
+        );
+    }
+
+    #[test]
+    fn test_discharge() {
+        let v = 6;
+        let (root, state) = commit(&random_scalars(1 << v));
+        let r = random_scalars(v);
+        let value = state.evaluate(&r);
+        let opening = open(&state, &r);
+
+        let mut tracker = ClaimTracker::new();
+        let w = tracker.register("W");
+        assert_eq!(
+            discharge(&mut tracker, w, &root, &opening),
+            Err(MlpcError::NoClaim)
+        );
+
+        // a rejected opening leaves the claim to be checked.
+        tracker.push(Claim::new(w, r.clone(), value + Scalar::one()));
+        assert!(discharge(&mut tracker, w, &root, &opening).is_err());
+        assert_eq!(tracker.remaining().len(), 1);
+
+        tracker.pop(w);
+        tracker.push(Claim::new(w, r, value));
+        assert_eq!(
+            discharge(&mut tracker, w, &root, &opening).unwrap().value,
+            value
+        );
+        tracker.assert_all_discharged();
+    }
+}
@@ -0,0 +1,177 @@
+// P of the commitment: the encoded rows, the Merkle tree over their columns and the openings,
+// see `mlpc`.
+use crate::mlpc::{
+    challenge_columns, challenge_round, dedup_claims, encode_at, init_batch_transcript,
+    init_transcript, powers, split_vars, MlBatchOpening, MlOpening, MlRoot, BLOWUP,
+};
+use crate::poly::univar_poly::Polynomial;
+use crate::transcript::default::Keccak256Transcript;
+use crate::transcript::Transcript;
+use crate::utils::eq_table;
+use alloc::vec;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use ff::PrimeField;
+use sumcheck::poly::eval_table::EvalTable;
+use sumcheck::secret::{Secret, ZeroizingEvalTable};
+use sumcheck::utils::axpy;
+use Merkle_tree_commitment::merkle_tree::hasher::Digest;
+use Merkle_tree_commitment::merkle_tree::MerkleTree;
+
+#[derive(Debug, Clone)]
+pub struct ProverState {
+    evals: ZeroizingEvalTable,
+    // the columns of the encoded rows, which are the leaves of the tree.
+    columns: Vec<Vec<Scalar>>,
+    tree: MerkleTree<Vec<Scalar>>,
+}
+
+// The unique poly of degree 2 through (0, e0), (1, e1), (2, e2).
+fn interpolate_degree_2(e0: Scalar, e1: Scalar, e2: Scalar) -> Polynomial {
+    let two_inv = Scalar::from(2).invert().unwrap();
+    let c2 = (e2 - e1 - e1 + e0) * two_inv;
+    let c1 = e1 - e0 - c2;
+    Polynomial {
+        coeffs: vec![e0, c1, c2],
+    }
+}
+
+pub fn commit(evals: &[Scalar]) -> (MlRoot, ProverState) {
+    assert!(
+        !evals.is_empty() && evals.len().is_power_of_two(),
+        "the table size must be 2^v"
+    );
+    let (_, col_vars) = split_vars(evals.len().trailing_zeros() as usize);
+    let codewords = evals
+        .chunks(1 << col_vars)
+        .map(|row| {
+            (0..BLOWUP << col_vars)
+                .map(|j| encode_at(row, j))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let columns = (0..BLOWUP << col_vars)
+        .map(|j| codewords.iter().map(|c| c[j]).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+
+    let state = ProverState {
+        evals: Secret::new(EvalTable::new(evals.to_vec())),
+        tree: MerkleTree::commit(&columns),
+        columns,
+    };
+    (state.root(), state)
+}
+
+impl ProverState {
+    pub fn root(&self) -> MlRoot {
+        MlRoot {
+            var_num: self.evals.var_num,
+            digest: self.tree.root_hash().to_repr(),
+        }
+    }
+
+    pub fn evaluate(&self, r: &[Scalar]) -> Scalar {
+        self.evals.evaluate(r)
+    }
+
+    // ∑_i c_i·M[i]
+    fn combine_rows(&self, coeffs: &[Scalar]) -> Vec<Scalar> {
+        let cols = self.evals.evals.len() / coeffs.len();
+        let mut combined = vec![Scalar::zero(); cols];
+        for (row, c) in self.evals.evals.chunks(cols).zip(coeffs) {
+            axpy(&mut combined, *c, row);
+        }
+        combined
+    }
+
+    fn path(&self, index: usize) -> Vec<Digest> {
+        self.tree.open_by_index(index).children
+    }
+}
+
+pub fn open(state: &ProverState, r: &[Scalar]) -> MlOpening {
+    let root = state.root();
+    assert_eq!(r.len(), root.var_num, "the point must have v coordinates");
+
+    let transcript = init_transcript(&root, r, &state.evaluate(r));
+    prove_product(state, EvalTable::new(eq_table(r)), transcript)
+}
+
+// Open W̃ at all the points with one sum-check, see `mlpc`. A repeated point is opened once.
+pub fn open_batch(state: &ProverState, points: &[Vec<Scalar>]) -> MlBatchOpening {
+    let root = state.root();
+    assert!(
+        points.iter().all(|r| r.len() == root.var_num),
+        "the points must have v coordinates"
+    );
+    let values = points.iter().map(|r| state.evaluate(r)).collect::<Vec<_>>();
+    let (points, values) = dedup_claims(points, &values).expect("the claims of P are consistent");
+
+    let mut transcript = init_batch_transcript(&root, &points, &values);
+    let gamma_k = powers(transcript.challenge(), points.len());
+    // ∑_k γ^k·eq̃(r_k,·)
+    let mut eq = vec![Scalar::zero(); 1 << root.var_num];
+    for (r, g) in points.iter().zip(gamma_k) {
+        axpy(&mut eq, g, &eq_table(r));
+    }
+    prove_product(state, EvalTable::new(eq), transcript)
+}
+
+// The product sum-check of ∑_w eq(w)·W(w), which ends at the point r' of the challenges, then
+// the tensor opening of W̃(r').
+pub(super) fn prove_product(
+    state: &ProverState,
+    mut eq: EvalTable,
+    mut transcript: Keccak256Transcript,
+) -> MlOpening {
+    let var_num = state.evals.var_num;
+    let mut w = state.evals.clone();
+    let mut rounds = Vec::with_capacity(var_num);
+    let mut r_prime = Vec::with_capacity(var_num);
+
+    for _ in 0..var_num {
+        // lo is x_j = 0 and hi is x_j = 1.
+        let (eq_lo, eq_hi) = eq.split_halves();
+        let (w_lo, w_hi) = w.split_halves();
+
+        // g_j(t) = ∑ ((1-t)·eq_lo + t·eq_hi) * ((1-t)·w_lo + t·w_hi), t = 0, 1, 2
+        let (mut e0, mut e1, mut e2) = (Scalar::zero(), Scalar::zero(), Scalar::zero());
+        for k in 0..eq_lo.len() {
+            e0 += eq_lo[k] * w_lo[k];
+            e1 += eq_hi[k] * w_hi[k];
+            e2 += (eq_hi[k].double() - eq_lo[k]) * (w_hi[k].double() - w_lo[k]);
+        }
+        let g_j = interpolate_degree_2(e0, e1, e2);
+
+        let r_j = challenge_round(&g_j, &mut transcript);
+        rounds.push(g_j);
+        r_prime.push(r_j);
+        eq.bind_first(r_j);
+        w.bind_first(r_j);
+    }
+
+    // u and y, then the columns V asks for.
+    let (row_vars, _) = split_vars(var_num);
+    let combined_row = state.combine_rows(&powers(transcript.challenge(), 1 << row_vars));
+    let eval_row = state.combine_rows(&eq_table(&r_prime[..row_vars]));
+    let queries = challenge_columns(&combined_row, &eval_row, &mut transcript);
+    let (columns, paths) = open_columns(state, &queries);
+    MlOpening {
+        rounds,
+        combined_row,
+        eval_row,
+        columns,
+        paths,
+    }
+}
+
+// The columns at the queries, and their Merkle paths.
+pub(super) fn open_columns(
+    state: &ProverState,
+    queries: &[usize],
+) -> (Vec<Vec<Scalar>>, Vec<Vec<Digest>>) {
+    queries
+        .iter()
+        .map(|j| (state.columns[*j].clone(), state.path(*j)))
+        .unzip()
+}
@@ -1,11 +1,12 @@
 use crate::poly::univar_poly::Polynomial;
-use crate::utils::{convert_to_binary, expand_factor_for_mpoly};
+use crate::utils::{binary_point, convert_to_binary, expand_factor_for_mpoly};
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
 use bls12_381::Scalar;
+use core::ops::AddAssign;
 use ff::Field;
 use log::{debug, log};
-use std::collections::HashMap;
-use std::hash::Hash;
-use std::ops::{Add, AddAssign};
 
 // A multivariate polynomial g is multilinear if the degree of the polynomial in each variable is at most one.
 // For example, the polynomial g(x1,x2) = x_1*x_2 +4x_1 +3x_2 is multilinear, but the polynomial
@@ -95,13 +96,14 @@ impl MPolynomial {
                 continue;
             }
             let factor = gen_X_wi(i, w_i.clone());
-            product = expand_factor_for_mpoly(var_num, product, factor);
+            // the factors are variable-disjoint, so the product is always multilinear.
+            product = expand_factor_for_mpoly(var_num, product, factor).unwrap();
         }
 
         product
     }
 
-    pub fn evaluate(&self, domain: &Vec<usize>) -> Scalar {
+    pub fn evaluate(&self, domain: &[Scalar]) -> Scalar {
         assert_eq!(domain.len(), self.var_num, "Domain is less than var_num");
 
         let mut sum_of_term = Scalar::zero();
@@ -113,37 +115,17 @@ impl MPolynomial {
                 continue;
             }
 
-            // if index is 0, then term = coeff.
-            if index == 0 {
-                sum_of_term += coeff;
-            } else {
-                // x_0^exps[0] * x_1^exps[1] * x_2^exps[2]+ ...
-                let exps = convert_to_binary(&self.var_num, index);
-
-                // compute product of x , eg: product_x = (x_1^exp1) * (x_2^exp2)
-                let mut product = 1;
-                for (x_i, exp_i) in domain.into_iter().zip(exps) {
-                    let x = x_i.clone();
-
-                    // Note, as the definition, the exp is in [0, 1]
-                    // if exp != 0 && x != 0 {
-                    product *= x.pow(exp_i as u32);
-
-                    // once product, the computation of product is over. As zero multiple anything is zero.
-                    if 0 == product {
-                        break;
-                    }
-                }
+            // x_0^exps[0] * x_1^exps[1] * x_2^exps[2]+ ...
+            let exps = convert_to_binary(&self.var_num, index);
 
-                match product {
-                    0 => continue,
-                    1 => sum_of_term += coeff,
-                    _ => {
-                        let term_i = coeff.mul(&Scalar::from(product as u64));
-                        sum_of_term.add_assign(term_i);
-                    }
-                }
-            }
+            // compute product of x , eg: product_x = (x_1^exp1) * (x_2^exp2)
+            // Note, as the definition, the exp is in [0, 1]
+            let product = domain
+                .iter()
+                .zip(exps)
+                .filter(|(_, exp_i)| *exp_i == 1)
+                .fold(Scalar::one(), |acc, (x_i, _)| acc * x_i);
+            sum_of_term += coeff * product;
         }
         sum_of_term
     }
@@ -152,25 +134,25 @@ impl MPolynomial {
     //      f(x1, x2, x3, x4) , x1,x2,x3,x4 in hypercube
     //      With inputs(r1,r2,X,x4), the multi-poly become a uni-poly p(X)
     //
-    // input: (r1, ..., r_{j-1}) in F, (In implement, r1 is a random usize, which is easy to construct a Field.)
+    // input: (r1, ..., r_{j-1}) in F,
     //        (x_j+1, ..., x_v} in hypercube{0,1}^v
     //
     // This is useful in sum-check protocol when obtaining g_i(X)
-    pub fn partial_evaluate(&self, challenge_domain: &Vec<usize>) -> Polynomial {
+    pub fn partial_evaluate(&self, challenge_domain: &[Scalar]) -> Polynomial {
         // the X = x_j, others has values.
         // Note here, x start with x_0, as the array index start with 0.
         let j = challenge_domain.len();
         assert!(j < self.var_num);
 
         // <k,v>: k is the exp of X, v is the coeff, aka. <exp, coeff>
-        let mut map: HashMap<usize, Scalar> = HashMap::new();
+        let mut map: BTreeMap<usize, Scalar> = BTreeMap::new();
 
         // var_num = challenger_len + 1 + extra_len
         let extra_var_num = self.var_num - j - 1;
         let extra_n = 1 << extra_var_num;
         let extra_domain = (0..extra_n)
             .into_iter()
-            .map(|n| convert_to_binary(&extra_var_num, n))
+            .map(|n| binary_point(&extra_var_num, n))
             .collect::<Vec<_>>();
         debug!(
             "extra domain {:?}, j {:?}, var_num:{:?}, extra_var_num: {:?}, extra_n: {:?}",
@@ -200,11 +182,11 @@ impl MPolynomial {
 
                 // compute product of x , eg: product_x = (x_1^exp1) * (x_2^exp2), except x_j
                 let mut key = 0;
-                let mut product = 1;
+                let mut product = Scalar::one();
 
                 // evaluate on domain + hypercube_i
-                let mut domain = challenge_domain.clone();
-                domain.push(0);
+                let mut domain = challenge_domain.to_vec();
+                domain.push(Scalar::zero());
                 domain.extend(extra.clone());
                 debug!(
                     "coeff:{:?}, domain:{:?}, j: {:?}, exps: {:?}",
@@ -213,21 +195,19 @@ impl MPolynomial {
                 for (index, (xi, exp)) in domain.iter().zip(exps.clone()).enumerate() {
                     if index == j {
                         key = exp.clone();
-                    } else {
-                        let pro = xi.pow(exp.clone() as u32);
-                        debug!("x_{:?}^exp: {:?}^{:?}={:?}", index + 1, xi, exp, pro);
-                        product *= pro;
-                        // product *= xi.pow(exp.clone() as u32);
+                    } else if exp == 1 {
+                        debug!("x_{:?}: {:?}", index + 1, xi);
+                        product *= xi;
                     }
                     // once product, the computation of product is over. As zero multiple anything is zero.
-                    if 0 == product {
+                    if product == Scalar::zero() {
                         break;
                     }
                 }
-                if 0 == product {
+                if product == Scalar::zero() {
                     continue;
                 } else {
-                    let term_i = coeff.mul(&Scalar::from(product as u64));
+                    let term_i = coeff * product;
                     debug!("k:{:?}, v:{:?}", key, term_i);
                     map.entry(key)
                         .and_modify(|v| v.add_assign(&term_i))
@@ -256,7 +236,7 @@ impl MPolynomial {
 mod test {
     use crate::poly::multivar_poly::MPolynomial;
     use crate::poly::univar_poly::Polynomial;
-    use crate::utils::convert_to_binary;
+    use crate::utils::binary_point;
     use bls12_381::Scalar;
     use ff::PrimeField;
 
@@ -313,7 +293,7 @@ mod test {
         let max_num: usize = 1 << var_num;
         let domains = (0..max_num)
             .into_iter()
-            .map(|n| convert_to_binary(&var_num, n))
+            .map(|n| binary_point(&var_num, n))
             .collect::<Vec<_>>();
 
         let actual = domains
@@ -329,7 +309,7 @@ mod test {
         let mpoly = gen_mpoly();
 
         // domain: (0,1,1)
-        let challenge_domain = vec![10];
+        let challenge_domain = vec![Scalar::from_u128(10)];
 
         let actual = mpoly.partial_evaluate(&challenge_domain);
 
@@ -371,7 +351,7 @@ mod test {
         };
 
         // domain: (0,1,1)
-        let domain = convert_to_binary(&var_num, 3);
+        let domain = binary_point(&var_num, 3);
         let target = Scalar::from_u128(10);
 
         let actual = poly.evaluate(&domain);
@@ -1,35 +1,59 @@
+#[cfg(feature = "prover")]
 use crate::poly::multivar_poly::MPolynomial;
-use crate::poly::univar_poly::Polynomial;
+#[cfg(feature = "prover")]
 use crate::sumcheck::prover::Prover;
 use crate::sumcheck::verifier::Verifier;
+use crate::trace::VerificationTrace;
 use crate::transcript::default::Keccak256Transcript;
 use crate::transcript::{poly_to_bytes, Transcript};
-use bls12_381::Scalar;
-use std::env::var;
-use std::iter::Sum;
+use alloc::vec::Vec;
+#[cfg(any(test, feature = "cost"))]
+use sumcheck::cost::CostReport;
 
+pub mod aggregate;
+pub mod batched;
+pub mod masked;
+pub mod params;
+pub mod proof;
+#[cfg(feature = "prover")]
 mod prover;
+#[cfg(feature = "prover")]
+pub mod session;
+pub mod subset;
 mod verifier;
 
-#[derive(Default)]
-pub struct Proofs {
-    target: Scalar,
-    g_i_vec: Vec<Polynomial>,
-}
+#[cfg(feature = "prover")]
+pub use aggregate::prove_aggregate;
+pub use aggregate::{verify_aggregate, AggregateError, AggregateSumCheckProof, PolyOracle};
+pub use batched::{BatchError, BatchedSumCheck, EvalClaims};
+pub use masked::{MaskedError, MaskedSumCheck, MaskedSumCheckProof};
+pub use params::{Binding, DegreeSchedule, SumCheckParams, VarOrder};
+pub use proof::{protocol_id, DecodeError, SumCheckProof};
+#[cfg(feature = "prover")]
+pub use session::{ProverCheckpoint, ProverSession, SessionError, SessionStatus, WorkBudget};
+pub use subset::{Indicator, SubsetClaim, SubsetError, SubsetSumCheck};
+pub use verifier::{BytesError, VerifiedClaim, VerifyError};
 
+#[cfg(feature = "prover")]
 pub struct SumCheck {
     prover: Prover,
     verifier: Verifier,
 }
 
+#[cfg(feature = "prover")]
 impl SumCheck {
     pub fn new(g: MPolynomial) -> Self {
+        Self::with_params(g, SumCheckParams::default())
+    }
+
+    // g is read and bound in the convention, see `params`.
+    pub fn with_params(g: MPolynomial, params: SumCheckParams) -> Self {
         let var_num = g.var_num;
 
-        let prover = Prover::new(var_num, g);
+        let prover = Prover::with_params(var_num, g, params.clone());
         let statement = prover.statement();
 
-        let verifier = Verifier::new(var_num, statement);
+        let verifier = Verifier::with_params(var_num, statement, params);
 
         Self { prover, verifier }
     }
@@ -39,12 +63,102 @@ impl SumCheck {
 
         self.verifier.verify(proofs);
     }
+
+    // Generate the proof in the wire format, see `proof` for the layout.
+    pub fn prove_non_interactive(&mut self) -> Vec<u8> {
+        self.prover.prove().to_bytes()
+    }
+
+    // The proof with the challenges of the given transcript, eg: `PoseidonTranscript`, see
+    // `verify_with_transcript`.
+    pub fn prove_with_transcript<T: Transcript>(&mut self, transcript: &mut T) -> SumCheckProof {
+        self.prover.prove_with_transcript(transcript)
+    }
+}
+
+// Verify a proof in the wire format, which may come from other implementations, and return the
+// claim g(r_1, ..., r_v) = final_eval it's reduced to.
+// The malformed bytes are rejected as `BytesError::Decode`, and the invalid proof as
+// `BytesError::Verify`.
+pub fn verify_non_interactive(bytes: &[u8]) -> Result<VerifiedClaim, BytesError> {
+    let proof = SumCheckProof::from_bytes(bytes)?;
+    let mut verifier = Verifier::new(proof.var_num as usize, proof.claimed_sum);
+    let value = proof.final_eval;
+    verifier.verify_with_sink(proof, &mut ())?;
+    Ok(verifier.claim(value))
+}
+
+// Verify the proof in the convention, and return the final point (x_1, ..., x_v) in the order of
+// the caller, where g(x_1, ..., x_v) is claimed to be the final_eval.
+pub fn verify_with_params(
+    proof: &SumCheckProof,
+    params: SumCheckParams,
+) -> Result<VerifiedClaim, VerifyError> {
+    verify_with_transcript(proof, params, &mut Keccak256Transcript::default())
 }
 
-#[cfg(test)]
+// The same as `verify_with_params`, but the challenges are of the given transcript, which is of
+// the backend P proved with, eg: a fresh `PoseidonTranscript`.
+pub fn verify_with_transcript<T: Transcript>(
+    proof: &SumCheckProof,
+    params: SumCheckParams,
+    transcript: &mut T,
+) -> Result<VerifiedClaim, VerifyError> {
+    let mut verifier = Verifier::with_params(proof.var_num as usize, proof.claimed_sum, params);
+    verifier.verify_with_transcript(proof.clone(), transcript, &mut ())?;
+    Ok(verifier.claim(proof.final_eval))
+}
+
+// Verify the proof, and return the trace of every check and challenge along with the result,
+// so the reason of a rejection can be found without touching the verifier.
+pub fn verify_with_trace(
+    proof: &SumCheckProof,
+) -> (Result<VerifiedClaim, VerifyError>, VerificationTrace) {
+    let var_num = proof.var_num as usize;
+    // a check and a challenge per round, and the final check.
+    let mut trace = VerificationTrace::with_capacity(2 * var_num + 1);
+    let mut verifier = Verifier::new(var_num, proof.claimed_sum);
+    let res = verifier
+        .verify_with_sink(proof.clone(), &mut trace)
+        .map(|_| verifier.claim(proof.final_eval));
+    (res, trace)
+}
+
+// Verify the proof in the convention, and return the ops of the verifier along with the result,
+// see `sumcheck::cost`. The result is the one of `verify_with_params`.
+#[cfg(any(test, feature = "cost"))]
+pub fn verify_with_cost(
+    proof: &SumCheckProof,
+    params: SumCheckParams,
+) -> (Result<VerifiedClaim, VerifyError>, CostReport) {
+    let mut cost = CostReport::default();
+    let mut verifier = Verifier::with_params(proof.var_num as usize, proof.claimed_sum, params);
+    let res = verifier
+        .verify_with_sink(proof.clone(), &mut cost)
+        .map(|_| verifier.claim(proof.final_eval));
+    (res, cost)
+}
+
+// The claim `verify_with_params` returns on success, from the challenges of the transcript only,
+// so nothing is checked, eg: to inspect a proof cheaply. The convention is the one of the proof.
+pub fn derive_challenges_only(proof: &SumCheckProof) -> VerifiedClaim {
+    let mut verifier = Verifier::with_params(
+        proof.var_num as usize,
+        proof.claimed_sum,
+        proof.params.clone(),
+    );
+    verifier.derive_challenges(proof);
+    verifier.claim(proof.final_eval)
+}
+
+#[cfg(all(test, feature = "prover"))]
 mod test {
     use crate::poly::multivar_poly::MPolynomial;
-    use crate::sumcheck::SumCheck;
+    use crate::sumcheck::{
+        derive_challenges_only, verify_non_interactive, verify_with_trace, BytesError, DecodeError,
+        SumCheck, SumCheckProof, VerifyError,
+    };
+    use crate::trace::TraceEvent;
     use bls12_381::Scalar;
     use ff::PrimeField;
 
@@ -82,4 +196,339 @@ mod test {
 
         sumcheck.run_protocol();
     }
+
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    // The same instance under both backends, each proof verifies with its own backend only.
+    #[test]
+    fn test_backends() {
+        use crate::sumcheck::{verify_with_params, verify_with_transcript, SumCheckParams};
+        use crate::transcript::default::{Keccak256Transcript, PoseidonTranscript};
+
+        let params = SumCheckParams::default();
+        let keccak =
+            SumCheck::new(gen_mpoly()).prove_with_transcript(&mut Keccak256Transcript::default());
+        let poseidon =
+            SumCheck::new(gen_mpoly()).prove_with_transcript(&mut PoseidonTranscript::default());
+        // Keccak is the backend of `prove`.
+        assert_eq!(keccak, SumCheck::new(gen_mpoly()).prover.prove());
+        assert_ne!(keccak.to_bytes(), poseidon.to_bytes());
+
+        let claim = verify_with_transcript(
+            &poseidon,
+            params.clone(),
+            &mut PoseidonTranscript::default(),
+        );
+        assert_eq!(claim.unwrap().value, poseidon.final_eval);
+        assert!(verify_with_params(&keccak, params.clone()).is_ok());
+        assert!(verify_with_params(&poseidon, params.clone()).is_err());
+        assert!(
+            verify_with_transcript(&keccak, params, &mut PoseidonTranscript::default()).is_err()
+        );
+    }
+
+    #[test]
+    fn test_non_interactive_bytes() {
+        let mut sumcheck = SumCheck::new(gen_mpoly());
+        let bytes = sumcheck.prove_non_interactive();
+        assert!(verify_non_interactive(&bytes).is_ok());
+    }
+
+    // A false proof in well-formed bytes is an error, as the malformed bytes are.
+    #[test]
+    fn test_non_interactive_rejects() {
+        let bytes = SumCheck::new(gen_mpoly()).prove_non_interactive();
+        let mut proof = SumCheckProof::from_bytes(&bytes).unwrap();
+        proof.final_eval += Scalar::one();
+        assert_eq!(
+            verify_non_interactive(&proof.to_bytes()),
+            Err(BytesError::Verify(VerifyError::FinalCheckFailed))
+        );
+        assert_eq!(
+            verify_non_interactive(&bytes[..bytes.len() - 1]),
+            Err(BytesError::Decode(DecodeError::UnexpectedEnd))
+        );
+    }
+
+    // v = 0 has no round, g() is the claimed sum and the final eval, and v = 1 has round 1 only.
+    #[test]
+    fn test_small_var_num() {
+        for var_num in 0..2 {
+            let coeffs = (0..1u128 << var_num).map(|i| Scalar::from_u128(i + 5));
+            let g = MPolynomial {
+                var_num,
+                coeffs: coeffs.collect(),
+            };
+            let bytes = SumCheck::new(g).prove_non_interactive();
+            let proof = SumCheckProof::from_bytes(&bytes).unwrap();
+            assert_eq!(proof.num_rounds(), var_num);
+            assert!(verify_non_interactive(&bytes).is_ok());
+            assert!(verify_with_trace(&proof).0.is_ok());
+
+            let mut bad = proof.clone();
+            bad.final_eval += Scalar::one();
+            assert_eq!(
+                verify_with_trace(&bad).0,
+                Err(VerifyError::FinalCheckFailed)
+            );
+        }
+    }
+
+    #[test]
+    fn test_known_answer() {
+        // The proof of g(x1, x2, x3) = 9 + 2*x3 + 3*x2 + 2 * x1 * x2 + 4* x1 * x2 * x3
+        let mut sumcheck = SumCheck::new(gen_mpoly());
+        let bytes = sumcheck.prove_non_interactive();
+        assert_eq!(to_hex(&bytes), KAT_HEX);
+    }
+
+    #[test]
+    fn test_verify_fixture() {
+        // V alone on the embedded proof, which is how a verifier without std (eg: in wasm) runs:
+        //      cargo test --no-default-features --features alloc,prover
+        let bytes = from_hex(KAT_HEX);
+        let proof = SumCheckProof::from_bytes(&bytes).unwrap();
+        let (res, trace) = verify_with_trace(&proof);
+        let claim = res.unwrap();
+        assert_eq!(trace.to_string(), TRACE_SNAPSHOT);
+        assert_eq!(verify_non_interactive(&bytes), Ok(claim.clone()));
+        // the same challenges, without the checks.
+        assert_eq!(derive_challenges_only(&proof), claim);
+    }
+
+    #[test]
+    fn test_trace_honest() {
+        let proof = SumCheck::new(gen_mpoly()).prover.prove();
+        let (res, trace) = verify_with_trace(&proof);
+        assert!(res.is_ok());
+        // 3 round checks, 3 challenges and the final check
+        assert_eq!(trace.events.len(), 7);
+        assert!(trace.events.iter().all(|e| e.is_ok()));
+        assert_eq!(trace.first_failure(), None);
+    }
+
+    // P is built for 3 variables and V for 4: V rejects the proof before any round.
+    #[test]
+    fn test_instance_mismatch() {
+        use crate::sumcheck::verifier::Verifier;
+        use crate::trace::VerificationTrace;
+
+        let mut sumcheck = SumCheck::new(gen_mpoly());
+        let statement = sumcheck.prover.statement();
+        let proof = sumcheck.prover.prove();
+        let mut trace = VerificationTrace::default();
+        let res = Verifier::new(4, statement).verify_with_sink(proof.clone(), &mut trace);
+        assert_eq!(
+            res,
+            Err(VerifyError::VarNumMismatch {
+                proof: 3,
+                instance: 4
+            })
+        );
+        assert!(trace.events.is_empty());
+
+        let mut missing = proof.clone();
+        missing.round_degrees.pop();
+        assert_eq!(
+            verify_with_trace(&missing).0,
+            Err(VerifyError::RoundCountMismatch {
+                var_num: 3,
+                rounds: 2
+            })
+        );
+
+        // g_2 is declared of degree 0, but it's sent with 2 coeffs.
+        let mut lower = proof;
+        lower.round_degrees[1] = 0;
+        let (res, trace) = verify_with_trace(&lower);
+        assert_eq!(
+            res,
+            Err(VerifyError::RoundDegreeMismatch {
+                round: 2,
+                declared: 0,
+                len: 2
+            })
+        );
+        // round 1 is checked, and r_1 derived.
+        assert_eq!(trace.events.len(), 2);
+    }
+
+    // A verifier left by a rejected proof checks the next one from the start, rather than
+    // panicking on the challenges of the one before.
+    #[test]
+    fn test_reused_verifier() {
+        use crate::sumcheck::verifier::Verifier;
+
+        let mut sumcheck = SumCheck::new(gen_mpoly());
+        let statement = sumcheck.prover.statement();
+        let proof = sumcheck.prover.prove();
+        let mut lie = proof.clone();
+        lie.final_eval += Scalar::one();
+
+        let mut verifier = Verifier::new(3, statement);
+        assert_eq!(
+            verifier.verify_with_sink(lie, &mut ()),
+            Err(VerifyError::FinalCheckFailed)
+        );
+        assert_eq!(verifier.verify_with_sink(proof.clone(), &mut ()), Ok(()));
+        assert_eq!(verifier.verify_with_sink(proof, &mut ()), Ok(()));
+    }
+
+    #[test]
+    fn test_trace_round_2_lie() {
+        let mut proof = SumCheck::new(gen_mpoly()).prover.prove();
+        proof.rounds[1].coeffs[0] += Scalar::one();
+
+        let (res, trace) = verify_with_trace(&proof);
+        assert_eq!(res, Err(VerifyError::RoundCheckFailed { round: 2 }));
+
+        // g_2(0) + g_2(1) != g_1(r_1)
+        let r_1 = match trace.events[1] {
+            TraceEvent::ChallengeDerived {
+                round: 1, value, ..
+            } => value,
+            _ => panic!("r_1 is expected"),
+        };
+        let lhs =
+            proof.rounds[1].evaluate(Scalar::zero()) + proof.rounds[1].evaluate(Scalar::one());
+        let rhs = proof.rounds[0].evaluate(r_1);
+        assert_ne!(lhs, rhs);
+        assert_eq!(
+            trace.first_failure(),
+            Some(&TraceEvent::RoundChecked {
+                round: 2,
+                lhs,
+                rhs,
+                ok: false
+            })
+        );
+        // it stops at the failure
+        assert_eq!(trace.events.len(), 3);
+    }
+
+    #[test]
+    fn test_trace_display() {
+        let proof = SumCheck::new(gen_mpoly()).prover.prove();
+        let (_, trace) = verify_with_trace(&proof);
+        println!("{}", trace);
+        assert_eq!(trace.to_string(), TRACE_SNAPSHOT);
+    }
+
+    // g(x1, x2, x3, x4) with the coeffs 1, ..., 16, every monomial is there, see `MPolynomial`.
+    fn fixture_mpoly() -> MPolynomial {
+        MPolynomial {
+            var_num: 4,
+            coeffs: (1..=16u64).map(Scalar::from).collect(),
+        }
+    }
+
+    // The golden proof in `tests/fixtures/sumcheck.hex`, see `Fiat_Shamir::fixtures`.
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_fixture() {
+        use crate::sumcheck::proof::protocol_id;
+        use crate::sumcheck::{verify_with_params, SumCheckParams};
+        use Fiat_Shamir::fixtures::{assert_same_bytes, load_or_regenerate};
+
+        let g = fixture_mpoly();
+        let current = SumCheck::new(g.clone()).prove_non_interactive();
+        let stored = load_or_regenerate("sumcheck", &current);
+
+        let expected = protocol_id(4, 1, &SumCheckParams::default());
+        let proof = SumCheckProof::from_bytes_for(&stored, &expected)
+            .unwrap_or_else(|e| panic!("fixture sumcheck: can't decode: {:?}", e));
+        let claim = verify_with_params(&proof, SumCheckParams::default())
+            .unwrap_or_else(|e| panic!("fixture sumcheck: rejected: {:?}", e));
+        assert_eq!(
+            g.evaluate(&claim.point),
+            claim.value,
+            "fixture sumcheck: final_eval is not g(r_1, ..., r_4)"
+        );
+        assert_eq!(
+            derive_challenges_only(&proof),
+            claim,
+            "fixture sumcheck: the derived challenges differ from the verified ones"
+        );
+        assert_same_bytes("sumcheck", &stored, &current);
+    }
+
+    // The point of the claim V is left with is the one P evaluated g at, whatever the convention.
+    #[test]
+    fn test_verified_claim() {
+        use crate::sumcheck::params::{Binding, SumCheckParams, VarOrder};
+        use crate::sumcheck::verify_with_params;
+
+        let g = fixture_mpoly();
+        for params in [
+            SumCheckParams::default(),
+            SumCheckParams::new(VarOrder::LsbFirst, Binding::Suffix),
+            SumCheckParams::new(VarOrder::MsbFirst, Binding::Suffix).with_compressed_rounds(),
+        ] {
+            let mut sumcheck = SumCheck::with_params(g.clone(), params.clone());
+            let proof = sumcheck.prover.prove();
+            let claim = verify_with_params(&proof, params).unwrap();
+            assert_eq!(claim.point, sumcheck.prover.final_point());
+            assert_eq!(claim.value, proof.final_eval);
+            assert_eq!(derive_challenges_only(&proof), claim);
+        }
+        let proof = SumCheck::new(g.clone()).prover.prove();
+        assert_eq!(
+            g.evaluate(
+                &verify_with_params(&proof, SumCheckParams::default())
+                    .unwrap()
+                    .point
+            ),
+            proof.final_eval
+        );
+
+        // the challenges are derived even if a check fails, as P sent it.
+        let mut proof = SumCheck::new(g).prover.prove();
+        proof.final_eval += Scalar::one();
+        let derived = derive_challenges_only(&proof);
+        assert_eq!(derived.value, proof.final_eval);
+        assert_eq!(
+            verify_with_params(&proof, SumCheckParams::default()),
+            Err(VerifyError::FinalCheckFailed)
+        );
+    }
+
+    // The trace of the proof in `test_known_answer`.
+    const TRACE_SNAPSHOT: &str = concat!(
+        "round 1: 0x0000000000000000000000000000000000000000000000000000000000000064",
+        " == 0x0000000000000000000000000000000000000000000000000000000000000064\n",
+        "challenge r_1 = 0x0838636f78b4439443bf7ecef2dd7590f1273c4d8669fa58ef3c0e4ae572741b\n",
+        "round 2: 0x41c31b7bc5a21ca21dfbf67796ebac878939e26c334fd2c779e072572b93a106",
+        " == 0x41c31b7bc5a21ca21dfbf67796ebac878939e26c334fd2c779e072572b93a106\n",
+        "challenge r_2 = 0x143df620c3c213e660590dded8887c72661ca93b2b3a903060406542d59d57bb\n",
+        "round 3: 0x36f24aec39e6e5b59dba7bc221fc660c8f4bdfb284e08997f7de727ee78ef911",
+        " == 0x36f24aec39e6e5b59dba7bc221fc660c8f4bdfb284e08997f7de727ee78ef911\n",
+        "challenge r_3 = 0x6fb9bc5db5299d1bc12843f57cb67a3b8dccedeced585e152901ac8157cede7f\n",
+        "final: 0x53daa79d3d2a960c88c9791bd7ac5e5a61355ca952867c2e3ef9010741ed6d8d",
+        " == 0x53daa79d3d2a960c88c9791bd7ac5e5a61355ca952867c2e3ef9010741ed6d8d\n",
+    );
+
+    // version = 1, var_num = 3, degree_bound = 1, claimed_sum = 100, g_1 = 46 + 8X,
+    // then g_2, g_3 and final_eval, which depend on the challenges from Keccak256Transcript
+    // bound to the protocol id of the proof, each reduced from two squeezes by
+    // `challenge_to_scalar`.
+    const KAT_HEX: &str = concat!(
+        "0503010064000000000000000000000000000000000000000000000000000000",
+        "00000000010101022e0000000000000000000000000000000000000000000000",
+        "0000000000000000080000000000000000000000000000000000000000000000",
+        "0000000000000000021400000000000000000000000000000000000000000000",
+        "000000000000000000dea0932b5772e079c7d24f336ce2398987aceb9677f6fb",
+        "1da21ca2c57b1bc34102e1c14fba8334584eae910f22c65eed11304ab4cfc0a9",
+        "c204993684fec0ed14495075ef7276092e5b3ac2bf4029c62ebfb1a9358c4800",
+        "6fc7cbf57b66bd16b6188d6ded410701f93e2e7c8652a95c35615a5eacd71b79",
+        "c9880c962a3d9da7da53",
+    );
 }
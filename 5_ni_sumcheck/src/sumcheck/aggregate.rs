@@ -0,0 +1,419 @@
+// Many independent sum-checks in one proof, eg: one per constraint group, which may have
+// different var_num. Unlike `batched`, nothing is combined: each instance has its own proof,
+// only the transcript is shared.
+//
+// P and V absorb the `ProtocolId` of the aggregate, the var_nums and the claimed sums of all the
+// instances up front, then run the sum-checks in order on the same transcript. So the challenges
+// of instance k depend on every claim, and no instance can be chosen after the challenges of
+// another one.
+//
+// An aggregate of one absorbs nothing, so it's the same as `prove_table`, challenges and all.
+//
+// The final check g_k(r) = final_eval_k is done by V with the oracle of each instance, see
+// `PolyOracle`.
+//
+// Layout (version 1), the integers are in little-endian:
+//
+//      | size | field                                              |
+//      |------|----------------------------------------------------|
+//      | 1    | version = 1                                        |
+//      | 4    | n, the instances num                               |
+//      | ...  | n proofs in order, each one is:                    |
+//      |      |   len: u32, the size of the proof                  |
+//      |      |   len bytes, the proof in the format of `proof`    |
+//
+// The rounds of each proof are compressed if its params say so. No trailing bytes are allowed.
+use crate::sumcheck::proof::DecodeError;
+use crate::sumcheck::verifier::Verifier;
+use crate::sumcheck::{SumCheckProof, VerifyError};
+use crate::transcript::default::Keccak256Transcript;
+use crate::transcript::Transcript;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use core::fmt;
+use sumcheck::poly::eval_table::EvalTable;
+use Fiat_Shamir::codec::{encode_bytes, encode_scalars};
+use Fiat_Shamir::protocol::ProtocolId;
+
+#[cfg(feature = "prover")]
+mod prover;
+#[cfg(feature = "prover")]
+pub use prover::prove_aggregate;
+
+pub const AGGREGATE_PROOF_VERSION: u8 = 1;
+
+// V's access to the poly of an instance, for the final check.
+pub trait PolyOracle {
+    fn var_num(&self) -> usize;
+
+    // g(x_1, ..., x_v), the point is in the order of the caller, see `SumCheckParams::point`.
+    fn evaluate(&self, point: &[Scalar]) -> Scalar;
+}
+
+// x_1 is the MSB of the index, so it's for the proofs in the default var_order.
+impl PolyOracle for EvalTable {
+    fn var_num(&self) -> usize {
+        self.var_num
+    }
+
+    fn evaluate(&self, point: &[Scalar]) -> Scalar {
+        EvalTable::evaluate(self, point)
+    }
+}
+
+impl<T: PolyOracle + ?Sized> PolyOracle for &T {
+    fn var_num(&self) -> usize {
+        (**self).var_num()
+    }
+
+    fn evaluate(&self, point: &[Scalar]) -> Scalar {
+        (**self).evaluate(point)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AggregateError {
+    // the proofs num is not the instances num.
+    CountMismatch { instances: usize, proofs: usize },
+    // the proof has another var_num from the oracle.
+    VarNumMismatch,
+    // the rounds num is not var_num.
+    MalformedProof,
+    Verify(VerifyError),
+}
+
+impl fmt::Display for AggregateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AggregateError::CountMismatch { instances, proofs } => {
+                write!(f, "{} proofs for {} instances", proofs, instances)
+            }
+            AggregateError::VarNumMismatch => write!(f, "The proof has another var_num"),
+            AggregateError::MalformedProof => write!(f, "The rounds num is not var_num"),
+            AggregateError::Verify(_) => write!(f, "Verifier rejected the instance"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AggregateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AggregateError::Verify(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<VerifyError> for AggregateError {
+    fn from(e: VerifyError) -> Self {
+        AggregateError::Verify(e)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AggregateSumCheckProof {
+    pub proofs: Vec<SumCheckProof>, // in the order of the instances
+}
+
+// The protocol of the aggregate, the params of each instance are in its own proof.
+pub fn protocol_id(var_nums: &[u8]) -> ProtocolId {
+    let params = encode_bytes(var_nums);
+    ProtocolId::new("ni_sumcheck", "aggregate", AGGREGATE_PROOF_VERSION, &params)
+}
+
+// Absorb the protocol, the var_nums and the claims of all the instances, nothing if there's only
+// one.
+fn absorb_instances(var_nums: &[u8], claims: &[Scalar], transcript: &mut Keccak256Transcript) {
+    if claims.len() == 1 {
+        return;
+    }
+    transcript.bind_protocol(&protocol_id(var_nums));
+    transcript.append(&encode_bytes(var_nums));
+    transcript.append(&encode_scalars(claims));
+}
+
+// Verify the proofs of the instances (oracle, claimed sum) on one transcript, and return the
+// index of the first instance which fails, or the first one without a proof.
+pub fn verify_aggregate<O: PolyOracle>(
+    instances: &[(O, Scalar)],
+    proof: &AggregateSumCheckProof,
+) -> Result<(), (usize, AggregateError)> {
+    let proofs = &proof.proofs;
+    if proofs.len() != instances.len() {
+        let index = proofs.len().min(instances.len());
+        return Err((
+            index,
+            AggregateError::CountMismatch {
+                instances: instances.len(),
+                proofs: proofs.len(),
+            },
+        ));
+    }
+
+    // V absorbs its own statements, the claims in the proofs are checked against them later.
+    let var_nums = instances
+        .iter()
+        .map(|(oracle, _)| oracle.var_num() as u8)
+        .collect::<Vec<_>>();
+    let claims = instances.iter().map(|(_, h)| *h).collect::<Vec<_>>();
+    let mut transcript = Keccak256Transcript::default();
+    absorb_instances(&var_nums, &claims, &mut transcript);
+
+    for (k, ((oracle, claim), proof)) in instances.iter().zip(proofs.iter()).enumerate() {
+        verify_instance(oracle, *claim, proof, &mut transcript).map_err(|e| (k, e))?;
+    }
+    Ok(())
+}
+
+fn verify_instance<O: PolyOracle>(
+    oracle: &O,
+    claim: Scalar,
+    proof: &SumCheckProof,
+    transcript: &mut Keccak256Transcript,
+) -> Result<(), AggregateError> {
+    let var_num = oracle.var_num();
+    if proof.var_num as usize != var_num {
+        return Err(AggregateError::VarNumMismatch);
+    }
+    if proof.num_rounds() != var_num {
+        return Err(AggregateError::MalformedProof);
+    }
+
+    let mut verifier = Verifier::with_params(var_num, claim, proof.params.clone());
+    verifier
+        .verify_with_transcript(proof.clone(), transcript, &mut ())
+        .map_err(AggregateError::Verify)?;
+    // g(r_1, ..., r_v) = final_eval by the oracle.
+    if oracle.evaluate(&verifier.point()) != proof.final_eval {
+        return Err(AggregateError::Verify(VerifyError::FinalCheckFailed));
+    }
+    Ok(())
+}
+
+impl AggregateSumCheckProof {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let proofs = self
+            .proofs
+            .iter()
+            .map(SumCheckProof::to_bytes)
+            .collect::<Vec<_>>();
+        let size = proofs.iter().map(|p| 4 + p.len()).sum::<usize>();
+        let mut bytes = Vec::with_capacity(1 + 4 + size);
+
+        bytes.push(AGGREGATE_PROOF_VERSION);
+        bytes.extend((proofs.len() as u32).to_le_bytes());
+        for p in proofs.iter() {
+            bytes.extend((p.len() as u32).to_le_bytes());
+            bytes.extend(p);
+        }
+        bytes
+    }
+
+    // Each proof is decoded by `SumCheckProof::from_bytes`, so its errors are returned as is.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let (version, mut rest) = bytes.split_first().ok_or(DecodeError::UnexpectedEnd)?;
+        if *version != AGGREGATE_PROOF_VERSION {
+            return Err(DecodeError::UnknownVersion(*version));
+        }
+        let n = read_u32(&mut rest)? as usize;
+        // each proof has its len at least, it's checked before the allocation.
+        if rest.len() / 4 < n {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+
+        let mut proofs = Vec::with_capacity(n);
+        for _ in 0..n {
+            let len = read_u32(&mut rest)? as usize;
+            if rest.len() < len {
+                return Err(DecodeError::UnexpectedEnd);
+            }
+            let (proof, tail) = rest.split_at(len);
+            proofs.push(SumCheckProof::from_bytes(proof)?);
+            rest = tail;
+        }
+        if !rest.is_empty() {
+            return Err(DecodeError::TrailingBytes(rest.len()));
+        }
+        Ok(Self { proofs })
+    }
+}
+
+fn read_u32(bytes: &mut &[u8]) -> Result<u32, DecodeError> {
+    if bytes.len() < 4 {
+        return Err(DecodeError::UnexpectedEnd);
+    }
+    let (head, tail) = bytes.split_at(4);
+    *bytes = tail;
+    Ok(u32::from_le_bytes([head[0], head[1], head[2], head[3]]))
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod test {
+    use crate::sumcheck::aggregate::{
+        prove_aggregate, verify_aggregate, AggregateError, AggregateSumCheckProof,
+    };
+    use crate::sumcheck::batched::prove_table;
+    use crate::sumcheck::proof::DecodeError;
+    use crate::sumcheck::{verify_with_params, SumCheckParams, VerifyError};
+    use crate::transcript::default::Keccak256Transcript;
+    use bls12_381::Scalar;
+    use sumcheck::poly::eval_table::{random_table, EvalTable};
+
+    // 10 instances with var_num from 2 to 6.
+    fn tables() -> Vec<EvalTable> {
+        (0..10).map(|k| random_table(2 + k % 5)).collect()
+    }
+
+    fn instances(tables: &[EvalTable]) -> Vec<(&EvalTable, Scalar)> {
+        tables.iter().map(|t| (t, t.sum())).collect()
+    }
+
+    #[test]
+    fn test_aggregate_of_ten() {
+        let tables = tables();
+        for params in [
+            SumCheckParams::default(),
+            SumCheckParams::default().with_compressed_rounds(),
+        ] {
+            let proof = prove_aggregate(&tables, params.clone());
+            assert_eq!(proof.proofs.len(), 10);
+            assert_eq!(proof.proofs[3].var_num, 5);
+            assert!(proof.proofs.iter().all(|p| p.params == params));
+            assert_eq!(verify_aggregate(&instances(&tables), &proof), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_corrupted_instance() {
+        let tables = tables();
+        let proof = prove_aggregate(&tables, SumCheckParams::default());
+
+        let mut corrupted = proof.clone();
+        corrupted.proofs[7].final_eval += Scalar::one();
+        assert_eq!(
+            verify_aggregate(&instances(&tables), &corrupted),
+            Err((7, AggregateError::Verify(VerifyError::FinalCheckFailed)))
+        );
+
+        let mut corrupted = proof.clone();
+        corrupted.proofs[7].rounds[1].coeffs[0] += Scalar::one();
+        assert_eq!(
+            verify_aggregate(&instances(&tables), &corrupted),
+            Err((
+                7,
+                AggregateError::Verify(VerifyError::RoundCheckFailed { round: 2 })
+            ))
+        );
+
+        // a wrong claim changes the challenges of all the instances, the first one fails.
+        let mut wrong = instances(&tables);
+        wrong[7].1 += Scalar::one();
+        assert!(matches!(
+            verify_aggregate(&wrong, &proof),
+            Err((0, AggregateError::Verify(_)))
+        ));
+
+        // the proof of another poly with the same sum passes the rounds, but not the oracle.
+        let mut other = tables.clone();
+        other[7].evals.swap(0, 1);
+        assert_eq!(
+            verify_aggregate(&instances(&other), &proof),
+            Err((7, AggregateError::Verify(VerifyError::FinalCheckFailed)))
+        );
+    }
+
+    #[test]
+    fn test_malformed_aggregate() {
+        let tables = tables();
+        let mut proof = prove_aggregate(&tables, SumCheckParams::default());
+
+        let mut swapped = proof.clone();
+        swapped.proofs.swap(0, 1);
+        assert_eq!(
+            verify_aggregate(&instances(&tables), &swapped),
+            Err((0, AggregateError::VarNumMismatch))
+        );
+
+        proof.proofs.pop();
+        assert_eq!(
+            verify_aggregate(&instances(&tables), &proof),
+            Err((
+                9,
+                AggregateError::CountMismatch {
+                    instances: 10,
+                    proofs: 9
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_aggregate_of_one() {
+        let table = random_table(4);
+        let proof = prove_aggregate(core::slice::from_ref(&table), SumCheckParams::default());
+        let standalone = prove_table(&table, &mut Keccak256Transcript::default());
+        // the same challenges, so the same proof.
+        assert_eq!(proof.proofs, vec![standalone.clone()]);
+
+        let instance = [(&table, table.sum())];
+        let params = SumCheckParams::default();
+        assert_eq!(verify_aggregate(&instance, &proof), Ok(()));
+        assert!(verify_with_params(&standalone, params.clone()).is_ok());
+
+        // and they reject alike.
+        let mut corrupted = proof.clone();
+        corrupted.proofs[0].rounds[2].coeffs[1] += Scalar::one();
+        let expected = verify_with_params(&corrupted.proofs[0], params).unwrap_err();
+        assert_eq!(expected, VerifyError::RoundCheckFailed { round: 3 });
+        assert_eq!(
+            verify_aggregate(&instance, &corrupted),
+            Err((0, AggregateError::Verify(expected)))
+        );
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let tables = tables();
+        for params in [
+            SumCheckParams::default(),
+            SumCheckParams::default().with_compressed_rounds(),
+        ] {
+            let proof = prove_aggregate(&tables, params);
+            let bytes = proof.to_bytes();
+            let size = proof
+                .proofs
+                .iter()
+                .map(|p| 4 + p.to_bytes().len())
+                .sum::<usize>();
+            assert_eq!(bytes.len(), 1 + 4 + size);
+            assert_eq!(AggregateSumCheckProof::from_bytes(&bytes), Ok(proof));
+
+            assert_eq!(
+                AggregateSumCheckProof::from_bytes(&bytes[..bytes.len() - 1]),
+                Err(DecodeError::UnexpectedEnd)
+            );
+            let mut trailing = bytes.clone();
+            trailing.push(0);
+            assert_eq!(
+                AggregateSumCheckProof::from_bytes(&trailing),
+                Err(DecodeError::TrailingBytes(1))
+            );
+            let mut version = bytes.clone();
+            version[0] = 2;
+            assert_eq!(
+                AggregateSumCheckProof::from_bytes(&version),
+                Err(DecodeError::UnknownVersion(2))
+            );
+        }
+
+        // u32::MAX proofs are claimed in a short buffer, which is rejected before the allocation.
+        let mut bytes = AggregateSumCheckProof { proofs: vec![] }.to_bytes();
+        assert_eq!(bytes, vec![1, 0, 0, 0, 0]);
+        bytes[1..5].copy_from_slice(&[0xff; 4]);
+        assert_eq!(
+            AggregateSumCheckProof::from_bytes(&bytes),
+            Err(DecodeError::UnexpectedEnd)
+        );
+    }
+}
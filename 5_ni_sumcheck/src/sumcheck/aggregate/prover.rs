@@ -0,0 +1,29 @@
+// P of the aggregated sum-checks, see `aggregate`.
+use crate::sumcheck::aggregate::{absorb_instances, AggregateSumCheckProof};
+use crate::sumcheck::batched::prove_table_with_params;
+use crate::sumcheck::params::SumCheckParams;
+use crate::transcript::default::Keccak256Transcript;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use sumcheck::poly::eval_table::EvalTable;
+
+// The proofs of the sums of each table, in order, with the rounds in the format of params.
+// The claimed sums are in the proofs.
+pub fn prove_aggregate(instances: &[EvalTable], params: SumCheckParams) -> AggregateSumCheckProof {
+    let var_nums = instances
+        .iter()
+        .map(|t| {
+            assert!(t.var_num <= u8::MAX as usize, "var_num is too large");
+            t.var_num as u8
+        })
+        .collect::<Vec<_>>();
+    let claims = instances.iter().map(|t| t.sum()).collect::<Vec<Scalar>>();
+    let mut transcript = Keccak256Transcript::default();
+    absorb_instances(&var_nums, &claims, &mut transcript);
+
+    let proofs = instances
+        .iter()
+        .map(|t| prove_table_with_params(t, params.clone(), &mut transcript))
+        .collect();
+    AggregateSumCheckProof { proofs }
+}
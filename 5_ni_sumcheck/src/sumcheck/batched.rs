@@ -0,0 +1,266 @@
+// Prove several sums over the hypercube in one sum-check run.
+//
+// For tables W_1, ..., W_k with the claimed sums H_1, ..., H_k, P absorbs [H_1, ..., H_k] and
+// squeezes γ, then runs the sum-check on W = ∑ γ^(i-1)·W_i with the claim H = ∑ γ^(i-1)·H_i.
+// A wrong H_i makes H wrong except with probability (k-1)/|F| over γ.
+//
+// W is never built: each W_i is folded by itself, and the round poly is combined per round,
+//      g_j(X) = ∑ γ^(i-1)·g_{i,j}(X), g_{i,j}(X) = sum(lo_i) + X·(sum(hi_i) - sum(lo_i))
+// where (lo_i, hi_i) are the halves of W_i after binding r_1, ..., r_{j-1}.
+//
+// The final check is left as the evaluation claims of each W_i at the common point r, which
+// are checked against the combined final_eval by `EvalClaims::check`.
+//
+// A batch of one absorbs nothing and has γ^0 = 1 only, so it's the same as `prove_table`.
+use crate::sumcheck::verifier::Verifier;
+use crate::sumcheck::{SumCheckProof, VerifyError};
+use crate::transcript::default::Keccak256Transcript;
+use crate::transcript::Transcript;
+use alloc::vec;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use core::fmt;
+use sumcheck::poly::eval_table::{batch_eval_mles, EvalTable};
+use sumcheck::rlc::{self, CoeffMode};
+use Fiat_Shamir::codec::encode_scalars;
+
+#[cfg(feature = "prover")]
+mod prover;
+#[cfg(feature = "prover")]
+pub(crate) use prover::prove_table_with_point;
+#[cfg(feature = "prover")]
+pub use prover::{prove_table, prove_table_with_params};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchError {
+    EmptyBatch,
+    // the instance has a different var_num from the first one.
+    VarNumMismatch { index: usize },
+    // the rounds num is not var_num.
+    MalformedProof,
+    Verify(VerifyError),
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BatchError::EmptyBatch => write!(f, "The batch is empty"),
+            BatchError::VarNumMismatch { index } => {
+                write!(f, "Instance {} has another var_num", index)
+            }
+            BatchError::MalformedProof => write!(f, "The rounds num is not var_num"),
+            BatchError::Verify(_) => write!(f, "Verifier rejected the batch"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BatchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BatchError::Verify(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<VerifyError> for BatchError {
+    fn from(e: VerifyError) -> Self {
+        BatchError::Verify(e)
+    }
+}
+
+// The result of a batched verification: V accepts iff ∑ weights[i]·W̃_i(point) == combined_eval.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvalClaims {
+    pub point: Vec<Scalar>,
+    pub weights: Vec<Scalar>, // γ^0, γ^1, ..., γ^(k-1)
+    pub combined_eval: Scalar,
+}
+
+impl EvalClaims {
+    // evals[i] is claimed to be W̃_i(point).
+    pub fn check(&self, evals: &[Scalar]) -> bool {
+        rlc::combine_scalars(evals, &self.weights) == Ok(self.combined_eval)
+    }
+
+    // The same as `check` with the evals of the tables, which share the eq table of the point,
+    // see `batch_eval_mles`.
+    pub fn check_tables(&self, tables: &[&EvalTable]) -> bool {
+        tables.len() == self.weights.len()
+            && tables.iter().all(|t| t.var_num == self.point.len())
+            && self.check(&batch_eval_mles(tables, &self.point))
+    }
+}
+
+// [γ^0, ..., γ^(k-1)], γ is squeezed after absorbing the claims, see `rlc::CoeffMode::Powers`.
+fn batch_weights(claims: &[Scalar], transcript: &mut Keccak256Transcript) -> Vec<Scalar> {
+    if claims.len() == 1 {
+        return vec![Scalar::one()];
+    }
+    transcript.append(&encode_scalars(claims));
+    rlc::coeffs_from_transcript(
+        transcript,
+        "sumcheck.batch",
+        claims.len(),
+        CoeffMode::Powers,
+    )
+}
+
+pub struct BatchedSumCheck;
+
+impl BatchedSumCheck {
+    pub fn verify(
+        claims: &[Scalar],
+        proof: &SumCheckProof,
+        transcript: &mut Keccak256Transcript,
+    ) -> Result<EvalClaims, BatchError> {
+        if claims.is_empty() {
+            return Err(BatchError::EmptyBatch);
+        }
+        if proof.num_rounds() != proof.var_num as usize {
+            return Err(BatchError::MalformedProof);
+        }
+
+        // V recomputes the combined claim by itself.
+        let weights = batch_weights(claims, transcript);
+        let combined = weights.iter().zip(claims.iter()).map(|(w, h)| w * h).sum();
+
+        let mut verifier = Verifier::new(proof.var_num as usize, combined);
+        verifier
+            .verify_with_transcript(proof.clone(), transcript, &mut ())
+            .map_err(BatchError::Verify)?;
+
+        Ok(EvalClaims {
+            point: verifier.point(),
+            weights,
+            combined_eval: proof.final_eval,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod test {
+    use crate::poly::multivar_poly::MPolynomial;
+    use crate::sumcheck::batched::{prove_table, BatchError, BatchedSumCheck};
+    use crate::sumcheck::{SumCheck, VerifyError};
+    use crate::transcript::default::Keccak256Transcript;
+    use crate::utils::binary_point;
+    use bls12_381::Scalar;
+    use ff::PrimeField;
+    use sumcheck::poly::eval_table::{random_table, EvalTable};
+    use sumcheck::scalars;
+
+    #[test]
+    fn test_batch_of_three() {
+        let tables = (0..3).map(|_| random_table(8)).collect::<Vec<_>>();
+        let (claims, proof) =
+            BatchedSumCheck::prove(&tables, &mut Keccak256Transcript::default()).unwrap();
+        assert_eq!(claims[1], tables[1].sum());
+
+        let evals_claims =
+            BatchedSumCheck::verify(&claims, &proof, &mut Keccak256Transcript::default()).unwrap();
+        let evals = tables
+            .iter()
+            .map(|t| t.evaluate(&evals_claims.point))
+            .collect::<Vec<_>>();
+        assert!(evals_claims.check(&evals));
+        assert!(evals_claims.check_tables(&tables.iter().collect::<Vec<_>>()));
+        assert!(!evals_claims.check_tables(&[&tables[0], &tables[2], &tables[1]]));
+        assert!(!evals_claims.check_tables(&[&tables[0], &tables[1]]));
+
+        // a wrong evaluation claim
+        let mut wrong = evals.clone();
+        wrong[2] += Scalar::one();
+        assert!(!evals_claims.check(&wrong));
+    }
+
+    #[test]
+    fn test_corrupt_claim() {
+        let tables = (0..3).map(|_| random_table(8)).collect::<Vec<_>>();
+        let (claims, proof) =
+            BatchedSumCheck::prove(&tables, &mut Keccak256Transcript::default()).unwrap();
+
+        for i in 0..3 {
+            let mut corrupted = claims.clone();
+            corrupted[i] += Scalar::one();
+            assert_eq!(
+                BatchedSumCheck::verify(&corrupted, &proof, &mut Keccak256Transcript::default()),
+                Err(BatchError::Verify(VerifyError::WrongClaimedSum))
+            );
+        }
+    }
+
+    #[test]
+    fn test_batch_of_one() {
+        let table = random_table(8);
+        let (claims, proof) = BatchedSumCheck::prove(
+            std::slice::from_ref(&table),
+            &mut Keccak256Transcript::default(),
+        )
+        .unwrap();
+        let expected = prove_table(&table, &mut Keccak256Transcript::default());
+        assert_eq!(claims, vec![table.sum()]);
+        assert_eq!(proof.to_bytes(), expected.to_bytes());
+    }
+
+    #[test]
+    fn test_var_num_mismatch() {
+        let tables = vec![random_table(3), random_table(3), random_table(4)];
+        assert_eq!(
+            BatchedSumCheck::prove(&tables, &mut Keccak256Transcript::default()),
+            Err(BatchError::VarNumMismatch { index: 2 })
+        );
+        assert_eq!(
+            BatchedSumCheck::prove(&[], &mut Keccak256Transcript::default()),
+            Err(BatchError::EmptyBatch)
+        );
+    }
+
+    #[test]
+    fn test_table_prover_is_the_mpoly_one() {
+        // g(x1, x2, x3) = 9 + 2*x3 + 3*x2 + 2 * x1 * x2 + 4* x1 * x2 * x3
+        let mpoly = MPolynomial {
+            var_num: 3,
+            coeffs: [9, 2, 3, 0, 0, 0, 2, 4]
+                .iter()
+                .map(|c| Scalar::from_u128(*c))
+                .collect(),
+        };
+        let table = EvalTable::new(
+            (0..8)
+                .map(|i| mpoly.evaluate(&binary_point(&3, i)))
+                .collect(),
+        );
+
+        let proof = prove_table(&table, &mut Keccak256Transcript::default());
+        let expected = SumCheck::new(mpoly).prove_non_interactive();
+        assert_eq!(proof.to_bytes(), expected);
+    }
+
+    // the copies are counted in std only.
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_witness_copies() {
+        use sumcheck::secret::witness_copies;
+
+        let mpoly = MPolynomial {
+            var_num: 4,
+            coeffs: scalars![3, 1, 4, 1, 5, 9, 2, 6, 5, 3, 5, 8, 9, 7, 9, 3],
+        };
+        let table = EvalTable::new(
+            (0..16)
+                .map(|i| mpoly.evaluate(&binary_point(&4, i)))
+                .collect(),
+        );
+
+        let before = witness_copies();
+        let proof = prove_table(&table, &mut Keccak256Transcript::default());
+        // a single working copy folded in place, it was a new table per round (1 + 4) before.
+        assert_eq!(witness_copies() - before, 1);
+
+        // the same proof with or without the `zeroize` feature.
+        let expected = SumCheck::new(mpoly).prove_non_interactive();
+        assert_eq!(proof.to_bytes(), expected);
+    }
+}
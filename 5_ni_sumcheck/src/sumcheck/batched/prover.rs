@@ -0,0 +1,144 @@
+// P of the batched sum-check, see `batched`.
+use crate::poly::univar_poly::Polynomial;
+use crate::sumcheck::batched::{batch_weights, BatchError, BatchedSumCheck};
+use crate::sumcheck::params::SumCheckParams;
+use crate::sumcheck::SumCheckProof;
+use crate::transcript::default::Keccak256Transcript;
+use crate::transcript::{poly_to_bytes, Transcript};
+use alloc::vec;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use sumcheck::poly::eval_table::EvalTable;
+use sumcheck::rlc;
+use sumcheck::secret::ZeroizingEvalTable;
+use sumcheck::utils::axpy;
+
+// The unbatched sum-check of a single table.
+pub fn prove_table(table: &EvalTable, transcript: &mut Keccak256Transcript) -> SumCheckProof {
+    prove_table_with_params(table, SumCheckParams::default(), transcript)
+}
+
+// The same as `prove_table`, with the table read and bound in the convention.
+pub fn prove_table_with_params(
+    table: &EvalTable,
+    params: SumCheckParams,
+    transcript: &mut Keccak256Transcript,
+) -> SumCheckProof {
+    prove_table_with_point(table, params, transcript).0
+}
+
+// The same as `prove_table_with_params`, and the point r of the final claim, eg: for P to open
+// another table at r.
+pub(crate) fn prove_table_with_point(
+    table: &EvalTable,
+    params: SumCheckParams,
+    transcript: &mut Keccak256Transcript,
+) -> (SumCheckProof, Vec<Scalar>) {
+    let weights = [Scalar::one()];
+    prove_combined(core::slice::from_ref(table), &weights, params, transcript)
+}
+
+// The halves of the table by the bound variable: the MSB splits it, and the LSB interleaves it.
+fn sum_halves(table: &EvalTable, binds_msb: bool) -> (Scalar, Scalar) {
+    if binds_msb {
+        let (lo, hi) = table.split_halves();
+        (lo.iter().sum(), hi.iter().sum())
+    } else {
+        let evals = &table.evals;
+        (
+            evals.iter().step_by(2).sum(),
+            evals.iter().skip(1).step_by(2).sum(),
+        )
+    }
+}
+
+// Bind the variable of the round to r, and return the halves of the next round. The MSB is
+// folded and summed in one pass over the table, see `EvalTable::bind_and_next_round`.
+fn bind_and_sum_halves(table: &mut EvalTable, r: Scalar, binds_msb: bool) -> (Scalar, Scalar) {
+    if binds_msb {
+        let coeffs = table.bind_and_next_round(r).coeffs();
+        (coeffs[0], coeffs[0] + coeffs[1])
+    } else {
+        table.bind_last(r);
+        sum_halves(table, false)
+    }
+}
+
+fn prove_combined(
+    tables: &[EvalTable],
+    weights: &[Scalar],
+    params: SumCheckParams,
+    transcript: &mut Keccak256Transcript,
+) -> (SumCheckProof, Vec<Scalar>) {
+    let var_num = tables[0].var_num;
+    let sums = tables.iter().map(|t| t.sum()).collect::<Vec<_>>();
+    let claimed_sum = rlc::combine_scalars(&sums, weights).expect("a weight per table");
+    // the tables are multilinear, so each g_j has degree 1 at most.
+    let mut proofs = SumCheckProof::new(var_num, 1, claimed_sum).with_params(params.clone());
+    transcript.bind_protocol(&proofs.protocol_id());
+    let binds_msb = params.binds_msb();
+
+    let mut challenges = Vec::with_capacity(var_num);
+
+    // the only copy of the witness, each table is folded in place.
+    let mut tables = tables
+        .iter()
+        .map(ZeroizingEvalTable::copy_of)
+        .collect::<Vec<_>>();
+    let mut halves = tables
+        .iter()
+        .map(|t| sum_halves(t, binds_msb))
+        .collect::<Vec<_>>();
+    for j in 0..var_num {
+        let mut coeffs = vec![Scalar::zero(); 2];
+        for (w, (sum_lo, sum_hi)) in weights.iter().zip(halves.iter()) {
+            axpy(&mut coeffs, *w, &[*sum_lo, *sum_hi - *sum_lo]);
+        }
+        let g_j = Polynomial { coeffs };
+
+        transcript.append(&poly_to_bytes(&g_j));
+        let r_j = transcript.challenge();
+        challenges.push(r_j);
+        if j + 1 < var_num {
+            halves = tables
+                .iter_mut()
+                .map(|t| bind_and_sum_halves(t, r_j, binds_msb))
+                .collect();
+        } else {
+            // the last round, with nothing to sum after it.
+            for t in tables.iter_mut() {
+                if binds_msb {
+                    t.bind_first(r_j);
+                } else {
+                    t.bind_last(r_j);
+                }
+            }
+        }
+        proofs.push_round(g_j);
+    }
+
+    let evals = tables.iter().map(|t| t.evals[0]).collect::<Vec<_>>();
+    proofs.final_eval = rlc::combine_scalars(&evals, weights).expect("a weight per table");
+    (proofs, params.point(&challenges))
+}
+
+impl BatchedSumCheck {
+    // Return the claimed sums of each instance, and the proof of their γ-combination.
+    pub fn prove(
+        instances: &[EvalTable],
+        transcript: &mut Keccak256Transcript,
+    ) -> Result<(Vec<Scalar>, SumCheckProof), BatchError> {
+        let first = instances.first().ok_or(BatchError::EmptyBatch)?;
+        if let Some(index) = instances.iter().position(|t| t.var_num != first.var_num) {
+            return Err(BatchError::VarNumMismatch { index });
+        }
+
+        let claims = instances.iter().map(|t| t.sum()).collect::<Vec<_>>();
+        let weights = batch_weights(&claims, transcript);
+        let params = SumCheckParams::default();
+        Ok((
+            claims,
+            prove_combined(instances, &weights, params, transcript).0,
+        ))
+    }
+}
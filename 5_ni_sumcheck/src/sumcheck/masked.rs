@@ -0,0 +1,261 @@
+// A sum-check whose round polys hide the ones of g, by masking g with a random table ρ.
+//
+// P commits to a random ρ with `mlpc` and sends S_ρ = ∑_x ρ(x) with the root. After absorbing
+// [H, root, S_ρ], λ is squeezed, and the sum-check is run on
+//      h = g + λ·ρ, with the claim H + λ·S_ρ
+// so each round poly g_j of h is the one of g shifted by λ times a random one of ρ. The final
+// claim h̃(r) = g̃(r) + λ·ρ̃(r) is split by V: g̃(r) is left to the oracle of g, and ρ̃(r) is
+// opened against the root.
+//
+// A wrong H makes the claim of h wrong, so the masked and the plain sum-check accept the same
+// claims, except with probability v/|F| over the challenges.
+//
+// It's a teaching version and it's NOT formally zero-knowledge: the opening of ρ̃(r) reveals
+// columns of the encoded ρ, and the final claim reveals g̃(r) once ρ̃(r) is known. The ZK
+// sum-check of the literature needs a masking poly of low degree and a hiding commitment, see
+// PAZK section 13.
+use crate::mlpc::{self, MlOpening, MlRoot, MlpcError};
+use crate::sumcheck::aggregate::PolyOracle;
+use crate::sumcheck::verifier::Verifier;
+use crate::sumcheck::{SumCheckProof, VerifyError};
+use crate::transcript::default::Keccak256Transcript;
+use crate::transcript::Transcript;
+use bls12_381::Scalar;
+use core::fmt;
+use Fiat_Shamir::codec::{encode_digest, encode_scalar, encode_u64};
+use Fiat_Shamir::protocol::ProtocolId;
+
+#[cfg(feature = "prover")]
+mod prover;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaskedSumCheckProof {
+    pub mask_root: MlRoot,
+    pub mask_sum: Scalar, // S_ρ
+    pub sumcheck: SumCheckProof,
+    pub mask_eval: Scalar, // ρ̃(r)
+    pub mask_opening: MlOpening,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MaskedError {
+    // the oracle, the mask or the proof have different var_num.
+    VarNumMismatch,
+    // the rounds num is not var_num.
+    MalformedProof,
+    Verify(VerifyError),
+    // ρ̃(r) is not the committed one.
+    MaskOpening(MlpcError),
+    // g̃(r) + λ·ρ̃(r) is not the final claim.
+    FinalCheckFailed,
+}
+
+impl fmt::Display for MaskedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MaskedError::VarNumMismatch => write!(f, "The var_num of the mask or proof differs"),
+            MaskedError::MalformedProof => write!(f, "The rounds num is not var_num"),
+            MaskedError::Verify(_) => write!(f, "Verifier rejected the masked sum-check"),
+            MaskedError::MaskOpening(_) => write!(f, "Verifier rejected the opening of the mask"),
+            MaskedError::FinalCheckFailed => write!(f, "Verifier rejected the final claim"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MaskedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MaskedError::Verify(e) => Some(e),
+            MaskedError::MaskOpening(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<VerifyError> for MaskedError {
+    fn from(e: VerifyError) -> Self {
+        MaskedError::Verify(e)
+    }
+}
+
+impl From<MlpcError> for MaskedError {
+    fn from(e: MlpcError) -> Self {
+        MaskedError::MaskOpening(e)
+    }
+}
+
+// The protocol of the masked sum-check of g with var_num variables.
+pub fn protocol_id(var_num: usize) -> ProtocolId {
+    ProtocolId::new("ni_sumcheck", "masked", 1, &encode_u64(var_num as u64))
+}
+
+// λ is squeezed after the claim and the mask are bound.
+fn mask_challenge(
+    claimed_sum: &Scalar,
+    mask_root: &MlRoot,
+    mask_sum: &Scalar,
+    transcript: &mut Keccak256Transcript,
+) -> Scalar {
+    transcript.bind_protocol(&protocol_id(mask_root.var_num));
+    transcript.append(&encode_scalar(claimed_sum));
+    transcript.append(&encode_digest(&mask_root.digest));
+    transcript.append(&encode_scalar(mask_sum));
+    transcript.challenge()
+}
+
+pub struct MaskedSumCheck;
+
+impl MaskedSumCheck {
+    pub fn verify<O: PolyOracle>(
+        claimed_sum: Scalar,
+        oracle: &O,
+        proof: &MaskedSumCheckProof,
+        transcript: &mut Keccak256Transcript,
+    ) -> Result<(), MaskedError> {
+        let var_num = oracle.var_num();
+        if proof.mask_root.var_num != var_num || proof.sumcheck.var_num as usize != var_num {
+            return Err(MaskedError::VarNumMismatch);
+        }
+        if proof.sumcheck.num_rounds() != var_num {
+            return Err(MaskedError::MalformedProof);
+        }
+
+        // 1. V recomputes the claim of h by itself.
+        let lambda = mask_challenge(&claimed_sum, &proof.mask_root, &proof.mask_sum, transcript);
+        let mut verifier = Verifier::new(var_num, claimed_sum + lambda * proof.mask_sum);
+        verifier
+            .verify_with_transcript(proof.sumcheck.clone(), transcript, &mut ())
+            .map_err(MaskedError::Verify)?;
+
+        // 2. ρ̃(r) is the committed one
+        let point = verifier.point();
+        mlpc::verify(
+            &proof.mask_root,
+            &point,
+            proof.mask_eval,
+            &proof.mask_opening,
+        )
+        .map_err(MaskedError::MaskOpening)?;
+
+        // 3. h̃(r) = g̃(r) + λ·ρ̃(r)
+        if oracle.evaluate(&point) + lambda * proof.mask_eval != proof.sumcheck.final_eval {
+            return Err(MaskedError::FinalCheckFailed);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod test {
+    use crate::sumcheck::aggregate::{prove_aggregate, verify_aggregate};
+    use crate::sumcheck::masked::{MaskedError, MaskedSumCheck};
+    use crate::sumcheck::{SumCheckParams, VerifyError};
+    use crate::transcript::default::Keccak256Transcript;
+    use bls12_381::Scalar;
+    use ff::Field;
+    use rand_core::{OsRng, RngCore};
+    use sumcheck::poly::eval_table::random_table;
+
+    #[test]
+    fn test_masked_sumcheck() {
+        for v in 1..=8 {
+            let g = random_table(v);
+            let proof = MaskedSumCheck::prove(&g, &mut Keccak256Transcript::default(), OsRng);
+            assert_eq!(
+                MaskedSumCheck::verify(g.sum(), &g, &proof, &mut Keccak256Transcript::default()),
+                Ok(())
+            );
+        }
+    }
+
+    #[test]
+    fn test_masks_differ() {
+        let g = random_table(6);
+        let p_1 = MaskedSumCheck::prove(&g, &mut Keccak256Transcript::default(), OsRng);
+        let p_2 = MaskedSumCheck::prove(&g, &mut Keccak256Transcript::default(), OsRng);
+        assert_ne!(p_1.mask_root, p_2.mask_root);
+        assert_ne!(p_1.sumcheck.rounds[0], p_2.sumcheck.rounds[0]);
+        for p in [p_1, p_2] {
+            assert_eq!(
+                MaskedSumCheck::verify(g.sum(), &g, &p, &mut Keccak256Transcript::default()),
+                Ok(())
+            );
+        }
+    }
+
+    #[test]
+    fn test_corrupt_mask() {
+        let v = 6;
+        let g = random_table(v);
+        let proof = MaskedSumCheck::prove(&g, &mut Keccak256Transcript::default(), OsRng);
+
+        // a wrong S_ρ changes the claim of h.
+        let mut bad = proof.clone();
+        bad.mask_sum += Scalar::one();
+        assert_eq!(
+            MaskedSumCheck::verify(g.sum(), &g, &bad, &mut Keccak256Transcript::default()),
+            Err(MaskedError::Verify(VerifyError::WrongClaimedSum))
+        );
+
+        // a wrong ρ̃(r) is rejected by the opening.
+        let mut bad = proof.clone();
+        bad.mask_eval += Scalar::one();
+        assert!(matches!(
+            MaskedSumCheck::verify(g.sum(), &g, &bad, &mut Keccak256Transcript::default()),
+            Err(MaskedError::MaskOpening(_))
+        ));
+
+        // a wrong column of ρ is rejected by the Merkle path.
+        let mut bad = proof.clone();
+        bad.mask_opening.columns[0][0] += Scalar::one();
+        assert!(matches!(
+            MaskedSumCheck::verify(g.sum(), &g, &bad, &mut Keccak256Transcript::default()),
+            Err(MaskedError::MaskOpening(_))
+        ));
+
+        // the proof of g is checked against another oracle.
+        assert_eq!(
+            MaskedSumCheck::verify(
+                g.sum(),
+                &random_table(v),
+                &proof,
+                &mut Keccak256Transcript::default()
+            ),
+            Err(MaskedError::FinalCheckFailed)
+        );
+        assert_eq!(
+            MaskedSumCheck::verify(
+                g.sum(),
+                &random_table(v + 1),
+                &proof,
+                &mut Keccak256Transcript::default()
+            ),
+            Err(MaskedError::VarNumMismatch)
+        );
+    }
+
+    #[test]
+    fn test_agrees_with_unmasked() {
+        for _ in 0..20 {
+            let v = 1 + OsRng.next_u32() as usize % 6;
+            let g = random_table(v);
+            let masked = MaskedSumCheck::prove(&g, &mut Keccak256Transcript::default(), OsRng);
+            let plain = prove_aggregate(core::slice::from_ref(&g), SumCheckParams::default());
+
+            // the honest sum, and a wrong one.
+            for claimed in [g.sum(), g.sum() + Scalar::random(OsRng)] {
+                let masked_ok = MaskedSumCheck::verify(
+                    claimed,
+                    &g,
+                    &masked,
+                    &mut Keccak256Transcript::default(),
+                )
+                .is_ok();
+                let plain_ok = verify_aggregate(&[(&g, claimed)], &plain).is_ok();
+                assert_eq!(masked_ok, plain_ok);
+                assert_eq!(masked_ok, claimed == g.sum());
+            }
+        }
+    }
+}
@@ -0,0 +1,48 @@
+// P of the masked sum-check, see `masked`.
+use crate::mlpc;
+use crate::sumcheck::batched::prove_table_with_point;
+use crate::sumcheck::masked::{mask_challenge, MaskedSumCheck, MaskedSumCheckProof};
+use crate::sumcheck::params::SumCheckParams;
+use crate::transcript::default::Keccak256Transcript;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use ff::Field;
+use rand_core::RngCore;
+use sumcheck::poly::eval_table::EvalTable;
+use sumcheck::secret::{Secret, ZeroizingEvalTable};
+
+impl MaskedSumCheck {
+    // The sum-check of g + λ·ρ for a fresh ρ from the rng, whose claimed sum is H + λ·S_ρ.
+    // ρ is a secret as g is: both are wiped, only the root, S_ρ and ρ̃(r) are sent.
+    pub fn prove<R: RngCore>(
+        g_table: &EvalTable,
+        transcript: &mut Keccak256Transcript,
+        mut rng: R,
+    ) -> MaskedSumCheckProof {
+        // 1. commit to ρ, and bind [H, root, S_ρ]
+        let mask: Secret<Vec<Scalar>> = Secret::new(
+            (0..1 << g_table.var_num)
+                .map(|_| Scalar::random(&mut rng))
+                .collect(),
+        );
+        let (mask_root, mask_state) = mlpc::commit(&mask);
+        let mask_sum = mask.iter().sum();
+        let lambda = mask_challenge(&g_table.sum(), &mask_root, &mask_sum, transcript);
+
+        // 2. the sum-check of h = g + λ·ρ, h is wiped as g is
+        let mut h = ZeroizingEvalTable::copy_of(g_table);
+        for (h_x, rho_x) in h.evals.iter_mut().zip(mask.iter()) {
+            *h_x += lambda * rho_x;
+        }
+        let (sumcheck, point) = prove_table_with_point(&h, SumCheckParams::default(), transcript);
+
+        // 3. open ρ̃ at the point of the final claim
+        MaskedSumCheckProof {
+            mask_root,
+            mask_sum,
+            mask_eval: mask_state.evaluate(&point),
+            mask_opening: mlpc::open(&mask_state, &point),
+            sumcheck,
+        }
+    }
+}
@@ -0,0 +1,407 @@
+// The variable conventions of the sum-check, which differ between implementations.
+//
+// var_order: which end of the index is x_1, for the table and the coeffs of MPolynomial alike.
+//      MsbFirst is `convert_to_binary`, eg: v = 2, evals = [W(0,0), W(0,1), W(1,0), W(1,1)],
+//      LsbFirst reads the same evals as [W(0,0), W(1,0), W(0,1), W(1,1)].
+// binding: the order of the rounds. Prefix binds x_1 in round 1, ..., x_v in round v, and
+//      Suffix binds x_v in round 1, ..., x_1 in round v.
+// compressed: the rounds are sent without g_j(0), see `CompressedRound`, which saves a scalar
+//      per round. It's the format of the proof only, the challenges are the same.
+// degree_schedule: the max degree of g_j in each round, see `DegreeSchedule`. P sends each g_j
+//      of that degree at most, and V rejects a round declared above it.
+//
+// The default is (MsbFirst, Prefix), which binds the MSB of the index first as `bind_first`.
+// P and V must agree on both, so the proof carries them and V rejects a mismatch up front,
+// instead of checking the final eval at a point in the wrong order.
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VarOrder {
+    #[default]
+    MsbFirst,
+    LsbFirst,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Binding {
+    #[default]
+    Prefix,
+    Suffix,
+}
+
+// The max degree of g_j in round j. The degree of g_j is the one of g in x_j, so it may differ
+// from round to round, eg: g = f(x_1) * h(x_1, x_2) is of degree 2 in x_1 and 1 in x_2, and
+// PerRound([2, 1]) saves a coeff in round 2 against Uniform(2).
+// The schedule isn't in the proof, V takes its own and checks the declared degrees against it.
+// The default bounds nothing but the `degree_bound` of the proof, whatever the prover is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DegreeSchedule {
+    Uniform(usize),
+    PerRound(Vec<usize>), // d_1, ..., d_v, one per variable
+}
+
+// The max degree_bound of the format, see `SumCheckProof::new`.
+pub const MAX_DEGREE: usize = u8::MAX as usize - 1;
+
+impl Default for DegreeSchedule {
+    fn default() -> Self {
+        DegreeSchedule::Uniform(MAX_DEGREE)
+    }
+}
+
+impl DegreeSchedule {
+    // The bound of round j, j in [1, v].
+    pub fn degree(&self, j: usize) -> usize {
+        assert!(j >= 1, "the rounds start from 1");
+        match self {
+            DegreeSchedule::Uniform(d) => *d,
+            DegreeSchedule::PerRound(degrees) => degrees[j - 1],
+        }
+    }
+
+    // The rounds of a PerRound schedule, None for a Uniform one, which fits any var_num.
+    pub fn rounds(&self) -> Option<usize> {
+        match self {
+            DegreeSchedule::Uniform(_) => None,
+            DegreeSchedule::PerRound(degrees) => Some(degrees.len()),
+        }
+    }
+
+    // Whether the schedule is the one of v variables.
+    pub fn fits(&self, var_num: usize) -> bool {
+        self.rounds().is_none_or(|rounds| rounds == var_num)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SumCheckParams {
+    pub var_order: VarOrder,
+    pub binding: Binding,
+    pub compressed: bool,
+    pub degree_schedule: DegreeSchedule,
+}
+
+impl SumCheckParams {
+    pub fn new(var_order: VarOrder, binding: Binding) -> Self {
+        Self {
+            var_order,
+            binding,
+            compressed: false,
+            degree_schedule: DegreeSchedule::default(),
+        }
+    }
+
+    // The same conventions, with the rounds sent as `CompressedRound`.
+    pub fn with_compressed_rounds(mut self) -> Self {
+        self.compressed = true;
+        self
+    }
+
+    // The same conventions, with g_j of degree d_j at most in round j.
+    pub fn with_degree_schedule(mut self, schedule: DegreeSchedule) -> Self {
+        self.degree_schedule = schedule;
+        self
+    }
+
+    // Whether the proofs of both are in the same format, which is all the proof tells of the
+    // params, see `ConventionMismatch`. The schedule is the one of V.
+    pub(crate) fn same_convention(&self, other: &Self) -> bool {
+        (self.var_order, self.binding, self.compressed)
+            == (other.var_order, other.binding, other.compressed)
+    }
+
+    // Whether the variables are read and bound as the default, whatever the rounds format.
+    pub(crate) fn is_default_convention(&self) -> bool {
+        (self.var_order, self.binding) == (VarOrder::default(), Binding::default())
+    }
+
+    // Whether each round binds the MSB of the index, see `EvalTable::bind_first`,
+    // otherwise the LSB, see `EvalTable::bind_last`.
+    pub(crate) fn binds_msb(&self) -> bool {
+        matches!(
+            (self.var_order, self.binding),
+            (VarOrder::MsbFirst, Binding::Prefix) | (VarOrder::LsbFirst, Binding::Suffix)
+        )
+    }
+
+    // The challenges (r_1, ..., r_v) of the rounds as the point (x_1, ..., x_v).
+    pub fn point(&self, challenges: &[Scalar]) -> Vec<Scalar> {
+        match self.binding {
+            Binding::Prefix => challenges.to_vec(),
+            Binding::Suffix => challenges.iter().rev().cloned().collect(),
+        }
+    }
+
+    // bit 0: var_order, bit 1: binding. The compressed flag is in the version of the proof, and
+    // the schedule is left out, it's the default one of a decoded proof.
+    pub(crate) fn to_byte(&self) -> u8 {
+        (self.var_order == VarOrder::LsbFirst) as u8
+            | ((self.binding == Binding::Suffix) as u8) << 1
+    }
+
+    pub(crate) fn from_byte(byte: u8) -> Option<Self> {
+        if byte > 0b11 {
+            return None;
+        }
+        let var_order = [VarOrder::MsbFirst, VarOrder::LsbFirst][(byte & 1) as usize];
+        let binding = [Binding::Prefix, Binding::Suffix][(byte >> 1) as usize];
+        Some(Self::new(var_order, binding))
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod test {
+    use crate::poly::multivar_poly::MPolynomial;
+    use crate::sumcheck::batched::prove_table_with_params;
+    use crate::sumcheck::params::{Binding, DegreeSchedule, SumCheckParams, VarOrder};
+    use crate::sumcheck::{verify_with_params, SumCheck, VerifyError};
+    use crate::transcript::default::Keccak256Transcript;
+    use crate::transcript::Transcript;
+    use crate::utils::binary_point;
+    use bls12_381::Scalar;
+    use ff::Field;
+    use rand_core::OsRng;
+    use sumcheck::poly::eval_table::EvalTable;
+    use sumcheck::scalars;
+    use Fiat_Shamir::protocol::ProtocolId;
+
+    fn all_params() -> Vec<SumCheckParams> {
+        let mut all = vec![];
+        for var_order in [VarOrder::MsbFirst, VarOrder::LsbFirst] {
+            for binding in [Binding::Prefix, Binding::Suffix] {
+                all.push(SumCheckParams::new(var_order, binding));
+            }
+        }
+        all
+    }
+
+    // W̃(point) with the table read in the var_order.
+    fn evaluate(table: &EvalTable, var_order: VarOrder, point: &[Scalar]) -> Scalar {
+        match var_order {
+            VarOrder::MsbFirst => table.evaluate(point),
+            VarOrder::LsbFirst => {
+                let reversed = point.iter().rev().cloned().collect::<Vec<_>>();
+                table.evaluate(&reversed)
+            }
+        }
+    }
+
+    #[test]
+    fn test_params_byte() {
+        for params in all_params() {
+            assert_eq!(SumCheckParams::from_byte(params.to_byte()), Some(params));
+        }
+        assert_eq!(SumCheckParams::default().to_byte(), 0);
+        assert_eq!(SumCheckParams::from_byte(4), None);
+    }
+
+    #[test]
+    fn test_convention_mismatch() {
+        let table = EvalTable::new((0..1 << 5).map(|_| Scalar::random(OsRng)).collect());
+        for params in all_params() {
+            let proof = prove_table_with_params(
+                &table,
+                params.clone(),
+                &mut Keccak256Transcript::default(),
+            );
+            for expected in all_params() {
+                let res = verify_with_params(&proof, expected.clone());
+                if expected == params {
+                    // the final point is in the order of the caller, whatever is bound first.
+                    let point = res.unwrap().point;
+                    assert_eq!(evaluate(&table, params.var_order, &point), proof.final_eval);
+                } else {
+                    assert_eq!(
+                        res,
+                        Err(VerifyError::ConventionMismatch {
+                            expected,
+                            found: params.clone()
+                        })
+                    );
+                }
+            }
+        }
+    }
+
+    // Keccak256Transcript without the protocol, so any convention draws the same challenges from
+    // the same rounds.
+    #[derive(Default)]
+    struct UnboundTranscript(Keccak256Transcript);
+
+    impl Transcript for UnboundTranscript {
+        fn append(&mut self, new_data: &[u8]) {
+            self.0.append(new_data);
+        }
+
+        fn challenge(&mut self) -> Scalar {
+            self.0.challenge()
+        }
+
+        fn bind_protocol(&mut self, _id: &ProtocolId) {}
+    }
+
+    #[test]
+    fn test_symmetric_poly() {
+        // g depends on the number of ones of the index only, so the rounds are the same in any
+        // convention, given the same challenges.
+        let weights = scalars![3, 1, 4, 1, 5];
+        let mpoly = MPolynomial {
+            var_num: 4,
+            coeffs: (0..16usize)
+                .map(|i| weights[i.count_ones() as usize])
+                .collect(),
+        };
+        let prove = |params: SumCheckParams, unbound: bool| {
+            let mut sumcheck = SumCheck::with_params(mpoly.clone(), params);
+            if unbound {
+                sumcheck.prove_with_transcript(&mut UnboundTranscript::default())
+            } else {
+                sumcheck.prove_with_transcript(&mut Keccak256Transcript::default())
+            }
+        };
+        let first = prove(SumCheckParams::default(), true);
+        for params in all_params() {
+            let proof = prove(params.clone(), true);
+            assert_eq!(proof.rounds, first.rounds);
+            assert_eq!(proof.final_eval, first.final_eval);
+
+            // the protocol binds the convention, so the challenges differ after round 1.
+            if params != SumCheckParams::default() {
+                let bound = prove(params, false);
+                assert_eq!(bound.rounds[0], first.rounds[0]);
+                assert_ne!(bound.rounds[1..], first.rounds[1..]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_mpoly_prover_params() {
+        // g(x1, x2, x3) = 9 + 2*x3 + 3*x2 + 2 * x1 * x2 + 4* x1 * x2 * x3, read in MsbFirst
+        let mpoly = MPolynomial {
+            var_num: 3,
+            coeffs: scalars![9, 2, 3, 0, 0, 0, 2, 4],
+        };
+        let table = EvalTable::new(
+            (0..8)
+                .map(|i| mpoly.evaluate(&binary_point(&3, i)))
+                .collect(),
+        );
+        // the coeffs and the evals have the same index, so the conventions apply to both.
+        for params in all_params() {
+            let proof =
+                SumCheck::with_params(mpoly.clone(), params.clone()).prove_non_interactive();
+            let expected =
+                prove_table_with_params(&table, params, &mut Keccak256Transcript::default());
+            assert_eq!(proof, expected.to_bytes());
+        }
+    }
+
+    #[test]
+    fn test_compressed_rounds() {
+        let table = EvalTable::new((0..1 << 5).map(|_| Scalar::random(OsRng)).collect());
+        for params in all_params() {
+            let compressed = params.clone().with_compressed_rounds();
+            let proof = prove_table_with_params(
+                &table,
+                params.clone(),
+                &mut Keccak256Transcript::default(),
+            );
+            let mut proof_c = prove_table_with_params(
+                &table,
+                compressed.clone(),
+                &mut Keccak256Transcript::default(),
+            );
+
+            // the same challenges, so the same final point.
+            let point = verify_with_params(&proof, params.clone()).unwrap();
+            assert_eq!(verify_with_params(&proof_c, compressed.clone()), Ok(point));
+            assert_eq!(proof_c.final_eval, proof.final_eval);
+            // g_j(0) is omitted in each of the 5 degree-1 rounds.
+            assert_eq!(proof.to_bytes().len() - proof_c.to_bytes().len(), 5 * 32);
+
+            // either side rejects the other format.
+            assert_eq!(
+                verify_with_params(&proof_c, params.clone()),
+                Err(VerifyError::ConventionMismatch {
+                    expected: params.clone(),
+                    found: compressed.clone()
+                })
+            );
+            assert_eq!(
+                verify_with_params(&proof, compressed.clone()),
+                Err(VerifyError::ConventionMismatch {
+                    expected: compressed.clone(),
+                    found: params
+                })
+            );
+
+            // g_3 is rebuilt from the running claim, so the round check holds anyway, but the
+            // final check fails.
+            proof_c.compressed_rounds[2].evals[0] += Scalar::one();
+            assert_eq!(
+                verify_with_params(&proof_c, compressed),
+                Err(VerifyError::FinalCheckFailed)
+            );
+        }
+    }
+
+    #[test]
+    fn test_mpoly_prover_compressed() {
+        let mpoly = MPolynomial {
+            var_num: 3,
+            coeffs: scalars![9, 2, 3, 0, 0, 0, 2, 4],
+        };
+        let table = EvalTable::new(
+            (0..8)
+                .map(|i| mpoly.evaluate(&binary_point(&3, i)))
+                .collect(),
+        );
+        for params in all_params() {
+            let params = params.with_compressed_rounds();
+            let proof =
+                SumCheck::with_params(mpoly.clone(), params.clone()).prove_non_interactive();
+            let expected =
+                prove_table_with_params(&table, params, &mut Keccak256Transcript::default());
+            assert_eq!(proof, expected.to_bytes());
+        }
+    }
+
+    #[test]
+    fn test_degree_schedule() {
+        let table = EvalTable::new((0..1 << 5).map(|_| Scalar::random(OsRng)).collect());
+        for params in all_params() {
+            let prove = |params: SumCheckParams| {
+                prove_table_with_params(&table, params, &mut Keccak256Transcript::default())
+            };
+            let proof = prove(params.clone());
+            // the multilinear rounds fit Uniform(1), so the bytes are the ones without it.
+            let uniform = params
+                .clone()
+                .with_degree_schedule(DegreeSchedule::Uniform(1));
+            assert_eq!(prove(uniform.clone()).to_bytes(), proof.to_bytes());
+            let claim = verify_with_params(&proof, params.clone());
+            assert!(claim.is_ok());
+            assert_eq!(verify_with_params(&proof, uniform), claim);
+
+            // V takes its own schedule, whatever P had.
+            let tight = DegreeSchedule::PerRound(vec![1, 1, 0, 1, 1]);
+            assert_eq!(
+                verify_with_params(&proof, params.clone().with_degree_schedule(tight)),
+                Err(VerifyError::DegreeAboveSchedule {
+                    round: 3,
+                    declared: 1,
+                    bound: 0
+                })
+            );
+            let short = DegreeSchedule::PerRound(vec![1; 4]);
+            assert_eq!(
+                verify_with_params(&proof, params.with_degree_schedule(short)),
+                Err(VerifyError::ScheduleLengthMismatch {
+                    var_num: 5,
+                    rounds: 4
+                })
+            );
+        }
+    }
+}
@@ -0,0 +1,927 @@
+// The versioned wire format of the non-interactive sum-check proof, so that the proofs can be
+// verified by other implementations.
+//
+// Layout (version 1), all scalars are the canonical 32 bytes in little-endian:
+//
+//      | offset | size         | field                                        |
+//      |--------|--------------|----------------------------------------------|
+//      | 0      | 1            | version = 1                                  |
+//      | 1      | 1            | var_num = v                                  |
+//      | 2      | 1            | degree_bound = d                             |
+//      | 3      | 32           | claimed_sum H                                |
+//      | 35     | v * (1+32*k) | rounds g_1, ..., g_v, each one is:           |
+//      |        |              |   k: u8, the coeffs num, k <= d + 1          |
+//      |        |              |   k scalars, the coeffs [a_0, ..., a_{k-1}]  |
+//      | ...    | 32           | final_eval g(r_1, ..., r_v)                  |
+//
+// Version 2 is for the proofs in other conventions, see `params`. It has one more byte at
+// offset 3, bit 0 is the var_order and bit 1 is the binding, and the rest is shifted by one.
+// The default convention is always written as version 1, so its proofs are unchanged.
+//
+// Versions 3 and 4 are versions 1 and 2 with the compressed rounds, see `CompressedRound`:
+//      k: u8, the evals num, k <= d
+//      k scalars, the evals [g_j(1), ..., g_j(k)]
+//
+// Version 5 is the one P writes, with the metadata of the instance up front, so V rejects a proof
+// of another instance before any round, see `VerifyError::VarNumMismatch`:
+//
+//      | offset | size         | field                                        |
+//      |--------|--------------|----------------------------------------------|
+//      | 0      | 1            | version = 5                                  |
+//      | 1      | 1            | var_num = v                                  |
+//      | 2      | 1            | degree_bound = d                             |
+//      | 3      | 1            | flags: the params byte, bit 2 is compressed  |
+//      | 4      | 32           | claimed_sum H                                |
+//      | 36     | v            | the declared degrees d_1, ..., d_v, d_j <= d |
+//      | 36 + v | ...          | the rounds as above, k = d_j + 1 coeffs, or  |
+//      |        |              |   k = d_j evals if compressed                |
+//      | ...    | 32           | final_eval g(r_1, ..., r_v)                  |
+//
+// The versions 1 to 4 are still read, their degrees are the ones of the rounds as sent, and
+// written by `without_metadata`, eg: for the verifiers of before.
+//
+// No trailing bytes are allowed.
+//
+// The header (var_num, degree_bound, the convention) is the `ProtocolId` of the proof, which P and
+// V absorb first, see `protocol_id`. The compressed flag is the format only, so it's not in, as
+// the challenges are the same. `from_bytes_for` rejects a proof of other params right after the
+// header, before the rounds are read.
+//
+// The decoder takes untrusted bytes, so it never panics: every length is checked against the
+// bytes left before anything is allocated, and the scalars the header claims are capped by
+// `from_bytes_with_max`, see `fuzz` for the malformed inputs it's run on.
+use crate::poly::univar_poly::Polynomial;
+use crate::sumcheck::params::SumCheckParams;
+use alloc::vec;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use core::fmt;
+use sumcheck::poly::univar_poly::Polynomial as UniPolynomial;
+use Fiat_Shamir::protocol::ProtocolId;
+
+pub const PROOF_VERSION: u8 = 1;
+pub const PROOF_VERSION_WITH_PARAMS: u8 = 2;
+pub const PROOF_VERSION_COMPRESSED: u8 = 3;
+pub const PROOF_VERSION_COMPRESSED_WITH_PARAMS: u8 = 4;
+pub const PROOF_VERSION_WITH_METADATA: u8 = 5;
+
+// bit 2 of the flags of version 5.
+const COMPRESSED_FLAG: u8 = 0b100;
+
+const HEADER_SIZE: usize = 3;
+const SCALAR_SIZE: usize = 32;
+
+// Any proof in the format, as var_num and degree_bound are single bytes.
+pub const DEFAULT_MAX_SCALARS: usize = u8::MAX as usize * (u8::MAX as usize + 1) + 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SumCheckProof {
+    pub version: u8,
+    pub var_num: u8,
+    pub degree_bound: u8, // the max degree of each round polynomial g_i
+    pub claimed_sum: Scalar,
+    pub round_degrees: Vec<u8>, // the declared degree d_j of each g_j, d_j <= degree_bound
+    pub rounds: Vec<Polynomial>, // g_1, ..., g_v, empty if the rounds are compressed
+    pub compressed_rounds: Vec<CompressedRound>, // g_1, ..., g_v if params.compressed
+    pub final_eval: Scalar,      // g(r_1, ..., r_v)
+    pub params: SumCheckParams,
+}
+
+// A round poly g_j without g_j(0), which V derives from the running claim:
+//      g_j(0) = claim - g_j(1), where claim = g_{j-1}(r_{j-1}), or H in round 1.
+// The evals are g_j(1), ..., g_j(k) for the k + 1 coeffs of g_j, eg: a constant g_j sends
+// nothing, it's claim / 2.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompressedRound {
+    pub evals: Vec<Scalar>,
+}
+
+impl CompressedRound {
+    // The coeffs num of g_j is d_j + 1, and the evals num is d_j.
+    fn degree(&self) -> usize {
+        self.evals.len()
+    }
+
+    pub fn compress(g: &Polynomial) -> Self {
+        let k = g.coeffs.len().saturating_sub(1);
+        let evals = (1..=k as u64)
+            .map(|x| g.evaluate(Scalar::from(x)))
+            .collect();
+        Self { evals }
+    }
+
+    // The g_j with k + 1 coeffs through (0, claim - g_j(1)), (1, g_j(1)), ..., (k, g_j(k)).
+    // It's the compressed one iff that one passes the round check g_j(0) + g_j(1) = claim.
+    pub fn decompress(&self, claim: Scalar) -> Polynomial {
+        let g_1 = match self.evals.first() {
+            Some(g_1) => *g_1,
+            None => {
+                let two_inv = Scalar::from(2).invert().unwrap();
+                return Polynomial {
+                    coeffs: vec![claim * two_inv],
+                };
+            }
+        };
+        let mut evals = vec![claim - g_1];
+        evals.extend(self.evals.iter());
+        let domains = (0..evals.len() as u64).map(Scalar::from).collect();
+        let coeffs = UniPolynomial::lagrange_interpolate(domains, evals).coeffs();
+        Polynomial { coeffs }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    UnknownVersion(u8),
+    UnknownParams(u8),
+    UnexpectedEnd,
+    EmptyRound { round: usize },
+    RoundTooLong { round: usize, len: usize, degree_bound: u8 },
+    // The declared degree of the round is above the degree bound.
+    DegreeTooHigh { round: usize, degree: u8, degree_bound: u8 },
+    // The round has other than the coeffs, or evals, its declared degree allows.
+    DegreeMismatch { round: usize, degree: u8, len: usize },
+    NonCanonicalScalar { offset: usize },
+    TrailingBytes(usize),
+    // The header allows more scalars than the max.
+    TooLarge { scalars: usize, max: usize },
+    // The header is of other params than the expected ones, see `from_bytes_for`.
+    ProtocolMismatch,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::UnknownVersion(v) => write!(f, "Unknown version {}", v),
+            DecodeError::UnknownParams(p) => write!(f, "Unknown params {}", p),
+            DecodeError::UnexpectedEnd => write!(f, "Unexpected end of the bytes"),
+            DecodeError::EmptyRound { round } => write!(f, "Round {} is empty", round),
+            DecodeError::RoundTooLong {
+                round,
+                len,
+                degree_bound,
+            } => write!(
+                f,
+                "Round {} has {} coeffs, more than the degree bound {} allows",
+                round, len, degree_bound
+            ),
+            DecodeError::DegreeTooHigh {
+                round,
+                degree,
+                degree_bound,
+            } => write!(
+                f,
+                "Round {} declares the degree {}, more than the degree bound {}",
+                round, degree, degree_bound
+            ),
+            DecodeError::DegreeMismatch { round, degree, len } => write!(
+                f,
+                "Round {} declares the degree {}, but has {} scalars",
+                round, degree, len
+            ),
+            DecodeError::NonCanonicalScalar { offset } => {
+                write!(f, "Non-canonical scalar at {}", offset)
+            }
+            DecodeError::TrailingBytes(n) => write!(f, "{} trailing bytes", n),
+            DecodeError::TooLarge { scalars, max } => {
+                write!(f, "{} scalars, more than the max {}", scalars, max)
+            }
+            DecodeError::ProtocolMismatch => write!(f, "The proof is of other params"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+// The protocol of a proof with the header, see the layout.
+pub fn protocol_id(var_num: u8, degree_bound: u8, params: &SumCheckParams) -> ProtocolId {
+    let params = [var_num, degree_bound, params.to_byte()];
+    ProtocolId::new("ni_sumcheck", "sumcheck", PROOF_VERSION, &params)
+}
+
+impl SumCheckProof {
+    pub fn new(var_num: usize, degree_bound: usize, claimed_sum: Scalar) -> Self {
+        assert!(var_num <= u8::MAX as usize, "var_num is too large");
+        assert!(degree_bound < u8::MAX as usize, "degree_bound is too large");
+        Self {
+            version: PROOF_VERSION_WITH_METADATA,
+            var_num: var_num as u8,
+            degree_bound: degree_bound as u8,
+            claimed_sum,
+            round_degrees: vec![],
+            rounds: vec![],
+            compressed_rounds: vec![],
+            final_eval: Scalar::zero(),
+            params: SumCheckParams::default(),
+        }
+    }
+
+    // The proof in the convention, the version follows it. The schedule must be of var_num.
+    pub fn with_params(mut self, params: SumCheckParams) -> Self {
+        assert!(
+            params.degree_schedule.fits(self.var_num as usize),
+            "the degree schedule is of {:?} rounds, but the instance has {} variables",
+            params.degree_schedule.rounds(),
+            self.var_num
+        );
+        if !has_metadata(self.version) {
+            self.version = legacy_version(&params);
+        }
+        self.params = params;
+        self
+    }
+
+    // The proof in the version of its params without the metadata, 1 to 4, eg: for a verifier
+    // which doesn't read version 5. The degrees are still the ones of the rounds.
+    pub fn without_metadata(mut self) -> Self {
+        self.version = legacy_version(&self.params);
+        self
+    }
+
+    // Append g_j in the format of the params, and declare its degree. g_j is cut to the degree
+    // of the round in the schedule, eg: a g_j interpolated from 3 points in a degree-1 round, and
+    // it must be zero above it.
+    pub(crate) fn push_round(&mut self, mut g_j: Polynomial) {
+        assert!(!g_j.coeffs.is_empty(), "g_j has a coeff at least");
+        let j = self.round_degrees.len() + 1;
+        let bound = self.params.degree_schedule.degree(j);
+        if g_j.coeffs.len() > bound + 1 {
+            assert!(
+                g_j.coeffs[bound + 1..].iter().all(|c| *c == Scalar::zero()),
+                "g_{} is of degree above {}, the one of the schedule",
+                j,
+                bound
+            );
+            g_j.coeffs.truncate(bound + 1);
+        }
+        self.round_degrees.push((g_j.coeffs.len() - 1) as u8);
+        if self.params.compressed {
+            self.compressed_rounds.push(CompressedRound::compress(&g_j));
+        } else {
+            self.rounds.push(g_j);
+        }
+    }
+
+    pub fn protocol_id(&self) -> ProtocolId {
+        protocol_id(self.var_num, self.degree_bound, &self.params)
+    }
+
+    pub fn num_rounds(&self) -> usize {
+        if self.params.compressed {
+            self.compressed_rounds.len()
+        } else {
+            self.rounds.len()
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let rounds_size = self
+            .rounds
+            .iter()
+            .map(|g| 1 + g.coeffs.len() * SCALAR_SIZE)
+            .chain(
+                self.compressed_rounds
+                    .iter()
+                    .map(|g| 1 + g.evals.len() * SCALAR_SIZE),
+            )
+            .sum::<usize>();
+        let mut bytes = Vec::with_capacity(
+            HEADER_SIZE + 1 + 2 * SCALAR_SIZE + self.round_degrees.len() + rounds_size,
+        );
+
+        bytes.extend([self.version, self.var_num, self.degree_bound]);
+        if has_metadata(self.version) {
+            let compressed = if self.params.compressed {
+                COMPRESSED_FLAG
+            } else {
+                0
+            };
+            bytes.push(self.params.to_byte() | compressed);
+        } else if has_params_byte(self.version) {
+            bytes.push(self.params.to_byte());
+        } else {
+            assert!(
+                self.params.is_default_convention(),
+                "version {} has no params",
+                self.version
+            );
+        }
+        assert!(
+            has_metadata(self.version) || self.params.compressed == is_compressed(self.version),
+            "the version doesn't match the rounds format"
+        );
+        bytes.extend(self.claimed_sum.to_bytes());
+        if has_metadata(self.version) {
+            assert_eq!(
+                self.round_degrees,
+                self.sent_degrees().collect::<Vec<_>>(),
+                "the declared degrees don't match the rounds"
+            );
+            bytes.extend(self.round_degrees.iter());
+        }
+        for g in self.rounds.iter() {
+            assert!(g.coeffs.len() <= self.degree_bound as usize + 1);
+            bytes.push(g.coeffs.len() as u8);
+            for c in g.coeffs.iter() {
+                bytes.extend(c.to_bytes());
+            }
+        }
+        for g in self.compressed_rounds.iter() {
+            assert!(g.evals.len() <= self.degree_bound as usize);
+            bytes.push(g.evals.len() as u8);
+            for e in g.evals.iter() {
+                bytes.extend(e.to_bytes());
+            }
+        }
+        bytes.extend(self.final_eval.to_bytes());
+
+        bytes
+    }
+
+    // The degrees of the rounds as they are, eg: a constant g_j is of degree 0. It's empty for
+    // the empty rounds, which no decoded proof has.
+    pub(crate) fn sent_degrees(&self) -> impl Iterator<Item = u8> + '_ {
+        let coeffs = self.rounds.iter().map(|g| g.coeffs.len().saturating_sub(1));
+        let evals = self.compressed_rounds.iter().map(CompressedRound::degree);
+        coeffs.chain(evals).map(|d| d as u8)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        Self::from_bytes_with_max(bytes, DEFAULT_MAX_SCALARS)
+    }
+
+    // The same as `from_bytes`, but the proof has max_scalars at most, which counts the
+    // claimed_sum, the final_eval and var_num * (degree_bound + 1) coeffs.
+    pub fn from_bytes_with_max(bytes: &[u8], max_scalars: usize) -> Result<Self, DecodeError> {
+        Self::decode(bytes, max_scalars, None)
+    }
+
+    // The same as `from_bytes`, but the proof must be of the expected protocol, which is checked
+    // right after the header.
+    pub fn from_bytes_for(bytes: &[u8], expected: &ProtocolId) -> Result<Self, DecodeError> {
+        Self::decode(bytes, DEFAULT_MAX_SCALARS, Some(expected))
+    }
+
+    fn decode(
+        bytes: &[u8],
+        max_scalars: usize,
+        expected: Option<&ProtocolId>,
+    ) -> Result<Self, DecodeError> {
+        let mut reader = Reader { bytes, offset: 0 };
+
+        let version = reader.read_u8()?;
+        if !(PROOF_VERSION..=PROOF_VERSION_WITH_METADATA).contains(&version) {
+            return Err(DecodeError::UnknownVersion(version));
+        }
+        let var_num = reader.read_u8()?;
+        let degree_bound = reader.read_u8()?;
+        let params = if has_metadata(version) {
+            let flags = reader.read_u8()?;
+            let mut params = SumCheckParams::from_byte(flags & !COMPRESSED_FLAG)
+                .ok_or(DecodeError::UnknownParams(flags))?;
+            params.compressed = flags & COMPRESSED_FLAG != 0;
+            params
+        } else {
+            let mut params = if has_params_byte(version) {
+                let byte = reader.read_u8()?;
+                SumCheckParams::from_byte(byte).ok_or(DecodeError::UnknownParams(byte))?
+            } else {
+                SumCheckParams::default()
+            };
+            params.compressed = is_compressed(version);
+            params
+        };
+        if expected.is_some_and(|id| *id != protocol_id(var_num, degree_bound, &params)) {
+            return Err(DecodeError::ProtocolMismatch);
+        }
+        let scalars = var_num as usize * (degree_bound as usize + 1) + 2;
+        if scalars > max_scalars {
+            return Err(DecodeError::TooLarge {
+                scalars,
+                max: max_scalars,
+            });
+        }
+        let claimed_sum = reader.read_scalar()?;
+
+        // the rounds count is fixed by var_num, each one has 1 + 32 bytes at least, or 1 byte
+        // if it's compressed, and its degree byte in version 5.
+        let min_round_size = if params.compressed {
+            1
+        } else {
+            1 + SCALAR_SIZE
+        } + has_metadata(version) as usize;
+        if reader.remaining() < var_num as usize * min_round_size + SCALAR_SIZE {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+        let declared = if has_metadata(version) {
+            let degrees = reader.read(var_num as usize)?.to_vec();
+            if let Some((round, degree)) =
+                degrees.iter().enumerate().find(|(_, d)| **d > degree_bound)
+            {
+                return Err(DecodeError::DegreeTooHigh {
+                    round,
+                    degree: *degree,
+                    degree_bound,
+                });
+            }
+            Some(degrees)
+        } else {
+            None
+        };
+        let (mut rounds, mut compressed_rounds) = (vec![], vec![]);
+        for round in 0..var_num as usize {
+            let len = reader.read_u8()? as usize;
+            if len == 0 && !params.compressed {
+                return Err(DecodeError::EmptyRound { round });
+            }
+            // the evals num of a compressed round is the coeffs num - 1.
+            if len + params.compressed as usize > degree_bound as usize + 1 {
+                return Err(DecodeError::RoundTooLong {
+                    round,
+                    len,
+                    degree_bound,
+                });
+            }
+            if let Some(degree) = declared.as_ref().map(|degrees| degrees[round]) {
+                if len + params.compressed as usize != degree as usize + 1 {
+                    return Err(DecodeError::DegreeMismatch { round, degree, len });
+                }
+            }
+            if reader.remaining() < len * SCALAR_SIZE {
+                return Err(DecodeError::UnexpectedEnd);
+            }
+            let scalars = (0..len)
+                .map(|_| reader.read_scalar())
+                .collect::<Result<Vec<_>, _>>()?;
+            if params.compressed {
+                compressed_rounds.push(CompressedRound { evals: scalars });
+            } else {
+                rounds.push(Polynomial { coeffs: scalars });
+            }
+        }
+        let final_eval = reader.read_scalar()?;
+
+        let remaining = reader.remaining();
+        if remaining != 0 {
+            return Err(DecodeError::TrailingBytes(remaining));
+        }
+
+        let mut proof = Self {
+            version,
+            var_num,
+            degree_bound,
+            claimed_sum,
+            round_degrees: vec![],
+            rounds,
+            compressed_rounds,
+            final_eval,
+            params,
+        };
+        proof.round_degrees = declared.unwrap_or_else(|| proof.sent_degrees().collect());
+        Ok(proof)
+    }
+}
+
+// The versions 1 to 4 by the params.
+fn legacy_version(params: &SumCheckParams) -> u8 {
+    match (params.is_default_convention(), params.compressed) {
+        (true, false) => PROOF_VERSION,
+        (false, false) => PROOF_VERSION_WITH_PARAMS,
+        (true, true) => PROOF_VERSION_COMPRESSED,
+        (false, true) => PROOF_VERSION_COMPRESSED_WITH_PARAMS,
+    }
+}
+
+fn has_metadata(version: u8) -> bool {
+    version == PROOF_VERSION_WITH_METADATA
+}
+
+fn has_params_byte(version: u8) -> bool {
+    version == PROOF_VERSION_WITH_PARAMS || version == PROOF_VERSION_COMPRESSED_WITH_PARAMS
+}
+
+fn is_compressed(version: u8) -> bool {
+    version == PROOF_VERSION_COMPRESSED || version == PROOF_VERSION_COMPRESSED_WITH_PARAMS
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn remaining(&self) -> usize {
+        self.bytes.len().saturating_sub(self.offset)
+    }
+
+    fn read(&mut self, n: usize) -> Result<&'a [u8], DecodeError> {
+        let end = self
+            .offset
+            .checked_add(n)
+            .ok_or(DecodeError::UnexpectedEnd)?;
+        let res = self
+            .bytes
+            .get(self.offset..end)
+            .ok_or(DecodeError::UnexpectedEnd)?;
+        self.offset = end;
+        Ok(res)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, DecodeError> {
+        self.read(1)?
+            .first()
+            .copied()
+            .ok_or(DecodeError::UnexpectedEnd)
+    }
+
+    fn read_scalar(&mut self) -> Result<Scalar, DecodeError> {
+        let offset = self.offset;
+        let repr: [u8; SCALAR_SIZE] = self
+            .read(SCALAR_SIZE)?
+            .try_into()
+            .map_err(|_| DecodeError::UnexpectedEnd)?;
+        Option::from(Scalar::from_bytes(&repr)).ok_or(DecodeError::NonCanonicalScalar { offset })
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod fuzz;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sumcheck::params::DegreeSchedule;
+    use ff::PrimeField;
+
+    // g_1 of degree 1 and g_2 of degree 0, in version 5.
+    fn gen_proof() -> SumCheckProof {
+        let mut proof = SumCheckProof::new(2, 1, Scalar::from_u128(10));
+        proof.push_round(Polynomial {
+            coeffs: vec![Scalar::from_u128(3), Scalar::from_u128(4)],
+        });
+        proof.push_round(Polynomial {
+            coeffs: vec![Scalar::from_u128(5)],
+        });
+        proof.final_eval = Scalar::from_u128(5);
+        proof
+    }
+
+    // The same proof in version 1.
+    fn gen_legacy_proof() -> SumCheckProof {
+        gen_proof().without_metadata()
+    }
+
+    // g_2 is interpolated at 3 points, but it's of degree 1.
+    fn gen_scheduled_proof(params: SumCheckParams) -> SumCheckProof {
+        let mut proof = SumCheckProof::new(2, 2, Scalar::from_u128(10)).with_params(params);
+        proof.push_round(Polynomial {
+            coeffs: vec![
+                Scalar::from_u128(3),
+                Scalar::from_u128(2),
+                Scalar::from_u128(2),
+            ],
+        });
+        proof.push_round(Polynomial {
+            coeffs: vec![Scalar::from_u128(6), Scalar::from_u128(7), Scalar::zero()],
+        });
+        proof.final_eval = Scalar::from_u128(20);
+        proof
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let proof = gen_proof();
+        let bytes = proof.to_bytes();
+        // header + flags + claimed_sum + degrees + (1 + 2*32) + (1 + 32) + final_eval
+        assert_eq!(bytes.len(), 3 + 1 + 32 + 2 + 65 + 33 + 32);
+        assert_eq!(bytes[0], PROOF_VERSION_WITH_METADATA);
+        assert_eq!(bytes[36..38], [1, 0]);
+        assert_eq!(SumCheckProof::from_bytes(&bytes), Ok(proof));
+
+        // version 1 has neither the flags nor the degrees, which are read from the rounds.
+        let legacy = gen_legacy_proof();
+        let bytes = legacy.to_bytes();
+        assert_eq!(
+            (bytes[0], bytes.len()),
+            (PROOF_VERSION, 3 + 32 + 65 + 33 + 32)
+        );
+        let decoded = SumCheckProof::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.round_degrees, vec![1, 0]);
+        assert_eq!(decoded, legacy);
+    }
+
+    #[test]
+    fn test_reject_degree_mismatch() {
+        let bytes = gen_proof().to_bytes();
+        // g_1 declared of degree 0, but it has 2 coeffs.
+        let mut lower = bytes.clone();
+        lower[36] = 0;
+        assert_eq!(
+            SumCheckProof::from_bytes(&lower),
+            Err(DecodeError::DegreeMismatch {
+                round: 0,
+                degree: 0,
+                len: 2
+            })
+        );
+        // g_2 declared of degree 1, but it has 1 coeff.
+        let mut higher = bytes.clone();
+        higher[37] = 1;
+        assert_eq!(
+            SumCheckProof::from_bytes(&higher),
+            Err(DecodeError::DegreeMismatch {
+                round: 1,
+                degree: 1,
+                len: 1
+            })
+        );
+        let mut above = bytes;
+        above[37] = 2;
+        assert_eq!(
+            SumCheckProof::from_bytes(&above),
+            Err(DecodeError::DegreeTooHigh {
+                round: 1,
+                degree: 2,
+                degree_bound: 1
+            })
+        );
+    }
+
+    // The flags are the params byte and the compressed bit, the other bits are unknown.
+    #[test]
+    fn test_metadata_flags() {
+        use crate::sumcheck::params::{Binding, VarOrder};
+
+        let params = SumCheckParams::new(VarOrder::LsbFirst, Binding::Suffix);
+        let mut proof = SumCheckProof::new(2, 1, Scalar::from_u128(10))
+            .with_params(params.with_compressed_rounds());
+        for g in gen_proof().rounds {
+            proof.push_round(g);
+        }
+        let mut bytes = proof.to_bytes();
+        assert_eq!((bytes[0], bytes[3]), (PROOF_VERSION_WITH_METADATA, 0b111));
+        // the compressed g_1 sends 1 eval for its degree 1.
+        assert_eq!(bytes[36..39], [1, 0, 1]);
+        assert_eq!(SumCheckProof::from_bytes(&bytes), Ok(proof));
+
+        bytes[3] = 0b1011;
+        assert_eq!(
+            SumCheckProof::from_bytes(&bytes),
+            Err(DecodeError::UnknownParams(0b1011))
+        );
+    }
+
+    #[test]
+    fn test_from_bytes_for() {
+        use crate::sumcheck::params::{Binding, VarOrder};
+
+        let proof = gen_proof();
+        let bytes = proof.to_bytes();
+        assert_eq!(
+            SumCheckProof::from_bytes_for(&bytes, &proof.protocol_id()),
+            Ok(proof.clone())
+        );
+        // the metadata is the format only, the protocol is the same.
+        assert_eq!(gen_legacy_proof().protocol_id(), proof.protocol_id());
+        // another degree_bound, var_num or convention
+        let lsb_first = SumCheckParams::new(VarOrder::LsbFirst, Binding::Prefix);
+        for other in [
+            protocol_id(2, 2, &SumCheckParams::default()),
+            protocol_id(3, 1, &SumCheckParams::default()),
+            protocol_id(2, 1, &lsb_first),
+        ] {
+            assert_eq!(
+                SumCheckProof::from_bytes_for(&bytes, &other),
+                Err(DecodeError::ProtocolMismatch)
+            );
+        }
+        // the compressed rounds are the format only.
+        let compressed = SumCheckParams::default().with_compressed_rounds();
+        assert_eq!(protocol_id(2, 1, &compressed), proof.protocol_id());
+    }
+
+    #[test]
+    fn test_reject_unknown_version() {
+        let mut bytes = gen_proof().to_bytes();
+        bytes[0] = 6;
+        assert_eq!(
+            SumCheckProof::from_bytes(&bytes),
+            Err(DecodeError::UnknownVersion(6))
+        );
+    }
+
+    #[test]
+    fn test_params_round_trip() {
+        use crate::sumcheck::params::{Binding, VarOrder};
+
+        // the default convention is still version 1 without the metadata.
+        let proof = gen_legacy_proof().with_params(SumCheckParams::default());
+        assert_eq!(proof.to_bytes(), gen_legacy_proof().to_bytes());
+
+        let params = SumCheckParams::new(VarOrder::LsbFirst, Binding::Suffix);
+        let proof = gen_legacy_proof().with_params(params);
+        let bytes = proof.to_bytes();
+        assert_eq!((bytes[0], bytes[3]), (PROOF_VERSION_WITH_PARAMS, 0b11));
+        assert_eq!(bytes.len(), gen_legacy_proof().to_bytes().len() + 1);
+        assert_eq!(SumCheckProof::from_bytes(&bytes), Ok(proof));
+
+        let mut bytes = bytes;
+        bytes[3] = 4;
+        assert_eq!(
+            SumCheckProof::from_bytes(&bytes),
+            Err(DecodeError::UnknownParams(4))
+        );
+    }
+
+    #[test]
+    fn test_reject_rounds_mismatch() {
+        // claims 3 rounds but only 2 are present, the final_eval is read as the 3rd round.
+        for proof in [gen_proof(), gen_legacy_proof()] {
+            let mut bytes = proof.to_bytes();
+            bytes[1] = 3;
+            assert!(SumCheckProof::from_bytes(&bytes).is_err());
+
+            // claims 1 round, the 2nd round is left as the final_eval and trailing bytes.
+            let mut bytes = proof.to_bytes();
+            bytes[1] = 1;
+            assert!(SumCheckProof::from_bytes(&bytes).is_err());
+        }
+    }
+
+    #[test]
+    fn test_reject_round_too_long() {
+        let mut proof = gen_legacy_proof();
+        proof.degree_bound = 2;
+        proof.rounds[1].coeffs = vec![Scalar::one(); 3];
+        let mut bytes = proof.to_bytes();
+        // degree_bound = 1 allows 2 coeffs at most.
+        bytes[2] = 1;
+        assert_eq!(
+            SumCheckProof::from_bytes(&bytes),
+            Err(DecodeError::RoundTooLong {
+                round: 1,
+                len: 3,
+                degree_bound: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_reject_trailing_bytes() {
+        let mut bytes = gen_proof().to_bytes();
+        bytes.push(0);
+        assert_eq!(
+            SumCheckProof::from_bytes(&bytes),
+            Err(DecodeError::TrailingBytes(1))
+        );
+    }
+
+    #[test]
+    fn test_reject_truncated_and_non_canonical() {
+        let bytes = gen_legacy_proof().to_bytes();
+        assert_eq!(
+            SumCheckProof::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(DecodeError::UnexpectedEnd)
+        );
+
+        // claimed_sum = 2^256 - 1 > p
+        let mut bytes = bytes;
+        bytes[3..35].copy_from_slice(&[0xff; 32]);
+        assert_eq!(
+            SumCheckProof::from_bytes(&bytes),
+            Err(DecodeError::NonCanonicalScalar { offset: 3 })
+        );
+    }
+
+    #[test]
+    fn test_reject_too_large() {
+        let bytes = gen_proof().to_bytes();
+        // 2 rounds of 2 coeffs, claimed_sum and final_eval.
+        assert!(SumCheckProof::from_bytes_with_max(&bytes, 6).is_ok());
+        assert_eq!(
+            SumCheckProof::from_bytes_with_max(&bytes, 5),
+            Err(DecodeError::TooLarge { scalars: 6, max: 5 })
+        );
+
+        // 255 rounds are claimed in a short buffer, which is rejected before the allocation.
+        let mut bytes = bytes;
+        bytes[1] = u8::MAX;
+        assert_eq!(
+            SumCheckProof::from_bytes(&bytes),
+            Err(DecodeError::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn test_compress_round() {
+        // g(X) = 3 + 4X + 5X^2, claim = g(0) + g(1) = 15
+        let g = Polynomial {
+            coeffs: vec![Scalar::from(3), Scalar::from(4), Scalar::from(5)],
+        };
+        let compressed = CompressedRound::compress(&g);
+        assert_eq!(compressed.evals, vec![Scalar::from(12), Scalar::from(31)]);
+        assert_eq!(compressed.decompress(Scalar::from(15)), g);
+        // another claim gives another g_j, which is caught by the later rounds.
+        assert_ne!(compressed.decompress(Scalar::from(16)), g);
+
+        // a constant g_j sends nothing.
+        let g = Polynomial {
+            coeffs: vec![Scalar::from(7)],
+        };
+        let compressed = CompressedRound::compress(&g);
+        assert!(compressed.evals.is_empty());
+        assert_eq!(compressed.decompress(Scalar::from(14)), g);
+    }
+
+    #[test]
+    fn test_compressed_round_trip() {
+        use crate::sumcheck::params::{Binding, VarOrder};
+
+        for (params, version) in [
+            (SumCheckParams::default(), PROOF_VERSION_COMPRESSED),
+            (
+                SumCheckParams::new(VarOrder::LsbFirst, Binding::Prefix),
+                PROOF_VERSION_COMPRESSED_WITH_PARAMS,
+            ),
+        ] {
+            let uncompressed = gen_legacy_proof().with_params(params.clone());
+            let mut proof = gen_legacy_proof().with_params(params.with_compressed_rounds());
+            proof.rounds = vec![];
+            proof.round_degrees = vec![];
+            for g in uncompressed.rounds.iter() {
+                proof.push_round(g.clone());
+            }
+            let bytes = proof.to_bytes();
+            assert_eq!(bytes[0], version);
+            // g_1(0) is omitted, and g_2 is a constant, which sends nothing.
+            assert_eq!(bytes.len(), uncompressed.to_bytes().len() - 2 * 32);
+            assert_eq!(SumCheckProof::from_bytes(&bytes), Ok(proof));
+        }
+
+        // degree_bound = 1 allows 1 eval at most.
+        let mut proof =
+            gen_legacy_proof().with_params(SumCheckParams::default().with_compressed_rounds());
+        proof.rounds = vec![];
+        proof.round_degrees = vec![];
+        proof.push_round(Polynomial {
+            coeffs: vec![Scalar::one(); 3],
+        });
+        proof.push_round(Polynomial {
+            coeffs: vec![Scalar::one(); 2],
+        });
+        proof.degree_bound = 2;
+        let mut bytes = proof.to_bytes();
+        bytes[2] = 1;
+        assert_eq!(
+            SumCheckProof::from_bytes(&bytes),
+            Err(DecodeError::RoundTooLong {
+                round: 0,
+                len: 2,
+                degree_bound: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_per_round_schedule() {
+        for compressed in [false, true] {
+            let with_schedule = |schedule| {
+                let params = SumCheckParams::default().with_degree_schedule(schedule);
+                let params = if compressed {
+                    params.with_compressed_rounds()
+                } else {
+                    params
+                };
+                gen_scheduled_proof(params)
+            };
+            let uniform = with_schedule(DegreeSchedule::Uniform(2));
+            let tight = with_schedule(DegreeSchedule::PerRound(vec![2, 1]));
+            assert_eq!(uniform.round_degrees, vec![2, 2]);
+            assert_eq!(tight.round_degrees, vec![2, 1]);
+
+            // the X^2 coeff of g_2, or its eval at 2 if compressed.
+            let bytes = tight.to_bytes();
+            assert_eq!(uniform.to_bytes().len() - bytes.len(), 32);
+            let decoded = SumCheckProof::from_bytes(&bytes).unwrap();
+            assert_eq!(decoded.round_degrees, vec![2, 1]);
+            assert_eq!(decoded.sent_degrees().collect::<Vec<_>>(), vec![2, 1]);
+            // the schedule isn't in the bytes.
+            assert_eq!(decoded.params.degree_schedule, DegreeSchedule::default());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "g_2 is of degree above 0, the one of the schedule")]
+    fn test_round_above_schedule() {
+        let schedule = DegreeSchedule::PerRound(vec![2, 0]);
+        gen_scheduled_proof(SumCheckParams::default().with_degree_schedule(schedule));
+    }
+
+    #[test]
+    #[should_panic(expected = "the degree schedule is of Some(3) rounds, but the instance has 2")]
+    fn test_schedule_of_other_var_num() {
+        let schedule = DegreeSchedule::PerRound(vec![2, 1, 1]);
+        gen_scheduled_proof(SumCheckParams::default().with_degree_schedule(schedule));
+    }
+}
@@ -0,0 +1,178 @@
+// The decoder on malformed bytes: it must return Ok or a DecodeError, and never panic.
+//
+// The inputs are (a) random bytes, with a valid version byte or not, (b) the truncations of valid
+// encodings at every position, and (c) valid encodings with a single byte flipped. A decoded proof
+// must encode to the same bytes, so the hardening leaves the accepted format as it was.
+use crate::poly::multivar_poly::MPolynomial;
+use crate::sumcheck::params::{Binding, SumCheckParams, VarOrder};
+use crate::sumcheck::proof::{DecodeError, SumCheckProof};
+use crate::sumcheck::SumCheck;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use sumcheck::scalars;
+use Fiat_Shamir::protocol::ProtocolId;
+
+const RANDOM_CASES: u64 = 2048;
+
+// SplitMix64, the cases are the same in every run.
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    fn bytes(&mut self, len: usize) -> Vec<u8> {
+        (0..len).map(|_| self.next_u64() as u8).collect()
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Panics with the input if the decoder panics, or if a decoded proof doesn't encode back.
+fn decode(bytes: &[u8]) -> Result<SumCheckProof, DecodeError> {
+    let res = catch_unwind(AssertUnwindSafe(|| SumCheckProof::from_bytes(bytes)))
+        .unwrap_or_else(|_| panic!("from_bytes panicked on {}", to_hex(bytes)));
+    if let Ok(proof) = res.as_ref() {
+        let encoded = catch_unwind(AssertUnwindSafe(|| proof.to_bytes()))
+            .unwrap_or_else(|_| panic!("to_bytes panicked on {}", to_hex(bytes)));
+        assert_eq!(encoded, bytes, "not a canonical encoding");
+    }
+    res
+}
+
+// The same as `decode`, but for the expected protocol.
+fn decode_for(bytes: &[u8], expected: &ProtocolId) -> Result<SumCheckProof, DecodeError> {
+    catch_unwind(AssertUnwindSafe(|| {
+        SumCheckProof::from_bytes_for(bytes, expected)
+    }))
+    .unwrap_or_else(|_| panic!("from_bytes_for panicked on {}", to_hex(bytes)))
+}
+
+// The proofs of versions 1 to 4, then the same ones in version 5: the default convention and
+// another one, with the rounds compressed or not.
+fn valid_encodings() -> Vec<Vec<u8>> {
+    // g(x1, x2, x3) = 9 + 2*x3 + 3*x2 + 2 * x1 * x2 + 4* x1 * x2 * x3
+    let mpoly = MPolynomial {
+        var_num: 3,
+        coeffs: scalars![9, 2, 3, 0, 0, 0, 2, 4],
+    };
+    let params = SumCheckParams::new(VarOrder::LsbFirst, Binding::Suffix);
+    [
+        SumCheckParams::default(),
+        params.clone(),
+        SumCheckParams::default().with_compressed_rounds(),
+        params.with_compressed_rounds(),
+    ]
+    .into_iter()
+    .map(|params| SumCheck::with_params(mpoly.clone(), params).prove_non_interactive())
+    .flat_map(|bytes| {
+        let legacy = SumCheckProof::from_bytes(&bytes)
+            .unwrap()
+            .without_metadata();
+        [legacy.to_bytes(), bytes]
+    })
+    .collect()
+}
+
+#[test]
+fn test_valid_round_trip() {
+    let versions = valid_encodings()
+        .iter()
+        .map(|bytes| bytes[0])
+        .collect::<Vec<_>>();
+    assert_eq!(versions, [1, 5, 2, 5, 3, 5, 4, 5]);
+    for bytes in valid_encodings() {
+        assert!(decode(&bytes).is_ok());
+    }
+}
+
+#[test]
+fn test_random_bytes() {
+    let mut rng = Rng(0x5eed);
+    for i in 0..RANDOM_CASES {
+        let len = (rng.next_u64() % 512) as usize;
+        let mut bytes = rng.bytes(len);
+        // half of them pass the version check, so the rest of the decoder is reached.
+        if i % 2 == 0 && len > 0 {
+            bytes[0] = 1 + (i / 2 % 5) as u8;
+        }
+        let _ = decode(&bytes);
+    }
+}
+
+#[test]
+fn test_truncations() {
+    for bytes in valid_encodings() {
+        for len in 0..bytes.len() {
+            assert_eq!(decode(&bytes[..len]), Err(DecodeError::UnexpectedEnd));
+        }
+    }
+}
+
+#[test]
+fn test_single_byte_flips() {
+    for bytes in valid_encodings() {
+        for i in 0..bytes.len() {
+            for mask in [0x01, 0x80, 0xff] {
+                let mut flipped = bytes.clone();
+                flipped[i] ^= mask;
+                let _ = decode(&flipped);
+            }
+        }
+    }
+}
+
+// A flipped byte of the header (var_num, degree_bound and the params byte) is a header of other
+// params, or no header at all, so it's never accepted for the protocol of the valid one.
+#[test]
+fn test_header_flips() {
+    for bytes in valid_encodings() {
+        let id = SumCheckProof::from_bytes(&bytes).unwrap().protocol_id();
+        assert!(decode_for(&bytes, &id).is_ok());
+        let header_len = if bytes[0] % 2 == 0 || bytes[0] == 5 {
+            4
+        } else {
+            3
+        };
+        for i in 1..header_len {
+            for mask in [0x01, 0x80, 0xff] {
+                let mut flipped = bytes.clone();
+                flipped[i] ^= mask;
+                assert!(
+                    decode_for(&flipped, &id).is_err(),
+                    "accepted {}",
+                    to_hex(&flipped)
+                );
+            }
+        }
+    }
+}
+
+// A flipped degree of version 5 is caught by the decoder: its round has the coeffs of another
+// degree, or the degree is above the bound.
+#[test]
+fn test_degree_flips() {
+    for bytes in valid_encodings().into_iter().filter(|bytes| bytes[0] == 5) {
+        let var_num = bytes[1] as usize;
+        for i in 36..36 + var_num {
+            for mask in [0x01, 0x02, 0x80] {
+                let mut flipped = bytes.clone();
+                flipped[i] ^= mask;
+                assert!(
+                    matches!(
+                        decode(&flipped),
+                        Err(DecodeError::DegreeMismatch { .. } | DecodeError::DegreeTooHigh { .. })
+                    ),
+                    "accepted {}",
+                    to_hex(&flipped)
+                );
+            }
+        }
+    }
+}
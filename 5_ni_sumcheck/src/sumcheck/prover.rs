@@ -1,23 +1,39 @@
 use crate::poly::multivar_poly::MPolynomial;
 use crate::poly::univar_poly::Polynomial;
-use crate::sumcheck::Proofs;
+use crate::sumcheck::params::SumCheckParams;
+use crate::sumcheck::SumCheckProof;
 use crate::transcript::default::Keccak256Transcript;
-use crate::transcript::{poly_to_bytes, Transcript};
-use crate::utils::convert_to_binary;
+use crate::transcript::Transcript;
+use crate::utils::binary_point;
+use alloc::vec;
+use alloc::vec::Vec;
 use bls12_381::Scalar;
 
 pub struct Prover {
     g: MPolynomial,
     v: usize,
-    challenges: Vec<usize>, // challenges: r1, r2, ..., rv. (In implement, r1 is a random usize, which is easy to construct a Field)
+    challenges: Vec<Scalar>, // challenges: r1, r2, ..., rv.
+    params: SumCheckParams,
 }
 
 impl Prover {
     pub fn new(v: usize, mpoly: MPolynomial) -> Self {
+        Self::with_params(v, mpoly, SumCheckParams::default())
+    }
+
+    // `partial_evaluate` binds the MSB of the index first, so the LSB-first conventions run on
+    // g with the variables reversed.
+    pub fn with_params(v: usize, mpoly: MPolynomial, params: SumCheckParams) -> Self {
+        let g = if params.binds_msb() {
+            mpoly
+        } else {
+            reverse_vars(mpoly)
+        };
         Self {
-            g: mpoly,
+            g,
             v,
             challenges: vec![],
+            params,
         }
     }
 
@@ -27,50 +43,62 @@ impl Prover {
         let n = 1 << self.g.var_num;
         (0..n)
             .map(|i| {
-                let domain = convert_to_binary(&self.g.var_num, i);
+                let domain = binary_point(&self.g.var_num, i);
                 self.g.evaluate(&domain)
             })
             .sum()
     }
 
-    fn prepare_for_next_round(
+    fn prepare_for_next_round<T: Transcript>(
         &mut self,
         g_i: &Polynomial,
-        proofs: &mut Proofs,
-        transcript: &mut Keccak256Transcript,
+        proofs: &mut SumCheckProof,
+        transcript: &mut T,
     ) {
-        assert_eq!(self.challenges.len(), proofs.g_i_vec.len());
+        assert_eq!(self.challenges.len(), proofs.num_rounds());
 
         // generate r1, r2, ..., rv
-        transcript.append(&poly_to_bytes(g_i));
+        transcript.append_scalars(&g_i.coeffs);
         self.challenges.push(transcript.challenge());
 
         // cache g_i
-        proofs.g_i_vec.push(g_i.clone());
+        proofs.push_round(g_i.clone());
     }
 
-    pub fn prove(&mut self) -> Proofs {
-        let mut proofs = Proofs::default();
-        let mut transcript = Keccak256Transcript::default();
+    pub fn prove(&mut self) -> SumCheckProof {
+        self.prove_with_transcript(&mut Keccak256Transcript::default())
+    }
 
-        // round 1
-        let g1 = self.round_1();
-        // self.challenges.push(prepare_next_round(&g1));
-        self.prepare_for_next_round(&g1, &mut proofs, &mut transcript);
+    // The same as `prove`, but the challenges are derived from the given transcript, eg: of
+    // another backend, which V has to replay with the same one, see `verify_with_transcript`.
+    pub fn prove_with_transcript<T: Transcript>(&mut self, transcript: &mut T) -> SumCheckProof {
+        // g is multilinear, so each g_i has degree 1 at most.
+        let mut proofs =
+            SumCheckProof::new(self.v, 1, self.statement()).with_params(self.params.clone());
+        transcript.bind_protocol(&proofs.protocol_id());
+
+        // round 1, none if v = 0: g() is the claimed sum, which V checks in the final check.
+        if self.v > 0 {
+            let g1 = self.round_1();
+            // self.challenges.push(prepare_next_round(&g1));
+            self.prepare_for_next_round(&g1, &mut proofs, transcript);
+        }
 
         // round 2 - (v-1)
         for _ in 2..self.v {
             let g_j = self.recursive_round_j();
-            self.prepare_for_next_round(&g_j, &mut proofs, &mut transcript);
+            self.prepare_for_next_round(&g_j, &mut proofs, transcript);
         }
 
-        // round v
-        let g_v = self.round_v();
-        self.prepare_for_next_round(&g_v, &mut proofs, &mut transcript);
+        // round v, which is round 1 if v = 1.
+        if self.v > 1 {
+            let g_v = self.round_v();
+            self.prepare_for_next_round(&g_v, &mut proofs, transcript);
+        }
 
         // finally check
         let target = self.evaluate();
-        proofs.target = target;
+        proofs.final_eval = target;
 
         proofs
     }
@@ -94,4 +122,25 @@ impl Prover {
     fn evaluate(&self) -> Scalar {
         self.g.evaluate(&self.challenges)
     }
+
+    // The point of `evaluate` in the order of the caller, for the tests of V only.
+    #[cfg(test)]
+    pub(crate) fn final_point(&self) -> Vec<Scalar> {
+        self.params.point(&self.challenges)
+    }
+}
+
+// g(x_v, ..., x_1), the coeff of the index i moves to the index with the bits of i reversed.
+fn reverse_vars(g: MPolynomial) -> MPolynomial {
+    let v = g.var_num;
+    let mut coeffs = vec![Scalar::zero(); g.coeffs.len()];
+    for (i, c) in g.coeffs.into_iter().enumerate() {
+        let j = if v == 0 {
+            i
+        } else {
+            i.reverse_bits() >> (usize::BITS as usize - v)
+        };
+        coeffs[j] = c;
+    }
+    MPolynomial { var_num: v, coeffs }
 }
@@ -0,0 +1,587 @@
+// The sum-check of a table by `prove_table_with_params`, split into calls of a bounded work, eg:
+// for a 2^24 table, with the progress reported after each call, and a checkpoint to resume the
+// prover from, in another process too. Dropping the session cancels it, the table is a `Secret`.
+//
+// The work is the entries of the table read or written by P, in passes over the table:
+//  - pass 0 sums the 2^v entries into the halves (lo, hi) of round 1,
+//  - pass j >= 1 binds r_j, folding the table of 2^(v-j+1) entries into 2^(v-j), and sums the
+//    folded ones into the halves of round j + 1,
+// so 2^(v+1) - 1 in all. A pass may take many calls, it's resumed at the entry it stopped at,
+// and the sums are accumulated in the index order. A round is sent to the transcript once the
+// pass before it is done, so the rounds, the challenges and the proof are the ones of
+// `prove_table_with_params` whatever the budgets are.
+//
+// The checkpoint is the state between two calls, in the encoding of `Fiat_Shamir::codec`:
+//
+//      | field      | encoding                                                   |
+//      |------------|------------------------------------------------------------|
+//      | version    | 1 byte, PROVER_CHECKPOINT_VERSION                          |
+//      | instance   | digest, `instance_digest` of the table and the params      |
+//      | cursor     | u64, the entries done in the current pass                  |
+//      | sums       | scalars, (lo, hi) of the current pass so far               |
+//      | rounds     | scalars, the 2 coeffs of each g_j sent so far              |
+//      | challenges | scalars, r_1, ..., r_j                                     |
+//      | transcript | bytes, `Keccak256Transcript::snapshot`                     |
+//      | table      | scalars, the table folded so far                           |
+//
+// The table is folded in place, so mid-pass it's the whole buffer of the pass: the folded
+// entries, then the ones left to fold. It's the witness, so a checkpoint is as secret as the
+// table. The instance digest ties a checkpoint to its table and params: resuming with another
+// instance is rejected rather than mixing the rounds of two tables. It's not authenticated.
+use crate::poly::univar_poly::Polynomial;
+use crate::sumcheck::params::SumCheckParams;
+use crate::sumcheck::proof::protocol_id;
+use crate::sumcheck::SumCheckProof;
+use crate::transcript::default::Keccak256Transcript;
+use crate::transcript::{poly_to_bytes, Transcript};
+use alloc::vec;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use core::fmt;
+use sha3::{Digest, Keccak256};
+use sumcheck::poly::eval_table::EvalTable;
+use sumcheck::secret::{Secret, ZeroizingEvalTable};
+use Fiat_Shamir::codec::{encode_bytes, encode_digest, encode_scalar, encode_scalars, encode_u64};
+use Fiat_Shamir::codec::{CodecError, Decoder};
+
+pub const PROVER_CHECKPOINT_VERSION: u8 = 1;
+
+// The entries of the table a call of `advance` reads or writes at most.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorkBudget(pub usize);
+
+impl WorkBudget {
+    // The whole proof in one call.
+    pub const UNLIMITED: Self = Self(usize::MAX);
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionStatus {
+    Finished(SumCheckProof),
+    // the work done over the whole work, in [0, 1).
+    InProgress { fraction_complete: f64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionError {
+    Decode(CodecError),
+    // The checkpoint is of another table or other params.
+    WrongInstance,
+    // The checkpoint doesn't fit the table, eg: more rounds than variables.
+    WrongShape,
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::Decode(_) => write!(f, "Can't decode the checkpoint"),
+            SessionError::WrongInstance => write!(f, "The checkpoint is of another instance"),
+            SessionError::WrongShape => write!(f, "The checkpoint doesn't fit the table"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SessionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SessionError::Decode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<CodecError> for SessionError {
+    fn from(e: CodecError) -> Self {
+        SessionError::Decode(e)
+    }
+}
+
+// keccak(labeled("session.instance"), the protocol of the proof, the table), the table is
+// hashed as it's read, so it's never encoded as a whole.
+pub fn instance_digest(table: &EvalTable, params: &SumCheckParams) -> [u8; 32] {
+    let protocol = protocol_id(table.var_num as u8, 1, params);
+    let mut hasher = Keccak256::new();
+    hasher.update(encode_bytes(b"session.instance"));
+    hasher.update(encode_bytes(&protocol.to_bytes()));
+    hasher.update(encode_u64(table.evals.len() as u64));
+    for e in table.evals.iter() {
+        hasher.update(encode_scalar(e));
+    }
+    hasher.finalize().into()
+}
+
+#[derive(Debug, Clone)]
+pub struct ProverCheckpoint {
+    pub instance: [u8; 32],
+    pub cursor: usize,
+    pub sums: [Scalar; 2],
+    pub rounds: Vec<Scalar>,
+    pub challenges: Vec<Scalar>,
+    pub transcript: Vec<u8>,
+    pub table: ZeroizingEvalTable,
+}
+
+impl ProverCheckpoint {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let scalars = 2 + self.rounds.len() + self.challenges.len() + self.table.evals.len();
+        let mut bytes = Vec::with_capacity(1 + 32 + 8 * 6 + self.transcript.len() + 32 * scalars);
+        bytes.push(PROVER_CHECKPOINT_VERSION);
+        bytes.extend(encode_digest(&self.instance));
+        bytes.extend(encode_u64(self.cursor as u64));
+        bytes.extend(encode_scalars(&self.sums));
+        bytes.extend(encode_scalars(&self.rounds));
+        bytes.extend(encode_scalars(&self.challenges));
+        bytes.extend(encode_bytes(&self.transcript));
+        bytes.extend(encode_scalars(&self.table.evals));
+        bytes
+    }
+
+    // A table of another size than 2^k is of no session, so it's WrongShape.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SessionError> {
+        let mut decoder = Decoder::new(bytes);
+        decoder.read_version(PROVER_CHECKPOINT_VERSION)?;
+        let instance = decoder.read_digest()?;
+        let cursor = decoder.read_usize()?;
+        let sums = decoder.read_scalars()?;
+        let rounds = decoder.read_scalars()?;
+        let challenges = decoder.read_scalars()?;
+        let transcript = decoder.read_bytes()?;
+        let evals = decoder.read_scalars()?;
+        decoder.finish()?;
+        let sums: [Scalar; 2] = sums.try_into().map_err(|_| SessionError::WrongShape)?;
+        if !evals.len().is_power_of_two() {
+            return Err(SessionError::WrongShape);
+        }
+        Ok(Self {
+            instance,
+            cursor,
+            sums,
+            rounds,
+            challenges,
+            transcript,
+            table: Secret::new(EvalTable::new(evals)),
+        })
+    }
+}
+
+pub struct ProverSession {
+    instance: [u8; 32],
+    params: SumCheckParams,
+    var_num: usize,
+    // the only copy of the witness, folded in place.
+    table: ZeroizingEvalTable,
+    transcript: Keccak256Transcript,
+    rounds: Vec<Polynomial>,
+    challenges: Vec<Scalar>,
+    // the entries done in the pass of `challenges.len()`, and their (lo, hi) so far.
+    cursor: usize,
+    sums: [Scalar; 2],
+}
+
+impl ProverSession {
+    // The session of `prove_table_with_params(table, params, &mut transcript)`, nothing of the
+    // table is read but for the instance digest.
+    pub fn new(
+        table: &EvalTable,
+        params: SumCheckParams,
+        mut transcript: Keccak256Transcript,
+    ) -> Self {
+        let var_num = table.var_num;
+        assert!(var_num > 0, "no variable to prove");
+        transcript.bind_protocol(&protocol_id(var_num as u8, 1, &params));
+        Self {
+            instance: instance_digest(table, &params),
+            params,
+            var_num,
+            table: Secret::copy_of(table),
+            transcript,
+            rounds: Vec::with_capacity(var_num),
+            challenges: Vec::with_capacity(var_num),
+            cursor: 0,
+            sums: [Scalar::zero(); 2],
+        }
+    }
+
+    // Resume from a checkpoint of the same table and params, which are hashed to check it.
+    pub fn resume(
+        table: &EvalTable,
+        params: SumCheckParams,
+        checkpoint: ProverCheckpoint,
+    ) -> Result<Self, SessionError> {
+        if checkpoint.instance != instance_digest(table, &params) {
+            return Err(SessionError::WrongInstance);
+        }
+        let var_num = table.var_num;
+        let pass = checkpoint.challenges.len();
+        let finished = pass == var_num && checkpoint.table.evals.len() == 1;
+        if pass > var_num
+            || checkpoint.rounds.len() != 2 * pass
+            || (!finished && checkpoint.table.evals.len() != Self::buffer_len(var_num, pass))
+            || (!finished && checkpoint.cursor >= Self::pass_len(var_num, pass))
+            || (finished && checkpoint.cursor != 0)
+        {
+            return Err(SessionError::WrongShape);
+        }
+        let rounds = checkpoint
+            .rounds
+            .chunks(2)
+            .map(|coeffs| Polynomial {
+                coeffs: coeffs.to_vec(),
+            })
+            .collect();
+        Ok(Self {
+            instance: checkpoint.instance,
+            params,
+            var_num,
+            table: checkpoint.table,
+            transcript: Keccak256Transcript::restore(&checkpoint.transcript),
+            rounds,
+            challenges: checkpoint.challenges,
+            cursor: checkpoint.cursor,
+            sums: checkpoint.sums,
+        })
+    }
+
+    // The table is copied, see `secret::witness_copies`.
+    pub fn checkpoint(&self) -> ProverCheckpoint {
+        ProverCheckpoint {
+            instance: self.instance,
+            cursor: self.cursor,
+            sums: self.sums,
+            rounds: self.rounds.iter().flat_map(|g| g.coeffs.clone()).collect(),
+            challenges: self.challenges.clone(),
+            transcript: self.transcript.snapshot(),
+            table: self.table.clone(),
+        }
+    }
+
+    // Do the work of the budget at most, and return the proof once it's done. A call after
+    // that returns it again.
+    pub fn advance(&mut self, budget: WorkBudget) -> SessionStatus {
+        let mut left = budget.0;
+        while !self.is_finished() {
+            let pass = self.challenges.len();
+            let len = Self::pass_len(self.var_num, pass);
+            let n = left.min(len - self.cursor);
+            if pass == 0 {
+                self.sum(n);
+            } else {
+                self.fold(n);
+            }
+            left -= n;
+            if self.cursor < len {
+                break;
+            }
+            self.finish_pass();
+        }
+        match self.is_finished() {
+            true => SessionStatus::Finished(self.proof()),
+            false => SessionStatus::InProgress {
+                fraction_complete: self.fraction_complete(),
+            },
+        }
+    }
+
+    // The rounds sent so far, a round is only in once the pass before it is done.
+    pub fn rounds(&self) -> &[Polynomial] {
+        &self.rounds
+    }
+
+    // The work done over the whole work, 1 once the proof is done.
+    pub fn fraction_complete(&self) -> f64 {
+        let size = 1usize << self.var_num;
+        let total = 2 * size - 1;
+        if self.is_finished() {
+            return 1.0;
+        }
+        // 2^v for pass 0, then 2^(v-1), ..., 2^(v-j+1) for the passes 1, ..., j-1.
+        let pass = self.challenges.len();
+        let before = match pass {
+            0 => 0,
+            _ => size + (size - (size >> (pass - 1))),
+        };
+        (before + self.cursor) as f64 / total as f64
+    }
+
+    fn is_finished(&self) -> bool {
+        self.challenges.len() == self.var_num && self.table.evals.len() == 1
+    }
+
+    // The entries of the pass: the table of pass 0, the folded one of pass j >= 1.
+    fn pass_len(var_num: usize, pass: usize) -> usize {
+        match pass {
+            0 => 1 << var_num,
+            _ => 1 << (var_num - pass),
+        }
+    }
+
+    // The size of the table while the pass runs, as it's truncated at the end of a fold.
+    fn buffer_len(var_num: usize, pass: usize) -> usize {
+        match pass {
+            0 => 1 << var_num,
+            _ => 1 << (var_num - pass + 1),
+        }
+    }
+
+    // The entry i of a table of `size` is in the lo half of the round: the MSB splits the table,
+    // and the LSB interleaves it.
+    fn is_lo(&self, i: usize, size: usize) -> bool {
+        match self.params.binds_msb() {
+            true => i < size / 2,
+            false => i.is_multiple_of(2),
+        }
+    }
+
+    fn add(&mut self, i: usize, size: usize, e: Scalar) {
+        let half = if self.is_lo(i, size) { 0 } else { 1 };
+        self.sums[half] += e;
+    }
+
+    // The next n entries of pass 0.
+    fn sum(&mut self, n: usize) {
+        let size = self.table.evals.len();
+        for i in self.cursor..self.cursor + n {
+            let e = self.table.evals[i];
+            self.add(i, size, e);
+        }
+        self.cursor += n;
+    }
+
+    // The next n folded entries of the pass j, the same as `EvalTable::bind_first` and
+    // `bind_last`: new[k] only reads the entries at k or after it, which are not written yet.
+    fn fold(&mut self, n: usize) {
+        let pass = self.challenges.len();
+        let r = self.challenges[pass - 1];
+        let half = self.table.evals.len() / 2;
+        let binds_msb = self.params.binds_msb();
+        for k in self.cursor..self.cursor + n {
+            let evals = &mut self.table.evals;
+            let (lo, hi) = match binds_msb {
+                true => (evals[k], evals[k + half]),
+                false => (evals[2 * k], evals[2 * k + 1]),
+            };
+            let folded = lo + r * (hi - lo);
+            evals[k] = folded;
+            // the last fold has no round after it.
+            if pass < self.var_num {
+                self.add(k, half, folded);
+            }
+        }
+        self.cursor += n;
+    }
+
+    // Truncate the folded table, and send the round of the sums, if there's one left.
+    fn finish_pass(&mut self) {
+        if !self.challenges.is_empty() {
+            let half = self.table.evals.len() / 2;
+            self.table.evals.truncate(half);
+            self.table.var_num -= 1;
+        }
+        self.cursor = 0;
+        if self.challenges.len() == self.var_num {
+            return;
+        }
+        let [sum_lo, sum_hi] = core::mem::replace(&mut self.sums, [Scalar::zero(); 2]);
+        let g_j = Polynomial {
+            coeffs: vec![sum_lo, sum_hi - sum_lo],
+        };
+        self.transcript.append(&poly_to_bytes(&g_j));
+        self.challenges.push(self.transcript.challenge());
+        self.rounds.push(g_j);
+    }
+
+    // The claimed sum is g_1(0) + g_1(1), the sum of pass 0.
+    fn proof(&self) -> SumCheckProof {
+        let g_1 = &self.rounds[0];
+        let claimed_sum = g_1.coeffs[0].double() + g_1.coeffs[1];
+        let mut proof =
+            SumCheckProof::new(self.var_num, 1, claimed_sum).with_params(self.params.clone());
+        for g_j in self.rounds.iter() {
+            proof.push_round(g_j.clone());
+        }
+        proof.final_eval = self.table.evals[0];
+        proof
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sumcheck::batched::prove_table_with_params;
+    use crate::sumcheck::params::{Binding, VarOrder};
+    use crate::sumcheck::verify_with_params;
+    use sumcheck::poly::eval_table::random_table;
+
+    const VAR_NUM: usize = 6;
+
+    fn all_params() -> [SumCheckParams; 3] {
+        [
+            SumCheckParams::default(),
+            SumCheckParams::new(VarOrder::LsbFirst, Binding::Prefix),
+            SumCheckParams::new(VarOrder::MsbFirst, Binding::Suffix).with_compressed_rounds(),
+        ]
+    }
+
+    // A transcript which absorbed something before the session, eg: of an outer proof.
+    fn prefixed() -> Keccak256Transcript {
+        let mut transcript = Keccak256Transcript::default();
+        transcript.append(b"outer");
+        transcript
+    }
+
+    fn single_shot(table: &EvalTable, params: &SumCheckParams) -> Vec<u8> {
+        prove_table_with_params(table, params.clone(), &mut prefixed()).to_bytes()
+    }
+
+    // Advance by the budget until it's done, with the fractions of each call.
+    fn run(session: &mut ProverSession, budget: usize) -> (SumCheckProof, Vec<f64>) {
+        let mut fractions = vec![];
+        loop {
+            match session.advance(WorkBudget(budget)) {
+                SessionStatus::Finished(proof) => return (proof, fractions),
+                SessionStatus::InProgress { fraction_complete } => {
+                    fractions.push(fraction_complete)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_small_budgets() {
+        let table = random_table(VAR_NUM);
+        for params in all_params() {
+            let expected = single_shot(&table, &params);
+            for budget in [1, 3, 7, 32, 64, 1000] {
+                let mut session = ProverSession::new(&table, params.clone(), prefixed());
+                let (proof, _) = run(&mut session, budget);
+                assert_eq!(proof.to_bytes(), expected, "budget {}", budget);
+            }
+            let mut session = ProverSession::new(&table, params.clone(), prefixed());
+            let SessionStatus::Finished(proof) = session.advance(WorkBudget::UNLIMITED) else {
+                panic!("not finished");
+            };
+            assert_eq!(proof.to_bytes(), expected);
+            assert_eq!(
+                session.advance(WorkBudget(0)),
+                SessionStatus::Finished(proof)
+            );
+        }
+
+        // the proof is a valid one.
+        let mut session = ProverSession::new(&table, SumCheckParams::default(), Default::default());
+        let (proof, _) = run(&mut session, 5);
+        let claim = verify_with_params(&proof, SumCheckParams::default()).unwrap();
+        assert_eq!(table.evaluate(&claim.point), proof.final_eval);
+    }
+
+    #[test]
+    fn test_one_variable() {
+        let table = random_table(1);
+        let params = SumCheckParams::default();
+        let mut session = ProverSession::new(&table, params.clone(), prefixed());
+        let (proof, fractions) = run(&mut session, 1);
+        assert_eq!(proof.to_bytes(), single_shot(&table, &params));
+        // 2 entries summed and 1 folded.
+        assert_eq!(fractions, vec![1.0 / 3.0, 2.0 / 3.0]);
+    }
+
+    // The work is 2^(v+1) - 1 entries, so a budget of 1 reports each of them.
+    #[test]
+    fn test_fraction_complete() {
+        let table = random_table(VAR_NUM);
+        let mut session = ProverSession::new(&table, SumCheckParams::default(), prefixed());
+        assert_eq!(session.fraction_complete(), 0.0);
+        let (_, fractions) = run(&mut session, 1);
+        assert_eq!(fractions.len(), (2 << VAR_NUM) - 2);
+        assert!(fractions.windows(2).all(|w| w[0] < w[1]));
+        assert!(fractions.iter().all(|f| (0.0..1.0).contains(f)));
+        assert_eq!(session.fraction_complete(), 1.0);
+
+        // a round is sent once the pass before it is done.
+        let mut session = ProverSession::new(&table, SumCheckParams::default(), prefixed());
+        session.advance(WorkBudget((1 << VAR_NUM) - 1));
+        assert!(session.rounds().is_empty());
+        session.advance(WorkBudget(1));
+        assert_eq!(session.rounds().len(), 1);
+        session.advance(WorkBudget((1 << (VAR_NUM - 1)) - 1));
+        assert_eq!(session.rounds().len(), 1);
+    }
+
+    // Checkpoint at each split, drop the session, and resume from the bytes, as another process
+    // would.
+    #[test]
+    fn test_checkpoint_and_resume() {
+        let table = random_table(VAR_NUM);
+        for params in all_params() {
+            let expected = single_shot(&table, &params);
+            for split in [0, 1, 31, 64, 65, 100, 126, 127] {
+                let mut session = ProverSession::new(&table, params.clone(), prefixed());
+                session.advance(WorkBudget(split));
+                let bytes = session.checkpoint().to_bytes();
+                drop(session);
+
+                let checkpoint = ProverCheckpoint::from_bytes(&bytes).unwrap();
+                let mut session =
+                    ProverSession::resume(&table, params.clone(), checkpoint).unwrap();
+                let (proof, _) = run(&mut session, 9);
+                assert_eq!(proof.to_bytes(), expected, "split at {}", split);
+            }
+        }
+    }
+
+    #[test]
+    fn test_wrong_instance() {
+        let (table, params) = (random_table(VAR_NUM), SumCheckParams::default());
+        let mut session = ProverSession::new(&table, params.clone(), prefixed());
+        session.advance(WorkBudget(100));
+        let checkpoint = session.checkpoint();
+
+        let resume = |table: &EvalTable, params: &SumCheckParams, checkpoint: &ProverCheckpoint| {
+            ProverSession::resume(table, params.clone(), checkpoint.clone()).err()
+        };
+        assert_eq!(
+            resume(&random_table(VAR_NUM), &params, &checkpoint),
+            Some(SessionError::WrongInstance)
+        );
+        // a table which differs in a single entry.
+        let mut other = table.clone();
+        other.evals[5] += Scalar::one();
+        assert_eq!(
+            resume(&other, &params, &checkpoint),
+            Some(SessionError::WrongInstance)
+        );
+        let lsb = SumCheckParams::new(VarOrder::LsbFirst, Binding::Prefix);
+        assert_eq!(
+            resume(&table, &lsb, &checkpoint),
+            Some(SessionError::WrongInstance)
+        );
+        assert_eq!(resume(&table, &params, &checkpoint), None);
+
+        // more rounds than the pass is at.
+        let mut wrong = checkpoint.clone();
+        wrong.challenges.push(Scalar::one());
+        assert_eq!(
+            resume(&table, &params, &wrong),
+            Some(SessionError::WrongShape)
+        );
+        let mut wrong = checkpoint.clone();
+        wrong.cursor = 1 << VAR_NUM;
+        assert_eq!(
+            resume(&table, &params, &wrong),
+            Some(SessionError::WrongShape)
+        );
+
+        // the table is cut, it's the buffer of 2^(v-1) entries of the pass 2.
+        let bytes = checkpoint.to_bytes();
+        let len = 1 << (VAR_NUM - 1);
+        assert_eq!(checkpoint.table.evals.len(), len);
+        assert_eq!(
+            ProverCheckpoint::from_bytes(&bytes[..bytes.len() - 1]).err(),
+            Some(SessionError::Decode(CodecError::LengthTooLarge {
+                offset: bytes.len() - 32 * len - 8,
+                len: len as u64
+            }))
+        );
+    }
+}
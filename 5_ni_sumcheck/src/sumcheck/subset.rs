@@ -0,0 +1,336 @@
+// Prove a sum over a subset S of the hypercube: H = ∑_{x ∈ S} g(x).
+//
+// It's the sum-check of g(x)·1_S(x) over the whole hypercube, where 1_S is the MLE of the
+// indicator of S. Each round poly has degree 2, and the final claim g̃(r)·1̃_S(r) is split by V:
+// V evaluates 1̃_S(r) by itself, and g̃(r) is left as the evaluation claim, see `SubsetClaim`.
+//
+// The structured subsets have a closed form 1̃_S(r) in O(v), so V never builds the table:
+//      prefix_fixed: x_1..x_k = b_1..b_k,  1̃_S(r) = ∏_{i<=k} (b_i ? r_i : 1 - r_i)
+//      parity:       even or odd |x|,     1̃_S(r) = (1 ± ∏ (1 - 2·r_i)) / 2
+//      from_set:     any members,         1̃_S(r) = ∑_{m ∈ S} eq(r, bits(m)), in O(|S|·v)
+// The variables are MSB first as in `EvalTable`, eg: x_1 is the MSB of the index.
+use crate::sumcheck::verifier::Verifier;
+use crate::sumcheck::{SumCheckProof, VerifyError};
+use crate::transcript::default::Keccak256Transcript;
+use alloc::vec;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use core::fmt;
+use sumcheck::poly::eval_table::EvalTable;
+use sumcheck::utils::eq_eval_at_index;
+
+#[cfg(feature = "prover")]
+mod prover;
+
+// A structured subset S of {0,1}^v.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Indicator {
+    // the first bits of x are the prefix.
+    PrefixFixed { var_num: usize, prefix: Vec<u8> },
+    // |x| is even, or odd.
+    Parity { var_num: usize, even: bool },
+    // the indices of the members, sorted and deduplicated.
+    FromSet { var_num: usize, members: Vec<usize> },
+}
+
+impl Indicator {
+    // eg: prefix_fixed(3, &[1]) is {100, 101, 110, 111}.
+    pub fn prefix_fixed(var_num: usize, prefix_bits: &[u8]) -> Self {
+        assert!(
+            prefix_bits.len() <= var_num,
+            "the prefix is longer than var_num"
+        );
+        assert!(
+            prefix_bits.iter().all(|b| *b <= 1),
+            "the prefix is not in bits"
+        );
+        Indicator::PrefixFixed {
+            var_num,
+            prefix: prefix_bits.to_vec(),
+        }
+    }
+
+    // eg: parity(2, true) is {00, 11}.
+    pub fn parity(var_num: usize, even: bool) -> Self {
+        Indicator::Parity { var_num, even }
+    }
+
+    // The members are the indices of the points, a duplicated member is counted once, as S is
+    // a set, eg: from_set(2, &[3, 1, 3]) is {01, 11}.
+    pub fn from_set(var_num: usize, members: &[usize]) -> Self {
+        assert!(
+            members.iter().all(|m| *m < 1 << var_num),
+            "the member is out of the hypercube"
+        );
+        let mut members = members.to_vec();
+        members.sort_unstable();
+        members.dedup();
+        Indicator::FromSet { var_num, members }
+    }
+
+    pub fn var_num(&self) -> usize {
+        match self {
+            Indicator::PrefixFixed { var_num, .. }
+            | Indicator::Parity { var_num, .. }
+            | Indicator::FromSet { var_num, .. } => *var_num,
+        }
+    }
+
+    // The index is in S, see `convert_to_binary` for the bits.
+    pub fn contains(&self, index: usize) -> bool {
+        match self {
+            Indicator::PrefixFixed { var_num, prefix } => {
+                let top = index >> (var_num - prefix.len());
+                prefix.iter().fold(0, |acc, b| (acc << 1) | *b as usize) == top
+            }
+            Indicator::Parity { even, .. } => index.count_ones().is_multiple_of(2) == *even,
+            Indicator::FromSet { members, .. } => members.binary_search(&index).is_ok(),
+        }
+    }
+
+    // [1_S(x) for x in {0,1}^v], for P.
+    pub fn table(&self) -> EvalTable {
+        let evals = match self {
+            Indicator::FromSet { var_num, members } => {
+                let mut evals = vec![Scalar::zero(); 1 << var_num];
+                members.iter().for_each(|m| evals[*m] = Scalar::one());
+                evals
+            }
+            _ => (0..1 << self.var_num())
+                .map(|i| Scalar::from(self.contains(i) as u64))
+                .collect(),
+        };
+        EvalTable::new(evals)
+    }
+
+    // 1̃_S(point) in the closed form, for V.
+    pub fn evaluate(&self, point: &[Scalar]) -> Scalar {
+        assert_eq!(point.len(), self.var_num(), "the point has a wrong var_num");
+        match self {
+            Indicator::PrefixFixed { prefix, .. } => prefix
+                .iter()
+                .zip(point.iter())
+                .map(|(b, r)| if *b == 1 { *r } else { Scalar::one() - r })
+                .fold(Scalar::one(), |acc, v| acc * v),
+            Indicator::Parity { even, .. } => {
+                let p = point
+                    .iter()
+                    .fold(Scalar::one(), |acc, r| acc * (Scalar::one() - r.double()));
+                let two_inv = Scalar::from(2).invert().unwrap();
+                if *even {
+                    (Scalar::one() + p) * two_inv
+                } else {
+                    (Scalar::one() - p) * two_inv
+                }
+            }
+            Indicator::FromSet { members, .. } => {
+                members.iter().map(|m| eq_eval_at_index(point, *m)).sum()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubsetError {
+    // the indicator and the proof have different var_num.
+    VarNumMismatch,
+    // the rounds num is not var_num.
+    MalformedProof,
+    Verify(VerifyError),
+}
+
+impl fmt::Display for SubsetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SubsetError::VarNumMismatch => write!(f, "The indicator has another var_num"),
+            SubsetError::MalformedProof => write!(f, "The rounds num is not var_num"),
+            SubsetError::Verify(_) => write!(f, "Verifier rejected the subset sum"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SubsetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SubsetError::Verify(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<VerifyError> for SubsetError {
+    fn from(e: VerifyError) -> Self {
+        SubsetError::Verify(e)
+    }
+}
+
+// The result of a subset verification: V accepts iff g̃(point)·indicator_eval == final_eval.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubsetClaim {
+    pub point: Vec<Scalar>,
+    pub indicator_eval: Scalar, // 1̃_S(point), by V itself
+    pub final_eval: Scalar,
+}
+
+impl SubsetClaim {
+    // g_eval is claimed to be g̃(point).
+    pub fn check(&self, g_eval: Scalar) -> bool {
+        g_eval * self.indicator_eval == self.final_eval
+    }
+}
+
+pub struct SubsetSumCheck;
+
+impl SubsetSumCheck {
+    pub fn verify(
+        claimed_sum: Scalar,
+        indicator: &Indicator,
+        proof: &SumCheckProof,
+        transcript: &mut Keccak256Transcript,
+    ) -> Result<SubsetClaim, SubsetError> {
+        let var_num = indicator.var_num();
+        if proof.var_num as usize != var_num {
+            return Err(SubsetError::VarNumMismatch);
+        }
+        if proof.num_rounds() != var_num {
+            return Err(SubsetError::MalformedProof);
+        }
+
+        let mut verifier = Verifier::new(var_num, claimed_sum);
+        verifier
+            .verify_with_transcript(proof.clone(), transcript, &mut ())
+            .map_err(SubsetError::Verify)?;
+
+        let point = verifier.point();
+        Ok(SubsetClaim {
+            indicator_eval: indicator.evaluate(&point),
+            point,
+            final_eval: proof.final_eval,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod test {
+    use crate::sumcheck::subset::{Indicator, SubsetError, SubsetSumCheck};
+    use crate::sumcheck::VerifyError;
+    use crate::transcript::default::Keccak256Transcript;
+    use bls12_381::Scalar;
+    use ff::Field;
+    use rand_core::{OsRng, RngCore};
+    use sumcheck::poly::eval_table::random_table;
+    use sumcheck::utils::eq_eval_at_index;
+
+    fn indicators(var_num: usize) -> Vec<Indicator> {
+        let members = (0..20)
+            .map(|_| OsRng.next_u32() as usize % (1 << var_num))
+            .collect::<Vec<_>>();
+        vec![
+            Indicator::prefix_fixed(var_num, &[0]),
+            Indicator::prefix_fixed(var_num, &[1, 0, 1]),
+            Indicator::prefix_fixed(var_num, &[]),
+            Indicator::parity(var_num, true),
+            Indicator::parity(var_num, false),
+            Indicator::from_set(var_num, &members),
+        ]
+    }
+
+    #[test]
+    fn test_subset_sums() {
+        let v = 8;
+        let g = random_table(v);
+        for indicator in indicators(v) {
+            let brute = (0..1 << v)
+                .filter(|i| indicator.contains(*i))
+                .map(|i| g.evals[i])
+                .sum::<Scalar>();
+
+            let proof =
+                SubsetSumCheck::prove(&g, &indicator.table(), &mut Keccak256Transcript::default());
+            assert_eq!(proof.claimed_sum, brute);
+
+            let claim = SubsetSumCheck::verify(
+                brute,
+                &indicator,
+                &proof,
+                &mut Keccak256Transcript::default(),
+            )
+            .unwrap();
+            assert!(claim.check(g.evaluate(&claim.point)));
+            assert!(!claim.check(g.evaluate(&claim.point) + Scalar::one()));
+        }
+    }
+
+    #[test]
+    fn test_closed_form_evaluations() {
+        let v = 8;
+        for indicator in indicators(v) {
+            let table = indicator.table();
+            for _ in 0..20 {
+                let point = (0..v).map(|_| Scalar::random(OsRng)).collect::<Vec<_>>();
+                assert_eq!(indicator.evaluate(&point), table.evaluate(&point));
+            }
+        }
+    }
+
+    #[test]
+    fn test_corrupt_claimed_sum() {
+        let v = 8;
+        let g = random_table(v);
+        let indicator = Indicator::parity(v, true);
+        let proof =
+            SubsetSumCheck::prove(&g, &indicator.table(), &mut Keccak256Transcript::default());
+
+        // the sum over the whole hypercube, or a wrong subset sum.
+        for claimed in [g.sum(), proof.claimed_sum + Scalar::one()] {
+            assert_eq!(
+                SubsetSumCheck::verify(
+                    claimed,
+                    &indicator,
+                    &proof,
+                    &mut Keccak256Transcript::default()
+                ),
+                Err(SubsetError::Verify(VerifyError::WrongClaimedSum))
+            );
+        }
+
+        // the proof of the odd points is checked against the even ones.
+        let odd = Indicator::parity(v, false);
+        let proof = SubsetSumCheck::prove(&g, &odd.table(), &mut Keccak256Transcript::default());
+        let claim = SubsetSumCheck::verify(
+            proof.claimed_sum,
+            &indicator,
+            &proof,
+            &mut Keccak256Transcript::default(),
+        )
+        .unwrap();
+        assert!(!claim.check(g.evaluate(&claim.point)));
+
+        assert_eq!(
+            SubsetSumCheck::verify(
+                proof.claimed_sum,
+                &Indicator::parity(v + 1, false),
+                &proof,
+                &mut Keccak256Transcript::default()
+            ),
+            Err(SubsetError::VarNumMismatch)
+        );
+    }
+
+    #[test]
+    fn test_from_set_duplicates() {
+        let v = 8;
+        let indicator = Indicator::from_set(v, &[7, 3, 7, 200, 3]);
+        assert_eq!(indicator, Indicator::from_set(v, &[3, 7, 200]));
+        // each member is 1 in the table, not the times it's listed.
+        assert_eq!(indicator.table().sum(), Scalar::from(3));
+
+        let point = (0..v).map(|_| Scalar::random(OsRng)).collect::<Vec<_>>();
+        let expected = [3, 7, 200]
+            .iter()
+            .map(|m| eq_eval_at_index(&point, *m))
+            .sum::<Scalar>();
+        assert_eq!(indicator.evaluate(&point), expected);
+    }
+}
@@ -0,0 +1,64 @@
+// P of the subset sum-check, see `subset`.
+use crate::poly::univar_poly::Polynomial;
+use crate::sumcheck::subset::SubsetSumCheck;
+use crate::sumcheck::SumCheckProof;
+use crate::transcript::default::Keccak256Transcript;
+use crate::transcript::{poly_to_bytes, Transcript};
+use alloc::vec;
+use bls12_381::Scalar;
+use sumcheck::poly::eval_table::EvalTable;
+use sumcheck::secret::ZeroizingEvalTable;
+
+impl SubsetSumCheck {
+    // The sum-check of g·1_S, whose claimed sum is ∑_{x ∈ S} g(x).
+    // The indicator table is public, see `Indicator::table`, only g is wiped.
+    pub fn prove(
+        g_table: &EvalTable,
+        indicator_table: &EvalTable,
+        transcript: &mut Keccak256Transcript,
+    ) -> SumCheckProof {
+        let var_num = g_table.var_num;
+        assert_eq!(
+            var_num, indicator_table.var_num,
+            "g and the indicator have different var_num"
+        );
+        let claimed_sum = g_table
+            .evals
+            .iter()
+            .zip(indicator_table.evals.iter())
+            .map(|(g, s)| g * s)
+            .sum();
+        // the product of two multilinear tables, so each g_j has degree 2 at most.
+        let mut proofs = SumCheckProof::new(var_num, 2, claimed_sum);
+        transcript.bind_protocol(&proofs.protocol_id());
+
+        let mut g = ZeroizingEvalTable::copy_of(g_table);
+        let mut s = indicator_table.clone();
+        let two_inv = Scalar::from(2).invert().unwrap();
+        for _ in 0..var_num {
+            // g_j(0), g_j(1) and g_j(2), where the bound variable is lo + X·(hi - lo).
+            let mut evals = [Scalar::zero(); 3];
+            let ((g_lo, g_hi), (s_lo, s_hi)) = (g.split_halves(), s.split_halves());
+            for i in 0..g_lo.len() {
+                evals[0] += g_lo[i] * s_lo[i];
+                evals[1] += g_hi[i] * s_hi[i];
+                evals[2] += (g_hi[i].double() - g_lo[i]) * (s_hi[i].double() - s_lo[i]);
+            }
+            // the coeffs through (0, e_0), (1, e_1), (2, e_2).
+            let c_2 = (evals[2] - evals[1].double() + evals[0]) * two_inv;
+            let c_1 = evals[1] - evals[0] - c_2;
+            let g_j = Polynomial {
+                coeffs: vec![evals[0], c_1, c_2],
+            };
+
+            transcript.append(&poly_to_bytes(&g_j));
+            let r_j = transcript.challenge();
+            g.bind_first(r_j);
+            s.bind_first(r_j);
+            proofs.push_round(g_j);
+        }
+
+        proofs.final_eval = g.evals[0] * s.evals[0];
+        proofs
+    }
+}
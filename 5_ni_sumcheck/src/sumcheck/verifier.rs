@@ -1,113 +1,481 @@
 use crate::poly::univar_poly::Polynomial;
-use crate::sumcheck::Proofs;
+use crate::sumcheck::params::SumCheckParams;
+use crate::sumcheck::{DecodeError, SumCheckProof};
+use crate::trace::{TraceEvent, TraceSink};
 use crate::transcript::default::Keccak256Transcript;
-use crate::transcript::{poly_to_bytes, Transcript};
+use crate::transcript::Transcript;
+use alloc::vec;
+use alloc::vec::Vec;
 use bls12_381::Scalar;
-use ff::{Field, PrimeField};
+use core::fmt;
+use sumcheck::cost::CostReport;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyError {
+    WrongClaimedSum,
+    RoundCheckFailed {
+        round: usize,
+    },
+    FinalCheckFailed,
+    // the proof is in another convention, see `params`.
+    ConventionMismatch {
+        expected: SumCheckParams,
+        found: SumCheckParams,
+    },
+    // the proof is of an instance of another var_num, eg: P and V built for 5 and 6 variables.
+    VarNumMismatch {
+        proof: usize,
+        instance: usize,
+    },
+    // the proof has other than var_num rounds, or declared degrees.
+    RoundCountMismatch {
+        var_num: usize,
+        rounds: usize,
+    },
+    // g_j has other than d_j + 1 coeffs, where d_j is its declared degree.
+    RoundDegreeMismatch {
+        round: usize,
+        declared: usize,
+        len: usize,
+    },
+    // d_j is above the degree of round j in the schedule of V, see `DegreeSchedule`.
+    DegreeAboveSchedule {
+        round: usize,
+        declared: usize,
+        bound: usize,
+    },
+    // the schedule of V has other than var_num rounds.
+    ScheduleLengthMismatch {
+        var_num: usize,
+        rounds: usize,
+    },
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::WrongClaimedSum => write!(f, "Wrong claimed sum"),
+            VerifyError::RoundCheckFailed { round: 1 } => write!(f, "No-equal in round_1"),
+            VerifyError::RoundCheckFailed { round } => write!(f, "Not-equal in round_{}", round),
+            VerifyError::FinalCheckFailed => write!(f, "Verifier rejected the proof"),
+            VerifyError::ConventionMismatch { expected, found } => {
+                write!(f, "Expect the convention {:?}, got {:?}", expected, found)
+            }
+            VerifyError::VarNumMismatch { proof, instance } => write!(
+                f,
+                "The proof is of {} variables, the instance of {}",
+                proof, instance
+            ),
+            VerifyError::RoundCountMismatch { var_num, rounds } => {
+                write!(f, "Expect {} rounds, got {}", var_num, rounds)
+            }
+            VerifyError::RoundDegreeMismatch {
+                round,
+                declared,
+                len,
+            } => write!(
+                f,
+                "Round {} is declared of degree {}, but has {} coeffs",
+                round, declared, len
+            ),
+            VerifyError::DegreeAboveSchedule {
+                round,
+                declared,
+                bound,
+            } => write!(
+                f,
+                "Round {} is declared of degree {}, above {} of the schedule",
+                round, declared, bound
+            ),
+            VerifyError::ScheduleLengthMismatch { var_num, rounds } => write!(
+                f,
+                "The degree schedule has {} rounds, the instance {} variables",
+                rounds, var_num
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for VerifyError {}
+
+// Why the bytes of a proof are rejected by `verify_non_interactive`: they don't decode, or the
+// proof they decode to is rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BytesError {
+    Decode(DecodeError),
+    Verify(VerifyError),
+}
+
+impl From<DecodeError> for BytesError {
+    fn from(e: DecodeError) -> Self {
+        BytesError::Decode(e)
+    }
+}
+
+impl From<VerifyError> for BytesError {
+    fn from(e: VerifyError) -> Self {
+        BytesError::Verify(e)
+    }
+}
+
+impl fmt::Display for BytesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BytesError::Decode(e) => write!(f, "Malformed proof: {}", e),
+            BytesError::Verify(e) => write!(f, "Invalid proof: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BytesError {}
+
+// What V is left with after an accepted proof: P claims g(point) = value, which V checks with
+// its oracle, or hands to another protocol, eg: an opening of a commitment to g.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedClaim {
+    pub point: Vec<Scalar>, // (x_1, ..., x_v) in the order of the caller
+    pub value: Scalar,      // the final_eval of the proof
+}
 
 pub struct Verifier {
     // The C1
     statement: Scalar,
     v: usize,
-    challenges: Vec<usize>, // challenges: r1, r2, ..., rv. (In implement, r1 is a random usize, which is easy to construct a Field)
+    challenges: Vec<Scalar>, // challenges: r1, r2, ..., rv.
+    params: SumCheckParams,
 }
 
 impl Verifier {
     pub fn new(v: usize, statement: Scalar) -> Self {
+        Self::with_params(v, statement, SumCheckParams::default())
+    }
+
+    pub fn with_params(v: usize, statement: Scalar, params: SumCheckParams) -> Self {
         Self {
             statement,
             v,
             challenges: vec![],
+            params,
+        }
+    }
+
+    // The final point (x_1, ..., x_v) in the order of the caller, see `SumCheckParams::point`,
+    // available after the verification.
+    pub(crate) fn point(&self) -> Vec<Scalar> {
+        self.params.point(&self.challenges)
+    }
+
+    pub(crate) fn claim(&self, value: Scalar) -> VerifiedClaim {
+        VerifiedClaim {
+            point: self.point(),
+            value,
+        }
+    }
+
+    // Replay the transcript of the proof for the challenges only, none of the checks is done.
+    // The compressed g_j are rebuilt from the running claim as in `verify_with_transcript`.
+    pub(crate) fn derive_challenges(&mut self, proofs: &SumCheckProof) {
+        assert_eq!(proofs.num_rounds(), self.v);
+        self.challenges.clear();
+        let mut transcript = Keccak256Transcript::default();
+        transcript.bind_protocol(&proofs.protocol_id());
+        let mut claim = proofs.claimed_sum;
+        for j in 1..=self.v {
+            let g_j = Self::round_poly(proofs, j, claim, &mut ());
+            self.prepare_for_next_round(j, &g_j, &mut transcript, &mut ());
+            claim = g_j.evaluate(*self.challenges.last().unwrap());
         }
     }
-    fn prepare_for_next_round(
+
+    fn prepare_for_next_round<T: Transcript, S: TraceSink>(
         &mut self,
         j: usize,
         g_j: &Polynomial,
-        transcript: &mut Keccak256Transcript,
+        transcript: &mut T,
+        sink: &mut S,
     ) {
-        assert!(j >= 1);
-        assert_eq!(self.challenges.len(), j - 1);
+        debug_assert!(j >= 1);
+        debug_assert_eq!(self.challenges.len(), j - 1);
 
         // generate r1, r2, ..., rv
-        transcript.append(&poly_to_bytes(g_j));
-        self.challenges.push(transcript.challenge());
+        transcript.append_scalars(&g_j.coeffs);
+        let r_j = transcript.challenge();
+        // the 2 digests of `challenge_scalar`.
+        sink.count(CostReport::hashes(2));
+        sink.record(TraceEvent::ChallengeDerived {
+            name: "r",
+            round: j,
+            value: r_j,
+        });
+        self.challenges.push(r_j);
     }
 
-    pub fn verify(&mut self, proofs: Proofs) {
-        assert_eq!(proofs.g_i_vec.len(), self.v);
+    pub fn verify(&mut self, proofs: SumCheckProof) {
+        if let Err(e) = self.verify_with_sink(proofs, &mut ()) {
+            panic!("{}", e);
+        }
+        #[cfg(feature = "std")]
+        println!("Verifier accepted the proof");
+    }
 
+    // Each check and challenge is reported to the sink in order, it stops at the first failure.
+    pub fn verify_with_sink<S: TraceSink>(
+        &mut self,
+        proofs: SumCheckProof,
+        sink: &mut S,
+    ) -> Result<(), VerifyError> {
         let mut transcript = Keccak256Transcript::default();
+        self.verify_with_transcript(proofs, &mut transcript, sink)
+    }
+
+    // The same as `verify_with_sink`, but the challenges are derived from the given transcript,
+    // which may have absorbed other messages before, eg: the claims of a batch.
+    // The protocol of the proof is absorbed before its rounds, which is the first message of a
+    // fresh transcript, see `proof::protocol_id`. It's of the backend P proved with, eg:
+    // `PoseidonTranscript`, a proof of one backend is rejected by the other.
+    pub fn verify_with_transcript<T: Transcript, S: TraceSink>(
+        &mut self,
+        proofs: SumCheckProof,
+        transcript: &mut T,
+        sink: &mut S,
+    ) -> Result<(), VerifyError> {
+        // the challenges of a proof verified before, if the verifier is reused.
+        self.challenges.clear();
+        if !proofs.params.same_convention(&self.params) {
+            return Err(VerifyError::ConventionMismatch {
+                expected: self.params.clone(),
+                found: proofs.params,
+            });
+        }
+        if let Some(rounds) = self.params.degree_schedule.rounds() {
+            if rounds != self.v {
+                return Err(VerifyError::ScheduleLengthMismatch {
+                    var_num: self.v,
+                    rounds,
+                });
+            }
+        }
+        // the metadata of the instance, before any round.
+        if proofs.var_num as usize != self.v {
+            return Err(VerifyError::VarNumMismatch {
+                proof: proofs.var_num as usize,
+                instance: self.v,
+            });
+        }
+        let rounds = if proofs.num_rounds() != self.v {
+            proofs.num_rounds()
+        } else {
+            proofs.round_degrees.len()
+        };
+        if rounds != self.v {
+            return Err(VerifyError::RoundCountMismatch {
+                var_num: self.v,
+                rounds,
+            });
+        }
+        sink.count(CostReport::comparisons(1));
+        if proofs.claimed_sum != self.statement {
+            return Err(VerifyError::WrongClaimedSum);
+        }
+        // the params digest of the id.
+        sink.count(CostReport::hashes(1));
+        transcript.bind_protocol(&proofs.protocol_id());
+        // v = 0 has no round, the final check is g() = C1.
+        if self.v == 0 {
+            return self.check_constant(proofs.final_eval, sink);
+        }
 
-        let g_i_vec = proofs.g_i_vec;
+        // The compressed g_j is rebuilt from the claim of round j before it's checked and
+        // absorbed, so the challenges are the same as the uncompressed proof.
+        let mut g_i_vec = Vec::with_capacity(self.v);
 
         // round 1
+        g_i_vec.push(self.checked_round_poly(&proofs, 1, self.statement, sink)?);
         let g1 = &g_i_vec[0];
-        self.round_1(&g1);
-        self.prepare_for_next_round(1, &g1, &mut transcript);
+        self.round_1(g1, sink)?;
+        self.prepare_for_next_round(1, g1, transcript, sink);
 
         // round 2 - (v-1)
         for j in 2..self.v {
+            let claim = g_i_vec[j - 2].evaluate(*self.challenges.last().unwrap());
+            sink.count(CostReport::horner(g_i_vec[j - 2].coeffs.len()));
+            g_i_vec.push(self.checked_round_poly(&proofs, j, claim, sink)?);
             let g_j = &g_i_vec[j - 1];
             let g_j_minus_1 = &g_i_vec[j - 2];
-            self.check_round_j(j, g_j_minus_1, g_j);
+            self.check_round_j(j, g_j_minus_1, g_j, sink)?;
 
-            self.prepare_for_next_round(j, &g_j, &mut transcript);
+            self.prepare_for_next_round(j, g_j, transcript, sink);
         }
 
-        // round v
+        // round v, which is round 1 if v = 1.
+        if self.v > 1 {
+            let claim = g_i_vec[self.v - 2].evaluate(*self.challenges.last().unwrap());
+            sink.count(CostReport::horner(g_i_vec[self.v - 2].coeffs.len()));
+            g_i_vec.push(self.checked_round_poly(&proofs, self.v, claim, sink)?);
+            let g_v = &g_i_vec[self.v - 1];
+            let g_v_minus_1 = &g_i_vec[self.v - 2];
+            self.round_v(g_v_minus_1, g_v, sink)?;
+            // prepare r_v and store g_v.
+            self.prepare_for_next_round(self.v, g_v, transcript, sink);
+        }
         let g_v = &g_i_vec[self.v - 1];
-        let g_v_minus_1 = &g_i_vec[self.v - 2];
-        self.round_v(g_v_minus_1, g_v);
-        // prepare r_v and store g_v.
-        self.prepare_for_next_round(self.v, &g_v, &mut transcript);
 
         // finally check.
-        self.check(proofs.target, &g_v);
+        self.check(proofs.final_eval, g_v, sink)
+    }
+
+    // `round_poly`, which must have the d_j + 1 coeffs of its declared degree, eg: a degree-1
+    // g_j where a degree-2 one is declared is rejected before its sum is checked, and d_j must be
+    // in the schedule.
+    fn checked_round_poly<S: TraceSink>(
+        &self,
+        proofs: &SumCheckProof,
+        j: usize,
+        claim: Scalar,
+        sink: &mut S,
+    ) -> Result<Polynomial, VerifyError> {
+        let g_j = Self::round_poly(proofs, j, claim, sink);
+        let declared = proofs.round_degrees[j - 1] as usize;
+        if g_j.coeffs.len() != declared + 1 {
+            return Err(VerifyError::RoundDegreeMismatch {
+                round: j,
+                declared,
+                len: g_j.coeffs.len(),
+            });
+        }
+        let bound = self.params.degree_schedule.degree(j);
+        if declared > bound {
+            return Err(VerifyError::DegreeAboveSchedule {
+                round: j,
+                declared,
+                bound,
+            });
+        }
+        Ok(g_j)
+    }
+
+    // g_j as sent, or rebuilt from the claim g_j-1(r_j-1) if it's compressed.
+    fn round_poly<S: TraceSink>(
+        proofs: &SumCheckProof,
+        j: usize,
+        claim: Scalar,
+        sink: &mut S,
+    ) -> Polynomial {
+        if proofs.params.compressed {
+            let round = &proofs.compressed_rounds[j - 1];
+            // claim / 2 of a constant g_j, or claim - g_j(1) and the interpolation of the evals.
+            sink.count(match round.evals.len() {
+                0 => CostReport::inversions(1) + CostReport::mults(1),
+                n => CostReport::adds(1) + CostReport::interpolate(n + 1),
+            });
+            round.decompress(claim)
+        } else {
+            proofs.rounds[j - 1].clone()
+        }
     }
 
     // Check: C1 = g_1(0) + g_1(1)
-    fn round_1(&mut self, g1: &Polynomial) {
+    fn round_1<S: TraceSink>(&mut self, g1: &Polynomial, sink: &mut S) -> Result<(), VerifyError> {
         let actual = g1.evaluate(Scalar::one()) + g1.evaluate(Scalar::zero());
+        let horner = CostReport::horner(g1.coeffs.len());
+        sink.count(horner.times(2) + CostReport::adds(1) + CostReport::comparisons(1));
 
-        assert_eq!(actual, self.statement, "No-equal in round_1");
+        let ok = actual == self.statement;
+        sink.record(TraceEvent::RoundChecked {
+            round: 1,
+            lhs: actual,
+            rhs: self.statement,
+            ok,
+        });
+        ok.then_some(()).ok_or(VerifyError::RoundCheckFailed { round: 1 })
     }
 
     // Check: gv−1 (rv−1 ) = gv (0) + gv (1).
-    fn round_v(&mut self, g_v_minus_1: &Polynomial, g_v: &Polynomial) {
-        self.check_round_j(self.v, g_v_minus_1, g_v);
+    fn round_v<S: TraceSink>(
+        &mut self,
+        g_v_minus_1: &Polynomial,
+        g_v: &Polynomial,
+        sink: &mut S,
+    ) -> Result<(), VerifyError> {
+        self.check_round_j(self.v, g_v_minus_1, g_v, sink)
     }
 
     // 1 < j < v, total v-2 rounds
     // check: g_j-1(r_j-1) = g_j(0) + g_j(1)
-    fn check_round_j(&mut self, j: usize, g_j_minus_1: &Polynomial, g_j: &Polynomial) {
-        assert_eq!(
+    fn check_round_j<S: TraceSink>(
+        &mut self,
+        j: usize,
+        g_j_minus_1: &Polynomial,
+        g_j: &Polynomial,
+        sink: &mut S,
+    ) -> Result<(), VerifyError> {
+        debug_assert_eq!(
             j - 1,
             self.challenges.len(),
             "length of challenges != (j-1)"
         );
 
         // r_j-1
-        let r_j_minus_1 = self.challenges.last().unwrap().clone() as u128;
+        let r_j_minus_1 = *self.challenges.last().unwrap();
         // g_j(0) + g_j(1)
         let actual = g_j.evaluate(Scalar::zero()) + g_j.evaluate(Scalar::one());
-        let target = g_j_minus_1.evaluate(Scalar::from_u128(r_j_minus_1));
+        let target = g_j_minus_1.evaluate(r_j_minus_1);
+        let horner = CostReport::horner(g_j.coeffs.len()).times(2)
+            + CostReport::horner(g_j_minus_1.coeffs.len());
+        sink.count(horner + CostReport::adds(1) + CostReport::comparisons(1));
 
-        assert_eq!(actual, target, "Not-equal in round_{}", j);
+        let ok = actual == target;
+        sink.record(TraceEvent::RoundChecked {
+            round: j,
+            lhs: actual,
+            rhs: target,
+            ok,
+        });
+        ok.then_some(()).ok_or(VerifyError::RoundCheckFailed { round: j })
     }
 
     // 3. check: gv(rv) = g(r1 , . . . , rv )
     // target = g(r1 , . . . , rv )
-    fn check(&self, target: Scalar, g_v: &Polynomial) {
-        assert_eq!(
+    fn check<S: TraceSink>(
+        &self,
+        target: Scalar,
+        g_v: &Polynomial,
+        sink: &mut S,
+    ) -> Result<(), VerifyError> {
+        debug_assert_eq!(
             self.v,
             self.challenges.len(),
             "length of challenges != (j-1)"
         );
-        let r_v = self.challenges.last().unwrap().clone() as u128;
-        let actual = g_v.evaluate(Scalar::from_u128(r_v));
+        let r_v = *self.challenges.last().unwrap();
+        let actual = g_v.evaluate(r_v);
+        sink.count(CostReport::horner(g_v.coeffs.len()) + CostReport::comparisons(1));
 
-        assert_eq!(actual, target, "Verifier rejected the proof");
-        println!("Verifier accepted the proof");
+        let ok = actual == target;
+        sink.record(TraceEvent::FinalChecked {
+            lhs: actual,
+            rhs: target,
+            ok,
+        });
+        ok.then_some(()).ok_or(VerifyError::FinalCheckFailed)
+    }
+
+    // 3. with v = 0: g is the constant g(), so check: C1 = g()
+    // target = g()
+    fn check_constant<S: TraceSink>(
+        &self,
+        target: Scalar,
+        sink: &mut S,
+    ) -> Result<(), VerifyError> {
+        sink.count(CostReport::comparisons(1));
+        let ok = self.statement == target;
+        sink.record(TraceEvent::FinalChecked {
+            lhs: self.statement,
+            rhs: target,
+            ok,
+        });
+        ok.then_some(()).ok_or(VerifyError::FinalCheckFailed)
     }
 }
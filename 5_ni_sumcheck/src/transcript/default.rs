@@ -1,32 +1,58 @@
 use crate::transcript::Transcript;
+use alloc::vec::Vec;
 use bls12_381::Scalar;
-use ff::PrimeField;
-use sha3::{Digest, Keccak256};
-use std::net::UdpSocket;
+use sumcheck::rlc::CoeffSource;
+use Fiat_Shamir::algebraic::AlgebraicSponge;
+use Fiat_Shamir::sponge::{Keccak256Sponge, Sponge};
+use Fiat_Shamir::Transcript as _;
 
-pub struct Keccak256Transcript {
-    hasher: Keccak256,
+// The squeeze and the map into a scalar are the ones of `Fiat_Shamir`, see `challenge_to_scalar`.
+// The backend is the sponge of the inner transcript, Keccak256 unless told otherwise.
+#[derive(Default)]
+pub struct SpongeTranscript<S: Sponge = Keccak256Sponge> {
+    inner: Fiat_Shamir::default::SpongeTranscript<S>,
 }
 
-impl Transcript for Keccak256Transcript {
+pub type Keccak256Transcript = SpongeTranscript<Keccak256Sponge>;
+
+// NOT for real proofs, see `Fiat_Shamir::algebraic`.
+pub type PoseidonTranscript = SpongeTranscript<AlgebraicSponge>;
+
+impl<S: Sponge> Transcript for SpongeTranscript<S> {
     fn append(&mut self, new_data: &[u8]) {
-        self.hasher.update(&mut new_data.to_owned());
+        self.inner.append(new_data);
+    }
+
+    fn append_scalars(&mut self, scalars: &[Scalar]) {
+        self.inner.append_scalars(scalars);
     }
 
-    fn challenge(&mut self) -> usize {
-        let mut result_hash = [0_u8; 32];
-        result_hash.copy_from_slice(&self.hasher.finalize_reset());
-        result_hash.reverse();
-        self.hasher.update(result_hash);
-        let sum = result_hash.to_vec().iter().map(|&b| b as usize).sum();
-        sum
+    fn challenge(&mut self) -> Scalar {
+        self.inner.challenge_scalar()
     }
 }
 
-impl Default for Keccak256Transcript {
-    fn default() -> Self {
+// The coeffs of `sumcheck::rlc` are the squeezes of the inner transcript.
+impl<S: Sponge> CoeffSource for SpongeTranscript<S> {
+    fn squeeze_scalar(&mut self) -> Scalar {
+        self.challenge()
+    }
+
+    fn squeeze_vector(&mut self, label: &str, n: usize) -> Vec<Scalar> {
+        self.inner.challenge_vector(label, n)
+    }
+}
+
+// See `Fiat_Shamir::default::Keccak256Transcript::snapshot`, it's specific to this transcript,
+// eg: for a `ProverSession` to be resumed.
+impl Keccak256Transcript {
+    pub fn snapshot(&self) -> Vec<u8> {
+        self.inner.snapshot()
+    }
+
+    pub fn restore(snapshot: &[u8]) -> Self {
         Self {
-            hasher: Keccak256::new(),
+            inner: Fiat_Shamir::default::Keccak256Transcript::restore(snapshot),
         }
     }
 }
@@ -37,6 +63,7 @@ mod tests {
     use bls12_381::Scalar;
     use ff::Field;
     use rand_core::OsRng;
+    use Fiat_Shamir::codec::encode_scalar;
 
     #[test]
     fn test() {
@@ -45,14 +72,14 @@ mod tests {
         let s_two = Scalar::random(rng);
 
         let mut transcript1 = Keccak256Transcript::default();
-        transcript1.append(&s_one.to_bytes());
-        transcript1.append(&s_two.to_bytes());
+        transcript1.append(&encode_scalar(&s_one));
+        transcript1.append(&encode_scalar(&s_two));
 
         let challenge_1 = transcript1.challenge();
 
         let mut transcript2 = Keccak256Transcript::default();
-        transcript2.append(&s_one.to_bytes());
-        transcript2.append(&s_two.to_bytes());
+        transcript2.append(&encode_scalar(&s_one));
+        transcript2.append(&encode_scalar(&s_two));
 
         let challenge_2 = transcript2.challenge();
 
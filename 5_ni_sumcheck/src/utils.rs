@@ -1,4 +1,7 @@
+use alloc::vec;
+use alloc::vec::Vec;
 use bls12_381::Scalar;
+pub use sumcheck::utils::{eq_table, expand_factor_for_mpoly};
 
 // convert a num into its binary form
 // eg: 8 -> 1000, will output [1, 0, 0, 0]
@@ -11,6 +14,14 @@ pub fn convert_to_binary(bit_len: &usize, num: usize) -> Vec<usize> {
         .collect::<Vec<usize>>()
 }
 
+// The same as `convert_to_binary`, but the bits are scalars, eg: a point of the hypercube.
+pub fn binary_point(bit_len: &usize, num: usize) -> Vec<Scalar> {
+    convert_to_binary(bit_len, num)
+        .into_iter()
+        .map(|b| Scalar::from(b as u64))
+        .collect()
+}
+
 // convert a num into its binary form
 // eg: 8 -> 1000, will output [1, 0, 0, 0]
 pub fn convert_from_binary(num: &Vec<usize>) -> usize {
@@ -33,34 +44,15 @@ fn expand_factor_for_upoly(lhs: Vec<Scalar>, rhs: Vec<Scalar>) -> Vec<Scalar> {
     product
 }
 
-// try to expand factorization form to coeffs form for multi-variables poly
-// For now, we'll only support two factorizations to a coeffs.
-// eg: f(x1,x2) = (1+x1) * (1−x2) = 1 + x1 - x2 - x1x2
-//
-// NOTE!!! Only support var_num=2, which mul form such as: (a+bx1) * (c+dx2) = ac + bc*x1 + ad*x2 + bd*x1*x2
-pub fn expand_factor_for_mpoly(var_num: usize, lhs: Vec<Scalar>, rhs: Vec<Scalar>) -> Vec<Scalar> {
-    let target_len = 1 << var_num;
-    assert_eq!(target_len, lhs.len());
-    assert_eq!(target_len, rhs.len());
-
-    let mut product = vec![Scalar::zero(); target_len];
-
-    for (n, l) in lhs.iter().enumerate() {
-        for (m, r) in rhs.iter().enumerate() {
-            product[n | m] += l * r;
-        }
-    }
-    product
-}
-
 #[cfg(test)]
 mod test {
     use crate::utils::{
-        convert_from_binary, convert_to_binary, expand_factor_for_mpoly, expand_factor_for_upoly,
+        convert_from_binary, convert_to_binary, eq_table, expand_factor_for_mpoly,
+        expand_factor_for_upoly,
     };
     use bls12_381::Scalar;
     use ff::PrimeField;
-    use rayon::scope;
+    use sumcheck::poly::multivar_poly::NotMultilinear;
 
     #[test]
     fn test_expand_factor_for_upoly() {
@@ -101,11 +93,41 @@ mod test {
             Scalar::one().neg(),
         ];
 
-        let actual = expand_factor_for_mpoly(var_num, poly_one, poly_two);
+        let actual = expand_factor_for_mpoly(var_num, poly_one, poly_two).unwrap();
         assert_eq!(target, actual);
         println!("{:?}", actual);
     }
 
+    #[test]
+    fn test_expand_factor_for_mpoly_overlapping() {
+        // (1+x1) * (2-x1) would need x1^2.
+        let poly_one = vec![Scalar::one(), Scalar::zero(), Scalar::one(), Scalar::zero()];
+        let poly_two = vec![
+            Scalar::from_u128(2),
+            Scalar::zero(),
+            Scalar::one().neg(),
+            Scalar::zero(),
+        ];
+        assert_eq!(
+            expand_factor_for_mpoly(2, poly_one, poly_two),
+            Err(NotMultilinear { var: 0 })
+        );
+    }
+
+    #[test]
+    fn test_eq_table() {
+        let r = vec![Scalar::from_u128(3), Scalar::from_u128(5)];
+        let one = Scalar::one();
+        let target = vec![
+            (one - r[0]) * (one - r[1]),
+            (one - r[0]) * r[1],
+            r[0] * (one - r[1]),
+            r[0] * r[1],
+        ];
+        assert_eq!(eq_table(&r), target);
+        assert_eq!(eq_table::<Scalar>(&[]), vec![one]);
+    }
+
     #[test]
     fn test_convert() {
         let raw: Vec<usize> = vec![1, 2, 3, 4, 8, 17];
@@ -1,12 +1,24 @@
-use crate::merkle_tree::proof::Proof;
+//! The merkle tree commitment, see `merkle_tree::MerkleTree`, and the demo of a prover
+//! convincing a verifier that a char is in its values.
+//!
+//! The `merkle_tree` builds without std, the demo and the random helpers need the `std` feature.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+#[cfg(all(test, feature = "std"))]
 use crate::prover::Prover;
+#[cfg(all(test, feature = "std"))]
 use crate::verifier::Verifier;
 
 pub mod merkle_tree;
+#[cfg(feature = "std")]
 pub mod prover;
 pub mod utils;
+#[cfg(feature = "std")]
 pub mod verifier;
 
+#[cfg(feature = "std")]
 #[test]
 fn test_merkle_tree_commit() {
     let k = 3;
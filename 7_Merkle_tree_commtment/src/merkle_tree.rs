@@ -1,32 +1,86 @@
 pub mod hasher;
+pub mod leaf;
+pub mod multi;
 pub mod node;
 pub mod proof;
+pub mod stream;
 
-use crate::merkle_tree::hasher::{calculate_hash, calculate_parent_hash};
+use crate::merkle_tree::hasher::{hash_node, hash_root, Digest};
+use crate::merkle_tree::leaf::Leaf;
 use crate::merkle_tree::node::TreeNode;
-use crate::merkle_tree::proof::Proof;
+use crate::merkle_tree::proof::{MerkleProof, Scratch};
+pub use crate::merkle_tree::multi::{verify_multi, MultiError, MultiVerifier};
 use crate::utils::convert_to_binary;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
 use ark_std::log2;
-use std::cmp::Ordering;
+use bls12_381::Scalar;
+use core::fmt;
+use core::marker::PhantomData;
+
+// Why a proof is rejected, see `MerkleTree::check`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MerkleError {
+    // the proof is of another root.
+    RootMismatch,
+    // the index has more bits than the path has levels.
+    IndexOutOfRange { index: usize, levels: usize },
+    // the proof is of another leaf than the one asked for, eg: by a query of the LDT.
+    IndexMismatch { expected: usize, found: usize },
+    // the path of the leaf hashes to another root.
+    PathMismatch { index: usize },
+    // the path is of a tree above the max height, see `proof::MAX_HEIGHT`.
+    HeightTooLarge { height: usize, max: usize },
+}
+
+impl fmt::Display for MerkleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MerkleError::RootMismatch => write!(f, "The proof is of another root"),
+            MerkleError::IndexOutOfRange { index, levels } => {
+                write!(f, "Leaf {} is out of a path of {} levels", index, levels)
+            }
+            MerkleError::IndexMismatch { expected, found } => {
+                write!(f, "Opened leaf {}, expected {}", found, expected)
+            }
+            MerkleError::PathMismatch { index } => {
+                write!(f, "The path of leaf {} doesn't hash to the root", index)
+            }
+            MerkleError::HeightTooLarge { height, max } => {
+                write!(
+                    f,
+                    "The path of height {} is above the max height {}",
+                    height, max
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MerkleError {}
 
 // A Merkle tree is a binary tree, with values of type `T` at the leafs,
 // and where every internal node holds the hash of the concatenation of the hashes of its children nodes.
+// The leaf, node and root hashes are domain separated, see `hasher`.
 // Note: For convinence, we suppose Merkle tree is a ![complete binary tree](https://www.geeksforgeeks.org/types-of-binary-tree/?ref=lbp)
 //      Degree: 2
 //      Leaf nodes: if tree height is h, so the number of leaf nodes will be `2^(h-1)`
 //      Total nodes: A tree of height h has total nodes = 2^h–1
 //      Height of tree: If tree has N nodes, the hight `h=log(N+1)–1=Θ(ln(n))`. From root to leaf: [1,h].
+// The tree is typed by the value `V` of the leafs, eg: the codewords of the LDT are scalars.
 #[derive(Clone, Debug)]
-pub struct MerkleTree {
+pub struct MerkleTree<V: Leaf = Scalar> {
     root: TreeNode, // The root of the inner binary tree
     height: usize,  // The height of the tree
+    _leaf: PhantomData<V>,
 }
 
-impl MerkleTree {
+impl<V: Leaf> MerkleTree<V> {
     // init and commit
     // Constructs a Merkle Tree from a vector of data.
-    // Root = hash_util(left.hash + right.hash)
-    pub fn init(values: Vec<char>) -> Self {
+    // Node = hash_node(left.hash, right.hash), Root = hash_root(height, top node)
+    pub fn commit(values: &[V]) -> Self {
         assert!(
             !values.is_empty(),
             "Can't initial MerkleTree from empty vector"
@@ -35,60 +89,105 @@ impl MerkleTree {
         let height: usize = 1 + log2(leaves_num) as usize;
         assert_eq!(1 << (height - 1), leaves_num, "It's not a perfect tree");
 
-        // lowest level
+        // lowest level, the leaves are hashed from the values, see `Leaf::leaf_hash`.
         let leaves_nodes = values
             .iter()
-            .map(|v| TreeNode::new_leaf(*v))
+            .map(|v| TreeNode::new(v.leaf_hash(), v.to_leaf_bytes()))
             .collect::<Vec<TreeNode>>();
 
-        // construct tree by leaves.
+        // construct tree by leaves, the children are moved into their parent, not cloned.
         let mut cur = leaves_nodes;
         for i in 0..(height - 1) {
-            let cur_len = cur.len();
-            let parant = (0..(cur_len / 2))
-                .map(|j| {
-                    let left = cur.get(2 * j).unwrap();
-                    let right = cur.get(2 * j + 1).unwrap();
-                    let parent_hash = calculate_parent_hash(left.get_hash(), right.get_hash());
-
-                    TreeNode::Node {
-                        hash: parent_hash,
-                        left: Box::new(left.clone()),
-                        right: Box::new(right.clone()),
-                    }
-                })
-                .collect::<Vec<TreeNode>>();
+            let mut children = cur.into_iter();
+            let mut parant = Vec::with_capacity(children.len() / 2);
+            while let (Some(left), Some(right)) = (children.next(), children.next()) {
+                let parent_hash = hash_node(&left.get_hash(), &right.get_hash());
+
+                parant.push(TreeNode::Node {
+                    hash: parent_hash,
+                    left: Box::new(left),
+                    right: Box::new(right),
+                });
+            }
             cur = parant;
         }
         assert_eq!(cur.len(), 1);
 
         let root = cur.remove(0);
 
-        MerkleTree { root, height }
+        MerkleTree {
+            root,
+            height,
+            _leaf: PhantomData,
+        }
+    }
+
+    // equal the commit, by open it by index of values.
+    pub fn open_by_index(&self, index: usize) -> MerkleProof<V> {
+        // index belong [0, leaves_num).
+        assert!(index < self.leaves_num(), "Wrong leaf index");
+
+        let path_len = self.height - 1;
+        // get leaf-root path,
+        // Suppose the left child is 0, the right child is 1, so the path can be indexed as binary form with (height-1) bits.
+        // eg: tree height is 3, which has total 2^2 leaves, the leave can ben indexed as (00, 01, 10, 11).
+        // a. turn the index into binary form with (height-1) bits.
+        // The binary form is MSB first, which is exactly the path from root to leaf.
+        let path = convert_to_binary(&path_len, index);
+
+        // b. according the path, we can found out the MerkleProof of the indexed leaf, which just need to collect the bro-node.
+        //    We'll collect the bro-node by the path. Collect the left child is 1, the right child is 0.
+
+        let mut values = Vec::with_capacity(self.height);
+        let root_hash = self.root_hash();
+
+        let mut cur_node = &self.root;
+
+        // for now the hash values are collected from root to leaf.
+        for p in path {
+            // let p = path.get(path_len - i).unwrap();
+
+            match cur_node {
+                TreeNode::Leaf { .. } => panic!("Never reach leaf"),
+                TreeNode::Node { left, right, .. } => {
+                    // collect the right as bro-node.
+                    if p == 0 {
+                        values.push(right.get_hash());
+                        cur_node = left.as_ref();
+                    } else {
+                        values.push(left.get_hash());
+                        cur_node = right.as_ref();
+                    }
+                }
+            }
+        }
+
+        // reverse the hash values to make sure it's from leaf to root
+        values.reverse();
+
+        MerkleProof::new(values, root_hash, index)
     }
 
-    // commit and open.
-    pub fn commit(&self, x: &char) -> Proof {
+    // open.
+    // The challenge maybe not in values, so return None.
+    pub fn open(&self, challenge: &V) -> Option<MerkleProof<V>> {
         let mut values = Vec::with_capacity(self.height - 1);
-        let root_hash = self.root.get_hash();
-        Self::dfs(&self.root, &x, &mut values);
-        Proof {
-            root: root_hash,
-            children: values,
+        let mut index = 0;
+        let root_hash = self.root_hash();
+        let target = challenge.to_leaf_bytes();
+        if !Self::dfs(&self.root, &target, &mut values, &mut index) {
+            return None;
         }
+        Some(MerkleProof::new(values, root_hash, index))
     }
 
-    fn dfs(root: &TreeNode, target: &char, res: &mut Vec<u64>) -> bool {
+    // The bro-nodes are collected from leaf to root, and the bit of the index in each level,
+    // 1 if the target is in the right child.
+    fn dfs(root: &TreeNode, target: &[u8], res: &mut Vec<Digest>, index: &mut usize) -> bool {
         match root {
-            TreeNode::Leaf { hash, value } => {
-                if value == target {
-                    true
-                } else {
-                    false
-                }
-            }
-            TreeNode::Node { hash, left, right } => {
-                let l = Self::dfs(left, target, res);
+            TreeNode::Leaf { value, .. } => value == target,
+            TreeNode::Node { left, right, .. } => {
+                let l = Self::dfs(left, target, res, index);
                 // if left meet target.
                 if l {
                     res.push(right.get_hash());
@@ -96,8 +195,9 @@ impl MerkleTree {
                 }
 
                 // if right meet target.
-                let r = Self::dfs(right, target, res);
+                let r = Self::dfs(right, target, res, index);
                 if r {
+                    *index |= 1 << res.len();
                     res.push(left.get_hash());
                 }
                 r
@@ -105,9 +205,9 @@ impl MerkleTree {
         }
     }
 
-    // Returns the root hash of Merkle tree
-    pub fn root_hash(&self) -> u64 {
-        self.root.get_hash()
+    // Returns the root hash of Merkle tree, which commits to the height.
+    pub fn root_hash(&self) -> Digest {
+        hash_root(self.height, &self.root.get_hash())
     }
 
     // Returns the height of Merkle tree
@@ -117,112 +217,432 @@ impl MerkleTree {
 
     // Leaf nodes: if tree height is h, so the number of leaf nodes will be `2^h`
     pub fn leaves_num(&self) -> usize {
-        2 ^ (self.height - 1)
+        1 << (self.height - 1)
     }
 
     // Total nodes: A tree of height h has total nodes = 2^(h+1)–1
     pub fn nodes_num(&self) -> usize {
         2 ^ self.height - 1
     }
+
+    // The only check of a proof, for the tree of any value.
+    // The proof is of the root, the path is hashed from the bytes of the leaf to the top,
+    // the bit i of the index is the side of the node in level i, and the root is derived with
+    // the height of the path, children.len() + 1.
+    pub fn verify(root: &Digest, proof: &MerkleProof<V>, leaf: &[u8]) -> bool {
+        Self::check(root, proof, leaf).is_ok()
+    }
+
+    // The same as `verify`, but returns why the proof is rejected.
+    pub fn check(root: &Digest, proof: &MerkleProof<V>, leaf: &[u8]) -> Result<(), MerkleError> {
+        if proof.root != *root {
+            return Err(MerkleError::RootMismatch);
+        }
+        MerkleProof::<V>::check_in_place(root, proof.index, leaf, &proof.children)
+    }
+
+    // The proofs of the values against the same root, eg: the coset of a query of the LDT.
+    // A scratch of its own for the batch, see `verify_batch_with_scratch` to reuse one.
+    pub fn verify_batch(root: &Digest, proofs: &[MerkleProof<V>], values: &[V]) -> bool {
+        Self::verify_batch_with_scratch(&mut Scratch::new(), root, proofs, values)
+    }
+
+    // The same as `verify_batch`, the leaf bytes are written into the scratch, so the batches
+    // verified with the same one allocate nothing after the first.
+    pub fn verify_batch_with_scratch(
+        scratch: &mut Scratch,
+        root: &Digest,
+        proofs: &[MerkleProof<V>],
+        values: &[V],
+    ) -> bool {
+        Self::check_batch_with_scratch(scratch, root, proofs, values).is_ok()
+    }
+
+    // The same as `verify_batch_with_scratch`, but returns why the first rejected proof is.
+    pub fn check_batch_with_scratch(
+        scratch: &mut Scratch,
+        root: &Digest,
+        proofs: &[MerkleProof<V>],
+        values: &[V],
+    ) -> Result<(), MerkleError> {
+        assert_eq!(proofs.len(), values.len(), "a value per proof");
+        for (proof, value) in proofs.iter().zip(values.iter()) {
+            if proof.root != *root {
+                return Err(MerkleError::RootMismatch);
+            }
+            let leaf = scratch.leaf_bytes(value);
+            MerkleProof::<V>::check_in_place(root, proof.index, leaf, &proof.children)?;
+        }
+        Ok(())
+    }
+
+    // equal the commit, by open it by index of values.
+    pub fn verify_by_index(&self, index: usize, proof: &MerkleProof<V>) -> bool {
+        // index belong [0, leaves_num).
+        assert!(index < self.leaves_num(), "Wrong leaf index");
+        if proof.index != index {
+            return false;
+        }
+
+        let path_len = self.height - 1;
+        // 1. get leaf-root path,
+        // Suppose the left child is 0, the right child is 1, so the path can be indexed as binary form with (height-1) bits.
+        // eg: tree height is 3, which has total 2^2 leaves, the leave can ben indexed as (00, 01, 10, 11).
+        // a. turn the index into binary form with (height-1) bits.
+        // The binary form is MSB first, which is exactly the path from root to leaf.
+        let path = convert_to_binary(&path_len, index);
+
+        // b. found out the target left.
+        let mut cur_node = &self.root;
+        for p in path {
+            if let TreeNode::Node { left, right, .. } = cur_node {
+                if p == 0 {
+                    cur_node = left.as_ref();
+                } else {
+                    cur_node = right.as_ref();
+                }
+            }
+        }
+        match cur_node {
+            TreeNode::Leaf { value, .. } => Self::verify(&self.root_hash(), proof, value),
+            TreeNode::Node { .. } => panic!("Never reach node"),
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::merkle_tree::proof::Proof;
-    use crate::merkle_tree::MerkleTree;
-    use crate::prover::Prover;
-    use crate::utils::random_chars;
-    use crate::verifier::Verifier;
+    use crate::merkle_tree::hasher::{hash_leaf, hash_node, Keccak256Hash, ScalarHash};
+    use crate::merkle_tree::leaf::Leaf;
+    use crate::merkle_tree::proof::MerkleProof;
+    use crate::merkle_tree::{MerkleError, MerkleTree};
+    use crate::utils::{random_chars, random_scalars};
+    use bls12_381::Scalar;
+    use ff::PrimeField;
 
     #[test]
     fn test_init_merkle_tree() {
-        let chars = random_chars(3);
-        println!("chars:{:?}", chars);
-        let merkle = MerkleTree::init(chars);
-        println!("merkle tree: {:?}", merkle);
+        let values = random_scalars(3);
+        println!("values:{:?}", values);
+        let merkle_tree = MerkleTree::commit(&values);
+        println!("merkle tree: {:?}", merkle_tree);
     }
 
     #[test]
-    fn test_commit() {
-        let chars = vec!['W', '8', 'E', 'X', 'D', '8', 'R', '3'];
-        let challenge = 'W';
-        let merkle_tree = MerkleTree::init(chars.clone());
-        {
-            // MerkleTree {
-            //     root: Node {
-            //         hash: 2997809638824881102,
-            //         left: Node {
-            //             hash: 13957922012229917015,
-            //             left: Node {
-            //                 hash: 10153464161223545464,
-            //                 left: Leaf {
-            //                     hash: 5949921715258702887,
-            //                     value: 'W',
-            //                 },
-            //                 right: Leaf {
-            //                     hash: 3209422213365730399,
-            //                     value: '8',
-            //                 },
-            //             },
-            //             right: Node {
-            //                 hash: 10895954492970826136,
-            //                 left: Leaf {
-            //                     hash: 15042720617947887434,
-            //                     value: 'E',
-            //                 },
-            //                 right: Leaf {
-            //                     hash: 15818208776807171099,
-            //                     value: 'X',
-            //                 },
-            //             },
-            //         },
-            //         right: Node {
-            //             hash: 14010322267561343302,
-            //             left: Node {
-            //                 hash: 2385074442875957999,
-            //                 left: Leaf {
-            //                     hash: 6796667025961897532,
-            //                     value: 'D',
-            //                 },
-            //                 right: Leaf {
-            //                     hash: 3209422213365730399,
-            //                     value: '8',
-            //                 },
-            //             },
-            //             right: Node {
-            //                 hash: 168356471189691628,
-            //                 left: Leaf {
-            //                     hash: 5573041882718737857,
-            //                     value: 'R',
-            //                 },
-            //                 right: Leaf {
-            //                     hash: 15080230035883566959,
-            //                     value: '3',
-            //                 },
-            //             },
-            //         },
-            //     },
-            //     height: 3,
-            // }
-            // println!("merkle_tree:{:#?}", merkle_tree);
-        }
-        let target = Proof {
-            root: 2997809638824881102,
-            children: vec![
-                3209422213365730399,  // '8'
-                10895954492970826136, // right
-                14010322267561343302, // right
-            ],
-        };
-
-        let prover = Prover {
-            values: chars,
-            merkle_tree: merkle_tree,
-        };
-        let (has, actual) = prover.has_x(&challenge);
-        if has {
-            let actual = actual.unwrap();
-            assert_eq!(target, actual);
+    fn test_commit_and_verify() {
+        let coeffs = vec![
+            Scalar::one(),
+            Scalar::from_u128(12),
+            Scalar::one(),
+            Scalar::from_u128(13),
+        ];
+        let merkle_tree = MerkleTree::commit(&coeffs);
+        // println!("merkle tree: {:?}", merkle_tree);
+
+        // MerkleTree {
+        //     root: Node {
+        //         hash: 0x683ebedd200d20faf088eb54da23a68b41176ee5d61ead944b72eb82e3c66a39,
+        //         left: Node {
+        //             hash: 0x3820b0267e47e841b7c34d3fc3f8daf3099c0f2de945077e6e8b3bb3cb428d5a,
+        //             left: Leaf {
+        //                 hash: 0x1fb18d7f0125be772d83b6a15bb5be2662bc833388d3fa4d13dce59e85b76e85,
+        //                 value: 0x0000000000000000000000000000000000000000000000000000000000000001
+        //             },
+        //             right: Leaf {
+        //                 hash: 0x11a3ee60769a684dc2a0b3467877815adb1840f04ba9ad1292fe2893156b94ce,
+        //                 value: 0x000000000000000000000000000000000000000000000000000000000000000c
+        //             }
+        //         },
+        //         right: Node {
+        //             hash: 0x09e2e4794034e8afe5f4cc75b124e2635ef1fe3106ba968f9dc48416c04661ab,
+        //             left: Leaf {
+        //                 hash: 0x1fb18d7f0125be772d83b6a15bb5be2662bc833388d3fa4d13dce59e85b76e85,
+        //                 value: 0x0000000000000000000000000000000000000000000000000000000000000001
+        //             },
+        //             right: Leaf {
+        //                 hash: 0x2487c300249e1ff6bd12b4bb4b19ab4f38488e6bb79c0e6b080fe45a726d516d,
+        //                 value: 0x000000000000000000000000000000000000000000000000000000000000000d
+        //             }
+        //         }
+        //     },
+        //     height: 3
+        // }
+        let challenge = Scalar::one();
+        // MerkleProof {
+        // 	children: [
+        //      0x11a3ee60769a684dc2a0b3467877815adb1840f04ba9ad1292fe2893156b94ce,
+        //      0x09e2e4794034e8afe5f4cc75b124e2635ef1fe3106ba968f9dc48416c04661ab
+        // ],
+        // 	root: 0x685eeca445877f3547b111e2cd95db97e0f0fe4ee5381d91ac26e62b108a63dd,
+        // 	index: 0
+        // }
+        let proof = merkle_tree.open(&challenge).unwrap();
+        println!("{:?}", proof);
+        // correct
+        let root = merkle_tree.root_hash();
+        assert!(MerkleTree::verify(
+            &root,
+            &proof,
+            &challenge.to_leaf_bytes()
+        ));
+
+        // not in the values.
+        assert_eq!(merkle_tree.open(&Scalar::from_u128(2)), None);
+    }
+
+    #[test]
+    fn test_commit_and_verify_by_index() {
+        let coeffs = vec![
+            Scalar::one(),
+            Scalar::from_u128(12),
+            Scalar::zero(),
+            Scalar::from_u128(13),
+        ];
+        let merkle_tree = MerkleTree::commit(&coeffs);
+        println!("merkle tree: {:?}", merkle_tree);
+
+        // merkle tree: MerkleTree {
+        //     root: Node {
+        //         hash: 0x4acbb45e562fdea21fd32adb1a72f6c019e4d6923506c554e8dea91586fdce72,
+        //         left: Node {
+        //             hash: 0x3820b0267e47e841b7c34d3fc3f8daf3099c0f2de945077e6e8b3bb3cb428d5a,
+        //             left: Leaf {
+        //                 hash: 0x1fb18d7f0125be772d83b6a15bb5be2662bc833388d3fa4d13dce59e85b76e85,
+        //                 value: 0x0000000000000000000000000000000000000000000000000000000000000001
+        //             },
+        //             right: Leaf {
+        //                 hash: 0x11a3ee60769a684dc2a0b3467877815adb1840f04ba9ad1292fe2893156b94ce,
+        //                 value: 0x000000000000000000000000000000000000000000000000000000000000000c
+        //             }
+        //         },
+        //         right: Node {
+        //             hash: 0x5ba526904cf7f4274e19a5c9cfd9535713d54a93d76d5f3e8b8a47f778972ea0,
+        //             left: Leaf {
+        //                 hash: 0x02cf75e0a0e05c8ff5a55f62a86acf890d6f3cf17e37a95c9ce1dc6746fb6d24,
+        //                 value: 0x0000000000000000000000000000000000000000000000000000000000000000
+        //             },
+        //             right: Leaf {
+        //                 hash: 0x2487c300249e1ff6bd12b4bb4b19ab4f38488e6bb79c0e6b080fe45a726d516d,
+        //                 value: 0x000000000000000000000000000000000000000000000000000000000000000d
+        //             }
+        //         }
+        //     },
+        //     height: 3
+        // }
+        // let challenge = (OsRng.next_u32() % 4) as usize;
+        let challenge = 3;
+        println!("{challenge}");
+        // [1, 1]
+        // value: 0x3820b0267e47e841b7c34d3fc3f8daf3099c0f2de945077e6e8b3bb3cb428d5a
+        // value: 0x02cf75e0a0e05c8ff5a55f62a86acf890d6f3cf17e37a95c9ce1dc6746fb6d24
+        // MerkleProof {
+        //     children: [
+        //          0x02cf75e0a0e05c8ff5a55f62a86acf890d6f3cf17e37a95c9ce1dc6746fb6d24,
+        //          0x3820b0267e47e841b7c34d3fc3f8daf3099c0f2de945077e6e8b3bb3cb428d5a
+        //      ],
+        //     root: 0x21ecc7445268e22442a5935024de2c15cc178cda7a8eea041e0ecc6fd1676042,
+        //     index: 3
+        // }
+        let proof = merkle_tree.open_by_index(challenge);
+        println!("{:?}", proof);
+        // correct
+        assert!(merkle_tree.verify_by_index(challenge, &proof));
+        assert!(!merkle_tree.verify_by_index(2, &proof));
+    }
+
+    #[test]
+    fn test_open_every_index() {
+        let values = random_scalars(3);
+        let merkle_tree = MerkleTree::commit(&values);
+        let root = merkle_tree.root_hash();
+        for (index, value) in values.iter().enumerate() {
+            let proof = merkle_tree.open_by_index(index);
+            assert!(MerkleTree::verify(&root, &proof, &value.to_leaf_bytes()));
+            assert!(merkle_tree.verify_by_index(index, &proof));
+        }
+    }
+
+    #[test]
+    fn test_open_chars() {
+        let values = random_chars(3);
+        let merkle_tree = MerkleTree::commit(&values);
+        let root = merkle_tree.root_hash();
+        for (index, value) in values.iter().enumerate() {
+            let proof = merkle_tree.open(value).unwrap();
+            // the first one of the same chars.
+            assert_eq!(values[proof.index], *value);
+            assert!(proof.index <= index);
+            assert!(MerkleTree::verify(&root, &proof, &value.to_leaf_bytes()));
+        }
+    }
+
+    #[test]
+    fn test_reject_other_root() {
+        let values = random_scalars(3);
+        let merkle_tree = MerkleTree::commit(&values);
+        let proof = merkle_tree.open_by_index(1);
+        let other = MerkleTree::commit(&random_scalars(3)).root_hash();
+        assert!(!MerkleTree::verify(
+            &other,
+            &proof,
+            &values[1].to_leaf_bytes()
+        ));
+
+        // a proof without children is not a bypass, but a tree of height 1.
+        let empty = MerkleProof::<Scalar>::new(vec![], proof.root, 0);
+        let root = merkle_tree.root_hash();
+        assert!(!MerkleTree::verify(
+            &root,
+            &empty,
+            &values[1].to_leaf_bytes()
+        ));
+    }
+
+    #[test]
+    fn test_check_errors() {
+        let values = random_scalars(3);
+        let merkle_tree = MerkleTree::commit(&values);
+        let root = merkle_tree.root_hash();
+        let proof = merkle_tree.open_by_index(5);
+        let leaf = values[5].to_leaf_bytes();
+        assert_eq!(MerkleTree::check(&root, &proof, &leaf), Ok(()));
+
+        let other = MerkleTree::commit(&random_scalars(3)).root_hash();
+        assert_eq!(
+            MerkleTree::check(&other, &proof, &leaf),
+            Err(MerkleError::RootMismatch)
+        );
+        assert_eq!(
+            MerkleTree::check(&root, &proof, &values[4].to_leaf_bytes()),
+            Err(MerkleError::PathMismatch { index: 5 })
+        );
+        let far = MerkleProof::<Scalar>::new(proof.children.clone(), proof.root, 8);
+        assert_eq!(
+            MerkleTree::check(&root, &far, &leaf),
+            Err(MerkleError::IndexOutOfRange {
+                index: 8,
+                levels: 3
+            })
+        );
+        assert_eq!(
+            MerkleError::PathMismatch { index: 5 }.to_string(),
+            "The path of leaf 5 doesn't hash to the root"
+        );
+    }
+
+    // The hashes before the domain separation: leaf = H(value), node = H(left + right).
+    fn old_root(values: &[Scalar]) -> Scalar {
+        let mut cur = values.iter().map(Keccak256Hash::hash).collect::<Vec<_>>();
+        while cur.len() > 1 {
+            cur = cur
+                .chunks(2)
+                .map(|pair| Keccak256Hash::hash(&pair[0].add(&pair[1])))
+                .collect();
+        }
+        cur[0]
+    }
+
+    fn old_check(value: &Scalar, children: &[Scalar], root: &Scalar) -> bool {
+        let actual = children
+            .iter()
+            .fold(Keccak256Hash::hash(value), |acc, bro| {
+                Keccak256Hash::hash(&acc.add(bro))
+            });
+        &actual == root
+    }
+
+    #[test]
+    fn test_root_test_vector() {
+        // leaf: H(0x00 || v), node: H(0x01 || l || r), root: H(0x02 || 3u64 || top)
+        let values = [1, 12, 0, 13].map(Scalar::from_u128);
+        let merkle_tree = MerkleTree::commit(&values);
+        assert_eq!(
+            format!("{:?}", merkle_tree.root_hash()),
+            "0x21ecc7445268e22442a5935024de2c15cc178cda7a8eea041e0ecc6fd1676042"
+        );
+    }
+
+    #[test]
+    fn test_leaf_hash() {
+        // the scalars are hashed from their repr, which is the same as from the bytes.
+        for value in random_scalars(3) {
+            assert_eq!(value.leaf_hash(), hash_leaf(&value.to_leaf_bytes()));
+        }
+        for value in random_chars(3) {
+            assert_eq!(value.leaf_hash(), hash_leaf(&value.to_leaf_bytes()));
+        }
+    }
+
+    #[test]
+    fn test_node_as_leaf() {
+        let values = random_scalars(3);
+        let merkle_tree = MerkleTree::commit(&values);
+        let proof = merkle_tree.open_by_index(5);
+        let left = hash_leaf(&values[4].to_leaf_bytes());
+        let right = hash_leaf(&values[5].to_leaf_bytes());
+
+        // the node of leaves 4 and 5 opened as the leaf 2 of a tree with height 3.
+        let node_proof = MerkleProof::<Scalar>::new(proof.children[1..].to_vec(), proof.root, 2);
+        for fake in [left.add(&right), hash_node(&left, &right)] {
+            assert!(!MerkleTree::verify(
+                &proof.root,
+                &node_proof,
+                &fake.to_leaf_bytes()
+            ));
+        }
+
+        // which was accepted before, as a node is H(left + right) and a leaf is H(value).
+        let old = values.iter().map(Keccak256Hash::hash).collect::<Vec<_>>();
+        let old_node = |l: &Scalar, r: &Scalar| Keccak256Hash::hash(&l.add(r));
+        let n_67 = old_node(&old[6], &old[7]);
+        let n_0123 = old_node(&old_node(&old[0], &old[1]), &old_node(&old[2], &old[3]));
+        let fake = old[4].add(&old[5]);
+        assert!(old_check(&fake, &[n_67, n_0123], &old_root(&values)));
+    }
+
+    #[test]
+    fn test_reject_old_format() {
+        let values = random_scalars(2);
+        let merkle_tree = MerkleTree::commit(&values);
+        let root = old_root(&values);
+        assert_ne!(merkle_tree.root_hash(), root);
+
+        // the old proof of the leaf 0, against the old root and the new one.
+        let old = values.iter().map(Keccak256Hash::hash).collect::<Vec<_>>();
+        let children = vec![old[1], Keccak256Hash::hash(&old[2].add(&old[3]))];
+        assert!(old_check(&values[0], &children, &root));
+        for root in [root, merkle_tree.root_hash()] {
+            let proof = MerkleProof::<Scalar>::new(children.clone(), root, 0);
+            assert!(!MerkleTree::verify(
+                &root,
+                &proof,
+                &values[0].to_leaf_bytes()
+            ));
+        }
+    }
+
+    #[test]
+    fn test_reject_wrong_height_and_index() {
+        let values = random_scalars(3);
+        let merkle_tree = MerkleTree::commit(&values);
+        let proof = merkle_tree.open_by_index(6);
+        let (root, leaf) = (merkle_tree.root_hash(), values[6].to_leaf_bytes());
+        assert!(MerkleTree::verify(&root, &proof, &leaf));
+
+        // the same top node, but as a tree of another height.
+        let mut longer = proof.clone();
+        longer.children.push(Scalar::zero());
+        let mut shorter = proof.clone();
+        shorter.children.pop();
+        shorter.index = 2;
+        // the value on another leaf, or an index out of the tree.
+        let mut moved = proof.clone();
+        moved.index = 7;
+        let mut out_of_range = proof.clone();
+        out_of_range.index = 6 + 8;
+        for wrong in [longer, shorter, moved, out_of_range] {
+            assert!(!MerkleTree::verify(&root, &wrong, &leaf));
         }
     }
 }
+//
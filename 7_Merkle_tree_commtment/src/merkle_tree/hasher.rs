@@ -1,32 +1,119 @@
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
+use bls12_381::Scalar;
+use core::marker::PhantomData;
+use ff::PrimeField;
+use sha3::{Digest as _, Keccak256};
 
-/// calculate the hash of the data
-pub fn calculate_hash<T: Hash>(t: &T) -> u64 {
-    let mut s = DefaultHasher::new();
-    t.hash(&mut s);
-    s.finish()
+// abstraction to set the hash function used
+pub trait ScalarHash<F: PrimeField>: Clone {
+    fn hash(inputs: &F) -> F;
+    fn hashes(inputs: &[F]) -> F;
 }
 
-pub fn calculate_parent_hash(left: u64, right: u64) -> u64 {
-    let mut sum: u128 = (left / 2 + right / 2) as u128;
-    let mut s = DefaultHasher::new();
-    sum.hash(&mut s);
-    s.finish()
+#[derive(Clone, Copy, Debug)]
+pub struct Keccak256Hash<F: PrimeField> {
+    _marker: PhantomData<F>,
+}
+
+impl ScalarHash<Scalar> for Keccak256Hash<Scalar> {
+    // same as calculate_hash, this is for Scalar
+    fn hash(input: &Scalar) -> Scalar {
+        // hash
+        let mut h = Keccak256::new();
+        h.update(input.to_repr().as_ref());
+
+        // let r = h.finalize().as_slice();
+        let slice: [u8; 32] = h.finalize().as_slice().try_into().unwrap();
+        // get_scalar
+        let bytes = [slice, slice].concat().as_slice().try_into().unwrap();
+        Scalar::from_bytes_wide(&bytes)
+    }
+
+    // same as calculate_parent_hash, this is for Scalar
+    fn hashes(inputs: &[Scalar]) -> Scalar {
+        // hash
+        let mut h = Keccak256::new();
+        for x in inputs {
+            h.update(x.to_repr().as_ref());
+        }
+
+        // let r = h.finalize().as_slice();
+        let slice: [u8; 32] = h.finalize().as_slice().try_into().unwrap();
+        // get_scalar
+        let bytes = [slice, slice].concat().as_slice().try_into().unwrap();
+        Scalar::from_bytes_wide(&bytes)
+    }
+}
+
+// The hashes of the tree are scalars, eg: the roots are absorbed by the transcripts as scalars.
+pub type Digest = Scalar;
+
+// The hashes of the tree are domain separated by a prefix byte, so a node can't be opened as a
+// leaf, eg: a "leaf" with the bytes of the two children of a node.
+//      leaf: H(0x00 || the bytes of the value), see `Leaf`
+//      node: H(0x01 || left || right)
+//      root: H(0x02 || height || top), height as u64 little-endian
+// The root commits to the height, so the path of a leaf can't be checked as a shorter or longer
+// one against the same root. All scalars are 32 bytes of `to_repr`.
+pub const LEAF_PREFIX: u8 = 0x00;
+pub const NODE_PREFIX: u8 = 0x01;
+pub const ROOT_PREFIX: u8 = 0x02;
+
+fn prefixed_hash(prefix: u8, inputs: &[&[u8]]) -> Digest {
+    let mut h = Keccak256::new();
+    h.update([prefix]);
+    for x in inputs {
+        h.update(x);
+    }
+    // the 32 bytes twice, in an array, so a path is checked without allocations.
+    let mut bytes = [0u8; 64];
+    bytes[..32].copy_from_slice(h.finalize().as_slice());
+    bytes.copy_within(..32, 32);
+    Scalar::from_bytes_wide(&bytes)
+}
+
+// The bytes of the leaf, see `Leaf::to_leaf_bytes`.
+pub fn hash_leaf(leaf: &[u8]) -> Digest {
+    prefixed_hash(LEAF_PREFIX, &[leaf])
+}
+
+// The same as `hash_leaf` of the concatenated parts, which are hashed one by one.
+pub fn hash_leaf_parts(parts: &[&[u8]]) -> Digest {
+    prefixed_hash(LEAF_PREFIX, parts)
+}
+
+pub fn hash_node(left: &Digest, right: &Digest) -> Digest {
+    prefixed_hash(
+        NODE_PREFIX,
+        &[left.to_repr().as_ref(), right.to_repr().as_ref()],
+    )
+}
+
+pub fn hash_root(height: usize, top: &Digest) -> Digest {
+    prefixed_hash(
+        ROOT_PREFIX,
+        &[&(height as u64).to_le_bytes(), top.to_repr().as_ref()],
+    )
 }
 
 #[cfg(test)]
 mod test {
-    use crate::merkle_tree::hasher::calculate_parent_hash;
-    use rand_core::{OsRng, RngCore};
+    use crate::merkle_tree::hasher::{Keccak256Hash, ScalarHash};
+    use bls12_381::Scalar;
+    use ff::{Field, PrimeField};
+    use rand_core::OsRng;
 
     #[test]
-    fn test_calculate_parent_hash() {
-        let rng = &mut OsRng;
-        let left = rng.next_u64();
-        let right = rng.next_u64();
+    fn test_calculate_parent_hash_with_scalar() {
+        let left = Scalar::random(&mut OsRng);
+        let right = Scalar::random(&mut OsRng);
+
+        let parent = Keccak256Hash::hash(&left.add(&right));
+        println!("{:?}", parent);
+
+        let left = Scalar::from_u128(10);
+        let right = Scalar::from_u128(12);
 
-        let parent = calculate_parent_hash(left, right);
+        let parent = Keccak256Hash::hashes(&[left, right]);
         println!("{:?}", parent);
     }
 }
@@ -0,0 +1,68 @@
+use crate::merkle_tree::hasher::{hash_leaf, hash_leaf_parts, Digest};
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use core::fmt::Debug;
+use ff::PrimeField;
+
+// The encoding of a leaf value, which is hashed by `hash_leaf`.
+// The trees and the proofs are typed by the value, so a proof of a tree of chars can't be taken
+// for the one of scalars, even if the bytes of the leaf are the same.
+pub trait Leaf: Clone + Debug + PartialEq + Eq {
+    fn to_leaf_bytes(&self) -> Vec<u8>;
+
+    // hash_leaf(to_leaf_bytes), the values may hash their bytes by parts instead, so no Vec is
+    // built for each leaf, eg: the scalars of a 2^20 codeword.
+    fn leaf_hash(&self) -> Digest {
+        hash_leaf(&self.to_leaf_bytes())
+    }
+
+    // Appends to_leaf_bytes to the buffer, the values may write them without a Vec of their own,
+    // eg: into the `Scratch` of a batch.
+    fn write_leaf_bytes(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_leaf_bytes());
+    }
+}
+
+// eg: the codewords of the LDT, 32 bytes of `to_repr`.
+impl Leaf for Scalar {
+    fn to_leaf_bytes(&self) -> Vec<u8> {
+        self.to_repr().as_ref().to_vec()
+    }
+
+    fn leaf_hash(&self) -> Digest {
+        hash_leaf_parts(&[self.to_repr().as_ref()])
+    }
+
+    fn write_leaf_bytes(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self.to_repr().as_ref());
+    }
+}
+
+// The 32 bytes of each scalar in order, eg: a row of a committed matrix, or a column of the
+// encoded rows of `mlpc`. The length isn't in the bytes, the caller binds it along with the root.
+impl Leaf for Vec<Scalar> {
+    fn to_leaf_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(32 * self.len());
+        self.write_leaf_bytes(&mut bytes);
+        bytes
+    }
+
+    fn write_leaf_bytes(&self, out: &mut Vec<u8>) {
+        for s in self.iter() {
+            out.extend_from_slice(s.to_repr().as_ref());
+        }
+    }
+}
+
+// The utf-8 bytes, 1 to 4 of them.
+impl Leaf for char {
+    fn to_leaf_bytes(&self) -> Vec<u8> {
+        let mut bytes = [0u8; 4];
+        self.encode_utf8(&mut bytes).as_bytes().to_vec()
+    }
+
+    fn write_leaf_bytes(&self, out: &mut Vec<u8>) {
+        let mut bytes = [0u8; 4];
+        out.extend_from_slice(self.encode_utf8(&mut bytes).as_bytes());
+    }
+}
@@ -0,0 +1,395 @@
+// The openings of several trees checked as one batch, eg: the cosets of the LDT queries, one
+// root per round. A claim is (root, index, leaf bytes, proof), and it's accepted iff
+// `MerkleTree::check` accepts it with the proof of the index asked for.
+//
+// The claims of the same root and height are a group, and each node a claim has verified is kept
+// with its level and position in the group, the siblings of its path included. A path stops as
+// soon as it reaches a kept node, eg: the opening of leaf 5 next to the one of leaf 4 is the hash
+// of its leaf only, as the sibling of leaf 4 was leaf 5. Any other node there is rejected, it
+// can't hash to the root as the kept one does without a collision.
+//
+// The claims are checked in order, and only the nodes of the accepted ones are kept, so the first
+// rejected claim is the first one `MerkleTree::check` rejects, see `MultiError`.
+use crate::merkle_tree::hasher::{hash_leaf, hash_node, hash_root, Digest};
+use crate::merkle_tree::leaf::Leaf;
+use crate::merkle_tree::proof::{MerkleProof, MAX_HEIGHT};
+use crate::merkle_tree::MerkleError;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use core::fmt;
+use core::ops::Range;
+
+// The claim at `claim` in the batch is rejected, the claims before it are accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MultiError {
+    pub claim: usize,
+    pub source: MerkleError,
+}
+
+impl fmt::Display for MultiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Claim {} of the batch is rejected", self.claim)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MultiError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+// Check the claims as one batch, see `MultiVerifier` to add the values of the leaves instead.
+pub fn verify_multi<V: Leaf>(
+    claims: &[(Digest, usize, &[u8], &MerkleProof<V>)],
+) -> Result<(), MultiError> {
+    let claims = claims
+        .iter()
+        .map(|(root, index, leaf, proof)| (root, *index, *leaf, *proof));
+    check_claims(claims).0
+}
+
+// The claims of a batch, the leaves are written by `Leaf`, so they are encoded the same way as
+// the committed ones, eg: `add(root, 5, &value, &proof)` for the value of leaf 5.
+#[derive(Clone, Debug)]
+pub struct MultiVerifier<'a, V: Leaf = Scalar> {
+    claims: Vec<(Digest, usize, Range<usize>, &'a MerkleProof<V>)>,
+    // the bytes of all the leaves, each claim has its range.
+    leaves: Vec<u8>,
+}
+
+impl<V: Leaf> Default for MultiVerifier<'_, V> {
+    fn default() -> Self {
+        Self {
+            claims: Vec::new(),
+            leaves: Vec::new(),
+        }
+    }
+}
+
+impl<'a, V: Leaf> MultiVerifier<'a, V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(
+        &mut self,
+        root: Digest,
+        index: usize,
+        value: &V,
+        proof: &'a MerkleProof<V>,
+    ) -> &mut Self {
+        let start = self.leaves.len();
+        value.write_leaf_bytes(&mut self.leaves);
+        let range = start..self.leaves.len();
+        self.claims.push((root, index, range, proof));
+        self
+    }
+
+    // The same as `add`, for the bytes of the leaf as they are.
+    pub fn add_bytes(
+        &mut self,
+        root: Digest,
+        index: usize,
+        leaf: &[u8],
+        proof: &'a MerkleProof<V>,
+    ) -> &mut Self {
+        let start = self.leaves.len();
+        self.leaves.extend_from_slice(leaf);
+        let range = start..self.leaves.len();
+        self.claims.push((root, index, range, proof));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.claims.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.claims.is_empty()
+    }
+
+    pub fn check(&self) -> Result<(), MultiError> {
+        self.check_counted().0
+    }
+
+    // The same as `check`, and the hashes it took, eg: for the cost of V.
+    pub fn check_counted(&self) -> (Result<(), MultiError>, usize) {
+        let claims = self
+            .claims
+            .iter()
+            .map(|(root, index, range, proof)| (root, *index, &self.leaves[range.clone()], *proof));
+        check_claims(claims)
+    }
+}
+
+// (group, level, position) of a node, the leaves are level 0.
+type NodeKey = (usize, usize, usize);
+
+fn check_claims<'b, V: Leaf + 'b>(
+    claims: impl Iterator<Item = (&'b Digest, usize, &'b [u8], &'b MerkleProof<V>)>,
+) -> (Result<(), MultiError>, usize) {
+    // (root, levels) of each group.
+    let mut groups: Vec<(Digest, usize)> = Vec::new();
+    let mut verified: BTreeMap<NodeKey, Digest> = BTreeMap::new();
+    let mut path = Vec::new();
+    let mut hashes = 0;
+    for (claim, (root, index, leaf, proof)) in claims.enumerate() {
+        let key = (*root, proof.children.len());
+        let group = match groups.iter().position(|g| *g == key) {
+            Some(group) => group,
+            None => {
+                groups.push(key);
+                groups.len() - 1
+            }
+        };
+        path.clear();
+        match check_claim(root, index, leaf, proof, group, &verified, &mut path) {
+            Ok(n) => hashes += n,
+            Err((n, source)) => return (Err(MultiError { claim, source }), hashes + n),
+        }
+        verified.extend(path.drain(..));
+    }
+    (Ok(()), hashes)
+}
+
+// The check of one claim against the nodes kept so far, the nodes of its path are pushed to
+// `path`, to be kept once it's accepted. Return the hashes it took, on a failure too.
+fn check_claim<V: Leaf>(
+    root: &Digest,
+    index: usize,
+    leaf: &[u8],
+    proof: &MerkleProof<V>,
+    group: usize,
+    verified: &BTreeMap<NodeKey, Digest>,
+    path: &mut Vec<(NodeKey, Digest)>,
+) -> Result<usize, (usize, MerkleError)> {
+    if proof.index != index {
+        let found = proof.index;
+        return Err((
+            0,
+            MerkleError::IndexMismatch {
+                expected: index,
+                found,
+            },
+        ));
+    }
+    if proof.root != *root {
+        return Err((0, MerkleError::RootMismatch));
+    }
+    let levels = proof.children.len();
+    if levels >= MAX_HEIGHT {
+        let height = levels + 1;
+        return Err((
+            0,
+            MerkleError::HeightTooLarge {
+                height,
+                max: MAX_HEIGHT,
+            },
+        ));
+    }
+    if index.checked_shr(levels as u32).unwrap_or(0) != 0 {
+        return Err((0, MerkleError::IndexOutOfRange { index, levels }));
+    }
+
+    // whether the node is the kept one, or None if there is none there.
+    let known = |level: usize, position: usize, node: &Digest| {
+        verified
+            .get(&(group, level, position))
+            .map(|kept| kept == node)
+    };
+    let mut node = hash_leaf(leaf);
+    let mut hashes = 1;
+    for (level, sibling) in proof.children.iter().enumerate() {
+        let position = index >> level;
+        match known(level, position, &node) {
+            Some(true) => return Ok(hashes),
+            Some(false) => return Err((hashes, MerkleError::PathMismatch { index })),
+            None => {}
+        }
+        if known(level, position ^ 1, sibling) == Some(false) {
+            return Err((hashes, MerkleError::PathMismatch { index }));
+        }
+        path.push(((group, level, position), node));
+        path.push(((group, level, position ^ 1), *sibling));
+        node = if position & 1 == 0 {
+            hash_node(&node, sibling)
+        } else {
+            hash_node(sibling, &node)
+        };
+        hashes += 1;
+    }
+    match known(levels, 0, &node) {
+        Some(true) => return Ok(hashes),
+        Some(false) => return Err((hashes, MerkleError::PathMismatch { index })),
+        None => {}
+    }
+    hashes += 1;
+    if *root != hash_root(levels + 1, &node) {
+        return Err((hashes, MerkleError::PathMismatch { index }));
+    }
+    path.push(((group, levels, 0), node));
+    Ok(hashes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::merkle_tree::MerkleTree;
+    use crate::utils::{random_chars, random_scalars};
+    use ff::PrimeField;
+
+    // The claims of the leaves at the indices, each one with its own proof.
+    fn open<V: Leaf>(tree: &MerkleTree<V>, indices: &[usize]) -> Vec<MerkleProof<V>> {
+        indices.iter().map(|i| tree.open_by_index(*i)).collect()
+    }
+
+    // Each claim checked alone, the first rejected one.
+    fn check_each<V: Leaf>(
+        claims: &[(Digest, usize, &[u8], &MerkleProof<V>)],
+    ) -> Result<(), MultiError> {
+        for (claim, (root, index, leaf, proof)) in claims.iter().enumerate() {
+            let res = match proof.index == *index {
+                true => MerkleTree::check(root, proof, leaf),
+                false => Err(MerkleError::IndexMismatch {
+                    expected: *index,
+                    found: proof.index,
+                }),
+            };
+            res.map_err(|source| MultiError { claim, source })?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_same_as_each_claim() {
+        // the test vector of `test_root_test_vector`, and random trees of two heights.
+        let vector = [1, 12, 0, 13].map(Scalar::from_u128).to_vec();
+        let trees = [vector, random_scalars(3), random_scalars(5)]
+            .map(|values| (MerkleTree::commit(&values), values));
+        let mut claims = vec![];
+        for (tree, values) in trees.iter() {
+            let indices = (0..values.len()).step_by(3).collect::<Vec<_>>();
+            for (index, proof) in indices.iter().zip(open(tree, &indices)) {
+                claims.push((tree.root_hash(), *index, values[*index].to_repr(), proof));
+            }
+        }
+        let as_claims = |claims: &[(Digest, usize, [u8; 32], MerkleProof)]| {
+            let claims = claims
+                .iter()
+                .map(|(root, index, leaf, proof)| (*root, *index, &leaf[..], proof))
+                .collect::<Vec<_>>();
+            (verify_multi(&claims), check_each(&claims))
+        };
+        let (multi, each) = as_claims(&claims);
+        assert_eq!(multi, Ok(()));
+        assert_eq!(each, Ok(()));
+
+        // another leaf, index, root or height in any claim.
+        for c in 0..claims.len() {
+            let mut leaf = claims.clone();
+            leaf[c].2[0] ^= 1;
+            let mut moved = claims.clone();
+            moved[c].1 ^= 1;
+            moved[c].3.index ^= 1;
+            let mut root = claims.clone();
+            root[c].0 = hash_leaf(b"other");
+            let mut shorter = claims.clone();
+            shorter[c].3.children.pop();
+            shorter[c].1 &= (1 << shorter[c].3.children.len()) - 1;
+            shorter[c].3.index = shorter[c].1;
+            for cases in [leaf, moved, root, shorter] {
+                let (multi, each) = as_claims(&cases);
+                assert!(multi.is_err());
+                assert_eq!(multi, each, "claim {}", c);
+            }
+        }
+    }
+
+    #[test]
+    fn test_adjacent_leaves_share_the_path() {
+        let values = random_scalars(4);
+        let tree = MerkleTree::commit(&values);
+        let root = tree.root_hash();
+        let proofs = open(&tree, &[4, 5, 4]);
+        let mut batch = MultiVerifier::new();
+        for (index, proof) in [4, 5, 4].iter().zip(proofs.iter()) {
+            batch.add(root, *index, &values[*index], proof);
+        }
+        // 4 levels: the leaf, 4 nodes and the root for leaf 4, then the leaf only for leaf 5,
+        // whose hash is the sibling of leaf 4, and for leaf 4 again.
+        let (res, hashes) = batch.check_counted();
+        assert_eq!(res, Ok(()));
+        assert_eq!(hashes, (1 + 4 + 1) + 1 + 1);
+
+        // the leaves of the two halves share the top only.
+        let proofs = open(&tree, &[1, 9]);
+        let mut batch = MultiVerifier::new();
+        batch.add(root, 1, &values[1], &proofs[0]);
+        batch.add(root, 9, &values[9], &proofs[1]);
+        assert_eq!(batch.check_counted(), (Ok(()), (1 + 4 + 1) + (1 + 3)));
+    }
+
+    #[test]
+    fn test_first_failure() {
+        let values = random_scalars(4);
+        let tree = MerkleTree::commit(&values);
+        let root = tree.root_hash();
+        let indices = [0, 3, 6, 7, 8, 11, 14, 15];
+        let proofs = open(&tree, &indices);
+        // the 5th claim opens leaf 8 with the value of leaf 9.
+        let mut batch = MultiVerifier::new();
+        for (c, (index, proof)) in indices.iter().zip(proofs.iter()).enumerate() {
+            let value = if c == 4 { &values[9] } else { &values[*index] };
+            batch.add(root, *index, value, proof);
+        }
+        let err = batch.check().unwrap_err();
+        assert_eq!(
+            err,
+            MultiError {
+                claim: 4,
+                source: MerkleError::PathMismatch { index: 8 }
+            }
+        );
+        assert_eq!(err.to_string(), "Claim 4 of the batch is rejected");
+        #[cfg(feature = "std")]
+        {
+            use std::error::Error;
+            assert_eq!(
+                err.source().unwrap().to_string(),
+                "The path of leaf 8 doesn't hash to the root"
+            );
+        }
+    }
+
+    #[test]
+    fn test_empty_and_single() {
+        let batch = MultiVerifier::<Scalar>::new();
+        assert!(batch.is_empty());
+        assert_eq!(batch.check_counted(), (Ok(()), 0));
+        assert_eq!(verify_multi::<Scalar>(&[]), Ok(()));
+
+        // a single claim is `MerkleTree::check`, of any leaf type.
+        let values = random_chars(3);
+        let tree = MerkleTree::commit(&values);
+        let proof = tree.open_by_index(2);
+        let leaf = values[2].to_leaf_bytes();
+        let mut batch = MultiVerifier::new();
+        batch.add(tree.root_hash(), 2, &values[2], &proof);
+        assert_eq!(batch.len(), 1);
+        assert_eq!(batch.check_counted(), (Ok(()), 3 + 2));
+        let other = values[3].to_leaf_bytes();
+        assert_eq!(
+            verify_multi(&[(tree.root_hash(), 2, &other[..], &proof)]),
+            Err(MultiError {
+                claim: 0,
+                source: MerkleError::PathMismatch { index: 2 }
+            })
+        );
+        assert_eq!(
+            verify_multi(&[(tree.root_hash(), 2, &leaf[..], &proof)]),
+            Ok(())
+        );
+    }
+}
@@ -1,15 +1,16 @@
-use crate::merkle_tree::hasher::calculate_hash;
-use std::hash::Hash;
+use crate::merkle_tree::hasher::{hash_leaf, Digest};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
 
 /// Node of a Binary Tree.
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Debug, Eq)]
 pub enum TreeNode {
     Leaf {
-        hash: u64,   // Hash of the node
-        value: char, // Value of the leaf node
+        hash: Digest,   // Hash of the node
+        value: Vec<u8>, // Value of the leaf node, see `Leaf::to_leaf_bytes`
     },
     Node {
-        hash: u64,            // Hash of the node
+        hash: Digest,         // Hash of the node
         left: Box<TreeNode>,  // Left child of the node
         right: Box<TreeNode>, // Right chiild of the node
     },
@@ -17,21 +18,44 @@ pub enum TreeNode {
 
 impl TreeNode {
     /// Create a new Node
-    pub fn new(hash: u64, value: char) -> Self {
+    pub fn new(hash: Digest, value: Vec<u8>) -> Self {
         Self::Leaf { hash, value }
     }
 
     // Create a new leaf
-    pub fn new_leaf(value: char) -> TreeNode {
-        let hash = calculate_hash(&value);
+    pub fn new_leaf(value: Vec<u8>) -> TreeNode {
+        let hash = hash_leaf(&value);
         Self::new(hash, value)
     }
 
     // Returns a hash from the Node.
-    pub fn get_hash(&self) -> u64 {
+    pub fn get_hash(&self) -> Digest {
         match self {
             &Self::Leaf { hash, .. } => hash,
             &Self::Node { hash, .. } => hash,
         }
     }
 }
+
+impl PartialEq<Self> for TreeNode {
+    fn eq(&self, other: &Self) -> bool {
+        match self {
+            TreeNode::Node { hash, left, right } => {
+                let (hash1, left1, right1) = (hash, left, right);
+                match other {
+                    TreeNode::Node { hash, left, right } => {
+                        hash1 == hash && left1 == left && right1 == right
+                    }
+                    _ => false,
+                }
+            }
+            TreeNode::Leaf { hash, value } => {
+                let (hash1, value1) = (hash, value);
+                match other {
+                    TreeNode::Leaf { hash, value } => hash1 == hash && value1 == value,
+                    _ => false,
+                }
+            }
+        }
+    }
+}
@@ -1,10 +1,286 @@
+use crate::merkle_tree::hasher::{hash_leaf, hash_node, hash_root, Digest};
+use crate::merkle_tree::leaf::Leaf;
+use crate::merkle_tree::MerkleError;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use core::marker::PhantomData;
+
+// The max height of a tree whose paths are checked in place, the root included, eg: 2^39 leaves.
+// A taller path is rejected before it's hashed, see `MerkleProof::check_in_place`.
+pub const MAX_HEIGHT: usize = 40;
+
 // Proof is a tree, only contain the hash values from target leaf to root with related brather-nodes.
 // Meanwhile, half of the tree can be calculated by the known leaf value..
 // So according the Figure 7.1(from zkbook), it's quite easy to find that just need to return the hasher from
 // brather-nodes(each layer has only one!), the left infos will be calculated by verifier.
 // And totally needs h hash values.
+// It's typed by the leaf value V of the tree, see `Leaf`.
 #[derive(Clone, Eq, PartialEq, Debug)]
-pub struct Proof {
-    pub children: Vec<u64>, // the children from left to root. aka evals
-    pub root: u64,          // root hash. aka cm
+pub struct MerkleProof<V: Leaf = Scalar> {
+    pub children: Vec<Digest>, // the children from left to root. aka evals
+    pub root: Digest,          // root hash. aka cm
+    pub index: usize,          // the leaf index, bit i is 1 if the node in level i is a right child
+    _leaf: PhantomData<V>,
+}
+
+impl<V: Leaf> MerkleProof<V> {
+    pub fn new(children: Vec<Digest>, root: Digest, index: usize) -> Self {
+        Self {
+            children,
+            root,
+            index,
+            _leaf: PhantomData,
+        }
+    }
+
+    // The check of `MerkleTree::check` on the parts of a proof, without allocations: the path is
+    // folded on the stack from the leaf to the top, eg: for the verifier in wasm, or the
+    // thousands of openings of the LDT queries.
+    pub fn verify_in_place(
+        root: &Digest,
+        index: usize,
+        leaf_bytes: &[u8],
+        siblings: &[Digest],
+    ) -> bool {
+        Self::check_in_place(root, index, leaf_bytes, siblings).is_ok()
+    }
+
+    // The same as `verify_in_place`, but returns why the path is rejected.
+    pub fn check_in_place(
+        root: &Digest,
+        index: usize,
+        leaf_bytes: &[u8],
+        siblings: &[Digest],
+    ) -> Result<(), MerkleError> {
+        let levels = siblings.len();
+        if levels >= MAX_HEIGHT {
+            return Err(MerkleError::HeightTooLarge {
+                height: levels + 1,
+                max: MAX_HEIGHT,
+            });
+        }
+        if index.checked_shr(levels as u32).unwrap_or(0) != 0 {
+            return Err(MerkleError::IndexOutOfRange { index, levels });
+        }
+        let top = siblings
+            .iter()
+            .enumerate()
+            .fold(hash_leaf(leaf_bytes), |acc, (i, bro)| {
+                if (index >> i) & 1 == 0 {
+                    hash_node(&acc, bro)
+                } else {
+                    hash_node(bro, &acc)
+                }
+            });
+        if *root != hash_root(levels + 1, &top) {
+            return Err(MerkleError::PathMismatch { index });
+        }
+        Ok(())
+    }
+}
+
+// The buffer of the leaf bytes of `MerkleTree::verify_batch_with_scratch`. It's reused across
+// the batches, so it grows to the longest leaf once, and the batches after allocate nothing.
+#[derive(Clone, Debug, Default)]
+pub struct Scratch {
+    leaf: Vec<u8>,
+}
+
+impl Scratch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // The bytes of the value, written over the ones of the last leaf.
+    pub(crate) fn leaf_bytes<V: Leaf>(&mut self, value: &V) -> &[u8] {
+        self.leaf.clear();
+        value.write_leaf_bytes(&mut self.leaf);
+        &self.leaf
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::merkle_tree::MerkleTree;
+    use crate::utils::{random_chars, random_scalars};
+    use ff::PrimeField;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    // Counts the allocations of the current thread, so the tests running in parallel don't add
+    // up to the ones of each other.
+    struct CountingAlloc;
+
+    thread_local! {
+        static ALLOCS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCS.try_with(|n| n.set(n.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: CountingAlloc = CountingAlloc;
+
+    // The allocations of f, and its result.
+    fn count_allocs<T>(f: impl FnOnce() -> T) -> (usize, T) {
+        let before = ALLOCS.with(|n| n.get());
+        let res = f();
+        (ALLOCS.with(|n| n.get()) - before, res)
+    }
+
+    #[test]
+    fn test_verify_in_place_allocs_nothing() {
+        let values = random_scalars(4);
+        let merkle_tree = MerkleTree::commit(&values);
+        let root = merkle_tree.root_hash();
+        let proof = merkle_tree.open_by_index(9);
+        let leaf = values[9].to_repr();
+        let (allocs, ok) = count_allocs(|| {
+            MerkleProof::<Scalar>::verify_in_place(&root, 9, leaf.as_ref(), &proof.children)
+        });
+        assert!(ok);
+        assert_eq!(allocs, 0);
+
+        // nor to reject it.
+        let (allocs, ok) = count_allocs(|| {
+            MerkleProof::<Scalar>::verify_in_place(&root, 8, leaf.as_ref(), &proof.children)
+        });
+        assert!(!ok);
+        assert_eq!(allocs, 0);
+    }
+
+    #[test]
+    fn test_batch_allocs_once() {
+        let values = random_scalars(4);
+        let merkle_tree = MerkleTree::commit(&values);
+        let root = merkle_tree.root_hash();
+        let indices = [1, 5, 9, 13];
+        let proofs = indices
+            .iter()
+            .map(|i| merkle_tree.open_by_index(*i))
+            .collect::<Vec<_>>();
+        let opened = indices.iter().map(|i| values[*i]).collect::<Vec<_>>();
+
+        // the buffer of the leaf grows in the first batch, then it's reused.
+        let mut scratch = Scratch::new();
+        let (first, ok) = count_allocs(|| {
+            MerkleTree::verify_batch_with_scratch(&mut scratch, &root, &proofs, &opened)
+        });
+        assert!(ok);
+        assert_eq!(first, 1);
+        let (allocs, all_ok) = count_allocs(|| {
+            (0..1000).all(|_| {
+                MerkleTree::verify_batch_with_scratch(&mut scratch, &root, &proofs, &opened)
+            })
+        });
+        assert!(all_ok);
+        assert_eq!(allocs, 0);
+
+        // a fresh scratch for each batch allocates each time.
+        let (allocs, _) =
+            count_allocs(|| (0..10).all(|_| MerkleTree::verify_batch(&root, &proofs, &opened)));
+        assert_eq!(allocs, 10);
+    }
+
+    #[test]
+    fn test_paths_agree() {
+        // the test vector of `test_root_test_vector`, then random trees of scalars and chars.
+        let vector = [1, 12, 0, 13].map(Scalar::from_u128).to_vec();
+        for values in [vector, random_scalars(3)] {
+            assert_paths_agree(&values, &MerkleTree::commit(&values));
+        }
+        let chars = random_chars(3);
+        assert_paths_agree(&chars, &MerkleTree::commit(&chars));
+    }
+
+    // Each path, and each one of another leaf, index or root, is accepted by the three checks
+    // or rejected by them all.
+    fn assert_paths_agree<V: Leaf>(values: &[V], merkle_tree: &MerkleTree<V>) {
+        let root = merkle_tree.root_hash();
+        let mut scratch = Scratch::new();
+        for (index, value) in values.iter().enumerate() {
+            let proof = merkle_tree.open_by_index(index);
+            let other = values[index ^ 1].clone();
+            let moved = MerkleProof::<V>::new(proof.children.clone(), root, index ^ 1);
+            let far = MerkleProof::<V>::new(proof.children.clone(), root, index + values.len());
+            let cases = [
+                (root, &proof, value),
+                (root, &proof, &other),
+                (root, &moved, value),
+                (root, &far, value),
+                (hash_leaf(b"other"), &proof, value),
+            ];
+            for (root, proof, value) in cases {
+                let expected = MerkleTree::check(&root, proof, &value.to_leaf_bytes());
+                let bytes = value.to_leaf_bytes();
+                let in_place =
+                    MerkleProof::<V>::check_in_place(&root, proof.index, &bytes, &proof.children);
+                let batch = MerkleTree::check_batch_with_scratch(
+                    &mut scratch,
+                    &root,
+                    core::slice::from_ref(proof),
+                    core::slice::from_ref(value),
+                );
+                assert_eq!(batch, expected);
+                // the proof carries the root it's of, the parts don't.
+                if expected == Err(MerkleError::RootMismatch) {
+                    assert_eq!(in_place, Err(MerkleError::PathMismatch { index }));
+                } else {
+                    assert_eq!(in_place, expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_height_overflow() {
+        let leaf = Scalar::one().to_repr();
+        let root = hash_leaf(b"root");
+        // 39 siblings are the max, the root is the 40th level.
+        let siblings = vec![Scalar::zero(); MAX_HEIGHT];
+        assert_eq!(
+            MerkleProof::<Scalar>::check_in_place(&root, 0, leaf.as_ref(), &siblings),
+            Err(MerkleError::HeightTooLarge {
+                height: MAX_HEIGHT + 1,
+                max: MAX_HEIGHT
+            })
+        );
+        assert_eq!(
+            MerkleProof::<Scalar>::check_in_place(&root, 0, leaf.as_ref(), &siblings[1..]),
+            Err(MerkleError::PathMismatch { index: 0 })
+        );
+
+        // the allocating check and the batch are bounded the same.
+        let proof = MerkleProof::<Scalar>::new(siblings, root, 0);
+        assert!(!MerkleTree::verify(&root, &proof, leaf.as_ref()));
+        assert_eq!(
+            MerkleTree::check_batch_with_scratch(
+                &mut Scratch::new(),
+                &root,
+                &[proof],
+                &[Scalar::one()]
+            ),
+            Err(MerkleError::HeightTooLarge {
+                height: MAX_HEIGHT + 1,
+                max: MAX_HEIGHT
+            })
+        );
+        assert_eq!(
+            MerkleError::HeightTooLarge {
+                height: 41,
+                max: 40
+            }
+            .to_string(),
+            "The path of height 41 is above the max height 40"
+        );
+    }
 }
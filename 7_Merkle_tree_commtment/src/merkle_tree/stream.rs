@@ -0,0 +1,279 @@
+// The incremental construction of a tree from leaves which arrive one by one, eg: a codeword
+// produced chunk by chunk, so it's never collected into a Vec before the commit.
+//
+// A node waits at its level until its right sibling arrives, then both move up as their parent,
+// so there is at most one pending node per level, height of them in all. The size is needed up
+// front for the height, and it must be a power of 2 as for `MerkleTree::commit`.
+use crate::merkle_tree::hasher::{hash_node, hash_root, Digest};
+use crate::merkle_tree::leaf::Leaf;
+use crate::merkle_tree::node::TreeNode;
+use crate::merkle_tree::MerkleTree;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use ark_std::log2;
+use bls12_381::Scalar;
+use core::borrow::Borrow;
+use core::fmt;
+use core::marker::PhantomData;
+
+// The leaves differ from the size claimed up front, eg: an ExactSizeIterator which lies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeafCountError {
+    TooFew { claimed: usize, actual: usize },
+    TooMany { claimed: usize },
+}
+
+impl fmt::Display for LeafCountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LeafCountError::TooFew { claimed, actual } => {
+                write!(f, "{} leaves of the {} claimed", actual, claimed)
+            }
+            LeafCountError::TooMany { claimed } => {
+                write!(f, "More leaves than the {} claimed", claimed)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LeafCountError {}
+
+// The pending nodes, levels[0] is the one of the leaves.
+#[derive(Clone, Debug)]
+struct Frontier<N> {
+    levels: Vec<Option<N>>,
+    leaves_num: usize,
+    pushed: usize,
+}
+
+impl<N> Frontier<N> {
+    fn new(leaves_num: usize) -> Self {
+        assert!(leaves_num > 0, "Can't initial MerkleTree from empty vector");
+        let height: usize = 1 + log2(leaves_num) as usize;
+        assert_eq!(1 << (height - 1), leaves_num, "It's not a perfect tree");
+        Self {
+            levels: (0..height).map(|_| None).collect(),
+            leaves_num,
+            pushed: 0,
+        }
+    }
+
+    fn height(&self) -> usize {
+        self.levels.len()
+    }
+
+    fn pending(&self) -> usize {
+        self.levels.iter().filter(|n| n.is_some()).count()
+    }
+
+    fn push(&mut self, leaf: N, join: impl Fn(N, N) -> N) -> Result<(), LeafCountError> {
+        if self.pushed == self.leaves_num {
+            return Err(LeafCountError::TooMany {
+                claimed: self.leaves_num,
+            });
+        }
+        self.pushed += 1;
+        let mut node = leaf;
+        let mut i = 0;
+        while let Some(left) = self.levels[i].take() {
+            node = join(left, node);
+            i += 1;
+        }
+        self.levels[i] = Some(node);
+        Ok(())
+    }
+
+    // The top node, once all the leaves are pushed.
+    fn finish(mut self) -> Result<N, LeafCountError> {
+        if self.pushed != self.leaves_num {
+            return Err(LeafCountError::TooFew {
+                claimed: self.leaves_num,
+                actual: self.pushed,
+            });
+        }
+        Ok(self.levels.pop().flatten().expect("the top of a full tree"))
+    }
+}
+
+impl<V: Leaf> MerkleTree<V> {
+    // The same tree as `commit` of the collected leaves, the leaves are hashed as they arrive.
+    pub fn from_leaf_iter<I>(leaves: I) -> Result<Self, LeafCountError>
+    where
+        I: ExactSizeIterator,
+        I::Item: Borrow<V>,
+    {
+        Self::from_leaf_iter_with_hook(leaves, |_| {})
+    }
+
+    // `on_push` is told the pending nodes after each leaf.
+    fn from_leaf_iter_with_hook<I>(
+        leaves: I,
+        mut on_push: impl FnMut(usize),
+    ) -> Result<Self, LeafCountError>
+    where
+        I: ExactSizeIterator,
+        I::Item: Borrow<V>,
+    {
+        let mut frontier = Frontier::new(leaves.len());
+        let join = |left: TreeNode, right: TreeNode| TreeNode::Node {
+            hash: hash_node(&left.get_hash(), &right.get_hash()),
+            left: Box::new(left),
+            right: Box::new(right),
+        };
+        for v in leaves {
+            let v = v.borrow();
+            frontier.push(TreeNode::new(v.leaf_hash(), v.to_leaf_bytes()), join)?;
+            on_push(frontier.pending());
+        }
+        let height = frontier.height();
+        Ok(MerkleTree {
+            root: frontier.finish()?,
+            height,
+            _leaf: PhantomData,
+        })
+    }
+}
+
+// The root alone, with only the pending hashes, eg: to recompute the root of a codeword on the
+// side of V, where no openings are ever needed.
+#[derive(Clone, Debug)]
+pub struct MerkleRootBuilder<V: Leaf = Scalar> {
+    frontier: Frontier<Digest>,
+    _leaf: PhantomData<V>,
+}
+
+impl<V: Leaf> MerkleRootBuilder<V> {
+    pub fn new(leaves_num: usize) -> Self {
+        Self {
+            frontier: Frontier::new(leaves_num),
+            _leaf: PhantomData,
+        }
+    }
+
+    pub fn push(&mut self, value: &V) -> Result<(), LeafCountError> {
+        self.frontier
+            .push(value.leaf_hash(), |left, right| hash_node(&left, &right))
+    }
+
+    // The hashes held now, at most the height of the tree.
+    pub fn pending(&self) -> usize {
+        self.frontier.pending()
+    }
+
+    // The same as `MerkleTree::root_hash` of the tree of the leaves.
+    pub fn finish(self) -> Result<Digest, LeafCountError> {
+        let height = self.frontier.height();
+        Ok(hash_root(height, &self.frontier.finish()?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::merkle_tree::stream::{LeafCountError, MerkleRootBuilder};
+    use crate::merkle_tree::MerkleTree;
+    use crate::utils::random_scalars;
+    use alloc::vec;
+    use bls12_381::Scalar;
+
+    // An iterator which claims `claimed` items, whatever it yields.
+    struct Lying {
+        inner: vec::IntoIter<Scalar>,
+        claimed: usize,
+    }
+
+    impl Iterator for Lying {
+        type Item = Scalar;
+
+        fn next(&mut self) -> Option<Scalar> {
+            self.inner.next()
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (self.claimed, Some(self.claimed))
+        }
+    }
+
+    impl ExactSizeIterator for Lying {}
+
+    #[test]
+    fn test_stream_agrees_with_commit() {
+        for k in [0, 1, 3, 6] {
+            let values = random_scalars(k);
+            let tree = MerkleTree::commit(&values);
+
+            let streamed = MerkleTree::<Scalar>::from_leaf_iter(values.iter()).unwrap();
+            assert_eq!(streamed.root_hash(), tree.root_hash());
+            assert_eq!(streamed.height(), tree.height());
+            // the leaves produced on the fly, and the openings.
+            let owned = MerkleTree::<Scalar>::from_leaf_iter(values.clone().into_iter()).unwrap();
+            for index in 0..values.len() {
+                assert_eq!(owned.open_by_index(index), tree.open_by_index(index));
+            }
+
+            let mut builder = MerkleRootBuilder::new(values.len());
+            for v in values.iter() {
+                builder.push(v).unwrap();
+            }
+            assert_eq!(builder.finish(), Ok(tree.root_hash()));
+        }
+    }
+
+    #[test]
+    fn test_stream_wrong_len() {
+        let values = random_scalars(3);
+        for (inner, claimed) in [(values[..7].to_vec(), 8), (values.clone(), 4)] {
+            let actual = inner.len();
+            let lying = Lying {
+                inner: inner.into_iter(),
+                claimed,
+            };
+            let expected = if actual < claimed {
+                LeafCountError::TooFew { claimed, actual }
+            } else {
+                LeafCountError::TooMany { claimed }
+            };
+            assert_eq!(
+                MerkleTree::<Scalar>::from_leaf_iter(lying).err(),
+                Some(expected)
+            );
+        }
+
+        let mut builder = MerkleRootBuilder::new(4);
+        for v in values[..4].iter() {
+            builder.push(v).unwrap();
+        }
+        assert_eq!(
+            builder.push(&values[4]),
+            Err(LeafCountError::TooMany { claimed: 4 })
+        );
+        let builder = MerkleRootBuilder::<Scalar>::new(4);
+        assert_eq!(
+            builder.finish(),
+            Err(LeafCountError::TooFew {
+                claimed: 4,
+                actual: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_stream_pending_is_log() {
+        for k in [4, 10] {
+            let values = random_scalars(k);
+            let mut peak = 0;
+            MerkleTree::<Scalar>::from_leaf_iter_with_hook(values.iter(), |n| peak = peak.max(n))
+                .unwrap();
+            // one node per level at most, the leaves are 2^k.
+            assert!(peak <= k + 1, "{} pending of 2^{} leaves", peak, k);
+
+            let mut builder = MerkleRootBuilder::new(values.len());
+            let mut peak = 0;
+            for v in values.iter() {
+                builder.push(v).unwrap();
+                peak = peak.max(builder.pending());
+            }
+            assert!(peak <= k + 1);
+        }
+    }
+}
@@ -1,17 +1,17 @@
-use crate::merkle_tree::proof::Proof;
+use crate::merkle_tree::proof::MerkleProof;
 use crate::merkle_tree::MerkleTree;
 use crate::utils::random_chars;
 
 pub struct Prover {
     pub(crate) values: Vec<char>, // statement.
-    pub(crate) merkle_tree: MerkleTree,
+    pub(crate) merkle_tree: MerkleTree<char>,
 }
 
 impl Prover {
     // k is the depth of tree, 2^k is the random values size.
     pub fn random_values(k: usize) -> Self {
         let values = random_chars(k);
-        let merkle_tree = MerkleTree::init(values.clone());
+        let merkle_tree = MerkleTree::commit(&values);
         assert_eq!(merkle_tree.height() - 1, k, "Unexpected Merkle tree height");
         Self {
             values,
@@ -20,11 +20,11 @@ impl Prover {
     }
 
     // V send a challenge to P,
-    pub fn has_x(&self, x: &char) -> (bool, Option<Proof>) {
+    pub fn has_x(&self, x: &char) -> (bool, Option<MerkleProof<char>>) {
         if self.values.contains(x) {
-            let proof = self.merkle_tree.commit(x);
+            let proof = self.merkle_tree.open(x);
 
-            (true, Some(proof))
+            (true, proof)
         } else {
             (false, None)
         }
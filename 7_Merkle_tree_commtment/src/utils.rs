@@ -1,4 +1,11 @@
+use alloc::vec::Vec;
+#[cfg(any(feature = "std", test))]
+use bls12_381::Scalar;
+#[cfg(any(feature = "std", test))]
+use ff::Field;
+#[cfg(any(feature = "std", test))]
 use rand::distributions::{Alphanumeric, DistString};
+#[cfg(any(feature = "std", test))]
 use rand_core::OsRng;
 
 // convert a num into its binary form
@@ -12,12 +19,20 @@ pub fn convert_to_binary(bit_len: &usize, num: usize) -> Vec<usize> {
         .collect::<Vec<usize>>()
 }
 
+// The random helpers sample from OsRng, so they are in std (and tests) only.
+#[cfg(any(feature = "std", test))]
 pub fn random_chars(k: usize) -> Vec<char> {
     let n = 1 << k;
     let random_code = Alphanumeric.sample_string(&mut OsRng, n);
     random_code.chars().collect::<Vec<char>>()
 }
 
+#[cfg(any(feature = "std", test))]
+pub fn random_scalars(k: usize) -> Vec<Scalar> {
+    let n = 1 << k;
+    (0..n).map(|_| Scalar::random(OsRng)).collect::<Vec<_>>()
+}
+
 #[cfg(test)]
 mod test {
     use crate::utils::random_chars;
@@ -1,5 +1,6 @@
-use crate::merkle_tree::hasher::{calculate_hash, calculate_parent_hash};
-use crate::merkle_tree::proof::Proof;
+use crate::merkle_tree::leaf::Leaf;
+use crate::merkle_tree::proof::MerkleProof;
+use crate::merkle_tree::MerkleTree;
 use crate::utils::random_chars;
 
 #[derive(Default)]
@@ -15,21 +16,18 @@ impl Verifier {
         challenge
     }
 
-    pub fn verify(&self, proof: &Proof) {
-        let target = proof.root;
-
-        let leaf_hash = calculate_hash(&self.challenge);
-        let actual = proof.children.iter().fold(leaf_hash, |acc, eval| {
-            calculate_parent_hash(acc, eval.clone())
-        });
-        assert_eq!(target, actual, "Verifier: verify failed!")
+    pub fn verify(&self, proof: &MerkleProof<char>) {
+        let leaf = self.challenge.to_leaf_bytes();
+        assert!(
+            MerkleTree::verify(&proof.root, proof, &leaf),
+            "Verifier: verify failed!"
+        )
     }
 }
 
 #[cfg(test)]
 mod test {
-
-    use crate::merkle_tree::proof::Proof;
+    use crate::merkle_tree::proof::MerkleProof;
     use crate::merkle_tree::MerkleTree;
     use crate::verifier::Verifier;
 
@@ -37,77 +35,27 @@ mod test {
     fn test_verify() {
         let chars = vec!['W', '8', 'E', 'X', 'D', '8', 'R', '3'];
         let challenge = 'W';
-        let merkle_tree = MerkleTree::init(chars);
-        {
-
-            // MerkleTree {
-            //     root: Node {
-            //         hash: 2997809638824881102,
-            //         left: Node {
-            //             hash: 13957922012229917015,
-            //             left: Node {
-            //                 hash: 10153464161223545464,
-            //                 left: Leaf {
-            //                     hash: 5949921715258702887,
-            //                     value: 'W',
-            //                 },
-            //                 right: Leaf {
-            //                     hash: 3209422213365730399,
-            //                     value: '8',
-            //                 },
-            //             },
-            //             right: Node {
-            //                 hash: 10895954492970826136,
-            //                 left: Leaf {
-            //                     hash: 15042720617947887434,
-            //                     value: 'E',
-            //                 },
-            //                 right: Leaf {
-            //                     hash: 15818208776807171099,
-            //                     value: 'X',
-            //                 },
-            //             },
-            //         },
-            //         right: Node {
-            //             hash: 14010322267561343302,
-            //             left: Node {
-            //                 hash: 2385074442875957999,
-            //                 left: Leaf {
-            //                     hash: 6796667025961897532,
-            //                     value: 'D',
-            //                 },
-            //                 right: Leaf {
-            //                     hash: 3209422213365730399,
-            //                     value: '8',
-            //                 },
-            //             },
-            //             right: Node {
-            //                 hash: 168356471189691628,
-            //                 left: Leaf {
-            //                     hash: 5573041882718737857,
-            //                     value: 'R',
-            //                 },
-            //                 right: Leaf {
-            //                     hash: 15080230035883566959,
-            //                     value: '3',
-            //                 },
-            //             },
-            //         },
-            //     },
-            //     height: 3,
-            // }
-            // println!("merkle_tree:{:#?}", merkle_tree);
-        }
-        let proofs = Proof {
-            root: 2997809638824881102,
-            children: vec![
-                3209422213365730399,  // '8'
-                10895954492970826136, // right
-                14010322267561343302, // right
-            ],
-        };
+        let merkle_tree = MerkleTree::commit(&chars);
+        let proof = merkle_tree.open(&challenge).unwrap();
+        // '8', then the nodes of ('E', 'X') and ('D', '8', 'R', '3').
+        assert_eq!(proof.children.len(), 3);
+        assert_eq!(proof.index, 0);
 
         let verifiy = Verifier { challenge };
-        verifiy.verify(&proofs);
+        verifiy.verify(&proof);
+
+        // the second '8' is opened as the first one.
+        assert_eq!(merkle_tree.open(&'8').unwrap().index, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Verifier: verify failed!")]
+    fn test_verify_wrong_char() {
+        let chars = vec!['W', '8', 'E', 'X', 'D', '8', 'R', '3'];
+        let merkle_tree = MerkleTree::commit(&chars);
+        let proof: MerkleProof<char> = merkle_tree.open(&'E').unwrap();
+
+        let verifiy = Verifier { challenge: 'X' };
+        verifiy.verify(&proof);
     }
 }
@@ -0,0 +1,215 @@
+// A minimal STARK-ish example, which shows how the pieces compose.
+//
+// Statement: the trace column t = (t_0, ..., t_{n-1}) is a Fibonacci sequence, aka
+//      t_{i+2} = t_{i+1} + t_i, for i in [0, n-2)
+//
+// 1. Interpolate the trace over the subgroup H = <ω> (|H| = n), so t(ω^i) = t_i.
+// 2. The transition constraint is expressed by two shifted interpolations:
+//      c(X) = t(ω^2 * X) - t(ω * X) - t(X)
+//    which vanishes on the constrained rows {ω^0, ..., ω^(n-3)}, so it's divisible by
+//      Z(X) = ∏(X - ω^i) = (X^n - 1) / ((X - ω^(n-2)) * (X - ω^(n-1)))
+//    and the quotient is q(X) = c(X) / Z(X).
+// 3. Commit the codewords of t and q over the LDE domain D = g * <ω'> with the Merkle tree,
+//    where |D| = blowup * n and ω = ω'^blowup, so t(ω * x) lives at the index + blowup.
+//    D is a coset disjoint with H, so Z(x) is never zero on it, see `is_disjoint`.
+// 4. Run the batched LDT on (t, q), and on each queried x check the constraint with the openings:
+//      t(ω^2 * x) - t(ω * x) - t(x) == q(x) * Z(x)
+//
+// The soundness error is the one of the batched LDT, whose queries are the ones of the
+// constraint, see `soundness`.
+use bls12_381::Scalar;
+use ff::PrimeField;
+use low_degree_test::domain::EvaluationDomain;
+use low_degree_test::ldt::batch::{BatchLDT, BatchLDTProof, CommittedCodeword, Opening};
+use low_degree_test::poly::Polynomial;
+use std::time::Instant;
+use sumcheck::rlc::SoundnessBudget;
+
+const TRACE_LEN: usize = 64;
+const BLOWUP: usize = 8;
+const NUM_QUERIES: usize = 16;
+
+pub struct StarkProof {
+    pub trace_root: Scalar,
+    pub quotient_root: Scalar,
+    pub ldt: BatchLDTProof,
+    pub shifted: Vec<(Opening, Opening)>, // (t(ω * x), t(ω^2 * x)) for each queried x
+}
+
+impl StarkProof {
+    pub fn size_in_bytes(&self) -> usize {
+        let opening_size = |o: &Opening| 1 + o.proof.children.len() + 1;
+        let shifted = self
+            .shifted
+            .iter()
+            .map(|(l, r)| opening_size(l) + opening_size(r))
+            .sum::<usize>();
+        (2 + self.ldt.num_scalars() + shifted) * 32
+    }
+}
+
+fn trace_domain() -> EvaluationDomain {
+    EvaluationDomain::new(TRACE_LEN)
+}
+
+fn lde_domain() -> EvaluationDomain {
+    EvaluationDomain::coset(TRACE_LEN * BLOWUP, Scalar::MULTIPLICATIVE_GENERATOR)
+}
+
+pub fn fibonacci_trace(n: usize) -> Vec<Scalar> {
+    let mut trace = vec![Scalar::one(), Scalar::one()];
+    for i in 2..n {
+        trace.push(trace[i - 1] + trace[i - 2]);
+    }
+    trace
+}
+
+// (X - ω^(n-2)) * (X - ω^(n-1)), the rows without transition constraint.
+fn unconstrained_rows(domain: &EvaluationDomain) -> Polynomial {
+    let x_minus = |x: Scalar| Polynomial::x() - Polynomial::constant(x);
+    x_minus(domain.element(domain.size - 2)) * x_minus(domain.element(domain.size - 1))
+}
+
+// Z(X) = (X^n - 1) / ((X - ω^(n-2)) * (X - ω^(n-1)))
+fn constraint_vanishing_poly(domain: &EvaluationDomain) -> Polynomial {
+    let (z, r) = domain
+        .vanishing_poly()
+        .div_rem(&unconstrained_rows(domain));
+    assert!(r.coeffs().iter().all(|c| c == &Scalar::zero()));
+    z
+}
+
+pub fn prove(trace: &[Scalar]) -> StarkProof {
+    assert_eq!(trace.len(), TRACE_LEN);
+    let trace_domain = trace_domain();
+    let lde_domain = lde_domain();
+    assert!(lde_domain.is_disjoint(&trace_domain));
+
+    // 1. t(X)
+    let t = trace_domain.interpolate(trace);
+
+    // 2. c(X) = t(ω^2 * X) - t(ω * X) - t(X), and q(X) = c(X) / Z(X).
+    //  The prover doesn't check the remainder, a wrong trace is left to be caught by the verifier.
+    let w = trace_domain.generator;
+    let c = &(&t.scale_arg(w.square()) - &t.scale_arg(w)) - &t;
+    let (q, _) = c.div_rem(&constraint_vanishing_poly(&trace_domain));
+
+    // 3. commit the codewords
+    let trace_cw = CommittedCodeword::commit(lde_domain.evaluate(&t));
+    let quotient_cw = CommittedCodeword::commit(lde_domain.evaluate(&q));
+
+    // 4. batched LDT, with the shifted openings on the same queries.
+    let ldt = BatchLDT::new(lde_domain, TRACE_LEN, NUM_QUERIES);
+    let ldt_proof = ldt.prove(&[&trace_cw, &quotient_cw]);
+    let shifted = ldt_proof
+        .queries
+        .iter()
+        .map(|query| {
+            (
+                trace_cw.open((query.index + BLOWUP) % lde_domain.size),
+                trace_cw.open((query.index + 2 * BLOWUP) % lde_domain.size),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    StarkProof {
+        trace_root: trace_cw.root(),
+        quotient_root: quotient_cw.root(),
+        ldt: ldt_proof,
+        shifted,
+    }
+}
+
+pub fn verify(proof: &StarkProof) {
+    let trace_domain = trace_domain();
+    let lde_domain = lde_domain();
+    assert!(lde_domain.is_disjoint(&trace_domain));
+
+    // 1. t and q are low degree
+    let ldt = BatchLDT::new(lde_domain, TRACE_LEN, NUM_QUERIES);
+    let indices = ldt
+        .verify(&[proof.trace_root, proof.quotient_root], &proof.ldt)
+        .indices();
+    assert_eq!(proof.shifted.len(), indices.len());
+
+    // 2. the transition constraint holds on the queried points
+    for ((index, query), (t_1, t_2)) in indices
+        .iter()
+        .zip(proof.ldt.queries.iter())
+        .zip(proof.shifted.iter())
+    {
+        assert_eq!(t_1.index, (index + BLOWUP) % lde_domain.size);
+        assert_eq!(t_2.index, (index + 2 * BLOWUP) % lde_domain.size);
+        t_1.verify(&proof.trace_root);
+        t_2.verify(&proof.trace_root);
+
+        // the openings of t(x) and q(x) have been checked by the LDT.
+        let t_0 = query.inputs[0][0].value;
+        let q = query.inputs[1][0].value;
+
+        // Z(x) = (x^n - 1) / ((x - ω^(n-2)) * (x - ω^(n-1)))
+        let x = lde_domain.element(*index);
+        let z = trace_domain.vanishing_poly().evaluate(x)
+            * unconstrained_rows(&trace_domain)
+                .evaluate(x)
+                .invert()
+                .unwrap();
+        assert_eq!(
+            t_2.value - t_1.value - t_0,
+            q * z,
+            "Verifier: transition constraint check failed at {index}."
+        );
+    }
+}
+
+// The queried x are the ones of the LDT, so a trace which breaks the constraint makes q far from
+// deg < n, which passes the LDT with its error only.
+pub fn soundness() -> SoundnessBudget {
+    let mut budget = SoundnessBudget::new();
+    BatchLDT::new(lde_domain(), TRACE_LEN, NUM_QUERIES).soundness(2, &mut budget);
+    budget
+}
+
+fn main() {
+    let trace = fibonacci_trace(TRACE_LEN);
+
+    let start = Instant::now();
+    let proof = prove(&trace);
+    println!("prove time: {:?}", start.elapsed());
+    println!("proof size: {} bytes", proof.size_in_bytes());
+
+    let start = Instant::now();
+    verify(&proof);
+    println!("verify time: {:?}", start.elapsed());
+    println!("soundness: {:.1} bits", soundness().bits());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_honest_trace() {
+        let proof = prove(&fibonacci_trace(TRACE_LEN));
+        verify(&proof);
+    }
+
+    // ρ = 1/8 and 16 queries: (9/16)^16 ≈ 2^-13.3, the γ and the folds add ~2^-245 only.
+    #[test]
+    fn test_soundness_budget() {
+        let budget = soundness();
+        assert_eq!(budget.terms().len(), 1 + 6 + 1);
+        assert!(budget.total() < 2f64.powi(-13), "{:?}", budget);
+        let (_, queries) = budget.terms().last().unwrap();
+        assert!(budget.total() - queries < 2f64.powi(-240));
+    }
+
+    #[test]
+    #[should_panic(expected = "transition constraint check failed")]
+    fn test_wrong_fibonacci_entry() {
+        let mut trace = fibonacci_trace(TRACE_LEN);
+        trace[17] += Scalar::one();
+        let proof = prove(&trace);
+        verify(&proof);
+    }
+}
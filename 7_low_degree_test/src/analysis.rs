@@ -0,0 +1,47 @@
+// The measured cost of V against the one claimed by the book: O(q * log^2 N) hashes for q queries
+// over a domain of size N, as each query opens log(d) layers with a merkle path of log N each.
+use crate::domain::EvaluationDomain;
+use crate::ldt::batch::{BatchLDT, CommittedCodeword};
+use crate::poly::random_poly;
+use bls12_381::Scalar;
+use ff::PrimeField;
+use sumcheck::cost::grows_like;
+
+const NUM_QUERIES: usize = 5;
+// log N, the rate is 1/8.
+const SIZES: [usize; 3] = [6, 8, 12];
+
+#[test]
+fn test_ldt_cost_is_polylog() {
+    let mut hashes = vec![];
+    let mut mults = vec![];
+    for log_n in SIZES {
+        let domain = EvaluationDomain::coset(1 << log_n, Scalar::MULTIPLICATIVE_GENERATOR);
+        let degree_bound = 1 << (log_n - 3);
+        let ldt = BatchLDT::new(domain, degree_bound, NUM_QUERIES);
+        let codewords = (0..2)
+            .map(|_| CommittedCodeword::commit(domain.evaluate(&random_poly(degree_bound - 1))))
+            .collect::<Vec<_>>();
+        let roots = codewords.iter().map(|c| c.root()).collect::<Vec<_>>();
+        let proof = ldt.prove(&codewords.iter().collect::<Vec<_>>());
+
+        let (res, cost) = ldt.verify_with_cost(&roots, &proof);
+        assert_eq!(res, ldt.verify_with_sink(&roots, &proof, &mut ()));
+        assert!(res.is_ok());
+        // 1/k, the folds by 2 are checked without inversion, see `fold_check`.
+        assert_eq!(cost.field_inversions, 1);
+        hashes.push(cost.hash_calls);
+        mults.push(cost.field_mults);
+    }
+
+    let log_squared = |log_n: usize| (log_n * log_n) as f64;
+    assert!(
+        grows_like(&SIZES, &hashes, log_squared, 1.5),
+        "{:?}",
+        hashes
+    );
+    assert!(grows_like(&SIZES, &mults, log_squared, 1.5), "{:?}", mults);
+    let n = |log_n: usize| (1u64 << log_n) as f64;
+    assert!(!grows_like(&SIZES, &hashes, n, 1.5));
+    assert!(!grows_like(&SIZES, &mults, n, 1.5));
+}
@@ -0,0 +1,300 @@
+use crate::poly::Polynomial;
+use alloc::vec;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use ff::PrimeField;
+
+// The evaluation domain D = offset * <ω>, where ω is a primitive n-th root of unity, n = 2^k.
+//  D = {offset * ω^0, offset * ω^1, ..., offset * ω^(n-1)}
+// When offset = 1, D is the multiplicative subgroup H of order n.
+// When offset is not in H (eg: the multiplicative generator), D is a coset of H, which is
+// disjoint with H, so it's used as the low degree extension (LDE) domain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvaluationDomain {
+    pub size: usize,
+    pub log_size: u32,
+    pub generator: Scalar, // ω
+    pub offset: Scalar,
+}
+
+impl EvaluationDomain {
+    // The subgroup H = <ω> of order `size`.
+    pub fn new(size: usize) -> Self {
+        Self::coset(size, Scalar::one())
+    }
+
+    // The coset offset * <ω> of order `size`.
+    pub fn coset(size: usize, offset: Scalar) -> Self {
+        assert!(size.is_power_of_two(), "domain size must be a power of 2");
+        assert!(offset != Scalar::zero(), "the offset must not be zero");
+        let log_size = size.trailing_zeros();
+        assert!(log_size <= Scalar::S, "domain size is too large for the field");
+
+        // ω = ROOT_OF_UNITY^(2^(S - k))
+        let mut generator = Scalar::ROOT_OF_UNITY;
+        for _ in log_size..Scalar::S {
+            generator = generator.square();
+        }
+
+        Self {
+            size,
+            log_size,
+            generator,
+            offset,
+        }
+    }
+
+    // offset * ω^i
+    pub fn element(&self, i: usize) -> Scalar {
+        self.offset * self.generator.pow_vartime(&[(i % self.size) as u64, 0, 0, 0])
+    }
+
+    pub fn elements(&self) -> Vec<Scalar> {
+        let mut cur = self.offset;
+        (0..self.size)
+            .map(|_| {
+                let x = cur;
+                cur *= self.generator;
+                x
+            })
+            .collect()
+    }
+
+    // The domain of the folded codeword: {x^2 | x ∈ D}, which has half size.
+    pub fn square(&self) -> Self {
+        assert!(self.size > 1, "can't square a domain of size 1");
+        Self {
+            size: self.size / 2,
+            log_size: self.log_size - 1,
+            generator: self.generator.square(),
+            offset: self.offset.square(),
+        }
+    }
+
+    // The domain of the k-folded codeword: {x^k | x ∈ D}, k = 2^i, which has size / k.
+    pub fn pow(&self, k: usize) -> Self {
+        assert!(
+            k.is_power_of_two() && k <= self.size,
+            "k must be 2^i, and at most the domain size"
+        );
+        (0..k.trailing_zeros()).fold(*self, |domain, _| domain.square())
+    }
+
+    // Z_D(X) = X^n - offset^n, which vanishes on the whole domain.
+    pub fn vanishing_poly(&self) -> Polynomial {
+        let mut coeffs = vec![Scalar::zero(); self.size + 1];
+        coeffs[0] = -self.offset.pow_vartime(&[self.size as u64, 0, 0, 0]);
+        coeffs[self.size] = Scalar::one();
+        Polynomial::from_coeffs(coeffs)
+    }
+
+    // The coset offset * H of the same size, where H is the subgroup of this domain.
+    pub fn to_coset(&self, offset: Scalar) -> Self {
+        assert!(offset != Scalar::zero(), "the offset must not be zero");
+        Self { offset, ..*self }
+    }
+
+    // The subgroup H of this domain, aka offset = 1.
+    pub fn subgroup(&self) -> Self {
+        self.to_coset(Scalar::one())
+    }
+
+    // Whether the two domains have no common point.
+    // eg: the quotient q = c / Z_H is only evaluated on a domain disjoint with H.
+    //
+    // Let |D_1| <= |D_2| = n, both are cosets of the subgroups, so H_1 ⊆ H_2, and D_1 is in the
+    // coset offset_1 * H_2, which is either D_2 (offset_1 / offset_2 ∈ H_2) or disjoint with it.
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        let n = self.size.max(other.size) as u64;
+        let ratio = self.offset * other.offset.invert().unwrap();
+        ratio.pow_vartime(&[n, 0, 0, 0]) != Scalar::one()
+    }
+
+    // The codeword (p(offset * ω^0), ..., p(offset * ω^(n-1))) from the coeffs of p, len <= n.
+    // p(offset * X) has coeffs a_i * offset^i, which are transformed by the plain FFT over H.
+    pub fn fft_on_coset(&self, coeffs: &[Scalar]) -> Vec<Scalar> {
+        assert!(
+            coeffs.len() <= self.size,
+            "poly degree is out of the domain size"
+        );
+        let mut values = vec![Scalar::zero(); self.size];
+        let mut shift = Scalar::one();
+        for (v, c) in values.iter_mut().zip(coeffs.iter()) {
+            *v = c * shift;
+            shift *= self.offset;
+        }
+        fft(&mut values, self.generator, self.log_size);
+        values
+    }
+
+    // The n coeffs from the codeword, the inverse of `fft_on_coset`.
+    pub fn ifft_on_coset(&self, evals: &[Scalar]) -> Vec<Scalar> {
+        assert_eq!(evals.len(), self.size, "evals size must equal the domain size");
+        let mut coeffs = evals.to_vec();
+        fft(&mut coeffs, self.generator.invert().unwrap(), self.log_size);
+
+        // divide by n, and remove the offset: a_i = a_i' / (n * offset^i)
+        let n_inv = Scalar::from(self.size as u64).invert().unwrap();
+        let offset_inv = self.offset.invert().unwrap();
+        let mut shift = n_inv;
+        for c in coeffs.iter_mut() {
+            *c *= shift;
+            shift *= offset_inv;
+        }
+        coeffs
+    }
+
+    // Evaluate p on every point of the domain, aka the codeword of p.
+    // Requires deg(p) < n.
+    pub fn evaluate(&self, poly: &Polynomial) -> Vec<Scalar> {
+        self.fft_on_coset(&poly.coeffs())
+    }
+
+    // Interpolate the unique poly with deg < n from the codeword over the domain.
+    pub fn interpolate(&self, evals: &[Scalar]) -> Polynomial {
+        Polynomial::from_coeffs(self.ifft_on_coset(evals))
+    }
+}
+
+// In-place radix-2 Cooley-Tukey FFT: a[i] <- ∑ a_j * ω^(i*j)
+fn fft(a: &mut [Scalar], omega: Scalar, log_n: u32) {
+    let n = a.len();
+    assert_eq!(n, 1 << log_n);
+
+    // bit-reverse permutation
+    for k in 0..n {
+        let rk = bitreverse(k, log_n);
+        if k < rk {
+            a.swap(rk, k);
+        }
+    }
+
+    let mut m = 1;
+    for _ in 0..log_n {
+        // w_m is a primitive (2m)-th root of unity
+        let w_m = omega.pow_vartime(&[(n / (2 * m)) as u64, 0, 0, 0]);
+        let mut k = 0;
+        while k < n {
+            let mut w = Scalar::one();
+            for j in 0..m {
+                let t = a[k + j + m] * w;
+                a[k + j + m] = a[k + j] - t;
+                a[k + j] += t;
+                w *= w_m;
+            }
+            k += 2 * m;
+        }
+        m *= 2;
+    }
+}
+
+fn bitreverse(mut n: usize, l: u32) -> usize {
+    let mut r = 0;
+    for _ in 0..l {
+        r = (r << 1) | (n & 1);
+        n >>= 1;
+    }
+    r
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::poly::random_poly;
+    use ff::Field;
+    use rand_core::OsRng;
+
+    #[test]
+    fn test_generator() {
+        let domain = EvaluationDomain::new(8);
+        // ω^8 = 1, ω^4 = -1
+        assert_eq!(domain.generator.pow_vartime(&[8, 0, 0, 0]), Scalar::one());
+        assert_eq!(domain.generator.pow_vartime(&[4, 0, 0, 0]), Scalar::one().neg());
+        assert_eq!(domain.elements().len(), 8);
+        assert_eq!(domain.element(9), domain.generator);
+    }
+
+    #[test]
+    fn test_evaluate_and_interpolate() {
+        let poly = random_poly(7);
+        for domain in [
+            EvaluationDomain::new(16),
+            EvaluationDomain::coset(16, Scalar::MULTIPLICATIVE_GENERATOR),
+        ] {
+            let evals = domain.evaluate(&poly);
+            for (x, y) in domain.elements().iter().zip(evals.iter()) {
+                assert_eq!(poly.evaluate(*x), *y);
+            }
+
+            let actual = domain.interpolate(&evals);
+            assert_eq!(actual.coeffs()[..8], poly.coeffs()[..]);
+            assert!(actual.coeffs()[8..].iter().all(|c| c == &Scalar::zero()));
+        }
+    }
+
+    #[test]
+    fn test_vanishing_and_square() {
+        let domain = EvaluationDomain::coset(8, Scalar::MULTIPLICATIVE_GENERATOR);
+        let z = domain.vanishing_poly();
+        for x in domain.elements() {
+            assert_eq!(z.evaluate(x), Scalar::zero());
+        }
+        assert_ne!(z.evaluate(Scalar::random(OsRng)), Scalar::zero());
+
+        let squared = domain.square();
+        for (i, x) in domain.elements().iter().enumerate().take(4) {
+            assert_eq!(squared.element(i), x.square());
+            // x and -x are mapped to the same point
+            assert_eq!(domain.element(i + 4), x.neg());
+        }
+
+        // x * ζ^m are mapped to the same point by x^4, ζ is a 4-th root of unity
+        let pow = domain.pow(4);
+        assert_eq!((pow.size, pow.log_size), (2, 1));
+        for (i, x) in domain.elements().iter().enumerate() {
+            assert_eq!(pow.element(i), x.pow_vartime(&[4, 0, 0, 0]));
+        }
+        assert_eq!(domain.pow(1), domain);
+        assert_eq!(domain.pow(2), squared);
+    }
+
+    #[test]
+    fn test_fft_on_coset() {
+        let domain = EvaluationDomain::new(32).to_coset(Scalar::MULTIPLICATIVE_GENERATOR);
+        let coeffs = (0..32).map(|_| Scalar::random(OsRng)).collect::<Vec<_>>();
+        assert_eq!(domain.ifft_on_coset(&domain.fft_on_coset(&coeffs)), coeffs);
+
+        // the codeword on the coset is the one of p(offset * X) on H.
+        let poly = Polynomial::from_coeffs(coeffs);
+        assert_eq!(
+            domain.fft_on_coset(&poly.coeffs()),
+            domain.subgroup().evaluate(&poly.scale_arg(domain.offset))
+        );
+    }
+
+    #[test]
+    fn test_coset_is_disjoint() {
+        let h = EvaluationDomain::new(16);
+        let lde = EvaluationDomain::coset(128, Scalar::MULTIPLICATIVE_GENERATOR);
+        assert!(lde.is_disjoint(&h) && h.is_disjoint(&lde));
+        // Z_H is never zero on the coset, so the quotient by Z_H is well defined.
+        let z_h = h.vanishing_poly();
+        assert!(lde
+            .elements()
+            .iter()
+            .all(|x| z_h.evaluate(*x) != Scalar::zero()));
+
+        // each folded domain is still disjoint with the subgroup of the same size.
+        let mut d = lde;
+        while d.size > 1 {
+            assert!(d.is_disjoint(&d.subgroup()));
+            d = d.square();
+        }
+
+        // an offset in H gives H itself, and H^2 is in H.
+        let shifted = h.to_coset(h.generator);
+        assert!(!shifted.is_disjoint(&h));
+        assert!(!h.square().is_disjoint(&h));
+        assert!(!h.is_disjoint(&EvaluationDomain::new(64)));
+    }
+}
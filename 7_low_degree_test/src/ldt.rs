@@ -1,30 +1,41 @@
+pub mod batch;
+#[cfg(all(feature = "std", feature = "prover"))]
 pub mod prover;
+#[cfg(feature = "std")]
 pub mod verifier;
 
+#[cfg(all(feature = "std", feature = "prover"))]
 use self::prover::Prover;
+#[cfg(all(feature = "std", feature = "prover"))]
 use self::verifier::Verifier;
 use crate::merkle_tree::proof::MerkleProof;
+#[cfg(all(feature = "std", feature = "prover"))]
 use crate::poly::*;
+use alloc::vec::Vec;
 use bls12_381::Scalar;
+#[cfg(all(feature = "std", feature = "prover"))]
 use ff::Field;
+#[cfg(all(feature = "std", feature = "prover"))]
 use rand_core::OsRng;
-use std::env::consts::OS;
-use std::iter::Scan;
 
 #[derive(Default)]
 pub struct LDTProof {
-    pub commits: Vec<MerkleProof>,    // commit of fi
+    pub commits: Vec<Vec<MerkleProof>>, // commit of fi, per round and per query
     pub evals: Vec<(Scalar, Scalar)>, // The open values on challenge z for fi: (f0(z), f0(−z)), f1(z^2), f1(−z^2)
     pub last_const: (Scalar, Scalar), // (p_L, p_R)
 }
 
 // Both P and V have oracle access to function f.
 // V wants to test if f is polynomial with deg(f) ≤ d.
+//
+// The poly and z are sampled by OsRng, and V prints the rounds, so it's std only.
+#[cfg(all(feature = "std", feature = "prover"))]
 pub struct LDT {
     prover: Prover,
     verifier: Verifier,
 }
 
+#[cfg(all(feature = "std", feature = "prover"))]
 impl LDT {
     pub fn new(degree: usize) -> Self {
         let poly = random_poly(degree);
@@ -44,7 +55,7 @@ impl LDT {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std", feature = "prover"))]
 mod test {
     use crate::ldt::LDT;
 
@@ -0,0 +1,1766 @@
+// The batched FRI-LDT over codewords.
+//
+// Given codewords f_0, ..., f_{m-1} over the domain D (|D| = N), P claims every f_k is the
+// evaluation of a poly with deg < d. Instead of testing them one by one, V picks γ and tests
+//      f = ∑ γ^k * f_k
+// which has deg < d (with high probability) only if every f_k has. The coeffs are the powers of γ
+// by default, or independent ones with `LdtParams::coeffs`, see `sumcheck::rlc`.
+//
+// Commit phase, for i in [0, log(d)):
+//      f_{i+1}(x^2) = fi_L(x^2) + αi * fi_R(x^2), where
+//      fi_L(x^2) = (fi(x) + fi(-x)) / 2
+//      fi_R(x^2) = (fi(x) - fi(-x)) / 2x
+// so f_{i+1} lives on D^2 = {x^2 | x ∈ D}, which has half size, and deg(f_{i+1}) < d / 2^(i+1).
+// After log(d) rounds f is a constant, P sends it in clear.
+//
+// Query phase: V picks j ∈ [0, N/2), P opens (fi(x), fi(-x)) in each round, V checks the folding.
+//
+// With the folding factor k = 2^s, split fi(x) = ∑ x^m * fi_m(x^k), m in [0, k), and
+//      f_{i+1}(x^k) = ∑ αi^m * fi_m(x^k)
+// so there are log_k(d) rounds. Let ζ be a k-th root of unity, P opens fi(x * ζ^m) in each
+// round, which are the k points of P(X) = ∑ X^m * fi_m(x^k) with deg < k, and f_{i+1}(x^k) = P(αi).
+// k = 2 is the fold above, where ζ = -1.
+//
+// P and V absorb the `ProtocolId` over (domain, d, queries num, k, coeffs mode) first, see
+// `protocol_id`, and the proof carries its digest, so a proof for other params is rejected up
+// front.
+use crate::domain::EvaluationDomain;
+use crate::merkle_tree::hasher::Digest;
+use crate::merkle_tree::proof::MerkleProof;
+use crate::merkle_tree::{MerkleError, MerkleTree, MultiVerifier};
+use crate::trace::{TraceEvent, TraceSink, VerificationTrace};
+use crate::transcript::default::Keccak256Transcript;
+use crate::transcript::Transcript;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use core::fmt;
+use ff::PrimeField;
+use sumcheck::cost::CostReport;
+use sumcheck::rlc::{self, SoundnessBudget};
+use sumcheck::utils::batch_invert;
+use Fiat_Shamir::challenge_to_index;
+use Fiat_Shamir::codec::{encode_digest, encode_scalar, encode_u64};
+use Fiat_Shamir::protocol::ProtocolId;
+
+mod codec;
+mod layout;
+#[cfg(feature = "prover")]
+mod prover;
+mod state;
+mod structure;
+
+#[cfg(all(test, feature = "prover"))]
+mod fuzz;
+
+pub use codec::{openings_from_bytes, openings_to_bytes, LDT_PROOF_VERSION, OPENINGS_VERSION};
+pub use layout::RoundStructure;
+#[cfg(feature = "prover")]
+pub use prover::{
+    fold_codeword, fold_codeword_in_place, fold_codeword_k, fold_codeword_k_in_place,
+    CommittedCodeword,
+};
+pub use state::LdtVerifierState;
+// the mode of `LdtParams::coeffs`.
+pub use sumcheck::rlc::CoeffMode;
+
+/// The opened value of a codeword at the index.
+///
+/// The proof is typed by the leaf, so the opening of a tree of another encoding, eg: the chars of
+/// the merkle demo, can't be taken for the one of a codeword:
+/// ```compile_fail
+/// use bls12_381::Scalar;
+/// use low_degree_test::ldt::batch::Opening;
+/// use low_degree_test::merkle_tree::proof::MerkleProof;
+/// use low_degree_test::merkle_tree::MerkleTree;
+///
+/// let tree = MerkleTree::commit(&['a', 'b']);
+/// let proof: MerkleProof<char> = tree.open_by_index(0);
+/// let opening = Opening { index: 0, value: Scalar::zero(), proof };
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Opening {
+    pub index: usize,
+    pub value: Scalar,
+    pub proof: MerkleProof,
+}
+
+impl Opening {
+    pub fn verify(&self, root: &Digest) {
+        assert_eq!(&self.proof.root, root, "Verifier: wrong merkle root");
+        assert_eq!(self.proof.index, self.index, "Verifier: wrong leaf index");
+        assert!(self.check(root), "Verifier: verify failed!");
+    }
+
+    // The same as `verify`, but returns the result instead of panic.
+    pub fn check(&self, root: &Digest) -> bool {
+        self.try_check(root).is_ok()
+    }
+
+    // The same as `check`, but returns why the opening is rejected.
+    pub fn try_check(&self, root: &Digest) -> Result<(), MerkleError> {
+        if self.proof.index != self.index {
+            return Err(MerkleError::IndexMismatch {
+                expected: self.index,
+                found: self.proof.index,
+            });
+        }
+        // the bytes of the scalar are its repr, on the stack.
+        MerkleTree::check(root, &self.proof, self.value.to_repr().as_ref())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueryProof {
+    pub index: usize,              // j ∈ [0, N/k)
+    pub inputs: Vec<Vec<Opening>>, // the input codeword at x * ζ^m, m in [0, k) for each one
+    pub layers: Vec<Vec<Opening>>, // fi(x * ζ^m), m in [0, k) for i in [1, log_k(d))
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BatchLDTProof {
+    pub protocol: [u8; 32],       // the digest of `BatchLDT::protocol_id`
+    pub roots: Vec<Scalar>,       // commits of the input codewords
+    pub layer_roots: Vec<Scalar>, // commits of fi, i in [1, log_k(d))
+    pub queries: Vec<QueryProof>,
+    pub last_const: Scalar,
+}
+
+impl BatchLDTProof {
+    // Number of the scalars in the proof, each one is 32 bytes.
+    pub fn num_scalars(&self) -> usize {
+        let opening_size = |o: &Opening| 1 + o.proof.children.len() + 1;
+        let pairs = |v: &Vec<Vec<Opening>>| v.iter().flatten().map(opening_size).sum::<usize>();
+        self.roots.len()
+            + self.layer_roots.len()
+            + self
+                .queries
+                .iter()
+                .map(|q| pairs(&q.inputs) + pairs(&q.layers))
+                .sum::<usize>()
+            + 1
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LDTError {
+    WrongInputCommits,
+    WrongQueryIndex {
+        query: usize,
+    },
+    // the opening of the index in the round is rejected by the commit of the round.
+    QueryOpening {
+        round: usize,
+        index: usize,
+        source: MerkleError,
+    },
+    FoldCheckFailed {
+        round: usize,
+    },
+    LastRoundCheckFailed,
+    // the proof is of other params, see `BatchLDT::protocol_id`.
+    ProtocolMismatch,
+    // the shape of the proof, see `BatchLDTProof::validate_structure`: the counts of the layer
+    // roots, the queries and the cosets of a query,
+    WrongRoundCount {
+        expected: usize,
+        found: usize,
+    },
+    WrongQueryCount {
+        expected: usize,
+        found: usize,
+    },
+    WrongInputCount {
+        query: usize,
+        expected: usize,
+        found: usize,
+    },
+    WrongLayerCount {
+        query: usize,
+        expected: usize,
+        found: usize,
+    },
+    // and the openings of the cosets, by the round and the opened position.
+    PositionOutOfRange {
+        round: usize,
+        position: usize,
+        size: usize,
+    },
+    DuplicateOpening {
+        round: usize,
+        position: usize,
+    },
+    UnexpectedOpening {
+        round: usize,
+        position: usize,
+    },
+    MissingOpening {
+        round: usize,
+        position: usize,
+    },
+    MisplacedOpening {
+        round: usize,
+        position: usize,
+    },
+    WrongPathLength {
+        round: usize,
+        position: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for LDTError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LDTError::WrongInputCommits => write!(f, "Verifier: wrong input commits"),
+            LDTError::WrongQueryIndex { query } => {
+                write!(f, "Verifier: wrong query index in query-{query}")
+            }
+            LDTError::QueryOpening { round, index, .. } => {
+                write!(f, "Verifier: round-{round} merkle check failed at {index}.")
+            }
+            LDTError::FoldCheckFailed { round } => {
+                write!(f, "Verifier: round-{round} fold check failed.")
+            }
+            LDTError::LastRoundCheckFailed => write!(f, "Verifier: Last round check failed."),
+            LDTError::ProtocolMismatch => {
+                write!(f, "Verifier: the proof is of another protocol or params.")
+            }
+            LDTError::WrongRoundCount { expected, found } => {
+                write!(f, "Verifier: {found} layer roots, the folds need {expected}.")
+            }
+            LDTError::WrongQueryCount { expected, found } => {
+                write!(f, "Verifier: {found} queries, expected {expected}.")
+            }
+            LDTError::WrongInputCount {
+                query,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Verifier: query-{query} opens {found} input codewords, expected {expected}."
+            ),
+            LDTError::WrongLayerCount {
+                query,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Verifier: query-{query} opens {found} layers, expected {expected}."
+            ),
+            LDTError::PositionOutOfRange {
+                round,
+                position,
+                size,
+            } => write!(
+                f,
+                "Verifier: round-{round} opens {position}, out of the domain of size {size}."
+            ),
+            LDTError::DuplicateOpening { round, position } => {
+                write!(f, "Verifier: round-{round} opens {position} twice.")
+            }
+            LDTError::UnexpectedOpening { round, position } => {
+                write!(f, "Verifier: round-{round} opens {position}, which is not queried.")
+            }
+            LDTError::MissingOpening { round, position } => {
+                write!(f, "Verifier: round-{round} doesn't open {position}.")
+            }
+            LDTError::MisplacedOpening { round, position } => {
+                write!(f, "Verifier: round-{round} opens {position} out of its coset order.")
+            }
+            LDTError::WrongPathLength {
+                round,
+                position,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Verifier: round-{round} opens {position} by {found} siblings, not {expected}."
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LDTError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LDTError::QueryOpening { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+// The challenges of an accepted proof, eg: to check the constraints of the caller on the same
+// positions, or to feed the αi to another protocol.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VerifiedLdt {
+    pub alphas: Vec<Scalar>,            // αi of each round
+    pub query_indices: Vec<Vec<usize>>, // (j_0, ..., j_{r-1}) of each query, see `query_path`
+}
+
+impl VerifiedLdt {
+    // j_0 of each query, the index in the domain.
+    pub fn indices(&self) -> Vec<usize> {
+        self.query_indices.iter().map(|path| path[0]).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LdtParams {
+    pub folding_factor: usize, // k = 2^s, fi is folded into f_{i+1} over D^k
+    pub coeffs: CoeffMode,     // the coeffs of f = ∑ c_k * f_k
+}
+
+impl Default for LdtParams {
+    fn default() -> Self {
+        Self {
+            folding_factor: 2,
+            coeffs: CoeffMode::Powers,
+        }
+    }
+}
+
+pub struct BatchLDT {
+    pub domain: EvaluationDomain,
+    pub degree_bound: usize, // d, the codewords have deg < d
+    pub num_queries: usize,
+    pub params: LdtParams,
+}
+
+impl BatchLDT {
+    pub fn new(domain: EvaluationDomain, degree_bound: usize, num_queries: usize) -> Self {
+        Self::with_params(domain, degree_bound, num_queries, LdtParams::default())
+    }
+
+    pub fn with_params(
+        domain: EvaluationDomain,
+        degree_bound: usize,
+        num_queries: usize,
+        params: LdtParams,
+    ) -> Self {
+        let k = params.folding_factor;
+        assert!(
+            k.is_power_of_two() && k > 1,
+            "folding factor must be 2^s, s > 0"
+        );
+        assert!(
+            params.rounds(degree_bound).is_some(),
+            "degree bound must be k^r, r > 0"
+        );
+        assert!(
+            domain.size > degree_bound,
+            "domain size must be larger than the degree bound"
+        );
+        Self {
+            domain,
+            degree_bound,
+            num_queries,
+            params,
+        }
+    }
+
+    // The protocol of the proofs, over every param the challenges or the checks depend on.
+    pub fn protocol_id(&self) -> ProtocolId {
+        let mut params = encode_u64(self.domain.size as u64).to_vec();
+        params.extend(encode_scalar(&self.domain.offset));
+        params.extend(encode_u64(self.degree_bound as u64));
+        params.extend(encode_u64(self.num_queries as u64));
+        params.extend(encode_u64(self.folding_factor() as u64));
+        // the independent coeffs only, so the ids of the powers are the ones before the mode.
+        if self.params.coeffs == CoeffMode::Independent {
+            params.push(1);
+        }
+        ProtocolId::new("low_degree_test", "batch_ldt", LDT_PROOF_VERSION, &params)
+    }
+
+    // Add the soundness errors of a proof of m codewords to the budget, in the unique decoding
+    // regime, where ρ = d/N is the rate:
+    //      γ:       see `rlc::CoeffMode`
+    //      αi:      |D_i|/|F| of each fold, a far fi folds into a close f_{i+1} by a bad αi only
+    //      queries: ((1 + ρ)/2)^q, a far f passes each query with probability < 1 - (1 - ρ)/2
+    pub fn soundness(&self, m: usize, budget: &mut SoundnessBudget) {
+        budget.add_rlc("ldt.gamma", m, self.params.coeffs);
+        let mut size = self.domain.size;
+        for _ in 0..self.rounds() {
+            budget.add_field_error("ldt.fold", size as u64);
+            size /= self.folding_factor();
+        }
+        let rate = self.degree_bound as f64 / self.domain.size as f64;
+        let mut queries = 1.0;
+        for _ in 0..self.num_queries {
+            queries *= (1.0 + rate) / 2.0;
+        }
+        budget.add("ldt.queries", queries);
+    }
+
+    fn folding_factor(&self) -> usize {
+        self.params.folding_factor
+    }
+
+    // log_k(d)
+    fn rounds(&self) -> usize {
+        self.params
+            .rounds(self.degree_bound)
+            .expect("the degree bound is k^r, see `with_params`")
+    }
+
+    // The index of the fold in each round: j_0 = index ∈ [0, N/k), j_{i+1} = j_i % (|D_i| / k^2),
+    // where D_i = D^(k^i). In round i, fi is opened on the coset of j_i, and the fold is at j_i
+    // of D_{i+1}, which is in the coset of j_{i+1}, see `layout`.
+    fn query_path(&self, index: usize) -> Vec<usize> {
+        let mut j = index;
+        let mut path = Vec::with_capacity(self.rounds());
+        for i in 0..self.rounds() {
+            path.push(j);
+            if i + 1 < self.rounds() {
+                j = self.fold_index(i + 1, self.fold_index(i, j));
+            }
+        }
+        path
+    }
+
+    // Verify the proof, return the αi and the queried indices, which the caller can use to
+    // check its own constraints on the same positions, see `VerifiedLdt::indices`.
+    pub fn verify(&self, roots: &[Scalar], proof: &BatchLDTProof) -> VerifiedLdt {
+        match self.verify_with_sink(roots, proof, &mut ()) {
+            Ok(verified) => {
+                #[cfg(feature = "std")]
+                println!("Verifier accepted the proof");
+                verified
+            }
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    // Verify the proof, and return the trace of every check and challenge along with the result.
+    pub fn verify_with_trace(
+        &self,
+        roots: &[Scalar],
+        proof: &BatchLDTProof,
+    ) -> (Result<VerifiedLdt, LDTError>, VerificationTrace) {
+        // γ and α_i, then for each query: the index, k merkle checks per input codeword,
+        // k merkle checks and a fold check per layer, and the last round check.
+        let (k, rounds) = (self.folding_factor(), self.rounds());
+        let per_query = 1 + k * roots.len() + (k + 1) * (rounds - 1) + 1;
+        let mut trace = VerificationTrace::with_capacity(1 + rounds + self.num_queries * per_query);
+        let res = self.verify_with_sink(roots, proof, &mut trace);
+        (res, trace)
+    }
+
+    // Verify the proof, and return the ops of V along with the result, see `sumcheck::cost`.
+    #[cfg(any(test, feature = "cost"))]
+    pub fn verify_with_cost(
+        &self,
+        roots: &[Scalar],
+        proof: &BatchLDTProof,
+    ) -> (Result<VerifiedLdt, LDTError>, CostReport) {
+        let mut cost = CostReport::default();
+        let res = self.verify_with_sink(roots, proof, &mut cost);
+        (res, cost)
+    }
+
+    // Each check and challenge is reported to the sink in order, it stops at the first failure.
+    // The shape of the proof is validated before the query phase, see `validate_structure`, so a
+    // malformed query is rejected before the openings of any query are checked.
+    pub fn verify_with_sink<S: TraceSink>(
+        &self,
+        roots: &[Scalar],
+        proof: &BatchLDTProof,
+        sink: &mut S,
+    ) -> Result<VerifiedLdt, LDTError> {
+        let mut transcript = Keccak256Transcript::default();
+        let challenges = self.replay_commit(&mut transcript, roots, proof, sink)?;
+
+        // 2. derive the queries, which depend on the transcript only, so the x of all the folds
+        // are inverted at once. The folds by 2 are checked without inversion, see `fold_check`.
+        let (k, rounds) = (self.folding_factor(), self.rounds());
+        let derived = (0..self.num_queries)
+            .map(|_| challenge_to_index(&transcript.challenge().to_bytes(), self.domain.size / k))
+            .collect::<Vec<_>>();
+        sink.count(CostReport::hashes(2).times(self.num_queries));
+        proof.validate_structure(self, &derived)?;
+        let points = self.fold_points(&derived, sink);
+        let x_inv = self.fold_inverses(&points, sink);
+
+        // 3. check each query
+        let mut query_indices = Vec::with_capacity(self.num_queries);
+        for (q, (query, index)) in proof.queries.iter().zip(derived).enumerate() {
+            let range = q * rounds..(q + 1) * rounds;
+            let x = FoldPoints {
+                x: &points[range.clone()],
+                x_inv: x_inv.get(range).unwrap_or(&[]),
+            };
+            self.check_query(sink, (q, index), query, x, proof, &challenges)?;
+            query_indices.push(self.query_path(index));
+        }
+        Ok(VerifiedLdt {
+            alphas: challenges.alphas,
+            query_indices,
+        })
+    }
+
+    // What `verify` returns on success, from the transcript of the commit phase only, so no
+    // opening or fold is checked, eg: to inspect a proof cheaply. The roots are the ones of the
+    // proof, and the queries are not read. It panics if the proof is of other params.
+    pub fn derive_challenges_only(&self, proof: &BatchLDTProof) -> VerifiedLdt {
+        let mut transcript = Keccak256Transcript::default();
+        let challenges = self
+            .replay_commit(&mut transcript, &proof.roots, proof, &mut ())
+            .unwrap_or_else(|e| panic!("{}", e));
+        let n = self.domain.size / self.folding_factor();
+        let query_indices = (0..self.num_queries)
+            .map(|_| self.query_path(challenge_to_index(&transcript.challenge().to_bytes(), n)))
+            .collect();
+        VerifiedLdt {
+            alphas: challenges.alphas,
+            query_indices,
+        }
+    }
+
+    // 1. replay the transcript of the commit phase, which derives γ and the αi.
+    fn replay_commit<S: TraceSink>(
+        &self,
+        transcript: &mut Keccak256Transcript,
+        roots: &[Scalar],
+        proof: &BatchLDTProof,
+        sink: &mut S,
+    ) -> Result<FoldChallenges, LDTError> {
+        let rounds = self.rounds();
+        let id = self.protocol_id();
+        // the digest of the params, and the one of the id.
+        sink.count(CostReport::hashes(2));
+        if proof.protocol != id.digest() {
+            return Err(LDTError::ProtocolMismatch);
+        }
+        transcript.bind_protocol(&id);
+        sink.count(CostReport::comparisons(roots.len()));
+        if proof.roots[..] != roots[..] {
+            return Err(LDTError::WrongInputCommits);
+        }
+        self.check_round_count(proof)?;
+
+        for root in roots.iter() {
+            absorb_root(transcript, root);
+        }
+        let mode = self.params.coeffs;
+        let gammas = rlc::squeeze_challenges(transcript, GAMMA_LABEL, roots.len(), mode);
+        sink.count(coeffs_cost(mode, roots.len()));
+        for (k, gamma) in gammas.iter().enumerate() {
+            sink.record(TraceEvent::ChallengeDerived {
+                name: "gamma",
+                round: k,
+                value: *gamma,
+            });
+        }
+        let mut alphas = Vec::with_capacity(rounds);
+        for i in 0..rounds {
+            let alpha_i = transcript.challenge();
+            sink.count(CostReport::hashes(2));
+            sink.record(TraceEvent::ChallengeDerived {
+                name: "alpha",
+                round: i,
+                value: alpha_i,
+            });
+            alphas.push(alpha_i);
+            if i + 1 < rounds {
+                absorb_root(transcript, &proof.layer_roots[i]);
+            }
+        }
+        absorb_last_const(transcript, &proof.last_const);
+        sink.count(FoldChallenges::cost(self.folding_factor()));
+        let gammas = (mode, gammas, roots.len());
+        Ok(FoldChallenges::new(self.folding_factor(), gammas, alphas))
+    }
+
+    // 3. check the q-th query at the derived index, at its fold points.
+    // Only the commit phase of the proof is read, eg: the roots and the last constant.
+    fn check_query<S: TraceSink>(
+        &self,
+        sink: &mut S,
+        (q, index): (usize, usize),
+        query: &QueryProof,
+        x: FoldPoints,
+        commit: &BatchLDTProof,
+        challenges: &FoldChallenges,
+    ) -> Result<(), LDTError> {
+        let (k, rounds) = (self.folding_factor(), self.rounds());
+        let roots = &commit.roots;
+        let ok = query.index == index;
+        sink.record(TraceEvent::QueryDerived {
+            query: q,
+            index,
+            ok,
+        });
+        if !ok {
+            return Err(LDTError::WrongQueryIndex { query: q });
+        }
+        debug_assert_eq!(query.inputs.len(), roots.len(), "see `validate_query`");
+        debug_assert_eq!(query.layers.len(), rounds - 1, "see `validate_query`");
+
+        // 3.1 the batched f(x * ζ^m)
+        let positions = self.conjugate_indices(0, index);
+        let mut cosets = Vec::with_capacity(roots.len());
+        for (coset, root) in query.inputs.iter().zip(roots.iter()) {
+            check_coset(sink, 0, coset, &positions, root)?;
+            cosets.push(coset.iter().map(|o| o.value).collect::<Vec<_>>());
+            sink.count(CostReport::mults(k) + CostReport::adds(k));
+        }
+        let mut values =
+            rlc::combine_vectors(&cosets, &challenges.coeffs).expect("a coeff per codeword");
+
+        // 3.2 fold round by round
+        let alphas = &challenges.alphas;
+        let path = self.query_path(index);
+        for (i, coset) in query.layers.iter().enumerate() {
+            let positions = self.conjugate_indices(i + 1, path[i + 1]);
+            check_coset(sink, i + 1, coset, &positions, &commit.layer_roots[i])?;
+
+            // the folded value must be consistent with the commit of f_{i+1}
+            let fold = self.fold_index(i, path[i]);
+            let m = positions.iter().position(|p| *p == fold);
+            let expected = coset[m.expect("the fold is in the coset of j_(i+1)")].value;
+            let (lhs, rhs) = fold_sides(sink, &values, alphas[i], (&x, i), expected, challenges);
+            let ok = lhs == rhs;
+            sink.count(CostReport::comparisons(1));
+            sink.record(TraceEvent::RoundChecked {
+                round: i,
+                lhs,
+                rhs,
+                ok,
+            });
+            if !ok {
+                return Err(LDTError::FoldCheckFailed { round: i });
+            }
+            values = coset.iter().map(|o| o.value).collect::<Vec<_>>();
+        }
+        let last = (&x, rounds - 1);
+        let (lhs, rhs) = fold_sides(
+            sink,
+            &values,
+            alphas[rounds - 1],
+            last,
+            commit.last_const,
+            challenges,
+        );
+        let ok = lhs == rhs;
+        sink.count(CostReport::comparisons(1));
+        sink.record(TraceEvent::FinalChecked { lhs, rhs, ok });
+        if !ok {
+            return Err(LDTError::LastRoundCheckFailed);
+        }
+        Ok(())
+    }
+
+    // The x of the folds in the query phase, `rounds` for each query: the j_0-th element of D,
+    // then the j_1-th of D^k, ..., the same as the checks walk through the layers.
+    fn fold_points<S: TraceSink>(&self, indices: &[usize], sink: &mut S) -> Vec<Scalar> {
+        let k = self.folding_factor();
+        let mut points = Vec::with_capacity(indices.len() * self.rounds());
+        for &index in indices {
+            let mut domain = self.domain;
+            for j in self.query_path(index) {
+                points.push(domain.element(j));
+                // offset * ω^j
+                let exp = [(j % domain.size) as u64, 0, 0, 0];
+                sink.count(CostReport::pow_vartime(&exp) + CostReport::mults(1));
+                domain = domain.pow(k);
+                sink.count(domain_pow_cost(k));
+            }
+        }
+        points
+    }
+
+    // The inverses of the x of the folds, batch inverted, none if k = 2, see `fold_sides`.
+    fn fold_inverses<S: TraceSink>(&self, points: &[Scalar], sink: &mut S) -> Vec<Scalar> {
+        if self.folding_factor() == 2 {
+            return Vec::new();
+        }
+        let mut x_inv = points.to_vec();
+        batch_invert(&mut x_inv).expect("the domain has no zero");
+        sink.count(CostReport::batch_invert(x_inv.len()));
+        x_inv
+    }
+}
+
+// D^k by log(k) squarings of the generator and the offset.
+fn domain_pow_cost(k: usize) -> CostReport {
+    CostReport::mults(2 * k.trailing_zeros() as usize)
+}
+
+// See `fold_k_with_inverses`: α/x, then per value: u, the k steps of Horner's rule for the
+// geometric sum, the product with the value and the sum; and 1/k * the sum.
+fn fold_k_cost(k: usize) -> CostReport {
+    CostReport::mults(k * (k + 2) + 2) + CostReport::adds(k * (k + 1))
+}
+
+// See `fold_check`: 3 mults and 4 adds.
+fn fold_check_cost() -> CostReport {
+    CostReport::mults(3) + CostReport::adds(4)
+}
+
+// The x of the folds of a query, and their inverses if k > 2.
+struct FoldPoints<'a> {
+    x: &'a [Scalar],
+    x_inv: &'a [Scalar],
+}
+
+// The two sides of the check that the fold of the values at the x of round i is `next`: the fold
+// and `next` if k > 2, and the sides of `fold_check` by 2, which need no inversion.
+fn fold_sides<S: TraceSink>(
+    sink: &mut S,
+    values: &[Scalar],
+    alpha: Scalar,
+    (points, i): (&FoldPoints, usize),
+    next: Scalar,
+    challenges: &FoldChallenges,
+) -> (Scalar, Scalar) {
+    if values.len() == 2 {
+        sink.count(fold_check_cost());
+        let (lhs, rhs) = fold_check_sides(values[0], values[1], next, alpha, points.x[i]);
+        debug_assert_eq!(
+            lhs == rhs,
+            fold(values[0], values[1], alpha, points.x[i]) == next
+        );
+        return (lhs, rhs);
+    }
+    sink.count(fold_k_cost(values.len()));
+    let (k_inv, zeta_inv) = (challenges.k_inv, &challenges.zeta_inv);
+    let folded = fold_k_with_inverses(values, alpha, k_inv, points.x_inv[i], zeta_inv);
+    (folded, next)
+}
+
+// The challenges of the commit phase, and the constants of the folds in the query phase.
+struct FoldChallenges {
+    gammas: Vec<Scalar>, // the squeezed ones, eg: γ of the powers, see `rlc::squeeze_challenges`
+    coeffs: Vec<Scalar>, // c_k of each codeword
+    alphas: Vec<Scalar>,
+    k_inv: Scalar,
+    zeta_inv: Vec<Scalar>,
+}
+
+impl FoldChallenges {
+    // gammas: (mode, the squeezed challenges, the codewords num)
+    fn new(
+        k: usize,
+        (mode, gammas, m): (CoeffMode, Vec<Scalar>, usize),
+        alphas: Vec<Scalar>,
+    ) -> Self {
+        Self {
+            coeffs: rlc::coeffs_from_challenges(mode, &gammas, m),
+            gammas,
+            alphas,
+            k_inv: Scalar::from(k as u64).invert().unwrap(),
+            zeta_inv: inverse_roots_of_unity(k),
+        }
+    }
+
+    // 1/k, and the ζ^(-m), see `inverse_roots_of_unity`: the squarings of the root of unity
+    // down to ζ, then ζ^(k-m) of each m.
+    fn cost(k: usize) -> CostReport {
+        let zeta = CostReport::mults((Scalar::S - k.trailing_zeros()) as usize);
+        let elements = (0..k)
+            .map(|m| {
+                CostReport::pow_vartime(&[((k - m) % k) as u64, 0, 0, 0]) + CostReport::mults(1)
+            })
+            .fold(CostReport::default(), |acc, c| acc + c);
+        CostReport::inversions(1) + zeta + elements
+    }
+}
+
+// The opened fi(x * ζ^m) are on the positions of `conjugate_indices`, see `validate_query`, and
+// must be consistent with the commit. They are checked as one batch, so the nodes their paths
+// share are hashed once, see `merkle_tree::multi`.
+fn check_coset<S: TraceSink>(
+    sink: &mut S,
+    round: usize,
+    coset: &[Opening],
+    positions: &[usize],
+    root: &Scalar,
+) -> Result<(), LDTError> {
+    debug_assert!(coset.iter().map(|o| o.index).eq(positions.iter().copied()));
+    let mut batch = MultiVerifier::new();
+    for o in coset.iter() {
+        batch.add(*root, o.index, &o.value, &o.proof);
+    }
+    let (res, hashes) = batch.check_counted();
+    let rejected = res.err();
+    // the openings up to the rejected one are checked, each one ends with a compare.
+    let checked = rejected.map_or(coset.len(), |e| e.claim + 1);
+    sink.count(CostReport::hashes(hashes) + CostReport::comparisons(checked));
+    for (c, o) in coset[..checked].iter().enumerate() {
+        sink.record(TraceEvent::MerkleChecked {
+            round,
+            index: o.index,
+            ok: rejected.is_none_or(|e| e.claim != c),
+        });
+    }
+    match rejected {
+        Some(e) => Err(LDTError::QueryOpening {
+            round,
+            index: coset[e.claim].index,
+            source: e.source,
+        }),
+        None => Ok(()),
+    }
+}
+
+// f_{i+1}(x^2) = (fi(x) + fi(-x)) / 2 + α * (fi(x) - fi(-x)) / 2x
+pub fn fold(f_x: Scalar, f_neg_x: Scalar, alpha: Scalar, x: Scalar) -> Scalar {
+    let two_inv = Scalar::from(2).invert().unwrap();
+    fold_with_inverses(f_x, f_neg_x, alpha, two_inv, x.invert().unwrap())
+}
+
+// The same as `fold`, with 1/2 and 1/x given, eg: batch inverted by V.
+pub fn fold_with_inverses(
+    f_x: Scalar,
+    f_neg_x: Scalar,
+    alpha: Scalar,
+    two_inv: Scalar,
+    x_inv: Scalar,
+) -> Scalar {
+    (f_x + f_neg_x) * two_inv + alpha * (f_x - f_neg_x) * two_inv * x_inv
+}
+
+// Whether f_next = fold(f_x, f_neg_x, α, x), by `fold` times 2x, so without any inversion:
+//      2x * f_{i+1}(x^2) = x * (fi(x) + fi(-x)) + α * (fi(x) - fi(-x))
+// x = 0 is in no domain, and both sides are α * (fi(x) - fi(-x)) there whatever f_next is, so
+// it's rejected first.
+pub fn fold_check(f_x: Scalar, f_neg_x: Scalar, f_next: Scalar, alpha: Scalar, x: Scalar) -> bool {
+    if x == Scalar::zero() {
+        return false;
+    }
+    let (lhs, rhs) = fold_check_sides(f_x, f_neg_x, f_next, alpha, x);
+    debug_assert_eq!(lhs == rhs, fold(f_x, f_neg_x, alpha, x) == f_next);
+    lhs == rhs
+}
+
+// (x * (fi(x) + fi(-x)) + α * (fi(x) - fi(-x)), 2x * f_next)
+fn fold_check_sides(
+    f_x: Scalar,
+    f_neg_x: Scalar,
+    f_next: Scalar,
+    alpha: Scalar,
+    x: Scalar,
+) -> (Scalar, Scalar) {
+    (
+        x * (f_x + f_neg_x) + alpha * (f_x - f_neg_x),
+        x.double() * f_next,
+    )
+}
+
+// f_{i+1}(x^k) = P(α), where P is the poly with deg < k on (x * ζ^m, fi(x * ζ^m)), m in [0, k).
+// P(X) = ∑ X^j * fi_j(x^k), and x^j * fi_j(x^k) = 1/k * ∑ ζ^(-mj) * fi(x * ζ^m), so
+//      P(α) = 1/k * ∑ fi(x * ζ^m) * ∑ (α / (x * ζ^m))^j
+// eg: k = 2 is `fold`.
+pub fn fold_k(values: &[Scalar], alpha: Scalar, x: Scalar) -> Scalar {
+    let k = values.len();
+    let k_inv = Scalar::from(k as u64).invert().unwrap();
+    fold_k_with_inverses(
+        values,
+        alpha,
+        k_inv,
+        x.invert().unwrap(),
+        &inverse_roots_of_unity(k),
+    )
+}
+
+// The same as `fold_k`, with 1/k, 1/x and ζ^(-m) given.
+pub fn fold_k_with_inverses(
+    values: &[Scalar],
+    alpha: Scalar,
+    k_inv: Scalar,
+    x_inv: Scalar,
+    zeta_inv: &[Scalar],
+) -> Scalar {
+    assert_eq!(values.len(), zeta_inv.len());
+    let t = alpha * x_inv;
+    let sum = values
+        .iter()
+        .zip(zeta_inv.iter())
+        .map(|(v, z)| {
+            // ∑ u^j, j in [0, k)
+            let u = t * z;
+            let geometric = zeta_inv
+                .iter()
+                .fold(Scalar::zero(), |acc, _| acc * u + Scalar::one());
+            v * geometric
+        })
+        .fold(Scalar::zero(), |acc, v| acc + v);
+    sum * k_inv
+}
+
+// ζ^(-m), m in [0, k), where ζ is the primitive k-th root of unity, which is ω^(N/k) of any
+// domain with size N.
+fn inverse_roots_of_unity(k: usize) -> Vec<Scalar> {
+    let zeta = EvaluationDomain::new(k);
+    (0..k).map(|m| zeta.element(k - m)).collect()
+}
+
+// The label of the independent coeffs, see `rlc::coeffs_from_transcript`.
+pub(crate) const GAMMA_LABEL: &str = "ldt.gamma";
+
+// The squeezes of the coeffs of m codewords: γ is a scalar challenge (2 hashes) and its m powers,
+// the independent ones are the seed and a Keccak512 per coeff.
+fn coeffs_cost(mode: CoeffMode, m: usize) -> CostReport {
+    match mode {
+        CoeffMode::Powers => CostReport::hashes(2) + CostReport::mults(m),
+        CoeffMode::Independent => CostReport::hashes(1 + m),
+    }
+}
+
+// The merkle roots are absorbed as digests, and the last constant as a scalar.
+fn absorb_root<T: Transcript>(transcript: &mut T, root: &Scalar) {
+    transcript.append(&encode_digest(&root.to_bytes()));
+}
+
+fn absorb_last_const<T: Transcript>(transcript: &mut T, last_const: &Scalar) {
+    transcript.append(&encode_scalar(last_const));
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod test {
+    use super::*;
+    use crate::merkle_tree::verify_multi;
+    use crate::poly::{random_poly, split_poly_k, Polynomial};
+    use ff::{Field, PrimeField};
+    use rand_core::OsRng;
+    use sumcheck::secret::Secret;
+    use Fiat_Shamir::codec::CodecError;
+
+    fn lde_domain() -> EvaluationDomain {
+        EvaluationDomain::coset(64, Scalar::MULTIPLICATIVE_GENERATOR)
+    }
+
+    fn folding(k: usize) -> LdtParams {
+        LdtParams {
+            folding_factor: k,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_fold_codeword() {
+        let domain = lde_domain();
+        let poly = random_poly(7);
+        let alpha = Scalar::random(OsRng);
+
+        // fold the codeword is equal to fold the poly
+        let folded = fold_codeword(&domain.evaluate(&poly), &domain, alpha);
+        let (p_l, p_r) = crate::poly::split_poly(&poly);
+        let p_next = &p_l + &(&p_r * alpha);
+        assert_eq!(folded, domain.square().evaluate(&p_next));
+    }
+
+    #[test]
+    fn test_fold_codeword_k() {
+        let domain = lde_domain();
+        let poly = random_poly(15);
+        let alpha = Scalar::random(OsRng);
+        let codeword = domain.evaluate(&poly);
+
+        // k = 2 is the same as `fold_codeword`
+        assert_eq!(
+            fold_codeword_k(&codeword, &domain, alpha, 2),
+            fold_codeword(&codeword, &domain, alpha)
+        );
+        let (x, v) = (domain.element(3), [codeword[3], codeword[35]]);
+        assert_eq!(fold_k(&v, alpha, x), fold(v[0], v[1], alpha, x));
+
+        // fold the codeword is equal to fold the poly: ∑ α^j * f_j
+        for k in [2, 4, 8] {
+            let folded = fold_codeword_k(&codeword, &domain, alpha, k);
+            let mut p_next = Polynomial::zero();
+            let mut alpha_j = Scalar::one();
+            for f_j in split_poly_k(&poly, k) {
+                p_next = &p_next + &(&f_j * &alpha_j);
+                alpha_j *= alpha;
+            }
+            assert_eq!(folded, domain.pow(k).evaluate(&p_next));
+        }
+    }
+
+    #[test]
+    fn test_fold_in_place() {
+        // 5 random rounds from 2^10 points for both factors, the last one is 1 point for k = 4.
+        for k in [2, 4] {
+            let mut domain = EvaluationDomain::coset(1 << 10, Scalar::MULTIPLICATIVE_GENERATOR);
+            let mut codeword = domain.evaluate(&random_poly(1 << 9));
+            let capacity = codeword.capacity();
+            for _ in 0..5 {
+                let alpha = Scalar::random(OsRng);
+                if k == 2 {
+                    let expected = fold_codeword(&codeword, &domain, alpha);
+                    fold_codeword_in_place(&mut codeword, alpha, &domain);
+                    assert_eq!(codeword, expected);
+                } else {
+                    let expected = fold_codeword_k(&codeword, &domain, alpha, k);
+                    fold_codeword_k_in_place(&mut codeword, alpha, &domain, k);
+                    assert_eq!(codeword, expected);
+                }
+                domain = domain.pow(k);
+            }
+            assert_eq!(codeword.len(), (1 << 10) >> (5 * k.trailing_zeros()));
+            // never reallocated
+            assert_eq!(codeword.capacity(), capacity);
+        }
+    }
+
+    // The batched LDT before the folding factor, which always folds by 2.
+    fn prove_by_2(ldt: &BatchLDT, codewords: &[&CommittedCodeword]) -> BatchLDTProof {
+        let mut transcript = Keccak256Transcript::default();
+        let id = ldt.protocol_id();
+        transcript.bind_protocol(&id);
+        let mut proof = BatchLDTProof {
+            protocol: id.digest(),
+            ..Default::default()
+        };
+        for cw in codewords.iter() {
+            proof.roots.push(cw.root());
+            absorb_root(&mut transcript, &cw.root());
+        }
+        let gamma = transcript.challenge();
+        let mut f = Secret::new(vec![Scalar::zero(); ldt.domain.size]);
+        let mut gamma_k = Scalar::one();
+        for cw in codewords.iter() {
+            for (f, v) in f.iter_mut().zip(cw.evals.iter()) {
+                *f += gamma_k * v;
+            }
+            gamma_k *= gamma;
+        }
+
+        let rounds = ldt.degree_bound.trailing_zeros() as usize;
+        let mut layers: Vec<CommittedCodeword> = vec![];
+        let mut domain = ldt.domain;
+        for i in 0..rounds {
+            let alpha_i = transcript.challenge();
+            let f_i = layers.last().map_or(&f, |layer| &layer.evals);
+            let f_i_plus_1 = fold_codeword(f_i, &domain, alpha_i);
+            domain = domain.square();
+            if i + 1 < rounds {
+                let layer = CommittedCodeword::commit(f_i_plus_1);
+                proof.layer_roots.push(layer.root());
+                absorb_root(&mut transcript, &layer.root());
+                layers.push(layer);
+            } else {
+                proof.last_const = f_i_plus_1[0];
+            }
+        }
+        absorb_last_const(&mut transcript, &proof.last_const);
+
+        for _ in 0..ldt.num_queries {
+            let half = ldt.domain.size / 2;
+            let index = challenge_to_index(&transcript.challenge().to_bytes(), half);
+            let inputs = codewords
+                .iter()
+                .map(|cw| vec![cw.open(index), cw.open(index + half)])
+                .collect();
+            let layers = layers
+                .iter()
+                .map(|layer| {
+                    let half = layer.evals.len() / 2;
+                    let j = index % half;
+                    vec![layer.open(j), layer.open(j + half)]
+                })
+                .collect();
+            proof.queries.push(QueryProof {
+                index,
+                inputs,
+                layers,
+            });
+        }
+        proof
+    }
+
+    #[test]
+    fn test_factor_2_unchanged() {
+        let domain = lde_domain();
+        let f_0 = CommittedCodeword::commit(domain.evaluate(&random_poly(15)));
+        let f_1 = CommittedCodeword::commit(domain.evaluate(&random_poly(7)));
+        let params = folding(2);
+        assert_eq!(LdtParams::default(), params);
+
+        let ldt = BatchLDT::with_params(domain, 16, 8, params);
+        let proof = ldt.prove(&[&f_0, &f_1]);
+        assert_eq!(proof, prove_by_2(&ldt, &[&f_0, &f_1]));
+        assert_eq!(proof, BatchLDT::new(domain, 16, 8).prove(&[&f_0, &f_1]));
+        assert_eq!(proof.layer_roots.len(), 3);
+    }
+
+    #[test]
+    fn test_factor_4() {
+        let domain = lde_domain();
+        let ldt = BatchLDT::with_params(domain, 16, 16, folding(4));
+        assert_eq!(
+            (ldt.rounds(), BatchLDT::new(domain, 16, 16).rounds()),
+            (2, 4)
+        );
+
+        let f_0 = CommittedCodeword::commit(domain.evaluate(&random_poly(15)));
+        let f_1 = CommittedCodeword::commit(domain.evaluate(&random_poly(3)));
+        let roots = [f_0.root(), f_1.root()];
+        let proof = ldt.prove(&[&f_0, &f_1]);
+        assert_eq!(proof.layer_roots.len(), 1);
+        assert!(proof.queries.iter().all(|q| q.index < 16));
+        assert!(proof.queries.iter().all(|q| q
+            .inputs
+            .iter()
+            .chain(q.layers.iter())
+            .all(|c| c.len() == 4)));
+
+        // γ, α_0, α_1, then 16 queries with: index + 4*2 inputs + 5 layer checks + last check
+        let (res, trace) = ldt.verify_with_trace(&roots, &proof);
+        assert_eq!(res.unwrap().query_indices.len(), 16);
+        assert_eq!(trace.events.len(), 1 + 2 + 16 * (1 + 8 + 5 + 1));
+    }
+
+    #[test]
+    fn test_high_degree_at_both_factors() {
+        // deg(f_1) = 31 > 15, the last fold is a line on 4 points for both factors, so each
+        // query catches it with probability 3/4.
+        let domain = lde_domain();
+        for k in [2, 4] {
+            let ldt = BatchLDT::with_params(domain, 16, 16, folding(k));
+            let f_0 = CommittedCodeword::commit(domain.evaluate(&random_poly(15)));
+            let f_1 = CommittedCodeword::commit(domain.evaluate(&random_poly(31)));
+            let roots = [f_0.root(), f_1.root()];
+            let proof = ldt.prove(&[&f_0, &f_1]);
+            assert_eq!(
+                ldt.verify_with_trace(&roots, &proof).0,
+                Err(LDTError::LastRoundCheckFailed)
+            );
+        }
+    }
+
+    #[test]
+    fn test_query_path_factor_4() {
+        // |D| = 64, d = 16: D_0 = D, D_1 = D^4 with 16 points, D_2 = D^16 with 4 points.
+        let domain = lde_domain();
+        let ldt = BatchLDT::with_params(domain, 16, 1, folding(4));
+        let (d_1, d_2) = (domain.pow(4), domain.pow(16));
+        let x_4 = |x: Scalar| x.pow_vartime(&[4, 0, 0, 0]);
+
+        let mut opened = vec![0; domain.size];
+        for index in 0..domain.size / 4 {
+            let path = ldt.query_path(index);
+            assert_eq!(path, vec![index, index % 4]);
+
+            // round 0: the coset of index in D, which is mapped to index of D_1
+            for m in 0..4 {
+                let p = index + m * 16;
+                opened[p] += 1;
+                assert_eq!(x_4(domain.element(p)), d_1.element(index));
+            }
+
+            // round 1: the coset of j_1 in D_1 has the fold of round 0, and is mapped to j_1 of D_2
+            let j_1 = path[1];
+            let coset = (0..4).map(|m| j_1 + m * 4).collect::<Vec<_>>();
+            assert_eq!(coset[index / 4], index);
+            for p in coset {
+                assert_eq!(x_4(d_1.element(p)), d_2.element(j_1));
+            }
+        }
+        // the cosets of round 0 cover D exactly once
+        assert!(opened.iter().all(|&n| n == 1));
+    }
+
+    #[test]
+    fn test_batch_ldt() {
+        let domain = lde_domain();
+        let ldt = BatchLDT::new(domain, 8, 4);
+        let f_0 = CommittedCodeword::commit(domain.evaluate(&random_poly(7)));
+        let f_1 = CommittedCodeword::commit(domain.evaluate(&random_poly(3)));
+
+        let proof = ldt.prove(&[&f_0, &f_1]);
+        let verified = ldt.verify(&[f_0.root(), f_1.root()], &proof);
+        assert_eq!(verified.query_indices.len(), 4);
+        assert_eq!(verified.alphas.len(), ldt.rounds());
+    }
+
+    // The powers of γ and the independent coeffs combine the same codewords into another f, so
+    // the proofs differ after the roots, and each verifies under its own params only.
+    #[test]
+    fn test_coeff_modes() {
+        let domain = lde_domain();
+        let powers = BatchLDT::new(domain, 8, 4);
+        let params = LdtParams {
+            coeffs: CoeffMode::Independent,
+            ..Default::default()
+        };
+        let independent = BatchLDT::with_params(domain, 8, 4, params);
+        let codewords = (0..3)
+            .map(|_| CommittedCodeword::commit(domain.evaluate(&random_poly(7))))
+            .collect::<Vec<_>>();
+        let codewords = codewords.iter().collect::<Vec<_>>();
+        let roots = codewords.iter().map(|c| c.root()).collect::<Vec<_>>();
+
+        let (p, i) = (powers.prove(&codewords), independent.prove(&codewords));
+        assert_eq!(p.roots, i.roots);
+        assert_ne!(p.layer_roots, i.layer_roots);
+        assert!(powers.verify_with_sink(&roots, &p, &mut ()).is_ok());
+        assert!(independent.verify_with_sink(&roots, &i, &mut ()).is_ok());
+        assert_eq!(
+            powers.verify_with_sink(&roots, &i, &mut ()),
+            Err(LDTError::ProtocolMismatch)
+        );
+
+        // a coeff per codeword is traced, and a high degree is caught as with the powers.
+        let (_, trace) = independent.verify_with_trace(&roots, &i);
+        let gammas = trace
+            .events
+            .iter()
+            .filter(|e| matches!(e, TraceEvent::ChallengeDerived { name: "gamma", .. }))
+            .count();
+        assert_eq!(gammas, 3);
+        let f_3 = CommittedCodeword::commit(domain.evaluate(&random_poly(31)));
+        let proof = independent.prove(&[codewords[0], &f_3]);
+        assert!(independent
+            .verify_with_sink(&[roots[0], f_3.root()], &proof, &mut ())
+            .is_err());
+    }
+
+    // The indices V returns are the ones P opened, round by round, for both factors.
+    #[test]
+    fn test_verified_query_indices() {
+        let domain = lde_domain();
+        for k in [2, 4] {
+            let ldt = BatchLDT::with_params(domain, 16, 6, folding(k));
+            let f_0 = CommittedCodeword::commit(domain.evaluate(&random_poly(15)));
+            let f_1 = CommittedCodeword::commit(domain.evaluate(&random_poly(7)));
+            let proof = ldt.prove(&[&f_0, &f_1]);
+            let verified = ldt.verify(&[f_0.root(), f_1.root()], &proof);
+            assert_eq!(ldt.derive_challenges_only(&proof), verified);
+
+            assert_eq!(verified.alphas.len(), ldt.rounds());
+            assert_eq!(verified.query_indices.len(), proof.queries.len());
+            for (path, query) in verified.query_indices.iter().zip(proof.queries.iter()) {
+                // the first opening of each coset is at m = 0, which is j_i.
+                let opened = query.inputs[..1]
+                    .iter()
+                    .chain(query.layers.iter())
+                    .map(|coset| coset[0].index)
+                    .collect::<Vec<_>>();
+                assert_eq!(path, &opened);
+                assert_eq!(path[0], query.index);
+            }
+            assert_eq!(
+                verified.indices(),
+                proof.queries.iter().map(|q| q.index).collect::<Vec<_>>()
+            );
+
+            // a wrong opening fails V, but the challenges are derived from the commit phase.
+            let mut corrupted = proof.clone();
+            corrupted.queries[0].layers[0][1].value += Scalar::one();
+            assert!(ldt
+                .verify_with_trace(&corrupted.roots, &corrupted)
+                .0
+                .is_err());
+            assert_eq!(ldt.derive_challenges_only(&corrupted), verified);
+        }
+    }
+
+    #[test]
+    fn test_proof_to_and_from_bytes() {
+        let domain = lde_domain();
+        let ldt = BatchLDT::new(domain, 8, 4);
+        let f_0 = CommittedCodeword::commit(domain.evaluate(&random_poly(7)));
+        let f_1 = CommittedCodeword::commit(domain.evaluate(&random_poly(3)));
+        let proof = ldt.prove(&[&f_0, &f_1]);
+
+        let bytes = proof.to_bytes();
+        assert_eq!(BatchLDTProof::from_bytes(&bytes), Ok(proof));
+        assert_eq!(
+            BatchLDTProof::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(CodecError::UnexpectedEnd)
+        );
+
+        let openings = f_0.open_coset(3, 4);
+        let bytes = openings_to_bytes(&openings);
+        assert_eq!(openings_from_bytes(&bytes), Ok(openings));
+        let mut trailing = bytes;
+        trailing.push(0);
+        assert_eq!(
+            openings_from_bytes(&trailing),
+            Err(CodecError::TrailingBytes(1))
+        );
+    }
+
+    // The index of a query is sent by P, one out of the domain is rejected rather than reduced.
+    #[test]
+    fn test_query_index_out_of_domain() {
+        let domain = lde_domain();
+        let ldt = BatchLDT::new(domain, 8, 4);
+        let f_0 = CommittedCodeword::commit(domain.evaluate(&random_poly(7)));
+        let roots = [f_0.root()];
+        let proof = ldt.prove(&[&f_0]);
+
+        for index in [domain.size, proof.queries[0].index + domain.size, usize::MAX] {
+            let mut crafted = proof.clone();
+            crafted.queries[0].index = index;
+            let crafted = BatchLDTProof::from_bytes(&crafted.to_bytes()).unwrap();
+            assert_eq!(
+                ldt.verify_with_sink(&roots, &crafted, &mut ()).err(),
+                Some(LDTError::WrongQueryIndex { query: 0 })
+            );
+        }
+    }
+
+    // The golden proofs in `tests/fixtures`, see `Fiat_Shamir::fixtures`.
+    // f = 1 + 2X + ... + 8X^7 on the coset of size 64, which is tested with 5 queries.
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_fixture() {
+        use Fiat_Shamir::fixtures::{assert_same_bytes, load_or_regenerate};
+
+        let domain = lde_domain();
+        let ldt = BatchLDT::new(domain, 8, 5);
+        let f = Polynomial::from_coeffs((1..=8u64).map(Scalar::from).collect());
+        let cw = CommittedCodeword::commit(domain.evaluate(&f));
+        let current = ldt.prove(&[&cw]).to_bytes();
+        let stored = load_or_regenerate("ldt", &current);
+
+        let proof = BatchLDTProof::from_bytes_for(&stored, &ldt.protocol_id())
+            .unwrap_or_else(|e| panic!("fixture ldt: can't decode: {:?}", e));
+        let verified = ldt
+            .verify_with_sink(&[cw.root()], &proof, &mut ())
+            .unwrap_or_else(|e| panic!("fixture ldt: rejected: {}", e));
+        assert_eq!(
+            ldt.derive_challenges_only(&proof),
+            verified,
+            "fixture ldt: the derived challenges differ from the verified ones"
+        );
+
+        // all the openings as one batch, against the root of their round: the same as each one
+        // alone, and the paths of the queries share their upper nodes.
+        let mut batch = MultiVerifier::new();
+        let mut each = 0;
+        for query in proof.queries.iter() {
+            let rounds = query.inputs.iter().map(|coset| (cw.root(), coset));
+            let layers = query.layers.iter().zip(proof.layer_roots.iter().copied());
+            for (root, coset) in rounds.chain(layers.map(|(coset, root)| (root, coset))) {
+                for o in coset.iter() {
+                    assert_eq!(o.try_check(&root), Ok(()), "fixture ldt: at {}", o.index);
+                    batch.add(root, o.index, &o.value, &o.proof);
+                    each += o.proof.children.len() + 2;
+                }
+            }
+        }
+        let (res, hashes) = batch.check_counted();
+        assert_eq!(res, Ok(()), "fixture ldt: rejected as one batch");
+        assert!(hashes < each, "{} hashes of {}", hashes, each);
+        assert_same_bytes("ldt", &stored, &current);
+    }
+
+    // The folds in place give the same bytes as the allocating P, see `prove_by_2`: for the
+    // fixture, which is one codeword moved into `prove_owned`, and for a batch of two.
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_in_place_fixture() {
+        use Fiat_Shamir::fixtures::{assert_same_bytes, load_or_regenerate};
+
+        let domain = lde_domain();
+        let ldt = BatchLDT::new(domain, 8, 5);
+        let f = Polynomial::from_coeffs((1..=8u64).map(Scalar::from).collect());
+        let cw = CommittedCodeword::commit(domain.evaluate(&f));
+        let current = ldt.prove_owned(domain.evaluate(&f)).to_bytes();
+        assert_eq!(current, prove_by_2(&ldt, &[&cw]).to_bytes());
+        assert_eq!(current, ldt.prove(&[&cw]).to_bytes());
+        assert_same_bytes("ldt", &load_or_regenerate("ldt", &current), &current);
+
+        let g = CommittedCodeword::commit(domain.evaluate(&random_poly(7)));
+        assert_eq!(
+            ldt.prove(&[&cw, &g]).to_bytes(),
+            prove_by_2(&ldt, &[&cw, &g]).to_bytes()
+        );
+    }
+
+    // The merkle openings of the values 1, ..., 16 at 1, 5, 9 and 13, aka the coset of 1 for k = 4.
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_merkle_batch_fixture() {
+        use crate::merkle_tree::proof::Scratch;
+        use Fiat_Shamir::fixtures::{assert_same_bytes, load_or_regenerate};
+
+        let cw = CommittedCodeword::commit((1..=16u64).map(Scalar::from).collect());
+        let current = openings_to_bytes(&cw.open_coset(1, 4));
+        let stored = load_or_regenerate("merkle_batch", &current);
+
+        let openings = openings_from_bytes(&stored)
+            .unwrap_or_else(|e| panic!("fixture merkle_batch: can't decode: {:?}", e));
+        assert_eq!(openings.len(), 4, "fixture merkle_batch: 4 openings");
+        for (opening, index) in openings.iter().zip([1, 5, 9, 13]) {
+            assert!(
+                opening.index == index && opening.check(&cw.root()),
+                "fixture merkle_batch: rejected the opening at {}",
+                index
+            );
+            let (bytes, siblings) = (opening.value.to_repr(), &opening.proof.children);
+            assert!(
+                MerkleProof::<Scalar>::verify_in_place(&cw.root(), index, bytes.as_ref(), siblings),
+                "fixture merkle_batch: rejected in place the opening at {}",
+                index
+            );
+        }
+        let proofs = openings.iter().map(|o| o.proof.clone()).collect::<Vec<_>>();
+        let values = openings.iter().map(|o| o.value).collect::<Vec<_>>();
+        let mut scratch = Scratch::new();
+        assert!(
+            MerkleTree::verify_batch_with_scratch(&mut scratch, &cw.root(), &proofs, &values),
+            "fixture merkle_batch: rejected as a batch"
+        );
+        let leaves = values.iter().map(|v| v.to_repr()).collect::<Vec<_>>();
+        let claims = openings
+            .iter()
+            .zip(leaves.iter())
+            .map(|(o, leaf)| (cw.root(), o.index, leaf.as_ref(), &o.proof))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            verify_multi(&claims),
+            Ok(()),
+            "fixture merkle_batch: rejected by verify_multi"
+        );
+        assert_same_bytes("merkle_batch", &stored, &current);
+    }
+
+    #[test]
+    fn test_batch_ldt_on_subgroup_and_coset() {
+        // the fold works on any coset: D^2 of offset * H is offset^2 * H^2.
+        // 16 queries, each one catches the high degree f_1 with probability ~3/4.
+        for domain in [lde_domain().subgroup(), lde_domain()] {
+            let ldt = BatchLDT::new(domain, 8, 16);
+            let f_0 = CommittedCodeword::commit(domain.evaluate(&random_poly(7)));
+            let f_1 = CommittedCodeword::commit(domain.evaluate(&random_poly(3)));
+            let roots = [f_0.root(), f_1.root()];
+            let proof = ldt.prove(&[&f_0, &f_1]);
+            assert!(ldt.verify_with_trace(&roots, &proof).0.is_ok());
+
+            // deg(f_1) = 31 > 7
+            let f_1 = CommittedCodeword::commit(domain.evaluate(&random_poly(31)));
+            let roots = [f_0.root(), f_1.root()];
+            let proof = ldt.prove(&[&f_0, &f_1]);
+            assert!(ldt.verify_with_trace(&roots, &proof).0.is_err());
+        }
+    }
+
+    #[test]
+    fn test_batch_ldt_trace() {
+        let domain = lde_domain();
+        let ldt = BatchLDT::new(domain, 8, 4);
+        let f_0 = CommittedCodeword::commit(domain.evaluate(&random_poly(7)));
+        let f_1 = CommittedCodeword::commit(domain.evaluate(&random_poly(3)));
+        let roots = [f_0.root(), f_1.root()];
+        let mut proof = ldt.prove(&[&f_0, &f_1]);
+
+        // γ, α_0..α_2, then 4 queries with: index + 2*2 inputs + 2*3 layer checks + last check
+        let (res, trace) = ldt.verify_with_trace(&roots, &proof);
+        assert_eq!(res.unwrap().indices().len(), 4);
+        assert_eq!(trace.events.len(), 1 + 3 + 4 * (1 + 4 + 6 + 1));
+        assert!(trace.events.iter().all(|e| e.is_ok()));
+
+        // a wrong opening of f_1 in the 2nd query
+        proof.queries[1].layers[0][0].value += Scalar::one();
+        let index = proof.queries[1].layers[0][0].index;
+        let (res, trace) = ldt.verify_with_trace(&roots, &proof);
+        let source = MerkleError::PathMismatch { index };
+        assert_eq!(
+            res,
+            Err(LDTError::QueryOpening {
+                round: 1,
+                index,
+                source
+            })
+        );
+        assert_eq!(
+            trace.first_failure(),
+            Some(&TraceEvent::MerkleChecked {
+                round: 1,
+                index,
+                ok: false
+            })
+        );
+        assert_eq!(trace.events.last(), trace.first_failure());
+    }
+
+    // The rejected opening is kept as the source of the error, with the round and index it's of.
+    #[test]
+    fn test_query_opening_source() {
+        use std::error::Error;
+
+        let domain = lde_domain();
+        let ldt = BatchLDT::new(domain, 8, 4);
+        let f_0 = CommittedCodeword::commit(domain.evaluate(&random_poly(7)));
+        let roots = [f_0.root()];
+        let mut proof = ldt.prove(&[&f_0]);
+        proof.queries[0].inputs[0][1].proof.index ^= 1;
+        let index = proof.queries[0].inputs[0][1].index;
+
+        let err = ldt.verify_with_sink(&roots, &proof, &mut ()).unwrap_err();
+        let source = MerkleError::IndexMismatch {
+            expected: index,
+            found: index ^ 1,
+        };
+        assert_eq!(
+            err,
+            LDTError::QueryOpening {
+                round: 0,
+                index,
+                source
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            format!("Verifier: round-0 merkle check failed at {}.", index)
+        );
+        let inner = err.source().unwrap();
+        assert_eq!(
+            inner.to_string(),
+            format!("Opened leaf {}, expected {}", index ^ 1, index)
+        );
+        assert!(inner.source().is_none());
+    }
+
+    // The folds of each query with `fold`, which inverts 2 and x every time, times 2x, in the
+    // order of the round and final checks in the trace.
+    fn fold_each_query(
+        ldt: &BatchLDT,
+        proof: &BatchLDTProof,
+        trace: &VerificationTrace,
+    ) -> Vec<Scalar> {
+        let challenges = trace
+            .events
+            .iter()
+            .filter_map(|e| match e {
+                TraceEvent::ChallengeDerived { value, .. } => Some(*value),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let (gamma, alphas) = (challenges[0], &challenges[1..]);
+
+        let mut folds = vec![];
+        for query in proof.queries.iter() {
+            let (mut f_x, mut f_neg_x) = (Scalar::zero(), Scalar::zero());
+            let mut gamma_k = Scalar::one();
+            for coset in query.inputs.iter() {
+                f_x += gamma_k * coset[0].value;
+                f_neg_x += gamma_k * coset[1].value;
+                gamma_k *= gamma;
+            }
+
+            let mut domain = ldt.domain;
+            let mut j = query.index;
+            let mut x = domain.element(j);
+            let mut folded = fold(f_x, f_neg_x, alphas[0], x);
+            for (i, coset) in query.layers.iter().enumerate() {
+                domain = domain.square();
+                j %= domain.size / 2;
+                folds.push(x.double() * folded);
+                x = domain.element(j);
+                folded = fold(coset[0].value, coset[1].value, alphas[i + 1], x);
+            }
+            folds.push(x.double() * folded);
+        }
+        folds
+    }
+
+    // The folds by 2 are checked by `fold_check`, whose lhs is the fold times 2x.
+    #[test]
+    fn test_inversion_free_folds() {
+        let domain = lde_domain();
+        let ldt = BatchLDT::new(domain, 8, 4);
+        let f_0 = CommittedCodeword::commit(domain.evaluate(&random_poly(7)));
+        let f_1 = CommittedCodeword::commit(domain.evaluate(&random_poly(3)));
+        let proof = ldt.prove(&[&f_0, &f_1]);
+
+        let (res, trace) = ldt.verify_with_trace(&[f_0.root(), f_1.root()], &proof);
+        assert!(res.is_ok());
+        let checked = trace
+            .events
+            .iter()
+            .filter_map(|e| match e {
+                TraceEvent::RoundChecked { lhs, .. } | TraceEvent::FinalChecked { lhs, .. } => {
+                    Some(*lhs)
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(checked.len(), 4 * 3);
+        assert_eq!(checked, fold_each_query(&ldt, &proof, &trace));
+    }
+
+    #[test]
+    fn test_fold_check() {
+        let domain = lde_domain();
+        for i in 0..1000 {
+            let (f_x, f_neg_x, alpha) = (
+                Scalar::random(OsRng),
+                Scalar::random(OsRng),
+                Scalar::random(OsRng),
+            );
+            // the points of the domain, where `fold` inverts x, then random ones.
+            let x = match i < domain.size {
+                true => domain.element(i),
+                false => Scalar::random(OsRng),
+            };
+            let f_next = fold(f_x, f_neg_x, alpha, x);
+            assert!(fold_check(f_x, f_neg_x, f_next, alpha, x));
+            assert!(!fold_check(f_x, f_neg_x, f_next + Scalar::one(), alpha, x));
+            assert!(!fold_check(f_x, f_neg_x, f_next, alpha + Scalar::one(), x));
+        }
+    }
+
+    // Both sides are α * (fi(x) - fi(-x)) at x = 0, whatever f_next is.
+    #[test]
+    fn test_fold_check_at_zero() {
+        let (f, alpha) = (Scalar::from(5), Scalar::from(7));
+        assert_eq!(
+            fold_check_sides(f, f, Scalar::from(3), alpha, Scalar::zero()),
+            (Scalar::zero(), Scalar::zero())
+        );
+        assert!(!fold_check(f, f, Scalar::from(3), alpha, Scalar::zero()));
+    }
+
+    // cargo test -p low_degree_test --release bench_batch_inverted_folds -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn bench_batch_inverted_folds() {
+        use std::time::Instant;
+
+        let domain = EvaluationDomain::coset(1 << 12, Scalar::MULTIPLICATIVE_GENERATOR);
+        let (degree_bound, num_queries) = (1 << 10, 20);
+        let ldt = BatchLDT::new(domain, degree_bound, num_queries);
+        let f = CommittedCodeword::commit(domain.evaluate(&random_poly(degree_bound - 1)));
+        let proof = ldt.prove(&[&f]);
+        let points = ldt.fold_points(&ldt.verify(&[f.root()], &proof).indices(), &mut ());
+
+        // `fold` inverts 2 and x in each round of each query, V with k > 2 inverts the batch once.
+        assert_eq!(points.len(), num_queries * ldt.rounds());
+        println!("inversions: {} -> {}", 2 * points.len(), 2);
+
+        let start = Instant::now();
+        let each = points
+            .iter()
+            .map(|x| x.invert().unwrap())
+            .collect::<Vec<_>>();
+        println!(
+            "{} inversions one by one: {:?}",
+            points.len(),
+            start.elapsed()
+        );
+
+        let start = Instant::now();
+        let mut batch = points.clone();
+        batch_invert(&mut batch).unwrap();
+        println!("batch_invert: {:?}", start.elapsed());
+        assert_eq!(batch, each);
+    }
+
+    // The live bytes of this thread and their peak, so the tests running in parallel don't
+    // interfere. The frees of other threads are not seen, so it's an observation only.
+    struct CountingAlloc;
+
+    std::thread_local! {
+        static LIVE: core::cell::Cell<isize> = const { core::cell::Cell::new(0) };
+        static PEAK: core::cell::Cell<isize> = const { core::cell::Cell::new(0) };
+    }
+
+    unsafe impl std::alloc::GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+            let _ = LIVE.try_with(|live| {
+                live.set(live.get() + layout.size() as isize);
+                let _ = PEAK.try_with(|peak| peak.set(peak.get().max(live.get())));
+            });
+            std::alloc::System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+            let _ = LIVE.try_with(|live| live.set(live.get() - layout.size() as isize));
+            std::alloc::System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: CountingAlloc = CountingAlloc;
+
+    // The peak of the live bytes above the ones before `f`.
+    fn peak_of(f: impl FnOnce()) -> usize {
+        let before = LIVE.with(|live| live.get());
+        PEAK.with(|peak| peak.set(before));
+        f();
+        (PEAK.with(|peak| peak.get()) - before) as usize
+    }
+
+    // cargo test -p low_degree_test --release bench_prove_owned_memory -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn bench_prove_owned_memory() {
+        let domain = EvaluationDomain::coset(1 << 16, Scalar::MULTIPLICATIVE_GENERATOR);
+        let ldt = BatchLDT::new(domain, 1 << 12, 20);
+        let codeword = domain.evaluate(&random_poly((1 << 12) - 1));
+        let codeword_bytes = codeword.len() * core::mem::size_of::<Scalar>();
+
+        let tree = peak_of(|| drop(MerkleTree::commit(&codeword)));
+        // the caller keeps its codeword, so it commits a copy, and P batches it into f.
+        let allocating = peak_of(|| {
+            let cw = CommittedCodeword::commit(codeword.clone());
+            drop(prove_by_2(&ldt, &[&cw]));
+        });
+        // the codeword is moved into P, and f is the codeword itself.
+        let owned = codeword.clone();
+        let in_place = peak_of(|| drop(ldt.prove_owned(owned)));
+
+        println!(
+            "codeword: {} bytes, its tree: {} bytes",
+            codeword_bytes, tree
+        );
+        for (name, peak) in [("allocating", allocating), ("in place", in_place)] {
+            println!(
+                "{}: peak {} bytes, {:.2} codewords + tree",
+                name,
+                peak,
+                (peak as f64 - tree as f64) / codeword_bytes as f64
+            );
+        }
+        assert!(in_place + codeword_bytes <= allocating);
+    }
+
+    // Records the absorbed bytes.
+    #[derive(Default)]
+    struct RecordingTranscript(Vec<u8>);
+
+    impl Transcript for RecordingTranscript {
+        fn append(&mut self, new_data: &[u8]) {
+            self.0.extend(new_data);
+        }
+
+        fn challenge(&mut self) -> Scalar {
+            Scalar::zero()
+        }
+    }
+
+    #[test]
+    fn test_absorb_test_vectors() {
+        use Fiat_Shamir::test_vectors::{fixture_scalars, to_hex, SCALAR_HEX};
+
+        let s = fixture_scalars()[2];
+        let mut transcript = RecordingTranscript::default();
+        absorb_root(&mut transcript, &s);
+        assert_eq!(to_hex(&transcript.0), SCALAR_HEX);
+
+        let mut transcript = RecordingTranscript::default();
+        absorb_last_const(&mut transcript, &s);
+        assert_eq!(to_hex(&transcript.0), SCALAR_HEX);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_batch_ldt_high_degree() {
+        let domain = lde_domain();
+        let ldt = BatchLDT::new(domain, 8, 4);
+        let f_0 = CommittedCodeword::commit(domain.evaluate(&random_poly(7)));
+        // deg(f_1) = 15 > 7
+        let f_1 = CommittedCodeword::commit(domain.evaluate(&random_poly(15)));
+
+        let proof = ldt.prove(&[&f_0, &f_1]);
+        ldt.verify(&[f_0.root(), f_1.root()], &proof);
+    }
+}
@@ -0,0 +1,156 @@
+// The wire format of the batched LDT proof and of the openings, so that a proof can be stored
+// and verified later, eg: the fixtures in `tests/fixtures`.
+//
+// Layout (version 2) in the encoding of `Fiat_Shamir::codec`:
+//
+//      | message         | encoding                                                  |
+//      |-----------------|-----------------------------------------------------------|
+//      | proof           | version: u8 = 2, protocol: digest, roots: scalars,        |
+//      |                 | layer_roots: scalars, queries: len: u64 then each query,  |
+//      |                 | last_const: scalar                                        |
+//      | query           | index: u64, inputs: cosets, layers: cosets                |
+//      | cosets          | len: u64, then each one as openings                       |
+//      | openings        | len: u64, then each opening                               |
+//      | opening         | index: u64, value: scalar, children: scalars, root,       |
+//      |                 | proof.index: u64                                          |
+//
+// The protocol is the digest of `BatchLDT::protocol_id`, so `from_bytes_for` rejects a proof of
+// other params before the rest is read. Version 1 had no protocol.
+//
+// The openings of a codeword alone, eg: `open_coset`, are OPENINGS_VERSION then openings.
+// No trailing bytes are allowed.
+use crate::ldt::batch::{BatchLDTProof, Opening, QueryProof};
+use crate::merkle_tree::proof::MerkleProof;
+use alloc::vec;
+use alloc::vec::Vec;
+use Fiat_Shamir::codec::{
+    encode_digest, encode_scalar, encode_scalars, encode_u64, CodecError, Decoder,
+};
+use Fiat_Shamir::protocol::ProtocolId;
+
+pub const LDT_PROOF_VERSION: u8 = 2;
+pub const OPENINGS_VERSION: u8 = 1;
+
+// index, value, children len, root and proof.index
+const MIN_OPENING_SIZE: usize = 8 + 32 + 8 + 32 + 8;
+
+fn encode_opening(bytes: &mut Vec<u8>, opening: &Opening) {
+    bytes.extend(encode_u64(opening.index as u64));
+    bytes.extend(encode_scalar(&opening.value));
+    bytes.extend(encode_scalars(&opening.proof.children));
+    bytes.extend(encode_scalar(&opening.proof.root));
+    bytes.extend(encode_u64(opening.proof.index as u64));
+}
+
+fn decode_opening(decoder: &mut Decoder) -> Result<Opening, CodecError> {
+    let index = decoder.read_usize()?;
+    let value = decoder.read_scalar()?;
+    let children = decoder.read_scalars()?;
+    let root = decoder.read_scalar()?;
+    let proof_index = decoder.read_usize()?;
+    Ok(Opening {
+        index,
+        value,
+        proof: MerkleProof::new(children, root, proof_index),
+    })
+}
+
+fn encode_openings(bytes: &mut Vec<u8>, openings: &[Opening]) {
+    bytes.extend(encode_u64(openings.len() as u64));
+    for opening in openings.iter() {
+        encode_opening(bytes, opening);
+    }
+}
+
+fn decode_openings(decoder: &mut Decoder) -> Result<Vec<Opening>, CodecError> {
+    let len = decoder.read_len(MIN_OPENING_SIZE)?;
+    (0..len).map(|_| decode_opening(decoder)).collect()
+}
+
+fn encode_cosets(bytes: &mut Vec<u8>, cosets: &[Vec<Opening>]) {
+    bytes.extend(encode_u64(cosets.len() as u64));
+    for coset in cosets.iter() {
+        encode_openings(bytes, coset);
+    }
+}
+
+fn decode_cosets(decoder: &mut Decoder) -> Result<Vec<Vec<Opening>>, CodecError> {
+    let len = decoder.read_len(8)?;
+    (0..len).map(|_| decode_openings(decoder)).collect()
+}
+
+impl BatchLDTProof {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![LDT_PROOF_VERSION];
+        bytes.extend(encode_digest(&self.protocol));
+        bytes.extend(encode_scalars(&self.roots));
+        bytes.extend(encode_scalars(&self.layer_roots));
+        bytes.extend(encode_u64(self.queries.len() as u64));
+        for query in self.queries.iter() {
+            bytes.extend(encode_u64(query.index as u64));
+            encode_cosets(&mut bytes, &query.inputs);
+            encode_cosets(&mut bytes, &query.layers);
+        }
+        bytes.extend(encode_scalar(&self.last_const));
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CodecError> {
+        let mut decoder = Decoder::new(bytes);
+        decoder.read_version(LDT_PROOF_VERSION)?;
+        let protocol = decoder.read_digest()?;
+        Self::decode_body(protocol, decoder)
+    }
+
+    // The same as `from_bytes`, but the proof must be of the expected protocol, which is checked
+    // right after the version.
+    pub fn from_bytes_for(bytes: &[u8], expected: &ProtocolId) -> Result<Self, CodecError> {
+        let mut decoder = Decoder::new(bytes);
+        decoder.read_version(LDT_PROOF_VERSION)?;
+        decoder.read_protocol(expected)?;
+        Self::decode_body(expected.digest(), decoder)
+    }
+
+    fn decode_body(protocol: [u8; 32], mut decoder: Decoder) -> Result<Self, CodecError> {
+        let roots = decoder.read_scalars()?;
+        let layer_roots = decoder.read_scalars()?;
+        // index and the two lengths
+        let len = decoder.read_len(3 * 8)?;
+        let mut queries = Vec::with_capacity(len);
+        for _ in 0..len {
+            let index = decoder.read_usize()?;
+            let inputs = decode_cosets(&mut decoder)?;
+            let layers = decode_cosets(&mut decoder)?;
+            queries.push(QueryProof {
+                index,
+                inputs,
+                layers,
+            });
+        }
+        let last_const = decoder.read_scalar()?;
+        decoder.finish()?;
+
+        Ok(Self {
+            protocol,
+            roots,
+            layer_roots,
+            queries,
+            last_const,
+        })
+    }
+}
+
+// The openings of one codeword, which are checked against the same root.
+pub fn openings_to_bytes(openings: &[Opening]) -> Vec<u8> {
+    let mut bytes = vec![OPENINGS_VERSION];
+    encode_openings(&mut bytes, openings);
+    bytes
+}
+
+pub fn openings_from_bytes(bytes: &[u8]) -> Result<Vec<Opening>, CodecError> {
+    let mut decoder = Decoder::new(bytes);
+    decoder.read_version(OPENINGS_VERSION)?;
+    let openings = decode_openings(&mut decoder)?;
+    decoder.finish()?;
+    Ok(openings)
+}
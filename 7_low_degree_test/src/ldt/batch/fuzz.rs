@@ -0,0 +1,140 @@
+// The verifier on structurally malformed proofs: each mutation must be rejected by
+// `BatchLDTProof::validate_structure`, with the same error from `verify_with_sink`, and never by
+// a panic later in the folds.
+//
+// The mutations are, for every opening of every coset of every query: (a) dropping it, (b)
+// opening it twice with the same value and (c) with another value, (d) opening a position next
+// to it instead. And for the proof: a query or a layer root dropped or added.
+use crate::domain::EvaluationDomain;
+use crate::ldt::batch::{BatchLDT, BatchLDTProof, CommittedCodeword, LdtParams, Opening};
+use crate::poly::random_poly;
+use bls12_381::Scalar;
+use ff::PrimeField;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+fn prove(k: usize) -> (BatchLDT, Vec<Scalar>, BatchLDTProof) {
+    let domain = EvaluationDomain::coset(64, Scalar::MULTIPLICATIVE_GENERATOR);
+    let params = LdtParams {
+        folding_factor: k,
+        ..Default::default()
+    };
+    let ldt = BatchLDT::with_params(domain, 16, 3, params);
+    let codewords = (0..2)
+        .map(|_| CommittedCodeword::commit(domain.evaluate(&random_poly(15))))
+        .collect::<Vec<_>>();
+    let proof = ldt.prove(&codewords.iter().collect::<Vec<_>>());
+    let roots = codewords.iter().map(|c| c.root()).collect();
+    (ldt, roots, proof)
+}
+
+// Panics with the mutation if the verifier panics, or if the proof passes the validation for
+// the indices derived by V, which are the ones of the honest proof.
+fn assert_caught(
+    ldt: &BatchLDT,
+    roots: &[Scalar],
+    honest: &BatchLDTProof,
+    crafted: &BatchLDTProof,
+    mutation: &str,
+) {
+    let derived = honest.queries.iter().map(|q| q.index).collect::<Vec<_>>();
+    let validated = crafted.validate_structure(ldt, &derived);
+    let verified = catch_unwind(AssertUnwindSafe(|| {
+        ldt.verify_with_sink(roots, crafted, &mut ()).err()
+    }))
+    .unwrap_or_else(|_| panic!("the verifier panicked on {}", mutation));
+    assert!(validated.is_err(), "{} passed the validation", mutation);
+    assert_eq!(validated.err(), verified, "{}", mutation);
+}
+
+// Each coset of the proof as (query, round, coset), the inputs are of round 0.
+fn cosets(proof: &BatchLDTProof) -> Vec<(usize, usize, usize)> {
+    let mut cosets = vec![];
+    for (q, query) in proof.queries.iter().enumerate() {
+        cosets.extend((0..query.inputs.len()).map(|c| (q, 0, c)));
+        cosets.extend((0..query.layers.len()).map(|i| (q, i + 1, 0)));
+    }
+    cosets
+}
+
+fn coset_mut(proof: &mut BatchLDTProof, (q, round, c): (usize, usize, usize)) -> &mut Vec<Opening> {
+    let query = &mut proof.queries[q];
+    if round == 0 {
+        &mut query.inputs[c]
+    } else {
+        &mut query.layers[round - 1]
+    }
+}
+
+#[test]
+fn test_opening_mutations() {
+    for k in [2, 4] {
+        let (ldt, roots, proof) = prove(k);
+        assert!(ldt.verify_with_sink(&roots, &proof, &mut ()).is_ok());
+        for at in cosets(&proof) {
+            let len = coset_mut(&mut proof.clone(), at).len();
+            for m in 0..len {
+                let name =
+                    |mutation: &str| format!("{} at {:?}, m = {}, k = {}", mutation, at, m, k);
+
+                let mut crafted = proof.clone();
+                coset_mut(&mut crafted, at).remove(m);
+                assert_caught(&ldt, &roots, &proof, &crafted, &name("a dropped opening"));
+
+                let mut crafted = proof.clone();
+                let coset = coset_mut(&mut crafted, at);
+                coset.insert(m, coset[m].clone());
+                assert_caught(
+                    &ldt,
+                    &roots,
+                    &proof,
+                    &crafted,
+                    &name("a duplicated opening"),
+                );
+
+                let mut crafted = proof.clone();
+                let coset = coset_mut(&mut crafted, at);
+                let mut twin = coset[m].clone();
+                twin.value += Scalar::one();
+                coset.insert(m + 1, twin);
+                assert_caught(
+                    &ldt,
+                    &roots,
+                    &proof,
+                    &crafted,
+                    &name("a conflicting opening"),
+                );
+
+                let mut crafted = proof.clone();
+                coset_mut(&mut crafted, at)[m].index += 1;
+                assert_caught(
+                    &ldt,
+                    &roots,
+                    &proof,
+                    &crafted,
+                    &name("an unqueried opening"),
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_proof_mutations() {
+    let (ldt, roots, proof) = prove(2);
+
+    let mut crafted = proof.clone();
+    crafted.queries.pop();
+    assert_caught(&ldt, &roots, &proof, &crafted, "a dropped query");
+
+    let mut crafted = proof.clone();
+    crafted.queries.push(proof.queries[0].clone());
+    assert_caught(&ldt, &roots, &proof, &crafted, "an extra query");
+
+    let mut crafted = proof.clone();
+    crafted.layer_roots.pop();
+    assert_caught(&ldt, &roots, &proof, &crafted, "a dropped layer root");
+
+    let mut crafted = proof.clone();
+    crafted.layer_roots.push(Scalar::one());
+    assert_caught(&ldt, &roots, &proof, &crafted, "an extra layer root");
+}
@@ -0,0 +1,233 @@
+// The rounds of a batched LDT from its params alone, without any proving, eg: for a tool which
+// precomputes the merkle leaves the queries of a transcript touch. P opens and V checks the
+// positions given here, so the layout can't drift from the proofs.
+//
+// Round i in [0, r), r = log_k(d), is over D_i = D^(k^i) of N/k^i points: the input codewords in
+// round 0, and fi in round i. The position p of D_i is in the coset of j = p % (|D_i|/k):
+//      conjugates: j + m * |D_i|/k, m in [0, k), the points x * ζ^m
+//      fold:       x^k, which is at j of D_{i+1}
+// eg: N = 64, k = 2, the position 37 of D_0 is -x of the coset {5, 37}, its fold is at 5 of D_1,
+// in the coset {5, 21}. The query j_0 opens the coset of j_i in each round, see `query_path`.
+use crate::ldt::batch::{BatchLDT, LdtParams};
+use alloc::vec::Vec;
+
+// The tree of round i is over D_i, so an opening has `tree_height` siblings, log2(|D_i|).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoundStructure {
+    pub round: usize,
+    pub domain_size: usize,
+    pub folding_factor: usize,
+    pub tree_height: usize,
+}
+
+impl LdtParams {
+    // r = log_k(d), None if d is not k^r for r > 0, eg: 1 or 7. The factor is 2^s, s > 0.
+    pub fn rounds(&self, degree_bound: usize) -> Option<usize> {
+        let k = self.folding_factor;
+        if !k.is_power_of_two() || k < 2 || !degree_bound.is_power_of_two() || degree_bound < 2 {
+            return None;
+        }
+        let (log_d, s) = (degree_bound.trailing_zeros(), k.trailing_zeros());
+        (log_d % s == 0).then_some((log_d / s) as usize)
+    }
+}
+
+impl BatchLDT {
+    pub fn round_structure(&self) -> Vec<RoundStructure> {
+        (0..self.rounds()).map(|i| self.round(i)).collect()
+    }
+
+    pub(super) fn round(&self, i: usize) -> RoundStructure {
+        assert!(i < self.rounds(), "round {} of {}", i, self.rounds());
+        let k = self.folding_factor();
+        let domain_size = self.domain.size >> (i * k.trailing_zeros() as usize);
+        RoundStructure {
+            round: i,
+            domain_size,
+            folding_factor: k,
+            tree_height: domain_size.trailing_zeros() as usize,
+        }
+    }
+
+    // The position of the fold of the position `index` of D_round in D_{round+1}, which is also
+    // the first one of its coset in D_round.
+    pub fn fold_index(&self, round: usize, index: usize) -> usize {
+        let size = self.round(round).domain_size;
+        assert!(index < size, "{} is out of D_{} of {}", index, round, size);
+        index % (size / self.folding_factor())
+    }
+
+    // The k positions of the coset of `index` in D_round, in the order of m, the ones P opens.
+    pub fn conjugate_indices(&self, round: usize, index: usize) -> Vec<usize> {
+        let j = self.fold_index(round, index);
+        let step = self.round(round).domain_size / self.folding_factor();
+        (0..self.folding_factor()).map(|m| j + m * step).collect()
+    }
+
+    // The positions opened in each round for the queries at the indices of D, sorted and each one
+    // once. The ones of round 0 are opened in every input codeword.
+    pub fn query_footprint(&self, query_indices: &[usize]) -> Vec<(usize, Vec<usize>)> {
+        let paths = query_indices
+            .iter()
+            .map(|index| self.query_path(self.fold_index(0, *index)))
+            .collect::<Vec<_>>();
+        (0..self.rounds())
+            .map(|i| {
+                let mut positions = paths
+                    .iter()
+                    .flat_map(|path| self.conjugate_indices(i, path[i]))
+                    .collect::<Vec<_>>();
+                positions.sort_unstable();
+                positions.dedup();
+                (i, positions)
+            })
+            .collect()
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod test {
+    use super::*;
+    use crate::domain::EvaluationDomain;
+    use crate::ldt::batch::{BatchLDTProof, CommittedCodeword};
+    use crate::poly::random_poly;
+    use bls12_381::Scalar;
+    use ff::PrimeField;
+
+    fn ldt(size: usize, degree_bound: usize, k: usize) -> BatchLDT {
+        let domain = EvaluationDomain::coset(size, Scalar::MULTIPLICATIVE_GENERATOR);
+        let params = LdtParams {
+            folding_factor: k,
+            ..Default::default()
+        };
+        BatchLDT::with_params(domain, degree_bound, 3, params)
+    }
+
+    // The positions of each round the proof opens, sorted and each one once.
+    fn opened(proof: &BatchLDTProof) -> Vec<(usize, Vec<usize>)> {
+        let rounds = proof.layer_roots.len() + 1;
+        (0..rounds)
+            .map(|i| {
+                let mut positions = proof
+                    .queries
+                    .iter()
+                    .flat_map(|q| match i {
+                        0 => q.inputs.iter().flatten().collect::<Vec<_>>(),
+                        _ => q.layers[i - 1].iter().collect(),
+                    })
+                    .map(|o| o.index)
+                    .collect::<Vec<_>>();
+                positions.sort_unstable();
+                positions.dedup();
+                (i, positions)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_rounds() {
+        let params = |k| LdtParams {
+            folding_factor: k,
+            ..Default::default()
+        };
+        // no schedule for 1 or 7, log_k(64) for the others.
+        for k in [2, 4, 8] {
+            assert_eq!(params(k).rounds(1), None);
+            assert_eq!(params(k).rounds(7), None);
+        }
+        assert_eq!(params(2).rounds(64), Some(6));
+        assert_eq!(params(4).rounds(64), Some(3));
+        assert_eq!(params(8).rounds(64), Some(2));
+        assert_eq!(params(4).rounds(8), None);
+        assert_eq!(params(3).rounds(9), None);
+
+        for (k, rounds) in [(2, 6), (4, 3), (8, 2)] {
+            let structure = ldt(256, 64, k).round_structure();
+            assert_eq!(structure.len(), rounds);
+            for (i, round) in structure.iter().enumerate() {
+                let size = 256 / k.pow(i as u32);
+                let expected = RoundStructure {
+                    round: i,
+                    domain_size: size,
+                    folding_factor: k,
+                    tree_height: size.trailing_zeros() as usize,
+                };
+                assert_eq!(*round, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fold_and_conjugates() {
+        let ldt = ldt(64, 8, 2);
+        assert_eq!(ldt.fold_index(0, 37), 5);
+        assert_eq!(ldt.conjugate_indices(0, 37), vec![5, 37]);
+        assert_eq!(ldt.conjugate_indices(1, 5), vec![5, 21]);
+        assert_eq!(ldt.fold_index(2, 15), 7);
+
+        // x * ζ^m of D_i are folded into the same x^k of D_{i+1}.
+        let ldt = ldt_4();
+        let (d_0, d_1) = (ldt.domain, ldt.domain.pow(4));
+        assert_eq!(ldt.conjugate_indices(0, 50), vec![2, 18, 34, 50]);
+        for p in ldt.conjugate_indices(0, 50) {
+            let x_4 = d_0.element(p).pow_vartime(&[4, 0, 0, 0]);
+            assert_eq!(x_4, d_1.element(ldt.fold_index(0, p)));
+        }
+        assert_eq!(ldt.conjugate_indices(1, 2), vec![2, 6, 10, 14]);
+    }
+
+    fn ldt_4() -> BatchLDT {
+        ldt(64, 16, 4)
+    }
+
+    #[test]
+    fn test_footprint_table() {
+        // |D| = 64, k = 2, d = 8: the cosets of D_0 (step 32), D_1 (step 16) and D_2 (step 8).
+        //      query   round 0     round 1     round 2
+        //      5       {5, 37}     {5, 21}     {5, 13}
+        //      21      {21, 53}    {5, 21}     {5, 13}
+        //      30      {30, 62}    {14, 30}    {6, 14}
+        let footprint = ldt(64, 8, 2).query_footprint(&[5, 21, 30]);
+        let expected = vec![
+            (0, vec![5, 21, 30, 37, 53, 62]),
+            (1, vec![5, 14, 21, 30]),
+            (2, vec![5, 6, 13, 14]),
+        ];
+        assert_eq!(footprint, expected);
+
+        // the same for the conjugate, and none for no query.
+        assert_eq!(ldt(64, 8, 2).query_footprint(&[37, 53, 62]), expected);
+        let empty = ldt(64, 8, 2).query_footprint(&[]);
+        assert!(empty.iter().all(|(_, positions)| positions.is_empty()));
+
+        // |D| = 64, k = 4, d = 16: steps 16 and 4.
+        let footprint = ldt_4().query_footprint(&[2, 7]);
+        let expected = vec![
+            (0, vec![2, 7, 18, 23, 34, 39, 50, 55]),
+            (1, vec![2, 3, 6, 7, 10, 11, 14, 15]),
+        ];
+        assert_eq!(footprint, expected);
+    }
+
+    #[test]
+    fn test_prover_opens_the_footprint() {
+        for (degree_bound, k) in [(8, 2), (16, 4)] {
+            let ldt = ldt(64, degree_bound, k);
+            let codewords = (0..2)
+                .map(|_| {
+                    let poly = random_poly(degree_bound - 1);
+                    CommittedCodeword::commit(ldt.domain.evaluate(&poly))
+                })
+                .collect::<Vec<_>>();
+            let proof = ldt.prove(&codewords.iter().collect::<Vec<_>>());
+            let roots = codewords.iter().map(|c| c.root()).collect::<Vec<_>>();
+            let verified = ldt.verify_with_sink(&roots, &proof, &mut ()).unwrap();
+            assert_eq!(
+                opened(&proof),
+                ldt.query_footprint(&verified.indices()),
+                "k = {}",
+                k
+            );
+        }
+    }
+}
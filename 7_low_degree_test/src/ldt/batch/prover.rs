@@ -0,0 +1,244 @@
+// P of the batched FRI-LDT: the committed codewords, the folds of the commit phase and the
+// openings of the queries, see `batch`.
+//
+// The committed layers are kept until the queries are opened, so each one is folded into a new
+// codeword. The batched f is never opened, so it's folded in place and becomes f_1, and one
+// codeword of the powers of γ is f itself (γ^0 = 1), which is not copied at all. So P holds the codewords, their
+// trees and the layers (N/k + N/k^2 + ...) at most, eg: `prove_owned` of a 2^20 codeword no
+// longer allocates another 2^20 scalars for f.
+use crate::domain::EvaluationDomain;
+use crate::ldt::batch::{
+    absorb_last_const, absorb_root, fold_k_with_inverses, fold_with_inverses,
+    inverse_roots_of_unity, BatchLDT, BatchLDTProof, Opening, QueryProof, GAMMA_LABEL,
+};
+use crate::merkle_tree::MerkleTree;
+use crate::transcript::default::Keccak256Transcript;
+use crate::transcript::Transcript;
+use alloc::vec;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use ff::BatchInvert;
+use sumcheck::rlc;
+use sumcheck::secret::Secret;
+use Fiat_Shamir::challenge_to_index;
+
+// A codeword with its merkle commitment, the leaf index is the index of the domain.
+// Only the opened values are public, so the evals are wiped on drop with the `zeroize` feature.
+#[derive(Clone, Debug)]
+pub struct CommittedCodeword {
+    pub evals: Secret<Vec<Scalar>>,
+    pub tree: MerkleTree,
+}
+
+impl CommittedCodeword {
+    pub fn commit(evals: Vec<Scalar>) -> Self {
+        Self::commit_secret(Secret::new(evals))
+    }
+
+    // The same as `commit`, eg: for a codeword of P which is folded in place already.
+    fn commit_secret(evals: Secret<Vec<Scalar>>) -> Self {
+        let tree = MerkleTree::commit(&evals);
+        Self { evals, tree }
+    }
+
+    pub fn root(&self) -> Scalar {
+        self.tree.root_hash()
+    }
+
+    pub fn open(&self, index: usize) -> Opening {
+        Opening {
+            index,
+            value: self.evals[index],
+            proof: self.tree.open_by_index(index),
+        }
+    }
+
+    // Open the k values at j + m * N/k, m in [0, k), eg: f(x) and f(-x) for k = 2.
+    pub fn open_coset(&self, j: usize, k: usize) -> Vec<Opening> {
+        let step = self.evals.len() / k;
+        assert!(j < step);
+        (0..k).map(|m| self.open(j + m * step)).collect()
+    }
+
+    // Open the values at the positions in order, eg: the ones of `BatchLDT::conjugate_indices`.
+    pub fn open_all(&self, positions: &[usize]) -> Vec<Opening> {
+        positions.iter().map(|p| self.open(*p)).collect()
+    }
+}
+
+impl BatchLDT {
+    // Commit and prove one codeword, which is moved into its `CommittedCodeword` rather than
+    // cloned, eg: a 2^20 codeword of the caller is the only copy P holds. The root is roots[0].
+    pub fn prove_owned(&self, codeword: Vec<Scalar>) -> BatchLDTProof {
+        self.prove(&[&CommittedCodeword::commit(codeword)])
+    }
+
+    pub fn prove(&self, codewords: &[&CommittedCodeword]) -> BatchLDTProof {
+        assert!(!codewords.is_empty());
+        let mut transcript = Keccak256Transcript::default();
+        let id = self.protocol_id();
+        transcript.bind_protocol(&id);
+        let mut proof = BatchLDTProof {
+            protocol: id.digest(),
+            ..Default::default()
+        };
+
+        // 1. batch the codewords: f = ∑ c_k * f_k
+        for cw in codewords.iter() {
+            assert_eq!(cw.evals.len(), self.domain.size);
+            proof.roots.push(cw.root());
+            absorb_root(&mut transcript, &cw.root());
+        }
+        let coeffs = rlc::coeffs_from_transcript(
+            &mut transcript,
+            GAMMA_LABEL,
+            codewords.len(),
+            self.params.coeffs,
+        );
+        // one codeword of the powers is f itself, as γ^0 = 1.
+        let mut f = (coeffs[..] != [Scalar::one()]).then(|| {
+            let evals = codewords.iter().map(|cw| &cw.evals[..]).collect::<Vec<_>>();
+            Secret::new(rlc::combine_vectors(&evals, &coeffs).expect("a coeff per codeword"))
+        });
+
+        // 2. commit phase, each fi is folded from the last committed layer, so it's not cloned,
+        //    and the batched f is folded in place.
+        let k = self.folding_factor();
+        let mut layers: Vec<CommittedCodeword> = vec![];
+        let mut domain = self.domain;
+        for i in 0..self.rounds() {
+            let alpha_i = transcript.challenge();
+            let f_i_plus_1 = match (layers.last(), f.take()) {
+                (Some(layer), _) => Secret::new(fold_codeword_k(&layer.evals, &domain, alpha_i, k)),
+                (None, Some(mut f)) => {
+                    fold_codeword_k_in_place(&mut f, alpha_i, &domain, k);
+                    f
+                }
+                (None, None) => {
+                    Secret::new(fold_codeword_k(&codewords[0].evals, &domain, alpha_i, k))
+                }
+            };
+            domain = domain.pow(k);
+
+            if i + 1 < self.rounds() {
+                let layer = CommittedCodeword::commit_secret(f_i_plus_1);
+                proof.layer_roots.push(layer.root());
+                absorb_root(&mut transcript, &layer.root());
+                layers.push(layer);
+            } else {
+                // f is a constant now, which is sent in clear.
+                proof.last_const = f_i_plus_1[0];
+            }
+        }
+        absorb_last_const(&mut transcript, &proof.last_const);
+
+        // 3. query phase
+        // the positions of `query_footprint`, see `layout`.
+        for _ in 0..self.num_queries {
+            let index =
+                challenge_to_index(&transcript.challenge().to_bytes(), self.domain.size / k);
+            let path = self.query_path(index);
+            let positions = self.conjugate_indices(0, index);
+            let inputs = codewords
+                .iter()
+                .map(|cw| cw.open_all(&positions))
+                .collect::<Vec<_>>();
+
+            let layers = layers
+                .iter()
+                .enumerate()
+                .map(|(i, layer)| layer.open_all(&self.conjugate_indices(i + 1, path[i + 1])))
+                .collect::<Vec<_>>();
+
+            proof.queries.push(QueryProof {
+                index,
+                inputs,
+                layers,
+            });
+        }
+
+        proof
+    }
+}
+
+// Fold the codeword over D into the codeword over D^2.
+pub fn fold_codeword(f: &[Scalar], domain: &EvaluationDomain, alpha: Scalar) -> Vec<Scalar> {
+    assert_eq!(f.len(), domain.size);
+    let half = domain.size / 2;
+    let two_inv = Scalar::from(2).invert().unwrap();
+    let mut x_inv = domain.elements()[..half].to_vec();
+    x_inv.iter_mut().batch_invert();
+
+    (0..half)
+        .map(|j| fold_with_inverses(f[j], f[j + half], alpha, two_inv, x_inv[j]))
+        .collect()
+}
+
+// Fold the codeword over D into the codeword over D^k.
+pub fn fold_codeword_k(
+    f: &[Scalar],
+    domain: &EvaluationDomain,
+    alpha: Scalar,
+    k: usize,
+) -> Vec<Scalar> {
+    assert_eq!(f.len(), domain.size);
+    let step = domain.size / k;
+    let k_inv = Scalar::from(k as u64).invert().unwrap();
+    let zeta_inv = inverse_roots_of_unity(k);
+    let mut x_inv = domain.elements()[..step].to_vec();
+    x_inv.iter_mut().batch_invert();
+
+    (0..step)
+        .map(|j| {
+            let values = (0..k).map(|m| f[j + m * step]).collect::<Vec<_>>();
+            fold_k_with_inverses(&values, alpha, k_inv, x_inv[j], &zeta_inv)
+        })
+        .collect()
+}
+
+// The same as `fold_codeword`, but the folded values are written in the front half of the
+// codeword, which is truncated, so it keeps its allocation, as `EvalTable::bind_first`.
+pub fn fold_codeword_in_place(
+    codeword: &mut Vec<Scalar>,
+    alpha: Scalar,
+    domain: &EvaluationDomain,
+) {
+    assert_eq!(codeword.len(), domain.size);
+    let half = domain.size / 2;
+    let two_inv = Scalar::from(2).invert().unwrap();
+    let mut x_inv = domain.offset.invert().unwrap();
+    let generator_inv = domain.generator.invert().unwrap();
+
+    // new[j] only reads f[j] and f[j + N/2], which are not overwritten yet.
+    for j in 0..half {
+        codeword[j] = fold_with_inverses(codeword[j], codeword[j + half], alpha, two_inv, x_inv);
+        x_inv *= generator_inv;
+    }
+    codeword.truncate(half);
+}
+
+// The same as `fold_codeword_k`, in place.
+pub fn fold_codeword_k_in_place(
+    codeword: &mut Vec<Scalar>,
+    alpha: Scalar,
+    domain: &EvaluationDomain,
+    k: usize,
+) {
+    assert_eq!(codeword.len(), domain.size);
+    let step = domain.size / k;
+    let k_inv = Scalar::from(k as u64).invert().unwrap();
+    let zeta_inv = inverse_roots_of_unity(k);
+    let mut x_inv = domain.offset.invert().unwrap();
+    let generator_inv = domain.generator.invert().unwrap();
+
+    // new[j] only reads f[j + m * N/k], m in [0, k), which are not overwritten yet.
+    let mut values = vec![Scalar::zero(); k];
+    for j in 0..step {
+        for (m, value) in values.iter_mut().enumerate() {
+            *value = codeword[j + m * step];
+        }
+        codeword[j] = fold_k_with_inverses(&values, alpha, k_inv, x_inv, &zeta_inv);
+        x_inv *= generator_inv;
+    }
+    codeword.truncate(step);
+}
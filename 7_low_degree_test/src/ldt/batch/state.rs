@@ -0,0 +1,300 @@
+// The verifier of the batched LDT, one query at a time, so it can stop after any query and
+// resume later from a `StateCheckpoint`, eg: a verifier with a bounded work per step.
+//
+// `start_verify` replays the commit phase, then `verify_query` checks the queries in order. The
+// checkpoint is bound to the commit phase of the proof (the roots, the layer roots and the last
+// constant), the queries left can be given after resuming, eg: `&proof.queries[round..]`.
+//
+//      | checkpoint | value                                                 |
+//      |------------|-------------------------------------------------------|
+//      | binding    | `BatchLDTProof::binding_digest`                       |
+//      | round      | the next query                                        |
+//      | transcript | the transcript after the last derived query index     |
+//      | claims     | γ (or the m coeffs), α_0, ..., α_{r-1}                |
+//
+// The indices and the trace events are the same as `verify_with_sink`, wherever it's split.
+use crate::ldt::batch::{
+    BatchLDT, BatchLDTProof, FoldChallenges, FoldPoints, LDTError, QueryProof,
+};
+use crate::trace::TraceSink;
+use crate::transcript::default::Keccak256Transcript;
+use crate::transcript::Transcript;
+use alloc::vec;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use sumcheck::cost::CostReport;
+use sumcheck::rlc;
+use Fiat_Shamir::challenge_to_index;
+use Fiat_Shamir::checkpoint::{proof_binding, CheckpointError, StateCheckpoint};
+
+impl BatchLDTProof {
+    // The proof without the queries.
+    pub fn commit_phase(&self) -> Self {
+        Self {
+            protocol: self.protocol,
+            roots: self.roots.clone(),
+            layer_roots: self.layer_roots.clone(),
+            queries: vec![],
+            last_const: self.last_const,
+        }
+    }
+
+    // The binding of the checkpoints of this proof, which is of the commit phase only.
+    pub fn binding_digest(&self) -> [u8; 32] {
+        proof_binding(&self.commit_phase().to_bytes())
+    }
+}
+
+pub struct LdtVerifierState<'a> {
+    ldt: &'a BatchLDT,
+    commit: BatchLDTProof,
+    binding: [u8; 32],
+    transcript: Keccak256Transcript,
+    challenges: FoldChallenges,
+    next_query: usize,
+}
+
+impl BatchLDT {
+    // Replay the commit phase of the proof, its queries are not read.
+    pub fn start_verify<S: TraceSink>(
+        &self,
+        roots: &[Scalar],
+        proof: &BatchLDTProof,
+        sink: &mut S,
+    ) -> Result<LdtVerifierState<'_>, LDTError> {
+        let mut transcript = Keccak256Transcript::default();
+        let challenges = self.replay_commit(&mut transcript, roots, proof, sink)?;
+        let commit = proof.commit_phase();
+        Ok(LdtVerifierState {
+            ldt: self,
+            binding: commit.binding_digest(),
+            commit,
+            transcript,
+            challenges,
+            next_query: 0,
+        })
+    }
+}
+
+impl<'a> LdtVerifierState<'a> {
+    pub fn next_query(&self) -> usize {
+        self.next_query
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.next_query == self.ldt.num_queries
+    }
+
+    // Check the next query, return its index in the domain. The state is unchanged on a failure.
+    pub fn verify_query<S: TraceSink>(
+        &mut self,
+        query: &QueryProof,
+        sink: &mut S,
+    ) -> Result<usize, LDTError> {
+        assert!(!self.is_done(), "all the queries are verified");
+        let ldt = self.ldt;
+        let mut transcript = self.transcript.clone();
+        let index = challenge_to_index(
+            &transcript.challenge().to_bytes(),
+            ldt.domain.size / ldt.folding_factor(),
+        );
+        sink.count(CostReport::hashes(2));
+        ldt.validate_query((self.next_query, index), query, self.commit.roots.len())?;
+        let x = ldt.fold_points(&[index], sink);
+        let x_inv = ldt.fold_inverses(&x, sink);
+        ldt.check_query(
+            sink,
+            (self.next_query, index),
+            query,
+            FoldPoints {
+                x: &x,
+                x_inv: &x_inv,
+            },
+            &self.commit,
+            &self.challenges,
+        )?;
+
+        self.transcript = transcript;
+        self.next_query += 1;
+        Ok(index)
+    }
+
+    pub fn verify_queries<S: TraceSink>(
+        &mut self,
+        queries: &[QueryProof],
+        sink: &mut S,
+    ) -> Result<Vec<usize>, LDTError> {
+        queries
+            .iter()
+            .map(|query| self.verify_query(query, sink))
+            .collect()
+    }
+
+    pub fn checkpoint(&self) -> StateCheckpoint {
+        let mut claims = self.challenges.gammas.clone();
+        claims.extend(self.challenges.alphas.iter());
+        StateCheckpoint {
+            binding: self.binding,
+            round: self.next_query,
+            transcript: self.transcript.snapshot(),
+            claims,
+        }
+    }
+
+    // Resume from a checkpoint of the same proof, of which only the commit phase is read.
+    pub fn resume(
+        ldt: &'a BatchLDT,
+        checkpoint: &StateCheckpoint,
+        proof: &BatchLDTProof,
+    ) -> Result<Self, CheckpointError> {
+        let commit = proof.commit_phase();
+        let binding = commit.binding_digest();
+        checkpoint.check_binding(&binding)?;
+        let (rounds, mode, m) = (ldt.rounds(), ldt.params.coeffs, commit.roots.len());
+        let gammas_len = rlc::challenges_len(mode, m);
+        if checkpoint.round > ldt.num_queries
+            || checkpoint.claims.len() != gammas_len + rounds
+            || commit.layer_roots.len() + 1 != rounds
+        {
+            return Err(CheckpointError::WrongShape);
+        }
+
+        let (gammas, alphas) = checkpoint.claims.split_at(gammas_len);
+        let gammas = (mode, gammas.to_vec(), m);
+        Ok(Self {
+            ldt,
+            commit,
+            binding,
+            transcript: Keccak256Transcript::restore(&checkpoint.transcript),
+            challenges: FoldChallenges::new(ldt.folding_factor(), gammas, alphas.to_vec()),
+            next_query: checkpoint.round,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod test {
+    use super::*;
+    use crate::domain::EvaluationDomain;
+    use crate::ldt::batch::{CoeffMode, CommittedCodeword, LdtParams};
+    use crate::poly::random_poly;
+    use crate::trace::VerificationTrace;
+    use ff::PrimeField;
+
+    fn prove(ldt: &BatchLDT) -> (Vec<Scalar>, BatchLDTProof) {
+        let codewords = (0..2)
+            .map(|_| {
+                CommittedCodeword::commit(ldt.domain.evaluate(&random_poly(ldt.degree_bound - 1)))
+            })
+            .collect::<Vec<_>>();
+        let proof = ldt.prove(&codewords.iter().collect::<Vec<_>>());
+        (codewords.iter().map(|c| c.root()).collect(), proof)
+    }
+
+    fn ldt() -> BatchLDT {
+        let domain = EvaluationDomain::coset(64, Scalar::MULTIPLICATIVE_GENERATOR);
+        BatchLDT::new(domain, 8, 5)
+    }
+
+    // verify the first `split` queries, then resume from the bytes of the checkpoint.
+    fn verify_split(
+        ldt: &BatchLDT,
+        roots: &[Scalar],
+        proof: &BatchLDTProof,
+        split: usize,
+        trace: &mut VerificationTrace,
+    ) -> Result<Vec<usize>, LDTError> {
+        let mut state = ldt.start_verify(roots, proof, trace)?;
+        let mut indices = state.verify_queries(&proof.queries[..split], trace)?;
+        let bytes = state.checkpoint().to_bytes();
+        drop(state);
+
+        let checkpoint = StateCheckpoint::from_bytes(&bytes).unwrap();
+        assert_eq!(checkpoint.round, split);
+        let mut state = LdtVerifierState::resume(ldt, &checkpoint, &proof.commit_phase()).unwrap();
+        indices.extend(state.verify_queries(&proof.queries[split..], trace)?);
+        assert!(state.is_done());
+        Ok(indices)
+    }
+
+    #[test]
+    fn test_split_at_every_query() {
+        let ldt = ldt();
+        let (roots, proof) = prove(&ldt);
+        let (expected, expected_trace) = ldt.verify_with_trace(&roots, &proof);
+        let expected = expected.map(|verified| verified.indices());
+        assert!(expected.is_ok());
+
+        for split in 0..=ldt.num_queries {
+            let mut trace = VerificationTrace::default();
+            let res = verify_split(&ldt, &roots, &proof, split, &mut trace);
+            assert_eq!(res, expected, "split at {}", split);
+            assert_eq!(trace, expected_trace, "split at {}", split);
+        }
+    }
+
+    // The checkpoint holds the m independent coeffs rather than γ.
+    #[test]
+    fn test_split_independent_coeffs() {
+        let params = LdtParams {
+            coeffs: CoeffMode::Independent,
+            ..Default::default()
+        };
+        let ldt = BatchLDT::with_params(ldt().domain, 8, 5, params);
+        let (roots, proof) = prove(&ldt);
+        let (expected, expected_trace) = ldt.verify_with_trace(&roots, &proof);
+        let expected = expected.map(|verified| verified.indices());
+        assert!(expected.is_ok());
+
+        let mut trace = VerificationTrace::default();
+        assert_eq!(verify_split(&ldt, &roots, &proof, 2, &mut trace), expected);
+        assert_eq!(trace, expected_trace);
+        let state = ldt.start_verify(&roots, &proof, &mut ()).unwrap();
+        assert_eq!(state.checkpoint().claims.len(), roots.len() + ldt.rounds());
+    }
+
+    #[test]
+    fn test_corrupted_query_after_resume() {
+        let ldt = ldt();
+        let (roots, mut proof) = prove(&ldt);
+        proof.queries[3].layers[0][1].value += Scalar::one();
+        let (expected, _) = ldt.verify_with_trace(&roots, &proof);
+        let expected = expected.map(|verified| verified.indices());
+        assert!(expected.is_err());
+
+        for split in 0..=3 {
+            let res = verify_split(
+                &ldt,
+                &roots,
+                &proof,
+                split,
+                &mut VerificationTrace::default(),
+            );
+            assert_eq!(res, expected, "split at {}", split);
+        }
+    }
+
+    #[test]
+    fn test_checkpoint_of_another_proof() {
+        let ldt = ldt();
+        let (roots, proof) = prove(&ldt);
+        let (_, another) = prove(&ldt);
+
+        let mut state = ldt.start_verify(&roots, &proof, &mut ()).unwrap();
+        state.verify_queries(&proof.queries[..2], &mut ()).unwrap();
+        let checkpoint = state.checkpoint();
+        assert!(matches!(
+            LdtVerifierState::resume(&ldt, &checkpoint, &another),
+            Err(CheckpointError::WrongProof)
+        ));
+        // the queries are not bound, only the commit phase.
+        assert!(LdtVerifierState::resume(&ldt, &checkpoint, &proof.commit_phase()).is_ok());
+
+        let mut past_the_end = checkpoint.clone();
+        past_the_end.round = ldt.num_queries + 1;
+        assert!(matches!(
+            LdtVerifierState::resume(&ldt, &past_the_end, &proof),
+            Err(CheckpointError::WrongShape)
+        ));
+    }
+}
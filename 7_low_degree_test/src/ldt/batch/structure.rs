@@ -0,0 +1,310 @@
+// The shape of a batched LDT proof, checked before any opening or fold, so a malformed proof is
+// rejected with the round and position it's wrong at, rather than by an index or an unwrap deep
+// in the folds.
+//
+// For the derived indices j_0 of the queries, with the paths (j_0, ..., j_{r-1}) of
+// `BatchLDT::query_path`, where r = log_k(d) and D_i = D^(k^i), the proof must have:
+//      rounds:  r - 1 layer roots, one per fi, i in [1, r)
+//      queries: one per derived index, at that index
+//      round 0: a coset per input codeword, and a coset of fi in each round i in [1, r)
+//      coset:   exactly the k positions j_i + m * |D_i|/k, m in [0, k), in that order
+//      path:    log2(|D_i|) siblings in each opening, the height of the tree over D_i
+// which are the `conjugate_indices` and the `RoundStructure` of the round, see `layout`.
+// So the checks of the queries read the cosets by m as they are, eg: `coset[path[i] / step]`.
+use crate::ldt::batch::{BatchLDT, BatchLDTProof, LDTError, Opening, QueryProof, RoundStructure};
+use alloc::vec::Vec;
+
+impl BatchLDTProof {
+    // Check the shape of the proof for the derived indices, see above. The openings are not
+    // checked against the roots, nor the folds.
+    pub fn validate_structure(&self, ldt: &BatchLDT, derived: &[usize]) -> Result<(), LDTError> {
+        ldt.check_round_count(self)?;
+        if self.queries.len() != derived.len() {
+            return Err(LDTError::WrongQueryCount {
+                expected: derived.len(),
+                found: self.queries.len(),
+            });
+        }
+        for (q, (query, index)) in self.queries.iter().zip(derived).enumerate() {
+            ldt.validate_query((q, *index), query, self.roots.len())?;
+        }
+        Ok(())
+    }
+}
+
+impl BatchLDT {
+    pub(super) fn check_round_count(&self, proof: &BatchLDTProof) -> Result<(), LDTError> {
+        let expected = self.rounds() - 1;
+        if proof.layer_roots.len() != expected {
+            return Err(LDTError::WrongRoundCount {
+                expected,
+                found: proof.layer_roots.len(),
+            });
+        }
+        Ok(())
+    }
+
+    // The shape of the q-th query at the derived index, for m input codewords.
+    pub(super) fn validate_query(
+        &self,
+        (q, index): (usize, usize),
+        query: &QueryProof,
+        m: usize,
+    ) -> Result<(), LDTError> {
+        if query.index != index {
+            return Err(LDTError::WrongQueryIndex { query: q });
+        }
+        if query.inputs.len() != m {
+            return Err(LDTError::WrongInputCount {
+                query: q,
+                expected: m,
+                found: query.inputs.len(),
+            });
+        }
+        let rounds = self.rounds();
+        if query.layers.len() != rounds - 1 {
+            return Err(LDTError::WrongLayerCount {
+                query: q,
+                expected: rounds - 1,
+                found: query.layers.len(),
+            });
+        }
+
+        let path = self.query_path(index);
+        for coset in query.inputs.iter() {
+            let positions = self.conjugate_indices(0, path[0]);
+            validate_coset(&self.round(0), coset, &positions)?;
+        }
+        for (i, coset) in query.layers.iter().enumerate() {
+            let positions = self.conjugate_indices(i + 1, path[i + 1]);
+            validate_coset(&self.round(i + 1), coset, &positions)?;
+        }
+        Ok(())
+    }
+}
+
+// The openings of the round must be the positions of `conjugate_indices` in order, each one with
+// a path of `tree_height` siblings. The first wrong position is reported, in the order of: out
+// of the domain, opened twice, not queried, not opened, out of its place, and the path length.
+fn validate_coset(
+    structure: &RoundStructure,
+    coset: &[Opening],
+    expected: &[usize],
+) -> Result<(), LDTError> {
+    let (round, size) = (structure.round, structure.domain_size);
+    let mut seen = Vec::with_capacity(coset.len());
+    for o in coset.iter() {
+        let position = o.index;
+        if position >= size {
+            return Err(LDTError::PositionOutOfRange {
+                round,
+                position,
+                size,
+            });
+        }
+        if seen.contains(&position) {
+            return Err(LDTError::DuplicateOpening { round, position });
+        }
+        if !expected.contains(&position) {
+            return Err(LDTError::UnexpectedOpening { round, position });
+        }
+        seen.push(position);
+    }
+    if let Some(&position) = expected.iter().find(|p| !seen.contains(p)) {
+        return Err(LDTError::MissingOpening { round, position });
+    }
+    // the same positions by now, so only the order may differ.
+    for (o, position) in coset.iter().zip(expected) {
+        if o.index != *position {
+            return Err(LDTError::MisplacedOpening {
+                round,
+                position: o.index,
+            });
+        }
+    }
+    let height = structure.tree_height;
+    for o in coset.iter() {
+        if o.proof.children.len() != height {
+            return Err(LDTError::WrongPathLength {
+                round,
+                position: o.index,
+                expected: height,
+                found: o.proof.children.len(),
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod test {
+    use super::*;
+    use crate::domain::EvaluationDomain;
+    use crate::ldt::batch::{CommittedCodeword, LdtParams};
+    use crate::poly::random_poly;
+    use bls12_381::Scalar;
+    use ff::PrimeField;
+
+    // 2 codewords over the coset of size 64, of deg < 8 by 2 and deg < 16 by 4.
+    fn prove(k: usize) -> (BatchLDT, Vec<Scalar>, BatchLDTProof) {
+        let domain = EvaluationDomain::coset(64, Scalar::MULTIPLICATIVE_GENERATOR);
+        let params = LdtParams {
+            folding_factor: k,
+            ..Default::default()
+        };
+        let degree_bound = if k == 2 { 8 } else { 16 };
+        let ldt = BatchLDT::with_params(domain, degree_bound, 3, params);
+        let codewords = (0..2)
+            .map(|_| CommittedCodeword::commit(domain.evaluate(&random_poly(degree_bound - 1))))
+            .collect::<Vec<_>>();
+        let proof = ldt.prove(&codewords.iter().collect::<Vec<_>>());
+        let roots = codewords.iter().map(|c| c.root()).collect();
+        (ldt, roots, proof)
+    }
+
+    fn derived(proof: &BatchLDTProof) -> Vec<usize> {
+        proof.queries.iter().map(|q| q.index).collect()
+    }
+
+    #[test]
+    fn test_honest_proof_is_valid() {
+        for k in [2, 4] {
+            let (ldt, roots, proof) = prove(k);
+            assert_eq!(proof.validate_structure(&ldt, &derived(&proof)), Ok(()));
+            assert!(ldt.verify_with_sink(&roots, &proof, &mut ()).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_malformed_openings() {
+        let (ldt, roots, proof) = prove(2);
+        let derived = derived(&proof);
+        let reject = |crafted: &BatchLDTProof, expected: LDTError| {
+            assert_eq!(crafted.validate_structure(&ldt, &derived), Err(expected));
+            assert_eq!(
+                ldt.verify_with_sink(&roots, crafted, &mut ()).err(),
+                Some(expected)
+            );
+        };
+        // the coset of round 1 in the 2nd query, over D^2 of size 32
+        let coset = &proof.queries[1].layers[0];
+        let (first, second) = (coset[0].clone(), coset[1].clone());
+
+        // the same position twice, with another value
+        let mut crafted = proof.clone();
+        let mut twin = first.clone();
+        twin.value += Scalar::one();
+        crafted.queries[1].layers[0][1] = twin;
+        let position = first.index;
+        reject(&crafted, LDTError::DuplicateOpening { round: 1, position });
+
+        // a position V never asked for
+        let mut crafted = proof.clone();
+        crafted.queries[1].layers[0][1].index = (second.index + 1) % 32;
+        let position = (second.index + 1) % 32;
+        reject(&crafted, LDTError::UnexpectedOpening { round: 1, position });
+
+        // no conjugate opening
+        let mut crafted = proof.clone();
+        crafted.queries[1].layers[0].pop();
+        let position = second.index;
+        reject(&crafted, LDTError::MissingOpening { round: 1, position });
+
+        // out of the domain of the round
+        let mut crafted = proof.clone();
+        crafted.queries[1].layers[0][0].index = 32 + first.index;
+        let (position, size) = (32 + first.index, 32);
+        let expected = LDTError::PositionOutOfRange {
+            round: 1,
+            position,
+            size,
+        };
+        reject(&crafted, expected);
+
+        // the conjugates swapped
+        let mut crafted = proof.clone();
+        crafted.queries[1].layers[0].swap(0, 1);
+        let position = second.index;
+        reject(&crafted, LDTError::MisplacedOpening { round: 1, position });
+
+        // a path of the tree over D, in round 1
+        let mut crafted = proof.clone();
+        let path = &mut crafted.queries[1].layers[0][0].proof.children;
+        path.push(path[0]);
+        let expected = LDTError::WrongPathLength {
+            round: 1,
+            position: first.index,
+            expected: 5,
+            found: 6,
+        };
+        reject(&crafted, expected);
+    }
+
+    #[test]
+    fn test_malformed_rounds_and_queries() {
+        let (ldt, roots, proof) = prove(4);
+        let derived = derived(&proof);
+        let reject = |crafted: &BatchLDTProof, expected: LDTError| {
+            assert_eq!(crafted.validate_structure(&ldt, &derived), Err(expected));
+            assert_eq!(
+                ldt.verify_with_sink(&roots, crafted, &mut ()).err(),
+                Some(expected)
+            );
+        };
+
+        // 16 = 4^2, so a layer root and a layer in each query
+        let mut crafted = proof.clone();
+        crafted.layer_roots.push(Scalar::one());
+        let expected = LDTError::WrongRoundCount {
+            expected: 1,
+            found: 2,
+        };
+        reject(&crafted, expected);
+
+        let mut crafted = proof.clone();
+        crafted.queries.pop();
+        let expected = LDTError::WrongQueryCount {
+            expected: 3,
+            found: 2,
+        };
+        reject(&crafted, expected);
+
+        let mut crafted = proof.clone();
+        crafted.queries[2].inputs.pop();
+        let expected = LDTError::WrongInputCount {
+            query: 2,
+            expected: 2,
+            found: 1,
+        };
+        reject(&crafted, expected);
+
+        let mut crafted = proof.clone();
+        crafted.queries[0].layers.clear();
+        let expected = LDTError::WrongLayerCount {
+            query: 0,
+            expected: 1,
+            found: 0,
+        };
+        reject(&crafted, expected);
+
+        // the inputs of the 1st codeword miss ζ^3, which is at 3 * 64/4 from the index
+        let mut crafted = proof.clone();
+        crafted.queries[0].inputs[0].pop();
+        let position = proof.queries[0].index + 48;
+        reject(&crafted, LDTError::MissingOpening { round: 0, position });
+    }
+
+    #[test]
+    fn test_error_names_round_and_position() {
+        let err = LDTError::DuplicateOpening {
+            round: 2,
+            position: 7,
+        };
+        assert_eq!(err.to_string(), "Verifier: round-2 opens 7 twice.");
+        let err = LDTError::MissingOpening {
+            round: 0,
+            position: 35,
+        };
+        assert_eq!(err.to_string(), "Verifier: round-0 doesn't open 35.");
+    }
+}
@@ -7,7 +7,7 @@ use ark_std::log2;
 use bls12_381::Scalar;
 use rayon::iter::split;
 use std::iter::Scan;
-use std::ops::{Add, Mul};
+use std::ops::Mul;
 
 pub struct Prover {
     poly: Polynomial,
@@ -72,13 +72,14 @@ impl Prover {
         let alpha_i = transcript.challenge();
         // compute new poly fi+1, which is the random linear combination of p_L,p_R,
         //      f_i_1 = f_L + c*f_R
-        let p_i_plus_1 = p_L.add(&p_L.mul(&alpha_i));
+        let p_i_plus_1 = &p_L + &(&p_L * &alpha_i);
 
         // 3. commit phase
         //  merkle tree commit the poly fi+1
-        let merkle_tree = MerkleTree::commit(p_i_plus_1.coeffs().clone());
+        let merkle_tree: MerkleTree = MerkleTree::commit(&p_i_plus_1.coeffs());
         // 4. query phase
-        let cm_i = merkle_tree.open(&merkle_c_i);
+        //  the challenge maybe not in the coeffs, so no opening.
+        let cm_i: Vec<MerkleProof> = merkle_tree.open(&merkle_c_i).into_iter().collect();
 
         // 5. evaluate
         let f_z = p_i_plus_1.evaluate(z_i.clone());
@@ -1,4 +1,6 @@
+use crate::ldt::batch::fold_check;
 use crate::ldt::LDTProof;
+use crate::merkle_tree::leaf::Leaf;
 use crate::merkle_tree::proof::MerkleProof;
 use crate::merkle_tree::MerkleTree;
 use crate::transcript::default::Keccak256Transcript;
@@ -30,45 +32,52 @@ impl Verifier {
         assert_eq!(proof.evals.len(), d - 1);
         let commits = &proof.commits;
         let evals = &proof.evals;
-        let mut z_i = self.z; // z^1 = z^(2^0)
+        let mut z_i = self.z; // z^1 = z^(2^0), then z^(2^i), Important !!!
 
         let mut merkle_c_i = self.merkle_c;
 
-        let two_inv = Scalar::from_u128(2).invert().unwrap();
+        assert!(z_i != Scalar::zero(), "Verifier: z must not be zero");
         for i in 0..(d - 1) {
             println!("");
             println!("round: i: {:?}", i);
             println!("evals.len: {:?}", evals.len());
-            // 1. obtain fi_L(z^2) ,fi_R (z^2) with: fi(z) = fi_L(z^2) + z fi_R (z^2)
             let (f_i_z, f_i_neg_z): (Scalar, Scalar) = *evals.get(i).unwrap();
-            let f_i_L = (f_i_z.add(&f_i_neg_z)).mul(&two_inv);
-            // calc fiR
-            let double_z_inv = z_i.double().invert().unwrap();
-            let f_i_R = (f_i_z.sub(&f_i_neg_z)).mul(&double_z_inv);
 
             if d == 1 || (d - 2) == i {
+                // 1. obtain fi_L(z^2) ,fi_R (z^2) with: fi(z) = fi_L(z^2) + z fi_R (z^2), the
+                // last round only, as the other ones are checked by `fold_check`.
+                let two_inv = Scalar::from_u128(2).invert().unwrap();
+                let f_i_l = (f_i_z.add(&f_i_neg_z)).mul(&two_inv);
+                // calc fiR
+                let f_i_r = (f_i_z.sub(&f_i_neg_z)).mul(&z_i.double().invert().unwrap());
+
                 // 2. last round check
                 assert_eq!(
                     proof.last_const,
-                    (f_i_L, f_i_R),
+                    (f_i_l, f_i_r),
                     "Verifier: Last round check failed."
                 );
             } else {
                 // 2. check fi+1(z^2) = fi_L(z^2) + αi*fi_R(z^2)
                 let alpha = transcript.challenge();
-                let f_i_plus_1 = f_i_L + alpha * f_i_R;
                 let (target_f_i_plus_1, _): (Scalar, Scalar) = *evals.get(i + 1).unwrap();
-                assert_eq!(
-                    f_i_plus_1, target_f_i_plus_1,
+                assert!(
+                    fold_check(f_i_z, f_i_neg_z, target_f_i_plus_1, alpha, z_i),
                     "Verifier: round-{i} check failed."
                 );
 
                 // 3. verify the cm todo
-                MerkleTree::verify(&merkle_c_i, commits.get(i).unwrap());
+                let leaf = merkle_c_i.to_leaf_bytes();
+                for cm in commits.get(i).unwrap() {
+                    assert!(
+                        MerkleTree::verify(&cm.root, cm, &leaf),
+                        "Verifier: round-{i} opening check failed."
+                    );
+                }
 
                 // prepare for next round
-                z_i = z_i.mul(&z_i); // z^(2^i), Important !!!
                 merkle_c_i.double();
+                z_i = z_i.mul(&z_i);
             }
         }
     }
@@ -1,8 +1,32 @@
 //! This is the implement of the FRI-LDT. See more on [Fast reed-solomon interactive oracle proofs of proximity](https://eccc.weizmann.ac.il/report/2017/134)
 //! and [A summary on the fri low degree test](https://eprint.iacr.org/2022/1216)
+//!
+//! Everything but the single-poly `LDT` builds without std, see the `alloc` feature.
+//!
+//! The `verifier` feature alone is the verifier-only build: `BatchLDT::verify*`, the
+//! `LdtVerifierState` and the merkle openings, without `CommittedCodeword` and `BatchLDT::prove`.
+//!
+//! `pcs` commits a poly by its codeword, and opens it at several points by one quotient and one
+//! batched LDT, see `PolyCommitment::open_multi`.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
+#[cfg(not(feature = "alloc"))]
+compile_error!("low_degree_test requires the `std` or `alloc` feature");
+
+// The verifier-only build has no OsRng nor rayon, which come with `std`.
+#[cfg(all(feature = "verifier", not(feature = "prover"), feature = "std"))]
+compile_error!(
+    "the verifier-only build of low_degree_test can't enable `std`, it brings OsRng and rayon"
+);
+
+extern crate alloc;
+
+#[cfg(all(test, feature = "prover"))]
+mod analysis;
+pub mod domain;
 pub mod ldt;
-mod merkle_tree;
-mod poly;
+pub use Merkle_tree_commitment::merkle_tree;
+pub mod pcs;
+pub mod poly;
+pub use sumcheck::trace;
 mod transcript;
-mod utils;
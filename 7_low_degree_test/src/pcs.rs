@@ -0,0 +1,372 @@
+// The polynomial commitment of the batched LDT: p with deg < d is committed by the merkle root of
+// its codeword over D, and the claims p(z_i) = y_i, i in [0, m), are opened at once by
+//      q(X) = (p(X) - I(X)) / Z(X), Z(X) = ∏(X - z_i)
+// where I is the poly with deg < m through the (z_i, y_i). q is a poly iff every claim holds, so
+// P commits the codeword of q, the batched LDT tests p and q together, and on each queried x V
+// checks the openings of the LDT by
+//      p(x) - I(x) == q(x) * Z(x)
+// so the m claims cost one quotient and one LDT rather than m of them.
+//
+// q has deg < d - m, but it's tested against d with p, so the identity is of degree < d + m,
+// which the queries of D catch as long as d + m is far below |D|.
+//
+// x may be some z_i, eg: a point of D is claimed, then Z(x) = 0 and q(x) is free, so V checks
+// p(x) == y_i instead, which is what the identity says there, see `Claims::check`.
+use crate::domain::EvaluationDomain;
+#[cfg(feature = "prover")]
+use crate::ldt::batch::CommittedCodeword;
+use crate::ldt::batch::{BatchLDT, BatchLDTProof, LDTError};
+use crate::poly::Polynomial;
+use alloc::vec::Vec;
+use bls12_381::Scalar;
+use core::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PcsError {
+    NoPoint,
+    // z_first == z_second, so I and Z are not defined.
+    DuplicatePoint { first: usize, second: usize },
+    // the proof claims `found` values for the `expected` points.
+    WrongValueCount { expected: usize, found: usize },
+    // p or q is rejected by the batched LDT.
+    Ldt(LDTError),
+    // p(x) - I(x) != q(x) * Z(x) at the index of D opened by the query.
+    QuotientMismatch { query: usize, index: usize },
+    // the index of D is z_point, and p(z_point) != y_point.
+    ClaimMismatch { point: usize, index: usize },
+}
+
+impl fmt::Display for PcsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PcsError::NoPoint => write!(f, "Verifier: no point to open."),
+            PcsError::DuplicatePoint { first, second } => {
+                write!(f, "Verifier: point-{second} is the same as point-{first}.")
+            }
+            PcsError::WrongValueCount { expected, found } => {
+                write!(f, "Verifier: {found} values claimed for {expected} points.")
+            }
+            PcsError::Ldt(e) => write!(f, "{}", e),
+            PcsError::QuotientMismatch { query, index } => {
+                write!(
+                    f,
+                    "Verifier: query-{query} quotient check failed at {index}."
+                )
+            }
+            PcsError::ClaimMismatch { point, index } => {
+                write!(f, "Verifier: the claim of point-{point} fails at {index}.")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PcsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PcsError::Ldt(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+// The claimed values of p at the points, and the batched LDT of (p, q), whose roots[1] is the
+// commit of q. The points are known by V, so they're not in the proof.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MultiEvalProof {
+    pub values: Vec<Scalar>, // y_i = p(z_i)
+    pub ldt: BatchLDTProof,
+}
+
+impl MultiEvalProof {
+    // Number of the scalars in the proof, each one is 32 bytes.
+    pub fn num_scalars(&self) -> usize {
+        self.values.len() + self.ldt.num_scalars()
+    }
+}
+
+// P of the commitment: p and its committed codeword.
+#[cfg(feature = "prover")]
+pub struct CommittedPoly {
+    pub poly: Polynomial,
+    pub codeword: CommittedCodeword,
+}
+
+#[cfg(feature = "prover")]
+impl CommittedPoly {
+    pub fn root(&self) -> Scalar {
+        self.codeword.root()
+    }
+}
+
+pub struct PolyCommitment {
+    pub ldt: BatchLDT, // the domain D, the degree bound d and the queries of the openings
+}
+
+impl PolyCommitment {
+    pub fn new(domain: EvaluationDomain, degree_bound: usize, num_queries: usize) -> Self {
+        Self {
+            ldt: BatchLDT::new(domain, degree_bound, num_queries),
+        }
+    }
+
+    #[cfg(feature = "prover")]
+    pub fn commit(&self, poly: Polynomial) -> CommittedPoly {
+        assert!(
+            poly.degree() < self.ldt.degree_bound,
+            "poly degree is out of the degree bound"
+        );
+        let codeword = CommittedCodeword::commit(self.ldt.domain.evaluate(&poly));
+        CommittedPoly { poly, codeword }
+    }
+
+    // Open p at the distinct points by the quotient q, see above.
+    #[cfg(feature = "prover")]
+    pub fn open_multi(&self, state: &CommittedPoly, points: &[Scalar]) -> MultiEvalProof {
+        if let Err(e) = check_points(points) {
+            panic!("{}", e);
+        }
+        let values = points
+            .iter()
+            .map(|z| state.poly.evaluate(*z))
+            .collect::<Vec<_>>();
+        let interpolant = Polynomial::lagrange_interpolate(points.to_vec(), values.clone());
+        let (q, r) = (&state.poly - &interpolant).div_rem(&vanishing_poly(points));
+        debug_assert!(r.is_zero(), "p - I vanishes on the points");
+        let quotient = CommittedCodeword::commit(self.ldt.domain.evaluate(&q));
+        MultiEvalProof {
+            values,
+            ldt: self.ldt.prove(&[&state.codeword, &quotient]),
+        }
+    }
+
+    // Verify the claims p(z_i) = y_i of the proof against the commit of p.
+    pub fn verify_multi(
+        &self,
+        root: &Scalar,
+        points: &[Scalar],
+        proof: &MultiEvalProof,
+    ) -> Result<(), PcsError> {
+        check_points(points)?;
+        if proof.values.len() != points.len() {
+            return Err(PcsError::WrongValueCount {
+                expected: points.len(),
+                found: proof.values.len(),
+            });
+        }
+        // the commit of q is the one of the proof, the LDT rejects the other counts of roots.
+        let quotient_root = proof.ldt.roots.get(1).copied().unwrap_or_default();
+        self.ldt
+            .verify_with_sink(&[*root, quotient_root], &proof.ldt, &mut ())
+            .map_err(PcsError::Ldt)?;
+
+        // the openings are checked by the LDT, and p and q are opened at the same indices.
+        let claims = Claims::new(points, &proof.values);
+        for (q, query) in proof.ldt.queries.iter().enumerate() {
+            for (p_x, q_x) in query.inputs[0].iter().zip(query.inputs[1].iter()) {
+                let x = self.ldt.domain.element(p_x.index);
+                claims.check((q, p_x.index), x, p_x.value, q_x.value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// The points are distinct, and there's one at least.
+fn check_points(points: &[Scalar]) -> Result<(), PcsError> {
+    if points.is_empty() {
+        return Err(PcsError::NoPoint);
+    }
+    for (second, z) in points.iter().enumerate() {
+        if let Some(first) = points[..second].iter().position(|other| other == z) {
+            return Err(PcsError::DuplicatePoint { first, second });
+        }
+    }
+    Ok(())
+}
+
+// Z(X) = ∏(X - z_i)
+#[cfg(feature = "prover")]
+fn vanishing_poly(points: &[Scalar]) -> Polynomial {
+    points
+        .iter()
+        .fold(Polynomial::constant(Scalar::one()), |z, z_i| {
+            &z * &(&Polynomial::x() - &Polynomial::constant(*z_i))
+        })
+}
+
+// The claims of V, with their interpolant I.
+struct Claims<'a> {
+    points: &'a [Scalar],
+    values: &'a [Scalar],
+    interpolant: Polynomial,
+}
+
+impl<'a> Claims<'a> {
+    fn new(points: &'a [Scalar], values: &'a [Scalar]) -> Self {
+        let interpolant = Polynomial::lagrange_interpolate(points.to_vec(), values.to_vec());
+        Self {
+            points,
+            values,
+            interpolant,
+        }
+    }
+
+    // p(x) - I(x) == q(x) * Z(x) at the index of D of the query, or p(x) == y_i if x = z_i.
+    fn check(
+        &self,
+        (query, index): (usize, usize),
+        x: Scalar,
+        p_x: Scalar,
+        q_x: Scalar,
+    ) -> Result<(), PcsError> {
+        if let Some(point) = self.points.iter().position(|z| *z == x) {
+            if p_x != self.values[point] {
+                return Err(PcsError::ClaimMismatch { point, index });
+            }
+            return Ok(());
+        }
+        let z_x = self
+            .points
+            .iter()
+            .fold(Scalar::one(), |acc, z_i| acc * (x - z_i));
+        if p_x - self.interpolant.evaluate(x) != q_x * z_x {
+            return Err(PcsError::QuotientMismatch { query, index });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod test {
+    use super::*;
+    use crate::poly::random_poly;
+    use ff::{Field, PrimeField};
+    use rand_core::OsRng;
+
+    // d = 16 on |D| = 64, k = 2, so the queries open 8 of the 32 cosets.
+    fn pcs() -> PolyCommitment {
+        let domain = EvaluationDomain::coset(64, Scalar::MULTIPLICATIVE_GENERATOR);
+        PolyCommitment::new(domain, 16, 8)
+    }
+
+    fn random_points(m: usize) -> Vec<Scalar> {
+        (0..m).map(|_| Scalar::random(OsRng)).collect()
+    }
+
+    #[test]
+    fn test_open_multi() {
+        let pcs = pcs();
+        let state = pcs.commit(random_poly(15));
+        let points = random_points(3);
+        let proof = pcs.open_multi(&state, &points);
+        assert_eq!(pcs.verify_multi(&state.root(), &points, &proof), Ok(()));
+        for (z, y) in points.iter().zip(proof.values.iter()) {
+            assert_eq!(state.poly.evaluate(*z), *y);
+        }
+
+        // 3 single-point openings take 3 LDTs.
+        let singles = points
+            .iter()
+            .map(|z| pcs.open_multi(&state, &[*z]).num_scalars())
+            .sum::<usize>();
+        assert!(proof.num_scalars() < singles);
+    }
+
+    #[test]
+    fn test_wrong_value() {
+        let pcs = pcs();
+        let state = pcs.commit(random_poly(15));
+        let points = random_points(3);
+        let proof = pcs.open_multi(&state, &points);
+        for i in 0..3 {
+            let mut wrong = proof.clone();
+            wrong.values[i] += Scalar::one();
+            assert!(matches!(
+                pcs.verify_multi(&state.root(), &points, &wrong),
+                Err(PcsError::QuotientMismatch { query: 0, .. })
+            ));
+        }
+
+        // nor for another commit, or other points.
+        let other = pcs.commit(random_poly(15));
+        assert!(matches!(
+            pcs.verify_multi(&other.root(), &points, &proof),
+            Err(PcsError::Ldt(LDTError::WrongInputCommits))
+        ));
+        let mut moved = points.clone();
+        moved[1] += Scalar::one();
+        assert!(pcs.verify_multi(&state.root(), &moved, &proof).is_err());
+        assert_eq!(
+            pcs.verify_multi(&state.root(), &points[..2], &proof),
+            Err(PcsError::WrongValueCount {
+                expected: 2,
+                found: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_duplicate_points() {
+        let pcs = pcs();
+        let state = pcs.commit(random_poly(15));
+        let points = random_points(3);
+        let proof = pcs.open_multi(&state, &points);
+        let duplicated = [points[0], points[1], points[0]];
+        assert_eq!(
+            pcs.verify_multi(&state.root(), &duplicated, &proof),
+            Err(PcsError::DuplicatePoint {
+                first: 0,
+                second: 2
+            })
+        );
+        assert_eq!(
+            pcs.verify_multi(&state.root(), &[], &proof),
+            Err(PcsError::NoPoint)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "point-1 is the same as point-0")]
+    fn test_open_duplicate_points() {
+        let pcs = pcs();
+        let state = pcs.commit(random_poly(15));
+        let z = Scalar::random(OsRng);
+        pcs.open_multi(&state, &[z, z]);
+    }
+
+    // z_1 is a point of D which the LDT opens, so Z(x) = 0 there, and p(x) is checked against y_1.
+    #[test]
+    fn test_query_on_point() {
+        let pcs = pcs();
+        let poly = Polynomial::from_coeffs((1..=16u64).map(Scalar::from).collect());
+        let state = pcs.commit(poly);
+        let others = [Scalar::from(3), Scalar::from(5)];
+        // the queries depend on the commit of q, so on z_1, try the indices until one is opened.
+        let (index, points, proof) = (0..pcs.ldt.domain.size)
+            .find_map(|index| {
+                let points = [pcs.ldt.domain.element(index), others[0], others[1]];
+                let proof = pcs.open_multi(&state, &points);
+                let opened = proof
+                    .ldt
+                    .queries
+                    .iter()
+                    .any(|query| query.inputs[0].iter().any(|o| o.index == index));
+                opened.then_some((index, points, proof))
+            })
+            .expect("a query on a point of D");
+        assert_eq!(pcs.verify_multi(&state.root(), &points, &proof), Ok(()));
+
+        // q(x) is free there, and p(x) must be y_1.
+        let claims = Claims::new(&points, &proof.values);
+        let (x, y) = (points[0], proof.values[0]);
+        assert_eq!(
+            claims.check((0, index), x, y, Scalar::random(OsRng)),
+            Ok(())
+        );
+        assert_eq!(
+            claims.check((0, index), x, y + Scalar::one(), Scalar::zero()),
+            Err(PcsError::ClaimMismatch { point: 0, index })
+        );
+    }
+}
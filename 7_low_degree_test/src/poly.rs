@@ -1,5 +1,8 @@
+use alloc::vec::Vec;
 use bls12_381::Scalar;
+#[cfg(any(feature = "std", test))]
 use ff::Field;
+#[cfg(any(feature = "std", test))]
 use rand_core::OsRng;
 pub use sumcheck::poly::univar_poly::*;
 
@@ -14,7 +17,25 @@ pub fn split_poly(p: &Polynomial) -> (Polynomial, Polynomial) {
     (Polynomial::from_coeffs(odd), Polynomial::from_coeffs(even))
 }
 
+// f(x) = ∑ x^j * f_j(x^k), j in [0, k), where f_j takes every k-th coeff from the j-th.
+// eg: k = 2 is the (fL, fR) of `split_poly`.
+pub fn split_poly_k(p: &Polynomial, k: usize) -> Vec<Polynomial> {
+    assert!(k.is_power_of_two() && k > 1, "k must be 2^i, i > 0");
+    let coeffs = p.coeffs();
+    (0..k)
+        .map(|j| {
+            let f_j: Vec<Scalar> = coeffs.iter().skip(j).step_by(k).cloned().collect();
+            if f_j.is_empty() {
+                Polynomial::zero()
+            } else {
+                Polynomial::from_coeffs(f_j)
+            }
+        })
+        .collect()
+}
+
 // random a poly with a degree
+#[cfg(any(feature = "std", test))]
 pub fn random_poly(degree: usize) -> Polynomial {
     assert!(degree >= 0);
     let coeffs = (0..=degree)
@@ -29,36 +50,82 @@ pub fn random_poly(degree: usize) -> Polynomial {
 #[cfg(test)]
 mod test {
     use super::*;
+    use sumcheck::scalars;
 
     #[test]
     fn test_split() {
         let deg = 5;
         let poly = random_poly(deg);
 
-        let (pL, pR) = split_poly(&poly);
+        let (p_l, p_r) = split_poly(&poly);
 
         // check that f(z) == fL(x^2) + x * fR(x^2), for a rand z
         let z = Scalar::random(OsRng);
         assert_eq!(
             poly.evaluate(z.clone()),
-            pL.evaluate(z.square()) + z * pR.evaluate(z.square())
+            p_l.evaluate(z.square()) + z * p_r.evaluate(z.square())
         );
     }
 
+    #[test]
+    fn test_split_fixture() {
+        // f(x) = 1 - 2x + 3x^2 + 4x^3 - 5x^4 + 6x^5
+        let poly = Polynomial::from_coeffs(scalars![1, -2, 3, 4, -5, 6]);
+
+        let (p_l, p_r) = split_poly(&poly);
+
+        // fL(x) = 1 + 3x - 5x^2, fR(x) = -2 + 4x + 6x^2
+        assert_eq!(p_l, Polynomial::from_coeffs(scalars![1, 3, -5]));
+        assert_eq!(p_r, Polynomial::from_coeffs(scalars![-2, 4, 6]));
+    }
+
     #[test]
     fn test_split_more() {
         // let deg = 5;
         for deg in 1..5 {
             let poly = random_poly(deg);
 
-            let (pL, pR) = split_poly(&poly);
+            let (p_l, p_r) = split_poly(&poly);
 
             // check that f(z) == fL(x^2) + x * fR(x^2), for a rand z
             let z = Scalar::random(OsRng);
             assert_eq!(
                 poly.evaluate(z.clone()),
-                pL.evaluate(z.square()) + z * pR.evaluate(z.square())
+                p_l.evaluate(z.square()) + z * p_r.evaluate(z.square())
             );
         }
     }
+
+    #[test]
+    fn test_split_k() {
+        // f(x) = 1 - 2x + 3x^2 + 4x^3 - 5x^4 + 6x^5
+        let poly = Polynomial::from_coeffs(scalars![1, -2, 3, 4, -5, 6]);
+        let (p_l, p_r) = split_poly(&poly);
+        assert_eq!(split_poly_k(&poly, 2), vec![p_l, p_r]);
+
+        // f_0 = 1 - 5x, f_1 = -2 + 6x, f_2 = 3, f_3 = 4
+        assert_eq!(
+            split_poly_k(&poly, 4),
+            vec![
+                Polynomial::from_coeffs(scalars![1, -5]),
+                Polynomial::from_coeffs(scalars![-2, 6]),
+                Polynomial::from_coeffs(scalars![3]),
+                Polynomial::from_coeffs(scalars![4]),
+            ]
+        );
+
+        // check that f(z) == ∑ z^j * f_j(z^k), for a rand z
+        for k in [2, 4, 8] {
+            let poly = random_poly(19);
+            let z = Scalar::random(OsRng);
+            let z_k = z.pow_vartime(&[k as u64, 0, 0, 0]);
+            let mut z_j = Scalar::one();
+            let mut sum = Scalar::zero();
+            for f_j in split_poly_k(&poly, k) {
+                sum += z_j * f_j.evaluate(z_k);
+                z_j *= z;
+            }
+            assert_eq!(poly.evaluate(z), sum);
+        }
+    }
 }
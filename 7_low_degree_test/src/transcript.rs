@@ -3,54 +3,50 @@
 use bls12_381::Scalar;
 
 use crate::poly::Polynomial;
+use alloc::vec::Vec;
+use Fiat_Shamir::codec::{encode_labeled, encode_scalars};
+use Fiat_Shamir::protocol::ProtocolId;
 pub mod default;
 
 pub trait Transcript {
     fn append(&mut self, new_data: &[u8]);
 
     fn challenge(&mut self) -> Scalar;
-}
 
-pub(crate) fn poly_to_bytes(poly: &Polynomial) -> Vec<u8> {
-    coeffs_to_bytes(&poly.coeffs())
+    // Absorb the protocol and its params first, the same bytes as
+    // `Fiat_Shamir::Transcript::bind_protocol`.
+    fn bind_protocol(&mut self, id: &ProtocolId) {
+        self.append(&encode_labeled("protocol", &id.to_bytes()));
+    }
 }
 
-fn coeffs_to_bytes(coeffs: &Vec<Scalar>) -> Vec<u8> {
-    coeffs
-        .iter()
-        .map(|c| c.to_bytes())
-        .flatten()
-        .collect::<Vec<_>>()
+// Absorb the poly as its coeffs, see `Fiat_Shamir::codec` for the encoding.
+pub(crate) fn poly_to_bytes(poly: &Polynomial) -> Vec<u8> {
+    encode_scalars(&poly.coeffs())
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::transcript::coeffs_to_bytes;
     use crate::transcript::default::Keccak256Transcript;
-    use bls12_381::Scalar;
-    use ff::Field;
-    use rand_core::OsRng;
+    use Fiat_Shamir::challenge_to_scalar;
+    use Fiat_Shamir::default::Keccak256Transcript as PinnedTranscript;
+    use Fiat_Shamir::test_vectors::{fixture_scalars, to_hex, SCALARS_HEX};
+    use Fiat_Shamir::Transcript as _;
 
     #[test]
-    fn test_coeff_to_transcript() {
-        let mut rng = OsRng;
-
-        let coeffs = (0..4).map(|_| Scalar::random(rng)).collect::<Vec<_>>();
-
-        // from scalar vector
-        let mut transcript_1 = Keccak256Transcript::default();
-        for x in coeffs.clone() {
-            transcript_1.append(&x.to_bytes());
-        }
-        let challenge_1 = transcript_1.challenge();
-
-        // from coeffs, as mock of poly
-        let mut transcript_2 = Keccak256Transcript::default();
-        let bytes = coeffs_to_bytes(&coeffs);
-        transcript_2.append(&bytes);
-        let challenge_2 = transcript_2.challenge();
-
-        assert_eq!(challenge_2, challenge_1);
+    fn test_poly_to_transcript() {
+        let poly = Polynomial::from_coeffs(fixture_scalars());
+        let bytes = poly_to_bytes(&poly);
+        assert_eq!(to_hex(&bytes), SCALARS_HEX);
+
+        // the challenge appends [1] before squeezing, and maps two hashes into a scalar.
+        let mut transcript = Keccak256Transcript::default();
+        transcript.append(&bytes);
+        let mut pinned = PinnedTranscript::default();
+        pinned.append(&bytes);
+        pinned.append(&[1]);
+        let (c_1, c_2) = (pinned.challenge(), pinned.challenge());
+        assert_eq!(transcript.challenge(), challenge_to_scalar(&c_1, &c_2));
     }
 }
@@ -1,35 +1,47 @@
 use crate::transcript::Transcript;
+use alloc::vec::Vec;
 use bls12_381::Scalar;
-use ff::PrimeField;
-use sha3::{Digest, Keccak256};
-use std::net::UdpSocket;
+use sumcheck::rlc::CoeffSource;
+use Fiat_Shamir::Transcript as _;
 
+// The squeeze and the map into a scalar are the ones of `Fiat_Shamir`, see `challenge_to_scalar`.
+#[derive(Clone, Default)]
 pub struct Keccak256Transcript {
-    hasher: Keccak256,
+    inner: Fiat_Shamir::default::Keccak256Transcript,
 }
 
 impl Transcript for Keccak256Transcript {
     fn append(&mut self, new_data: &[u8]) {
-        self.hasher.update(&mut new_data.to_owned());
+        self.inner.append(new_data);
     }
 
     // auto append and gen challenge
     fn challenge(&mut self) -> Scalar {
         self.append(&[1]);
+        self.inner.challenge_scalar()
+    }
+}
+
+// The coeffs of `sumcheck::rlc`: γ is squeezed as `challenge`, the vector by the inner transcript.
+impl CoeffSource for Keccak256Transcript {
+    fn squeeze_scalar(&mut self) -> Scalar {
+        self.challenge()
+    }
 
-        let mut result_hash = [0_u8; 32];
-        result_hash.copy_from_slice(&self.hasher.finalize_reset());
-        result_hash.reverse();
-        self.hasher.update(result_hash);
-        let sum = result_hash.to_vec().iter().map(|&b| b as u128).sum();
-        Scalar::from_u128(sum)
+    fn squeeze_vector(&mut self, label: &str, n: usize) -> Vec<Scalar> {
+        self.inner.challenge_vector(label, n)
     }
 }
 
-impl Default for Keccak256Transcript {
-    fn default() -> Self {
+// See `Fiat_Shamir::default::Keccak256Transcript::snapshot`, it's specific to this transcript.
+impl Keccak256Transcript {
+    pub fn snapshot(&self) -> Vec<u8> {
+        self.inner.snapshot()
+    }
+
+    pub fn restore(snapshot: &[u8]) -> Self {
         Self {
-            hasher: Keccak256::new(),
+            inner: Fiat_Shamir::default::Keccak256Transcript::restore(snapshot),
         }
     }
 }
@@ -0,0 +1,86 @@
+/*
+ * The sum-check and LDT verifiers of pazk-notes, see ffi/src/lib.rs.
+ *
+ * Link with -lpazk_ffi, the cdylib of `cargo build --release -p pazk_ffi`.
+ *
+ * Inputs
+ *
+ *   The proofs are the bytes the Rust prover writes:
+ *     - sum-check: `SumCheckProof::to_bytes`, eg: tests/fixtures/sumcheck.hex.
+ *     - LDT: `BatchLDTProof::to_bytes`, eg: tests/fixtures/ldt.hex.
+ *
+ *   The instances are the public side of a proof, in the encoding of ffi/src/instance.rs
+ *   (integers are little-endian, a scalar is 32 bytes in its canonical little-endian form):
+ *
+ *     | version | u8 = 1                                                           |
+ *     | context | u64 length, then the params, see thin_verifier/src/precomputed.rs |
+ *     | oracle  | u64 count, then the scalars                                      |
+ *
+ *   The oracle is the 2^var_num coeffs of the multilinear g of a sum-check, whose degree_bound
+ *   must be 1, or the merkle roots of the codewords of an LDT. A Rust service builds them with
+ *   `SumCheckInstance::to_bytes` and `LdtInstance::to_bytes`.
+ *
+ * Return codes
+ *
+ *   PAZK_ACCEPT             the proof is accepted for the instance.
+ *   PAZK_REJECT             the proof is well-formed and of the params of the instance, but
+ *                           a check fails, eg: a round of the sum-check, the final evaluation
+ *                           against g, or a merkle opening of the LDT.
+ *   PAZK_MALFORMED_PROOF    the proof can't be decoded, or is of other params than the ones
+ *                           of the instance, or the proof ptr is null with len > 0.
+ *   PAZK_MALFORMED_INSTANCE the instance can't be decoded, its params are invalid or tampered
+ *                           with, it's of the other protocol, its oracle is of the wrong size,
+ *                           or the instance ptr is null with len > 0. It's checked before the
+ *                           proof, so a malformed instance is reported whatever the proof is.
+ *   PAZK_INTERNAL_ERROR     a panic, which is caught and never crosses the boundary. It's a
+ *                           bug of the library rather than of the inputs.
+ *
+ *   Any code but PAZK_ACCEPT keeps its reason for pazk_last_error_message on the thread of the
+ *   call, until the next verification on that thread.
+ *
+ * Pointers
+ *
+ *   Each (ptr, len) is null with len 0, or valid for reads of len bytes during the call. The
+ *   library keeps no pointer after a call returns, and the calls are thread-safe.
+ */
+#ifndef PAZK_H
+#define PAZK_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define PAZK_ACCEPT 0
+#define PAZK_REJECT 1
+#define PAZK_MALFORMED_PROOF 2
+#define PAZK_MALFORMED_INSTANCE 3
+#define PAZK_INTERNAL_ERROR (-1)
+
+int32_t pazk_verify_sumcheck(const uint8_t *proof_ptr, size_t proof_len,
+                             const uint8_t *instance_ptr, size_t instance_len);
+
+int32_t pazk_verify_ldt(const uint8_t *proof_ptr, size_t proof_len,
+                        const uint8_t *instance_ptr, size_t instance_len);
+
+/*
+ * Copy the reason of the last verification on this thread into buf, truncated to len - 1 bytes
+ * and NUL-terminated, and return the length of the whole reason without the NUL, so a return
+ * >= len means it's truncated. It's 0 if the last verification accepted or there's none.
+ * buf is left as it is if it's NULL or len is 0, eg: to ask for the size first:
+ *
+ *     size_t n = pazk_last_error_message(NULL, 0);
+ *     char *reason = malloc(n + 1);
+ *     pazk_last_error_message(reason, n + 1);
+ *
+ * The reason is for humans, its text is not part of the contract.
+ */
+size_t pazk_last_error_message(char *buf, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* PAZK_H */
@@ -0,0 +1,226 @@
+// The instances a proof is verified against, in the encoding of `Fiat_Shamir::codec`:
+//
+//      | field     | encoding                                              |
+//      |-----------|-------------------------------------------------------|
+//      | version   | u8 = 1                                                |
+//      | context   | bytes, `Precomputed::to_bytes` of the params          |
+//      | oracle    | scalars, see below                                    |
+//
+//      | instance  | context   | oracle                                          |
+//      |-----------|-----------|-------------------------------------------------|
+//      | sum-check | sum-check | the 2^var_num coeffs of g, see `MPolynomial`    |
+//      | LDT       | LDT       | the roots of the committed codewords            |
+//
+// The context is decoded by the protocol in its header, see `Precomputed::from_bytes`, so the
+// caller holds no `ProtocolId`, and the proof is decoded against that protocol. g is multilinear,
+// so the degree_bound of a sum-check must be 1.
+use bls12_381::Scalar;
+use ni_sumcheck::poly::multivar_poly::MPolynomial;
+use std::fmt;
+use thin_verifier::precomputed::{Precomputed, PrecomputedError, PrecomputedKind};
+use Fiat_Shamir::codec::{encode_bytes, encode_scalars, CodecError, Decoder};
+
+pub const INSTANCE_VERSION: u8 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstanceError {
+    Decode(CodecError),
+    Precomputed(PrecomputedError),
+    // g has `found` coeffs, but the sum-check is over var_num variables.
+    OracleSize { var_num: u8, found: usize },
+    // the sum-check is of degree_bound > 1, which a multilinear g can't be.
+    NotMultilinear { degree_bound: u8 },
+}
+
+impl fmt::Display for InstanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InstanceError::Decode(_) => write!(f, "can't decode the instance"),
+            InstanceError::Precomputed(_) => write!(f, "bad context in the instance"),
+            InstanceError::OracleSize { var_num, found } => write!(
+                f,
+                "g has {} coeffs, but the sum-check is over {} variables",
+                found, var_num
+            ),
+            InstanceError::NotMultilinear { degree_bound } => write!(
+                f,
+                "the sum-check is of degree_bound {}, but g is multilinear",
+                degree_bound
+            ),
+        }
+    }
+}
+
+impl std::error::Error for InstanceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            InstanceError::Decode(e) => Some(e),
+            InstanceError::Precomputed(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<CodecError> for InstanceError {
+    fn from(e: CodecError) -> Self {
+        InstanceError::Decode(e)
+    }
+}
+
+impl From<PrecomputedError> for InstanceError {
+    fn from(e: PrecomputedError) -> Self {
+        InstanceError::Precomputed(e)
+    }
+}
+
+fn to_bytes(context: &Precomputed, oracle: &[Scalar]) -> Vec<u8> {
+    let mut bytes = vec![INSTANCE_VERSION];
+    bytes.extend(encode_bytes(&context.to_bytes()));
+    bytes.extend(encode_scalars(oracle));
+    bytes
+}
+
+// The context of the kind and the oracle.
+fn from_bytes(
+    bytes: &[u8],
+    kind: PrecomputedKind,
+) -> Result<(Precomputed, Vec<Scalar>), InstanceError> {
+    let mut decoder = Decoder::new(bytes);
+    decoder.read_version(INSTANCE_VERSION)?;
+    let context = Precomputed::from_bytes(&decoder.read_bytes()?)?;
+    let oracle = decoder.read_scalars()?;
+    decoder.finish()?;
+    if context.kind() != kind {
+        return Err(PrecomputedError::WrongKind {
+            expected: kind,
+            found: context.kind(),
+        }
+        .into());
+    }
+    Ok((context, oracle))
+}
+
+pub struct SumCheckInstance {
+    pub context: Precomputed,
+    pub g: MPolynomial,
+}
+
+impl SumCheckInstance {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(&self.context, &self.g.coeffs)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, InstanceError> {
+        let (context, coeffs) = from_bytes(bytes, PrecomputedKind::SumCheck)?;
+        let (var_num, degree_bound) = context.sumcheck_shape()?;
+        if degree_bound != 1 {
+            return Err(InstanceError::NotMultilinear { degree_bound });
+        }
+        if 1usize.checked_shl(var_num as u32) != Some(coeffs.len()) {
+            return Err(InstanceError::OracleSize {
+                var_num,
+                found: coeffs.len(),
+            });
+        }
+        let g = MPolynomial {
+            var_num: var_num as usize,
+            coeffs,
+        };
+        Ok(Self { context, g })
+    }
+}
+
+pub struct LdtInstance {
+    pub context: Precomputed,
+    pub roots: Vec<Scalar>,
+}
+
+impl LdtInstance {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        to_bytes(&self.context, &self.roots)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, InstanceError> {
+        let (context, roots) = from_bytes(bytes, PrecomputedKind::Ldt)?;
+        Ok(Self { context, roots })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ni_sumcheck::sumcheck::SumCheckParams;
+    use thin_verifier::{ldt_precomputed, ldt_root, sumcheck_oracle, sumcheck_precomputed};
+
+    fn sumcheck_instance() -> SumCheckInstance {
+        SumCheckInstance {
+            context: sumcheck_precomputed(),
+            g: sumcheck_oracle(),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let bytes = sumcheck_instance().to_bytes();
+        let instance = SumCheckInstance::from_bytes(&bytes).ok().unwrap();
+        assert_eq!(instance.g, sumcheck_oracle());
+        assert_eq!(instance.to_bytes(), bytes);
+
+        let ldt = LdtInstance {
+            context: ldt_precomputed(),
+            roots: vec![ldt_root()],
+        };
+        let instance = LdtInstance::from_bytes(&ldt.to_bytes()).ok().unwrap();
+        assert_eq!(instance.roots, vec![ldt_root()]);
+        assert_eq!(instance.to_bytes(), ldt.to_bytes());
+    }
+
+    #[test]
+    fn test_malformed() {
+        let err = |bytes: &[u8]| SumCheckInstance::from_bytes(bytes).err().unwrap();
+        // the 16 coeffs of g after the version and the context of 8 + 47 bytes.
+        let bytes = sumcheck_instance().to_bytes();
+        assert_eq!(
+            err(&bytes[..bytes.len() - 1]),
+            InstanceError::Decode(CodecError::LengthTooLarge {
+                offset: 56,
+                len: 16
+            })
+        );
+        assert_eq!(
+            err(&[2]),
+            InstanceError::Decode(CodecError::UnknownVersion(2))
+        );
+
+        // g over 3 variables for a sum-check over 4.
+        let mut instance = sumcheck_instance();
+        instance.g.coeffs.truncate(8);
+        assert_eq!(
+            err(&instance.to_bytes()),
+            InstanceError::OracleSize {
+                var_num: 4,
+                found: 8
+            }
+        );
+
+        let mut instance = sumcheck_instance();
+        instance.context = Precomputed::for_sumcheck(4, 2, SumCheckParams::default());
+        assert_eq!(
+            err(&instance.to_bytes()),
+            InstanceError::NotMultilinear { degree_bound: 2 }
+        );
+
+        // the instance of the LDT for a sum-check.
+        let ldt = LdtInstance {
+            context: ldt_precomputed(),
+            roots: vec![],
+        };
+        assert_eq!(
+            err(&ldt.to_bytes()),
+            InstanceError::Precomputed(PrecomputedError::WrongKind {
+                expected: PrecomputedKind::SumCheck,
+                found: PrecomputedKind::Ldt
+            })
+        );
+    }
+}
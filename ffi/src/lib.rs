@@ -0,0 +1,270 @@
+//! The sum-check and LDT verifiers of `thin_verifier` behind a small C ABI, for the services in
+//! other languages which receive the proofs of a Rust prover, see `include/pazk.h` for the
+//! contract:
+//!
+//! ```c
+//! int32_t pazk_verify_sumcheck(proof_ptr, proof_len, instance_ptr, instance_len);
+//! int32_t pazk_verify_ldt(proof_ptr, proof_len, instance_ptr, instance_len);
+//! size_t pazk_last_error_message(buf, len);
+//! ```
+//!
+//! The proofs are in their wire formats, and the instances in the encoding of `instance`. The
+//! return codes are the `PAZK_*` consts, see `Outcome::code`.
+//!
+//! No panic crosses the boundary: each entry point runs under `catch_unwind`, and a panic is
+//! `PAZK_INTERNAL_ERROR` with its message. The reason of anything but `PAZK_ACCEPT` is kept per
+//! thread for `pazk_last_error_message`, until the next call of a verifier on that thread.
+pub mod instance;
+
+use instance::{InstanceError, LdtInstance, SumCheckInstance};
+use std::cell::RefCell;
+use std::error::Error;
+use std::os::raw::c_char;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::slice;
+use thin_verifier::{verify_ldt_proof, verify_sumcheck_proof, ProtocolError};
+
+pub const PAZK_ACCEPT: i32 = 0;
+pub const PAZK_REJECT: i32 = 1;
+pub const PAZK_MALFORMED_PROOF: i32 = 2;
+pub const PAZK_MALFORMED_INSTANCE: i32 = 3;
+// a panic caught at the boundary, which is a bug of this library rather than of the inputs.
+pub const PAZK_INTERNAL_ERROR: i32 = -1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Accept,
+    Reject(String),
+    MalformedProof(String),
+    MalformedInstance(String),
+    Internal(String),
+}
+
+impl Outcome {
+    pub fn code(&self) -> i32 {
+        match self {
+            Outcome::Accept => PAZK_ACCEPT,
+            Outcome::Reject(_) => PAZK_REJECT,
+            Outcome::MalformedProof(_) => PAZK_MALFORMED_PROOF,
+            Outcome::MalformedInstance(_) => PAZK_MALFORMED_INSTANCE,
+            Outcome::Internal(_) => PAZK_INTERNAL_ERROR,
+        }
+    }
+
+    // The reason, None for Accept.
+    pub fn message(&self) -> Option<&str> {
+        match self {
+            Outcome::Accept => None,
+            Outcome::Reject(m)
+            | Outcome::MalformedProof(m)
+            | Outcome::MalformedInstance(m)
+            | Outcome::Internal(m) => Some(m),
+        }
+    }
+}
+
+// The error and its sources, as anyhow prints them. The errors of the protocol crates are
+// sources with the `error-sources` feature of thin_verifier only, so an error without any is
+// printed with its Debug form too.
+fn reason(e: &dyn Error) -> String {
+    let mut res = e.to_string();
+    let mut source = e.source();
+    if source.is_none() {
+        return format!("{}: {:?}", res, e);
+    }
+    while let Some(inner) = source {
+        res += &format!(": {}", inner);
+        source = inner.source();
+    }
+    res
+}
+
+// A proof which can't be decoded, or is of other params than the instance, is malformed. A context
+// of another kind is caught by the instance already.
+fn outcome(res: Result<(), ProtocolError>) -> Outcome {
+    match res {
+        Ok(()) => Outcome::Accept,
+        Err(e @ (ProtocolError::SumCheckDecode(_) | ProtocolError::Decode { .. })) => {
+            Outcome::MalformedProof(reason(&e))
+        }
+        Err(e @ ProtocolError::Precomputed(_)) => Outcome::MalformedInstance(reason(&e)),
+        Err(e) => Outcome::Reject(reason(&e)),
+    }
+}
+
+fn malformed_instance(e: InstanceError) -> Outcome {
+    Outcome::MalformedInstance(reason(&e))
+}
+
+// The instance is decoded first, so a malformed proof of a malformed instance is the latter.
+pub fn verify_sumcheck(proof: &[u8], instance: &[u8]) -> Outcome {
+    match SumCheckInstance::from_bytes(instance) {
+        Ok(instance) => outcome(verify_sumcheck_proof(&instance.context, proof, &instance.g)),
+        Err(e) => malformed_instance(e),
+    }
+}
+
+pub fn verify_ldt(proof: &[u8], instance: &[u8]) -> Outcome {
+    match LdtInstance::from_bytes(instance) {
+        Ok(instance) => outcome(verify_ldt_proof(&instance.context, proof, &instance.roots)),
+        Err(e) => malformed_instance(e),
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+// The bytes of (ptr, len), None for a null ptr of len > 0. A null ptr of len 0 is empty.
+unsafe fn bytes<'a>(ptr: *const u8, len: usize) -> Option<&'a [u8]> {
+    match (ptr.is_null(), len) {
+        (true, 0) => Some(&[]),
+        (true, _) => None,
+        _ => Some(slice::from_raw_parts(ptr, len)),
+    }
+}
+
+// Run the verifier on the inputs, keep the reason of the outcome and return its code.
+unsafe fn entry(
+    (proof_ptr, proof_len): (*const u8, usize),
+    (instance_ptr, instance_len): (*const u8, usize),
+    verify: fn(&[u8], &[u8]) -> Outcome,
+) -> i32 {
+    let res = catch_unwind(AssertUnwindSafe(|| {
+        match (
+            bytes(proof_ptr, proof_len),
+            bytes(instance_ptr, instance_len),
+        ) {
+            (_, None) => Outcome::MalformedInstance("the instance is null".to_string()),
+            (None, _) => Outcome::MalformedProof("the proof is null".to_string()),
+            (Some(proof), Some(instance)) => verify(proof, instance),
+        }
+    }));
+    let outcome = res.unwrap_or_else(|payload| {
+        let message = match (
+            payload.downcast_ref::<&str>(),
+            payload.downcast_ref::<String>(),
+        ) {
+            (Some(m), _) => m.to_string(),
+            (_, Some(m)) => m.clone(),
+            _ => "unknown".to_string(),
+        };
+        Outcome::Internal(format!("panic: {}", message))
+    });
+    let code = outcome.code();
+    LAST_ERROR.with(|last| *last.borrow_mut() = outcome.message().map(str::to_string));
+    code
+}
+
+/// Verify a sum-check proof in its wire format against a sum-check instance, see
+/// `include/pazk.h` for the return codes.
+///
+/// # Safety
+///
+/// Each ptr is null with len 0, or valid for reads of len bytes during the call.
+#[no_mangle]
+pub unsafe extern "C" fn pazk_verify_sumcheck(
+    proof_ptr: *const u8,
+    proof_len: usize,
+    instance_ptr: *const u8,
+    instance_len: usize,
+) -> i32 {
+    entry(
+        (proof_ptr, proof_len),
+        (instance_ptr, instance_len),
+        verify_sumcheck,
+    )
+}
+
+/// Verify a batched LDT proof in its wire format against an LDT instance, see `include/pazk.h`
+/// for the return codes.
+///
+/// # Safety
+///
+/// Each ptr is null with len 0, or valid for reads of len bytes during the call.
+#[no_mangle]
+pub unsafe extern "C" fn pazk_verify_ldt(
+    proof_ptr: *const u8,
+    proof_len: usize,
+    instance_ptr: *const u8,
+    instance_len: usize,
+) -> i32 {
+    entry(
+        (proof_ptr, proof_len),
+        (instance_ptr, instance_len),
+        verify_ldt,
+    )
+}
+
+/// Copy the reason of the last verification on this thread into buf, truncated to len - 1 bytes
+/// and NUL-terminated, and return the length of the whole reason without the NUL. It's 0 if the
+/// last one accepted or there's none, and buf is left as it is if it's null or len is 0, eg: to
+/// ask for the size first.
+///
+/// # Safety
+///
+/// buf is null, or valid for writes of len bytes.
+#[no_mangle]
+pub unsafe extern "C" fn pazk_last_error_message(buf: *mut c_char, len: usize) -> usize {
+    let res = catch_unwind(|| {
+        LAST_ERROR.with(|last| {
+            let last = last.borrow();
+            let message = last.as_deref().unwrap_or("").as_bytes();
+            if !buf.is_null() && len > 0 {
+                let n = message.len().min(len - 1);
+                let buf = slice::from_raw_parts_mut(buf as *mut u8, n + 1);
+                buf[..n].copy_from_slice(&message[..n]);
+                buf[n] = 0;
+            }
+            message.len()
+        })
+    });
+    res.unwrap_or(0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use instance::SumCheckInstance;
+    use thin_verifier::{sumcheck_oracle, sumcheck_precomputed, SUMCHECK_HEX};
+    use Fiat_Shamir::fixtures::from_hex_lines;
+
+    fn instance() -> Vec<u8> {
+        SumCheckInstance {
+            context: sumcheck_precomputed(),
+            g: sumcheck_oracle(),
+        }
+        .to_bytes()
+    }
+
+    // The claimed sum, the degree_bound in the header, and a g off by one in a coeff.
+    #[test]
+    fn test_outcomes() {
+        let proof = from_hex_lines(SUMCHECK_HEX).unwrap();
+        assert_eq!(verify_sumcheck(&proof, &instance()), Outcome::Accept);
+
+        let mut corrupted = proof.clone();
+        corrupted[4] ^= 1;
+        assert_eq!(verify_sumcheck(&corrupted, &instance()).code(), PAZK_REJECT);
+        let mut other = proof.clone();
+        other[2] ^= 1;
+        assert_eq!(
+            verify_sumcheck(&other, &instance()).code(),
+            PAZK_MALFORMED_PROOF
+        );
+
+        let mut g = sumcheck_oracle();
+        g.coeffs[0] += bls12_381::Scalar::one();
+        let other_g = SumCheckInstance {
+            context: sumcheck_precomputed(),
+            g,
+        };
+        let outcome = verify_sumcheck(&proof, &other_g.to_bytes());
+        assert_eq!(outcome.code(), PAZK_REJECT);
+        assert_eq!(
+            outcome.message(),
+            Some("final_eval is not g(r_1, ..., r_v): SumCheckOracle")
+        );
+        assert_eq!(verify_sumcheck(&proof, &[]).code(), PAZK_MALFORMED_INSTANCE);
+    }
+}
@@ -0,0 +1,176 @@
+// The cdylib loaded by its C symbols, as a Go or Python caller does, on the golden fixtures and
+// on the inputs of each return code of `include/pazk.h`.
+//
+// cargo builds the cdylib next to this test, eg: target/debug/deps/libpazk_ffi.so.
+use bls12_381::Scalar;
+use libloading::{library_filename, Library, Symbol};
+use pazk_ffi::instance::{LdtInstance, SumCheckInstance};
+use pazk_ffi::{PAZK_ACCEPT, PAZK_MALFORMED_INSTANCE, PAZK_MALFORMED_PROOF, PAZK_REJECT};
+use std::os::raw::c_char;
+use std::ptr;
+use thin_verifier::{ldt_precomputed, ldt_root, sumcheck_oracle, sumcheck_precomputed};
+use thin_verifier::{LDT_HEX, SUMCHECK_HEX};
+use Fiat_Shamir::fixtures::from_hex_lines;
+
+type Verify = unsafe extern "C" fn(*const u8, usize, *const u8, usize) -> i32;
+type LastError = unsafe extern "C" fn(*mut c_char, usize) -> usize;
+
+fn load() -> Library {
+    let exe = std::env::current_exe().unwrap();
+    let dir = exe.parent().unwrap();
+    let path = dir.join(library_filename("pazk_ffi"));
+    unsafe { Library::new(&path) }.unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+struct Ffi {
+    lib: Library,
+}
+
+impl Ffi {
+    fn verify(&self, name: &[u8], proof: &[u8], instance: &[u8]) -> i32 {
+        unsafe {
+            let verify: Symbol<Verify> = self.lib.get(name).unwrap();
+            verify(
+                proof.as_ptr(),
+                proof.len(),
+                instance.as_ptr(),
+                instance.len(),
+            )
+        }
+    }
+
+    fn sumcheck(&self, proof: &[u8], instance: &[u8]) -> i32 {
+        self.verify(b"pazk_verify_sumcheck", proof, instance)
+    }
+
+    fn ldt(&self, proof: &[u8], instance: &[u8]) -> i32 {
+        self.verify(b"pazk_verify_ldt", proof, instance)
+    }
+
+    // The reason of the last call, read as a C caller does: the size first.
+    fn last_error(&self) -> String {
+        unsafe {
+            let last_error: Symbol<LastError> = self.lib.get(b"pazk_last_error_message").unwrap();
+            let n = last_error(ptr::null_mut(), 0);
+            let mut buf = vec![0u8; n + 1];
+            assert_eq!(last_error(buf.as_mut_ptr() as *mut c_char, buf.len()), n);
+            assert_eq!(buf[n], 0);
+            buf.truncate(n);
+            String::from_utf8(buf).unwrap()
+        }
+    }
+}
+
+fn sumcheck_instance() -> Vec<u8> {
+    SumCheckInstance {
+        context: sumcheck_precomputed(),
+        g: sumcheck_oracle(),
+    }
+    .to_bytes()
+}
+
+fn ldt_instance(roots: Vec<Scalar>) -> Vec<u8> {
+    LdtInstance {
+        context: ldt_precomputed(),
+        roots,
+    }
+    .to_bytes()
+}
+
+// Flip the lowest bit of a byte.
+fn corrupt(bytes: &[u8], offset: usize) -> Vec<u8> {
+    let mut bytes = bytes.to_vec();
+    bytes[offset] ^= 1;
+    bytes
+}
+
+#[test]
+fn test_fixtures() {
+    let ffi = Ffi { lib: load() };
+    let proof = from_hex_lines(SUMCHECK_HEX).unwrap();
+    assert_eq!(ffi.sumcheck(&proof, &sumcheck_instance()), PAZK_ACCEPT);
+    assert_eq!(ffi.last_error(), "");
+
+    let proof = from_hex_lines(LDT_HEX).unwrap();
+    assert_eq!(
+        ffi.ldt(&proof, &ldt_instance(vec![ldt_root()])),
+        PAZK_ACCEPT
+    );
+    assert_eq!(ffi.last_error(), "");
+}
+
+// The offsets are the ones of thin_verifier: the claimed sum and the degree_bound of the
+// sum-check, the first opened value and the protocol digest of the LDT.
+#[test]
+fn test_return_codes() {
+    let ffi = Ffi { lib: load() };
+    let proof = from_hex_lines(SUMCHECK_HEX).unwrap();
+    let instance = sumcheck_instance();
+    assert_eq!(ffi.sumcheck(&corrupt(&proof, 4), &instance), PAZK_REJECT);
+    assert!(ffi.last_error().starts_with("sum-check rejected"));
+    assert_eq!(
+        ffi.sumcheck(&corrupt(&proof, 2), &instance),
+        PAZK_MALFORMED_PROOF
+    );
+    assert!(ffi
+        .last_error()
+        .starts_with("can't decode the sum-check proof"));
+    assert_eq!(ffi.sumcheck(&proof[..10], &instance), PAZK_MALFORMED_PROOF);
+    assert_eq!(
+        ffi.sumcheck(&proof, &instance[1..]),
+        PAZK_MALFORMED_INSTANCE
+    );
+    assert!(ffi.last_error().starts_with("can't decode the instance"));
+    // the LDT instance for a sum-check proof.
+    assert_eq!(
+        ffi.sumcheck(&proof, &ldt_instance(vec![ldt_root()])),
+        PAZK_MALFORMED_INSTANCE
+    );
+
+    let proof = from_hex_lines(LDT_HEX).unwrap();
+    let instance = ldt_instance(vec![ldt_root()]);
+    assert_eq!(ffi.ldt(&corrupt(&proof, 41), &instance), PAZK_REJECT);
+    assert!(ffi.last_error().starts_with("LDT rejected"));
+    let other_root = ldt_instance(vec![ldt_root() + Scalar::one()]);
+    assert_eq!(ffi.ldt(&proof, &other_root), PAZK_REJECT);
+    assert_eq!(
+        ffi.ldt(&corrupt(&proof, 1), &instance),
+        PAZK_MALFORMED_PROOF
+    );
+    assert!(ffi.last_error().starts_with("can't decode the ldt proof"));
+    assert_eq!(
+        ffi.ldt(&proof, &sumcheck_instance()),
+        PAZK_MALFORMED_INSTANCE
+    );
+    // the params after the header of the context.
+    let tampered = corrupt(&instance, instance.len() - 8 - 32 - 9 - 1);
+    assert_eq!(ffi.ldt(&proof, &tampered), PAZK_MALFORMED_INSTANCE);
+
+    // accept clears the reason.
+    assert_eq!(ffi.ldt(&proof, &instance), PAZK_ACCEPT);
+    assert_eq!(ffi.last_error(), "");
+}
+
+#[test]
+fn test_null_and_truncated() {
+    let ffi = Ffi { lib: load() };
+    let instance = sumcheck_instance();
+    unsafe {
+        let verify: Symbol<Verify> = ffi.lib.get(b"pazk_verify_sumcheck").unwrap();
+        let code = verify(ptr::null(), 8, instance.as_ptr(), instance.len());
+        assert_eq!(code, PAZK_MALFORMED_PROOF);
+        assert_eq!(ffi.last_error(), "the proof is null");
+        let code = verify(ptr::null(), 0, ptr::null(), 8);
+        assert_eq!(code, PAZK_MALFORMED_INSTANCE);
+        // an empty proof is a malformed one, not a null one.
+        let code = verify(ptr::null(), 0, instance.as_ptr(), instance.len());
+        assert_eq!(code, PAZK_MALFORMED_PROOF);
+
+        // the reason truncated to the buf, NUL-terminated.
+        let last_error: Symbol<LastError> = ffi.lib.get(b"pazk_last_error_message").unwrap();
+        let mut buf = [0x7fu8; 8];
+        let n = last_error(buf.as_mut_ptr() as *mut c_char, buf.len());
+        assert!(n > buf.len());
+        assert_eq!(&buf, b"can't d\0");
+    }
+}
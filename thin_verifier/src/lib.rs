@@ -0,0 +1,439 @@
+//! The verifiers of sum-check, GKR, the batched LDT and the merkle openings on the golden proofs
+//! of `tests/fixtures`, which are embedded. Each protocol crate is built with its `verifier`
+//! feature only, so this is what a constrained verifier links, see `Cargo.toml`.
+//!
+//! The instances are the ones of the fixture tests in the protocol crates, see
+//! `tests/fixtures/README.md`. The commitments V holds are pinned here, rather than recomputed,
+//! as committing is the prover's side. So are the protocols, the proofs are decoded against the
+//! `ProtocolId` of each instance, so a proof of other params is rejected at its header.
+//!
+//! A service verifying many proofs of the same params builds their `Precomputed` once, and calls
+//! the `_with` variants, see `precomputed`.
+//!
+//! Each verifier returns a `ProtocolError`, which keeps the typed error of the protocol crate as
+//! its `source`, see the `error-sources` feature.
+pub mod precomputed;
+
+use bls12_381::Scalar;
+use ff::PrimeField;
+use low_degree_test::domain::EvaluationDomain;
+use low_degree_test::ldt::batch::{openings_from_bytes, BatchLDT, BatchLDTProof, LDTError};
+use low_degree_test::merkle_tree::MerkleError;
+use ni_sumcheck::poly::multivar_poly::MPolynomial;
+use ni_sumcheck::sumcheck::{verify_with_params, SumCheckParams, SumCheckProof};
+use ni_sumcheck::sumcheck::{DecodeError, VerifyError};
+use precomputed::{Precomputed, PrecomputedError};
+use std::fmt;
+use Fiat_Shamir::codec::CodecError;
+use Fiat_Shamir::fixtures::from_hex_lines;
+use GKR::arithmetic::layered_circuit::Ops::MUL;
+use GKR::arithmetic::layered_circuit::{CircuitConfig, Layer};
+use GKR::gkr::{Diagnosis, GkrProof, GkrVerifierState};
+
+pub const SUMCHECK_HEX: &str = include_str!("../../tests/fixtures/sumcheck.hex");
+pub const GKR_HEX: &str = include_str!("../../tests/fixtures/gkr.hex");
+pub const LDT_HEX: &str = include_str!("../../tests/fixtures/ldt.hex");
+pub const MERKLE_BATCH_HEX: &str = include_str!("../../tests/fixtures/merkle_batch.hex");
+
+// The merkle root of the codeword of 1 + 2X + ... + 8X^7 on the coset of size 64.
+const LDT_ROOT: &str = "2cf17df9de980cb26d42047d1baf5651b4bf769dcbd0b4fa7c7c49260bb7700a";
+// The merkle root of the values 1, ..., 16.
+const MERKLE_BATCH_ROOT: &str = "9b881e790d1e0025f4845e2342162e38fc20bf8d411d0ccd3adcd5bed182ee36";
+
+// Why a fixture is rejected, by the protocol it's of.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProtocolError {
+    // the fixture is not in hex lines, see `from_hex_lines`.
+    Fixture(String),
+    SumCheckDecode(DecodeError),
+    SumCheck(VerifyError),
+    // the final_eval is not g(r_1, ..., r_v) by the oracle of V.
+    SumCheckOracle,
+    // the proof of the protocol can't be decoded, eg: "gkr".
+    Decode {
+        protocol: &'static str,
+        source: CodecError,
+    },
+    // any other diagnosis than `LooksConsistent`.
+    Gkr(Diagnosis),
+    Ldt(LDTError),
+    OpeningCount {
+        expected: usize,
+        found: usize,
+    },
+    // the opening at the index is of another leaf or root.
+    MerkleOpening {
+        index: usize,
+        source: MerkleError,
+    },
+    // the opening at the index is accepted, but of another value.
+    OpenedValue {
+        index: usize,
+    },
+    // the context is of another protocol than the verifier.
+    Precomputed(PrecomputedError),
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtocolError::Fixture(e) => write!(f, "bad fixture: {}", e),
+            ProtocolError::SumCheckDecode(_) => write!(f, "can't decode the sum-check proof"),
+            ProtocolError::SumCheck(_) => write!(f, "sum-check rejected"),
+            ProtocolError::SumCheckOracle => write!(f, "final_eval is not g(r_1, ..., r_v)"),
+            ProtocolError::Decode { protocol, .. } => {
+                write!(f, "can't decode the {} proof", protocol)
+            }
+            ProtocolError::Gkr(Diagnosis::ProofInvalid { layer, .. }) => {
+                write!(f, "GKR rejected at layer {}", layer)
+            }
+            ProtocolError::Gkr(_) => write!(f, "GKR rejected"),
+            ProtocolError::Ldt(_) => write!(f, "LDT rejected"),
+            ProtocolError::OpeningCount { expected, found } => {
+                write!(f, "{} openings, expected {}", found, expected)
+            }
+            ProtocolError::MerkleOpening { index, .. } => {
+                write!(f, "rejected the opening at {}", index)
+            }
+            ProtocolError::OpenedValue { index } => {
+                write!(f, "the opening at {} is of another value", index)
+            }
+            ProtocolError::Precomputed(_) => write!(f, "bad precomputed context"),
+        }
+    }
+}
+
+// The errors of the sum-check, the LDT and the merkle tree are sources with `error-sources`
+// only, as they implement `Error` with the `std` feature of their crates.
+impl std::error::Error for ProtocolError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProtocolError::Decode { source, .. } => Some(source),
+            ProtocolError::Gkr(diagnosis) => Some(diagnosis),
+            ProtocolError::Precomputed(e) => Some(e),
+            #[cfg(feature = "error-sources")]
+            ProtocolError::SumCheckDecode(e) => Some(e),
+            #[cfg(feature = "error-sources")]
+            ProtocolError::SumCheck(e) => Some(e),
+            #[cfg(feature = "error-sources")]
+            ProtocolError::Ldt(e) => Some(e),
+            #[cfg(feature = "error-sources")]
+            ProtocolError::MerkleOpening { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<DecodeError> for ProtocolError {
+    fn from(e: DecodeError) -> Self {
+        ProtocolError::SumCheckDecode(e)
+    }
+}
+
+impl From<VerifyError> for ProtocolError {
+    fn from(e: VerifyError) -> Self {
+        ProtocolError::SumCheck(e)
+    }
+}
+
+impl From<Diagnosis> for ProtocolError {
+    fn from(diagnosis: Diagnosis) -> Self {
+        ProtocolError::Gkr(diagnosis)
+    }
+}
+
+impl From<PrecomputedError> for ProtocolError {
+    fn from(e: PrecomputedError) -> Self {
+        ProtocolError::Precomputed(e)
+    }
+}
+
+impl From<LDTError> for ProtocolError {
+    fn from(e: LDTError) -> Self {
+        ProtocolError::Ldt(e)
+    }
+}
+
+// The protocol of the proof which can't be decoded.
+fn decode_error(protocol: &'static str) -> impl Fn(CodecError) -> ProtocolError {
+    move |source| ProtocolError::Decode { protocol, source }
+}
+
+fn read_fixture(hex: &str) -> Result<Vec<u8>, ProtocolError> {
+    from_hex_lines(hex).map_err(ProtocolError::Fixture)
+}
+
+fn to_scalar(hex: &str) -> Scalar {
+    let bytes: [u8; 32] = from_hex_lines(hex).unwrap().try_into().unwrap();
+    Scalar::from_bytes(&bytes).unwrap()
+}
+
+// g(x1, ..., x4) with the coeffs 1, ..., 16, which V has the oracle access to.
+pub fn verify_sumcheck(hex: &str) -> Result<(), ProtocolError> {
+    verify_sumcheck_with(&sumcheck_precomputed(), hex)
+}
+
+pub fn sumcheck_precomputed() -> Precomputed {
+    Precomputed::for_sumcheck(4, 1, SumCheckParams::default())
+}
+
+pub fn verify_sumcheck_with(precomputed: &Precomputed, hex: &str) -> Result<(), ProtocolError> {
+    let bytes = read_fixture(hex)?;
+    verify_sumcheck_proof(precomputed, &bytes, &sumcheck_oracle())
+}
+
+// g of the fixture.
+pub fn sumcheck_oracle() -> MPolynomial {
+    MPolynomial {
+        var_num: 4,
+        coeffs: (1..=16u64).map(Scalar::from).collect(),
+    }
+}
+
+// The proof in the wire format on the sum of g, which V has the oracle access to.
+pub fn verify_sumcheck_proof(
+    precomputed: &Precomputed,
+    bytes: &[u8],
+    g: &MPolynomial,
+) -> Result<(), ProtocolError> {
+    let params = precomputed.sumcheck_params()?;
+    let proof = SumCheckProof::from_bytes_for(bytes, precomputed.protocol())?;
+    let claim = verify_with_params(&proof, params)?;
+    if g.evaluate(&claim.point) != claim.value {
+        return Err(ProtocolError::SumCheckOracle);
+    }
+    Ok(())
+}
+
+// The circuit of Figure 4.12, which is known by V.
+fn gkr_circuit() -> CircuitConfig {
+    let layer_1 = Layer {
+        gates: vec![MUL(0, 0), MUL(1, 1), MUL(1, 2), MUL(3, 3)],
+        var_num: 2,
+    };
+    let output_layer = Layer {
+        gates: vec![MUL(0, 1), MUL(2, 3)],
+        var_num: 1,
+    };
+    CircuitConfig {
+        layers: vec![output_layer, layer_1],
+        input_var_num: 2,
+        depth: 3,
+    }
+}
+
+// The inputs (1, 2, 1, 4) and the claimed outputs (4, 32), checked one layer at a time.
+pub fn verify_gkr(hex: &str) -> Result<(), ProtocolError> {
+    verify_gkr_with(&gkr_precomputed(), hex)
+}
+
+pub fn gkr_precomputed() -> Precomputed {
+    Precomputed::for_gkr(gkr_circuit())
+}
+
+pub fn verify_gkr_with(precomputed: &Precomputed, hex: &str) -> Result<(), ProtocolError> {
+    let circuit = precomputed.circuit()?;
+    let bytes = read_fixture(hex)?;
+    let proof =
+        GkrProof::from_bytes_for(&bytes, precomputed.protocol()).map_err(decode_error("gkr"))?;
+    let inputs = [1, 2, 1, 4].map(Scalar::from_u128);
+    let outputs = [4, 32].map(Scalar::from_u128);
+
+    let mut state = GkrVerifierState::new(circuit, &outputs, &proof)?;
+    for layer_proof in proof.layers.iter() {
+        state.verify_layer(layer_proof)?;
+    }
+    match state.finish(&inputs) {
+        Diagnosis::LooksConsistent => Ok(()),
+        diagnosis => Err(diagnosis.into()),
+    }
+}
+
+// The codeword is tested with deg < 8 and 5 queries.
+pub fn verify_ldt(hex: &str) -> Result<(), ProtocolError> {
+    verify_ldt_with(&ldt_precomputed(), hex)
+}
+
+pub fn ldt_precomputed() -> Precomputed {
+    let domain = EvaluationDomain::coset(64, Scalar::MULTIPLICATIVE_GENERATOR);
+    Precomputed::for_ldt(BatchLDT::new(domain, 8, 5))
+}
+
+pub fn verify_ldt_with(precomputed: &Precomputed, hex: &str) -> Result<(), ProtocolError> {
+    let bytes = read_fixture(hex)?;
+    verify_ldt_proof(precomputed, &bytes, &[ldt_root()])
+}
+
+// The root of the codeword of the fixture, which V holds.
+pub fn ldt_root() -> Scalar {
+    to_scalar(LDT_ROOT)
+}
+
+// The proof in the wire format on the codewords committed to the roots.
+pub fn verify_ldt_proof(
+    precomputed: &Precomputed,
+    bytes: &[u8],
+    roots: &[Scalar],
+) -> Result<(), ProtocolError> {
+    let ldt = precomputed.ldt()?;
+    let proof = BatchLDTProof::from_bytes_for(bytes, precomputed.protocol())
+        .map_err(decode_error("ldt"))?;
+    ldt.verify_with_sink(roots, &proof, &mut ())?;
+    Ok(())
+}
+
+// The values 1, ..., 16 opened at 1, 5, 9 and 13.
+pub fn verify_merkle_batch(hex: &str) -> Result<(), ProtocolError> {
+    let bytes = read_fixture(hex)?;
+    let openings = openings_from_bytes(&bytes).map_err(decode_error("merkle_batch"))?;
+    if openings.len() != 4 {
+        return Err(ProtocolError::OpeningCount {
+            expected: 4,
+            found: openings.len(),
+        });
+    }
+    let root = to_scalar(MERKLE_BATCH_ROOT);
+    for (opening, index) in openings.iter().zip([1, 5, 9, 13]) {
+        if opening.index != index {
+            return Err(ProtocolError::MerkleOpening {
+                index,
+                source: MerkleError::IndexMismatch {
+                    expected: index,
+                    found: opening.index,
+                },
+            });
+        }
+        opening
+            .try_check(&root)
+            .map_err(|source| ProtocolError::MerkleOpening { index, source })?;
+        if opening.value != Scalar::from(index as u64 + 1) {
+            return Err(ProtocolError::OpenedValue { index });
+        }
+    }
+    Ok(())
+}
+
+// The result of each fixture by name.
+pub fn verify_all() -> Vec<(&'static str, Result<(), ProtocolError>)> {
+    vec![
+        ("sumcheck", verify_sumcheck(SUMCHECK_HEX)),
+        ("gkr", verify_gkr(GKR_HEX)),
+        ("ldt", verify_ldt(LDT_HEX)),
+        ("merkle_batch", verify_merkle_batch(MERKLE_BATCH_HEX)),
+    ]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use Fiat_Shamir::test_vectors::to_hex;
+
+    #[test]
+    fn test_verify_all() {
+        for (name, res) in verify_all() {
+            assert_eq!(res, Ok(()), "fixture {}", name);
+        }
+    }
+
+    // Flip the lowest bit of a byte, eg: of a scalar in little-endian.
+    fn corrupt(hex: &str, offset: usize) -> String {
+        let mut bytes = from_hex_lines(hex).unwrap();
+        bytes[offset] ^= 1;
+        to_hex(&bytes)
+    }
+
+    // The claimed sum, a_0 of g_1 in layer 0, the root and the first opened value, see the
+    // layouts of the proofs.
+    #[test]
+    fn test_reject_corrupted() {
+        assert!(verify_sumcheck(&corrupt(SUMCHECK_HEX, 4)).is_err());
+        assert!(verify_gkr(&corrupt(GKR_HEX, 161)).is_err());
+        assert!(verify_ldt(&corrupt(LDT_HEX, 41)).is_err());
+        assert!(verify_merkle_batch(&corrupt(MERKLE_BATCH_HEX, 17)).is_err());
+    }
+
+    // The degree_bound of sum-check and the protocol digest of GKR and LDT.
+    #[test]
+    fn test_reject_other_protocol() {
+        assert_eq!(
+            verify_sumcheck(&corrupt(SUMCHECK_HEX, 2)),
+            Err(ProtocolError::SumCheckDecode(DecodeError::ProtocolMismatch))
+        );
+        for (protocol, res) in [
+            ("gkr", verify_gkr(&corrupt(GKR_HEX, 1))),
+            ("ldt", verify_ldt(&corrupt(LDT_HEX, 1))),
+        ] {
+            assert_eq!(
+                res,
+                Err(ProtocolError::Decode {
+                    protocol,
+                    source: CodecError::ProtocolMismatch
+                })
+            );
+        }
+    }
+
+    // The error and its sources, as anyhow prints them.
+    fn chain(e: &dyn std::error::Error) -> String {
+        let mut res = e.to_string();
+        let mut source = e.source();
+        while let Some(inner) = source {
+            res += &format!(": {}", inner);
+            source = inner.source();
+        }
+        res
+    }
+
+    #[test]
+    fn test_typed_errors() {
+        // the first opened value, which is checked by its merkle path first.
+        let err = verify_merkle_batch(&corrupt(MERKLE_BATCH_HEX, 17)).unwrap_err();
+        match err {
+            ProtocolError::MerkleOpening { index, source } => {
+                assert_eq!(index, 1);
+                assert_eq!(source, MerkleError::PathMismatch { index: 1 });
+            }
+            e => panic!("unexpected {:?}", e),
+        }
+
+        // a_0 of g_1 in layer 0
+        let err = verify_gkr(&corrupt(GKR_HEX, 161)).unwrap_err();
+        assert!(matches!(
+            err,
+            ProtocolError::Gkr(Diagnosis::ProofInvalid {
+                layer: 0,
+                round: 1,
+                ..
+            })
+        ));
+        assert_eq!(err.to_string(), "GKR rejected at layer 0");
+        let source = std::error::Error::source(&err).unwrap();
+        assert!(source.downcast_ref::<Diagnosis>().is_some());
+        assert!(source.to_string().starts_with("layer 0, round 1: "));
+
+        assert!(matches!(
+            verify_sumcheck(&corrupt(SUMCHECK_HEX, 4)),
+            Err(ProtocolError::SumCheck(_))
+        ));
+    }
+
+    #[test]
+    fn test_error_chains() {
+        assert_eq!(
+            chain(&verify_gkr(&corrupt(GKR_HEX, 1)).unwrap_err()),
+            "can't decode the gkr proof: The bytes are of another protocol"
+        );
+        assert_eq!(
+            chain(&verify_ldt(&corrupt(LDT_HEX, 1)).unwrap_err()),
+            "can't decode the ldt proof: The bytes are of another protocol"
+        );
+        let err = verify_merkle_batch(&corrupt(MERKLE_BATCH_HEX, 17)).unwrap_err();
+        #[cfg(feature = "error-sources")]
+        assert_eq!(
+            chain(&err),
+            "rejected the opening at 1: The path of leaf 1 doesn't hash to the root"
+        );
+        #[cfg(not(feature = "error-sources"))]
+        assert_eq!(chain(&err), "rejected the opening at 1");
+    }
+}
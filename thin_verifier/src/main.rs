@@ -0,0 +1,28 @@
+// Verify the embedded golden proofs with the verifiers alone, eg:
+//      cargo run -p thin_verifier
+use std::error::Error;
+use std::process::exit;
+use thin_verifier::verify_all;
+
+fn main() {
+    let mut rejected = 0;
+    for (name, res) in verify_all() {
+        match res {
+            Ok(()) => println!("{}: ok", name),
+            Err(e) => {
+                // the error, then its sources, eg: the merkle error of an opening.
+                print!("{}: {}", name, e);
+                let mut source = e.source();
+                while let Some(inner) = source {
+                    print!(": {}", inner);
+                    source = inner.source();
+                }
+                println!();
+                rejected += 1;
+            }
+        }
+    }
+    if rejected > 0 {
+        exit(1);
+    }
+}
@@ -0,0 +1,460 @@
+// What a verifier service builds once per params and reuses for every proof of them: the
+// `ProtocolId` the proofs are decoded against, and the params objects of the verifier, eg: the
+// circuit of GKR, which is hashed into the id, or the `BatchLDT` over its domain.
+//
+// The constructors take the params only, so nothing of a witness or a proof can be in it. It's
+// saved in the encoding of `Fiat_Shamir::codec`:
+//
+//      | field     | encoding                                              |
+//      |-----------|-------------------------------------------------------|
+//      | version   | u8 = 1                                                |
+//      | protocol  | digest, `ProtocolId::digest`                          |
+//      | kind      | u8, 0: sum-check, 1: LDT, 2: GKR                      |
+//      | params    | bytes, see below                                      |
+//
+//      | kind      | params                                                          |
+//      |-----------|-----------------------------------------------------------------|
+//      | sum-check | var_num: u8, degree_bound: u8, var_order: u8, binding: u8,      |
+//      |           | compressed: u8                                                  |
+//      | LDT       | size: u64, offset: scalar, degree_bound: u64, num_queries: u64, |
+//      |           | folding_factor: u64, [u8 = 1 of the independent coeffs]         |
+//      | GKR       | `CircuitConfig::to_bytes`                                       |
+//
+// `load_for` rejects the file of another protocol by its header, and rebuilds the context from
+// the params, so params edited under the same header are rejected as their id is another one.
+use bls12_381::Scalar;
+use ff::PrimeField;
+use low_degree_test::domain::EvaluationDomain;
+use low_degree_test::ldt::batch::{BatchLDT, CoeffMode, LdtParams};
+use ni_sumcheck::sumcheck::{protocol_id, Binding, SumCheckParams, VarOrder};
+use std::fmt;
+use std::path::Path;
+use Fiat_Shamir::codec::{encode_bytes, encode_digest, encode_scalar, encode_u64};
+use Fiat_Shamir::codec::{CodecError, Decoder};
+use Fiat_Shamir::protocol::ProtocolId;
+use GKR::arithmetic::layered_circuit::CircuitConfig;
+use GKR::gkr::protocol_id as gkr_protocol_id;
+
+pub const PRECOMPUTED_VERSION: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrecomputedKind {
+    SumCheck,
+    Ldt,
+    Gkr,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrecomputedError {
+    Io(std::io::ErrorKind),
+    // the file can't be decoded, or its header is of another protocol.
+    Decode(CodecError),
+    // the params of the file are not the ones of its header.
+    Tampered,
+    // the params can't be the ones of a verifier, eg: a domain not larger than the degree bound.
+    InvalidParams,
+    // a context of another protocol, eg: the one of the LDT for a GKR proof.
+    WrongKind {
+        expected: PrecomputedKind,
+        found: PrecomputedKind,
+    },
+}
+
+impl fmt::Display for PrecomputedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrecomputedError::Io(kind) => write!(f, "can't access the file: {:?}", kind),
+            PrecomputedError::Decode(_) => write!(f, "can't decode the precomputed context"),
+            PrecomputedError::Tampered => write!(f, "the params are not the ones of the header"),
+            PrecomputedError::InvalidParams => write!(f, "the params are invalid"),
+            PrecomputedError::WrongKind { expected, found } => {
+                write!(f, "a context of {:?}, expected {:?}", found, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PrecomputedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PrecomputedError::Decode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<CodecError> for PrecomputedError {
+    fn from(e: CodecError) -> Self {
+        PrecomputedError::Decode(e)
+    }
+}
+
+enum Tables {
+    SumCheck {
+        var_num: u8,
+        degree_bound: u8,
+        params: SumCheckParams,
+    },
+    Ldt(BatchLDT),
+    Gkr(CircuitConfig),
+}
+
+pub struct Precomputed {
+    protocol: ProtocolId,
+    tables: Tables,
+}
+
+impl Precomputed {
+    pub fn for_sumcheck(var_num: u8, degree_bound: u8, params: SumCheckParams) -> Self {
+        Self {
+            protocol: protocol_id(var_num, degree_bound, &params),
+            tables: Tables::SumCheck {
+                var_num,
+                degree_bound,
+                params,
+            },
+        }
+    }
+
+    pub fn for_ldt(ldt: BatchLDT) -> Self {
+        Self {
+            protocol: ldt.protocol_id(),
+            tables: Tables::Ldt(ldt),
+        }
+    }
+
+    pub fn for_gkr(circuit: CircuitConfig) -> Self {
+        Self {
+            protocol: gkr_protocol_id(&circuit),
+            tables: Tables::Gkr(circuit),
+        }
+    }
+
+    // The protocol of the proofs this context verifies.
+    pub fn protocol(&self) -> &ProtocolId {
+        &self.protocol
+    }
+
+    pub fn kind(&self) -> PrecomputedKind {
+        match self.tables {
+            Tables::SumCheck { .. } => PrecomputedKind::SumCheck,
+            Tables::Ldt(_) => PrecomputedKind::Ldt,
+            Tables::Gkr(_) => PrecomputedKind::Gkr,
+        }
+    }
+
+    fn wrong_kind(&self, expected: PrecomputedKind) -> PrecomputedError {
+        PrecomputedError::WrongKind {
+            expected,
+            found: self.kind(),
+        }
+    }
+
+    // (var_num, degree_bound) of the sum-check, eg: to check the size of the oracle of g.
+    pub fn sumcheck_shape(&self) -> Result<(u8, u8), PrecomputedError> {
+        match &self.tables {
+            Tables::SumCheck {
+                var_num,
+                degree_bound,
+                ..
+            } => Ok((*var_num, *degree_bound)),
+            _ => Err(self.wrong_kind(PrecomputedKind::SumCheck)),
+        }
+    }
+
+    pub fn sumcheck_params(&self) -> Result<SumCheckParams, PrecomputedError> {
+        match &self.tables {
+            Tables::SumCheck { params, .. } => Ok(params.clone()),
+            _ => Err(self.wrong_kind(PrecomputedKind::SumCheck)),
+        }
+    }
+
+    pub fn ldt(&self) -> Result<&BatchLDT, PrecomputedError> {
+        match &self.tables {
+            Tables::Ldt(ldt) => Ok(ldt),
+            _ => Err(self.wrong_kind(PrecomputedKind::Ldt)),
+        }
+    }
+
+    pub fn circuit(&self) -> Result<&CircuitConfig, PrecomputedError> {
+        match &self.tables {
+            Tables::Gkr(circuit) => Ok(circuit),
+            _ => Err(self.wrong_kind(PrecomputedKind::Gkr)),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![PRECOMPUTED_VERSION];
+        bytes.extend(encode_digest(&self.protocol.digest()));
+        bytes.push(self.kind() as u8);
+        let params = match &self.tables {
+            Tables::SumCheck {
+                var_num,
+                degree_bound,
+                params,
+            } => vec![
+                *var_num,
+                *degree_bound,
+                (params.var_order == VarOrder::LsbFirst) as u8,
+                (params.binding == Binding::Suffix) as u8,
+                params.compressed as u8,
+            ],
+            Tables::Ldt(ldt) => {
+                let mut params = encode_u64(ldt.domain.size as u64).to_vec();
+                params.extend(encode_scalar(&ldt.domain.offset));
+                params.extend(encode_u64(ldt.degree_bound as u64));
+                params.extend(encode_u64(ldt.num_queries as u64));
+                params.extend(encode_u64(ldt.params.folding_factor as u64));
+                if ldt.params.coeffs == CoeffMode::Independent {
+                    params.push(1);
+                }
+                params
+            }
+            Tables::Gkr(circuit) => circuit.to_bytes(),
+        };
+        bytes.extend(encode_bytes(&params));
+        bytes
+    }
+
+    // The bytes are untrusted, the context is rebuilt from the params, which must be of `expected`.
+    pub fn from_bytes_for(bytes: &[u8], expected: &ProtocolId) -> Result<Self, PrecomputedError> {
+        let mut decoder = Decoder::new(bytes);
+        decoder.read_version(PRECOMPUTED_VERSION)?;
+        decoder.read_protocol(expected)?;
+        let res = Self::read_params(decoder)?;
+        if res.protocol != *expected {
+            return Err(PrecomputedError::Tampered);
+        }
+        Ok(res)
+    }
+
+    // Same as `from_bytes_for`, but of the protocol in the header, eg: a context sent along with
+    // the proof by a caller which holds no `ProtocolId`. The params must still be the ones of the
+    // header, so it's the protocol the context verifies.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PrecomputedError> {
+        let mut decoder = Decoder::new(bytes);
+        decoder.read_version(PRECOMPUTED_VERSION)?;
+        let digest = decoder.read_digest()?;
+        let res = Self::read_params(decoder)?;
+        if res.protocol.digest() != digest {
+            return Err(PrecomputedError::Tampered);
+        }
+        Ok(res)
+    }
+
+    // The kind and the params after the header.
+    fn read_params(mut decoder: Decoder) -> Result<Self, PrecomputedError> {
+        let kind = decoder.read_tag(3)?;
+        let params = decoder.read_bytes()?;
+        decoder.finish()?;
+
+        let mut decoder = Decoder::new(&params);
+        Ok(match kind {
+            0 => {
+                let (var_num, degree_bound) = (decoder.read_u8()?, decoder.read_u8()?);
+                let var_order = [VarOrder::MsbFirst, VarOrder::LsbFirst];
+                let binding = [Binding::Prefix, Binding::Suffix];
+                let mut params = SumCheckParams::new(
+                    var_order[decoder.read_tag(2)? as usize],
+                    binding[decoder.read_tag(2)? as usize],
+                );
+                params.compressed = decoder.read_tag(2)? == 1;
+                decoder.finish()?;
+                Self::for_sumcheck(var_num, degree_bound, params)
+            }
+            1 => {
+                let size = decoder.read_usize()?;
+                let offset = decoder.read_scalar()?;
+                let degree_bound = decoder.read_usize()?;
+                let num_queries = decoder.read_usize()?;
+                let folding_factor = decoder.read_usize()?;
+                // the powers have no byte of the mode, see `BatchLDT::protocol_id`.
+                let coeffs = match decoder.remaining() {
+                    0 => CoeffMode::Powers,
+                    _ => [CoeffMode::Powers, CoeffMode::Independent][decoder.read_tag(2)? as usize],
+                };
+                decoder.finish()?;
+                if !ldt_params_ok(size, offset, degree_bound, folding_factor) {
+                    return Err(PrecomputedError::InvalidParams);
+                }
+                let domain = EvaluationDomain::coset(size, offset);
+                let params = LdtParams {
+                    folding_factor,
+                    coeffs,
+                };
+                Self::for_ldt(BatchLDT::with_params(
+                    domain,
+                    degree_bound,
+                    num_queries,
+                    params,
+                ))
+            }
+            _ => Self::for_gkr(CircuitConfig::from_bytes(&params)?),
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PrecomputedError> {
+        std::fs::write(path, self.to_bytes()).map_err(|e| PrecomputedError::Io(e.kind()))
+    }
+
+    pub fn load_for(
+        path: impl AsRef<Path>,
+        expected: &ProtocolId,
+    ) -> Result<Self, PrecomputedError> {
+        let bytes = std::fs::read(path).map_err(|e| PrecomputedError::Io(e.kind()))?;
+        Self::from_bytes_for(&bytes, expected)
+    }
+}
+
+// The asserts of `EvaluationDomain::coset` and `BatchLDT::with_params`, on untrusted params.
+fn ldt_params_ok(size: usize, offset: Scalar, degree_bound: usize, k: usize) -> bool {
+    size.is_power_of_two()
+        && size.trailing_zeros() <= Scalar::S
+        && offset != Scalar::zero()
+        && k.is_power_of_two()
+        && k > 1
+        && degree_bound.is_power_of_two()
+        && degree_bound > 1
+        && degree_bound
+            .trailing_zeros()
+            .is_multiple_of(k.trailing_zeros())
+        && size > degree_bound
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::*;
+    use std::time::Instant;
+
+    fn contexts() -> Vec<Precomputed> {
+        vec![sumcheck_precomputed(), ldt_precomputed(), gkr_precomputed()]
+    }
+
+    // A context shared by the proofs gives the results of the verifiers building their own.
+    #[test]
+    fn test_same_results() {
+        let (sumcheck, ldt, gkr) = (sumcheck_precomputed(), ldt_precomputed(), gkr_precomputed());
+        for _ in 0..2 {
+            assert_eq!(
+                verify_sumcheck_with(&sumcheck, SUMCHECK_HEX),
+                verify_sumcheck(SUMCHECK_HEX)
+            );
+            assert_eq!(verify_ldt_with(&ldt, LDT_HEX), verify_ldt(LDT_HEX));
+            assert_eq!(verify_gkr_with(&gkr, GKR_HEX), verify_gkr(GKR_HEX));
+        }
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = std::env::temp_dir();
+        for (i, context) in contexts().iter().enumerate() {
+            let path = dir.join(format!("thin_verifier_precomputed_{}.bin", i));
+            context.save(&path).unwrap();
+            let loaded = Precomputed::load_for(&path, context.protocol());
+            std::fs::remove_file(&path).unwrap();
+            let loaded = loaded.ok().unwrap();
+            assert_eq!(loaded.kind(), context.kind());
+            assert_eq!(loaded.to_bytes(), context.to_bytes());
+        }
+        assert!(matches!(
+            Precomputed::load_for(
+                dir.join("thin_verifier_no_file"),
+                ldt_precomputed().protocol()
+            ),
+            Err(PrecomputedError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_tampered() {
+        let context = ldt_precomputed();
+        let bytes = context.to_bytes();
+        let load = |bytes: &[u8], expected: &ProtocolId| {
+            Precomputed::from_bytes_for(bytes, expected).err()
+        };
+        assert_eq!(load(&bytes, context.protocol()), None);
+
+        // num_queries, under the header of the params before.
+        let mut tampered = bytes.clone();
+        let n = tampered.len();
+        tampered[n - 9] ^= 1;
+        assert_eq!(
+            load(&tampered, context.protocol()),
+            Some(PrecomputedError::Tampered)
+        );
+
+        // the file of other params.
+        assert_eq!(
+            load(&bytes, gkr_precomputed().protocol()),
+            Some(PrecomputedError::Decode(CodecError::ProtocolMismatch))
+        );
+
+        // the degree bound is the size of the domain.
+        let mut invalid = bytes.clone();
+        invalid[n - 24..n - 16].copy_from_slice(&encode_u64(64));
+        assert_eq!(
+            load(&invalid, context.protocol()),
+            Some(PrecomputedError::InvalidParams)
+        );
+    }
+
+    // The header is the protocol, so a context loads without the id, but not a tampered one.
+    #[test]
+    fn test_from_bytes() {
+        for context in contexts() {
+            let loaded = Precomputed::from_bytes(&context.to_bytes()).ok().unwrap();
+            assert_eq!(loaded.protocol(), context.protocol());
+            assert_eq!(loaded.to_bytes(), context.to_bytes());
+        }
+        let mut tampered = ldt_precomputed().to_bytes();
+        let n = tampered.len();
+        tampered[n - 9] ^= 1;
+        assert_eq!(
+            Precomputed::from_bytes(&tampered).err(),
+            Some(PrecomputedError::Tampered)
+        );
+    }
+
+    // The proofs are decoded against the id of the context, so a proof of other params is
+    // rejected at its header, before any check.
+    #[test]
+    fn test_other_params() {
+        let degree_2 = Precomputed::for_sumcheck(4, 2, SumCheckParams::default());
+        assert_eq!(
+            verify_sumcheck_with(&degree_2, SUMCHECK_HEX),
+            Err(ProtocolError::SumCheckDecode(DecodeError::ProtocolMismatch))
+        );
+        assert_eq!(
+            verify_gkr_with(&ldt_precomputed(), GKR_HEX),
+            Err(ProtocolError::Precomputed(PrecomputedError::WrongKind {
+                expected: PrecomputedKind::Gkr,
+                found: PrecomputedKind::Ldt
+            }))
+        );
+    }
+
+    // cargo test -p thin_verifier --release -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn bench_shared_context() {
+        let start = Instant::now();
+        for _ in 0..100 {
+            let _ = verify_ldt_with(&ldt_precomputed(), LDT_HEX);
+            let _ = verify_gkr_with(&gkr_precomputed(), GKR_HEX);
+        }
+        let fresh = start.elapsed();
+
+        let (ldt, gkr) = (ldt_precomputed(), gkr_precomputed());
+        let start = Instant::now();
+        for _ in 0..100 {
+            let _ = verify_ldt_with(&ldt, LDT_HEX);
+            let _ = verify_gkr_with(&gkr, GKR_HEX);
+        }
+        let shared = start.elapsed();
+        println!(
+            "100 proofs: {:?} with a context each, {:?} shared",
+            fresh, shared
+        );
+    }
+}